@@ -0,0 +1,24 @@
+#!/usr/bin/env sh
+# Build the headless remote agent for each platform the app deploys it to,
+# named the way Tauri expects sidecars (`<name>-<host triple>`), for
+# `npm run tauri:build:agents`. Targets need their Rust toolchain and linker.
+set -eu
+
+cd "$(dirname "$0")/../src-tauri"
+host=$(rustc -vV | sed -n 's/^host: //p')
+case "$host" in
+    *windows*) ext=".exe" ;;
+    *) ext="" ;;
+esac
+mkdir -p binaries
+
+build() {
+    platform=$1
+    target=$2
+    cargo build --release --bin cortex-server --no-default-features \
+        --features agent-server --target "$target"
+    cp "target/$target/release/cortex-server" "binaries/cortex-server-$platform-$host$ext"
+}
+
+build linux-x86_64 x86_64-unknown-linux-gnu
+build linux-aarch64 aarch64-unknown-linux-gnu
@@ -1,3 +1,5 @@
 fn main() {
-    tauri_build::build()
+    // The headless agent has no app to configure
+    #[cfg(feature = "desktop")]
+    tauri_build::build();
 }
@@ -10,6 +10,7 @@ macro_rules! editor_commands {
             $crate::lsp::commands::server::lsp_get_server_info,
             $crate::lsp::commands::server::lsp_restart,
             $crate::lsp::commands::server::lsp_get_logs,
+            $crate::lsp::commands::server_requests::lsp_respond_show_message,
             $crate::lsp::commands::server::lsp_clear_logs,
             $crate::lsp::commands::server::lsp_get_servers_for_language,
             // LSP commands - document sync
@@ -84,11 +85,36 @@ macro_rules! editor_commands {
             // LSP autodetect commands
             $crate::lsp::commands::autodetect::lsp_detect_servers,
             $crate::lsp::commands::autodetect::lsp_get_installed_servers,
+            // LSP managed installs
+            $crate::lsp::commands::installer::lsp_installer_list,
+            $crate::lsp::commands::installer::lsp_installer_install,
+            $crate::lsp::commands::installer::lsp_installer_install_from_archive,
+            $crate::lsp::commands::installer::lsp_installer_uninstall,
+            $crate::lsp::commands::installer::lsp_installer_check_updates,
+            // LSP traces
+            $crate::lsp::commands::trace::lsp_trace_start,
+            $crate::lsp::commands::trace::lsp_trace_stop,
+            $crate::lsp::commands::trace::lsp_replay_trace,
             // Debugger commands
             $crate::dap::commands::debug_start_session,
             $crate::dap::commands::debug_stop_session,
             $crate::dap::commands::debug_get_sessions,
             $crate::dap::commands::debug_get_session_state,
+            $crate::dap::commands::debug_get_launch_variables,
+            $crate::dap::commands::debug_launch,
+            $crate::dap::commands::debug_stop_compound,
+            $crate::dap::commands::debug_adapters_list,
+            $crate::dap::commands::debug_adapters_install,
+            $crate::dap::commands::debug_adapters_install_from_archive,
+            $crate::dap::commands::debug_adapters_uninstall,
+            $crate::dap::commands::debug_adapters_check_updates,
+            $crate::dap::commands::debug_default_launch_configs,
+            $crate::dap::commands::debug_rr_available,
+            $crate::dap::commands::debug_rr_record,
+            $crate::dap::commands::debug_rr_stop_recording,
+            $crate::dap::commands::debug_rr_list_recordings,
+            $crate::dap::commands::debug_rr_delete_recording,
+            $crate::dap::commands::debug_rr_replay,
             $crate::dap::commands::debug_set_breakpoints,
             $crate::dap::commands::debug_set_function_breakpoints,
             $crate::dap::commands::debug_toggle_breakpoint,
@@ -48,6 +48,14 @@ macro_rules! git_commands {
             $crate::git::blame::git_blame,
             $crate::git::blame::git_blame_line_range,
             $crate::git::blame::git_blame_with_heatmap,
+            $crate::git::blame::git_blame_at_parent,
+            // Git history search commands
+            $crate::git::history::git_log_pickaxe,
+            $crate::git::history::git_log_pickaxe_cancel,
+            $crate::git::history::git_log_line_range,
+            $crate::git::history::git_file_history,
+            $crate::git::hooks::git_hooks_list,
+            $crate::git::hooks::git_hooks_run,
             // Git hunk staging commands
             $crate::git::hunk::git_stage_hunk,
             $crate::git::hunk::git_unstage_hunk,
@@ -132,10 +140,24 @@ macro_rules! git_commands {
             // Git clone commands
             $crate::git::clone::git_clone,
             $crate::git::clone::git_clone_recursive,
+            $crate::git::clone::git_clone_with_options,
+            // Git sparse-checkout commands
+            $crate::git::sparse::git_sparse_checkout_status,
+            $crate::git::sparse::git_sparse_checkout_set,
+            $crate::git::sparse::git_sparse_checkout_add,
+            $crate::git::sparse::git_sparse_checkout_disable,
+            $crate::git::sparse::git_sparse_list_excluded,
             // Git merge commands
             $crate::git::merge::git_merge,
             $crate::git::merge::git_merge_abort,
             $crate::git::merge::git_merge_continue,
+            // Git merge editor commands
+            $crate::git::merge_editor::git_get_merge_conflicts,
+            $crate::git::merge_editor::git_get_three_way_diff,
+            $crate::git::merge_editor::git_resolve_conflict,
+            $crate::git::merge_editor::git_abort_merge,
+            $crate::git::merge_editor::git_auto_resolve_conflicts,
+            $crate::git::merge_editor::git_ai_propose_resolution,
             // Git branch commands
             $crate::git::branch::git_publish_branch,
             $crate::git::branch::git_set_upstream,
@@ -164,6 +186,9 @@ macro_rules! git_commands {
             $crate::git::forge::git_forge_pr_checks,
             $crate::git::forge::git_forge_get_pr_reviews,
             $crate::git::forge::git_forge_authenticate,
+            // Git AI-assisted message generation
+            $crate::git::commit_message::git_generate_commit_message,
+            $crate::git::commit_message::git_generate_pr_description,
         ])
     };
 }
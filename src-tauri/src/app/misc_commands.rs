@@ -98,6 +98,19 @@ macro_rules! misc_commands {
             $crate::diagnostics::diagnostics_add_build,
             $crate::diagnostics::diagnostics_clear_build,
             $crate::diagnostics::write_file,
+            // Output channel commands
+            $crate::output_channels::output_channel_create,
+            $crate::output_channels::output_channel_delete,
+            $crate::output_channels::output_channel_append,
+            $crate::output_channels::output_channel_append_line,
+            $crate::output_channels::output_channel_clear,
+            $crate::output_channels::output_channel_show,
+            $crate::output_channels::output_channel_hide,
+            $crate::output_channels::output_channel_list,
+            $crate::output_channels::output_channel_get_content,
+            $crate::output_channels::output_channel_replace,
+            $crate::output_channels::output_channel_set_language,
+            $crate::output_channels::output_channel_dispose,
             // Rules Library commands
             $crate::rules_library::rules_scan_project,
             $crate::rules_library::rules_read_file,
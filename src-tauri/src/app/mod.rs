@@ -408,6 +408,7 @@ pub fn register_state(
         .manage(remote_manager)
         .manage(LspState::new())
         .manage(crate::diagnostics::DiagnosticsState::new())
+        .manage(Arc::new(crate::output_channels::OutputChannelState::new()))
         .manage(REPLState(Arc::new(Mutex::new(None))))
         .manage(LazyState::new(DebuggerState::new))
         .manage(crate::dap::commands::WatchState::new())
@@ -426,6 +427,8 @@ pub fn register_state(
         .manage(AgentStoreState::new())
         .manage(crate::terminal::TerminalState::new())
         .manage(crate::terminal::TerminalProfilesState::new())
+        .manage(crate::terminal::TerminalLayoutsState::default())
+        .manage(crate::remote::agent::RemoteAgentsState::default())
         .manage(crate::settings::SettingsState::new())
         .manage(crate::settings_sync::SettingsSyncState::new())
         .manage(Arc::new(crate::fs::FileWatcherState::new()))
@@ -456,6 +459,7 @@ pub fn register_state(
         .manage(crate::remote::port_forwarding::PortForwardingState::new())
         .manage(LazyState::new(SandboxState::new))
         .manage(crate::remote::tunnel::TunnelState::new())
+        .manage(crate::remote::devcontainer::DevContainerState::new())
         .manage(crate::git::forge::ForgeState::new())
         .manage(LazyState::new(CollabState::new));
 
@@ -677,8 +681,8 @@ pub fn handle_run_event(app: &AppHandle, event: RunEvent) {
             info!("Application exit requested, cleaning up all child processes...");
 
             let terminal_state = app.state::<crate::terminal::TerminalState>();
-            let _ = terminal_state.close_all(app);
-            info!("All terminals closed on app exit");
+            terminal_state.detach_all(app);
+            info!("All terminals closed on app exit, persistent terminals left running");
 
             #[cfg(feature = "remote-ssh")]
             {
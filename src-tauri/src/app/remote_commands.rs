@@ -26,6 +26,15 @@ macro_rules! remote_commands {
             $crate::remote::commands::remote_save_profile_with_credentials,
             $crate::remote::commands::remote_has_stored_password,
             $crate::remote::commands::remote_has_stored_passphrase,
+            $crate::remote::commands::remote_import_ssh_config,
+            // SSH host key commands
+            $crate::remote::host_keys::remote_trusted_hosts_list,
+            $crate::remote::host_keys::remote_trust_host,
+            $crate::remote::host_keys::remote_untrust_host,
+            // Remote agent commands
+            $crate::remote::agent::remote_agent_start,
+            $crate::remote::agent::remote_agent_stop,
+            $crate::remote::agent::remote_agent_list,
             // Remote port forwarding commands
             $crate::remote::commands::remote_forward_port,
             $crate::remote::commands::remote_stop_forward,
@@ -37,6 +46,7 @@ macro_rules! remote_commands {
             $crate::remote::tunnel::remote_tunnel_status,
             $crate::remote::tunnel::remote_tunnel_list,
             // DevContainer commands
+            $crate::remote::commands::devcontainer_list,
             $crate::remote::commands::devcontainer_connect,
             $crate::remote::commands::devcontainer_start,
             $crate::remote::commands::devcontainer_stop,
@@ -46,6 +56,15 @@ macro_rules! remote_commands {
             $crate::remote::commands::devcontainer_save_config,
             $crate::remote::commands::devcontainer_list_features,
             $crate::remote::commands::devcontainer_list_templates,
+            $crate::remote::commands::devcontainer_read_file,
+            $crate::remote::commands::devcontainer_write_file,
+            $crate::remote::commands::devcontainer_list_directory,
+            $crate::remote::commands::devcontainer_delete,
+            $crate::remote::commands::devcontainer_create_directory,
+            $crate::remote::commands::devcontainer_rename,
+            $crate::remote::commands::devcontainer_execute_command,
+            $crate::remote::commands::devcontainer_open_terminal,
+            $crate::remote::commands::devcontainer_run_task,
         ])
     };
 }
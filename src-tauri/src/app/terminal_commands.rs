@@ -15,6 +15,7 @@ macro_rules! terminal_commands {
             $crate::terminal::commands::terminal_send_eof,
             $crate::terminal::commands::terminal_ack,
             $crate::terminal::commands::terminal_close_all,
+            $crate::terminal::commands::terminal_restore,
             $crate::terminal::commands::terminal_get_default_shell,
             // Port management commands
             $crate::terminal::commands::get_process_on_port,
@@ -25,6 +26,24 @@ macro_rules! terminal_commands {
             $crate::terminal::commands::path_exists,
             $crate::terminal::commands::terminal_detect_links,
             $crate::terminal::commands::terminal_search,
+            $crate::terminal::commands::terminal_search_scrollback,
+            $crate::terminal::commands::terminal_export_scrollback,
+            $crate::terminal::commands::terminal_snapshot,
+            $crate::terminal::commands::terminal_command_blocks_list,
+            $crate::terminal::commands::terminal_command_blocks_search,
+            $crate::terminal::commands::terminal_command_block_rerun,
+            $crate::terminal::commands::terminal_command_block_copy_output,
+            $crate::terminal::commands::terminal_command_block_send_to_ai,
+            $crate::terminal::commands::terminal_command_blocks_clear,
+            $crate::terminal::commands::terminal_set_problem_matchers,
+            $crate::terminal::commands::terminal_get_problem_matchers,
+            $crate::terminal::commands::terminal_recording_start,
+            $crate::terminal::commands::terminal_recording_stop,
+            $crate::terminal::commands::terminal_recording_status,
+            $crate::terminal::commands::terminal_replay_start,
+            $crate::terminal::commands::terminal_replay_set_speed,
+            $crate::terminal::commands::terminal_replay_set_paused,
+            $crate::terminal::commands::terminal_replay_stop,
             $crate::terminal::commands::terminal_parse_shell_integration,
             $crate::terminal::commands::terminal_strip_sequences,
             // Terminal profiles commands
@@ -33,6 +52,12 @@ macro_rules! terminal_commands {
             $crate::terminal::profiles::terminal_profiles_delete,
             $crate::terminal::profiles::terminal_profiles_get,
             $crate::terminal::profiles::terminal_profiles_set_default,
+            // Terminal layouts commands
+            $crate::terminal::layouts::terminal_layouts_load,
+            $crate::terminal::layouts::terminal_layout_spawn,
+            $crate::terminal::layouts::terminal_layouts_running,
+            $crate::terminal::layouts::terminal_layout_restart,
+            $crate::terminal::layouts::terminal_layout_stop,
             // SSH Terminal PTY commands
             $crate::ssh_terminal::ssh_connect,
             $crate::ssh_terminal::ssh_pty_write,
@@ -69,6 +94,7 @@ macro_rules! terminal_commands {
             $crate::terminal::commands::terminal_send_eof,
             $crate::terminal::commands::terminal_ack,
             $crate::terminal::commands::terminal_close_all,
+            $crate::terminal::commands::terminal_restore,
             $crate::terminal::commands::terminal_get_default_shell,
             // Port management commands
             $crate::terminal::commands::get_process_on_port,
@@ -79,6 +105,24 @@ macro_rules! terminal_commands {
             $crate::terminal::commands::path_exists,
             $crate::terminal::commands::terminal_detect_links,
             $crate::terminal::commands::terminal_search,
+            $crate::terminal::commands::terminal_search_scrollback,
+            $crate::terminal::commands::terminal_export_scrollback,
+            $crate::terminal::commands::terminal_snapshot,
+            $crate::terminal::commands::terminal_command_blocks_list,
+            $crate::terminal::commands::terminal_command_blocks_search,
+            $crate::terminal::commands::terminal_command_block_rerun,
+            $crate::terminal::commands::terminal_command_block_copy_output,
+            $crate::terminal::commands::terminal_command_block_send_to_ai,
+            $crate::terminal::commands::terminal_command_blocks_clear,
+            $crate::terminal::commands::terminal_set_problem_matchers,
+            $crate::terminal::commands::terminal_get_problem_matchers,
+            $crate::terminal::commands::terminal_recording_start,
+            $crate::terminal::commands::terminal_recording_stop,
+            $crate::terminal::commands::terminal_recording_status,
+            $crate::terminal::commands::terminal_replay_start,
+            $crate::terminal::commands::terminal_replay_set_speed,
+            $crate::terminal::commands::terminal_replay_set_paused,
+            $crate::terminal::commands::terminal_replay_stop,
             $crate::terminal::commands::terminal_parse_shell_integration,
             $crate::terminal::commands::terminal_strip_sequences,
             // Terminal profiles commands
@@ -87,6 +131,12 @@ macro_rules! terminal_commands {
             $crate::terminal::profiles::terminal_profiles_delete,
             $crate::terminal::profiles::terminal_profiles_get,
             $crate::terminal::profiles::terminal_profiles_set_default,
+            // Terminal layouts commands
+            $crate::terminal::layouts::terminal_layouts_load,
+            $crate::terminal::layouts::terminal_layout_spawn,
+            $crate::terminal::layouts::terminal_layouts_running,
+            $crate::terminal::layouts::terminal_layout_restart,
+            $crate::terminal::layouts::terminal_layout_stop,
         ])
     };
 }
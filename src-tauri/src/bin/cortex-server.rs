@@ -0,0 +1,16 @@
+//! Headless remote agent
+//!
+//! The app uploads this binary to remote hosts as `cortex-server-<platform>`
+//! and starts it there with `--remote-agent`. Build it without the default
+//! features, which only the desktop app uses:
+//!
+//! ```sh
+//! cargo build --release --bin cortex-server --no-default-features --features agent-server
+//! ```
+
+fn main() {
+    if !cortex_gui_lib::run_remote_agent() {
+        eprintln!("cortex-server is started by Cortex Desktop with --remote-agent");
+        std::process::exit(2);
+    }
+}
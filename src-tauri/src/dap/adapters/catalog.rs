@@ -0,0 +1,211 @@
+//! Catalog of managed debug adapters
+//!
+//! Entries use the language server catalog types, so downloads, checksums and
+//! update checks work exactly as they do for language servers. Adapters that
+//! ship as scripts name the runtime that starts them.
+
+use std::path::Path;
+
+use crate::lsp::installer::catalog::{ArchiveFormat, CatalogEntry, InstallMethod, ReleaseAsset};
+
+/// A debug adapter Cortex can install
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct AdapterEntry {
+    pub package: CatalogEntry,
+    /// Launch configuration `type`s the adapter serves
+    pub debug_types: &'static [&'static str],
+    /// Program that runs the installed file, for adapters shipped as scripts
+    pub runtime: Option<&'static str>,
+}
+
+impl AdapterEntry {
+    pub(crate) fn id(&self) -> &'static str {
+        self.package.id
+    }
+
+    /// Command line starting the adapter installed at `path`
+    pub(crate) fn command_line(&self, path: &Path) -> (String, Vec<String>) {
+        let path = path.to_string_lossy().to_string();
+        let args = self.package.args.iter().map(|a| a.to_string());
+        match self.runtime {
+            Some(runtime) => (
+                runtime.to_string(),
+                std::iter::once(path).chain(args).collect(),
+            ),
+            None => (path, args.collect()),
+        }
+    }
+}
+
+pub(crate) const ADAPTERS: &[AdapterEntry] = &[
+    AdapterEntry {
+        package: CatalogEntry {
+            id: "codelldb",
+            name: "CodeLLDB",
+            languages: &["rust", "c", "cpp"],
+            version: "1.11.4",
+            command: "codelldb",
+            args: &[],
+            method: InstallMethod::GithubRelease {
+                repo: "vadimcn/codelldb",
+                tag: "v{version}",
+                assets: &[
+                    ReleaseAsset {
+                        target: "x86_64-linux",
+                        name: "codelldb-linux-x64.vsix",
+                        format: ArchiveFormat::Zip,
+                        binary: "extension/adapter/codelldb",
+                        sha256: None,
+                    },
+                    ReleaseAsset {
+                        target: "aarch64-linux",
+                        name: "codelldb-linux-arm64.vsix",
+                        format: ArchiveFormat::Zip,
+                        binary: "extension/adapter/codelldb",
+                        sha256: None,
+                    },
+                    ReleaseAsset {
+                        target: "x86_64-macos",
+                        name: "codelldb-darwin-x64.vsix",
+                        format: ArchiveFormat::Zip,
+                        binary: "extension/adapter/codelldb",
+                        sha256: None,
+                    },
+                    ReleaseAsset {
+                        target: "aarch64-macos",
+                        name: "codelldb-darwin-arm64.vsix",
+                        format: ArchiveFormat::Zip,
+                        binary: "extension/adapter/codelldb",
+                        sha256: None,
+                    },
+                    ReleaseAsset {
+                        target: "x86_64-windows",
+                        name: "codelldb-win32-x64.vsix",
+                        format: ArchiveFormat::Zip,
+                        binary: "extension/adapter/codelldb.exe",
+                        sha256: None,
+                    },
+                ],
+            },
+        },
+        debug_types: &["lldb", "codelldb", "rust"],
+        runtime: None,
+    },
+    AdapterEntry {
+        package: CatalogEntry {
+            id: "debugpy",
+            name: "debugpy",
+            languages: &["python"],
+            version: "1.8.14",
+            command: "python",
+            args: &["-m", "debugpy.adapter"],
+            method: InstallMethod::Pip { package: "debugpy" },
+        },
+        debug_types: &["debugpy", "python"],
+        runtime: None,
+    },
+    AdapterEntry {
+        package: CatalogEntry {
+            id: "delve",
+            name: "Delve",
+            languages: &["go"],
+            version: "v1.24.2",
+            command: "dlv",
+            args: &["dap"],
+            method: InstallMethod::Go {
+                module: "github.com/go-delve/delve/cmd/dlv",
+            },
+        },
+        debug_types: &["go", "delve"],
+        runtime: None,
+    },
+    AdapterEntry {
+        package: CatalogEntry {
+            id: "js-debug",
+            name: "JavaScript Debugger",
+            languages: &["javascript", "typescript"],
+            version: "1.97.0",
+            command: "dapDebugServer.js",
+            args: &[],
+            method: InstallMethod::GithubRelease {
+                repo: "microsoft/vscode-js-debug",
+                tag: "v{version}",
+                assets: &[ReleaseAsset {
+                    target: "*",
+                    name: "js-debug-dap-v{version}.tar.gz",
+                    format: ArchiveFormat::TarGz,
+                    binary: "js-debug/src/dapDebugServer.js",
+                    sha256: None,
+                }],
+            },
+        },
+        debug_types: &[
+            "pwa-node",
+            "node",
+            "node2",
+            "pwa-chrome",
+            "chrome",
+            "pwa-msedge",
+        ],
+        runtime: Some("node"),
+    },
+    AdapterEntry {
+        package: CatalogEntry {
+            id: "netcoredbg",
+            name: "netcoredbg",
+            languages: &["csharp", "fsharp"],
+            version: "3.1.2-1054",
+            command: "netcoredbg",
+            args: &["--interpreter=vscode"],
+            method: InstallMethod::GithubRelease {
+                repo: "Samsung/netcoredbg",
+                tag: "{version}",
+                assets: &[
+                    ReleaseAsset {
+                        target: "x86_64-linux",
+                        name: "netcoredbg-linux-amd64.tar.gz",
+                        format: ArchiveFormat::TarGz,
+                        binary: "netcoredbg/netcoredbg",
+                        sha256: None,
+                    },
+                    ReleaseAsset {
+                        target: "aarch64-linux",
+                        name: "netcoredbg-linux-arm64.tar.gz",
+                        format: ArchiveFormat::TarGz,
+                        binary: "netcoredbg/netcoredbg",
+                        sha256: None,
+                    },
+                    ReleaseAsset {
+                        target: "x86_64-macos",
+                        name: "netcoredbg-osx-amd64.tar.gz",
+                        format: ArchiveFormat::TarGz,
+                        binary: "netcoredbg/netcoredbg",
+                        sha256: None,
+                    },
+                    ReleaseAsset {
+                        target: "x86_64-windows",
+                        name: "netcoredbg-win64.zip",
+                        format: ArchiveFormat::Zip,
+                        binary: "netcoredbg/netcoredbg.exe",
+                        sha256: None,
+                    },
+                ],
+            },
+        },
+        debug_types: &["coreclr", "netcoredbg"],
+        runtime: None,
+    },
+];
+
+/// Look up an adapter by id
+pub(crate) fn find(id: &str) -> Option<&'static AdapterEntry> {
+    ADAPTERS.iter().find(|a| a.id() == id)
+}
+
+/// Adapter serving a launch configuration `type`
+pub(crate) fn find_by_debug_type(debug_type: &str) -> Option<&'static AdapterEntry> {
+    let debug_type = debug_type.to_lowercase();
+    ADAPTERS
+        .iter()
+        .find(|a| a.debug_types.contains(&debug_type.as_str()))
+}
@@ -0,0 +1,443 @@
+//! Default launch configurations
+//!
+//! Looks at a project the way the toolchain and testing modules do (manifest
+//! files, selected interpreters, the detected test framework) and proposes
+//! launch.json configurations for the catalog adapters.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::testing::types::TestFramework;
+use crate::toolchain::{self, ProjectToolchains};
+
+/// A proposed launch configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DefaultLaunchConfig {
+    /// Catalog adapter serving the configuration
+    pub adapter_id: String,
+    /// Whether that adapter is installed from the catalog
+    pub installed: bool,
+    pub configuration: Value,
+}
+
+/// A .NET project file
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct DotnetProject {
+    pub name: String,
+    pub target_framework: Option<String>,
+}
+
+/// What the defaults are generated from
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ProjectProfile {
+    /// Package name from Cargo.toml, empty for a workspace without a root package
+    pub cargo_package: Option<String>,
+    /// Channel from rust-toolchain(.toml)
+    pub rust_toolchain: Option<String>,
+    pub python_project: bool,
+    /// Interpreter selected for the project or found in its virtual environment
+    pub python: Option<String>,
+    /// `main` from package.json
+    pub node_main: Option<String>,
+    pub node_project: bool,
+    /// Node version from .nvmrc and friends
+    pub node_version: Option<String>,
+    /// Node executable selected for the project
+    pub node_runtime: Option<String>,
+    pub go_module: bool,
+    pub dotnet_project: Option<DotnetProject>,
+    pub test_framework: Option<TestFramework>,
+}
+
+/// `key = "value"` in the `[section]` of a TOML file, without a TOML parser
+fn toml_value(content: &str, section: &str, key: &str) -> Option<String> {
+    let mut in_section = false;
+    for line in content.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_section = line == format!("[{}]", section);
+            continue;
+        }
+        if !in_section {
+            continue;
+        }
+        let Some((name, value)) = line.split_once('=') else {
+            continue;
+        };
+        if name.trim() == key {
+            return Some(value.trim().trim_matches('"').to_string());
+        }
+    }
+    None
+}
+
+fn xml_element(content: &str, element: &str) -> Option<String> {
+    let open = format!("<{}>", element);
+    let start = content.find(&open)? + open.len();
+    let end = content[start..].find("</")? + start;
+    Some(content[start..end].trim().to_string())
+}
+
+fn find_dotnet_project(root: &Path) -> Option<DotnetProject> {
+    let mut projects: Vec<_> = std::fs::read_dir(root)
+        .ok()?
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| {
+            p.extension()
+                .is_some_and(|ext| ext == "csproj" || ext == "fsproj")
+        })
+        .collect();
+    projects.sort();
+    let path = projects.into_iter().next()?;
+    let content = std::fs::read_to_string(&path).unwrap_or_default();
+    Some(DotnetProject {
+        name: path.file_stem()?.to_string_lossy().to_string(),
+        target_framework: xml_element(&content, "TargetFramework"),
+    })
+}
+
+impl ProjectProfile {
+    /// Inspect the project at `root`; the test framework is filled in by the caller
+    pub(crate) fn detect(root: &Path, selected: &ProjectToolchains) -> Self {
+        let cargo_package = std::fs::read_to_string(root.join("Cargo.toml"))
+            .ok()
+            .map(|content| toml_value(&content, "package", "name").unwrap_or_default());
+        let package_json = std::fs::read_to_string(root.join("package.json"))
+            .ok()
+            .and_then(|content| serde_json::from_str::<Value>(&content).ok());
+        let python_project = ["pyproject.toml", "setup.py", "requirements.txt", "Pipfile"]
+            .iter()
+            .any(|f| root.join(f).exists());
+
+        Self {
+            cargo_package,
+            rust_toolchain: toolchain::detect_project_rust_toolchain(root),
+            python_project,
+            python: selected.python.clone().or_else(|| {
+                python_project
+                    .then(|| toolchain::detect_project_venv(root))
+                    .flatten()
+                    .map(|venv| venv.path)
+            }),
+            node_main: package_json
+                .as_ref()
+                .and_then(|p| p.get("main")?.as_str().map(String::from)),
+            node_project: package_json.is_some(),
+            node_version: toolchain::detect_project_node_version(root),
+            node_runtime: selected.node.clone(),
+            go_module: root.join("go.mod").exists(),
+            dotnet_project: find_dotnet_project(root),
+            test_framework: None,
+        }
+    }
+}
+
+fn rust_configurations(profile: &ProjectProfile, out: &mut Vec<(&'static str, Value)>) {
+    let Some(package) = &profile.cargo_package else {
+        return;
+    };
+    let cargo = |args: Value| {
+        let mut cargo = json!({ "args": args });
+        if let Some(channel) = &profile.rust_toolchain {
+            cargo["env"] = json!({ "RUSTUP_TOOLCHAIN": channel });
+        }
+        cargo
+    };
+
+    if package.is_empty() {
+        // A workspace without a root package
+        out.push((
+            "codelldb",
+            json!({
+                "type": "lldb",
+                "request": "launch",
+                "name": "Debug executable",
+                "cargo": cargo(json!(["build"])),
+                "cwd": "${workspaceFolder}"
+            }),
+        ));
+    } else {
+        out.push((
+            "codelldb",
+            json!({
+                "type": "lldb",
+                "request": "launch",
+                "name": format!("Debug executable '{}'", package),
+                "cargo": cargo(json!(["build", format!("--bin={}", package), format!("--package={}", package)])),
+                "cwd": "${workspaceFolder}"
+            }),
+        ));
+    }
+    if profile.test_framework == Some(TestFramework::Cargo) {
+        out.push((
+            "codelldb",
+            json!({
+                "type": "lldb",
+                "request": "launch",
+                "name": "Debug unit tests",
+                "cargo": cargo(json!(["test", "--no-run"])),
+                "cwd": "${workspaceFolder}"
+            }),
+        ));
+    }
+}
+
+fn python_configurations(profile: &ProjectProfile, out: &mut Vec<(&'static str, Value)>) {
+    if !profile.python_project && profile.test_framework != Some(TestFramework::Pytest) {
+        return;
+    }
+    let with_python = |mut config: Value| {
+        if let Some(python) = &profile.python {
+            config["python"] = json!(python);
+        }
+        config
+    };
+
+    out.push((
+        "debugpy",
+        with_python(json!({
+            "type": "debugpy",
+            "request": "launch",
+            "name": "Python: Current File",
+            "program": "${file}",
+            "console": "integratedTerminal"
+        })),
+    ));
+    if profile.test_framework == Some(TestFramework::Pytest) {
+        out.push((
+            "debugpy",
+            with_python(json!({
+                "type": "debugpy",
+                "request": "launch",
+                "name": "Python: pytest current file",
+                "module": "pytest",
+                "args": ["${file}"],
+                "console": "integratedTerminal",
+                "justMyCode": false
+            })),
+        ));
+    }
+}
+
+fn node_configurations(profile: &ProjectProfile, out: &mut Vec<(&'static str, Value)>) {
+    if !profile.node_project {
+        return;
+    }
+    let with_runtime = |mut config: Value| {
+        if let Some(runtime) = &profile.node_runtime {
+            config["runtimeExecutable"] = json!(runtime);
+        } else if let Some(version) = &profile.node_version {
+            config["runtimeVersion"] = json!(version.trim_start_matches('v'));
+        }
+        config
+    };
+
+    let program = profile
+        .node_main
+        .as_deref()
+        .map(|main| format!("${{workspaceFolder}}/{}", main.trim_start_matches("./")))
+        .unwrap_or_else(|| "${file}".to_string());
+    out.push((
+        "js-debug",
+        with_runtime(json!({
+            "type": "pwa-node",
+            "request": "launch",
+            "name": "Launch Program",
+            "program": program,
+            "skipFiles": ["<node_internals>/**"]
+        })),
+    ));
+
+    let test_runner = match profile.test_framework {
+        Some(TestFramework::Jest) => Some((
+            "Jest: current file",
+            "${workspaceFolder}/node_modules/jest/bin/jest.js",
+            json!(["--runInBand", "${relativeFile}"]),
+        )),
+        Some(TestFramework::Vitest) => Some((
+            "Vitest: current file",
+            "${workspaceFolder}/node_modules/vitest/vitest.mjs",
+            json!(["run", "${relativeFile}"]),
+        )),
+        Some(TestFramework::Mocha) => Some((
+            "Mocha: current file",
+            "${workspaceFolder}/node_modules/mocha/bin/mocha.js",
+            json!(["${relativeFile}"]),
+        )),
+        _ => None,
+    };
+    if let Some((name, program, args)) = test_runner {
+        out.push((
+            "js-debug",
+            with_runtime(json!({
+                "type": "pwa-node",
+                "request": "launch",
+                "name": name,
+                "program": program,
+                "args": args,
+                "console": "integratedTerminal",
+                "skipFiles": ["<node_internals>/**"]
+            })),
+        ));
+    }
+}
+
+fn go_configurations(profile: &ProjectProfile, out: &mut Vec<(&'static str, Value)>) {
+    if !profile.go_module {
+        return;
+    }
+    out.push((
+        "delve",
+        json!({
+            "type": "go",
+            "request": "launch",
+            "name": "Launch Package",
+            "mode": "debug",
+            "program": "${workspaceFolder}"
+        }),
+    ));
+    out.push((
+        "delve",
+        json!({
+            "type": "go",
+            "request": "launch",
+            "name": "Test Current Package",
+            "mode": "test",
+            "program": "${fileDirname}"
+        }),
+    ));
+}
+
+fn dotnet_configurations(profile: &ProjectProfile, out: &mut Vec<(&'static str, Value)>) {
+    let Some(project) = &profile.dotnet_project else {
+        return;
+    };
+    let framework = project.target_framework.as_deref().unwrap_or("net8.0");
+    out.push((
+        "netcoredbg",
+        json!({
+            "type": "coreclr",
+            "request": "launch",
+            "name": format!(".NET Launch ({})", project.name),
+            "program": format!("${{workspaceFolder}}/bin/Debug/{}/{}.dll", framework, project.name),
+            "cwd": "${workspaceFolder}",
+            "stopAtEntry": false
+        }),
+    ));
+}
+
+/// Default configurations for a project, as `(adapter id, configuration)`
+pub(crate) fn default_configurations(profile: &ProjectProfile) -> Vec<(&'static str, Value)> {
+    let mut configurations = Vec::new();
+    rust_configurations(profile, &mut configurations);
+    python_configurations(profile, &mut configurations);
+    node_configurations(profile, &mut configurations);
+    go_configurations(profile, &mut configurations);
+    dotnet_configurations(profile, &mut configurations);
+    configurations
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    fn names(configs: &[(&str, Value)]) -> Vec<String> {
+        configs
+            .iter()
+            .map(|(_, c)| c["name"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn reads_package_name_from_cargo_toml() {
+        let content =
+            "[workspace]\nmembers = []\n\n[package]\nname = \"cortex\"\nversion = \"0.1.0\"\n";
+        assert_eq!(
+            toml_value(content, "package", "name").as_deref(),
+            Some("cortex")
+        );
+        assert_eq!(toml_value(content, "package", "edition"), None);
+    }
+
+    #[test]
+    fn detects_projects_from_manifests() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("Cargo.toml"),
+            "[package]\nname = \"demo\"\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("package.json"),
+            r#"{"main": "./dist/index.js"}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("App.csproj"),
+            "<Project><PropertyGroup><TargetFramework>net9.0</TargetFramework></PropertyGroup></Project>",
+        )
+        .unwrap();
+
+        let profile = ProjectProfile::detect(dir.path(), &ProjectToolchains::default());
+        assert_eq!(profile.cargo_package.as_deref(), Some("demo"));
+        assert_eq!(profile.node_main.as_deref(), Some("./dist/index.js"));
+        assert_eq!(
+            profile.dotnet_project,
+            Some(DotnetProject {
+                name: "App".to_string(),
+                target_framework: Some("net9.0".to_string()),
+            })
+        );
+        assert!(!profile.go_module);
+        assert!(!profile.python_project);
+    }
+
+    #[test]
+    fn generates_configurations_per_project_type() {
+        let profile = ProjectProfile {
+            cargo_package: Some("demo".to_string()),
+            rust_toolchain: Some("nightly".to_string()),
+            go_module: true,
+            test_framework: Some(TestFramework::Cargo),
+            ..Default::default()
+        };
+        let configs = default_configurations(&profile);
+        assert_eq!(
+            names(&configs),
+            vec![
+                "Debug executable 'demo'",
+                "Debug unit tests",
+                "Launch Package",
+                "Test Current Package",
+            ]
+        );
+        assert_eq!(configs[0].0, "codelldb");
+        assert_eq!(configs[0].1["cargo"]["env"]["RUSTUP_TOOLCHAIN"], "nightly");
+        assert_eq!(configs[2].0, "delve");
+    }
+
+    #[test]
+    fn uses_selected_interpreters_and_test_frameworks() {
+        let profile = ProjectProfile {
+            python_project: true,
+            python: Some("/work/.venv/bin/python".to_string()),
+            node_project: true,
+            node_version: Some("v20.11.0".to_string()),
+            test_framework: Some(TestFramework::Jest),
+            ..Default::default()
+        };
+        let configs = default_configurations(&profile);
+        assert_eq!(configs[0].1["python"], "/work/.venv/bin/python");
+        let jest = configs
+            .iter()
+            .find(|(_, c)| c["name"] == "Jest: current file")
+            .unwrap();
+        assert_eq!(jest.1["runtimeVersion"], "20.11.0");
+        assert_eq!(configs[1].1["program"], "${file}");
+    }
+}
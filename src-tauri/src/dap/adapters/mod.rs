@@ -0,0 +1,119 @@
+//! Managed debug adapters
+//!
+//! Adapters from the catalog are installed by the language server installer
+//! into their own root:
+//!
+//! ```text
+//! <data>/Cortex/debug-adapters/<id>/manifest.json
+//! <data>/Cortex/debug-adapters/<id>/<version>/...
+//! ```
+//!
+//! Sessions prefer an installed adapter over one found on the system.
+
+pub(crate) mod catalog;
+pub(crate) mod defaults;
+
+use serde::{Deserialize, Serialize};
+
+use crate::lsp::installer::{self, InstallRoot, ManagedServerInfo};
+use catalog::AdapterEntry;
+
+/// Root of managed debug adapters
+pub(crate) const DEBUG_ADAPTERS: InstallRoot = InstallRoot::new("debug-adapters");
+
+/// Catalog adapter together with its install state
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManagedAdapterInfo {
+    #[serde(flatten)]
+    pub package: ManagedServerInfo,
+    pub debug_types: Vec<String>,
+}
+
+pub(crate) fn status(entry: &AdapterEntry) -> ManagedAdapterInfo {
+    ManagedAdapterInfo {
+        package: installer::status(DEBUG_ADAPTERS, &entry.package),
+        debug_types: entry.debug_types.iter().map(|t| t.to_string()).collect(),
+    }
+}
+
+/// Whether the adapter is installed from the catalog
+pub(crate) fn is_installed(entry: &AdapterEntry) -> bool {
+    installer::managed_command(DEBUG_ADAPTERS, entry.id()).is_some()
+}
+
+/// Command line of the installed adapter for a launch configuration `type`
+pub(crate) fn managed_adapter_command(debug_type: &str) -> Option<(String, Vec<String>)> {
+    let entry = catalog::find_by_debug_type(debug_type)?;
+    let path = installer::managed_command(DEBUG_ADAPTERS, entry.id())?;
+    Some(entry.command_line(&path))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn adapter_ids_and_types_are_unique() {
+        let mut ids: Vec<&str> = catalog::ADAPTERS.iter().map(|a| a.id()).collect();
+        let mut types: Vec<&str> = catalog::ADAPTERS
+            .iter()
+            .flat_map(|a| a.debug_types.iter().copied())
+            .collect();
+        let (id_count, type_count) = (ids.len(), types.len());
+        ids.sort();
+        ids.dedup();
+        types.sort();
+        types.dedup();
+        assert_eq!(ids.len(), id_count);
+        assert_eq!(types.len(), type_count);
+    }
+
+    #[test]
+    fn resolves_adapters_by_debug_type() {
+        assert_eq!(
+            catalog::find_by_debug_type("Rust").unwrap().id(),
+            "codelldb"
+        );
+        assert_eq!(
+            catalog::find_by_debug_type("pwa-node").unwrap().id(),
+            "js-debug"
+        );
+        assert_eq!(
+            catalog::find_by_debug_type("coreclr").unwrap().id(),
+            "netcoredbg"
+        );
+        assert!(catalog::find_by_debug_type("cppdbg").is_none());
+    }
+
+    #[test]
+    fn script_adapters_run_under_their_runtime() {
+        let js = catalog::find("js-debug").unwrap();
+        let (command, args) = js.command_line(Path::new("/a/dapDebugServer.js"));
+        assert_eq!(command, "node");
+        assert_eq!(args, vec!["/a/dapDebugServer.js"]);
+
+        let delve = catalog::find("delve").unwrap();
+        let (command, args) = delve.command_line(Path::new("/a/dlv"));
+        assert_eq!(command, "/a/dlv");
+        assert_eq!(args, vec!["dap"]);
+    }
+
+    #[test]
+    fn every_adapter_has_a_build_for_common_targets() {
+        for entry in catalog::ADAPTERS {
+            if let installer::catalog::InstallMethod::GithubRelease { assets, .. } =
+                entry.package.method
+            {
+                assert!(
+                    assets
+                        .iter()
+                        .any(|a| a.target == "x86_64-linux" || a.target == "*"),
+                    "{} has no linux build",
+                    entry.id()
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,117 @@
+//! Managed debug adapter commands
+//!
+//! Commands for listing, installing, updating and removing the adapters from
+//! the built-in catalog, and for proposing launch configurations that use them.
+
+use std::path::PathBuf;
+
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::error;
+
+use super::super::adapters::defaults::{
+    DefaultLaunchConfig, ProjectProfile, default_configurations,
+};
+use super::super::adapters::{self, DEBUG_ADAPTERS, ManagedAdapterInfo, catalog};
+use crate::lsp::installer::{self, InstallProgressEvent, ServerUpdateInfo};
+use crate::testing::detection::testing_detect_framework;
+use crate::testing::types::TestFramework;
+use crate::toolchain::ToolchainState;
+
+fn entry(adapter_id: &str) -> Result<&'static catalog::AdapterEntry, String> {
+    catalog::find(adapter_id).ok_or_else(|| format!("Unknown debug adapter: {}", adapter_id))
+}
+
+fn progress_emitter(app: AppHandle) -> impl Fn(InstallProgressEvent) + Send + Sync {
+    move |event| {
+        if let Err(e) = app.emit("debug:adapter-install-progress", &event) {
+            error!("Failed to emit install progress: {}", e);
+        }
+    }
+}
+
+/// List catalog adapters with their install state
+#[tauri::command]
+pub async fn debug_adapters_list() -> Result<Vec<ManagedAdapterInfo>, String> {
+    tokio::task::spawn_blocking(|| catalog::ADAPTERS.iter().map(adapters::status).collect())
+        .await
+        .map_err(|e| format!("Listing task failed: {}", e))
+}
+
+/// Install (or update to) the pinned version of an adapter
+#[tauri::command]
+pub async fn debug_adapters_install(
+    app: AppHandle,
+    adapter_id: String,
+) -> Result<ManagedAdapterInfo, String> {
+    let entry = entry(&adapter_id)?;
+    let progress = progress_emitter(app);
+    installer::install(DEBUG_ADAPTERS, &entry.package, &progress).await?;
+    Ok(adapters::status(entry))
+}
+
+/// Install an adapter from a local archive, for machines without network access
+#[tauri::command]
+pub async fn debug_adapters_install_from_archive(
+    app: AppHandle,
+    adapter_id: String,
+    archive_path: String,
+) -> Result<ManagedAdapterInfo, String> {
+    let entry = entry(&adapter_id)?;
+    let progress = progress_emitter(app);
+    installer::install_from_archive(
+        DEBUG_ADAPTERS,
+        &entry.package,
+        &PathBuf::from(archive_path),
+        &progress,
+    )
+    .await?;
+    Ok(adapters::status(entry))
+}
+
+/// Remove an installed adapter
+#[tauri::command]
+pub async fn debug_adapters_uninstall(adapter_id: String) -> Result<(), String> {
+    let entry = entry(&adapter_id)?;
+    tokio::task::spawn_blocking(move || installer::uninstall(DEBUG_ADAPTERS, &entry.package))
+        .await
+        .map_err(|e| format!("Uninstall task failed: {}", e))?
+}
+
+/// Compare installed adapters with the catalog and with upstream releases
+#[tauri::command]
+pub async fn debug_adapters_check_updates() -> Result<Vec<ServerUpdateInfo>, String> {
+    Ok(
+        installer::check_updates(DEBUG_ADAPTERS, catalog::ADAPTERS.iter().map(|a| &a.package))
+            .await,
+    )
+}
+
+/// Propose launch configurations for the project at `workspace_path`
+#[tauri::command]
+pub async fn debug_default_launch_configs(
+    app: AppHandle,
+    workspace_path: String,
+) -> Result<Vec<DefaultLaunchConfig>, String> {
+    let root = PathBuf::from(&workspace_path);
+    let selected = app
+        .state::<ToolchainState>()
+        .0
+        .lock()
+        .get_project_toolchains(&root);
+    let framework = testing_detect_framework(workspace_path).await?.framework;
+
+    tokio::task::spawn_blocking(move || {
+        let mut profile = ProjectProfile::detect(&root, &selected);
+        profile.test_framework = (framework != TestFramework::Unknown).then_some(framework);
+        default_configurations(&profile)
+            .into_iter()
+            .map(|(adapter_id, configuration)| DefaultLaunchConfig {
+                adapter_id: adapter_id.to_string(),
+                installed: catalog::find(adapter_id).is_some_and(adapters::is_installed),
+                configuration,
+            })
+            .collect()
+    })
+    .await
+    .map_err(|e| format!("Detection task failed: {}", e))
+}
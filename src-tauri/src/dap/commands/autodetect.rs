@@ -5,6 +5,8 @@
 use serde::{Deserialize, Serialize};
 use tracing::info;
 
+use super::super::adapters::managed_adapter_command;
+
 /// Result of auto-detecting a debug adapter
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -37,7 +39,7 @@ fn command_exists(cmd: &str) -> bool {
 }
 
 fn detect_adapters() -> Vec<DebugAdapterInfo> {
-    vec![
+    let adapters = vec![
         // Node.js debug adapter (built-in)
         DebugAdapterInfo {
             language: "javascript".to_string(),
@@ -83,7 +85,29 @@ fn detect_adapters() -> Vec<DebugAdapterInfo> {
             args: vec!["dap".to_string()],
             installed: command_exists("dlv"),
         },
-    ]
+        // .NET - netcoredbg
+        DebugAdapterInfo {
+            language: "csharp".to_string(),
+            adapter_name: "netcoredbg".to_string(),
+            adapter_type: "coreclr".to_string(),
+            command: "netcoredbg".to_string(),
+            args: vec!["--interpreter=vscode".to_string()],
+            installed: command_exists("netcoredbg"),
+        },
+    ];
+
+    // Adapters installed from the catalog are used ahead of system ones
+    adapters
+        .into_iter()
+        .map(|mut info| {
+            if let Some((command, args)) = managed_adapter_command(&info.adapter_type) {
+                info.command = command;
+                info.args = args;
+                info.installed = true;
+            }
+            info
+        })
+        .collect()
 }
 
 /// Detect available debug adapters on the system
@@ -127,6 +151,7 @@ mod tests {
         assert!(languages.contains(&"rust"));
         assert!(languages.contains(&"cpp"));
         assert!(languages.contains(&"go"));
+        assert!(languages.contains(&"csharp"));
     }
 
     #[test]
@@ -0,0 +1,221 @@
+//! launch.json commands
+//!
+//! Starts a named configuration or compound from `.vscode/launch.json`. Every
+//! configuration is resolved before anything runs, so a missing input fails
+//! the launch up front. Pre-launch tasks run to completion (or readiness, for
+//! background tasks) before their sessions start; post-debug tasks and
+//! `stopAll` are handled when a session ends.
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use super::super::launch::{
+    LaunchFile, LaunchTarget, LaunchVariables, RequiredVariables, resolve_configuration,
+};
+use super::session::start_session;
+use super::state::{CompoundSessions, DebuggerState, PostDebugTask};
+use super::types::DebugSessionInfo;
+use crate::LazyState;
+use crate::tasks::{run_task_until_ready, tasks_run_with_dependencies};
+
+/// Sessions started by `debug_launch`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DebugLaunchResult {
+    /// Set when the launch was a compound
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compound_id: Option<String>,
+    pub sessions: Vec<DebugSessionInfo>,
+}
+
+/// Stop a session and forget it
+async fn stop_session(state: &DebuggerState, session_id: &str) {
+    let session = state.sessions.read().await.get(session_id).cloned();
+    if let Some(session) = session {
+        if let Err(e) = session.read().await.stop(true).await {
+            warn!("Failed to stop debug session {}: {}", session_id, e);
+        }
+    }
+    state.sessions.write().await.remove(session_id);
+}
+
+/// Start the post-debug task of a session, if it has one
+async fn run_post_debug_task(app: &AppHandle, state: &DebuggerState, session_id: &str) {
+    let Some(post) = state.post_debug_tasks.write().await.remove(session_id) else {
+        return;
+    };
+    info!("Running postDebugTask '{}'", post.task);
+    if let Err(e) =
+        tasks_run_with_dependencies(post.task.clone(), Some(post.workspace_path), app.clone()).await
+    {
+        warn!("Failed to run postDebugTask '{}': {}", post.task, e);
+    }
+}
+
+/// Stop sessions and run their post-debug tasks
+async fn end_sessions(app: &AppHandle, state: &DebuggerState, session_ids: &[String]) {
+    for session_id in session_ids {
+        stop_session(state, session_id).await;
+        run_post_debug_task(app, state, session_id).await;
+    }
+}
+
+/// Called whenever a session ends, by terminating or being stopped
+///
+/// Stops the session's rr replay, runs its post-debug task and, if the
+/// session belongs to a compound with `stopAll`, stops the rest of the
+/// compound.
+pub(super) async fn session_ended(app: &AppHandle, session_id: &str) {
+    let state = app.state::<LazyState<DebuggerState>>();
+    let state = state.get();
+    state.replay_servers.write().await.remove(session_id);
+    run_post_debug_task(app, state, session_id).await;
+
+    let peers = {
+        let mut compounds = state.compounds.write().await;
+        let Some((compound_id, compound)) = compounds
+            .iter_mut()
+            .find(|(_, c)| c.session_ids.iter().any(|id| id == session_id))
+        else {
+            return;
+        };
+        compound.session_ids.retain(|id| id != session_id);
+        if !compound.stop_all && !compound.session_ids.is_empty() {
+            return;
+        }
+        let compound_id = compound_id.clone();
+        compounds
+            .remove(&compound_id)
+            .map(|c| c.session_ids)
+            .unwrap_or_default()
+    };
+    end_sessions(app, state, &peers).await;
+}
+
+/// List the `${input:*}` and `${command:*}` variables a launch needs
+#[tauri::command]
+pub async fn debug_get_launch_variables(
+    workspace_path: String,
+    name: String,
+) -> Result<RequiredVariables, String> {
+    LaunchFile::load(&workspace_path)?.required_variables(&name)
+}
+
+/// Start a configuration or compound from launch.json
+#[tauri::command]
+pub async fn debug_launch(
+    app: AppHandle,
+    state: State<'_, LazyState<DebuggerState>>,
+    workspace_path: String,
+    name: String,
+    variables: Option<LaunchVariables>,
+) -> Result<DebugLaunchResult, String> {
+    let state = state.get();
+    let variables = variables.unwrap_or_default();
+    let file = LaunchFile::load(&workspace_path)?;
+    let compound = match file.target(&name) {
+        Some(LaunchTarget::Compound(compound)) => Some(compound.clone()),
+        _ => None,
+    };
+    let launches = file
+        .configurations_for(&name)?
+        .into_iter()
+        .map(|config| {
+            resolve_configuration(
+                config,
+                &workspace_path,
+                &variables,
+                Uuid::new_v4().to_string(),
+            )
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let compound_id = match &compound {
+        Some(compound) => {
+            if let Some(task) = &compound.pre_launch_task {
+                run_task_until_ready(task, &workspace_path, &app)
+                    .await
+                    .map_err(|e| format!("preLaunchTask '{}': {}", task, e))?;
+            }
+            let compound_id = Uuid::new_v4().to_string();
+            state.compounds.write().await.insert(
+                compound_id.clone(),
+                CompoundSessions {
+                    name: compound.name.clone(),
+                    session_ids: Vec::new(),
+                    stop_all: compound.stop_all,
+                },
+            );
+            Some(compound_id)
+        }
+        None => None,
+    };
+
+    let mut sessions = Vec::new();
+    for launch in launches {
+        let session_id = launch.config.id.clone();
+        let result = async {
+            if let Some(task) = &launch.pre_launch_task {
+                run_task_until_ready(task, &workspace_path, &app)
+                    .await
+                    .map_err(|e| format!("preLaunchTask '{}': {}", task, e))?;
+            }
+            if let Some(task) = launch.post_debug_task {
+                state.post_debug_tasks.write().await.insert(
+                    session_id.clone(),
+                    PostDebugTask {
+                        task,
+                        workspace_path: workspace_path.clone(),
+                    },
+                );
+            }
+            if let Some(compound_id) = &compound_id {
+                if let Some(compound) = state.compounds.write().await.get_mut(compound_id) {
+                    compound.session_ids.push(session_id.clone());
+                }
+            }
+            start_session(&app, state, launch.config).await
+        }
+        .await;
+
+        match result {
+            Ok(info) => sessions.push(info),
+            Err(e) => {
+                // A compound starts completely or not at all
+                state.post_debug_tasks.write().await.remove(&session_id);
+                if let Some(compound_id) = &compound_id {
+                    state.compounds.write().await.remove(compound_id);
+                }
+                let started: Vec<_> = sessions.iter().map(|s| s.id.clone()).collect();
+                end_sessions(&app, state, &started).await;
+                return Err(e);
+            }
+        }
+    }
+
+    Ok(DebugLaunchResult {
+        compound_id,
+        sessions,
+    })
+}
+
+/// Stop every session of a compound
+#[tauri::command]
+pub async fn debug_stop_compound(
+    app: AppHandle,
+    state: State<'_, LazyState<DebuggerState>>,
+    compound_id: String,
+) -> Result<(), String> {
+    let state = state.get();
+    let compound = state
+        .compounds
+        .write()
+        .await
+        .remove(&compound_id)
+        .ok_or_else(|| format!("Compound not found: {}", compound_id))?;
+    info!("Stopping compound '{}'", compound.name);
+    end_sessions(&app, state, &compound.session_ids).await;
+    Ok(())
+}
@@ -7,6 +7,9 @@
 //! - `state`: Global debugger state management
 //! - `types`: Request/response types for commands
 //! - `session`: Session management (start, stop, get sessions)
+//! - `launch`: launch.json configurations and compounds
+//! - `adapters`: Managed adapter installs and default launch configurations
+//! - `rr`: rr recordings and replays with reverse execution
 //! - `breakpoints`: Breakpoint management (set, toggle, get)
 //! - `execution`: Execution control (continue, pause, step)
 //! - `threads`: Thread and stack frame operations
@@ -15,12 +18,15 @@
 //! - `navigation`: Code navigation (goto, step-in targets)
 //! - `misc`: Miscellaneous commands (completions, sources, modules)
 
+mod adapters;
 mod autodetect;
 mod breakpoints;
 mod execution;
+mod launch;
 mod memory;
 mod misc;
 mod navigation;
+mod rr;
 mod session;
 mod state;
 mod threads;
@@ -35,6 +41,15 @@ pub use state::DebuggerState;
 // Re-export session commands (using * to include hidden __cmd__ functions)
 pub use session::*;
 
+// Re-export launch.json commands
+pub use launch::*;
+
+// Re-export managed adapter commands
+pub use adapters::*;
+
+// Re-export rr commands
+pub use rr::*;
+
 // Re-export breakpoint commands
 pub use breakpoints::*;
 
@@ -0,0 +1,176 @@
+//! rr record/replay commands
+//!
+//! Records native programs with rr and debugs the recordings as replays with
+//! reverse execution. A launch configuration with `"rr": true` does both in
+//! one step; recordings can also be made on their own (for example of a
+//! failing test run) and replayed later.
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, State};
+use tokio::sync::oneshot;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use super::super::DebugSessionConfig;
+use super::super::rr::{self, RecordRequest, RecordingInfo, ReplayDebugger, ReplayServer};
+use super::session::start_adapter_session;
+use super::state::DebuggerState;
+use super::types::DebugSessionInfo;
+use crate::LazyState;
+
+/// Output of a program being recorded
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RrRecordOutput {
+    /// ID of the session (or recording request) the program belongs to
+    pub id: String,
+    pub line: String,
+}
+
+/// Record `config`, registered under its ID so it can be stopped
+async fn record_with_output(
+    app: &AppHandle,
+    state: &DebuggerState,
+    config: &DebugSessionConfig,
+) -> Result<RecordingInfo, String> {
+    let request = RecordRequest::from_config(config)?;
+    let (stop_tx, stop_rx) = oneshot::channel();
+    state
+        .recordings
+        .write()
+        .await
+        .insert(config.id.clone(), stop_tx);
+
+    let id = config.id.clone();
+    let app = app.clone();
+    let result = rr::record(request, stop_rx, move |line| {
+        let output = RrRecordOutput {
+            id: id.clone(),
+            line: line.to_string(),
+        };
+        if let Err(e) = app.emit("debug:rr-output", &output) {
+            error!("Failed to emit rr output: {}", e);
+        }
+    })
+    .await;
+    state.recordings.write().await.remove(&config.id);
+
+    let recording = result?;
+    info!(
+        "Recorded '{}' as {} (exit code {:?})",
+        recording.name, recording.id, recording.exit_code
+    );
+    Ok(recording)
+}
+
+/// Start a replay of `recording` and debug it
+///
+/// When the replay stands in for a launch configuration, the session keeps
+/// that configuration's ID and adapter overrides.
+async fn replay(
+    app: &AppHandle,
+    state: &DebuggerState,
+    recording: &RecordingInfo,
+    debugger: ReplayDebugger,
+    launch: Option<&DebugSessionConfig>,
+) -> Result<DebugSessionInfo, String> {
+    let server = ReplayServer::start(&recording.id).await?;
+    let session_id = launch
+        .map(|c| c.id.clone())
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+    let mut config = rr::replay_config(session_id.clone(), recording, debugger, server.address());
+    if let Some(launch) = launch {
+        config.adapter_path = launch.adapter_path.clone();
+        config.adapter_args = launch.adapter_args.clone();
+    }
+
+    state
+        .replay_servers
+        .write()
+        .await
+        .insert(session_id.clone(), server);
+    let result = start_adapter_session(app, state, config).await;
+    if result.is_err() {
+        state.replay_servers.write().await.remove(&session_id);
+    }
+    result
+}
+
+/// Record a launch configuration with rr, then debug the replay
+///
+/// The replay starts once the program exits or the recording is stopped with
+/// `debug_rr_stop_recording`.
+pub(super) async fn record_and_replay(
+    app: &AppHandle,
+    state: &DebuggerState,
+    config: DebugSessionConfig,
+) -> Result<DebugSessionInfo, String> {
+    let recording = record_with_output(app, state, &config).await?;
+    let debugger = ReplayDebugger::for_type(&config.type_);
+    replay(app, state, &recording, debugger, Some(&config)).await
+}
+
+/// Whether rr is available on this machine
+#[tauri::command]
+pub async fn debug_rr_available() -> Result<bool, String> {
+    Ok(rr::is_available().await)
+}
+
+/// Record a program with rr without debugging it
+#[tauri::command]
+pub async fn debug_rr_record(
+    app: AppHandle,
+    state: State<'_, LazyState<DebuggerState>>,
+    config: DebugSessionConfig,
+) -> Result<RecordingInfo, String> {
+    record_with_output(&app, state.get(), &config).await
+}
+
+/// Stop a running recording, keeping what was recorded so far
+///
+/// `id` is the ID of the configuration being recorded.
+#[tauri::command]
+pub async fn debug_rr_stop_recording(
+    state: State<'_, LazyState<DebuggerState>>,
+    id: String,
+) -> Result<(), String> {
+    let stop = state
+        .get()
+        .recordings
+        .write()
+        .await
+        .remove(&id)
+        .ok_or_else(|| format!("No recording in progress: {}", id))?;
+    // The recording may have ended on its own in the meantime
+    let _ = stop.send(());
+    Ok(())
+}
+
+/// List saved recordings, newest first
+#[tauri::command]
+pub async fn debug_rr_list_recordings() -> Result<Vec<RecordingInfo>, String> {
+    tokio::task::spawn_blocking(rr::list)
+        .await
+        .map_err(|e| format!("Listing task failed: {}", e))
+}
+
+/// Delete a saved recording
+#[tauri::command]
+pub async fn debug_rr_delete_recording(recording_id: String) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || rr::delete(&recording_id))
+        .await
+        .map_err(|e| format!("Delete task failed: {}", e))?
+}
+
+/// Debug a saved recording with reverse execution
+#[tauri::command]
+pub async fn debug_rr_replay(
+    app: AppHandle,
+    state: State<'_, LazyState<DebuggerState>>,
+    recording_id: String,
+    debugger: Option<ReplayDebugger>,
+) -> Result<DebugSessionInfo, String> {
+    let recording = rr::load(&recording_id)?;
+    let debugger = debugger.unwrap_or(ReplayDebugger::Gdb);
+    replay(&app, state.get(), &recording, debugger, None).await
+}
@@ -8,7 +8,10 @@ use std::sync::Arc;
 use tauri::{AppHandle, Emitter, State};
 use tokio::sync::{RwLock, mpsc};
 
+use super::super::rr::is_record_launch;
 use super::super::{DebugSession, DebugSessionConfig, DebugSessionEvent, DebugSessionState};
+use super::launch::session_ended;
+use super::rr::record_and_replay;
 use super::state::DebuggerState;
 use super::types::DebugSessionInfo;
 use crate::LazyState;
@@ -19,6 +22,30 @@ pub async fn debug_start_session(
     app: AppHandle,
     state: State<'_, LazyState<DebuggerState>>,
     config: DebugSessionConfig,
+) -> Result<DebugSessionInfo, String> {
+    start_session(&app, state.get(), config).await
+}
+
+/// Create, start and register a debug session
+///
+/// Configurations with `"rr": true` are recorded first and debugged as a
+/// replay.
+pub(super) async fn start_session(
+    app: &AppHandle,
+    state: &DebuggerState,
+    config: DebugSessionConfig,
+) -> Result<DebugSessionInfo, String> {
+    if is_record_launch(&config) {
+        return record_and_replay(app, state, config).await;
+    }
+    start_adapter_session(app, state, config).await
+}
+
+/// Start an adapter for `config` and register the session
+pub(super) async fn start_adapter_session(
+    app: &AppHandle,
+    state: &DebuggerState,
+    config: DebugSessionConfig,
 ) -> Result<DebugSessionInfo, String> {
     let session_id = config.id.clone();
     let session_name = config.name.clone();
@@ -28,7 +55,7 @@ pub async fn debug_start_session(
     let (event_tx, mut event_rx) = mpsc::unbounded_channel::<DebugSessionEvent>();
 
     // Store event sender
-    *state.get().event_tx.write().await = Some(event_tx.clone());
+    *state.event_tx.write().await = Some(event_tx.clone());
 
     // Forward events to frontend
     let app_clone = app.clone();
@@ -38,6 +65,10 @@ pub async fn debug_start_session(
             // Emit event to frontend
             let _ = app_clone.emit(&format!("debug:event:{}", session_id_clone), &event);
             let _ = app_clone.emit("debug:event", &event);
+
+            if matches!(event, DebugSessionEvent::Terminated { restart: false }) {
+                session_ended(&app_clone, &session_id_clone).await;
+            }
         }
     });
 
@@ -57,7 +88,6 @@ pub async fn debug_start_session(
 
     // Store the session
     state
-        .get()
         .sessions
         .write()
         .await
@@ -81,6 +111,7 @@ pub async fn debug_start_session(
 /// Stop a debug session
 #[tauri::command]
 pub async fn debug_stop_session(
+    app: AppHandle,
     state: State<'_, LazyState<DebuggerState>>,
     session_id: String,
     terminate_debuggee: bool,
@@ -101,6 +132,7 @@ pub async fn debug_stop_session(
 
     // Remove the session
     state.get().sessions.write().await.remove(&session_id);
+    session_ended(&app, &session_id).await;
 
     Ok(())
 }
@@ -162,6 +194,7 @@ pub async fn debug_get_capabilities(
 /// Terminate a debug session (alias for stop with terminate_debuggee=true)
 #[tauri::command]
 pub async fn debug_terminate(
+    app: AppHandle,
     state: State<'_, LazyState<DebuggerState>>,
     session_id: String,
 ) -> Result<(), String> {
@@ -181,6 +214,7 @@ pub async fn debug_terminate(
 
     // Remove the session
     state.get().sessions.write().await.remove(&session_id);
+    session_ended(&app, &session_id).await;
 
     Ok(())
 }
@@ -188,6 +222,7 @@ pub async fn debug_terminate(
 /// Disconnect from a debug session without terminating the debuggee
 #[tauri::command]
 pub async fn debug_disconnect(
+    app: AppHandle,
     state: State<'_, LazyState<DebuggerState>>,
     session_id: String,
 ) -> Result<(), String> {
@@ -207,6 +242,7 @@ pub async fn debug_disconnect(
 
     // Remove the session
     state.get().sessions.write().await.remove(&session_id);
+    session_ended(&app, &session_id).await;
 
     Ok(())
 }
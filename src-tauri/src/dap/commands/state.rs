@@ -5,16 +5,40 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 
-use tokio::sync::{RwLock, mpsc};
+use tokio::sync::{RwLock, mpsc, oneshot};
 
+use super::super::rr::ReplayServer;
 use super::super::{DebugSession, DebugSessionEvent};
 
+/// Task to run once a session launched from launch.json ends
+pub(crate) struct PostDebugTask {
+    pub task: String,
+    pub workspace_path: String,
+}
+
+/// Sessions started together from a launch.json compound
+pub(crate) struct CompoundSessions {
+    pub name: String,
+    /// Sessions of the compound that are still running
+    pub session_ids: Vec<String>,
+    /// Stop the remaining sessions when one of them ends
+    pub stop_all: bool,
+}
+
 /// Global state for managing debug sessions
 pub struct DebuggerState {
     /// Active debug sessions by ID
     pub(crate) sessions: RwLock<HashMap<String, Arc<RwLock<DebugSession>>>>,
     /// Event channel for broadcasting debug events
     pub(crate) event_tx: RwLock<Option<mpsc::UnboundedSender<DebugSessionEvent>>>,
+    /// Post-debug tasks by session ID
+    pub(crate) post_debug_tasks: RwLock<HashMap<String, PostDebugTask>>,
+    /// Running compounds by compound ID
+    pub(crate) compounds: RwLock<HashMap<String, CompoundSessions>>,
+    /// rr replay servers by the ID of the session debugging them
+    pub(crate) replay_servers: RwLock<HashMap<String, ReplayServer>>,
+    /// Stop signals of running rr recordings by session (or request) ID
+    pub(crate) recordings: RwLock<HashMap<String, oneshot::Sender<()>>>,
 }
 
 impl DebuggerState {
@@ -22,6 +46,10 @@ impl DebuggerState {
         Self {
             sessions: RwLock::new(HashMap::new()),
             event_tx: RwLock::new(None),
+            post_debug_tasks: RwLock::new(HashMap::new()),
+            compounds: RwLock::new(HashMap::new()),
+            replay_servers: RwLock::new(HashMap::new()),
+            recordings: RwLock::new(HashMap::new()),
         }
     }
 
@@ -0,0 +1,437 @@
+//! launch.json resolution
+//!
+//! Reads `.vscode/launch.json` and turns a named configuration or compound into
+//! session configs ready for `DebugSession::new`. Variables are substituted the
+//! way tasks do it; `${input:*}` and `${command:*}` values come from the
+//! frontend, which prompts for them before launching. `preLaunchTask` and
+//! `postDebugTask` are taken out of the config and returned separately since
+//! they are run by Cortex, not by the adapter.
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::PathBuf;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::DebugSessionConfig;
+use crate::tasks::{remove_json_comments, substitute_variables};
+
+#[allow(clippy::expect_used)]
+static PROMPT_VAR_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\$\{(input|command):([^}]+)\}").expect("Invalid launch variable regex")
+});
+
+/// Contents of `.vscode/launch.json`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LaunchFile {
+    #[serde(default)]
+    pub configurations: Vec<Value>,
+    #[serde(default)]
+    pub compounds: Vec<CompoundConfig>,
+    #[serde(default)]
+    pub inputs: Vec<Value>,
+}
+
+/// A compound launch: several configurations started together
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompoundConfig {
+    pub name: String,
+    pub configurations: Vec<CompoundMember>,
+    #[serde(default)]
+    pub pre_launch_task: Option<String>,
+    /// Stop every session of the compound when one of them ends
+    #[serde(default)]
+    pub stop_all: bool,
+}
+
+/// A configuration in a compound, by name or as `{"name", "folder"}`
+///
+/// The folder is ignored: configurations are looked up in this workspace.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum CompoundMember {
+    Name(String),
+    InFolder { name: String },
+}
+
+impl CompoundMember {
+    pub fn name(&self) -> &str {
+        match self {
+            CompoundMember::Name(name) | CompoundMember::InFolder { name } => name,
+        }
+    }
+}
+
+/// Values for the variables that need the frontend
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LaunchVariables {
+    /// Map: input id -> value
+    #[serde(default)]
+    pub inputs: HashMap<String, String>,
+    /// Map: command id -> value
+    #[serde(default)]
+    pub commands: HashMap<String, String>,
+    #[serde(default)]
+    pub file: Option<String>,
+    #[serde(default)]
+    pub line_number: Option<u32>,
+    #[serde(default)]
+    pub selected_text: Option<String>,
+}
+
+/// Variables a launch refers to that the frontend has to supply
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RequiredVariables {
+    /// Input definitions from launch.json, or `{"id": ...}` when undefined
+    pub inputs: Vec<Value>,
+    /// Command ids
+    pub commands: Vec<String>,
+}
+
+/// A configuration ready to start, with the tasks around it
+#[derive(Debug, Clone)]
+pub struct ResolvedLaunch {
+    pub config: DebugSessionConfig,
+    pub pre_launch_task: Option<String>,
+    pub post_debug_task: Option<String>,
+}
+
+/// What a launch name refers to
+pub enum LaunchTarget<'a> {
+    Configuration(&'a Value),
+    Compound(&'a CompoundConfig),
+}
+
+impl LaunchFile {
+    pub fn load(workspace_path: &str) -> Result<Self, String> {
+        let path = PathBuf::from(workspace_path)
+            .join(".vscode")
+            .join("launch.json");
+        let content = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        Self::parse(&content)
+    }
+
+    pub fn parse(content: &str) -> Result<Self, String> {
+        serde_json::from_str(&remove_json_comments(content))
+            .map_err(|e| format!("Failed to parse launch.json: {}", e))
+    }
+
+    pub fn configuration(&self, name: &str) -> Option<&Value> {
+        self.configurations
+            .iter()
+            .find(|c| c.get("name").and_then(|n| n.as_str()) == Some(name))
+    }
+
+    /// Find a compound or configuration by name; compounds win on a clash
+    pub fn target(&self, name: &str) -> Option<LaunchTarget<'_>> {
+        if let Some(compound) = self.compounds.iter().find(|c| c.name == name) {
+            return Some(LaunchTarget::Compound(compound));
+        }
+        self.configuration(name).map(LaunchTarget::Configuration)
+    }
+
+    /// Configurations a launch name starts
+    pub fn configurations_for(&self, name: &str) -> Result<Vec<&Value>, String> {
+        match self.target(name) {
+            Some(LaunchTarget::Configuration(config)) => Ok(vec![config]),
+            Some(LaunchTarget::Compound(compound)) => compound
+                .configurations
+                .iter()
+                .map(|member| {
+                    self.configuration(member.name()).ok_or_else(|| {
+                        format!(
+                            "Compound '{}' refers to unknown configuration '{}'",
+                            compound.name,
+                            member.name()
+                        )
+                    })
+                })
+                .collect(),
+            None => Err(format!("Launch configuration '{}' not found", name)),
+        }
+    }
+
+    /// Inputs and commands referenced by the configurations of `name`
+    pub fn required_variables(&self, name: &str) -> Result<RequiredVariables, String> {
+        let mut inputs = BTreeSet::new();
+        let mut commands = BTreeSet::new();
+        for config in self.configurations_for(name)? {
+            collect_prompt_variables(config, &mut inputs, &mut commands);
+        }
+
+        let inputs = inputs
+            .into_iter()
+            .map(|id| {
+                self.inputs
+                    .iter()
+                    .find(|i| i.get("id").and_then(|v| v.as_str()) == Some(id.as_str()))
+                    .cloned()
+                    .unwrap_or_else(|| serde_json::json!({ "id": id }))
+            })
+            .collect();
+        Ok(RequiredVariables {
+            inputs,
+            commands: commands.into_iter().collect(),
+        })
+    }
+}
+
+fn collect_prompt_variables(
+    value: &Value,
+    inputs: &mut BTreeSet<String>,
+    commands: &mut BTreeSet<String>,
+) {
+    match value {
+        Value::String(s) => {
+            for caps in PROMPT_VAR_RE.captures_iter(s) {
+                let set = if &caps[1] == "input" {
+                    &mut *inputs
+                } else {
+                    &mut *commands
+                };
+                set.insert(caps[2].to_string());
+            }
+        }
+        Value::Array(items) => items
+            .iter()
+            .for_each(|v| collect_prompt_variables(v, inputs, commands)),
+        Value::Object(map) => map
+            .values()
+            .for_each(|v| collect_prompt_variables(v, inputs, commands)),
+        _ => {}
+    }
+}
+
+/// Substitute variables in one string
+fn substitute(
+    input: &str,
+    workspace_path: &str,
+    variables: &LaunchVariables,
+) -> Result<String, String> {
+    let mut missing = None;
+    let prompted = PROMPT_VAR_RE.replace_all(input, |caps: &regex::Captures| {
+        let values = if &caps[1] == "input" {
+            &variables.inputs
+        } else {
+            &variables.commands
+        };
+        values.get(&caps[2]).cloned().unwrap_or_else(|| {
+            missing = Some(caps[0].to_string());
+            String::new()
+        })
+    });
+    if let Some(variable) = missing {
+        return Err(format!("No value given for {}", variable));
+    }
+
+    Ok(substitute_variables(
+        &prompted,
+        workspace_path,
+        variables.file.as_deref(),
+        variables.line_number,
+        variables.selected_text.as_deref(),
+    ))
+}
+
+fn substitute_value(
+    value: &Value,
+    workspace_path: &str,
+    variables: &LaunchVariables,
+) -> Result<Value, String> {
+    Ok(match value {
+        Value::String(s) => Value::String(substitute(s, workspace_path, variables)?),
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|v| substitute_value(v, workspace_path, variables))
+                .collect::<Result<_, _>>()?,
+        ),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(k, v)| Ok((k.clone(), substitute_value(v, workspace_path, variables)?)))
+                .collect::<Result<_, String>>()?,
+        ),
+        other => other.clone(),
+    })
+}
+
+/// Key of the platform-specific overrides in a configuration
+fn platform_key() -> &'static str {
+    if cfg!(target_os = "windows") {
+        "windows"
+    } else if cfg!(target_os = "macos") {
+        "osx"
+    } else {
+        "linux"
+    }
+}
+
+/// Resolve a launch.json configuration into a session config
+///
+/// Platform overrides (`linux`, `osx`, `windows`) are merged in, variables
+/// are substituted, and `session_id` becomes the session's id.
+pub fn resolve_configuration(
+    config: &Value,
+    workspace_path: &str,
+    variables: &LaunchVariables,
+    session_id: String,
+) -> Result<ResolvedLaunch, String> {
+    let Value::Object(map) = config else {
+        return Err("Launch configuration is not an object".to_string());
+    };
+    let mut map = map.clone();
+    let overrides = map.get(platform_key()).cloned();
+    for key in ["linux", "osx", "windows"] {
+        map.remove(key);
+    }
+    if let Some(Value::Object(overrides)) = overrides {
+        map.extend(overrides);
+    }
+
+    let mut map = map
+        .into_iter()
+        .map(|(k, v)| Ok((k, substitute_value(&v, workspace_path, variables)?)))
+        .collect::<Result<serde_json::Map<_, _>, String>>()?;
+    let take_task = |map: &mut serde_json::Map<String, Value>, key: &str| {
+        map.remove(key)
+            .and_then(|v| v.as_str().map(str::to_string))
+            .filter(|s| !s.is_empty())
+    };
+    let pre_launch_task = take_task(&mut map, "preLaunchTask");
+    let post_debug_task = take_task(&mut map, "postDebugTask");
+    map.insert("id".to_string(), Value::String(session_id));
+
+    let config = serde_json::from_value(Value::Object(map))
+        .map_err(|e| format!("Invalid launch configuration: {}", e))?;
+    Ok(ResolvedLaunch {
+        config,
+        pre_launch_task,
+        post_debug_task,
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const LAUNCH: &str = r#"{
+        // Comments are allowed
+        "version": "0.2.0",
+        "configurations": [
+            {
+                "name": "Server",
+                "type": "node",
+                "request": "launch",
+                "program": "${workspaceFolder}/server.js",
+                "args": ["--port", "${input:port}"],
+                "preLaunchTask": "build",
+                "postDebugTask": "cleanup"
+            },
+            {
+                "name": "Client",
+                "type": "chrome",
+                "request": "launch",
+                "url": "http://localhost:${command:pickPort}"
+            }
+        ],
+        "compounds": [
+            {
+                "name": "Full stack",
+                "configurations": ["Server", {"name": "Client", "folder": "web"}],
+                "preLaunchTask": "build",
+                "stopAll": true
+            }
+        ],
+        "inputs": [
+            {"id": "port", "type": "promptString", "default": "3000"}
+        ]
+    }"#;
+
+    #[test]
+    fn parses_compounds() {
+        let file = LaunchFile::parse(LAUNCH).unwrap();
+        let Some(LaunchTarget::Compound(compound)) = file.target("Full stack") else {
+            panic!("expected a compound");
+        };
+        assert!(compound.stop_all);
+        assert_eq!(compound.pre_launch_task.as_deref(), Some("build"));
+        let names: Vec<_> = compound.configurations.iter().map(|m| m.name()).collect();
+        assert_eq!(names, vec!["Server", "Client"]);
+        assert_eq!(file.configurations_for("Full stack").unwrap().len(), 2);
+        assert!(file.configurations_for("Missing").is_err());
+    }
+
+    #[test]
+    fn lists_required_variables() {
+        let file = LaunchFile::parse(LAUNCH).unwrap();
+        let required = file.required_variables("Full stack").unwrap();
+        assert_eq!(
+            required.inputs,
+            vec![json!({"id": "port", "type": "promptString", "default": "3000"})]
+        );
+        assert_eq!(required.commands, vec!["pickPort"]);
+    }
+
+    #[test]
+    fn resolves_variables_and_tasks() {
+        let file = LaunchFile::parse(LAUNCH).unwrap();
+        let variables = LaunchVariables {
+            inputs: HashMap::from([("port".to_string(), "8080".to_string())]),
+            ..Default::default()
+        };
+        let resolved = resolve_configuration(
+            file.configuration("Server").unwrap(),
+            "/work",
+            &variables,
+            "s1".to_string(),
+        )
+        .unwrap();
+        assert_eq!(resolved.config.id, "s1");
+        assert_eq!(resolved.config.program.as_deref(), Some("/work/server.js"));
+        assert_eq!(
+            resolved.config.args,
+            Some(vec!["--port".to_string(), "8080".to_string()])
+        );
+        assert_eq!(resolved.pre_launch_task.as_deref(), Some("build"));
+        assert_eq!(resolved.post_debug_task.as_deref(), Some("cleanup"));
+        assert!(!resolved.config.additional.contains_key("preLaunchTask"));
+    }
+
+    #[test]
+    fn missing_prompt_value_is_an_error() {
+        let file = LaunchFile::parse(LAUNCH).unwrap();
+        let err = resolve_configuration(
+            file.configuration("Client").unwrap(),
+            "/work",
+            &LaunchVariables::default(),
+            "s2".to_string(),
+        )
+        .unwrap_err();
+        assert!(err.contains("${command:pickPort}"));
+    }
+
+    #[test]
+    fn merges_platform_overrides() {
+        let config = json!({
+            "name": "App",
+            "type": "lldb",
+            "request": "launch",
+            "program": "app",
+            (platform_key()): {"program": "app-native"}
+        });
+        let resolved =
+            resolve_configuration(&config, "/work", &LaunchVariables::default(), "s3".into())
+                .unwrap();
+        assert_eq!(resolved.config.program.as_deref(), Some("app-native"));
+        assert!(!resolved.config.additional.contains_key(platform_key()));
+    }
+}
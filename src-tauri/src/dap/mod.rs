@@ -3,9 +3,12 @@
 //! This module provides a DAP client that can communicate with debug adapters
 //! for various languages (Node.js, Python, etc.) using the standard DAP protocol.
 
+mod adapters;
 mod client;
 pub mod commands;
+mod launch;
 pub mod protocol;
+mod rr;
 mod session;
 mod transport;
 
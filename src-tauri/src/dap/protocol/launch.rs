@@ -43,17 +43,19 @@ pub enum DebugAdapterType {
     Go,
     Rust,
     Cpp,
+    DotNet,
     Custom(String),
 }
 
 impl DebugAdapterType {
     pub fn from_str(s: &str) -> Self {
         match s.to_lowercase().as_str() {
-            "node" | "pwa-node" | "node2" => Self::Node,
+            "node" | "pwa-node" | "node2" | "chrome" | "pwa-chrome" | "pwa-msedge" => Self::Node,
             "python" | "debugpy" => Self::Python,
             "go" | "delve" => Self::Go,
             "rust" | "lldb" | "codelldb" => Self::Rust,
             "cpp" | "cppdbg" | "cppvsdbg" => Self::Cpp,
+            "coreclr" | "netcoredbg" | "dotnet" => Self::DotNet,
             other => Self::Custom(other.to_string()),
         }
     }
@@ -65,6 +67,7 @@ impl DebugAdapterType {
             Self::Go => Some("dlv"),
             Self::Rust => Some("lldb-vscode"),
             Self::Cpp => Some("gdb"),
+            Self::DotNet => Some("netcoredbg"),
             Self::Custom(_) => None,
         }
     }
@@ -0,0 +1,574 @@
+//! rr record and replay
+//!
+//! Native Linux programs can be recorded with `rr record` and replayed under
+//! gdb or lldb through rr's gdbserver. The replay target executes backwards,
+//! so `stepBack` and `reverseContinue` work whatever the adapter supports for
+//! live processes.
+//!
+//! Recordings are kept so a failing run can be replayed later:
+//!
+//! ```text
+//! <data>/Cortex/rr-recordings/<id>/recording.json
+//! <data>/Cortex/rr-recordings/<id>/trace/...
+//! ```
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::{mpsc, oneshot};
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+use walkdir::WalkDir;
+
+use super::session::DebugSessionConfig;
+
+const RECORDING_FILE: &str = "recording.json";
+const TRACE_DIR: &str = "trace";
+
+/// How long to wait for rr's gdbserver to come up
+const REPLAY_STARTUP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long rr gets to finish the trace after being asked to stop
+const RECORD_STOP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A saved rr recording
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingInfo {
+    pub id: String,
+    pub name: String,
+    pub program: String,
+    pub args: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+    /// Seconds since the Unix epoch
+    pub created_at: u64,
+    /// Exit code of the recorded program, unset when it died from a signal
+    pub exit_code: Option<i32>,
+    /// Size of the trace on disk, filled in when listing
+    #[serde(default)]
+    pub size_bytes: u64,
+}
+
+/// Program run to record
+#[derive(Debug, Clone)]
+pub(crate) struct RecordRequest {
+    pub name: String,
+    pub program: String,
+    pub args: Vec<String>,
+    pub cwd: Option<String>,
+    pub env: HashMap<String, String>,
+}
+
+impl RecordRequest {
+    pub(crate) fn from_config(config: &DebugSessionConfig) -> Result<Self, String> {
+        let program = config
+            .program
+            .clone()
+            .ok_or("rr recording needs a 'program' to run")?;
+        Ok(Self {
+            name: config.name.clone(),
+            program,
+            args: config.args.clone().unwrap_or_default(),
+            cwd: config.cwd.clone(),
+            env: config.env.clone().unwrap_or_default(),
+        })
+    }
+}
+
+/// Debugger that drives a replay
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReplayDebugger {
+    Gdb,
+    Lldb,
+}
+
+impl ReplayDebugger {
+    /// Debugger matching the adapter a launch configuration asked for
+    pub(crate) fn for_type(debug_type: &str) -> Self {
+        match debug_type.to_lowercase().as_str() {
+            "lldb" | "codelldb" | "rust" => Self::Lldb,
+            _ => Self::Gdb,
+        }
+    }
+}
+
+/// Whether a launch configuration asks to be recorded with rr first
+pub(crate) fn is_record_launch(config: &DebugSessionConfig) -> bool {
+    config.request == "launch" && config.additional.get("rr").and_then(Value::as_bool) == Some(true)
+}
+
+/// Whether rr is installed
+pub(crate) async fn is_available() -> bool {
+    cfg!(target_os = "linux")
+        && Command::new("rr")
+            .arg("--version")
+            .output()
+            .await
+            .is_ok_and(|o| o.status.success())
+}
+
+fn recordings_dir() -> Result<PathBuf, String> {
+    let data_dir = dirs::data_dir().ok_or("Could not find app data directory")?;
+    Ok(data_dir.join("Cortex").join("rr-recordings"))
+}
+
+fn recording_dir(id: &str) -> Result<PathBuf, String> {
+    // Ids are generated here; anything else could escape the recordings dir
+    if Uuid::parse_str(id).is_err() {
+        return Err(format!("Invalid recording id: {}", id));
+    }
+    Ok(recordings_dir()?.join(id))
+}
+
+/// Directory holding the rr trace of a recording
+pub(crate) fn trace_dir(id: &str) -> Result<PathBuf, String> {
+    Ok(recording_dir(id)?.join(TRACE_DIR))
+}
+
+fn read_recording(dir: &Path) -> Option<RecordingInfo> {
+    let content = std::fs::read_to_string(dir.join(RECORDING_FILE)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn dir_size(dir: &Path) -> u64 {
+    WalkDir::new(dir)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter_map(|e| e.metadata().ok())
+        .filter(|m| m.is_file())
+        .map(|m| m.len())
+        .sum()
+}
+
+fn list_in(root: &Path) -> Vec<RecordingInfo> {
+    let Ok(entries) = std::fs::read_dir(root) else {
+        return Vec::new();
+    };
+    let mut recordings: Vec<RecordingInfo> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let dir = entry.path();
+            let mut recording = read_recording(&dir)?;
+            recording.size_bytes = dir_size(&dir.join(TRACE_DIR));
+            Some(recording)
+        })
+        .collect();
+    recordings.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    recordings
+}
+
+/// Saved recordings, newest first
+pub(crate) fn list() -> Vec<RecordingInfo> {
+    recordings_dir()
+        .map(|root| list_in(&root))
+        .unwrap_or_default()
+}
+
+/// Load a saved recording
+pub(crate) fn load(id: &str) -> Result<RecordingInfo, String> {
+    read_recording(&recording_dir(id)?).ok_or_else(|| format!("Recording not found: {}", id))
+}
+
+/// Delete a saved recording and its trace
+pub(crate) fn delete(id: &str) -> Result<(), String> {
+    let dir = recording_dir(id)?;
+    if !dir.exists() {
+        return Err(format!("Recording not found: {}", id));
+    }
+    std::fs::remove_dir_all(&dir).map_err(|e| format!("Failed to delete recording: {}", e))
+}
+
+async fn forward_lines(reader: impl AsyncRead + Unpin, on_output: &(impl Fn(&str) + Sync)) {
+    let mut lines = BufReader::new(reader).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        on_output(&line);
+    }
+}
+
+/// Ask rr to stop recording
+///
+/// rr ends the recorded program on SIGINT and still writes a complete trace,
+/// unlike when it is killed. Falls back to killing rr if it does not exit.
+async fn stop_recording(child: &mut Child) -> std::io::Result<std::process::ExitStatus> {
+    if let Some(pid) = child.id() {
+        let interrupted = Command::new("kill")
+            .arg("-INT")
+            .arg(pid.to_string())
+            .status()
+            .await;
+        if let Err(e) = interrupted {
+            warn!("Failed to interrupt rr: {}", e);
+        }
+    }
+    match tokio::time::timeout(RECORD_STOP_TIMEOUT, child.wait()).await {
+        Ok(status) => status,
+        Err(_) => {
+            warn!("rr did not stop in time, killing it");
+            child.kill().await?;
+            child.wait().await
+        }
+    }
+}
+
+/// Run a program under `rr record` and save the recording
+///
+/// The program runs until it exits or `stop` fires; its output is passed to
+/// `on_output` line by line. A non-zero exit or a stopped run still produces
+/// a recording.
+pub(crate) async fn record(
+    request: RecordRequest,
+    stop: oneshot::Receiver<()>,
+    on_output: impl Fn(&str) + Sync,
+) -> Result<RecordingInfo, String> {
+    if !is_available().await {
+        return Err("rr is not installed or not supported on this platform".to_string());
+    }
+
+    let id = Uuid::new_v4().to_string();
+    let dir = recording_dir(&id)?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create recording dir: {}", e))?;
+    let trace = dir.join(TRACE_DIR);
+
+    let mut command = Command::new("rr");
+    command
+        .arg("record")
+        .arg("-o")
+        .arg(&trace)
+        .arg("--")
+        .arg(&request.program)
+        .args(&request.args)
+        .envs(&request.env)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    if let Some(cwd) = &request.cwd {
+        command.current_dir(cwd);
+    }
+
+    info!("Recording {} with rr", request.program);
+    let result = async {
+        let mut child = command
+            .spawn()
+            .map_err(|e| format!("Failed to start rr: {}", e))?;
+        let stdout = child.stdout.take();
+        let stderr = child.stderr.take();
+        let (status, _, _) = tokio::join!(
+            async {
+                tokio::select! {
+                    status = child.wait() => status,
+                    // A dropped sender only means nobody can stop the run
+                    Ok(()) = stop => {
+                        info!("Stopping rr recording of {}", request.program);
+                        stop_recording(&mut child).await
+                    }
+                }
+            },
+            async {
+                if let Some(stdout) = stdout {
+                    forward_lines(stdout, &on_output).await;
+                }
+            },
+            async {
+                if let Some(stderr) = stderr {
+                    forward_lines(stderr, &on_output).await;
+                }
+            },
+        );
+        status.map_err(|e| format!("Failed to wait for rr: {}", e))
+    }
+    .await;
+
+    let status = match result {
+        Ok(status) if trace.is_dir() => status,
+        Ok(status) => {
+            std::fs::remove_dir_all(&dir).ok();
+            return Err(format!("rr did not produce a recording ({})", status));
+        }
+        Err(e) => {
+            std::fs::remove_dir_all(&dir).ok();
+            return Err(e);
+        }
+    };
+
+    let created_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let mut recording = RecordingInfo {
+        id,
+        name: request.name,
+        program: request.program,
+        args: request.args,
+        cwd: request.cwd,
+        created_at,
+        exit_code: status.code(),
+        size_bytes: 0,
+    };
+    let json = serde_json::to_string_pretty(&recording)
+        .map_err(|e| format!("Failed to serialize recording: {}", e))?;
+    std::fs::write(dir.join(RECORDING_FILE), json)
+        .map_err(|e| format!("Failed to save recording: {}", e))?;
+    recording.size_bytes = dir_size(&trace);
+    Ok(recording)
+}
+
+/// Address of the gdbserver in rr's "Launch gdb with ..." hint
+fn gdbserver_address(line: &str) -> Option<String> {
+    let (_, rest) = line.split_once("target extended-remote ")?;
+    let address: String = rest
+        .chars()
+        .take_while(|c| !c.is_whitespace() && *c != '\'' && *c != '"')
+        .collect();
+    (!address.is_empty()).then_some(address)
+}
+
+/// rr replaying a recording as a gdbserver
+///
+/// The replay is killed when the server is dropped.
+pub(crate) struct ReplayServer {
+    _child: Child,
+    address: String,
+}
+
+impl ReplayServer {
+    pub(crate) async fn start(recording_id: &str) -> Result<Self, String> {
+        let trace = trace_dir(recording_id)?;
+        if !trace.is_dir() {
+            return Err(format!("Recording not found: {}", recording_id));
+        }
+
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .and_then(|l| l.local_addr())
+            .map(|a| a.port())
+            .map_err(|e| format!("Failed to pick a port for rr: {}", e))?;
+
+        let mut child = Command::new("rr")
+            .arg("replay")
+            .arg("-s")
+            .arg(port.to_string())
+            .arg(&trace)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| format!("Failed to start rr replay: {}", e))?;
+
+        // rr prints how to connect once the gdbserver is listening; watch
+        // both streams and keep draining them so rr never blocks on a pipe
+        let (line_tx, mut line_rx) = mpsc::unbounded_channel::<String>();
+        let stdout = child.stdout.take();
+        let stderr = child.stderr.take();
+        for reader in [
+            stdout.map(|s| Box::new(s) as Box<dyn AsyncRead + Send + Unpin>),
+            stderr.map(|s| Box::new(s) as Box<dyn AsyncRead + Send + Unpin>),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            let line_tx = line_tx.clone();
+            tokio::spawn(async move {
+                forward_lines(reader, &|line: &str| {
+                    debug!("rr replay: {}", line);
+                    line_tx.send(line.to_string()).ok();
+                })
+                .await;
+            });
+        }
+        drop(line_tx);
+
+        let address = tokio::time::timeout(REPLAY_STARTUP_TIMEOUT, async {
+            while let Some(line) = line_rx.recv().await {
+                if let Some(address) = gdbserver_address(&line) {
+                    return Some(address);
+                }
+            }
+            None
+        })
+        .await
+        .map_err(|_| "Timed out waiting for rr replay to start".to_string())?
+        .ok_or("rr replay exited before its gdbserver started")?;
+
+        info!("rr replay of {} listening on {}", recording_id, address);
+        Ok(Self {
+            _child: child,
+            address,
+        })
+    }
+
+    pub(crate) fn address(&self) -> &str {
+        &self.address
+    }
+}
+
+/// Session configuration attaching `debugger` to a replay server
+pub(crate) fn replay_config(
+    session_id: String,
+    recording: &RecordingInfo,
+    debugger: ReplayDebugger,
+    address: &str,
+) -> DebugSessionConfig {
+    let mut additional = HashMap::new();
+    additional.insert("reverseDebugging".to_string(), Value::Bool(true));
+
+    let (type_, request, program) = match debugger {
+        ReplayDebugger::Gdb => {
+            additional.insert("target".to_string(), Value::String(address.to_string()));
+            ("gdb", "attach", Some(recording.program.clone()))
+        }
+        ReplayDebugger::Lldb => {
+            additional.insert(
+                "targetCreateCommands".to_string(),
+                serde_json::json!([format!("target create \"{}\"", recording.program)]),
+            );
+            additional.insert(
+                "processCreateCommands".to_string(),
+                serde_json::json!([format!("gdb-remote {}", address)]),
+            );
+            ("lldb", "custom", None)
+        }
+    };
+
+    DebugSessionConfig {
+        id: session_id,
+        name: format!("{} (rr replay)", recording.name),
+        type_: type_.to_string(),
+        request: request.to_string(),
+        program,
+        args: None,
+        cwd: recording.cwd.clone(),
+        env: None,
+        stop_on_entry: None,
+        console: None,
+        port: None,
+        host: None,
+        adapter_path: None,
+        adapter_args: None,
+        additional,
+        replay: true,
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    fn config(type_: &str, additional: Value) -> DebugSessionConfig {
+        let mut value = serde_json::json!({
+            "id": "s1",
+            "name": "tests",
+            "type": type_,
+            "request": "launch",
+            "program": "/work/target/debug/app",
+            "args": ["--fail"],
+        });
+        if let (Value::Object(map), Value::Object(extra)) = (&mut value, additional) {
+            map.extend(extra);
+        }
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn recording() -> RecordingInfo {
+        RecordingInfo {
+            id: Uuid::new_v4().to_string(),
+            name: "tests".to_string(),
+            program: "/work/target/debug/app".to_string(),
+            args: vec!["--fail".to_string()],
+            cwd: Some("/work".to_string()),
+            created_at: 1,
+            exit_code: Some(101),
+            size_bytes: 0,
+        }
+    }
+
+    #[test]
+    fn recognizes_record_launches() {
+        assert!(is_record_launch(&config(
+            "cppdbg",
+            serde_json::json!({"rr": true})
+        )));
+        assert!(!is_record_launch(&config("cppdbg", serde_json::json!({}))));
+        let mut attach = config("cppdbg", serde_json::json!({"rr": true}));
+        attach.request = "attach".to_string();
+        assert!(!is_record_launch(&attach));
+    }
+
+    #[test]
+    fn parses_gdbserver_hint() {
+        let line = "  gdb '-l' '10000' '-ex' 'set sysroot /' '-ex' 'target extended-remote 127.0.0.1:40221' /tmp/app";
+        assert_eq!(gdbserver_address(line).unwrap(), "127.0.0.1:40221");
+        assert!(gdbserver_address("Launch gdb with").is_none());
+    }
+
+    #[test]
+    fn gdb_replay_attaches_to_the_server() {
+        let replay = replay_config(
+            "s1".to_string(),
+            &recording(),
+            ReplayDebugger::Gdb,
+            "127.0.0.1:9",
+        );
+        assert_eq!(replay.id, "s1");
+        assert_eq!(replay.type_, "gdb");
+        assert_eq!(replay.request, "attach");
+        assert_eq!(replay.additional["target"], "127.0.0.1:9");
+        assert_eq!(replay.additional["reverseDebugging"], true);
+        assert!(replay.replay);
+        assert!(replay.args.is_none());
+        assert!(!replay.additional.contains_key("rr"));
+    }
+
+    #[test]
+    fn lldb_replay_connects_with_gdb_remote() {
+        let base = config("lldb", serde_json::json!({}));
+        let debugger = ReplayDebugger::for_type(&base.type_);
+        assert_eq!(debugger, ReplayDebugger::Lldb);
+        let replay = replay_config("s1".to_string(), &recording(), debugger, "127.0.0.1:9");
+        assert_eq!(replay.request, "custom");
+        assert_eq!(
+            replay.additional["processCreateCommands"],
+            serde_json::json!(["gdb-remote 127.0.0.1:9"])
+        );
+        assert_eq!(replay.cwd.as_deref(), Some("/work"));
+    }
+
+    #[test]
+    fn lists_saved_recordings_newest_first() {
+        let root = tempfile::tempdir().unwrap();
+        for (created_at, name) in [(1, "old"), (5, "new")] {
+            let mut info = recording();
+            info.created_at = created_at;
+            info.name = name.to_string();
+            let dir = root.path().join(&info.id);
+            std::fs::create_dir_all(dir.join(TRACE_DIR)).unwrap();
+            std::fs::write(dir.join(TRACE_DIR).join("data"), [0u8; 16]).unwrap();
+            std::fs::write(
+                dir.join(RECORDING_FILE),
+                serde_json::to_string(&info).unwrap(),
+            )
+            .unwrap();
+        }
+        std::fs::create_dir_all(root.path().join("partial")).unwrap();
+
+        let recordings = list_in(root.path());
+        let names: Vec<&str> = recordings.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, vec!["new", "old"]);
+        assert_eq!(recordings[0].size_bytes, 16);
+    }
+
+    #[test]
+    fn rejects_ids_outside_the_recordings_dir() {
+        assert!(recording_dir("../../etc").is_err());
+        assert!(delete("not-a-uuid").is_err());
+    }
+}
@@ -12,9 +12,16 @@ pub fn get_adapter_command(config: &DebugSessionConfig) -> Result<(String, Vec<S
         return Ok((adapter_path.clone(), args));
     }
 
+    // Adapters installed from the catalog take precedence over system ones
+    if let Some(command) = crate::dap::adapters::managed_adapter_command(&config.type_) {
+        return Ok(command);
+    }
+
     // Determine command based on adapter type
     match config.type_.to_lowercase().as_str() {
-        "node" | "pwa-node" | "node2" => resolve_node_adapter(),
+        "node" | "pwa-node" | "node2" | "chrome" | "pwa-chrome" | "pwa-msedge" => {
+            resolve_node_adapter()
+        }
         "python" | "debugpy" => Ok((
             "python".to_string(),
             vec!["-m".to_string(), "debugpy.adapter".to_string()],
@@ -22,6 +29,11 @@ pub fn get_adapter_command(config: &DebugSessionConfig) -> Result<(String, Vec<S
         "go" | "delve" => Ok(("dlv".to_string(), vec!["dap".to_string()])),
         "lldb" | "codelldb" => resolve_lldb_adapter(),
         "cppdbg" | "cppvsdbg" => resolve_cpp_adapter(),
+        "gdb" => Ok(("gdb".to_string(), vec!["--interpreter=dap".to_string()])),
+        "coreclr" | "netcoredbg" => Ok((
+            "netcoredbg".to_string(),
+            vec!["--interpreter=vscode".to_string()],
+        )),
         "rust" => {
             // For Rust, prefer codelldb, fallback to lldb
             get_adapter_command(&DebugSessionConfig {
@@ -31,7 +43,7 @@ pub fn get_adapter_command(config: &DebugSessionConfig) -> Result<(String, Vec<S
         }
         other => {
             anyhow::bail!(
-                "Unknown debug adapter type: '{}'. Supported types: node, python, go, lldb, cppdbg, rust. \
+                "Unknown debug adapter type: '{}'. Supported types: node, chrome, python, go, lldb, cppdbg, gdb, rust, coreclr. \
                  For other adapters, please provide 'adapterPath' in the configuration.",
                 other
             )
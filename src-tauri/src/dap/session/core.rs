@@ -82,7 +82,13 @@ impl DebugSession {
         client.start_receive_loop();
 
         // Initialize the adapter
-        let capabilities = self.client.initialize(&self.config.type_).await?;
+        let mut capabilities = self.client.initialize(&self.config.type_).await?;
+        if self.config.replay {
+            // rr replays execute backwards even when the adapter does not
+            // advertise it for live processes
+            capabilities.supports_step_back = Some(true);
+            capabilities.supports_reverse_continue = Some(true);
+        }
         *self.capabilities.write().await = Some(capabilities.clone());
 
         // Send launch or attach request
@@ -50,6 +50,9 @@ pub struct DebugSessionConfig {
     /// Additional configuration passed directly to the adapter
     #[serde(flatten)]
     pub additional: HashMap<String, serde_json::Value>,
+    /// Debugging an rr replay, whose target can always execute backwards
+    #[serde(skip)]
+    pub replay: bool,
 }
 
 impl DebugSessionConfig {
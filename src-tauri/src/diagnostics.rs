@@ -65,6 +65,87 @@ pub struct UnifiedDiagnostic {
     pub code: Option<String>,
 }
 
+impl UnifiedDiagnostic {
+    /// Convert a diagnostic reported by a language server.
+    pub fn from_lsp(uri: &str, server_name: &str, diag: &crate::lsp::types::Diagnostic) -> Self {
+        use crate::lsp::types::DiagnosticSeverity as LspSeverity;
+
+        Self {
+            uri: uri.to_string(),
+            range: DiagnosticRange {
+                start: DiagnosticPosition {
+                    line: diag.range.start.line,
+                    character: diag.range.start.character,
+                },
+                end: DiagnosticPosition {
+                    line: diag.range.end.line,
+                    character: diag.range.end.character,
+                },
+            },
+            severity: match diag.severity {
+                Some(LspSeverity::Warning) => DiagnosticSeverity::Warning,
+                Some(LspSeverity::Information) => DiagnosticSeverity::Information,
+                Some(LspSeverity::Hint) => DiagnosticSeverity::Hint,
+                // Servers that omit severity mean the client default, i.e. error
+                Some(LspSeverity::Error) | None => DiagnosticSeverity::Error,
+            },
+            source: DiagnosticSource::Lsp,
+            source_name: Some(
+                diag.source
+                    .clone()
+                    .unwrap_or_else(|| server_name.to_string()),
+            ),
+            message: diag.message.clone(),
+            code: diag.code.clone(),
+        }
+    }
+
+    /// Convert a problem-matcher hit into a build diagnostic.
+    ///
+    /// Relative file paths are resolved against `base_dir`. Matchers report
+    /// 1-based lines and columns; diagnostics use 0-based positions.
+    pub fn from_problem(
+        problem: &crate::tasks::TaskDiagnosticEvent,
+        base_dir: &Path,
+        source_name: &str,
+    ) -> Option<Self> {
+        if problem.file.is_empty() {
+            return None;
+        }
+        let file_path = Path::new(&problem.file);
+        let file_path = if file_path.is_absolute() {
+            file_path.to_path_buf()
+        } else {
+            base_dir.join(file_path)
+        };
+        let uri = url::Url::from_file_path(&file_path).ok()?.to_string();
+
+        let severity = match problem.severity.to_ascii_lowercase().as_str() {
+            "warning" | "warn" => DiagnosticSeverity::Warning,
+            "info" | "information" | "note" => DiagnosticSeverity::Information,
+            "hint" | "help" => DiagnosticSeverity::Hint,
+            _ => DiagnosticSeverity::Error,
+        };
+        let position = DiagnosticPosition {
+            line: problem.line.saturating_sub(1),
+            character: problem.column.saturating_sub(1),
+        };
+
+        Some(Self {
+            uri,
+            range: DiagnosticRange {
+                start: position.clone(),
+                end: position,
+            },
+            severity,
+            source: DiagnosticSource::Build,
+            source_name: Some(source_name.to_string()),
+            message: problem.message.clone(),
+            code: problem.code.clone(),
+        })
+    }
+}
+
 /// Summary of diagnostic counts
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct DiagnosticSummary {
@@ -150,6 +231,36 @@ impl DiagnosticsState {
         all
     }
 
+    /// Replace all build diagnostics reported under `source_name`.
+    ///
+    /// Entries from other build sources on the same files are kept, so
+    /// independent producers (git hooks, terminal problem matchers) can
+    /// refresh their own results without clobbering each other.
+    pub fn replace_build_diagnostics(
+        &self,
+        app: &AppHandle,
+        source_name: &str,
+        diagnostics: Vec<UnifiedDiagnostic>,
+    ) {
+        {
+            let mut build_diags = self.build_diagnostics.lock();
+            for diags in build_diags.values_mut() {
+                diags.retain(|d| d.source_name.as_deref() != Some(source_name));
+            }
+            build_diags.retain(|_, diags| !diags.is_empty());
+            for diag in diagnostics {
+                build_diags.entry(diag.uri.clone()).or_default().push(diag);
+            }
+        }
+
+        let all = self.all_diagnostics();
+        let summary = Self::compute_summary(&all);
+
+        if let Err(e) = app.emit("diagnostics:refreshed", &summary) {
+            error!("Failed to emit diagnostics event: {}", e);
+        }
+    }
+
     fn filter_diagnostics(
         diagnostics: &[UnifiedDiagnostic],
         filter: &DiagnosticFilter,
@@ -788,4 +899,70 @@ mod tests {
         assert!(!deserialized.success);
         assert_eq!(deserialized.summary.total_count, 0);
     }
+
+    #[cfg(unix)]
+    #[test]
+    fn from_problem_resolves_relative_path_and_zero_bases_position() {
+        let problem = crate::tasks::TaskDiagnosticEvent {
+            task_id: "hook".to_string(),
+            file: "src/main.rs".to_string(),
+            line: 10,
+            column: 5,
+            severity: "warning".to_string(),
+            message: "unused variable".to_string(),
+            code: Some("E0001".to_string()),
+            source: "$rustc".to_string(),
+        };
+        let diag = UnifiedDiagnostic::from_problem(&problem, Path::new("/repo"), "pre-commit hook")
+            .unwrap();
+        assert_eq!(diag.uri, "file:///repo/src/main.rs");
+        assert_eq!(diag.range.start.line, 9);
+        assert_eq!(diag.range.start.character, 4);
+        assert_eq!(diag.severity, DiagnosticSeverity::Warning);
+        assert_eq!(diag.source, DiagnosticSource::Build);
+        assert_eq!(diag.source_name.as_deref(), Some("pre-commit hook"));
+    }
+
+    #[test]
+    fn from_problem_skips_problems_without_file() {
+        let problem = crate::tasks::TaskDiagnosticEvent {
+            task_id: "hook".to_string(),
+            file: String::new(),
+            line: 1,
+            column: 1,
+            severity: "error".to_string(),
+            message: "failed".to_string(),
+            code: None,
+            source: "$eslint-stylish".to_string(),
+        };
+        assert!(UnifiedDiagnostic::from_problem(&problem, Path::new("/repo"), "hook").is_none());
+    }
+
+    #[test]
+    fn from_lsp_defaults_missing_severity_to_error() {
+        use crate::lsp::types::{Diagnostic, Position, Range};
+
+        let diag = Diagnostic {
+            range: Range {
+                start: Position {
+                    line: 3,
+                    character: 1,
+                },
+                end: Position {
+                    line: 3,
+                    character: 8,
+                },
+            },
+            severity: None,
+            code: Some("2304".to_string()),
+            source: None,
+            message: "Cannot find name".to_string(),
+            related_information: None,
+        };
+        let unified = UnifiedDiagnostic::from_lsp("file:///a.ts", "typescript", &diag);
+        assert_eq!(unified.severity, DiagnosticSeverity::Error);
+        assert_eq!(unified.source, DiagnosticSource::Lsp);
+        assert_eq!(unified.source_name.as_deref(), Some("typescript"));
+        assert_eq!(unified.range.end.character, 8);
+    }
 }
@@ -124,7 +124,7 @@ pub async fn read_directory_parallel(
         .await
         .map_err(|e| format!("Semaphore error: {}", e))?;
 
-    let raw_entries: Vec<PathBuf> = tokio::task::spawn_blocking(move || {
+    let (raw_entries, sparse_excluded) = tokio::task::spawn_blocking(move || {
         let mut paths = Vec::new();
         if let Ok(entries) = std::fs::read_dir(&dir_path) {
             for entry in entries.flatten() {
@@ -144,7 +144,21 @@ pub async fn read_directory_parallel(
                 paths.push(entry_path);
             }
         }
-        paths
+        // Tracked paths outside the sparse set are listed even though they
+        // are not on disk, so they can be added to it from the tree
+        let excluded: Vec<(PathBuf, bool)> = crate::git::sparse::excluded_children(&dir_path)
+            .into_iter()
+            .filter(|(path, _)| {
+                let name = path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                !path.exists()
+                    && (show_hidden || !is_hidden(&name, path))
+                    && (include_ignored || !should_ignore(&name))
+            })
+            .collect();
+        (paths, excluded)
     })
     .await
     .map_err(|e| format!("Task join error: {}", e))?;
@@ -182,11 +196,31 @@ pub async fn read_directory_parallel(
                     modified_at,
                     extension: if is_dir { None } else { get_extension(&name) },
                     children: None,
+                    sparse_excluded: false,
                 });
             }
         }
     }
 
+    for (path, is_dir) in sparse_excluded {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        entries.push(FileEntry {
+            is_hidden: is_hidden(&name, &path),
+            path: path.to_string_lossy().to_string(),
+            is_dir,
+            is_symlink: false,
+            size: None,
+            modified_at: None,
+            extension: if is_dir { None } else { get_extension(&name) },
+            children: None,
+            sparse_excluded: true,
+            name,
+        });
+    }
+
     parallel_sort_entries(&mut entries);
     Ok(entries)
 }
@@ -231,6 +265,7 @@ pub fn build_file_tree_parallel(
             modified_at,
             extension: if is_dir { None } else { get_extension(&name) },
             children: None,
+            sparse_excluded: false,
         };
 
         if is_dir && depth > 0 {
@@ -256,7 +291,8 @@ pub fn build_file_tree_parallel(
                 let mut join_set: JoinSet<Result<FileEntry, String>> = JoinSet::new();
 
                 for child in immediate_children {
-                    if child.is_dir {
+                    // Sparse-excluded directories are not on disk to descend into
+                    if child.is_dir && !child.sparse_excluded {
                         let child_path = PathBuf::from(&child.path);
                         let sem = Arc::clone(&semaphore);
                         let cache_clone = Arc::clone(&cache);
@@ -397,6 +433,7 @@ pub async fn fs_get_file_tree_shallow(
         modified_at,
         extension: if is_dir { None } else { get_extension(&name) },
         children: None,
+        sparse_excluded: false,
     };
 
     if is_dir {
@@ -417,7 +454,8 @@ pub async fn fs_get_file_tree_shallow(
         let shallow_children: Vec<FileEntry> = children
             .into_iter()
             .map(|mut child| {
-                child.children = if child.is_dir { Some(Vec::new()) } else { None };
+                let expandable = child.is_dir && !child.sparse_excluded;
+                child.children = if expandable { Some(Vec::new()) } else { None };
                 child
             })
             .collect();
@@ -22,14 +22,19 @@
 //! - `encoding` - File encoding detection and conversion
 //! - `workspace_edit` - Text edit operations for refactoring
 
+#[cfg(feature = "desktop")]
 pub mod directory;
+#[cfg(feature = "desktop")]
 pub mod encoding;
+#[cfg(feature = "desktop")]
 pub mod operations;
 pub mod search;
 pub mod security;
 pub mod types;
 pub mod utils;
+#[cfg(feature = "desktop")]
 pub mod watcher;
+#[cfg(feature = "desktop")]
 pub mod workspace_edit;
 
 // Re-export types for public use
@@ -40,21 +45,26 @@ pub use types::{DirectoryCache, FileWatcherState, IoSemaphore};
 // from the module path used in invoke_handler!, so we use glob re-exports.
 
 // Operations module - file CRUD
+#[cfg(feature = "desktop")]
 pub use operations::*;
 
 // Directory module - directory operations and tree traversal
+#[cfg(feature = "desktop")]
 pub use directory::*;
 
 // Watcher module - file system monitoring
+#[cfg(feature = "desktop")]
 pub use watcher::*;
 
 // Search module - file and content search
 pub use search::*;
 
 // Encoding module - encoding detection and conversion
+#[cfg(feature = "desktop")]
 pub use encoding::*;
 
 // Workspace edit module - text edit operations
+#[cfg(feature = "desktop")]
 pub use workspace_edit::*;
 
 // ============================================================================
@@ -108,6 +118,7 @@ mod tests {
             modified_at: Some(1234567890),
             extension: Some("txt".to_string()),
             children: None,
+            sparse_excluded: false,
         }];
 
         cache.insert("/test".to_string(), entries.clone());
@@ -135,6 +146,7 @@ mod tests {
                 modified_at: None,
                 extension: Some("txt".to_string()),
                 children: None,
+                sparse_excluded: false,
             },
             FileEntry {
                 name: "src".to_string(),
@@ -146,6 +158,7 @@ mod tests {
                 modified_at: None,
                 extension: None,
                 children: None,
+                sparse_excluded: false,
             },
             FileEntry {
                 name: "alpha.txt".to_string(),
@@ -157,6 +170,7 @@ mod tests {
                 modified_at: None,
                 extension: Some("txt".to_string()),
                 children: None,
+                sparse_excluded: false,
             },
         ];
 
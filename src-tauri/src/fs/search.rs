@@ -11,6 +11,7 @@ use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::path::PathBuf;
 use std::sync::Arc;
+#[cfg(feature = "desktop")]
 use tauri::{AppHandle, Emitter, Manager};
 use tokio::sync::Semaphore;
 use tokio::task::JoinSet;
@@ -23,6 +24,7 @@ use crate::fs::utils::{get_extension, should_ignore, should_skip_for_search, sys
 // File Name Search
 // ============================================================================
 
+#[cfg(feature = "desktop")]
 #[tauri::command]
 pub async fn fs_search_files(
     app: AppHandle,
@@ -109,6 +111,7 @@ fn search_files_parallel(
                             .and_then(system_time_to_unix),
                         extension: get_extension(name),
                         children: None,
+                        sparse_excluded: false,
                     })
                 } else {
                     None
@@ -362,10 +365,8 @@ fn try_ripgrep_search(
                     total_matches += 1;
                 }
             }
-            RgMessage::End { data } => {
-                if data.stats.is_some() {
-                    files_searched += 1;
-                }
+            RgMessage::End { data } if data.stats.is_some() => {
+                files_searched += 1;
             }
             RgMessage::Summary { data } => {
                 files_searched = data.stats.searches as u32;
@@ -398,8 +399,55 @@ fn try_ripgrep_search(
 // ============================================================================
 
 #[allow(clippy::too_many_arguments)]
+#[cfg(feature = "desktop")]
 #[tauri::command]
 pub async fn fs_search_content(
+    app: AppHandle,
+    path: String,
+    query: String,
+    case_sensitive: Option<bool>,
+    regex: Option<bool>,
+    whole_word: Option<bool>,
+    multiline: Option<bool>,
+    include: Option<String>,
+    exclude: Option<String>,
+    max_results: Option<u32>,
+) -> Result<ContentSearchResponse, String> {
+    // Remote workspaces are searched by their agent
+    let mount = app
+        .state::<crate::remote::agent::RemoteAgentsState>()
+        .mount_for(&path);
+    if let Some(mount) = mount {
+        let params = crate::remote::agent::SearchContentParams {
+            path,
+            query,
+            case_sensitive,
+            regex,
+            whole_word,
+            multiline,
+            include,
+            exclude,
+            max_results,
+        };
+        return mount.search_content(params).await;
+    }
+    fs_search_content_local(
+        path,
+        query,
+        case_sensitive,
+        regex,
+        whole_word,
+        multiline,
+        include,
+        exclude,
+        max_results,
+    )
+    .await
+}
+
+/// Content search on this machine, as served by the remote agent
+#[allow(clippy::too_many_arguments)]
+pub async fn fs_search_content_local(
     path: String,
     query: String,
     case_sensitive: Option<bool>,
@@ -615,6 +663,7 @@ pub async fn fs_search_content(
 // ============================================================================
 
 #[allow(clippy::too_many_arguments)]
+#[cfg(feature = "desktop")]
 #[tauri::command]
 pub async fn fs_search_content_streaming(
     app: AppHandle,
@@ -629,6 +678,7 @@ pub async fn fs_search_content_streaming(
     max_results: Option<u32>,
 ) -> Result<ContentSearchResponse, String> {
     let response = fs_search_content(
+        app.clone(),
         path,
         query,
         case_sensitive,
@@ -654,6 +704,7 @@ pub async fn fs_search_content_streaming(
 }
 
 /// Search content across multiple workspace roots in a single call
+#[cfg(feature = "desktop")]
 #[tauri::command]
 #[allow(clippy::too_many_arguments)]
 pub async fn fs_search_content_multiroot(
@@ -1125,6 +1176,7 @@ fn run_ripgrep_workspace(
 }
 
 #[allow(clippy::too_many_arguments)]
+#[cfg(feature = "desktop")]
 #[tauri::command]
 pub async fn search_workspace_ripgrep(
     roots: Vec<String>,
@@ -1278,6 +1330,7 @@ pub struct ReplaceInFilesResult {
     pub is_dry_run: bool,
 }
 
+#[cfg(feature = "desktop")]
 #[tauri::command]
 pub async fn replace_in_files(
     replacements: Vec<FileReplacement>,
@@ -49,6 +49,13 @@ pub struct FileEntry {
     pub modified_at: Option<u64>,
     pub extension: Option<String>,
     pub children: Option<Vec<FileEntry>>,
+    /// Tracked but not materialized, as it is outside the sparse-checkout set
+    #[serde(
+        rename = "sparseExcluded",
+        default,
+        skip_serializing_if = "std::ops::Not::not"
+    )]
+    pub sparse_excluded: bool,
 }
 
 /// File metadata
@@ -294,7 +301,7 @@ impl Default for IoSemaphore {
 // Search Types
 // ============================================================================
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchMatch {
     pub line: u32,
     pub column: u32,
@@ -305,13 +312,13 @@ pub struct SearchMatch {
     pub match_end: u32,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchResult {
     pub file: String,
     pub matches: Vec<SearchMatch>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ContentSearchResponse {
     pub results: Vec<SearchResult>,
     #[serde(rename = "totalMatches")]
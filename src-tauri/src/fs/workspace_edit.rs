@@ -22,6 +22,19 @@ pub async fn apply_workspace_edit(uri: String, edits: Vec<TextEdit>) -> Result<(
         .await
         .map_err(|e| format!("Failed to read file '{}': {}", file_path, e))?;
 
+    let final_content = apply_text_edits(&content, edits)?;
+
+    tokio::fs::write(file_path, final_content)
+        .await
+        .map_err(|e| format!("Failed to write file '{}': {}", file_path, e))?;
+
+    tracing::info!("Successfully applied workspace edits to {}", file_path);
+
+    Ok(())
+}
+
+/// Apply text edits to `content`, returning the edited text
+pub(crate) fn apply_text_edits(content: &str, edits: Vec<TextEdit>) -> Result<String, String> {
     // Split into lines for editing
     let mut lines: Vec<String> = content.lines().map(|s| s.to_string()).collect();
 
@@ -97,17 +110,9 @@ pub async fn apply_workspace_edit(uri: String, edits: Vec<TextEdit>) -> Result<(
     let new_content = lines.join("\n");
 
     // Preserve trailing newline if original had one
-    let final_content = if content.ends_with('\n') && !new_content.ends_with('\n') {
-        format!("{}\n", new_content)
+    if content.ends_with('\n') && !new_content.ends_with('\n') {
+        Ok(format!("{}\n", new_content))
     } else {
-        new_content
-    };
-
-    tokio::fs::write(file_path, final_content)
-        .await
-        .map_err(|e| format!("Failed to write file '{}': {}", file_path, e))?;
-
-    tracing::info!("Successfully applied workspace edits to {}", file_path);
-
-    Ok(())
+        Ok(new_content)
+    }
 }
@@ -108,6 +108,64 @@ pub async fn git_blame_with_heatmap(
     .map_err(|e| format!("Task join error: {}", e))?
 }
 
+/// Blame of a file as it was in a specific revision.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlameAtRevision {
+    pub revision: String,
+    pub file_path: String,
+    pub entries: Vec<BlameEntry>,
+}
+
+/// Step blame back to the parent of `commit`.
+///
+/// Used to look past a commit that only reformatted or moved lines: the file
+/// is blamed at `commit^`, following a rename made by `commit` so the blame
+/// continues on the file's previous path.
+#[tauri::command]
+pub async fn git_blame_at_parent(
+    path: String,
+    file: String,
+    commit: String,
+    start_line: Option<u32>,
+    end_line: Option<u32>,
+) -> Result<BlameAtRevision, String> {
+    debug!(
+        "git_blame_at_parent: path={}, file={}, commit={}",
+        path, file, commit
+    );
+    tokio::task::spawn_blocking(move || {
+        let repo_root = get_repo_root(&path)?;
+        let repo_path = Path::new(&repo_root);
+        let parent_path = super::history::path_in_parent(repo_path, &commit, &file)?;
+        let revision = format!("{}^", commit);
+
+        let mut args = vec!["blame".to_string(), "--porcelain".to_string()];
+        if let (Some(start), Some(end)) = (start_line, end_line) {
+            args.push(format!("-L{},{}", start, end));
+        }
+        args.push(revision.clone());
+        args.push("--".to_string());
+        args.push(parent_path.clone());
+        let arg_refs: Vec<&str> = args.iter().map(String::as_str).collect();
+
+        let output = git_command_with_timeout(&arg_refs, repo_path)?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(format!("git blame failed: {}", stderr.trim()));
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        Ok(BlameAtRevision {
+            revision,
+            file_path: parent_path,
+            entries: parse_porcelain_blame(&stdout)?,
+        })
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
 // ============================================================================
 // Internal Implementation
 // ============================================================================
@@ -7,9 +7,17 @@ use std::time::{Duration, Instant};
 use tauri::Emitter;
 use tracing::info;
 
-use super::types::CloneProgress;
+use super::types::{CloneOptions, CloneProgress};
 use crate::process_utils;
 
+const CLONE_PROGRESS_EVENT: &str = "git:clone-progress";
+
+/// Timeout for clone and checkout phases (large partial clones can be slow).
+const CLONE_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+
+/// Number of stderr lines kept for error reporting.
+const ERROR_TAIL_LINES: usize = 5;
+
 // ============================================================================
 // Clone Commands
 // ============================================================================
@@ -21,7 +29,7 @@ pub async fn git_clone(
     target_dir: String,
     app: tauri::AppHandle,
 ) -> Result<String, String> {
-    git_clone_internal(url, target_dir, false, app).await
+    git_clone_internal(url, target_dir, CloneOptions::default(), app).await
 }
 
 /// Clone a git repository with submodules recursively
@@ -31,14 +39,97 @@ pub async fn git_clone_recursive(
     target_dir: String,
     app: tauri::AppHandle,
 ) -> Result<String, String> {
-    git_clone_internal(url, target_dir, true, app).await
+    let options = CloneOptions {
+        recursive: true,
+        ..CloneOptions::default()
+    };
+    git_clone_internal(url, target_dir, options, app).await
+}
+
+/// Clone a git repository with partial-clone, shallow and sparse-checkout options.
+///
+/// With `sparse_patterns` set, the clone is created in cone mode with only the
+/// top-level files checked out, then the sparse set is applied as a separate
+/// phase so each step reports its own progress.
+#[tauri::command]
+pub async fn git_clone_with_options(
+    url: String,
+    target_dir: String,
+    options: CloneOptions,
+    app: tauri::AppHandle,
+) -> Result<String, String> {
+    git_clone_internal(url, target_dir, options, app).await
+}
+
+/// Validate a `--filter` spec for partial clones.
+fn validate_filter(filter: &str) -> Result<(), String> {
+    let valid = matches!(filter, "blob:none" | "tree:0")
+        || filter.strip_prefix("blob:limit=").is_some_and(|limit| {
+            let digits = limit.trim_end_matches(['k', 'm', 'g']);
+            !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit())
+        });
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Unsupported clone filter: {}", filter))
+    }
+}
+
+/// Build the `git clone` argument list for the given options.
+fn build_clone_args(
+    url: &str,
+    target_dir: &str,
+    options: &CloneOptions,
+) -> Result<Vec<String>, String> {
+    let mut args = vec!["clone".to_string(), "--progress".to_string()];
+
+    if options.recursive {
+        args.push("--recurse-submodules".to_string());
+        if options.depth.is_some() {
+            args.push("--shallow-submodules".to_string());
+        }
+    }
+    if let Some(ref filter) = options.filter {
+        validate_filter(filter)?;
+        args.push(format!("--filter={}", filter));
+    }
+    if let Some(depth) = options.depth {
+        if depth == 0 {
+            return Err("Clone depth must be greater than zero".to_string());
+        }
+        args.push(format!("--depth={}", depth));
+    }
+    if let Some(ref since) = options.shallow_since {
+        if since.starts_with('-') {
+            return Err(format!("Invalid shallow-since date: {}", since));
+        }
+        args.push(format!("--shallow-since={}", since));
+    }
+    if let Some(ref branch) = options.branch {
+        if branch.is_empty() || branch.starts_with('-') {
+            return Err(format!("Invalid branch name: {}", branch));
+        }
+        args.push("--branch".to_string());
+        args.push(branch.clone());
+    }
+    if options.single_branch {
+        args.push("--single-branch".to_string());
+    }
+    if options.sparse_patterns.is_some() {
+        args.push("--sparse".to_string());
+    }
+
+    args.push("--".to_string());
+    args.push(url.to_string());
+    args.push(target_dir.to_string());
+    Ok(args)
 }
 
 /// Internal clone implementation with progress tracking
 async fn git_clone_internal(
     url: String,
     target_dir: String,
-    recursive: bool,
+    options: CloneOptions,
     app: tauri::AppHandle,
 ) -> Result<String, String> {
     tokio::task::spawn_blocking(move || {
@@ -50,22 +141,24 @@ async fn git_clone_internal(
                 .map_err(|e| format!("Failed to create parent directory: {}", e))?;
         }
 
-        // Build clone command with progress
-        let mut args = vec!["clone", "--progress"];
-        if recursive {
-            args.push("--recurse-submodules");
+        let args = build_clone_args(&url, &target_dir, &options)?;
+        if let Some(ref patterns) = options.sparse_patterns {
+            super::sparse::validate_patterns(patterns)?;
         }
-        args.push(&url);
-        args.push(&target_dir);
 
         info!(
-            "Cloning repository from {} to {} (recursive: {})",
-            url, target_dir, recursive
+            "Cloning repository from {} to {} (recursive: {}, filter: {:?}, depth: {:?}, sparse: {})",
+            url,
+            target_dir,
+            options.recursive,
+            options.filter,
+            options.depth,
+            options.sparse_patterns.is_some()
         );
 
         // Emit initial progress
         let _ = app.emit(
-            "git:clone-progress",
+            CLONE_PROGRESS_EVENT,
             CloneProgress {
                 stage: "starting".to_string(),
                 current: 0,
@@ -75,89 +168,152 @@ async fn git_clone_internal(
             },
         );
 
-        // Spawn git clone process
-        let mut child = process_utils::command("git")
-            .args(&args)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()
-            .map_err(|e| format!("Failed to spawn git clone: {}", e))?;
-
-        // Git outputs progress to stderr
-        let stderr = child
-            .stderr
-            .take()
-            .ok_or_else(|| "Failed to capture stderr".to_string())?;
-
-        let reader = BufReader::new(stderr);
-        let app_clone = app.clone();
-
-        // Process stderr for progress in a separate thread
-        let progress_handle = std::thread::spawn(move || {
-            for line in reader.lines().map_while(Result::ok) {
-                // Parse git progress output
-                let progress = parse_git_progress(&line);
-                let _ = app_clone.emit("git:clone-progress", progress);
-            }
-        });
+        let arg_refs: Vec<&str> = args.iter().map(String::as_str).collect();
+        if let Err(e) =
+            run_git_with_progress(&arg_refs, None, CLONE_PROGRESS_EVENT, CLONE_TIMEOUT, &app)
+        {
+            emit_clone_error(&app, "Clone failed");
+            return Err(format!("Git clone failed: {}", e));
+        }
 
-        // Wait for the process to complete with timeout (10 minutes for clone)
-        let clone_timeout = Duration::from_secs(600);
-        let start = Instant::now();
-
-        loop {
-            match child.try_wait() {
-                Ok(Some(status)) => {
-                    // Wait for progress thread to finish
-                    let _ = progress_handle.join();
-
-                    if status.success() {
-                        // Emit completion
-                        let _ = app.emit(
-                            "git:clone-progress",
-                            CloneProgress {
-                                stage: "complete".to_string(),
-                                current: 100,
-                                total: 100,
-                                bytes_received: None,
-                                message: Some("Clone completed successfully".to_string()),
-                            },
-                        );
-
-                        info!("Clone completed successfully: {}", target_dir);
-                        return Ok(target_dir);
-                    } else {
-                        // Emit error
-                        let _ = app.emit(
-                            "git:clone-progress",
-                            CloneProgress {
-                                stage: "error".to_string(),
-                                current: 0,
-                                total: 0,
-                                bytes_received: None,
-                                message: Some("Clone failed".to_string()),
-                            },
-                        );
-                        return Err("Git clone failed".to_string());
-                    }
-                }
-                Ok(None) => {
-                    if start.elapsed() > clone_timeout {
-                        let _ = child.kill();
-                        return Err("Git clone timed out after 10 minutes".to_string());
-                    }
-                    std::thread::sleep(Duration::from_millis(100));
-                }
-                Err(e) => {
-                    return Err(format!("Error waiting for git clone: {}", e));
-                }
+        if let Some(ref patterns) = options.sparse_patterns {
+            let _ = app.emit(
+                CLONE_PROGRESS_EVENT,
+                CloneProgress {
+                    stage: "sparse_checkout".to_string(),
+                    current: 0,
+                    total: 0,
+                    bytes_received: None,
+                    message: Some(format!(
+                        "Applying sparse-checkout ({} patterns)",
+                        patterns.len()
+                    )),
+                },
+            );
+            let mut sparse_args = vec!["sparse-checkout", "set", "--cone"];
+            sparse_args.extend(patterns.iter().map(String::as_str));
+            if let Err(e) = run_git_with_progress(
+                &sparse_args,
+                Some(target_path),
+                CLONE_PROGRESS_EVENT,
+                CLONE_TIMEOUT,
+                &app,
+            ) {
+                emit_clone_error(&app, "Sparse-checkout failed");
+                return Err(format!("Failed to apply sparse-checkout: {}", e));
             }
         }
+
+        // Emit completion
+        let _ = app.emit(
+            CLONE_PROGRESS_EVENT,
+            CloneProgress {
+                stage: "complete".to_string(),
+                current: 100,
+                total: 100,
+                bytes_received: None,
+                message: Some("Clone completed successfully".to_string()),
+            },
+        );
+
+        info!("Clone completed successfully: {}", target_dir);
+        Ok(target_dir)
     })
     .await
     .map_err(|e| format!("Task join error: {}", e))?
 }
 
+fn emit_clone_error(app: &tauri::AppHandle, message: &str) {
+    let _ = app.emit(
+        CLONE_PROGRESS_EVENT,
+        CloneProgress {
+            stage: "error".to_string(),
+            current: 0,
+            total: 0,
+            bytes_received: None,
+            message: Some(message.to_string()),
+        },
+    );
+}
+
+/// Run a long git command, forwarding its stderr progress as `event` payloads.
+///
+/// On failure, the last few stderr lines are returned as the error message.
+pub(super) fn run_git_with_progress(
+    args: &[&str],
+    cwd: Option<&Path>,
+    event: &str,
+    timeout: Duration,
+    app: &tauri::AppHandle,
+) -> Result<(), String> {
+    let mut command = process_utils::command("git");
+    command
+        .args(args)
+        // Nothing is read from stdout, so a full pipe must not block git
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped());
+    if let Some(dir) = cwd {
+        command.current_dir(dir);
+    }
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("Failed to spawn git: {}", e))?;
+
+    // Git outputs progress to stderr
+    let stderr = child
+        .stderr
+        .take()
+        .ok_or_else(|| "Failed to capture stderr".to_string())?;
+
+    let reader = BufReader::new(stderr);
+    let app_clone = app.clone();
+    let event_name = event.to_string();
+
+    // Process stderr for progress in a separate thread
+    let progress_handle = std::thread::spawn(move || {
+        let mut tail: Vec<String> = Vec::new();
+        for line in reader.lines().map_while(Result::ok) {
+            // Parse git progress output
+            let progress = parse_git_progress(&line);
+            let _ = app_clone.emit(&event_name, progress);
+            if tail.len() == ERROR_TAIL_LINES {
+                tail.remove(0);
+            }
+            tail.push(line);
+        }
+        tail
+    });
+
+    let start = Instant::now();
+
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => {
+                // Wait for progress thread to finish
+                let tail = progress_handle.join().unwrap_or_default();
+                return if status.success() {
+                    Ok(())
+                } else {
+                    Err(tail.join("\n"))
+                };
+            }
+            Ok(None) => {
+                if start.elapsed() > timeout {
+                    let _ = child.kill();
+                    return Err(format!(
+                        "Timed out after {} minutes",
+                        timeout.as_secs() / 60
+                    ));
+                }
+                std::thread::sleep(Duration::from_millis(100));
+            }
+            Err(e) => {
+                return Err(format!("Error waiting for git: {}", e));
+            }
+        }
+    }
+}
+
 /// Parse git progress output into CloneProgress struct
 fn parse_git_progress(line: &str) -> CloneProgress {
     let line = line.trim();
@@ -182,6 +338,8 @@ fn parse_git_progress(line: &str) -> CloneProgress {
             ("starting", (0, 0))
         } else if line.contains("Updating files") {
             ("updating", extract_progress(line))
+        } else if line.contains("Filtering content") {
+            ("filtering", extract_progress(line))
         } else {
             ("unknown", (0, 0))
         };
@@ -0,0 +1,558 @@
+//! AI-assisted commit message and pull request description generation.
+//!
+//! Staged changes are condensed into a prompt that fits the model context:
+//! small diffs are sent verbatim, large files are reduced to a per-file summary
+//! (hunk headers plus a few changed lines). The commit style (conventional
+//! commits, scopes, subject casing) is inferred from recent `git_log` entries
+//! so generated messages match the repository's history.
+
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use super::diff::git_diff_staged;
+use super::log::{git_compare_branches, git_log};
+use super::types::{DiffLineType, GitCommit, StructuredDiff};
+use crate::ai::{AIProvider, AIState, Message};
+
+/// Upper bound on the diff text included in a single prompt (in characters).
+const MAX_DIFF_CHARS: usize = 24_000;
+
+/// Files whose diff exceeds this size are summarized instead of sent verbatim.
+const MAX_FILE_DIFF_CHARS: usize = 4_000;
+
+/// Number of changed lines kept per hunk when a file is summarized.
+const SUMMARY_LINES_PER_HUNK: usize = 6;
+
+/// Room kept at the end of a summary for the count of hunks left out.
+const MORE_HUNKS_NOTE_CHARS: usize = 32;
+
+/// Number of recent commits inspected to infer the commit style.
+const STYLE_SAMPLE_SIZE: u32 = 50;
+
+const CONVENTIONAL_TYPES: &[&str] = &[
+    "feat", "fix", "docs", "style", "refactor", "perf", "test", "build", "ci", "chore", "revert",
+];
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// Commit style inferred from the repository history.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitStyle {
+    /// Whether most recent subjects follow `type(scope): subject`.
+    pub conventional: bool,
+    /// Whether conventional subjects usually carry a scope.
+    pub uses_scope: bool,
+    /// Most frequently used scopes, most common first.
+    pub common_scopes: Vec<String>,
+    /// Whether subjects usually start with a lowercase letter.
+    pub lowercase_subject: bool,
+    /// Median subject length, used as a soft length target.
+    pub typical_subject_length: usize,
+    /// A handful of recent subjects shown to the model as examples.
+    pub examples: Vec<String>,
+}
+
+/// A generated commit message.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GeneratedCommitMessage {
+    pub subject: String,
+    pub body: Option<String>,
+    pub style: CommitStyle,
+    /// Files whose diff was summarized rather than sent in full.
+    pub summarized_files: Vec<String>,
+}
+
+/// A generated pull request title and body, ready for `git_forge_create_pr`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GeneratedPrDescription {
+    pub title: String,
+    pub body: String,
+    pub head: String,
+    pub base: String,
+}
+
+// ============================================================================
+// Style Inference
+// ============================================================================
+
+/// Parse a conventional commit subject into `(type, scope)`.
+fn parse_conventional(subject: &str) -> Option<(String, Option<String>)> {
+    let (prefix, rest) = subject.split_once(':')?;
+    if !rest.starts_with(' ') {
+        return None;
+    }
+    let prefix = prefix.trim_end_matches('!');
+    let (kind, scope) = match prefix.split_once('(') {
+        Some((kind, scope)) => (kind, Some(scope.strip_suffix(')')?.to_string())),
+        None => (prefix, None),
+    };
+    if !CONVENTIONAL_TYPES.contains(&kind) {
+        return None;
+    }
+    Some((kind.to_string(), scope))
+}
+
+/// Infer the commit style from a list of recent commits.
+pub(crate) fn infer_commit_style(commits: &[GitCommit]) -> CommitStyle {
+    let subjects: Vec<&str> = commits
+        .iter()
+        .map(|c| c.message.lines().next().unwrap_or("").trim())
+        .filter(|s| !s.is_empty() && !s.starts_with("Merge "))
+        .collect();
+
+    if subjects.is_empty() {
+        return CommitStyle::default();
+    }
+
+    let parsed: Vec<(String, Option<String>)> = subjects
+        .iter()
+        .filter_map(|s| parse_conventional(s))
+        .collect();
+    let conventional = parsed.len() * 2 > subjects.len();
+
+    let mut scope_counts: std::collections::HashMap<String, usize> =
+        std::collections::HashMap::new();
+    for scope in parsed.iter().filter_map(|(_, scope)| scope.clone()) {
+        *scope_counts.entry(scope).or_default() += 1;
+    }
+    let scoped = parsed.iter().filter(|(_, scope)| scope.is_some()).count();
+    let mut common_scopes: Vec<(String, usize)> = scope_counts.into_iter().collect();
+    common_scopes.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+    let descriptions: Vec<&str> = subjects
+        .iter()
+        .map(|s| match parse_conventional(s) {
+            Some(_) => s.split_once(": ").map(|(_, d)| d).unwrap_or(s),
+            None => s,
+        })
+        .collect();
+    let lowercase = descriptions
+        .iter()
+        .filter(|d| d.chars().next().is_some_and(|c| c.is_lowercase()))
+        .count();
+
+    let mut lengths: Vec<usize> = subjects.iter().map(|s| s.chars().count()).collect();
+    lengths.sort_unstable();
+
+    CommitStyle {
+        conventional,
+        uses_scope: conventional && scoped * 2 > parsed.len(),
+        common_scopes: common_scopes.into_iter().take(8).map(|(s, _)| s).collect(),
+        lowercase_subject: lowercase * 2 > descriptions.len(),
+        typical_subject_length: lengths[lengths.len() / 2],
+        examples: subjects.iter().take(8).map(|s| s.to_string()).collect(),
+    }
+}
+
+// ============================================================================
+// Diff Condensing
+// ============================================================================
+
+fn render_file_diff(diff: &StructuredDiff) -> String {
+    let mut out = format!("--- {}\n", diff.file_path);
+    for hunk in &diff.hunks {
+        out.push_str(hunk.header.trim_end());
+        out.push('\n');
+        for line in &hunk.lines {
+            let prefix = match line.change_type {
+                DiffLineType::Addition => '+',
+                DiffLineType::Deletion => '-',
+                DiffLineType::Context => ' ',
+                DiffLineType::Header => continue,
+            };
+            out.push(prefix);
+            out.push_str(line.content.trim_end_matches('\n'));
+            out.push('\n');
+        }
+    }
+    out
+}
+
+fn summarize_file_diff(diff: &StructuredDiff) -> String {
+    let mut out = format!(
+        "--- {} (summarized: +{} -{}, {} hunks)\n",
+        diff.file_path,
+        diff.additions,
+        diff.deletions,
+        diff.hunks.len()
+    );
+    for (index, hunk) in diff.hunks.iter().enumerate() {
+        let mut text = hunk.header.trim_end().to_string();
+        text.push('\n');
+        let changed = hunk.lines.iter().filter(|l| {
+            matches!(
+                l.change_type,
+                DiffLineType::Addition | DiffLineType::Deletion
+            )
+        });
+        for line in changed.take(SUMMARY_LINES_PER_HUNK) {
+            let prefix = if matches!(line.change_type, DiffLineType::Addition) {
+                '+'
+            } else {
+                '-'
+            };
+            text.push(prefix);
+            text.push_str(line.content.trim_end_matches('\n'));
+            text.push('\n');
+        }
+        // Summaries are capped like full diffs, so one file with many hunks
+        // cannot crowd the others out of the prompt
+        if out.len() + text.len() + MORE_HUNKS_NOTE_CHARS > MAX_FILE_DIFF_CHARS {
+            out.push_str(&format!("... +{} more hunks\n", diff.hunks.len() - index));
+            break;
+        }
+        out.push_str(&text);
+    }
+    out
+}
+
+/// Condense staged diffs into prompt text, returning the text and the list of
+/// files that were summarized.
+pub(crate) fn condense_diffs(diffs: &[StructuredDiff]) -> (String, Vec<String>) {
+    let mut out = String::new();
+    let mut summarized = Vec::new();
+
+    for (index, diff) in diffs.iter().enumerate() {
+        let full = render_file_diff(diff);
+        let chunk = if full.len() > MAX_FILE_DIFF_CHARS {
+            summarized.push(diff.file_path.clone());
+            summarize_file_diff(diff)
+        } else {
+            full
+        };
+
+        if out.len() + chunk.len() > MAX_DIFF_CHARS {
+            // Out of budget: list the remaining files by name and stats only.
+            for rest in &diffs[index..] {
+                out.push_str(&format!(
+                    "--- {} (+{} -{}, omitted)\n",
+                    rest.file_path, rest.additions, rest.deletions
+                ));
+                if !summarized.contains(&rest.file_path) {
+                    summarized.push(rest.file_path.clone());
+                }
+            }
+            break;
+        }
+        out.push_str(&chunk);
+    }
+
+    (out, summarized)
+}
+
+fn style_instructions(style: &CommitStyle) -> String {
+    let mut rules = Vec::new();
+    if style.conventional {
+        rules.push(format!(
+            "Use the Conventional Commits format `type(scope): subject` with one of: {}.",
+            CONVENTIONAL_TYPES.join(", ")
+        ));
+        if style.uses_scope && !style.common_scopes.is_empty() {
+            rules.push(format!(
+                "Include a scope; prefer one of: {}.",
+                style.common_scopes.join(", ")
+            ));
+        }
+    } else {
+        rules.push("Write a plain imperative subject line without a type prefix.".to_string());
+    }
+    if style.lowercase_subject {
+        rules.push("Start the subject description with a lowercase letter.".to_string());
+    } else {
+        rules.push("Start the subject with a capital letter.".to_string());
+    }
+    let target = style.typical_subject_length.clamp(30, 72);
+    rules.push(format!(
+        "Keep the subject under {} characters and do not end it with a period.",
+        target.max(50)
+    ));
+    if !style.examples.is_empty() {
+        rules.push(format!(
+            "Recent subjects from this repository:\n{}",
+            style
+                .examples
+                .iter()
+                .map(|e| format!("- {}", e))
+                .collect::<Vec<_>>()
+                .join("\n")
+        ));
+    }
+    rules.join("\n")
+}
+
+/// Split a model reply into subject and optional body, stripping code fences.
+pub(crate) fn split_message(reply: &str) -> (String, Option<String>) {
+    let cleaned: Vec<&str> = reply
+        .trim()
+        .lines()
+        .filter(|l| !l.trim_start().starts_with("```"))
+        .collect();
+    let text = cleaned.join("\n");
+    let text = text.trim();
+    let (subject, body) = match text.split_once('\n') {
+        Some((subject, body)) => (subject, Some(body.trim())),
+        None => (text, None),
+    };
+    let subject = subject.trim().trim_matches('"').to_string();
+    let body = body.filter(|b| !b.is_empty()).map(|b| b.to_string());
+    (subject, body)
+}
+
+// ============================================================================
+// Tauri Commands
+// ============================================================================
+
+/// Generate a commit message for the currently staged changes.
+#[tauri::command]
+pub async fn git_generate_commit_message(
+    path: String,
+    model: String,
+    provider: AIProvider,
+    state: tauri::State<'_, AIState>,
+) -> Result<GeneratedCommitMessage, String> {
+    let diffs = git_diff_staged(path.clone(), None).await?;
+    if diffs.is_empty() {
+        return Err("No staged changes to describe".to_string());
+    }
+
+    let recent = git_log(path, Some(STYLE_SAMPLE_SIZE), None)
+        .await
+        .unwrap_or_default();
+    let style = infer_commit_style(&recent);
+    let (diff_text, summarized_files) = condense_diffs(&diffs);
+
+    let messages = vec![
+        Message::system(format!(
+            "You write git commit messages. Reply with the commit message only: a subject \
+             line, then optionally a blank line and a short body explaining why the change \
+             was made. Do not wrap the reply in code fences.\n{}",
+            style_instructions(&style)
+        )),
+        Message::user(format!("Staged changes:\n{}", diff_text)),
+    ];
+
+    let manager = state.provider_manager.lock().await;
+    let reply = manager
+        .complete(messages, &model, provider)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let (subject, body) = split_message(&reply);
+    if subject.is_empty() {
+        return Err("Model returned an empty commit message".to_string());
+    }
+    info!(
+        "Generated commit message ({} files, {} summarized)",
+        diffs.len(),
+        summarized_files.len()
+    );
+
+    Ok(GeneratedCommitMessage {
+        subject,
+        body,
+        style,
+        summarized_files,
+    })
+}
+
+/// Draft a pull request title and body from the commits between two branches.
+///
+/// The result maps directly onto `PullRequestCreate` for `git_forge_create_pr`.
+#[tauri::command]
+pub async fn git_generate_pr_description(
+    path: String,
+    base_branch: String,
+    head_branch: Option<String>,
+    model: String,
+    provider: AIProvider,
+    state: tauri::State<'_, AIState>,
+) -> Result<GeneratedPrDescription, String> {
+    let head = match head_branch {
+        Some(h) => h,
+        None => super::status::git_branch(path.clone())
+            .await?
+            .branch
+            .ok_or_else(|| "Not on a branch".to_string())?,
+    };
+
+    let comparison =
+        git_compare_branches(path.clone(), base_branch.clone(), Some(head.clone())).await?;
+    if comparison.commits_ahead.is_empty() {
+        return Err(format!(
+            "No commits on '{}' ahead of '{}'",
+            head, base_branch
+        ));
+    }
+
+    let commit_list = comparison
+        .commits_ahead
+        .iter()
+        .map(|c| format!("- {} {}", c.short_sha, c.message))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let messages = vec![
+        Message::system(
+            "You write pull request descriptions. Reply with the title on the first line, \
+             then a blank line, then a Markdown body that opens with one or two sentences \
+             on what the change does and why, followed by a short list of notable changes. \
+             Do not wrap the reply in code fences.",
+        ),
+        Message::user(format!(
+            "Branch `{}` into `{}` ({} commits):\n{}",
+            head, base_branch, comparison.ahead, commit_list
+        )),
+    ];
+
+    let manager = state.provider_manager.lock().await;
+    let reply = manager
+        .complete(messages, &model, provider)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let (title, body) = split_message(&reply);
+    if title.is_empty() {
+        return Err("Model returned an empty pull request title".to_string());
+    }
+
+    Ok(GeneratedPrDescription {
+        title,
+        body: body.unwrap_or_default(),
+        head,
+        base: base_branch,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::types::{DiffHunkData, DiffLine};
+
+    fn commit(message: &str) -> GitCommit {
+        GitCommit {
+            sha: String::new(),
+            short_sha: String::new(),
+            message: message.to_string(),
+            author: String::new(),
+            author_email: String::new(),
+            date: 0,
+        }
+    }
+
+    #[test]
+    fn infers_conventional_style_with_scopes() {
+        let commits = vec![
+            commit("feat(lsp): add pull diagnostics"),
+            commit("fix(git): handle detached HEAD"),
+            commit("fix(lsp): restart on crash"),
+            commit("Merge branch 'main'"),
+        ];
+        let style = infer_commit_style(&commits);
+        assert!(style.conventional);
+        assert!(style.uses_scope);
+        assert_eq!(style.common_scopes.first().map(String::as_str), Some("lsp"));
+        assert!(style.lowercase_subject);
+    }
+
+    #[test]
+    fn infers_plain_style() {
+        let commits = vec![
+            commit("Add terminal search"),
+            commit("Fix blame on renamed files"),
+        ];
+        let style = infer_commit_style(&commits);
+        assert!(!style.conventional);
+        assert!(!style.lowercase_subject);
+    }
+
+    #[test]
+    fn large_files_are_summarized() {
+        let lines = (0..2000)
+            .map(|i| DiffLine {
+                change_type: DiffLineType::Addition,
+                old_line_no: None,
+                new_line_no: Some(i),
+                content: format!("line {}\n", i),
+            })
+            .collect();
+        let diff = StructuredDiff {
+            file_path: "big.rs".to_string(),
+            hunks: vec![DiffHunkData {
+                old_start: 0,
+                old_lines: 0,
+                new_start: 1,
+                new_lines: 2000,
+                header: "@@ -0,0 +1,2000 @@".to_string(),
+                lines,
+            }],
+            additions: 2000,
+            deletions: 0,
+        };
+        let (text, summarized) = condense_diffs(&[diff]);
+        assert_eq!(summarized, vec!["big.rs".to_string()]);
+        assert!(text.len() < MAX_FILE_DIFF_CHARS);
+    }
+
+    fn many_hunks(file_path: &str) -> StructuredDiff {
+        let hunks = (0..600)
+            .map(|h| DiffHunkData {
+                old_start: 0,
+                old_lines: 0,
+                new_start: h * 10 + 1,
+                new_lines: 10,
+                header: format!("@@ -0,0 +{},10 @@", h * 10 + 1),
+                lines: (0..10)
+                    .map(|i| DiffLine {
+                        change_type: DiffLineType::Addition,
+                        old_line_no: None,
+                        new_line_no: Some(h * 10 + i),
+                        content: format!("line {}\n", i),
+                    })
+                    .collect(),
+            })
+            .collect();
+        StructuredDiff {
+            file_path: file_path.to_string(),
+            hunks,
+            additions: 6000,
+            deletions: 0,
+        }
+    }
+
+    #[test]
+    fn summaries_with_many_hunks_are_capped() {
+        let (text, summarized) = condense_diffs(&[many_hunks("huge.rs"), many_hunks("next.rs")]);
+        assert_eq!(
+            summarized,
+            vec!["huge.rs".to_string(), "next.rs".to_string()]
+        );
+        let huge = text.split("--- next.rs").next().unwrap();
+        assert!(huge.len() <= MAX_FILE_DIFF_CHARS);
+        assert!(huge.contains("more hunks"));
+        assert!(!text.contains("omitted"));
+    }
+
+    #[test]
+    fn files_over_budget_are_listed_once() {
+        let diffs: Vec<StructuredDiff> = (0..8)
+            .map(|i| many_hunks(&format!("huge{}.rs", i)))
+            .collect();
+        let (text, summarized) = condense_diffs(&diffs);
+        assert_eq!(summarized.len(), diffs.len());
+        assert!(text.len() <= MAX_DIFF_CHARS + 8 * 64);
+        assert_eq!(text.matches("huge7.rs").count(), 1);
+        assert!(text.contains("huge7.rs (+6000 -0, omitted)"));
+    }
+
+    #[test]
+    fn split_message_strips_fences() {
+        let (subject, body) = split_message("```\nfix: x\n\nbecause y\n```");
+        assert_eq!(subject, "fix: x");
+        assert_eq!(body.as_deref(), Some("because y"));
+    }
+}
@@ -0,0 +1,492 @@
+//! Automatic conflict resolution and resolution recording for the merge editor.
+//!
+//! The structural resolver handles conflicts that do not need a human: both
+//! sides made the same edit, the sides differ only in whitespace, or both
+//! sides appended different entries to an import list. Resolutions chosen by
+//! the user are recorded under `.git/cortex-rr-cache` keyed by the conflict
+//! preimage, so the same conflict is resolved automatically when it reappears
+//! (for example while replaying commits during a rebase).
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use super::types::{ConflictResolutionStrategy, MergeConflictRegion};
+
+const RR_CACHE_DIR: &str = "cortex-rr-cache";
+
+// ============================================================================
+// Structural Resolver
+// ============================================================================
+
+/// Extensions (and file names) of languages where indentation is syntax
+const INDENTATION_SIGNIFICANT: &[&str] = &[
+    "py",
+    "pyi",
+    "pyw",
+    "pyx",
+    "yaml",
+    "yml",
+    "haml",
+    "pug",
+    "jade",
+    "sass",
+    "styl",
+    "coffee",
+    "nim",
+    "hs",
+    "elm",
+    "fs",
+    "mk",
+    "makefile",
+    "gnumakefile",
+];
+
+fn is_indentation_significant(file_path: &str) -> bool {
+    let name = Path::new(file_path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let kind = name.rsplit_once('.').map_or(name.as_str(), |(_, ext)| ext);
+    INDENTATION_SIGNIFICANT.contains(&kind)
+}
+
+/// A dotted or `::`-separated module path such as `os.path` or `std::io`
+fn is_module_path(text: &str) -> bool {
+    !text.is_empty()
+        && text
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '.' | ':' | '*' | '$'))
+}
+
+fn is_import_line(line: &str) -> bool {
+    let trimmed = line.trim();
+    let is_statement = trimmed.ends_with(';');
+
+    // Python: `from pkg.module import name`
+    if let Some(rest) = trimmed.strip_prefix("from ") {
+        return rest
+            .split_once(" import ")
+            .is_some_and(|(module, names)| is_module_path(module) && !names.trim().is_empty());
+    }
+    // JS/TS `import x from "y";`, Go `import "fmt"`, Java `import a.b.C;`,
+    // Python `import os.path as p, sys`
+    if let Some(rest) = trimmed.strip_prefix("import ") {
+        if rest.contains('"') || rest.contains('\'') {
+            return true;
+        }
+        return rest.trim_end_matches(';').split(',').all(|part| {
+            let mut words = part.split_whitespace();
+            match (words.next(), words.next(), words.next(), words.next()) {
+                (Some(module), None, None, None) => is_module_path(module),
+                (Some(module), Some("as"), Some(alias), None) => {
+                    is_module_path(module) && is_module_path(alias)
+                }
+                _ => false,
+            }
+        });
+    }
+    if let Some(rest) = trimmed.strip_prefix("#include") {
+        return matches!(rest.trim_start().chars().next(), Some('<' | '"'));
+    }
+    if trimmed.starts_with("use ")
+        || trimmed.starts_with("pub use ")
+        || trimmed.starts_with("using ")
+        || trimmed.starts_with("@import ")
+    {
+        return is_statement;
+    }
+    // CommonJS: `const x = require("y");`
+    trimmed.contains("require(") && (is_statement || trimmed.ends_with(')'))
+}
+
+fn non_blank(lines: &[String]) -> Vec<&str> {
+    lines
+        .iter()
+        .map(|l| l.as_str())
+        .filter(|l| !l.trim().is_empty())
+        .collect()
+}
+
+fn is_sorted(lines: &[&str]) -> bool {
+    lines.windows(2).all(|w| w[0].trim() <= w[1].trim())
+}
+
+fn normalize_whitespace(lines: &[String]) -> Vec<&str> {
+    lines.iter().flat_map(|l| l.split_whitespace()).collect()
+}
+
+/// Merge two import lists that only added entries relative to the base.
+fn resolve_import_union(
+    base: Option<&[String]>,
+    ours: &[String],
+    theirs: &[String],
+) -> Option<Vec<String>> {
+    let ours_lines = non_blank(ours);
+    let theirs_lines = non_blank(theirs);
+    let base_lines = base.map(non_blank).unwrap_or_default();
+
+    if ours_lines.is_empty() || theirs_lines.is_empty() {
+        return None;
+    }
+    let all_imports = ours_lines
+        .iter()
+        .chain(theirs_lines.iter())
+        .chain(base_lines.iter())
+        .all(|l| is_import_line(l));
+    if !all_imports {
+        return None;
+    }
+
+    // Only additions are merged; a side that removed an import needs a human.
+    let ours_set: HashSet<&str> = ours_lines.iter().map(|l| l.trim()).collect();
+    let theirs_set: HashSet<&str> = theirs_lines.iter().map(|l| l.trim()).collect();
+    if base_lines
+        .iter()
+        .any(|l| !ours_set.contains(l.trim()) || !theirs_set.contains(l.trim()))
+    {
+        return None;
+    }
+
+    let mut merged: Vec<&str> = ours_lines.clone();
+    for line in &theirs_lines {
+        if !ours_set.contains(line.trim()) {
+            merged.push(line);
+        }
+    }
+    if is_sorted(&ours_lines) && is_sorted(&theirs_lines) {
+        merged.sort_by(|a, b| a.trim().cmp(b.trim()));
+    }
+    Some(merged.into_iter().map(str::to_string).collect())
+}
+
+/// Try to resolve a conflict region of `file_path` without user input.
+pub(crate) fn resolve_trivial(
+    region: &MergeConflictRegion,
+    file_path: &str,
+) -> Option<(ConflictResolutionStrategy, Vec<String>)> {
+    if region.ours_content == region.theirs_content {
+        return Some((
+            ConflictResolutionStrategy::IdenticalChanges,
+            region.ours_content.clone(),
+        ));
+    }
+
+    // Where indentation is syntax, a whitespace change can change meaning
+    if !is_indentation_significant(file_path)
+        && normalize_whitespace(&region.ours_content)
+            == normalize_whitespace(&region.theirs_content)
+    {
+        // Keep whichever side actually changed the layout relative to the base.
+        let pick_theirs = region
+            .base_content
+            .as_ref()
+            .is_some_and(|base| *base == region.ours_content);
+        let lines = if pick_theirs {
+            region.theirs_content.clone()
+        } else {
+            region.ours_content.clone()
+        };
+        return Some((ConflictResolutionStrategy::WhitespaceOnly, lines));
+    }
+
+    resolve_import_union(
+        region.base_content.as_deref(),
+        &region.ours_content,
+        &region.theirs_content,
+    )
+    .map(|lines| (ConflictResolutionStrategy::ImportUnion, lines))
+}
+
+// ============================================================================
+// Content Rewriting
+// ============================================================================
+
+/// Rebuild file content, replacing the given regions (by index) and keeping
+/// the conflict markers of every other region intact.
+pub(crate) fn replace_regions(
+    raw: &str,
+    regions: &[MergeConflictRegion],
+    replacements: &HashMap<u32, Vec<String>>,
+) -> String {
+    let lines: Vec<&str> = raw.lines().collect();
+    let mut out: Vec<&str> = Vec::with_capacity(lines.len());
+    let mut line_no = 0usize;
+
+    for region in regions {
+        let start = region.start_line as usize;
+        let end = region.end_line as usize;
+        out.extend_from_slice(&lines[line_no.min(lines.len())..start.min(lines.len())]);
+        match replacements.get(&region.index) {
+            Some(replacement) => out.extend(replacement.iter().map(String::as_str)),
+            None => {
+                out.extend_from_slice(&lines[start.min(lines.len())..(end + 1).min(lines.len())])
+            }
+        }
+        line_no = end + 1;
+    }
+    if line_no < lines.len() {
+        out.extend_from_slice(&lines[line_no..]);
+    }
+
+    let mut result = out.join("\n");
+    if raw.ends_with('\n') {
+        result.push('\n');
+    }
+    result
+}
+
+/// Recover the per-region resolutions from a fully resolved file by aligning
+/// it with the unchanged text between conflict regions.
+///
+/// Returns `None` if the alignment is ambiguous (for example two adjacent
+/// regions, or surrounding text edited during resolution).
+pub(crate) fn extract_resolutions(
+    raw: &str,
+    regions: &[MergeConflictRegion],
+    resolved: &str,
+) -> Option<Vec<Vec<String>>> {
+    let raw_lines: Vec<&str> = raw.lines().collect();
+    let resolved_lines: Vec<&str> = resolved.lines().collect();
+    let first = regions.first()?;
+
+    let mut chunks: Vec<&[&str]> = Vec::with_capacity(regions.len() + 1);
+    chunks.push(raw_lines.get(..first.start_line as usize)?);
+    for pair in regions.windows(2) {
+        chunks.push(raw_lines.get(pair[0].end_line as usize + 1..pair[1].start_line as usize)?);
+    }
+    chunks.push(raw_lines.get(regions.last()?.end_line as usize + 1..)?);
+
+    if resolved_lines.get(..chunks[0].len())? != chunks[0] {
+        return None;
+    }
+    let mut cursor = chunks[0].len();
+    let mut result = Vec::with_capacity(regions.len());
+
+    for (i, next) in chunks.iter().enumerate().skip(1) {
+        let end = if i == chunks.len() - 1 {
+            let end = resolved_lines.len().checked_sub(next.len())?;
+            if end < cursor || resolved_lines.get(end..)? != *next {
+                return None;
+            }
+            end
+        } else {
+            if next.is_empty() {
+                return None;
+            }
+            (cursor..=resolved_lines.len().saturating_sub(next.len()))
+                .find(|&p| resolved_lines.get(p..p + next.len()) == Some(*next))?
+        };
+        result.push(
+            resolved_lines[cursor..end]
+                .iter()
+                .map(|l| l.to_string())
+                .collect(),
+        );
+        cursor = end + next.len();
+    }
+
+    Some(result)
+}
+
+// ============================================================================
+// Resolution Recording
+// ============================================================================
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RecordedResolution {
+    preimage: Vec<Vec<String>>,
+    resolution: Vec<String>,
+    recorded_at: i64,
+}
+
+/// Key a conflict by its two sides, independent of which side is "ours".
+///
+/// During a rebase the roles of the two sides are swapped compared to a
+/// merge, so the sides are ordered before hashing.
+fn conflict_key(region: &MergeConflictRegion) -> Option<String> {
+    let mut sides = [
+        region.ours_content.join("\n"),
+        region.theirs_content.join("\n"),
+    ];
+    sides.sort();
+    let preimage = format!("{}\0{}", sides[0], sides[1]);
+    git2::Oid::hash_object(git2::ObjectType::Blob, preimage.as_bytes())
+        .ok()
+        .map(|oid| oid.to_string())
+}
+
+fn cache_path(git_dir: &Path, key: &str) -> PathBuf {
+    git_dir.join(RR_CACHE_DIR).join(format!("{}.json", key))
+}
+
+/// Look up a previously recorded resolution for this conflict.
+pub(crate) fn load_recorded(git_dir: &Path, region: &MergeConflictRegion) -> Option<Vec<String>> {
+    let key = conflict_key(region)?;
+    let content = std::fs::read_to_string(cache_path(git_dir, &key)).ok()?;
+    let recorded: RecordedResolution = serde_json::from_str(&content).ok()?;
+    Some(recorded.resolution)
+}
+
+/// Record the resolution chosen for a conflict so it can be reused later.
+pub(crate) fn record_resolution(
+    git_dir: &Path,
+    region: &MergeConflictRegion,
+    resolution: &[String],
+) -> Result<(), String> {
+    let key = conflict_key(region).ok_or_else(|| "Failed to hash conflict".to_string())?;
+    let path = cache_path(git_dir, &key);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create resolution cache: {}", e))?;
+    }
+    let recorded = RecordedResolution {
+        preimage: vec![region.ours_content.clone(), region.theirs_content.clone()],
+        resolution: resolution.to_vec(),
+        recorded_at: chrono::Utc::now().timestamp(),
+    };
+    let json = serde_json::to_string_pretty(&recorded)
+        .map_err(|e| format!("Failed to serialize resolution: {}", e))?;
+    std::fs::write(&path, json).map_err(|e| format!("Failed to record resolution: {}", e))?;
+    debug!("Recorded conflict resolution {}", key);
+    Ok(())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    fn lines(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn region(
+        start: u32,
+        end: u32,
+        base: Option<&[&str]>,
+        ours: &[&str],
+        theirs: &[&str],
+    ) -> MergeConflictRegion {
+        MergeConflictRegion {
+            id: format!("conflict-{}", start),
+            index: start,
+            start_line: start,
+            end_line: end,
+            separator_line: start + 1,
+            base_marker_line: None,
+            ours_content: lines(ours),
+            theirs_content: lines(theirs),
+            base_content: base.map(lines),
+            ours_label: "HEAD".to_string(),
+            theirs_label: "feature".to_string(),
+        }
+    }
+
+    #[test]
+    fn identical_changes_resolve() {
+        let r = region(0, 4, None, &["a"], &["a"]);
+        let (strategy, resolved) = resolve_trivial(&r, "src/lib.rs").unwrap();
+        assert_eq!(strategy, ConflictResolutionStrategy::IdenticalChanges);
+        assert_eq!(resolved, lines(&["a"]));
+    }
+
+    #[test]
+    fn whitespace_only_prefers_changed_side() {
+        let r = region(
+            0,
+            4,
+            Some(&["let x = 1;"]),
+            &["let x = 1;"],
+            &["let  x = 1;"],
+        );
+        let (strategy, resolved) = resolve_trivial(&r, "src/lib.rs").unwrap();
+        assert_eq!(strategy, ConflictResolutionStrategy::WhitespaceOnly);
+        assert_eq!(resolved, lines(&["let  x = 1;"]));
+    }
+
+    #[test]
+    fn import_additions_are_unioned() {
+        let r = region(
+            0,
+            6,
+            Some(&["use a;"]),
+            &["use a;", "use b;"],
+            &["use a;", "use c;"],
+        );
+        let (strategy, resolved) = resolve_trivial(&r, "src/lib.rs").unwrap();
+        assert_eq!(strategy, ConflictResolutionStrategy::ImportUnion);
+        assert_eq!(resolved, lines(&["use a;", "use b;", "use c;"]));
+    }
+
+    #[test]
+    fn import_removal_is_not_resolved() {
+        let r = region(
+            0,
+            6,
+            Some(&["use a;", "use b;"]),
+            &["use a;"],
+            &["use a;", "use b;", "use c;"],
+        );
+        assert!(resolve_trivial(&r, "src/lib.rs").is_none());
+    }
+
+    #[test]
+    fn python_indentation_is_not_whitespace_only() {
+        let r = region(
+            0,
+            4,
+            Some(&["    return x"]),
+            &["    return x"],
+            &["return x"],
+        );
+        assert!(resolve_trivial(&r, "app/main.py").is_none());
+        assert!(resolve_trivial(&r, "Makefile").is_none());
+        assert!(resolve_trivial(&r, "src/main.rs").is_some());
+    }
+
+    #[test]
+    fn prose_and_sql_are_not_imports() {
+        assert!(is_import_line("from collections import OrderedDict"));
+        assert!(is_import_line("import os.path as p, sys"));
+        assert!(is_import_line("import { a } from \"./a\";"));
+        assert!(is_import_line("#include <stdio.h>"));
+        assert!(is_import_line("const fs = require('fs');"));
+        assert!(!is_import_line("from users where id = 1"));
+        assert!(!is_import_line("from the start, import everything"));
+        assert!(!is_import_line("import the data before running"));
+        assert!(!is_import_line("use the cache when possible"));
+    }
+
+    #[test]
+    fn real_conflicts_are_left_alone() {
+        let r = region(0, 4, None, &["x = 1"], &["x = 2"]);
+        assert!(resolve_trivial(&r, "src/lib.rs").is_none());
+    }
+
+    #[test]
+    fn replace_and_extract_roundtrip() {
+        let raw = "head\n<<<<<<< HEAD\nours\n=======\ntheirs\n>>>>>>> feature\ntail\n";
+        let regions = vec![region(1, 5, None, &["ours"], &["theirs"])];
+        let mut replacements = HashMap::new();
+        replacements.insert(1, lines(&["merged", "lines"]));
+        let resolved = replace_regions(raw, &regions, &replacements);
+        assert_eq!(resolved, "head\nmerged\nlines\ntail\n");
+
+        let extracted = extract_resolutions(raw, &regions, &resolved).unwrap();
+        assert_eq!(extracted, vec![lines(&["merged", "lines"])]);
+    }
+
+    #[test]
+    fn recorded_resolution_survives_side_swap() {
+        let dir = tempfile::tempdir().unwrap();
+        let merge = region(0, 4, None, &["x = 1"], &["x = 2"]);
+        record_resolution(dir.path(), &merge, &lines(&["x = 3"])).unwrap();
+
+        let rebase = region(0, 4, None, &["x = 2"], &["x = 1"]);
+        assert_eq!(load_recorded(dir.path(), &rebase), Some(lines(&["x = 3"])));
+    }
+}
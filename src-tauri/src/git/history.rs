@@ -0,0 +1,513 @@
+//! Git history search: pickaxe, line-range history and rename-following file history.
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use tauri::Emitter;
+use tracing::{debug, info};
+
+use super::command::git_command_with_timeout;
+use super::helpers::get_repo_root;
+use super::types::{
+    DiffHunkData, DiffLine, DiffLineType, FileHistoryEntry, GitCommit, LineHistoryEntry,
+    PickaxeMatch, PickaxeMode, PickaxeSummary,
+};
+use crate::process_utils;
+
+/// Record separator placed before every commit header in `git log` output.
+const RECORD_SEP: char = '\x1e';
+/// Field separator inside a commit header.
+const FIELD_SEP: char = '\x1f';
+const LOG_FORMAT: &str = "--format=%x1e%H%x1f%h%x1f%s%x1f%an%x1f%ae%x1f%ct";
+
+const PICKAXE_EVENT: &str = "git:pickaxe-result";
+const DEFAULT_PICKAXE_LIMIT: u32 = 500;
+const DEFAULT_HISTORY_LIMIT: u32 = 200;
+
+/// Cancellation flags for running pickaxe searches, keyed by search id.
+static PICKAXE_SEARCHES: Lazy<Mutex<HashMap<String, Arc<AtomicBool>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+// ============================================================================
+// Parsing Helpers
+// ============================================================================
+
+fn validate_revision(rev: &str) -> Result<(), String> {
+    if rev.is_empty() || rev.starts_with('-') || rev.contains("..") {
+        return Err(format!("Invalid revision: {}", rev));
+    }
+    Ok(())
+}
+
+/// Parse a commit header line emitted with [`LOG_FORMAT`].
+fn parse_commit_header(line: &str) -> Option<GitCommit> {
+    let fields: Vec<&str> = line
+        .trim_start_matches(RECORD_SEP)
+        .splitn(6, FIELD_SEP)
+        .collect();
+    if fields.len() < 6 {
+        return None;
+    }
+    Some(GitCommit {
+        sha: fields[0].to_string(),
+        short_sha: fields[1].to_string(),
+        message: fields[2].to_string(),
+        author: fields[3].to_string(),
+        author_email: fields[4].to_string(),
+        date: fields[5].trim().parse::<i64>().unwrap_or(0),
+    })
+}
+
+/// Parse a `@@ -a,b +c,d @@` hunk header.
+fn parse_hunk_header(line: &str) -> Option<(u32, u32, u32, u32)> {
+    let inner = line.strip_prefix("@@ ")?;
+    let end = inner.find(" @@")?;
+    let mut parts = inner[..end].split_whitespace();
+    let parse_range = |s: &str| -> Option<(u32, u32)> {
+        let mut it = s.splitn(2, ',');
+        let start = it.next()?.parse().ok()?;
+        let len = it.next().map(|l| l.parse().ok()).unwrap_or(Some(1))?;
+        Some((start, len))
+    };
+    let (old_start, old_lines) = parse_range(parts.next()?.strip_prefix('-')?)?;
+    let (new_start, new_lines) = parse_range(parts.next()?.strip_prefix('+')?)?;
+    Some((old_start, old_lines, new_start, new_lines))
+}
+
+/// Parse `git log -L` output into per-commit entries.
+pub(crate) fn parse_line_log(output: &str, fallback_path: &str) -> Vec<LineHistoryEntry> {
+    let mut entries: Vec<LineHistoryEntry> = Vec::new();
+    let mut old_line = 0u32;
+    let mut new_line = 0u32;
+    // Lines still expected in the current hunk, on each side
+    let mut old_left = 0u32;
+    let mut new_left = 0u32;
+
+    for line in output.lines() {
+        if line.starts_with(RECORD_SEP) {
+            if let Some(commit) = parse_commit_header(line) {
+                entries.push(LineHistoryEntry {
+                    commit,
+                    file_path: fallback_path.to_string(),
+                    hunks: Vec::new(),
+                });
+            }
+            continue;
+        }
+        let Some(entry) = entries.last_mut() else {
+            continue;
+        };
+
+        // Inside a hunk, removed `-- ` and added `++ ` lines look like file
+        // headers, so headers are only recognized between hunks
+        let in_hunk = old_left > 0 || new_left > 0;
+        if !in_hunk && line.starts_with("+++ ") {
+            if let Some(path) = line.strip_prefix("+++ b/") {
+                entry.file_path = path.to_string();
+            }
+        } else if !in_hunk && (line.starts_with("--- ") || line.starts_with("diff --git")) {
+            continue;
+        } else if !in_hunk && line.starts_with("@@ ") {
+            if let Some((old_start, old_lines, new_start, new_lines)) = parse_hunk_header(line) {
+                old_line = old_start;
+                new_line = new_start;
+                old_left = old_lines;
+                new_left = new_lines;
+                entry.hunks.push(DiffHunkData {
+                    old_start,
+                    old_lines,
+                    new_start,
+                    new_lines,
+                    header: line.to_string(),
+                    lines: Vec::new(),
+                });
+            }
+        } else if let Some(hunk) = entry.hunks.last_mut() {
+            let (change_type, content) = match line.chars().next() {
+                Some('+') => (DiffLineType::Addition, &line[1..]),
+                Some('-') => (DiffLineType::Deletion, &line[1..]),
+                Some(' ') => (DiffLineType::Context, &line[1..]),
+                _ => continue,
+            };
+            let (old_line_no, new_line_no) = match change_type {
+                DiffLineType::Addition => {
+                    new_line += 1;
+                    new_left = new_left.saturating_sub(1);
+                    (None, Some(new_line - 1))
+                }
+                DiffLineType::Deletion => {
+                    old_line += 1;
+                    old_left = old_left.saturating_sub(1);
+                    (Some(old_line - 1), None)
+                }
+                _ => {
+                    old_line += 1;
+                    new_line += 1;
+                    old_left = old_left.saturating_sub(1);
+                    new_left = new_left.saturating_sub(1);
+                    (Some(old_line - 1), Some(new_line - 1))
+                }
+            };
+            hunk.lines.push(DiffLine {
+                change_type,
+                old_line_no,
+                new_line_no,
+                content: content.to_string(),
+            });
+        }
+    }
+
+    entries
+}
+
+/// Parse `git log --follow --name-status` output.
+pub(crate) fn parse_file_history(output: &str) -> Vec<FileHistoryEntry> {
+    let mut entries = Vec::new();
+    let mut current: Option<GitCommit> = None;
+
+    for line in output.lines() {
+        if line.starts_with(RECORD_SEP) {
+            current = parse_commit_header(line);
+            continue;
+        }
+        let Some(ref commit) = current else {
+            continue;
+        };
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() < 2 || fields[0].is_empty() {
+            continue;
+        }
+        // Rename/copy status carries a similarity score, e.g. "R087".
+        let status = fields[0][..1].to_string();
+        let (old_path, file_path) = if fields.len() >= 3 {
+            (Some(fields[1].to_string()), fields[2].to_string())
+        } else {
+            (None, fields[1].to_string())
+        };
+        entries.push(FileHistoryEntry {
+            commit: commit.clone(),
+            file_path,
+            old_path,
+            status,
+        });
+    }
+
+    entries
+}
+
+/// Resolve the path a file had in the first parent of `commit`, following a
+/// rename made by that commit.
+pub(crate) fn path_in_parent(repo_root: &Path, commit: &str, file: &str) -> Result<String, String> {
+    validate_revision(commit)?;
+    let output = git_command_with_timeout(
+        &[
+            "diff-tree",
+            "-M",
+            "-r",
+            "--name-status",
+            "--no-commit-id",
+            &format!("{}^", commit),
+            commit,
+        ],
+        repo_root,
+    )?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!(
+            "Failed to inspect commit {}: {}",
+            commit,
+            stderr.trim()
+        ));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    for line in stdout.lines() {
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() >= 3 && fields[0].starts_with('R') && fields[2] == file {
+            return Ok(fields[1].to_string());
+        }
+        if fields.len() == 2 && fields[0] == "A" && fields[1] == file {
+            return Err(format!(
+                "{} was added in {}; it has no earlier history",
+                file, commit
+            ));
+        }
+    }
+    Ok(file.to_string())
+}
+
+// ============================================================================
+// Tauri Commands
+// ============================================================================
+
+/// Search history for commits that added or removed a string (`-S`) or whose
+/// changed lines match a regex (`-G`).
+///
+/// Matches are streamed as `git:pickaxe-result` events while `git log` runs;
+/// the returned summary is sent once the search finishes or is cancelled.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn git_log_pickaxe(
+    path: String,
+    query: String,
+    mode: PickaxeMode,
+    search_id: String,
+    file_path: Option<String>,
+    max_count: Option<u32>,
+    ignore_case: Option<bool>,
+    app: tauri::AppHandle,
+) -> Result<PickaxeSummary, String> {
+    if query.is_empty() {
+        return Err("Search query cannot be empty".to_string());
+    }
+    let cancelled = Arc::new(AtomicBool::new(false));
+    PICKAXE_SEARCHES
+        .lock()
+        .insert(search_id.clone(), cancelled.clone());
+
+    let id = search_id.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        let repo_root = get_repo_root(&path)?;
+        let limit = max_count.unwrap_or(DEFAULT_PICKAXE_LIMIT);
+
+        let mut args = vec![
+            "log".to_string(),
+            LOG_FORMAT.to_string(),
+            "--name-only".to_string(),
+            format!("--max-count={}", limit),
+        ];
+        args.push(match mode {
+            PickaxeMode::String => format!("-S{}", query),
+            PickaxeMode::Regex => format!("-G{}", query),
+        });
+        if ignore_case.unwrap_or(false) {
+            args.push("--regexp-ignore-case".to_string());
+        }
+        args.push("--".to_string());
+        if let Some(ref fp) = file_path {
+            args.push(fp.clone());
+        }
+
+        let mut child = process_utils::command("git")
+            .args(&args)
+            .current_dir(&repo_root)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to spawn git log: {}", e))?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| "Failed to capture stdout".to_string())?;
+        let mut stderr = child
+            .stderr
+            .take()
+            .ok_or_else(|| "Failed to capture stderr".to_string())?;
+        // Drained concurrently so a chatty git cannot block on a full pipe
+        let stderr_handle = std::thread::spawn(move || {
+            let mut text = String::new();
+            let _ = stderr.read_to_string(&mut text);
+            text
+        });
+
+        let mut total_matches = 0u32;
+        let mut pending: Option<PickaxeMatch> = None;
+        let emit = |m: &PickaxeMatch| {
+            let _ = app.emit(PICKAXE_EVENT, m);
+        };
+
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            if cancelled.load(Ordering::Relaxed) {
+                let _ = child.kill();
+                break;
+            }
+            if line.starts_with(RECORD_SEP) {
+                if let Some(m) = pending.take() {
+                    emit(&m);
+                    total_matches += 1;
+                }
+                pending = parse_commit_header(&line).map(|commit| PickaxeMatch {
+                    search_id: id.clone(),
+                    commit,
+                    files: Vec::new(),
+                });
+            } else if !line.trim().is_empty() {
+                if let Some(ref mut m) = pending {
+                    m.files.push(line);
+                }
+            }
+        }
+        if let Some(m) = pending.take() {
+            if !cancelled.load(Ordering::Relaxed) {
+                emit(&m);
+                total_matches += 1;
+            }
+        }
+
+        let status = child
+            .wait()
+            .map_err(|e| format!("Failed to wait for git log: {}", e))?;
+        let stderr = stderr_handle.join().unwrap_or_default();
+        let was_cancelled = cancelled.load(Ordering::Relaxed);
+        if !status.success() && !was_cancelled {
+            return Err(format!("git log pickaxe search failed: {}", stderr.trim()));
+        }
+
+        info!(
+            "Pickaxe search {} found {} commits (cancelled: {})",
+            id, total_matches, was_cancelled
+        );
+        Ok(PickaxeSummary {
+            search_id: id,
+            total_matches,
+            cancelled: was_cancelled,
+            truncated: total_matches >= limit,
+        })
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e));
+
+    PICKAXE_SEARCHES.lock().remove(&search_id);
+    result?
+}
+
+/// Cancel a running pickaxe search.
+#[tauri::command]
+pub async fn git_log_pickaxe_cancel(search_id: String) -> Result<bool, String> {
+    let flag = PICKAXE_SEARCHES.lock().get(&search_id).cloned();
+    match flag {
+        Some(flag) => {
+            flag.store(true, Ordering::Relaxed);
+            debug!("Cancelled pickaxe search {}", search_id);
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// History of a line range (`git log -L`), with each commit's diff limited to
+/// the range.
+#[tauri::command]
+pub async fn git_log_line_range(
+    path: String,
+    file: String,
+    start_line: u32,
+    end_line: u32,
+    max_count: Option<u32>,
+) -> Result<Vec<LineHistoryEntry>, String> {
+    if start_line == 0 || end_line < start_line {
+        return Err(format!("Invalid line range {}-{}", start_line, end_line));
+    }
+    tokio::task::spawn_blocking(move || {
+        let repo_root = get_repo_root(&path)?;
+        let range = format!("-L{},{}:{}", start_line, end_line, file);
+        let limit = format!("--max-count={}", max_count.unwrap_or(DEFAULT_HISTORY_LIMIT));
+        let output = git_command_with_timeout(
+            &["log", LOG_FORMAT, "--no-color", &limit, &range],
+            Path::new(&repo_root),
+        )?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(format!("git log -L failed: {}", stderr.trim()));
+        }
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        Ok(parse_line_log(&stdout, &file))
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+/// Whole-file history that follows renames.
+#[tauri::command]
+pub async fn git_file_history(
+    path: String,
+    file: String,
+    max_count: Option<u32>,
+) -> Result<Vec<FileHistoryEntry>, String> {
+    tokio::task::spawn_blocking(move || {
+        let repo_root = get_repo_root(&path)?;
+        let limit = format!("--max-count={}", max_count.unwrap_or(DEFAULT_HISTORY_LIMIT));
+        let output = git_command_with_timeout(
+            &[
+                "log",
+                "--follow",
+                "-M",
+                "--name-status",
+                LOG_FORMAT,
+                &limit,
+                "--",
+                &file,
+            ],
+            Path::new(&repo_root),
+        )?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(format!("git log --follow failed: {}", stderr.trim()));
+        }
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        Ok(parse_file_history(&stdout))
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_file_history_with_rename() {
+        let output = "\x1eabc\x1fa\x1fRename\x1fDev\x1fdev@x\x1f100\n\nR095\told.rs\tnew.rs\n\
+                      \x1edef\x1fd\x1fAdd\x1fDev\x1fdev@x\x1f50\n\nA\told.rs\n";
+        let entries = parse_file_history(output);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].status, "R");
+        assert_eq!(entries[0].old_path.as_deref(), Some("old.rs"));
+        assert_eq!(entries[0].file_path, "new.rs");
+        assert_eq!(entries[1].file_path, "old.rs");
+        assert_eq!(entries[1].commit.date, 50);
+    }
+
+    #[test]
+    fn parses_line_log_hunks() {
+        let output = "\x1eabc\x1fa\x1fTweak\x1fDev\x1fdev@x\x1f100\n\n\
+                      diff --git a/src/lib.rs b/src/lib.rs\n\
+                      --- a/src/lib.rs\n\
+                      +++ b/src/lib.rs\n\
+                      @@ -10,2 +10,2 @@\n\
+                      -let a = 1;\n\
+                      +let a = 2;\n \
+                      let b = 3;\n";
+        let entries = parse_line_log(output, "src/lib.rs");
+        assert_eq!(entries.len(), 1);
+        let hunk = &entries[0].hunks[0];
+        assert_eq!(hunk.old_start, 10);
+        assert_eq!(hunk.lines.len(), 3);
+        assert_eq!(hunk.lines[1].new_line_no, Some(10));
+        assert_eq!(hunk.lines[2].old_line_no, Some(11));
+    }
+
+    #[test]
+    fn dash_lines_inside_hunks_are_content() {
+        let output = "\x1eabc\x1fa\x1fDocs\x1fDev\x1fdev@x\x1f100\n\n\
+                      diff --git a/notes.md b/notes.md\n\
+                      --- a/notes.md\n\
+                      +++ b/notes.md\n\
+                      @@ -1,2 +1,2 @@\n\
+                      --- old rule\n\
+                      +++ b/new rule\n \
+                      tail\n";
+        let entries = parse_line_log(output, "notes.md");
+        assert_eq!(entries[0].file_path, "notes.md");
+        let hunk = &entries[0].hunks[0];
+        assert_eq!(hunk.lines.len(), 3);
+        assert_eq!(hunk.lines[0].content, "-- old rule");
+        assert_eq!(hunk.lines[1].content, "++ b/new rule");
+    }
+}
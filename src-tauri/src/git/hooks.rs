@@ -0,0 +1,549 @@
+//! Git hook discovery and execution.
+//!
+//! Commits are written through libgit2, which never runs hooks, and pushes pass
+//! `--no-verify`, so the pre-commit, commit-msg and pre-push hooks are run here
+//! instead. Running them ourselves lets their output stream into the "Git
+//! Hooks" output channel, lets compiler and linter errors in that output show
+//! up as build diagnostics, and lets the user skip a single hook for one
+//! operation.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Arc, mpsc};
+use std::time::{Duration, Instant};
+
+use tauri::{Emitter, Manager};
+use tracing::{info, warn};
+
+use super::helpers::find_repo;
+use super::types::{GitHookInfo, GitHookRunResult, GitHookSource, GitHooksInfo};
+use crate::diagnostics::{DiagnosticsState, UnifiedDiagnostic};
+use crate::output_channels::{OutputChannelState, OutputLevel};
+use crate::process_utils;
+use crate::tasks::{
+    ProblemMatcher, get_builtin_problem_matcher, match_problem_line, parse_rustc_header,
+};
+
+const HOOKS_OUTPUT_CHANNEL: &str = "Git Hooks";
+const HOOK_FINISHED_EVENT: &str = "git:hook-finished";
+
+/// Upper bound for a single hook run; test suites in pre-push hooks can be slow.
+const HOOK_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+
+/// Lines of output kept in the run result and in failure messages.
+const OUTPUT_TAIL_LINES: usize = 40;
+
+/// Hooks that Cortex runs itself around commit and push.
+const MANAGED_HOOKS: &[&str] = &["pre-commit", "commit-msg", "pre-push"];
+
+/// Problem matchers applied to hook output.
+const HOOK_MATCHERS: &[&str] = &["$rustc", "$tsc", "$gcc", "$go", "$python"];
+
+const LEFTHOOK_CONFIGS: &[&str] = &[
+    "lefthook.yml",
+    ".lefthook.yml",
+    "lefthook.yaml",
+    ".lefthook.yaml",
+];
+const PRE_COMMIT_CONFIG: &str = ".pre-commit-config.yaml";
+
+// ============================================================================
+// Discovery
+// ============================================================================
+
+/// Effective hooks directory and the source it counts as.
+fn hooks_dir(repo: &git2::Repository) -> (PathBuf, GitHookSource) {
+    let configured = repo
+        .config()
+        .and_then(|c| c.get_path("core.hooksPath"))
+        .ok();
+    match configured {
+        Some(dir) if dir.is_absolute() => (dir, GitHookSource::HooksPath),
+        Some(dir) => {
+            // Relative hooksPath is resolved against the working tree root.
+            let base = repo.workdir().unwrap_or_else(|| repo.path());
+            (base.join(dir), GitHookSource::HooksPath)
+        }
+        None => (repo.path().join("hooks"), GitHookSource::HooksDir),
+    }
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+fn lefthook_config(workdir: &Path) -> Option<PathBuf> {
+    LEFTHOOK_CONFIGS
+        .iter()
+        .map(|name| workdir.join(name))
+        .find(|p| p.is_file())
+}
+
+/// Whether a lefthook config declares `hook` as a top-level key.
+fn lefthook_declares(config: &str, hook: &str) -> bool {
+    config.lines().any(|line| {
+        !line.starts_with(char::is_whitespace)
+            && line
+                .trim_end()
+                .strip_prefix(hook)
+                .is_some_and(|rest| rest == ":")
+    })
+}
+
+/// Whether the pre-commit framework would run anything for `hook`.
+fn pre_commit_handles(config: &str, hook: &str) -> bool {
+    match hook {
+        "pre-commit" => true,
+        // Only hooks that opt into the commit-msg stage run there.
+        "commit-msg" => config.contains("commit-msg"),
+        _ => false,
+    }
+}
+
+/// Find the hook that would run for `name`.
+///
+/// Installed scripts win over framework configs, matching what git itself
+/// would execute; framework configs only apply when their hooks have not been
+/// installed (e.g. `pre-commit install` was never run).
+fn discover_hook(repo: &git2::Repository, name: &str) -> Option<GitHookInfo> {
+    let (dir, source) = hooks_dir(repo);
+    let script = dir.join(name);
+    if is_executable(&script) {
+        return Some(GitHookInfo {
+            name: name.to_string(),
+            source,
+            path: script.to_string_lossy().to_string(),
+        });
+    }
+
+    let workdir = repo.workdir()?;
+
+    let husky = workdir.join(".husky").join(name);
+    if husky.is_file() {
+        return Some(GitHookInfo {
+            name: name.to_string(),
+            source: GitHookSource::Husky,
+            path: husky.to_string_lossy().to_string(),
+        });
+    }
+
+    if let Some(config) = lefthook_config(workdir) {
+        let content = std::fs::read_to_string(&config).unwrap_or_default();
+        if lefthook_declares(&content, name) {
+            return Some(GitHookInfo {
+                name: name.to_string(),
+                source: GitHookSource::Lefthook,
+                path: config.to_string_lossy().to_string(),
+            });
+        }
+    }
+
+    let pre_commit = workdir.join(PRE_COMMIT_CONFIG);
+    if pre_commit.is_file() {
+        let content = std::fs::read_to_string(&pre_commit).unwrap_or_default();
+        if pre_commit_handles(&content, name) {
+            return Some(GitHookInfo {
+                name: name.to_string(),
+                source: GitHookSource::PreCommit,
+                path: pre_commit.to_string_lossy().to_string(),
+            });
+        }
+    }
+
+    None
+}
+
+fn discover_hooks(repo: &git2::Repository) -> GitHooksInfo {
+    let (dir, _) = hooks_dir(repo);
+
+    let mut names: Vec<String> = std::fs::read_dir(&dir)
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .filter(|e| is_executable(&e.path()))
+                .map(|e| e.file_name().to_string_lossy().to_string())
+                .filter(|n| !n.ends_with(".sample"))
+                .collect()
+        })
+        .unwrap_or_default();
+    for hook in MANAGED_HOOKS {
+        if !names.iter().any(|n| n == hook) {
+            names.push((*hook).to_string());
+        }
+    }
+    names.sort();
+
+    GitHooksInfo {
+        hooks_dir: dir.to_string_lossy().to_string(),
+        hooks: names
+            .iter()
+            .filter_map(|name| discover_hook(repo, name))
+            .collect(),
+    }
+}
+
+/// Program and arguments that run `hook`.
+fn hook_command(hook: &GitHookInfo, args: &[String]) -> (String, Vec<String>) {
+    match hook.source {
+        GitHookSource::Lefthook => {
+            let mut full = vec!["run".to_string(), hook.name.clone()];
+            full.extend(args.iter().cloned());
+            ("lefthook".to_string(), full)
+        }
+        GitHookSource::PreCommit => {
+            let mut full = vec![
+                "run".to_string(),
+                "--hook-stage".to_string(),
+                hook.name.clone(),
+            ];
+            if hook.name == "commit-msg" {
+                if let Some(msg_file) = args.first() {
+                    full.push("--commit-msg-filename".to_string());
+                    full.push(msg_file.clone());
+                }
+            }
+            ("pre-commit".to_string(), full)
+        }
+        // Husky scripts are not necessarily executable, and on Windows hooks
+        // are shell scripts that need git's bundled sh.
+        GitHookSource::Husky => sh_command(&hook.path, args),
+        GitHookSource::HooksDir | GitHookSource::HooksPath if cfg!(windows) => {
+            sh_command(&hook.path, args)
+        }
+        GitHookSource::HooksDir | GitHookSource::HooksPath => (hook.path.clone(), args.to_vec()),
+    }
+}
+
+fn sh_command(script: &str, args: &[String]) -> (String, Vec<String>) {
+    let mut full = vec![script.to_string()];
+    full.extend(args.iter().cloned());
+    ("sh".to_string(), full)
+}
+
+// ============================================================================
+// Execution
+// ============================================================================
+
+/// Writes hook output into the shared "Git Hooks" output channel.
+struct HookOutput {
+    app: tauri::AppHandle,
+    channels: Option<Arc<OutputChannelState>>,
+    channel_id: Option<String>,
+}
+
+impl HookOutput {
+    fn new(app: &tauri::AppHandle) -> Self {
+        let channels = app
+            .try_state::<Arc<OutputChannelState>>()
+            .map(|s| Arc::clone(s.inner()));
+        let channel_id = channels
+            .as_ref()
+            .and_then(|c| c.ensure_channel(app, HOOKS_OUTPUT_CHANNEL, None).ok());
+        Self {
+            app: app.clone(),
+            channels,
+            channel_id,
+        }
+    }
+
+    fn line(&self, text: &str, level: OutputLevel) {
+        if let (Some(channels), Some(id)) = (&self.channels, &self.channel_id) {
+            let _ = channels.append_line(&self.app, id, text, level);
+        }
+    }
+}
+
+fn hook_matchers() -> Vec<ProblemMatcher> {
+    HOOK_MATCHERS
+        .iter()
+        .filter_map(|name| get_builtin_problem_matcher(name))
+        .collect()
+}
+
+fn spawn_line_reader<R: Read + Send + 'static>(reader: R, tx: mpsc::Sender<String>) {
+    std::thread::spawn(move || {
+        for line in BufReader::new(reader).lines().map_while(Result::ok) {
+            if tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+/// Run the hook named `hook` if the repository has one.
+///
+/// Returns `Ok(None)` when no hook is configured. A hook that runs and exits
+/// non-zero is reported through `GitHookRunResult::success`, not as an error.
+fn run_hook(
+    repo: &git2::Repository,
+    hook: &str,
+    args: &[String],
+    stdin: Option<String>,
+    app: &tauri::AppHandle,
+) -> Result<Option<GitHookRunResult>, String> {
+    let Some(info) = discover_hook(repo, hook) else {
+        return Ok(None);
+    };
+    let workdir = repo
+        .workdir()
+        .ok_or("Repository has no working directory")?
+        .to_path_buf();
+
+    let output = HookOutput::new(app);
+    output.line(
+        &format!("> Running {} hook ({})", hook, info.path),
+        OutputLevel::Info,
+    );
+
+    let (program, full_args) = hook_command(&info, args);
+    let mut command = process_utils::command(&program);
+    command
+        .args(&full_args)
+        .current_dir(&workdir)
+        .stdin(if stdin.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    let start = Instant::now();
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("Failed to run {} hook ({}): {}", hook, program, e))?;
+
+    if let (Some(input), Some(mut pipe)) = (stdin, child.stdin.take()) {
+        // Hooks may exit without reading stdin; a broken pipe is not an error.
+        let _ = pipe.write_all(input.as_bytes());
+    }
+
+    let (tx, rx) = mpsc::channel();
+    if let Some(stdout) = child.stdout.take() {
+        spawn_line_reader(stdout, tx.clone());
+    }
+    if let Some(stderr) = child.stderr.take() {
+        spawn_line_reader(stderr, tx);
+    }
+
+    let matchers = hook_matchers();
+    let source_name = format!("{} hook", hook);
+    let mut last_rustc: Option<(String, String)> = None;
+    let mut diagnostics: Vec<UnifiedDiagnostic> = Vec::new();
+    let mut tail: Vec<String> = Vec::new();
+
+    loop {
+        match rx.recv_timeout(Duration::from_millis(100)) {
+            Ok(line) => {
+                output.line(&line, OutputLevel::Info);
+                if let Some(ctx) = parse_rustc_header(&line) {
+                    last_rustc = Some(ctx);
+                }
+                if let Some(problem) = match_problem_line(&line, &matchers, hook, &last_rustc) {
+                    diagnostics.extend(UnifiedDiagnostic::from_problem(
+                        &problem,
+                        &workdir,
+                        &source_name,
+                    ));
+                }
+                if tail.len() == OUTPUT_TAIL_LINES {
+                    tail.remove(0);
+                }
+                tail.push(line);
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                if start.elapsed() > HOOK_TIMEOUT {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    output.line(&format!("{} hook timed out", hook), OutputLevel::Error);
+                    return Err(format!(
+                        "{} hook timed out after {} minutes",
+                        hook,
+                        HOOK_TIMEOUT.as_secs() / 60
+                    ));
+                }
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+    }
+
+    let status = child
+        .wait()
+        .map_err(|e| format!("Error waiting for {} hook: {}", hook, e))?;
+
+    let diagnostics_count = diagnostics.len();
+    if let Some(state) = app.try_state::<DiagnosticsState>() {
+        // Always replace, so a passing run clears the previous failure.
+        state.replace_build_diagnostics(app, &source_name, diagnostics);
+    }
+
+    let result = GitHookRunResult {
+        hook: hook.to_string(),
+        source: info.source,
+        success: status.success(),
+        exit_code: status.code(),
+        output: tail.join("\n"),
+        duration_ms: start.elapsed().as_millis() as u64,
+        diagnostics_count,
+    };
+
+    if result.success {
+        output.line(
+            &format!("{} hook passed in {} ms", hook, result.duration_ms),
+            OutputLevel::Info,
+        );
+    } else {
+        output.line(
+            &format!(
+                "{} hook failed with exit code {}",
+                hook,
+                result
+                    .exit_code
+                    .map(|c| c.to_string())
+                    .unwrap_or_else(|| "unknown".to_string())
+            ),
+            OutputLevel::Error,
+        );
+    }
+    let _ = app.emit(HOOK_FINISHED_EVENT, &result);
+
+    Ok(Some(result))
+}
+
+/// Run a hook as part of a git operation, failing the operation if it fails.
+///
+/// Hooks listed in `skip_hooks` are not run.
+pub(super) fn run_hook_checked(
+    repo: &git2::Repository,
+    hook: &str,
+    args: &[String],
+    stdin: Option<String>,
+    skip_hooks: &[String],
+    app: &tauri::AppHandle,
+) -> Result<(), String> {
+    if skip_hooks.iter().any(|h| h == hook) {
+        if discover_hook(repo, hook).is_some() {
+            info!("Skipping {} hook at user request", hook);
+            HookOutput::new(app).line(&format!("Skipped {} hook", hook), OutputLevel::Warning);
+        }
+        return Ok(());
+    }
+
+    match run_hook(repo, hook, args, stdin, app)? {
+        Some(result) if !result.success => {
+            warn!("{} hook failed with {:?}", hook, result.exit_code);
+            let mut message = format!(
+                "{} hook failed. See the \"{}\" output channel for details.",
+                hook, HOOKS_OUTPUT_CHANNEL
+            );
+            if !result.output.is_empty() {
+                message.push_str("\n\n");
+                message.push_str(&result.output);
+            }
+            Err(message)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Whether `hook` would run for this repository.
+pub(super) fn has_hook(repo: &git2::Repository, hook: &str) -> bool {
+    discover_hook(repo, hook).is_some()
+}
+
+// ============================================================================
+// Tauri Commands
+// ============================================================================
+
+/// List the hooks that would run for a repository.
+#[tauri::command]
+pub async fn git_hooks_list(path: String) -> Result<GitHooksInfo, String> {
+    tokio::task::spawn_blocking(move || {
+        let repo = find_repo(&path)?;
+        Ok(discover_hooks(&repo))
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+/// Run a single hook on demand, e.g. to re-check after fixing a failure.
+#[tauri::command]
+pub async fn git_hooks_run(
+    path: String,
+    hook: String,
+    args: Option<Vec<String>>,
+    app: tauri::AppHandle,
+) -> Result<Option<GitHookRunResult>, String> {
+    tokio::task::spawn_blocking(move || {
+        let repo = find_repo(&path)?;
+        run_hook(&repo, &hook, &args.unwrap_or_default(), None, &app)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lefthook_top_level_keys_only() {
+        let config = "pre-commit:\n  commands:\n    lint:\n      run: npm run lint\n";
+        assert!(lefthook_declares(config, "pre-commit"));
+        assert!(!lefthook_declares(config, "pre-push"));
+        assert!(!lefthook_declares(config, "lint"));
+        assert!(!lefthook_declares("pre-commit-extra:\n", "pre-commit"));
+    }
+
+    #[test]
+    fn pre_commit_commit_msg_requires_stage() {
+        let config = "repos:\n- repo: local\n  hooks:\n  - id: fmt\n";
+        assert!(pre_commit_handles(config, "pre-commit"));
+        assert!(!pre_commit_handles(config, "commit-msg"));
+        assert!(pre_commit_handles(
+            "default_install_hook_types: [pre-commit, commit-msg]\n",
+            "commit-msg"
+        ));
+        assert!(!pre_commit_handles(config, "pre-push"));
+    }
+
+    #[test]
+    fn framework_hook_commands() {
+        let lefthook = GitHookInfo {
+            name: "commit-msg".to_string(),
+            source: GitHookSource::Lefthook,
+            path: "lefthook.yml".to_string(),
+        };
+        let (program, args) = hook_command(&lefthook, &[".git/COMMIT_EDITMSG".to_string()]);
+        assert_eq!(program, "lefthook");
+        assert_eq!(args, vec!["run", "commit-msg", ".git/COMMIT_EDITMSG"]);
+
+        let pre_commit = GitHookInfo {
+            name: "commit-msg".to_string(),
+            source: GitHookSource::PreCommit,
+            path: PRE_COMMIT_CONFIG.to_string(),
+        };
+        let (program, args) = hook_command(&pre_commit, &["MSG".to_string()]);
+        assert_eq!(program, "pre-commit");
+        assert_eq!(
+            args,
+            vec![
+                "run",
+                "--hook-stage",
+                "commit-msg",
+                "--commit-msg-filename",
+                "MSG"
+            ]
+        );
+    }
+}
@@ -1,11 +1,22 @@
 //! Git merge editor operations for conflict resolution.
 
+use std::collections::HashMap;
 use std::path::Path;
 use tracing::{info, warn};
 
 use super::command::git_command_with_timeout;
+use super::conflict_resolver::{
+    extract_resolutions, load_recorded, record_resolution, replace_regions, resolve_trivial,
+};
 use super::helpers::{find_repo, get_repo_root};
-use super::types::{MergeConflictFile, MergeConflictRegion, ThreeWayDiffResult};
+use super::types::{
+    AiConflictProposal, AutoResolveResult, AutoResolvedConflict, ConflictResolutionStrategy,
+    MergeConflictFile, MergeConflictRegion, ThreeWayDiffResult,
+};
+use crate::ai::{AIProvider, AIState, Message};
+
+/// Lines of unchanged context sent around a conflict to the AI resolver.
+const AI_CONTEXT_LINES: usize = 15;
 
 // ============================================================================
 // Conflict Marker Parsing
@@ -289,6 +300,27 @@ pub async fn git_resolve_conflict(
 
         let full_path = repo_root.join(&file_path);
 
+        // Record per-region resolutions so the same conflict can be replayed
+        // automatically later (e.g. when a rebase hits it again).
+        if let Ok(raw) = std::fs::read_to_string(&full_path) {
+            let parsed = parse_conflict_markers(&raw);
+            if !parsed.regions.is_empty() {
+                match extract_resolutions(&raw, &parsed.regions, &resolved_content) {
+                    Some(resolutions) => {
+                        for (region, resolution) in parsed.regions.iter().zip(resolutions) {
+                            if let Err(e) = record_resolution(repo.path(), region, &resolution) {
+                                warn!("Failed to record resolution for {}: {}", file_path, e);
+                            }
+                        }
+                    }
+                    None => warn!(
+                        "Could not align resolution with conflicts in {}; not recorded",
+                        file_path
+                    ),
+                }
+            }
+        }
+
         std::fs::write(&full_path, &resolved_content)
             .map_err(|e| format!("Failed to write resolved content: {}", e))?;
 
@@ -331,3 +363,170 @@ pub async fn git_abort_merge(path: String) -> Result<(), String> {
     .await
     .map_err(|e| format!("Task join error: {}", e))?
 }
+
+/// Automatically resolve trivial conflicts in a file.
+///
+/// Runs before the file is shown in the merge editor. Each region is first
+/// checked against previously recorded resolutions, then against the
+/// structural resolver. Resolved regions are written back to the file while
+/// the remaining conflict markers are preserved; a file with no remaining
+/// conflicts is staged.
+#[tauri::command]
+pub async fn git_auto_resolve_conflicts(
+    path: String,
+    file_path: String,
+) -> Result<AutoResolveResult, String> {
+    tokio::task::spawn_blocking(move || {
+        let repo = find_repo(&path)?;
+        let repo_root = repo
+            .workdir()
+            .ok_or_else(|| "Could not determine repository root".to_string())?;
+
+        let full_path = repo_root.join(&file_path);
+        let raw = std::fs::read_to_string(&full_path)
+            .map_err(|e| format!("Failed to read file {}: {}", file_path, e))?;
+        let parsed = parse_conflict_markers(&raw);
+
+        let mut resolved = Vec::new();
+        let mut replacements = HashMap::new();
+        for region in &parsed.regions {
+            let resolution = load_recorded(repo.path(), region)
+                .map(|lines| (ConflictResolutionStrategy::Recorded, lines))
+                .or_else(|| resolve_trivial(region, &file_path));
+            if let Some((strategy, lines)) = resolution {
+                replacements.insert(region.index, lines.clone());
+                resolved.push(AutoResolvedConflict {
+                    conflict_id: region.id.clone(),
+                    strategy,
+                    resolved_lines: lines,
+                });
+            }
+        }
+
+        let remaining_conflicts = (parsed.regions.len() - resolved.len()) as u32;
+        let mut staged = false;
+
+        if !resolved.is_empty() {
+            let content = replace_regions(&raw, &parsed.regions, &replacements);
+            std::fs::write(&full_path, content)
+                .map_err(|e| format!("Failed to write resolved content: {}", e))?;
+
+            if remaining_conflicts == 0 {
+                let mut index = repo
+                    .index()
+                    .map_err(|e| format!("Failed to get index: {}", e))?;
+                index
+                    .add_path(Path::new(&file_path))
+                    .map_err(|e| format!("Failed to stage resolved file: {}", e))?;
+                index
+                    .write()
+                    .map_err(|e| format!("Failed to write index: {}", e))?;
+                staged = true;
+            }
+
+            info!(
+                "Auto-resolved {} of {} conflicts in {}",
+                resolved.len(),
+                parsed.regions.len(),
+                file_path
+            );
+        }
+
+        Ok(AutoResolveResult {
+            file_path,
+            resolved,
+            remaining_conflicts,
+            staged,
+        })
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+#[derive(serde::Deserialize)]
+struct AiResolutionReply {
+    resolved: String,
+    explanation: String,
+}
+
+fn parse_ai_reply(reply: &str) -> Result<AiResolutionReply, String> {
+    let start = reply.find('{');
+    let end = reply.rfind('}');
+    match (start, end) {
+        (Some(start), Some(end)) if start < end => serde_json::from_str(&reply[start..=end])
+            .map_err(|e| format!("Failed to parse AI resolution: {}", e)),
+        _ => Err("AI reply did not contain a resolution".to_string()),
+    }
+}
+
+/// Ask the AI provider to propose a merged hunk for a single conflict.
+///
+/// Nothing is written to disk; the frontend applies the proposal through
+/// `git_resolve_conflict` once the user confirms it.
+#[tauri::command]
+pub async fn git_ai_propose_resolution(
+    path: String,
+    file_path: String,
+    conflict_id: String,
+    model: String,
+    provider: AIProvider,
+    state: tauri::State<'_, AIState>,
+) -> Result<AiConflictProposal, String> {
+    let diff = git_get_three_way_diff(path, file_path.clone()).await?;
+    let region = diff
+        .conflicts
+        .iter()
+        .find(|c| c.id == conflict_id)
+        .ok_or_else(|| format!("Conflict '{}' not found in {}", conflict_id, file_path))?;
+
+    let raw_lines: Vec<&str> = diff.raw_content.lines().collect();
+    let start = region.start_line as usize;
+    let end = (region.end_line as usize + 1).min(raw_lines.len());
+    let before = raw_lines[start.saturating_sub(AI_CONTEXT_LINES)..start].join("\n");
+    let after = raw_lines[end..(end + AI_CONTEXT_LINES).min(raw_lines.len())].join("\n");
+    let base = region
+        .base_content
+        .as_ref()
+        .map(|b| b.join("\n"))
+        .unwrap_or_else(|| "(not available)".to_string());
+
+    let messages = vec![
+        Message::system(
+            "You resolve git merge conflicts. Combine the intent of both sides into a single \
+             hunk. Reply with a JSON object only: {\"resolved\": \"<merged lines>\", \
+             \"explanation\": \"<one or two sentences on how the sides were combined>\"}.",
+        ),
+        Message::user(format!(
+            "File: {}\n\nContext before:\n{}\n\nBase:\n{}\n\nOurs ({}):\n{}\n\nTheirs ({}):\n{}\n\nContext after:\n{}",
+            file_path,
+            before,
+            base,
+            region.ours_label,
+            region.ours_content.join("\n"),
+            region.theirs_label,
+            region.theirs_content.join("\n"),
+            after
+        )),
+    ];
+
+    let reply = {
+        let manager = state.provider_manager.lock().await;
+        manager
+            .complete(messages, &model, provider)
+            .await
+            .map_err(|e| e.to_string())?
+    };
+    let parsed = parse_ai_reply(&reply)?;
+    let resolved_lines: Vec<String> = parsed.resolved.lines().map(str::to_string).collect();
+
+    let mut replacements = HashMap::new();
+    replacements.insert(region.index, resolved_lines.clone());
+    let proposed_content = replace_regions(&diff.raw_content, &diff.conflicts, &replacements);
+
+    Ok(AiConflictProposal {
+        conflict_id,
+        resolved_lines,
+        explanation: parsed.explanation,
+        proposed_content,
+    })
+}
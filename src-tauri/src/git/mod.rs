@@ -4,33 +4,65 @@
 //! including repository status, staging, commits, branches, remotes, rebasing, cherry-picking,
 //! bisecting, LFS support, worktrees, and more.
 
+#[cfg(feature = "desktop")]
 pub mod bisect;
+#[cfg(feature = "desktop")]
 pub mod blame;
+#[cfg(feature = "desktop")]
 pub mod branch;
 pub(crate) mod cache;
+#[cfg(feature = "desktop")]
 pub mod cherry_pick;
+#[cfg(feature = "desktop")]
 pub mod clone;
 pub(crate) mod command;
+#[cfg(feature = "desktop")]
+pub mod commit_message;
+#[cfg(feature = "desktop")]
+pub(crate) mod conflict_resolver;
+#[cfg(feature = "desktop")]
 pub mod diff;
+#[cfg(feature = "desktop")]
 pub mod forge;
+#[cfg(feature = "desktop")]
 pub mod graph;
 pub(crate) mod helpers;
+#[cfg(feature = "desktop")]
+pub mod history;
+#[cfg(feature = "desktop")]
+pub mod hooks;
+#[cfg(feature = "desktop")]
 pub mod hunk;
+#[cfg(feature = "desktop")]
 pub mod lfs;
+#[cfg(feature = "desktop")]
 pub mod lines;
+#[cfg(feature = "desktop")]
 pub mod log;
+#[cfg(feature = "desktop")]
 pub mod merge;
+#[cfg(feature = "desktop")]
 pub mod merge_editor;
+#[cfg(feature = "desktop")]
 pub mod pull_request;
+#[cfg(feature = "desktop")]
 pub mod rebase;
+#[cfg(feature = "desktop")]
 pub mod remote;
+pub mod sparse;
+#[cfg(feature = "desktop")]
 pub mod staging;
+#[cfg(feature = "desktop")]
 pub mod stash;
 pub mod status;
+#[cfg(feature = "desktop")]
 pub mod submodule;
+#[cfg(feature = "desktop")]
 pub mod tag;
 pub mod types;
+#[cfg(feature = "desktop")]
 pub mod watcher;
+#[cfg(feature = "desktop")]
 pub mod worktree;
 
 // ============================================================================
@@ -39,6 +71,7 @@ pub mod worktree;
 
 /// Trait for SCM provider abstraction (Git, SVN, Perforce, etc.)
 /// This provides a common interface for future SCM backend support.
+#[cfg(feature = "desktop")]
 #[allow(dead_code)]
 pub trait ScmProvider: Send + Sync {
     fn name(&self) -> &str;
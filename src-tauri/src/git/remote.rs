@@ -5,6 +5,7 @@ use tracing::info;
 
 use super::command::git_command_with_timeout;
 use super::helpers::{find_repo, get_repo_root};
+use super::hooks;
 use super::types::{ForcePushInfo, GitCommit};
 
 // ============================================================================
@@ -154,6 +155,32 @@ pub async fn git_fetch_with_options(
     .map_err(|e| format!("Task join error: {}", e))?
 }
 
+/// Build the stdin lines git passes to a pre-push hook:
+/// `<local ref> <local sha> <remote ref> <remote sha>`.
+fn pre_push_stdin(
+    repo: &git2::Repository,
+    remote_name: &str,
+    branch: Option<&str>,
+) -> Option<String> {
+    const ZERO_OID: &str = "0000000000000000000000000000000000000000";
+
+    let branch = match branch {
+        Some(b) => b.to_string(),
+        None => repo.head().ok()?.shorthand()?.to_string(),
+    };
+    let local_ref = format!("refs/heads/{}", branch);
+    let local_sha = repo.refname_to_id(&local_ref).ok()?;
+    let remote_sha = repo
+        .refname_to_id(&format!("refs/remotes/{}/{}", remote_name, branch))
+        .map(|oid| oid.to_string())
+        .unwrap_or_else(|_| ZERO_OID.to_string());
+
+    Some(format!(
+        "{} {} {} {}\n",
+        local_ref, local_sha, local_ref, remote_sha
+    ))
+}
+
 /// Push to a remote with --follow-tags option
 /// This pushes annotated tags that are reachable from the pushed commits
 #[tauri::command]
@@ -162,13 +189,31 @@ pub async fn git_push_with_tags(
     remote: Option<String>,
     branch: Option<String>,
     follow_tags: bool,
+    skip_hooks: Option<Vec<String>>,
+    app: tauri::AppHandle,
 ) -> Result<(), String> {
     tokio::task::spawn_blocking(move || {
         let repo_root = get_repo_root(&path)?;
         let repo_root_path = Path::new(&repo_root);
         let remote_name = remote.unwrap_or_else(|| "origin".to_string());
 
-        let mut args = vec!["push", remote_name.as_str()];
+        let repo = find_repo(&path)?;
+        let remote_url = repo
+            .find_remote(&remote_name)
+            .ok()
+            .and_then(|r| r.url().map(str::to_string))
+            .unwrap_or_else(|| remote_name.clone());
+        hooks::run_hook_checked(
+            &repo,
+            "pre-push",
+            &[remote_name.clone(), remote_url],
+            pre_push_stdin(&repo, &remote_name, branch.as_deref()),
+            &skip_hooks.unwrap_or_default(),
+            &app,
+        )?;
+
+        // The pre-push hook already ran above.
+        let mut args = vec!["push", "--no-verify", remote_name.as_str()];
 
         // Add branch if specified
         let branch_ref;
@@ -290,8 +335,10 @@ pub async fn git_push(
     path: String,
     remote: Option<String>,
     branch: Option<String>,
+    skip_hooks: Option<Vec<String>>,
+    app: tauri::AppHandle,
 ) -> Result<(), String> {
-    git_push_with_tags(path, remote, branch, false).await
+    git_push_with_tags(path, remote, branch, false, skip_hooks, app).await
 }
 
 /// Check if a force push is needed for the current branch
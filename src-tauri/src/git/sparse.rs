@@ -0,0 +1,288 @@
+//! Git sparse-checkout management.
+//!
+//! libgit2 has no sparse-checkout support, so the sparse set is edited through
+//! the git CLI. Paths outside the sparse set keep the skip-worktree bit in the
+//! index; this module reads that bit so status and the file tree can tell an
+//! unmaterialized path apart from a deleted one.
+
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::info;
+
+#[cfg(feature = "desktop")]
+use super::clone::run_git_with_progress;
+use super::command::git_command_with_timeout;
+use super::helpers::{find_repo, get_repo_root};
+use super::types::{SparseCheckoutStatus, SparseExcludedEntry};
+
+const SPARSE_PROGRESS_EVENT: &str = "git:sparse-checkout-progress";
+
+/// Timeout for sparse-checkout updates, which may fetch missing blobs.
+const SPARSE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// `GIT_INDEX_ENTRY_SKIP_WORKTREE` in `git_index_entry.flags_extended`.
+const INDEX_ENTRY_SKIP_WORKTREE: u16 = 1 << 14;
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+/// Reject patterns that git would interpret as options.
+pub(super) fn validate_patterns(patterns: &[String]) -> Result<(), String> {
+    for pattern in patterns {
+        if pattern.trim().is_empty() {
+            return Err("Sparse-checkout patterns cannot be empty".to_string());
+        }
+        if pattern.starts_with('-') {
+            return Err(format!("Invalid sparse-checkout pattern: {}", pattern));
+        }
+    }
+    Ok(())
+}
+
+/// Whether sparse-checkout is enabled for the repository.
+pub(crate) fn is_sparse_enabled(repo: &git2::Repository) -> bool {
+    repo.config()
+        .and_then(|c| c.get_bool("core.sparseCheckout"))
+        .unwrap_or(false)
+}
+
+/// Paths in the index that are outside the sparse set.
+pub(crate) fn skip_worktree_paths(repo: &git2::Repository) -> HashSet<String> {
+    let Ok(index) = repo.index() else {
+        return HashSet::new();
+    };
+    index
+        .iter()
+        .filter(|entry| entry.flags_extended & INDEX_ENTRY_SKIP_WORKTREE != 0)
+        .map(|entry| String::from_utf8_lossy(&entry.path).to_string())
+        .collect()
+}
+
+/// Tracked children of `prefix` (empty or ending in `/`) that are wholly
+/// outside the sparse set.
+fn excluded_entries(
+    repo: &git2::Repository,
+    prefix: &str,
+) -> Result<Vec<SparseExcludedEntry>, String> {
+    let index = repo
+        .index()
+        .map_err(|e| format!("Failed to get index: {}", e))?;
+
+    // child name -> (is_dir, every entry below it is skip-worktree)
+    let mut children: BTreeMap<String, (bool, bool)> = BTreeMap::new();
+    for entry in index.iter() {
+        let entry_path = String::from_utf8_lossy(&entry.path);
+        let Some(rest) = entry_path.strip_prefix(prefix) else {
+            continue;
+        };
+        let skipped = entry.flags_extended & INDEX_ENTRY_SKIP_WORKTREE != 0;
+        let (name, is_dir) = match rest.split_once('/') {
+            Some((dir, _)) => (dir, true),
+            None => (rest, false),
+        };
+        let slot = children.entry(name.to_string()).or_insert((is_dir, true));
+        slot.1 &= skipped;
+    }
+
+    Ok(children
+        .into_iter()
+        .filter(|(_, (_, all_skipped))| *all_skipped)
+        .map(|(name, (is_dir, _))| SparseExcludedEntry {
+            path: format!("{}{}", prefix, name),
+            name,
+            is_dir,
+        })
+        .collect())
+}
+
+/// Children of a working tree directory that are outside the sparse set, as
+/// `(path, is_dir)`, for the file tree to show alongside the materialized ones.
+///
+/// Empty when the directory is not in a sparse checkout.
+pub(crate) fn excluded_children(dir: &Path) -> Vec<(PathBuf, bool)> {
+    let Ok(repo) = git2::Repository::discover(dir) else {
+        return Vec::new();
+    };
+    if !is_sparse_enabled(&repo) {
+        return Vec::new();
+    }
+    let Some(workdir) = repo.workdir() else {
+        return Vec::new();
+    };
+    let relative = match (dir.canonicalize(), workdir.canonicalize()) {
+        (Ok(dir), Ok(root)) => match dir.strip_prefix(&root) {
+            Ok(relative) => relative.to_path_buf(),
+            Err(_) => return Vec::new(),
+        },
+        _ => return Vec::new(),
+    };
+    let mut prefix = relative.to_string_lossy().replace('\\', "/");
+    if !prefix.is_empty() {
+        prefix.push('/');
+    }
+    excluded_entries(&repo, &prefix)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|entry| (dir.join(&entry.name), entry.is_dir))
+        .collect()
+}
+
+fn sparse_status_sync(path: &str) -> Result<SparseCheckoutStatus, String> {
+    let repo = find_repo(path)?;
+    let enabled = is_sparse_enabled(&repo);
+    if !enabled {
+        return Ok(SparseCheckoutStatus {
+            enabled: false,
+            cone: false,
+            patterns: Vec::new(),
+        });
+    }
+
+    let cone = repo
+        .config()
+        .and_then(|c| c.get_bool("core.sparseCheckoutCone"))
+        .unwrap_or(false);
+
+    let repo_root = get_repo_root(path)?;
+    let output = git_command_with_timeout(&["sparse-checkout", "list"], Path::new(&repo_root))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!(
+            "Failed to list sparse-checkout patterns: {}",
+            stderr
+        ));
+    }
+    let patterns = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(str::to_string)
+        .collect();
+
+    Ok(SparseCheckoutStatus {
+        enabled,
+        cone,
+        patterns,
+    })
+}
+
+#[cfg(feature = "desktop")]
+fn run_sparse_command(path: &str, args: &[&str], app: &tauri::AppHandle) -> Result<(), String> {
+    let repo_root = get_repo_root(path)?;
+    run_git_with_progress(
+        args,
+        Some(Path::new(&repo_root)),
+        SPARSE_PROGRESS_EVENT,
+        SPARSE_TIMEOUT,
+        app,
+    )
+    .map_err(|e| format!("git {} failed: {}", args.join(" "), e))
+}
+
+// ============================================================================
+// Tauri Commands
+// ============================================================================
+
+/// Get the sparse-checkout mode and patterns of a repository.
+#[cfg(feature = "desktop")]
+#[tauri::command]
+pub async fn git_sparse_checkout_status(path: String) -> Result<SparseCheckoutStatus, String> {
+    tokio::task::spawn_blocking(move || sparse_status_sync(&path))
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?
+}
+
+/// Replace the sparse-checkout set. Enables sparse-checkout if needed.
+#[cfg(feature = "desktop")]
+#[tauri::command]
+pub async fn git_sparse_checkout_set(
+    path: String,
+    patterns: Vec<String>,
+    cone: Option<bool>,
+    app: tauri::AppHandle,
+) -> Result<SparseCheckoutStatus, String> {
+    tokio::task::spawn_blocking(move || {
+        validate_patterns(&patterns)?;
+        let mode = if cone.unwrap_or(true) {
+            "--cone"
+        } else {
+            "--no-cone"
+        };
+        let mut args = vec!["sparse-checkout", "set", mode];
+        args.extend(patterns.iter().map(String::as_str));
+        run_sparse_command(&path, &args, &app)?;
+        info!(
+            "Set {} sparse-checkout patterns in {}",
+            patterns.len(),
+            path
+        );
+        sparse_status_sync(&path)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+/// Add patterns to the existing sparse-checkout set.
+#[cfg(feature = "desktop")]
+#[tauri::command]
+pub async fn git_sparse_checkout_add(
+    path: String,
+    patterns: Vec<String>,
+    app: tauri::AppHandle,
+) -> Result<SparseCheckoutStatus, String> {
+    tokio::task::spawn_blocking(move || {
+        validate_patterns(&patterns)?;
+        if patterns.is_empty() {
+            return sparse_status_sync(&path);
+        }
+        let mut args = vec!["sparse-checkout", "add"];
+        args.extend(patterns.iter().map(String::as_str));
+        run_sparse_command(&path, &args, &app)?;
+        sparse_status_sync(&path)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+/// Disable sparse-checkout and materialize the full working tree.
+#[cfg(feature = "desktop")]
+#[tauri::command]
+pub async fn git_sparse_checkout_disable(
+    path: String,
+    app: tauri::AppHandle,
+) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || {
+        run_sparse_command(&path, &["sparse-checkout", "disable"], &app)?;
+        info!("Disabled sparse-checkout in {}", path);
+        Ok(())
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+/// List tracked entries of a directory that are outside the sparse set.
+///
+/// Lists the unmaterialized directories and files that can be added to the
+/// sparse set; directory listings include them through `excluded_children`.
+/// `directory` is relative to the repository root; `None` lists the top level.
+#[cfg(feature = "desktop")]
+#[tauri::command]
+pub async fn git_sparse_list_excluded(
+    path: String,
+    directory: Option<String>,
+) -> Result<Vec<SparseExcludedEntry>, String> {
+    tokio::task::spawn_blocking(move || {
+        let repo = find_repo(&path)?;
+        if !is_sparse_enabled(&repo) {
+            return Ok(Vec::new());
+        }
+        let prefix = match directory.as_deref().map(|d| d.trim_matches('/')) {
+            Some(dir) if !dir.is_empty() => format!("{}/", dir),
+            _ => String::new(),
+        };
+        excluded_entries(&repo, &prefix)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
@@ -5,6 +5,7 @@ use tracing::info;
 
 use super::command::git_command_with_timeout;
 use super::helpers::find_repo;
+use super::hooks;
 
 // ============================================================================
 // Staging Commands
@@ -104,14 +105,52 @@ pub async fn git_unstage_all(path: String) -> Result<(), String> {
 // Commit Commands
 // ============================================================================
 
+/// Run the pre-commit and commit-msg hooks and return the final message.
+///
+/// The commit-msg hook may rewrite the message file (e.g. to append a
+/// Change-Id trailer), so the message is read back after it runs.
+fn run_commit_hooks(
+    repo: &git2::Repository,
+    message: String,
+    skip_hooks: &[String],
+    app: &tauri::AppHandle,
+) -> Result<String, String> {
+    hooks::run_hook_checked(repo, "pre-commit", &[], None, skip_hooks, app)?;
+
+    if skip_hooks.iter().any(|h| h == "commit-msg") || !hooks::has_hook(repo, "commit-msg") {
+        return Ok(message);
+    }
+
+    let msg_file = repo.path().join("COMMIT_EDITMSG");
+    std::fs::write(&msg_file, &message)
+        .map_err(|e| format!("Failed to write commit message file: {}", e))?;
+    hooks::run_hook_checked(
+        repo,
+        "commit-msg",
+        &[msg_file.to_string_lossy().to_string()],
+        None,
+        skip_hooks,
+        app,
+    )?;
+    std::fs::read_to_string(&msg_file)
+        .map_err(|e| format!("Failed to read commit message file: {}", e))
+}
+
+/// Create a commit from the index.
+///
+/// Hooks run through `hooks` rather than through git; names listed in
+/// `skip_hooks` (e.g. `["pre-commit"]`) are skipped for this commit only.
 #[tauri::command]
 pub async fn git_commit(
     path: String,
     message: String,
     sign: Option<bool>,
+    skip_hooks: Option<Vec<String>>,
+    app: tauri::AppHandle,
 ) -> Result<String, String> {
     tokio::task::spawn_blocking(move || {
         let repo = find_repo(&path)?;
+        let message = run_commit_hooks(&repo, message, &skip_hooks.unwrap_or_default(), &app)?;
 
         // If signing is requested, use git CLI for GPG support (libgit2 GPG support is limited)
         if sign.unwrap_or(false) {
@@ -119,7 +158,11 @@ pub async fn git_commit(
                 .workdir()
                 .ok_or("Repository has no working directory")?;
 
-            let output = git_command_with_timeout(&["commit", "-S", "-m", &message], workdir)?;
+            // Hooks already ran above.
+            let output = git_command_with_timeout(
+                &["commit", "--no-verify", "-S", "-m", &message],
+                workdir,
+            )?;
 
             if !output.status.success() {
                 let stderr = String::from_utf8_lossy(&output.stderr);
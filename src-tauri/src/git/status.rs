@@ -1,8 +1,12 @@
 //! Git status operations.
 
 use git2::{BranchType, StatusOptions};
+use std::collections::HashSet;
 use tracing::info;
 
+#[cfg(feature = "desktop")]
+use tauri::{AppHandle, Manager};
+
 use super::cache::{MAX_STATUS_FILES, cache_status, get_cached_status};
 use super::helpers::{find_repo, status_to_string};
 use super::types::{
@@ -14,6 +18,7 @@ use super::types::{
 // Tauri Commands
 // ============================================================================
 
+#[cfg(feature = "desktop")]
 #[tauri::command]
 pub async fn git_is_repo(path: String) -> Result<IsRepoResponse, String> {
     tokio::task::spawn_blocking(move || {
@@ -24,6 +29,7 @@ pub async fn git_is_repo(path: String) -> Result<IsRepoResponse, String> {
     .map_err(|e| format!("Task join error: {}", e))?
 }
 
+#[cfg(feature = "desktop")]
 #[tauri::command]
 pub async fn git_init(path: String, default_branch: Option<String>) -> Result<(), String> {
     tokio::task::spawn_blocking(move || {
@@ -53,6 +59,7 @@ pub async fn git_init(path: String, default_branch: Option<String>) -> Result<()
     .map_err(|e| format!("Task join error: {}", e))?
 }
 
+#[cfg(feature = "desktop")]
 #[tauri::command]
 pub async fn git_root(path: String) -> Result<RootResponse, String> {
     tokio::task::spawn_blocking(move || match git2::Repository::discover(&path) {
@@ -66,8 +73,20 @@ pub async fn git_root(path: String) -> Result<RootResponse, String> {
     .map_err(|e| format!("Task join error: {}", e))?
 }
 
+#[cfg(feature = "desktop")]
 #[tauri::command]
-pub async fn git_status(path: String) -> Result<StatusResponse, String> {
+pub async fn git_status(app: AppHandle, path: String) -> Result<StatusResponse, String> {
+    let mount = app
+        .state::<crate::remote::agent::RemoteAgentsState>()
+        .mount_for(&path);
+    if let Some(mount) = mount {
+        return mount.git_status(&path).await;
+    }
+    git_status_local(path).await
+}
+
+/// Status of a repository on this machine, as served by the remote agent
+pub async fn git_status_local(path: String) -> Result<StatusResponse, String> {
     // Run the blocking git operations in a separate thread to avoid blocking the main thread
     tokio::task::spawn_blocking(move || git_status_sync(&path))
         .await
@@ -108,7 +127,21 @@ pub fn git_status_sync(path: &str) -> Result<StatusResponse, String> {
         .statuses(Some(&mut opts))
         .map_err(|e| format!("Failed to get status: {}", e))?;
 
+    // libgit2 ignores skip-worktree, so paths outside the sparse-checkout set
+    // would otherwise show up as deleted.
+    let sparse_excluded = if super::sparse::is_sparse_enabled(&repo) {
+        super::sparse::skip_worktree_paths(&repo)
+    } else {
+        HashSet::new()
+    };
+
     for entry in statuses.iter() {
+        if entry.status() == git2::Status::WT_DELETED
+            && entry.path().is_some_and(|p| sparse_excluded.contains(p))
+        {
+            continue;
+        }
+
         total_files += 1;
 
         // Limit total files processed for very large repos
@@ -225,6 +258,7 @@ fn get_ahead_behind(repo: &git2::Repository) -> Result<(u32, u32), git2::Error>
     Ok((0, 0))
 }
 
+#[cfg(feature = "desktop")]
 #[tauri::command]
 pub async fn git_branches(path: String) -> Result<BranchesResponse, String> {
     tokio::task::spawn_blocking(move || git_branches_sync(&path))
@@ -312,6 +346,7 @@ fn git_branches_sync(path: &str) -> Result<BranchesResponse, String> {
     Ok(BranchesResponse { branches })
 }
 
+#[cfg(feature = "desktop")]
 #[tauri::command]
 pub async fn git_remotes(path: String) -> Result<RemotesResponse, String> {
     tokio::task::spawn_blocking(move || git_remotes_sync(&path))
@@ -342,6 +377,7 @@ fn git_remotes_sync(path: &str) -> Result<RemotesResponse, String> {
 }
 
 /// Get the primary remote URL (origin or first remote)
+#[cfg(feature = "desktop")]
 #[tauri::command]
 pub async fn git_remote(path: String) -> Result<GitRemoteResponse, String> {
     tokio::task::spawn_blocking(move || {
@@ -376,6 +412,7 @@ pub async fn git_remote(path: String) -> Result<GitRemoteResponse, String> {
 }
 
 /// Get current branch name
+#[cfg(feature = "desktop")]
 #[tauri::command]
 pub async fn git_branch(path: String) -> Result<GitBranchResponse, String> {
     tokio::task::spawn_blocking(move || {
@@ -416,6 +453,7 @@ pub async fn git_branch(path: String) -> Result<GitBranchResponse, String> {
 }
 
 /// Get current HEAD commit SHA
+#[cfg(feature = "desktop")]
 #[tauri::command]
 pub async fn git_head(path: String) -> Result<GitHeadResponse, String> {
     tokio::task::spawn_blocking(move || {
@@ -63,7 +63,7 @@ pub struct RootResponse {
     pub root: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StatusResponse {
     pub branch: String,
     pub staged: Vec<GitFile>,
@@ -327,6 +327,48 @@ pub struct CloneProgress {
     pub message: Option<String>,
 }
 
+/// Options for partial, shallow and sparse clones.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CloneOptions {
+    /// Clone submodules recursively.
+    pub recursive: bool,
+    /// Partial-clone filter: `blob:none`, `tree:0` or `blob:limit=<size>`.
+    pub filter: Option<String>,
+    /// Create a shallow clone with this many commits.
+    pub depth: Option<u32>,
+    /// Create a shallow clone with history after this date.
+    pub shallow_since: Option<String>,
+    /// Branch to check out instead of the remote HEAD.
+    pub branch: Option<String>,
+    /// Only fetch the history of the checked-out branch.
+    pub single_branch: bool,
+    /// Cone-mode sparse-checkout directories. When set, only these directories
+    /// (plus top-level files) are checked out.
+    pub sparse_patterns: Option<Vec<String>>,
+}
+
+// ============================================================================
+// Sparse Checkout Types
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SparseCheckoutStatus {
+    pub enabled: bool,
+    pub cone: bool,
+    pub patterns: Vec<String>,
+}
+
+/// A tracked path that is not materialized because it is outside the sparse set.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SparseExcludedEntry {
+    pub path: String,
+    pub name: String,
+    pub is_dir: bool,
+}
+
 // ============================================================================
 // Merge Types
 // ============================================================================
@@ -474,6 +516,110 @@ pub struct StructuredDiff {
     pub deletions: u32,
 }
 
+// ============================================================================
+// History Search Types
+// ============================================================================
+
+/// Pickaxe search mode: `-S` matches changes in the number of occurrences of
+/// a string, `-G` matches added or removed lines against a regex.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PickaxeMode {
+    String,
+    Regex,
+}
+
+/// A commit found by a pickaxe search, emitted as it is found.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PickaxeMatch {
+    pub search_id: String,
+    pub commit: GitCommit,
+    pub files: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PickaxeSummary {
+    pub search_id: String,
+    pub total_matches: u32,
+    pub cancelled: bool,
+    pub truncated: bool,
+}
+
+/// One commit in the history of a line range, with its diff limited to that range.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LineHistoryEntry {
+    pub commit: GitCommit,
+    /// Path of the file in this commit (differs from the requested path
+    /// before a rename).
+    pub file_path: String,
+    pub hunks: Vec<DiffHunkData>,
+}
+
+/// One commit in the rename-following history of a file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileHistoryEntry {
+    pub commit: GitCommit,
+    pub file_path: String,
+    /// Previous path when this commit renamed or copied the file.
+    pub old_path: Option<String>,
+    /// Single-letter status from `--name-status` (A, M, D, R, C, T).
+    pub status: String,
+}
+
+// ============================================================================
+// Hook Types
+// ============================================================================
+
+/// Where a hook was discovered.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum GitHookSource {
+    /// Executable in `.git/hooks`.
+    HooksDir,
+    /// Executable in the directory named by `core.hooksPath`.
+    HooksPath,
+    /// Script in `.husky/` that is not installed through `core.hooksPath`.
+    Husky,
+    /// Hook declared in `lefthook.yml`, run through `lefthook run`.
+    Lefthook,
+    /// `.pre-commit-config.yaml`, run through `pre-commit run`.
+    PreCommit,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitHookInfo {
+    pub name: String,
+    pub source: GitHookSource,
+    /// Script path, or the framework config file for framework hooks.
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitHooksInfo {
+    /// Effective hooks directory (`core.hooksPath` or `.git/hooks`).
+    pub hooks_dir: String,
+    pub hooks: Vec<GitHookInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitHookRunResult {
+    pub hook: String,
+    pub source: GitHookSource,
+    pub success: bool,
+    pub exit_code: Option<i32>,
+    /// Last lines of combined stdout/stderr.
+    pub output: String,
+    pub duration_ms: u64,
+    pub diagnostics_count: usize,
+}
+
 // ============================================================================
 // Word Diff Types
 // ============================================================================
@@ -856,6 +1002,49 @@ pub struct ThreeWayDiffResult {
     pub raw_content: String,
 }
 
+/// How a conflict region was resolved without user interaction.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ConflictResolutionStrategy {
+    /// Both sides made the same edit.
+    IdenticalChanges,
+    /// The sides differ only in whitespace.
+    WhitespaceOnly,
+    /// Both sides added different entries to an import list.
+    ImportUnion,
+    /// A resolution previously recorded for the same conflict was reused.
+    Recorded,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AutoResolvedConflict {
+    pub conflict_id: String,
+    pub strategy: ConflictResolutionStrategy,
+    pub resolved_lines: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AutoResolveResult {
+    pub file_path: String,
+    pub resolved: Vec<AutoResolvedConflict>,
+    pub remaining_conflicts: u32,
+    /// Whether the file was fully resolved and staged.
+    pub staged: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AiConflictProposal {
+    pub conflict_id: String,
+    pub resolved_lines: Vec<String>,
+    pub explanation: String,
+    /// Full file content with only this conflict replaced, suitable for
+    /// `git_resolve_conflict` once every region has been handled.
+    pub proposed_content: String,
+}
+
 #[cfg(test)]
 #[allow(clippy::unwrap_used, clippy::expect_used)]
 mod tests {
@@ -1,66 +1,112 @@
 #![allow(dead_code)]
+// The headless agent build compiles only part of the modules it shares
+#![cfg_attr(not(feature = "desktop"), allow(unused_imports))]
 //! Cortex Desktop - Tauri application backend
 //!
 //! This module provides the Rust backend for the Cortex Desktop application.
 //! Command registration is split into feature-grouped modules under `app/`.
 
+#[cfg(feature = "desktop")]
 mod acp;
+#[cfg(feature = "desktop")]
 mod action_log;
+#[cfg(feature = "desktop")]
 mod activity;
+#[cfg(feature = "desktop")]
 mod ai;
+#[cfg(feature = "desktop")]
 mod app;
+#[cfg(feature = "desktop")]
 mod auto_update;
+#[cfg(feature = "desktop")]
 mod batch;
+#[cfg(feature = "desktop")]
 mod batch_ipc;
+#[cfg(feature = "desktop")]
 mod browser;
+#[cfg(feature = "desktop")]
 mod collab;
+#[cfg(feature = "desktop")]
 mod context_server;
+#[cfg(feature = "desktop")]
 mod cortex_engine;
+#[cfg(feature = "desktop")]
 mod cortex_protocol;
+#[cfg(feature = "desktop")]
 mod cortex_storage;
+#[cfg(feature = "desktop")]
 mod dap;
+#[cfg(feature = "desktop")]
 mod deep_link;
+#[cfg(feature = "desktop")]
 mod diagnostics;
+#[cfg(feature = "desktop")]
 mod editor;
 pub mod error;
+#[cfg(feature = "desktop")]
 mod extensions;
+#[cfg(feature = "desktop")]
 mod factory;
+#[cfg(feature = "desktop")]
 mod formatter;
 mod fs;
 mod git;
+#[cfg(feature = "desktop")]
 mod i18n;
+#[cfg(feature = "desktop")]
 mod keybindings;
+#[cfg(feature = "desktop")]
 mod language_selector;
+#[cfg(feature = "desktop")]
 mod lsp;
+#[cfg(feature = "desktop")]
 mod mcp;
+#[cfg(feature = "desktop")]
 mod notebook;
+#[cfg(feature = "desktop")]
+mod output_channels;
+#[cfg(feature = "desktop")]
 mod process;
 mod process_utils;
+#[cfg(feature = "desktop")]
 mod prompt_store;
 mod remote;
+#[cfg(feature = "desktop")]
 mod repl;
+#[cfg(feature = "desktop")]
 mod rules_library;
+#[cfg(feature = "desktop")]
 mod sandbox;
+#[cfg(feature = "desktop")]
 mod search;
+#[cfg(feature = "desktop")]
 mod settings;
+#[cfg(feature = "desktop")]
 mod settings_sync;
-#[cfg(feature = "remote-ssh")]
+#[cfg(all(feature = "desktop", feature = "remote-ssh"))]
 mod ssh_terminal;
+#[cfg(feature = "desktop")]
 mod system_specs;
 mod tasks;
 mod terminal;
+#[cfg(feature = "desktop")]
 mod testing;
+#[cfg(feature = "desktop")]
 mod themes;
+#[cfg(feature = "desktop")]
 mod timeline;
+#[cfg(feature = "desktop")]
 mod toolchain;
+#[cfg(feature = "desktop")]
 mod window;
+#[cfg(feature = "desktop")]
 mod workspace;
+#[cfg(feature = "desktop")]
 mod workspace_settings;
+#[cfg(feature = "desktop")]
 mod wsl;
 
-use std::sync::{Arc, OnceLock};
-
-use tracing::info;
+use std::sync::OnceLock;
 
 pub use error::CortexError;
 
@@ -98,11 +144,25 @@ impl<T: Clone> Clone for LazyState<T> {
     }
 }
 
+/// Entry point of the headless `cortex-server` binary
+///
+/// Returns `false` when it was not started as the remote agent.
+pub fn run_remote_agent() -> bool {
+    remote::agent::run_from_args()
+}
+
+#[cfg(feature = "desktop")]
 #[allow(clippy::expect_used)]
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     use tauri::{Manager, WindowEvent};
 
+    // The same executable doubles as the persistent terminal host
+    #[cfg(unix)]
+    if terminal::pty_host::run_from_args() {
+        return;
+    }
+
     tracing_subscriber::fmt()
         .with_env_filter(
             tracing_subscriber::EnvFilter::from_default_env()
@@ -111,7 +171,7 @@ pub fn run() {
         .init();
 
     let _startup_span = tracing::info_span!("startup").entered();
-    info!("Starting Cortex Desktop with optimized startup...");
+    tracing::info!("Starting Cortex Desktop with optimized startup...");
     let startup_time = std::time::Instant::now();
 
     let builder = tauri::Builder::default()
@@ -139,7 +199,7 @@ pub fn run() {
         .plugin(tauri_plugin_updater::Builder::new().build())
         .plugin(tauri_plugin_deep_link::init());
 
-    let remote_manager = Arc::new(remote::RemoteManager::new());
+    let remote_manager = std::sync::Arc::new(remote::RemoteManager::new());
 
     let builder = app::register_state(builder, remote_manager);
 
@@ -3,22 +3,25 @@
 //! This module contains the main LspClient struct and its core functionality
 //! for process management, message passing, and basic operations.
 
-use std::collections::HashMap;
-use std::io::{BufRead, BufReader, Write};
-use std::process::{Child, Stdio};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::{BufRead, BufReader, Read, Write};
 use std::sync::Arc;
-use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::atomic::{AtomicI32, AtomicU32, Ordering};
 use std::thread;
 
-use anyhow::{Context, Result, anyhow};
+use anyhow::{Result, anyhow};
 use parking_lot::Mutex;
 use serde::{Serialize, de::DeserializeOwned};
 use serde_json::{Value, json};
-use tokio::sync::{mpsc, oneshot};
+use tokio::sync::{mpsc, oneshot, watch};
 use tracing::{debug, error, info, warn};
 
 use super::conversions::*;
+use super::health::{self, ServerFault};
 use super::protocol_types::*;
+use super::server_requests::{RequestHandler, RequestResult, ServerRequestContext};
+use super::trace::{self, TraceDirection, TraceWriter};
+use super::transport::{ServerProcess, ServerTransport, UriMap};
 use crate::lsp::types::*;
 
 const JSON_RPC_VERSION: &str = "2.0";
@@ -36,11 +39,16 @@ pub(super) enum OutgoingMessage {
         method: String,
         params: Value,
     },
+    /// Reply to a request initiated by the server
+    Response {
+        id: Value,
+        result: RequestResult,
+    },
     Shutdown,
 }
 
 /// Handler for incoming notifications
-type NotificationHandler = Box<dyn Fn(String, Value) + Send + Sync>;
+pub(super) type NotificationHandler = Box<dyn Fn(String, Value) + Send + Sync>;
 
 /// LSP Client for communicating with a language server
 pub struct LspClient {
@@ -53,8 +61,26 @@ pub struct LspClient {
     pub(super) next_request_id: AtomicI32,
     pub(super) outgoing_tx: mpsc::UnboundedSender<OutgoingMessage>,
     pub(super) pending_requests: Arc<Mutex<HashMap<i32, oneshot::Sender<Result<Value>>>>>,
-    notification_handlers: Arc<Mutex<HashMap<String, NotificationHandler>>>,
-    pub(super) process: Arc<Mutex<Option<Child>>>,
+    pub(super) notification_handlers: Arc<Mutex<HashMap<String, NotificationHandler>>>,
+    pub(super) request_handlers: Arc<Mutex<HashMap<String, RequestHandler>>>,
+    pub(super) registrations: Arc<Mutex<Vec<DynamicRegistration>>>,
+    /// Static `diagnosticProvider` options from the initialize result
+    pub(super) diagnostic_provider: Arc<Mutex<Option<Value>>>,
+    /// Map: document uri -> last pull diagnostics result id
+    pub(super) diagnostic_result_ids: Arc<Mutex<HashMap<String, String>>>,
+    /// Documents pulled through textDocument/diagnostic, re-pulled on refresh
+    pub(super) pulled_documents: Arc<Mutex<HashSet<String>>>,
+    pub(super) process: Arc<Mutex<Option<Box<dyn ServerProcess>>>>,
+    /// Set once the server exits or stops answering requests
+    pub(super) fault_tx: Arc<watch::Sender<Option<ServerFault>>>,
+    /// Requests that timed out since the last answered one
+    pub(super) consecutive_timeouts: AtomicU32,
+    /// Last lines the server wrote to stderr
+    pub(super) stderr_tail: Arc<Mutex<VecDeque<String>>>,
+    /// Map: document uri -> latest didOpen/didChange contents
+    pub(super) open_documents: Arc<Mutex<HashMap<String, DidOpenParams>>>,
+    /// JSON-RPC trace of the traffic, when enabled
+    pub(super) trace: Arc<Mutex<Option<TraceWriter>>>,
     pub(super) diagnostics_tx: Option<mpsc::UnboundedSender<DiagnosticsEvent>>,
 }
 
@@ -63,40 +89,38 @@ impl LspClient {
     pub fn new(
         config: LanguageServerConfig,
         diagnostics_tx: Option<mpsc::UnboundedSender<DiagnosticsEvent>>,
+    ) -> Result<Self> {
+        let transport = ServerTransport::spawn_local(&config)?;
+        Self::with_transport(config, transport, diagnostics_tx)
+    }
+
+    /// Create a client for a server reached through `transport`
+    pub fn with_transport(
+        config: LanguageServerConfig,
+        transport: ServerTransport,
+        diagnostics_tx: Option<mpsc::UnboundedSender<DiagnosticsEvent>>,
     ) -> Result<Self> {
         let id = config.id.clone();
         let name = config.name.clone();
-
-        info!("Starting language server: {} ({})", name, config.command);
-
-        // Spawn the language server process
-        let mut process = crate::process_utils::command(&config.command)
-            .args(&config.args)
-            .current_dir(&config.root_path)
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()
-            .with_context(|| format!("Failed to spawn language server: {}", config.command))?;
-
-        let stdin = process
-            .stdin
-            .take()
-            .ok_or_else(|| anyhow!("Failed to open stdin"))?;
-        let stdout = process
-            .stdout
-            .take()
-            .ok_or_else(|| anyhow!("Failed to open stdout"))?;
-        let stderr = process
-            .stderr
-            .take()
-            .ok_or_else(|| anyhow!("Failed to open stderr"))?;
+        let ServerTransport {
+            stdin,
+            stdout,
+            stderr,
+            process,
+            uri_map,
+        } = transport;
 
         let (outgoing_tx, outgoing_rx) = mpsc::unbounded_channel();
         let pending_requests: Arc<Mutex<HashMap<i32, oneshot::Sender<Result<Value>>>>> =
             Arc::new(Mutex::new(HashMap::new()));
         let notification_handlers: Arc<Mutex<HashMap<String, NotificationHandler>>> =
             Arc::new(Mutex::new(HashMap::new()));
+        let request_context = ServerRequestContext {
+            server_id: id.clone(),
+            outgoing_tx: outgoing_tx.clone(),
+            handlers: Arc::new(Mutex::new(HashMap::new())),
+            registrations: Arc::new(Mutex::new(Vec::new())),
+        };
 
         let client = Self {
             id: id.clone(),
@@ -108,29 +132,78 @@ impl LspClient {
             outgoing_tx,
             pending_requests: pending_requests.clone(),
             notification_handlers: notification_handlers.clone(),
+            request_handlers: request_context.handlers.clone(),
+            registrations: request_context.registrations.clone(),
+            diagnostic_provider: Arc::new(Mutex::new(None)),
+            diagnostic_result_ids: Arc::new(Mutex::new(HashMap::new())),
+            pulled_documents: Arc::new(Mutex::new(HashSet::new())),
             process: Arc::new(Mutex::new(Some(process))),
+            fault_tx: Arc::new(watch::channel(None).0),
+            consecutive_timeouts: AtomicU32::new(0),
+            stderr_tail: Arc::new(Mutex::new(VecDeque::new())),
+            open_documents: Arc::new(Mutex::new(HashMap::new())),
+            trace: Arc::new(Mutex::new(None)),
             diagnostics_tx,
         };
 
+        if client.config.trace {
+            let started = trace::default_trace_path(&id).and_then(|path| client.start_trace(&path));
+            if let Err(e) = started {
+                warn!("Failed to start LSP trace for {}: {}", name, e);
+            }
+        }
+
         // Start the writer thread
         let writer_pending = pending_requests.clone();
+        let writer_uri_map = uri_map.clone();
+        let writer_trace = client.trace.clone();
         thread::spawn(move || {
-            Self::writer_thread(stdin, outgoing_rx, writer_pending);
+            Self::writer_thread(
+                stdin,
+                outgoing_rx,
+                writer_pending,
+                writer_uri_map,
+                writer_trace,
+            );
         });
 
-        // Start the reader thread
+        // Start the reader thread; stdout closing means the server is gone
         let reader_pending = pending_requests;
         let reader_handlers = notification_handlers;
         let server_id = id.clone();
         let diag_tx = client.diagnostics_tx.clone();
+        let exit_status = client.status.clone();
+        let exit_fault = client.fault_tx.clone();
+        let reader_trace = client.trace.clone();
         thread::spawn(move || {
-            Self::reader_thread(stdout, reader_pending, reader_handlers, server_id, diag_tx);
+            Self::reader_thread(
+                stdout,
+                reader_pending.clone(),
+                reader_handlers,
+                request_context,
+                server_id,
+                diag_tx,
+                uri_map,
+                reader_trace,
+            );
+
+            for (_, tx) in reader_pending.lock().drain() {
+                let _ = tx.send(Err(anyhow!("Language server exited")));
+            }
+            {
+                let mut status = exit_status.lock();
+                if *status != ServerStatus::Stopped {
+                    *status = ServerStatus::Error;
+                }
+            }
+            health::report_fault(&exit_fault, ServerFault::Exited);
         });
 
         // Start stderr reader thread
         let server_name = name;
+        let stderr_tail = client.stderr_tail.clone();
         thread::spawn(move || {
-            Self::stderr_thread(stderr, server_name);
+            Self::stderr_thread(stderr, server_name, stderr_tail);
         });
 
         Ok(client)
@@ -138,10 +211,20 @@ impl LspClient {
 
     /// Writer thread that sends messages to the language server
     fn writer_thread(
-        mut stdin: std::process::ChildStdin,
+        mut stdin: Box<dyn Write + Send>,
         mut rx: mpsc::UnboundedReceiver<OutgoingMessage>,
         pending: Arc<Mutex<HashMap<i32, oneshot::Sender<Result<Value>>>>>,
+        uri_map: Option<UriMap>,
+        trace: Arc<Mutex<Option<TraceWriter>>>,
     ) {
+        let write = |stdin: &mut Box<dyn Write + Send>, mut message: Value| {
+            if let Some(map) = &uri_map {
+                map.to_remote(&mut message);
+            }
+            trace::record(&trace, TraceDirection::Send, &message);
+            Self::write_message(stdin, &message)
+        };
+
         while let Some(msg) = rx.blocking_recv() {
             match msg {
                 OutgoingMessage::Request {
@@ -159,7 +242,7 @@ impl LspClient {
 
                     pending.lock().insert(id, response_tx);
 
-                    if let Err(e) = Self::write_message(&mut stdin, &request) {
+                    if let Err(e) = write(&mut stdin, request) {
                         error!("Failed to write request: {}", e);
                         if let Some(tx) = pending.lock().remove(&id) {
                             let _ = tx.send(Err(anyhow!("Failed to send request: {}", e)));
@@ -173,10 +256,28 @@ impl LspClient {
                         "params": params,
                     });
 
-                    if let Err(e) = Self::write_message(&mut stdin, &notification) {
+                    if let Err(e) = write(&mut stdin, notification) {
                         error!("Failed to write notification: {}", e);
                     }
                 }
+                OutgoingMessage::Response { id, result } => {
+                    let response = match result {
+                        Ok(result) => json!({
+                            "jsonrpc": JSON_RPC_VERSION,
+                            "id": id,
+                            "result": result,
+                        }),
+                        Err(error) => json!({
+                            "jsonrpc": JSON_RPC_VERSION,
+                            "id": id,
+                            "error": error,
+                        }),
+                    };
+
+                    if let Err(e) = write(&mut stdin, response) {
+                        error!("Failed to write response: {}", e);
+                    }
+                }
                 OutgoingMessage::Shutdown => {
                     debug!("Writer thread shutting down");
                     break;
@@ -186,7 +287,7 @@ impl LspClient {
     }
 
     /// Write a JSON-RPC message to the output stream
-    fn write_message(writer: &mut impl Write, message: &Value) -> Result<()> {
+    pub(super) fn write_message(writer: &mut impl Write, message: &Value) -> Result<()> {
         let content = serde_json::to_string(message)?;
         let header = format!("{}{}\r\n\r\n", CONTENT_LENGTH_HEADER, content.len());
 
@@ -200,12 +301,16 @@ impl LspClient {
     }
 
     /// Reader thread that receives messages from the language server
+    #[allow(clippy::too_many_arguments)]
     fn reader_thread(
-        stdout: std::process::ChildStdout,
+        stdout: Box<dyn Read + Send>,
         pending: Arc<Mutex<HashMap<i32, oneshot::Sender<Result<Value>>>>>,
         handlers: Arc<Mutex<HashMap<String, NotificationHandler>>>,
+        requests: ServerRequestContext,
         server_id: String,
         diagnostics_tx: Option<mpsc::UnboundedSender<DiagnosticsEvent>>,
+        uri_map: Option<UriMap>,
+        trace: Arc<Mutex<Option<TraceWriter>>>,
     ) {
         let mut reader = BufReader::new(stdout);
         let mut header_buf = String::new();
@@ -244,16 +349,27 @@ impl LspClient {
             debug!("Received LSP message: {}", content_str);
 
             // Parse the message
-            let message: Value = match serde_json::from_str(&content_str) {
+            let mut message: Value = match serde_json::from_str(&content_str) {
                 Ok(v) => v,
                 Err(e) => {
                     error!("Failed to parse LSP message: {}", e);
                     continue;
                 }
             };
+            trace::record(&trace, TraceDirection::Receive, &message);
+            if let Some(map) = &uri_map {
+                map.to_local(&mut message);
+            }
 
             // Handle the message
-            if let Some(id) = message.get("id") {
+            if let (Some(id), Some(method)) = (
+                message.get("id"),
+                message.get("method").and_then(|m| m.as_str()),
+            ) {
+                // This is a request from the server
+                let params = message.get("params").cloned().unwrap_or(Value::Null);
+                requests.dispatch(id.clone(), method, params);
+            } else if let Some(id) = message.get("id") {
                 // This is a response
                 if let Some(id) = id.as_i64() {
                     if let Some(tx) = pending.lock().remove(&(id as i32)) {
@@ -302,10 +418,7 @@ impl LspClient {
     }
 
     /// Read LSP message headers and return the content length
-    fn read_headers(
-        reader: &mut BufReader<std::process::ChildStdout>,
-        buf: &mut String,
-    ) -> Result<usize> {
+    pub(super) fn read_headers(reader: &mut impl BufRead, buf: &mut String) -> Result<usize> {
         let mut content_length: Option<usize> = None;
 
         loop {
@@ -329,12 +442,17 @@ impl LspClient {
     }
 
     /// Stderr reader thread
-    fn stderr_thread(stderr: std::process::ChildStderr, server_name: String) {
+    fn stderr_thread(
+        stderr: Box<dyn Read + Send>,
+        server_name: String,
+        tail: Arc<Mutex<VecDeque<String>>>,
+    ) {
         let reader = BufReader::new(stderr);
         for line in reader.lines() {
             match line {
                 Ok(line) => {
                     warn!("[{}] stderr: {}", server_name, line);
+                    health::push_stderr_line(&mut tail.lock(), line);
                 }
                 Err(e) => {
                     debug!("[{}] stderr closed: {}", server_name, e);
@@ -364,9 +482,25 @@ impl LspClient {
             })
             .map_err(|_| anyhow!("Failed to send request"))?;
 
-        let result = response_rx
-            .await
-            .map_err(|_| anyhow!("Request cancelled"))??;
+        let response = match health::request_timeout(method) {
+            Some(limit) => match tokio::time::timeout(limit, response_rx).await {
+                Ok(response) => response,
+                Err(_) => {
+                    self.pending_requests.lock().remove(&id);
+                    let _ = self.notify("$/cancelRequest", json!({ "id": id }));
+                    self.record_timeout(method);
+                    return Err(anyhow!(
+                        "Request {} timed out after {}s",
+                        method,
+                        limit.as_secs()
+                    ));
+                }
+            },
+            None => response_rx.await,
+        };
+        self.consecutive_timeouts.store(0, Ordering::SeqCst);
+
+        let result = response.map_err(|_| anyhow!("Request cancelled"))??;
         let response: R = serde_json::from_value(result)?;
         Ok(response)
     }
@@ -388,12 +522,40 @@ impl LspClient {
     /// Initialize the language server
     pub async fn initialize(&self) -> Result<()> {
         let params = InitializeParams {
-            process_id: Some(std::process::id() as i32),
+            // A remote server cannot watch a local process id
+            process_id: self
+                .config
+                .remote
+                .is_none()
+                .then(|| std::process::id() as i32),
             root_uri: Some(format!(
                 "file://{}",
                 self.config.root_path.replace('\\', "/")
             )),
-            capabilities: ClientCapabilities::default(),
+            capabilities: ClientCapabilities {
+                text_document: Some(TextDocumentClientCapabilities {
+                    diagnostic: Some(DiagnosticClientCapabilities {
+                        dynamic_registration: Some(true),
+                        related_document_support: Some(true),
+                    }),
+                    ..Default::default()
+                }),
+                workspace: Some(WorkspaceClientCapabilities {
+                    apply_edit: Some(true),
+                    configuration: Some(true),
+                    diagnostics: Some(RefreshClientCapabilities {
+                        refresh_support: Some(true),
+                    }),
+                    did_change_watched_files: Some(DidChangeWatchedFilesClientCapabilities {
+                        dynamic_registration: Some(true),
+                        relative_pattern_support: Some(true),
+                    }),
+                }),
+                window: Some(WindowClientCapabilities {
+                    work_done_progress: Some(true),
+                }),
+                ..Default::default()
+            },
         };
 
         let result: InitializeResult = self.request("initialize", params).await?;
@@ -401,6 +563,7 @@ impl LspClient {
         // Store capabilities
         let caps = convert_server_capabilities(&result.capabilities);
         *self.capabilities.lock() = Some(caps);
+        *self.diagnostic_provider.lock() = result.capabilities.diagnostic_provider.clone();
         *self.status.lock() = ServerStatus::Running;
 
         // Send initialized notification
@@ -425,7 +588,7 @@ impl LspClient {
 
         // Kill the process
         if let Some(mut process) = self.process.lock().take() {
-            let _ = process.kill();
+            process.kill();
         }
 
         info!("Language server {} shut down", self.name);
@@ -467,7 +630,7 @@ impl Drop for LspClient {
     fn drop(&mut self) {
         let _ = self.outgoing_tx.send(OutgoingMessage::Shutdown);
         if let Some(mut process) = self.process.lock().take() {
-            let _ = process.kill();
+            process.kill();
         }
     }
 }
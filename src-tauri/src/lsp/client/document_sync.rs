@@ -11,6 +11,9 @@ use crate::lsp::types::*;
 impl LspClient {
     /// Notify that a document was opened
     pub fn did_open(&self, params: DidOpenParams) -> Result<()> {
+        self.open_documents
+            .lock()
+            .insert(params.uri.clone(), params.clone());
         self.notify(
             "textDocument/didOpen",
             json!({
@@ -26,6 +29,10 @@ impl LspClient {
 
     /// Notify that a document was changed
     pub fn did_change(&self, params: DidChangeParams) -> Result<()> {
+        if let Some(document) = self.open_documents.lock().get_mut(&params.uri) {
+            document.version = params.version;
+            document.text = params.text.clone();
+        }
         self.notify(
             "textDocument/didChange",
             json!({
@@ -57,6 +64,7 @@ impl LspClient {
 
     /// Notify that a document was closed
     pub fn did_close(&self, params: DidCloseParams) -> Result<()> {
+        self.open_documents.lock().remove(&params.uri);
         self.notify(
             "textDocument/didClose",
             json!({
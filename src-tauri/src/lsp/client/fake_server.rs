@@ -0,0 +1,357 @@
+//! Fake language servers for client tests
+//!
+//! A `FakeServer` plays the server side of a trace. Every server message in
+//! the trace is attached to the client message it followed; when the fake
+//! receives that method again it sends the same messages back, with response
+//! ids rewritten to the live request. The client tests below run `LspClient`
+//! against fakes built from hand-written traces and from traces the client
+//! recorded itself.
+
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, BufReader};
+use std::net::Shutdown;
+use std::os::unix::net::UnixStream;
+use std::thread;
+
+use serde_json::Value;
+
+use super::replay::{read_message, write_message};
+use super::trace::{TraceDirection, TraceEntry};
+use super::transport::{ServerProcess, ServerTransport};
+
+/// Server messages sent in answer to one client message
+struct Reaction {
+    /// Id of the recorded request, replaced by the live one in responses
+    request_id: Option<Value>,
+    messages: Vec<Value>,
+}
+
+pub(super) struct FakeServer {
+    /// Map: client method -> reactions, in recorded order
+    reactions: HashMap<String, VecDeque<Reaction>>,
+    /// Close the connection after handling this method
+    exit_on: Option<String>,
+}
+
+impl FakeServer {
+    pub(super) fn from_entries(entries: &[TraceEntry]) -> Self {
+        let mut reactions: HashMap<String, VecDeque<Reaction>> = HashMap::new();
+        let mut current: Option<String> = None;
+
+        for entry in entries {
+            let message = &entry.message;
+            match entry.direction {
+                TraceDirection::Send => {
+                    if let Some(method) = message.get("method").and_then(|m| m.as_str()) {
+                        reactions
+                            .entry(method.to_string())
+                            .or_default()
+                            .push_back(Reaction {
+                                request_id: message.get("id").cloned(),
+                                messages: Vec::new(),
+                            });
+                        current = Some(method.to_string());
+                    }
+                }
+                TraceDirection::Receive => {
+                    if let Some(reaction) = current
+                        .as_ref()
+                        .and_then(|method| reactions.get_mut(method))
+                        .and_then(|queue| queue.back_mut())
+                    {
+                        reaction.messages.push(message.clone());
+                    }
+                }
+            }
+        }
+
+        Self {
+            reactions,
+            exit_on: None,
+        }
+    }
+
+    /// Build from `(direction, message)` pairs
+    pub(super) fn from_messages(messages: Vec<(TraceDirection, Value)>) -> Self {
+        let entries: Vec<_> = messages
+            .into_iter()
+            .map(|(direction, message)| TraceEntry {
+                time: chrono::Utc::now(),
+                direction,
+                message,
+            })
+            .collect();
+        Self::from_entries(&entries)
+    }
+
+    /// Simulate a crash after `method` is received
+    pub(super) fn exit_on(mut self, method: &str) -> Self {
+        self.exit_on = Some(method.to_string());
+        self
+    }
+
+    fn serve(mut self, stream: UnixStream) -> io::Result<()> {
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut writer = stream;
+
+        while let Ok(message) = read_message(&mut reader) {
+            let Some(method) = message.get("method").and_then(|m| m.as_str()) else {
+                continue;
+            };
+            if let Some(reaction) = self
+                .reactions
+                .get_mut(method)
+                .and_then(|queue| queue.pop_front())
+            {
+                for mut reply in reaction.messages {
+                    let answers_request = reply.get("method").is_none()
+                        && reaction.request_id.is_some()
+                        && reply.get("id") == reaction.request_id.as_ref();
+                    if answers_request {
+                        reply["id"] = message["id"].clone();
+                    }
+                    if write_message(&mut writer, &reply).is_err() {
+                        return Ok(());
+                    }
+                }
+            }
+            if method == "exit" || self.exit_on.as_deref() == Some(method) {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Start serving on a background thread
+    pub(super) fn spawn(self) -> ServerTransport {
+        let (client, server) = UnixStream::pair().expect("socket pair");
+        thread::spawn(move || self.serve(server));
+        ServerTransport::new(
+            Box::new(client.try_clone().expect("clone socket")),
+            Box::new(client.try_clone().expect("clone socket")),
+            Box::new(io::empty()),
+            Box::new(FakeProcess(client)),
+        )
+    }
+}
+
+struct FakeProcess(UnixStream);
+
+impl ServerProcess for FakeProcess {
+    fn kill(&mut self) {
+        let _ = self.0.shutdown(Shutdown::Both);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use serde_json::json;
+    use tokio::sync::mpsc;
+
+    use super::super::core::LspClient;
+    use super::super::health::ServerFault;
+    use super::super::replay::replay;
+    use super::super::trace::read_trace;
+    use super::*;
+    use crate::lsp::types::*;
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    fn config() -> LanguageServerConfig {
+        LanguageServerConfig {
+            id: "fake".to_string(),
+            name: "fake-server".to_string(),
+            command: "fake".to_string(),
+            args: Vec::new(),
+            root_path: "/work".to_string(),
+            file_extensions: vec!["rs".to_string()],
+            language_id: "rust".to_string(),
+            remote: None,
+            trace: false,
+        }
+    }
+
+    fn hover_params() -> TextDocumentPositionParams {
+        TextDocumentPositionParams {
+            uri: "/work/main.rs".to_string(),
+            position: Position {
+                line: 0,
+                character: 3,
+            },
+        }
+    }
+
+    fn open_params() -> DidOpenParams {
+        DidOpenParams {
+            uri: "/work/main.rs".to_string(),
+            language_id: "rust".to_string(),
+            version: 1,
+            text: "fn main() {}".to_string(),
+        }
+    }
+
+    /// initialize, initialized and one hover answered with `hover_text`
+    fn hover_session(hover_text: &str) -> Vec<(TraceDirection, Value)> {
+        vec![
+            (
+                TraceDirection::Send,
+                json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {}}),
+            ),
+            (
+                TraceDirection::Receive,
+                json!({"jsonrpc": "2.0", "id": 1, "result": {"capabilities": {"hoverProvider": true}}}),
+            ),
+            (
+                TraceDirection::Send,
+                json!({"jsonrpc": "2.0", "method": "initialized", "params": {}}),
+            ),
+            (
+                TraceDirection::Send,
+                json!({"jsonrpc": "2.0", "id": 2, "method": "textDocument/hover", "params": {}}),
+            ),
+            (
+                TraceDirection::Receive,
+                json!({"jsonrpc": "2.0", "id": 2, "result": {"contents": hover_text}}),
+            ),
+        ]
+    }
+
+    #[tokio::test]
+    async fn initializes_and_answers_requests() {
+        let server = FakeServer::from_messages(hover_session("fn main()"));
+        let client = LspClient::with_transport(config(), server.spawn(), None).unwrap();
+
+        client.initialize().await.unwrap();
+        assert_eq!(client.status(), ServerStatus::Running);
+        assert!(client.capabilities().unwrap().hover);
+
+        let hover = client.hover(hover_params()).await.unwrap().unwrap();
+        assert_eq!(hover.contents, "fn main()");
+    }
+
+    #[tokio::test]
+    async fn forwards_published_diagnostics() {
+        let server = FakeServer::from_messages(vec![
+            (
+                TraceDirection::Send,
+                json!({"method": "textDocument/didOpen"}),
+            ),
+            (
+                TraceDirection::Receive,
+                json!({"jsonrpc": "2.0", "method": "textDocument/publishDiagnostics", "params": {
+                    "uri": "file:///work/main.rs",
+                    "diagnostics": [{
+                        "range": {"start": {"line": 0, "character": 0}, "end": {"line": 0, "character": 2}},
+                        "severity": 1,
+                        "message": "expected item"
+                    }]
+                }}),
+            ),
+        ]);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let client = LspClient::with_transport(config(), server.spawn(), Some(tx)).unwrap();
+
+        client.did_open(open_params()).unwrap();
+        let event = tokio::time::timeout(TIMEOUT, rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(event.server_id, "fake");
+        assert_eq!(event.diagnostics.len(), 1);
+        assert_eq!(event.diagnostics[0].message, "expected item");
+    }
+
+    #[tokio::test]
+    async fn reports_exit_and_fails_pending_requests() {
+        let server = FakeServer::from_messages(Vec::new()).exit_on("textDocument/hover");
+        let client = LspClient::with_transport(config(), server.spawn(), None).unwrap();
+
+        assert!(client.hover(hover_params()).await.is_err());
+        let fault = tokio::time::timeout(TIMEOUT, client.wait_for_fault())
+            .await
+            .unwrap();
+        assert_eq!(fault, ServerFault::Exited);
+        assert_eq!(client.status(), ServerStatus::Error);
+    }
+
+    #[tokio::test]
+    async fn restores_open_documents_on_a_new_server() {
+        let previous = LspClient::with_transport(
+            config(),
+            FakeServer::from_messages(Vec::new()).spawn(),
+            None,
+        )
+        .unwrap();
+        previous.did_open(open_params()).unwrap();
+        previous
+            .did_change(DidChangeParams {
+                uri: "/work/main.rs".to_string(),
+                version: 2,
+                text: "fn main() { }".to_string(),
+            })
+            .unwrap();
+        previous.kill();
+
+        let client = LspClient::with_transport(
+            config(),
+            FakeServer::from_messages(Vec::new()).spawn(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(client.restore_from(&previous).unwrap(), 1);
+        assert!(client.registrations().is_empty());
+        let documents = client.open_documents.lock();
+        assert_eq!(documents["/work/main.rs"].version, 2);
+        assert_eq!(documents["/work/main.rs"].text, "fn main() { }");
+    }
+
+    #[tokio::test]
+    async fn recorded_trace_replays_and_diffs() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("fake.jsonl");
+
+        let server = FakeServer::from_messages(hover_session("fn main()"));
+        let client = LspClient::with_transport(config(), server.spawn(), None).unwrap();
+        client.start_trace(&path).unwrap();
+        client.initialize().await.unwrap();
+        client.hover(hover_params()).await.unwrap();
+        client.stop_trace();
+
+        let entries = read_trace(&path).unwrap();
+        assert_eq!(entries.len(), 5);
+
+        let entries_for_replay = entries.clone();
+        let same = tokio::task::spawn_blocking(move || {
+            let transport = FakeServer::from_entries(&entries_for_replay).spawn();
+            replay(
+                transport.stdin,
+                transport.stdout,
+                &entries_for_replay,
+                TIMEOUT,
+            )
+        })
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(same.requests, 2);
+        assert_eq!(same.matched, 2);
+        assert!(same.mismatches.is_empty());
+
+        let changed = tokio::task::spawn_blocking(move || {
+            let transport = FakeServer::from_messages(hover_session("fn main() -> ()")).spawn();
+            replay(transport.stdin, transport.stdout, &entries, TIMEOUT)
+        })
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(changed.matched, 1);
+        assert_eq!(changed.mismatches.len(), 1);
+        assert_eq!(changed.mismatches[0].method, "textDocument/hover");
+        assert_eq!(
+            changed.mismatches[0].differences,
+            vec!["result/contents: expected \"fn main()\", got \"fn main() -> ()\""]
+        );
+    }
+}
@@ -0,0 +1,417 @@
+//! Watched files
+//!
+//! Servers register glob watchers for `workspace/didChangeWatchedFiles` through
+//! `client/registerCapability`. The watchers are compiled from the current
+//! registrations whenever a batch of file system changes is delivered, and only
+//! the changes matching a watcher (and its kind mask) are forwarded.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use glob::{MatchOptions, Pattern};
+use tracing::{debug, warn};
+
+use super::core::LspClient;
+use super::protocol_types::*;
+use crate::lsp::types::DynamicRegistration;
+
+const WATCHED_FILES_METHOD: &str = "workspace/didChangeWatchedFiles";
+
+/// Kind mask used when a watcher does not specify one
+const WATCH_ALL: u8 = 0b111;
+
+/// Type of a file change, as sent in `FileEvent.type`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileChangeType {
+    Created = 1,
+    Changed = 2,
+    Deleted = 3,
+}
+
+impl FileChangeType {
+    /// Bit in a watcher's kind mask that selects this change type
+    fn kind_bit(self) -> u8 {
+        match self {
+            Self::Created => 1,
+            Self::Changed => 2,
+            Self::Deleted => 4,
+        }
+    }
+}
+
+/// File changes collected over a debounce window
+///
+/// Repeated changes to the same path are coalesced so a server sees at most
+/// one event per file: a file created and deleted within the window is dropped,
+/// a file deleted and re-created is reported as changed.
+#[derive(Debug, Default)]
+pub struct FileChangeBatch {
+    changes: HashMap<PathBuf, FileChangeType>,
+    order: Vec<PathBuf>,
+}
+
+impl FileChangeBatch {
+    pub fn push(&mut self, path: PathBuf, change: FileChangeType) {
+        use FileChangeType::*;
+
+        let Some(previous) = self.changes.get(&path).copied() else {
+            self.order.push(path.clone());
+            self.changes.insert(path, change);
+            return;
+        };
+
+        let merged = match (previous, change) {
+            (Created, Deleted) => None,
+            (Created, _) => Some(Created),
+            (Deleted, Created) => Some(Changed),
+            (_, next) => Some(next),
+        };
+        match merged {
+            Some(merged) => {
+                self.changes.insert(path, merged);
+            }
+            None => {
+                self.changes.remove(&path);
+                self.order.retain(|p| p != &path);
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+
+    /// Changes in the order their paths were first seen
+    pub fn changes(&self) -> impl Iterator<Item = (&Path, FileChangeType)> {
+        self.order
+            .iter()
+            .filter_map(|p| self.changes.get(p).map(|c| (p.as_path(), *c)))
+    }
+}
+
+/// A registered watcher compiled to glob patterns
+#[derive(Debug)]
+struct CompiledWatcher {
+    /// Directory the patterns are relative to; `None` for absolute patterns
+    base: Option<PathBuf>,
+    patterns: Vec<Pattern>,
+    kind: u8,
+}
+
+impl CompiledWatcher {
+    fn matches(&self, path: &Path, change: FileChangeType) -> bool {
+        if self.kind & change.kind_bit() == 0 {
+            return false;
+        }
+
+        let candidate = match &self.base {
+            Some(base) => match path.strip_prefix(base) {
+                Ok(relative) => relative,
+                Err(_) => return false,
+            },
+            None => path,
+        };
+        let candidate = candidate.to_string_lossy().replace('\\', "/");
+
+        let options = MatchOptions {
+            case_sensitive: true,
+            require_literal_separator: true,
+            require_literal_leading_dot: false,
+        };
+        self.patterns
+            .iter()
+            .any(|p| p.matches_with(&candidate, options))
+    }
+}
+
+/// Expand `{a,b}` alternatives, which the glob crate does not support
+fn expand_braces(pattern: &str) -> Vec<String> {
+    let Some(open) = pattern.find('{') else {
+        return vec![pattern.to_string()];
+    };
+
+    let mut depth = 0;
+    let mut close = None;
+    let mut splits = Vec::new();
+    for (i, c) in pattern[open..].char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    close = Some(open + i);
+                    break;
+                }
+            }
+            ',' if depth == 1 => splits.push(open + i),
+            _ => {}
+        }
+    }
+    let Some(close) = close else {
+        return vec![pattern.to_string()];
+    };
+
+    let prefix = &pattern[..open];
+    let suffix = &pattern[close + 1..];
+    let mut bounds = vec![open];
+    bounds.extend(splits);
+    bounds.push(close);
+
+    bounds
+        .windows(2)
+        .flat_map(|w| {
+            let alternative = &pattern[w[0] + 1..w[1]];
+            expand_braces(&format!("{}{}{}", prefix, alternative, suffix))
+        })
+        .collect()
+}
+
+fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    match url::Url::parse(uri) {
+        Ok(url) => url.to_file_path().ok(),
+        Err(_) => Some(PathBuf::from(uri)),
+    }
+}
+
+fn compile_patterns(pattern: &str) -> Vec<Pattern> {
+    expand_braces(pattern)
+        .iter()
+        .filter_map(|p| match Pattern::new(p) {
+            Ok(compiled) => Some(compiled),
+            Err(e) => {
+                warn!("Ignoring invalid watcher glob {}: {}", p, e);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Compile the watchers of all `workspace/didChangeWatchedFiles` registrations
+///
+/// Plain patterns that are not absolute are matched relative to `root`.
+fn compile_watchers(root: &Path, registrations: &[DynamicRegistration]) -> Vec<CompiledWatcher> {
+    let mut compiled = Vec::new();
+
+    for registration in registrations {
+        if registration.method != WATCHED_FILES_METHOD {
+            continue;
+        }
+        let Some(options) = registration.register_options.clone() else {
+            continue;
+        };
+        let options: LspDidChangeWatchedFilesRegistrationOptions =
+            match serde_json::from_value(options) {
+                Ok(options) => options,
+                Err(e) => {
+                    warn!("Invalid watcher registration {}: {}", registration.id, e);
+                    continue;
+                }
+            };
+
+        for watcher in options.watchers {
+            let kind = watcher.kind.unwrap_or(WATCH_ALL);
+            let (base, pattern) = match watcher.glob_pattern {
+                LspGlobPattern::Pattern(pattern) => {
+                    let absolute = pattern.starts_with('/') || Path::new(&pattern).is_absolute();
+                    let base = (!absolute).then(|| root.to_path_buf());
+                    (base, pattern)
+                }
+                LspGlobPattern::Relative { base_uri, pattern } => {
+                    let uri = match base_uri {
+                        LspBaseUri::Uri(uri) | LspBaseUri::WorkspaceFolder { uri } => uri,
+                    };
+                    let Some(base) = uri_to_path(&uri) else {
+                        continue;
+                    };
+                    (Some(base), pattern)
+                }
+            };
+
+            let patterns = compile_patterns(&pattern.replace('\\', "/"));
+            if !patterns.is_empty() {
+                compiled.push(CompiledWatcher {
+                    base,
+                    patterns,
+                    kind,
+                });
+            }
+        }
+    }
+
+    compiled
+}
+
+/// Select the changes matched by any watcher, as LSP file events
+fn matching_events(watchers: &[CompiledWatcher], batch: &FileChangeBatch) -> Vec<LspFileEvent> {
+    batch
+        .changes()
+        .filter(|(path, change)| watchers.iter().any(|w| w.matches(path, *change)))
+        .map(|(path, change)| LspFileEvent {
+            uri: format!("file://{}", path.to_string_lossy().replace('\\', "/")),
+            change_type: change as u8,
+        })
+        .collect()
+}
+
+impl LspClient {
+    /// Whether the server has registered any file watchers
+    pub fn watches_files(&self) -> bool {
+        self.registrations
+            .lock()
+            .iter()
+            .any(|r| r.method == WATCHED_FILES_METHOD)
+    }
+
+    /// Send the changes in `batch` that match the server's registered watchers
+    ///
+    /// Returns the number of changes sent.
+    pub fn did_change_watched_files(&self, batch: &FileChangeBatch) -> Result<usize> {
+        let registrations = self.registrations.lock().clone();
+        let watchers = compile_watchers(Path::new(&self.config.root_path), &registrations);
+        if watchers.is_empty() {
+            return Ok(0);
+        }
+
+        let changes = matching_events(&watchers, batch);
+        if changes.is_empty() {
+            return Ok(0);
+        }
+
+        let count = changes.len();
+        debug!("[{}] {} watched file change(s)", self.name, count);
+        self.notify(
+            WATCHED_FILES_METHOD,
+            LspDidChangeWatchedFilesParams { changes },
+        )?;
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn registration(watchers: serde_json::Value) -> DynamicRegistration {
+        DynamicRegistration {
+            id: "watch".to_string(),
+            method: WATCHED_FILES_METHOD.to_string(),
+            register_options: Some(json!({ "watchers": watchers })),
+        }
+    }
+
+    fn batch(changes: &[(&str, FileChangeType)]) -> FileChangeBatch {
+        let mut batch = FileChangeBatch::default();
+        for (path, change) in changes {
+            batch.push(PathBuf::from(path), *change);
+        }
+        batch
+    }
+
+    #[test]
+    fn expands_braces() {
+        assert_eq!(expand_braces("*.rs"), vec!["*.rs"]);
+        assert_eq!(expand_braces("**/*.{ts,tsx}"), vec!["**/*.ts", "**/*.tsx"]);
+        assert_eq!(
+            expand_braces("{src,lib}/*.{a,b}"),
+            vec!["src/*.a", "src/*.b", "lib/*.a", "lib/*.b"]
+        );
+        assert_eq!(expand_braces("a{b,{c,d}}"), vec!["ab", "ac", "ad"]);
+        assert_eq!(expand_braces("unclosed{a,b"), vec!["unclosed{a,b"]);
+    }
+
+    #[test]
+    fn matches_relative_and_absolute_patterns() {
+        let root = Path::new("/work/project");
+        let watchers = compile_watchers(
+            root,
+            &[registration(json!([
+                { "globPattern": "**/Cargo.toml" },
+                { "globPattern": "/opt/shared/*.json" },
+                { "globPattern": { "baseUri": "file:///work/project/src", "pattern": "*.rs" } },
+            ]))],
+        );
+        assert_eq!(watchers.len(), 3);
+
+        let changes = batch(&[
+            ("/work/project/Cargo.toml", FileChangeType::Changed),
+            ("/work/project/crates/a/Cargo.toml", FileChangeType::Created),
+            ("/opt/shared/config.json", FileChangeType::Changed),
+            ("/work/project/src/main.rs", FileChangeType::Changed),
+            ("/work/project/src/nested/mod.rs", FileChangeType::Changed),
+            ("/elsewhere/Cargo.toml", FileChangeType::Changed),
+        ]);
+        let uris: Vec<String> = matching_events(&watchers, &changes)
+            .into_iter()
+            .map(|e| e.uri)
+            .collect();
+        assert_eq!(
+            uris,
+            vec![
+                "file:///work/project/Cargo.toml",
+                "file:///work/project/crates/a/Cargo.toml",
+                "file:///opt/shared/config.json",
+                "file:///work/project/src/main.rs",
+            ]
+        );
+    }
+
+    #[test]
+    fn respects_kind_mask() {
+        let watchers = compile_watchers(
+            Path::new("/work"),
+            &[registration(
+                json!([{ "globPattern": "**/*.ts", "kind": 5 }]),
+            )],
+        );
+        let events = matching_events(
+            &watchers,
+            &batch(&[
+                ("/work/a.ts", FileChangeType::Created),
+                ("/work/b.ts", FileChangeType::Changed),
+                ("/work/c.ts", FileChangeType::Deleted),
+            ]),
+        );
+        let types: Vec<u8> = events.iter().map(|e| e.change_type).collect();
+        assert_eq!(types, vec![1, 3]);
+    }
+
+    #[test]
+    fn ignores_other_registrations() {
+        let other = DynamicRegistration {
+            id: "fmt".to_string(),
+            method: "textDocument/formatting".to_string(),
+            register_options: Some(json!({ "watchers": [{ "globPattern": "**/*" }] })),
+        };
+        assert!(compile_watchers(Path::new("/work"), &[other]).is_empty());
+    }
+
+    #[test]
+    fn coalesces_changes() {
+        use FileChangeType::*;
+
+        let batch = batch(&[
+            ("/a", Created),
+            ("/a", Changed),
+            ("/b", Created),
+            ("/b", Deleted),
+            ("/c", Deleted),
+            ("/c", Created),
+            ("/d", Changed),
+            ("/d", Deleted),
+        ]);
+        let changes: Vec<(String, FileChangeType)> = batch
+            .changes()
+            .map(|(p, c)| (p.to_string_lossy().to_string(), c))
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                ("/a".to_string(), Created),
+                ("/c".to_string(), Changed),
+                ("/d".to_string(), Deleted),
+            ]
+        );
+    }
+}
@@ -0,0 +1,163 @@
+//! Server health
+//!
+//! The client notices when its server goes away (stdout closes) or stops
+//! answering (several requests in a row time out) and reports it once through
+//! a watch channel. It also keeps what a replacement server needs to pick up
+//! where the old one left off: the open documents, the dynamic registrations
+//! and the last lines of stderr for the crash log.
+
+use std::collections::VecDeque;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use anyhow::Result;
+use tokio::sync::watch;
+use tracing::warn;
+
+use super::core::LspClient;
+use crate::lsp::types::ServerStatus;
+
+/// Default time a server gets to answer a request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+/// Time a server gets to acknowledge `shutdown`
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+/// Timeouts in a row after which the server counts as unresponsive
+const MAX_CONSECUTIVE_TIMEOUTS: u32 = 3;
+/// Number of stderr lines kept for the crash log
+pub(crate) const STDERR_TAIL_LINES: usize = 50;
+
+/// Why a server stopped serving the client
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServerFault {
+    /// The process exited or closed its output
+    Exited,
+    /// Several requests in a row went unanswered
+    Unresponsive,
+}
+
+impl std::fmt::Display for ServerFault {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ServerFault::Exited => write!(f, "exited unexpectedly"),
+            ServerFault::Unresponsive => write!(f, "stopped responding"),
+        }
+    }
+}
+
+/// Time limit for a request, or `None` for long-running methods
+pub(super) fn request_timeout(method: &str) -> Option<Duration> {
+    match method {
+        // Long-polling and user-triggered work may legitimately take minutes
+        "workspace/diagnostic" | "workspace/executeCommand" => None,
+        "shutdown" => Some(SHUTDOWN_TIMEOUT),
+        _ => Some(REQUEST_TIMEOUT),
+    }
+}
+
+/// Record the first fault; later ones are ignored
+pub(super) fn report_fault(tx: &watch::Sender<Option<ServerFault>>, fault: ServerFault) {
+    tx.send_if_modified(|current| {
+        if current.is_none() {
+            *current = Some(fault);
+            true
+        } else {
+            false
+        }
+    });
+}
+
+/// Append a stderr line, dropping the oldest beyond `STDERR_TAIL_LINES`
+pub(super) fn push_stderr_line(tail: &mut VecDeque<String>, line: String) {
+    if tail.len() == STDERR_TAIL_LINES {
+        tail.pop_front();
+    }
+    tail.push_back(line);
+}
+
+impl LspClient {
+    pub(super) fn record_timeout(&self, method: &str) {
+        let timeouts = self.consecutive_timeouts.fetch_add(1, Ordering::SeqCst) + 1;
+        warn!(
+            "[{}] {} timed out ({} in a row)",
+            self.name, method, timeouts
+        );
+        if timeouts >= MAX_CONSECUTIVE_TIMEOUTS {
+            report_fault(&self.fault_tx, ServerFault::Unresponsive);
+        }
+    }
+
+    /// Wait until the server exits or becomes unresponsive
+    pub async fn wait_for_fault(&self) -> ServerFault {
+        let mut rx = self.fault_tx.subscribe();
+        match rx.wait_for(|fault| fault.is_some()).await {
+            Ok(fault) => fault.unwrap_or(ServerFault::Exited),
+            // The sender lives as long as the client
+            Err(_) => ServerFault::Exited,
+        }
+    }
+
+    /// Last lines written to stderr, oldest first
+    pub fn stderr_tail(&self) -> Vec<String> {
+        self.stderr_tail.lock().iter().cloned().collect()
+    }
+
+    /// Stop a faulty server without the shutdown handshake
+    pub fn kill(&self) {
+        {
+            let mut status = self.status.lock();
+            if *status != ServerStatus::Stopped {
+                *status = ServerStatus::Error;
+            }
+        }
+        if let Some(mut process) = self.process.lock().take() {
+            process.kill();
+        }
+    }
+
+    /// Carry the session of `previous` over to this (freshly initialized) client
+    ///
+    /// Every open document is re-opened at its current version. Dynamic
+    /// registrations are not carried over: the new server registers its own
+    /// after `initialized`, and stale ones could never be unregistered.
+    /// Returns the number of documents re-opened.
+    pub fn restore_from(&self, previous: &LspClient) -> Result<usize> {
+        let documents: Vec<_> = previous.open_documents.lock().values().cloned().collect();
+        for document in &documents {
+            self.did_open(document.clone())?;
+        }
+        Ok(documents.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_first_fault() {
+        let (tx, rx) = watch::channel(None);
+        report_fault(&tx, ServerFault::Unresponsive);
+        report_fault(&tx, ServerFault::Exited);
+        assert_eq!(*rx.borrow(), Some(ServerFault::Unresponsive));
+    }
+
+    #[test]
+    fn bounds_stderr_tail() {
+        let mut tail = VecDeque::new();
+        for i in 0..STDERR_TAIL_LINES + 5 {
+            push_stderr_line(&mut tail, i.to_string());
+        }
+        assert_eq!(tail.len(), STDERR_TAIL_LINES);
+        assert_eq!(tail.front().map(String::as_str), Some("5"));
+    }
+
+    #[test]
+    fn long_running_methods_have_no_timeout() {
+        assert_eq!(request_timeout("workspace/diagnostic"), None);
+        assert_eq!(request_timeout("shutdown"), Some(SHUTDOWN_TIMEOUT));
+        assert_eq!(
+            request_timeout("textDocument/completion"),
+            Some(REQUEST_TIMEOUT)
+        );
+    }
+}
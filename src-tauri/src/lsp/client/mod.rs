@@ -10,14 +10,39 @@
 //! - `document_sync`: Document lifecycle operations (open, change, save, close)
 //! - `language_features`: Standard language features (completion, hover, etc.)
 //! - `extended_features`: Additional LSP methods for VS Code parity
+//! - `file_watchers`: Registered glob watchers and workspace/didChangeWatchedFiles
+//! - `health`: Exit and timeout detection, state carried over to a restarted server
+//! - `pull_diagnostics`: Pull-model diagnostics (textDocument/diagnostic, workspace/diagnostic)
+//! - `server_requests`: Requests initiated by the server (configuration, registrations, ...)
+//! - `trace`: Opt-in JSON-RPC traces written to rotating files
+//! - `replay`: Replaying a trace against a server and diffing its responses
+//! - `transport`: Byte streams to the server (local process or remote channel) and URI mapping
 
 mod conversions;
 mod core;
 mod document_sync;
 mod extended_features;
+#[cfg(all(test, unix))]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod fake_server;
+mod file_watchers;
+mod health;
 mod hierarchy;
 mod language_features;
 mod protocol_types;
+mod pull_diagnostics;
+mod replay;
+mod server_requests;
+mod trace;
+mod transport;
 
 // Re-export the main client type
 pub use core::LspClient;
+pub use file_watchers::{FileChangeBatch, FileChangeType};
+pub use health::ServerFault;
+pub(crate) use protocol_types::LspResponseError;
+pub use pull_diagnostics::PulledDiagnostics;
+pub use replay::{ReplayReport, ResponseMismatch, replay, replay_command};
+pub(crate) use server_requests::{INVALID_PARAMS, REQUEST_FAILED};
+pub use trace::{TraceDirection, TraceEntry, TraceWriter, default_trace_path, read_trace};
+pub use transport::{ServerProcess, ServerTransport, UriMap};
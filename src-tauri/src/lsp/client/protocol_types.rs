@@ -2,6 +2,8 @@
 //!
 //! These types are used for serialization/deserialization of LSP messages.
 
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
@@ -20,6 +22,42 @@ pub(crate) struct InitializeParams {
 pub(crate) struct ClientCapabilities {
     #[serde(rename = "textDocument")]
     pub text_document: Option<TextDocumentClientCapabilities>,
+    pub workspace: Option<WorkspaceClientCapabilities>,
+    pub window: Option<WindowClientCapabilities>,
+}
+
+/// Workspace specific client capabilities
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct WorkspaceClientCapabilities {
+    #[serde(rename = "applyEdit")]
+    pub apply_edit: Option<bool>,
+    pub configuration: Option<bool>,
+    pub diagnostics: Option<RefreshClientCapabilities>,
+    #[serde(rename = "didChangeWatchedFiles")]
+    pub did_change_watched_files: Option<DidChangeWatchedFilesClientCapabilities>,
+}
+
+/// File watching capability; watchers are only ever registered dynamically
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct DidChangeWatchedFilesClientCapabilities {
+    #[serde(rename = "dynamicRegistration")]
+    pub dynamic_registration: Option<bool>,
+    #[serde(rename = "relativePatternSupport")]
+    pub relative_pattern_support: Option<bool>,
+}
+
+/// Capability for server-initiated `*/refresh` requests
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct RefreshClientCapabilities {
+    #[serde(rename = "refreshSupport")]
+    pub refresh_support: Option<bool>,
+}
+
+/// Window specific client capabilities
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct WindowClientCapabilities {
+    #[serde(rename = "workDoneProgress")]
+    pub work_done_progress: Option<bool>,
 }
 
 /// Text document specific client capabilities
@@ -29,6 +67,16 @@ pub(crate) struct TextDocumentClientCapabilities {
     pub hover: Option<HoverClientCapabilities>,
     #[serde(rename = "synchronization")]
     pub sync: Option<SynchronizationCapabilities>,
+    pub diagnostic: Option<DiagnosticClientCapabilities>,
+}
+
+/// Pull diagnostics client capabilities
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct DiagnosticClientCapabilities {
+    #[serde(rename = "dynamicRegistration")]
+    pub dynamic_registration: Option<bool>,
+    #[serde(rename = "relatedDocumentSupport")]
+    pub related_document_support: Option<bool>,
 }
 
 /// Completion related client capabilities
@@ -86,6 +134,8 @@ pub(crate) struct LspServerCapabilities {
     pub code_action_provider: Option<Value>,
     #[serde(rename = "signatureHelpProvider")]
     pub signature_help_provider: Option<Value>,
+    #[serde(rename = "diagnosticProvider")]
+    pub diagnostic_provider: Option<Value>,
 }
 
 /// Parameters for publishDiagnostics notification
@@ -155,3 +205,117 @@ pub(crate) struct LspCompletionItem {
     #[serde(rename = "filterText")]
     pub filter_text: Option<String>,
 }
+
+/// JSON-RPC error returned for a server-to-client request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct LspResponseError {
+    pub code: i64,
+    pub message: String,
+}
+
+/// Parameters for client/registerCapability
+#[derive(Debug, Deserialize)]
+pub(crate) struct LspRegistrationParams {
+    pub registrations: Vec<LspRegistration>,
+}
+
+/// A single dynamic registration
+#[derive(Debug, Deserialize)]
+pub(crate) struct LspRegistration {
+    pub id: String,
+    pub method: String,
+    #[serde(rename = "registerOptions")]
+    pub register_options: Option<Value>,
+}
+
+/// Parameters for client/unregisterCapability
+#[derive(Debug, Deserialize)]
+pub(crate) struct LspUnregistrationParams {
+    /// The LSP specification misspells this field.
+    #[serde(rename = "unregisterations")]
+    pub unregistrations: Vec<LspUnregistration>,
+}
+
+/// A single dynamic unregistration
+#[derive(Debug, Deserialize)]
+pub(crate) struct LspUnregistration {
+    pub id: String,
+}
+
+/// Response to textDocument/diagnostic (full or unchanged report)
+#[derive(Debug, Deserialize)]
+pub(crate) struct LspDocumentDiagnosticReport {
+    pub kind: String,
+    #[serde(rename = "resultId")]
+    pub result_id: Option<String>,
+    #[serde(default)]
+    pub items: Vec<LspDiagnostic>,
+    #[serde(rename = "relatedDocuments", default)]
+    pub related_documents: HashMap<String, LspDocumentDiagnosticReport>,
+}
+
+/// Response to workspace/diagnostic
+#[derive(Debug, Deserialize)]
+pub(crate) struct LspWorkspaceDiagnosticReport {
+    pub items: Vec<LspWorkspaceDocumentDiagnosticReport>,
+}
+
+/// Per-document entry of a workspace diagnostic report
+#[derive(Debug, Deserialize)]
+pub(crate) struct LspWorkspaceDocumentDiagnosticReport {
+    pub uri: String,
+    pub kind: String,
+    #[serde(rename = "resultId")]
+    pub result_id: Option<String>,
+    #[serde(default)]
+    pub items: Vec<LspDiagnostic>,
+}
+
+/// Register options for workspace/didChangeWatchedFiles
+#[derive(Debug, Deserialize)]
+pub(crate) struct LspDidChangeWatchedFilesRegistrationOptions {
+    pub watchers: Vec<LspFileSystemWatcher>,
+}
+
+/// A glob watcher registered by the server
+#[derive(Debug, Deserialize)]
+pub(crate) struct LspFileSystemWatcher {
+    #[serde(rename = "globPattern")]
+    pub glob_pattern: LspGlobPattern,
+    /// Bit mask of create (1), change (2) and delete (4); all when absent
+    pub kind: Option<u8>,
+}
+
+/// A glob, either a plain pattern or one relative to a base folder
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum LspGlobPattern {
+    Pattern(String),
+    Relative {
+        #[serde(rename = "baseUri")]
+        base_uri: LspBaseUri,
+        pattern: String,
+    },
+}
+
+/// Base of a relative pattern: a URI or a workspace folder
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum LspBaseUri {
+    Uri(String),
+    WorkspaceFolder { uri: String },
+}
+
+/// A single change in workspace/didChangeWatchedFiles
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct LspFileEvent {
+    pub uri: String,
+    #[serde(rename = "type")]
+    pub change_type: u8,
+}
+
+/// Parameters for workspace/didChangeWatchedFiles
+#[derive(Debug, Serialize)]
+pub(crate) struct LspDidChangeWatchedFilesParams {
+    pub changes: Vec<LspFileEvent>,
+}
@@ -0,0 +1,168 @@
+//! Pull diagnostics
+//!
+//! Servers that advertise `diagnosticProvider` (statically or through a dynamic
+//! registration) report diagnostics in response to `textDocument/diagnostic`
+//! and `workspace/diagnostic` instead of pushing them. Result ids from previous
+//! pulls are sent back so unchanged documents come back as `unchanged` reports.
+
+use anyhow::Result;
+use serde_json::{Value, json};
+
+use super::conversions::convert_diagnostic;
+use super::core::LspClient;
+use super::protocol_types::*;
+use crate::lsp::types::Diagnostic;
+
+const PULL_METHOD: &str = "textDocument/diagnostic";
+
+/// Fresh diagnostics for one document from a pull
+#[derive(Debug, Clone)]
+pub struct PulledDiagnostics {
+    pub uri: String,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Normalize a file path or `file://` URI to the form used in didOpen
+fn document_uri(uri: &str) -> String {
+    if uri.starts_with("file://") {
+        uri.to_string()
+    } else {
+        format!("file://{}", uri.replace('\\', "/"))
+    }
+}
+
+impl LspClient {
+    /// Static options, or the options of the first dynamic registration
+    fn diagnostic_options(&self) -> Option<Value> {
+        if let Some(options) = self.diagnostic_provider.lock().clone() {
+            return Some(options);
+        }
+        self.registrations
+            .lock()
+            .iter()
+            .find(|r| r.method == PULL_METHOD)
+            .map(|r| r.register_options.clone().unwrap_or(json!({})))
+    }
+
+    /// Whether the server answers `textDocument/diagnostic`
+    pub fn supports_pull_diagnostics(&self) -> bool {
+        self.diagnostic_options().is_some()
+    }
+
+    /// Whether the server answers `workspace/diagnostic`
+    pub fn supports_workspace_diagnostics(&self) -> bool {
+        self.diagnostic_options()
+            .and_then(|o| o.get("workspaceDiagnostics").and_then(|w| w.as_bool()))
+            .unwrap_or(false)
+    }
+
+    /// Whether edits in one file can change diagnostics in others
+    pub fn has_inter_file_dependencies(&self) -> bool {
+        self.diagnostic_options()
+            .and_then(|o| o.get("interFileDependencies").and_then(|w| w.as_bool()))
+            .unwrap_or(false)
+    }
+
+    fn diagnostic_identifier(&self) -> Option<String> {
+        self.diagnostic_options().and_then(|o| {
+            o.get("identifier")
+                .and_then(|i| i.as_str())
+                .map(String::from)
+        })
+    }
+
+    /// Record a report's result id and return its diagnostics if it changed
+    fn take_report(
+        &self,
+        uri: String,
+        kind: &str,
+        result_id: Option<String>,
+        items: Vec<LspDiagnostic>,
+    ) -> Option<PulledDiagnostics> {
+        {
+            let mut result_ids = self.diagnostic_result_ids.lock();
+            match result_id {
+                Some(id) => {
+                    result_ids.insert(uri.clone(), id);
+                }
+                None => {
+                    result_ids.remove(&uri);
+                }
+            }
+        }
+
+        if kind == "unchanged" {
+            return None;
+        }
+        Some(PulledDiagnostics {
+            uri,
+            diagnostics: items.into_iter().map(convert_diagnostic).collect(),
+        })
+    }
+
+    /// Pull diagnostics for a document
+    ///
+    /// `uri` is a file path or `file://` URI. Returns the documents whose
+    /// diagnostics changed, including related documents the server reported.
+    pub async fn pull_document_diagnostics(&self, uri: &str) -> Result<Vec<PulledDiagnostics>> {
+        let uri = document_uri(uri);
+        self.pulled_documents.lock().insert(uri.clone());
+
+        let mut params = json!({ "textDocument": { "uri": uri } });
+        if let Some(identifier) = self.diagnostic_identifier() {
+            params["identifier"] = json!(identifier);
+        }
+        if let Some(previous) = self.diagnostic_result_ids.lock().get(&uri) {
+            params["previousResultId"] = json!(previous);
+        }
+
+        let report: LspDocumentDiagnosticReport = self.request(PULL_METHOD, params).await?;
+
+        let mut pulled = Vec::new();
+        for (related_uri, related) in report.related_documents {
+            pulled.extend(self.take_report(
+                related_uri,
+                &related.kind,
+                related.result_id,
+                related.items,
+            ));
+        }
+        pulled.extend(self.take_report(uri, &report.kind, report.result_id, report.items));
+        Ok(pulled)
+    }
+
+    /// Pull diagnostics for the whole workspace
+    pub async fn pull_workspace_diagnostics(&self) -> Result<Vec<PulledDiagnostics>> {
+        let previous: Vec<Value> = self
+            .diagnostic_result_ids
+            .lock()
+            .iter()
+            .map(|(uri, id)| json!({ "uri": uri, "value": id }))
+            .collect();
+
+        let mut params = json!({ "previousResultIds": previous });
+        if let Some(identifier) = self.diagnostic_identifier() {
+            params["identifier"] = json!(identifier);
+        }
+
+        let report: LspWorkspaceDiagnosticReport =
+            self.request("workspace/diagnostic", params).await?;
+
+        Ok(report
+            .items
+            .into_iter()
+            .filter_map(|item| self.take_report(item.uri, &item.kind, item.result_id, item.items))
+            .collect())
+    }
+
+    /// Documents that were pulled individually and are still open
+    pub fn pulled_documents(&self) -> Vec<String> {
+        self.pulled_documents.lock().iter().cloned().collect()
+    }
+
+    /// Stop re-pulling a document after it is closed
+    pub fn forget_pulled_document(&self, uri: &str) {
+        let uri = document_uri(uri);
+        self.pulled_documents.lock().remove(&uri);
+    }
+}
@@ -0,0 +1,370 @@
+//! Trace replay
+//!
+//! Replays the client side of a recorded trace against a server: requests and
+//! notifications are sent in their recorded order, each request waits for its
+//! response, and requests the server makes are answered with the replies that
+//! were recorded for the same method. Every response is compared with the
+//! recorded one and differences are reported by JSON path.
+
+use std::collections::{HashMap, VecDeque};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result, anyhow};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use super::core::LspClient;
+use super::trace::{TraceDirection, TraceEntry};
+
+/// Differences reported per response before the rest are summarized
+const MAX_DIFFERENCES: usize = 50;
+
+/// Outcome of replaying a trace
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReplayReport {
+    /// Requests that had a recorded response to compare against
+    pub requests: usize,
+    /// Requests whose response matched the recording
+    pub matched: usize,
+    pub mismatches: Vec<ResponseMismatch>,
+}
+
+/// A response that differs from the recorded one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResponseMismatch {
+    pub id: Value,
+    pub method: String,
+    /// One line per difference, e.g. `result/items/0/label: expected "a", got "b"`
+    pub differences: Vec<String>,
+}
+
+/// Key for matching JSON-RPC ids, which may be numbers or strings
+fn id_key(id: &Value) -> String {
+    id.to_string()
+}
+
+fn is_response(message: &Value) -> bool {
+    message.get("id").is_some() && message.get("method").is_none()
+}
+
+/// Read one framed message
+pub(super) fn read_message(reader: &mut impl BufRead) -> Result<Value> {
+    let mut header_buf = String::new();
+    let length = LspClient::read_headers(reader, &mut header_buf)?;
+    let mut content = vec![0u8; length];
+    reader.read_exact(&mut content)?;
+    Ok(serde_json::from_slice(&content)?)
+}
+
+/// Write one framed message
+pub(super) fn write_message(writer: &mut impl Write, message: &Value) -> Result<()> {
+    LspClient::write_message(writer, message)
+}
+
+/// The client side of a trace
+struct ReplayScript {
+    /// Requests and notifications in the order they were sent
+    outgoing: Vec<Value>,
+    /// Map: request id -> recorded response
+    responses: HashMap<String, Value>,
+    /// Map: server request method -> recorded replies, in order
+    replies: HashMap<String, VecDeque<Value>>,
+}
+
+impl ReplayScript {
+    fn from_entries(entries: &[TraceEntry]) -> Self {
+        let mut outgoing = Vec::new();
+        let mut responses = HashMap::new();
+        let mut replies: HashMap<String, VecDeque<Value>> = HashMap::new();
+        // Map: server request id -> method
+        let mut server_requests = HashMap::new();
+
+        for entry in entries {
+            let message = &entry.message;
+            match entry.direction {
+                TraceDirection::Send if is_response(message) => {
+                    if let Some(method) = server_requests.remove(&id_key(&message["id"])) {
+                        replies
+                            .entry(method)
+                            .or_default()
+                            .push_back(message.clone());
+                    }
+                }
+                TraceDirection::Send => outgoing.push(message.clone()),
+                TraceDirection::Receive if is_response(message) => {
+                    responses.insert(id_key(&message["id"]), message.clone());
+                }
+                TraceDirection::Receive => {
+                    if let (Some(id), Some(method)) = (
+                        message.get("id"),
+                        message.get("method").and_then(|m| m.as_str()),
+                    ) {
+                        server_requests.insert(id_key(id), method.to_string());
+                    }
+                }
+            }
+        }
+
+        Self {
+            outgoing,
+            responses,
+            replies,
+        }
+    }
+}
+
+/// Drives one replay over a server's stdin and stdout
+struct Replayer<W> {
+    stdin: W,
+    incoming: Receiver<Value>,
+    replies: HashMap<String, VecDeque<Value>>,
+    /// Responses that arrived while waiting for another one
+    early: HashMap<String, Value>,
+}
+
+impl<W: Write> Replayer<W> {
+    /// Answer a request from the server with the next recorded reply
+    fn answer(&mut self, id: &Value, method: &str) -> Result<()> {
+        let mut reply = self
+            .replies
+            .get_mut(method)
+            .and_then(|queue| queue.pop_front())
+            .unwrap_or_else(|| json!({ "jsonrpc": "2.0", "result": null }));
+        reply["id"] = id.clone();
+        write_message(&mut self.stdin, &reply)
+    }
+
+    /// Wait for the response to request `key`
+    fn wait_for(&mut self, key: &str, timeout: Duration) -> Result<Option<Value>> {
+        if let Some(response) = self.early.remove(key) {
+            return Ok(Some(response));
+        }
+
+        let deadline = Instant::now() + timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let message = match self.incoming.recv_timeout(remaining) {
+                Ok(message) => message,
+                Err(RecvTimeoutError::Timeout | RecvTimeoutError::Disconnected) => {
+                    return Ok(None);
+                }
+            };
+
+            if let (Some(id), Some(method)) = (
+                message.get("id"),
+                message.get("method").and_then(|m| m.as_str()),
+            ) {
+                self.answer(id, method)?;
+            } else if let Some(id) = message.get("id") {
+                let received = id_key(id);
+                if received == key {
+                    return Ok(Some(message));
+                }
+                self.early.insert(received, message);
+            }
+        }
+    }
+}
+
+/// Compare the `result` and `error` of two responses
+fn compare_responses(expected: &Value, actual: &Value) -> Vec<String> {
+    let mut differences = Vec::new();
+    for field in ["result", "error"] {
+        diff_values(
+            field,
+            expected.get(field).unwrap_or(&Value::Null),
+            actual.get(field).unwrap_or(&Value::Null),
+            &mut differences,
+        );
+    }
+    if differences.len() > MAX_DIFFERENCES {
+        let more = differences.len() - MAX_DIFFERENCES;
+        differences.truncate(MAX_DIFFERENCES);
+        differences.push(format!("... and {} more", more));
+    }
+    differences
+}
+
+fn diff_values(path: &str, expected: &Value, actual: &Value, out: &mut Vec<String>) {
+    match (expected, actual) {
+        (Value::Object(expected), Value::Object(actual)) => {
+            for (key, value) in expected {
+                let child = format!("{}/{}", path, key);
+                match actual.get(key) {
+                    Some(other) => diff_values(&child, value, other, out),
+                    None => out.push(format!("{}: missing", child)),
+                }
+            }
+            for key in actual.keys().filter(|k| !expected.contains_key(*k)) {
+                out.push(format!("{}/{}: unexpected", path, key));
+            }
+        }
+        (Value::Array(expected), Value::Array(actual)) => {
+            if expected.len() != actual.len() {
+                out.push(format!(
+                    "{}: expected {} items, got {}",
+                    path,
+                    expected.len(),
+                    actual.len()
+                ));
+            }
+            for (index, (e, a)) in expected.iter().zip(actual).enumerate() {
+                diff_values(&format!("{}/{}", path, index), e, a, out);
+            }
+        }
+        _ if expected != actual => {
+            out.push(format!("{}: expected {}, got {}", path, expected, actual));
+        }
+        _ => {}
+    }
+}
+
+/// Replay `entries` against a server connected through `stdin` and `stdout`
+///
+/// Each request waits at most `timeout` for its response.
+pub fn replay(
+    stdin: impl Write,
+    stdout: impl Read + Send + 'static,
+    entries: &[TraceEntry],
+    timeout: Duration,
+) -> Result<ReplayReport> {
+    let script = ReplayScript::from_entries(entries);
+
+    let (tx, incoming) = mpsc::channel();
+    thread::spawn(move || {
+        let mut reader = BufReader::new(stdout);
+        while let Ok(message) = read_message(&mut reader) {
+            if tx.send(message).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut replayer = Replayer {
+        stdin,
+        incoming,
+        replies: script.replies,
+        early: HashMap::new(),
+    };
+    let mut report = ReplayReport::default();
+
+    for message in &script.outgoing {
+        write_message(&mut replayer.stdin, message)?;
+
+        let Some(id) = message.get("id") else {
+            continue;
+        };
+        let key = id_key(id);
+        // Nothing to compare against, e.g. the trace ended mid-request
+        let Some(expected) = script.responses.get(&key) else {
+            continue;
+        };
+        report.requests += 1;
+
+        let differences = match replayer.wait_for(&key, timeout)? {
+            Some(actual) => compare_responses(expected, &actual),
+            None => vec![format!("no response within {}s", timeout.as_secs())],
+        };
+        if differences.is_empty() {
+            report.matched += 1;
+        } else {
+            report.mismatches.push(ResponseMismatch {
+                id: id.clone(),
+                method: message["method"].as_str().unwrap_or_default().to_string(),
+                differences,
+            });
+        }
+    }
+
+    Ok(report)
+}
+
+/// Start `command` and replay `entries` against it
+pub fn replay_command(
+    command: &str,
+    args: &[String],
+    cwd: &Path,
+    entries: &[TraceEntry],
+    timeout: Duration,
+) -> Result<ReplayReport> {
+    let mut process = crate::process_utils::command(command)
+        .args(args)
+        .current_dir(cwd)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("Failed to spawn language server: {}", command))?;
+
+    let stdin = process
+        .stdin
+        .take()
+        .ok_or_else(|| anyhow!("Failed to open stdin"))?;
+    let stdout = process
+        .stdout
+        .take()
+        .ok_or_else(|| anyhow!("Failed to open stdout"))?;
+
+    let report = replay(stdin, stdout, entries, timeout);
+    let _ = process.kill();
+    let _ = process.wait();
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn entry(direction: TraceDirection, message: Value) -> TraceEntry {
+        TraceEntry {
+            time: Utc::now(),
+            direction,
+            message,
+        }
+    }
+
+    #[test]
+    fn splits_trace_into_script() {
+        let entries = vec![
+            entry(
+                TraceDirection::Send,
+                json!({"id": 1, "method": "initialize"}),
+            ),
+            entry(
+                TraceDirection::Receive,
+                json!({"id": "c1", "method": "workspace/configuration"}),
+            ),
+            entry(TraceDirection::Send, json!({"id": "c1", "result": [{}]})),
+            entry(TraceDirection::Receive, json!({"id": 1, "result": {}})),
+            entry(TraceDirection::Send, json!({"method": "initialized"})),
+        ];
+        let script = ReplayScript::from_entries(&entries);
+        assert_eq!(script.outgoing.len(), 2);
+        assert!(script.responses.contains_key("1"));
+        assert_eq!(script.replies["workspace/configuration"].len(), 1);
+    }
+
+    #[test]
+    fn reports_differences_by_path() {
+        let expected =
+            json!({"id": 1, "result": {"items": [{"label": "a"}], "isIncomplete": false}});
+        let actual = json!({"id": 1, "result": {"items": [{"label": "b"}, {"label": "c"}]}});
+        let mut differences = compare_responses(&expected, &actual);
+        differences.sort();
+        assert_eq!(
+            differences,
+            vec![
+                "result/isIncomplete: missing",
+                "result/items/0/label: expected \"a\", got \"b\"",
+                "result/items: expected 1 items, got 2",
+            ]
+        );
+        assert!(compare_responses(&expected, &expected).is_empty());
+    }
+}
@@ -0,0 +1,243 @@
+//! Server-to-client requests
+//!
+//! Language servers send requests of their own, such as `workspace/configuration`
+//! or `client/registerCapability`. Requests the client can answer from its own
+//! state are handled here; the rest are passed to handlers registered with
+//! `LspClient::on_request`. Unknown methods are answered with `MethodNotFound`
+//! so that a server never waits on a reply that will not come.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use serde_json::Value;
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+
+use super::core::{LspClient, OutgoingMessage};
+use super::protocol_types::*;
+use crate::lsp::types::DynamicRegistration;
+
+/// JSON-RPC `MethodNotFound`
+pub(crate) const METHOD_NOT_FOUND: i64 = -32601;
+/// JSON-RPC `InvalidParams`
+pub(crate) const INVALID_PARAMS: i64 = -32602;
+/// LSP `RequestFailed`
+pub(crate) const REQUEST_FAILED: i64 = -32803;
+
+/// Future returned by a server request handler
+pub(crate) type RequestFuture = Pin<Box<dyn Future<Output = RequestResult> + Send>>;
+
+/// Result sent back to the server for a server-to-client request
+pub(crate) type RequestResult = Result<Value, LspResponseError>;
+
+/// Handler for a server-to-client request method
+pub(super) type RequestHandler = Arc<dyn Fn(Value) -> RequestFuture + Send + Sync>;
+
+impl LspResponseError {
+    pub(crate) fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+/// State the reader thread needs to answer server requests
+#[derive(Clone)]
+pub(super) struct ServerRequestContext {
+    pub(super) server_id: String,
+    pub(super) outgoing_tx: mpsc::UnboundedSender<OutgoingMessage>,
+    pub(super) handlers: Arc<Mutex<HashMap<String, RequestHandler>>>,
+    pub(super) registrations: Arc<Mutex<Vec<DynamicRegistration>>>,
+}
+
+impl ServerRequestContext {
+    fn respond(&self, id: Value, result: RequestResult) {
+        if self
+            .outgoing_tx
+            .send(OutgoingMessage::Response { id, result })
+            .is_err()
+        {
+            debug!(
+                "[{}] Writer closed before response was sent",
+                self.server_id
+            );
+        }
+    }
+
+    /// Answer a request received from the server
+    pub(super) fn dispatch(&self, id: Value, method: &str, params: Value) {
+        debug!("[{}] Server request: {}", self.server_id, method);
+
+        match method {
+            "client/registerCapability" => {
+                let result = self.register(params);
+                self.respond(id, result);
+            }
+            "client/unregisterCapability" => {
+                let result = self.unregister(params);
+                self.respond(id, result);
+            }
+            // Progress itself arrives as `$/progress` notifications; creating
+            // the token needs no client-side bookkeeping.
+            "window/workDoneProgress/create" => self.respond(id, Ok(Value::Null)),
+            _ => {
+                let handler = self.handlers.lock().get(method).cloned();
+                match handler {
+                    Some(handler) => {
+                        let future = handler(params);
+                        let ctx = self.clone();
+                        tauri::async_runtime::spawn(async move {
+                            let result = future.await;
+                            ctx.respond(id, result);
+                        });
+                    }
+                    None => {
+                        warn!("[{}] Unhandled server request: {}", self.server_id, method);
+                        self.respond(
+                            id,
+                            Err(LspResponseError::new(
+                                METHOD_NOT_FOUND,
+                                format!("Unhandled method {}", method),
+                            )),
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    fn register(&self, params: Value) -> RequestResult {
+        let params: LspRegistrationParams = serde_json::from_value(params)
+            .map_err(|e| LspResponseError::new(INVALID_PARAMS, e.to_string()))?;
+
+        let mut registrations = self.registrations.lock();
+        for reg in params.registrations {
+            registrations.retain(|r| r.id != reg.id);
+            registrations.push(DynamicRegistration {
+                id: reg.id,
+                method: reg.method,
+                register_options: reg.register_options,
+            });
+        }
+        Ok(Value::Null)
+    }
+
+    fn unregister(&self, params: Value) -> RequestResult {
+        let params: LspUnregistrationParams = serde_json::from_value(params)
+            .map_err(|e| LspResponseError::new(INVALID_PARAMS, e.to_string()))?;
+
+        let mut registrations = self.registrations.lock();
+        for unreg in params.unregistrations {
+            registrations.retain(|r| r.id != unreg.id);
+        }
+        Ok(Value::Null)
+    }
+}
+
+impl LspClient {
+    /// Register a handler for a server-to-client request method
+    ///
+    /// The handler runs on the async runtime, so it may wait on the user or on
+    /// I/O without blocking the reader thread.
+    pub(crate) fn on_request<F, Fut>(&self, method: &str, handler: F)
+    where
+        F: Fn(Value) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = RequestResult> + Send + 'static,
+    {
+        let handler: RequestHandler = Arc::new(move |params| Box::pin(handler(params)));
+        self.request_handlers
+            .lock()
+            .insert(method.to_string(), handler);
+    }
+
+    /// Register a handler for a server notification
+    pub fn on_notification<F>(&self, method: &str, handler: F)
+    where
+        F: Fn(String, Value) + Send + Sync + 'static,
+    {
+        self.notification_handlers
+            .lock()
+            .insert(method.to_string(), Box::new(handler));
+    }
+
+    /// Capabilities the server has registered dynamically
+    pub fn registrations(&self) -> Vec<DynamicRegistration> {
+        self.registrations.lock().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn context() -> (
+        ServerRequestContext,
+        mpsc::UnboundedReceiver<OutgoingMessage>,
+    ) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let ctx = ServerRequestContext {
+            server_id: "test".to_string(),
+            outgoing_tx: tx,
+            handlers: Arc::new(Mutex::new(HashMap::new())),
+            registrations: Arc::new(Mutex::new(Vec::new())),
+        };
+        (ctx, rx)
+    }
+
+    fn next_response(rx: &mut mpsc::UnboundedReceiver<OutgoingMessage>) -> (Value, RequestResult) {
+        match rx.try_recv() {
+            Ok(OutgoingMessage::Response { id, result }) => (id, result),
+            _ => panic!("expected a response"),
+        }
+    }
+
+    #[test]
+    fn tracks_registrations() {
+        let (ctx, mut rx) = context();
+        ctx.dispatch(
+            json!(1),
+            "client/registerCapability",
+            json!({"registrations": [
+                {"id": "a", "method": "workspace/didChangeWatchedFiles",
+                 "registerOptions": {"watchers": [{"globPattern": "**/*.rs"}]}},
+                {"id": "b", "method": "textDocument/formatting"}
+            ]}),
+        );
+        let (id, result) = next_response(&mut rx);
+        assert_eq!(id, json!(1));
+        assert!(result.is_ok());
+        assert_eq!(ctx.registrations.lock().len(), 2);
+
+        ctx.dispatch(
+            json!("2"),
+            "client/unregisterCapability",
+            json!({"unregisterations": [{"id": "a", "method": "workspace/didChangeWatchedFiles"}]}),
+        );
+        let (id, _) = next_response(&mut rx);
+        assert_eq!(id, json!("2"));
+        let registrations = ctx.registrations.lock();
+        assert_eq!(registrations.len(), 1);
+        assert_eq!(registrations[0].method, "textDocument/formatting");
+    }
+
+    #[test]
+    fn unknown_method_is_rejected() {
+        let (ctx, mut rx) = context();
+        ctx.dispatch(json!(7), "custom/unknown", Value::Null);
+        let (_, result) = next_response(&mut rx);
+        assert_eq!(result.unwrap_err().code, METHOD_NOT_FOUND);
+    }
+
+    #[test]
+    fn invalid_registration_params() {
+        let (ctx, mut rx) = context();
+        ctx.dispatch(json!(3), "client/registerCapability", json!({"bad": true}));
+        let (_, result) = next_response(&mut rx);
+        assert_eq!(result.unwrap_err().code, INVALID_PARAMS);
+    }
+}
@@ -0,0 +1,248 @@
+//! JSON-RPC traces
+//!
+//! When tracing is on, every message exchanged with a server is appended to a
+//! JSON Lines file as it goes over the wire, with a timestamp and direction.
+//! Files rotate once they reach `MAX_TRACE_BYTES`, keeping `KEPT_ROTATIONS`
+//! older files next to the current one (`<server>.jsonl.1`, `.2`, ...).
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::warn;
+
+use super::core::LspClient;
+
+/// Size at which a trace file is rotated
+const MAX_TRACE_BYTES: u64 = 10 * 1024 * 1024;
+/// Rotated files kept besides the current one
+const KEPT_ROTATIONS: usize = 3;
+
+/// Which way a message travelled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TraceDirection {
+    /// Client to server
+    Send,
+    /// Server to client
+    Receive,
+}
+
+/// One line of a trace file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TraceEntry {
+    pub time: DateTime<Utc>,
+    pub direction: TraceDirection,
+    pub message: Value,
+}
+
+/// Directory traces are written to by default
+pub fn traces_dir() -> Result<PathBuf> {
+    let data_dir = dirs::data_dir().ok_or_else(|| anyhow!("Could not find app data directory"))?;
+    Ok(data_dir.join("Cortex").join("lsp-traces"))
+}
+
+/// Trace file name for a server id
+fn trace_file_name(server_id: &str) -> String {
+    let name: String = server_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{}.jsonl", name)
+}
+
+/// Default trace file for a server
+pub fn default_trace_path(server_id: &str) -> Result<PathBuf> {
+    Ok(traces_dir()?.join(trace_file_name(server_id)))
+}
+
+/// Appends trace entries to a rotating file
+pub struct TraceWriter {
+    path: PathBuf,
+    file: File,
+    written: u64,
+    max_bytes: u64,
+}
+
+impl TraceWriter {
+    pub fn open(path: &Path) -> Result<Self> {
+        Self::with_limit(path, MAX_TRACE_BYTES)
+    }
+
+    fn with_limit(path: &Path, max_bytes: u64) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open trace file {}", path.display()))?;
+        let written = file.metadata().map(|m| m.len()).unwrap_or(0);
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            written,
+            max_bytes,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append one message
+    pub fn record(&mut self, direction: TraceDirection, message: &Value) -> io::Result<()> {
+        if self.written >= self.max_bytes {
+            self.rotate()?;
+        }
+        let entry = TraceEntry {
+            time: Utc::now(),
+            direction,
+            message: message.clone(),
+        };
+        let mut line = serde_json::to_string(&entry)?;
+        line.push('\n');
+        self.file.write_all(line.as_bytes())?;
+        self.written += line.len() as u64;
+        Ok(())
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut name = self.path.as_os_str().to_owned();
+        name.push(format!(".{}", index));
+        PathBuf::from(name)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        let _ = fs::remove_file(self.rotated_path(KEPT_ROTATIONS));
+        for index in (1..KEPT_ROTATIONS).rev() {
+            let from = self.rotated_path(index);
+            if from.exists() {
+                fs::rename(&from, self.rotated_path(index + 1))?;
+            }
+        }
+        fs::rename(&self.path, self.rotated_path(1))?;
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        self.written = 0;
+        Ok(())
+    }
+}
+
+/// Record a message if tracing is on; failures turn tracing off
+pub(super) fn record(
+    trace: &Mutex<Option<TraceWriter>>,
+    direction: TraceDirection,
+    message: &Value,
+) {
+    let mut trace = trace.lock();
+    if let Some(writer) = trace.as_mut() {
+        if let Err(e) = writer.record(direction, message) {
+            warn!("Stopping LSP trace to {}: {}", writer.path().display(), e);
+            *trace = None;
+        }
+    }
+}
+
+/// Read a trace file, skipping lines that do not parse
+pub fn read_trace(path: &Path) -> Result<Vec<TraceEntry>> {
+    let file = File::open(path)
+        .with_context(|| format!("Failed to open trace file {}", path.display()))?;
+    let mut entries = Vec::new();
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(&line) {
+            Ok(entry) => entries.push(entry),
+            Err(e) => warn!("Skipping trace line {}: {}", index + 1, e),
+        }
+    }
+    Ok(entries)
+}
+
+impl LspClient {
+    /// Start writing a trace to `path`, replacing any trace in progress
+    pub fn start_trace(&self, path: &Path) -> Result<()> {
+        *self.trace.lock() = Some(TraceWriter::open(path)?);
+        Ok(())
+    }
+
+    /// Stop tracing
+    pub fn stop_trace(&self) {
+        *self.trace.lock() = None;
+    }
+
+    /// File the trace is being written to, if tracing is on
+    pub fn trace_path(&self) -> Option<PathBuf> {
+        self.trace.lock().as_ref().map(|w| w.path().to_path_buf())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn writes_and_reads_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ra.jsonl");
+        let mut writer = TraceWriter::open(&path).unwrap();
+        writer
+            .record(
+                TraceDirection::Send,
+                &json!({"id": 1, "method": "initialize"}),
+            )
+            .unwrap();
+        writer
+            .record(TraceDirection::Receive, &json!({"id": 1, "result": {}}))
+            .unwrap();
+
+        let entries = read_trace(&path).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].direction, TraceDirection::Send);
+        assert_eq!(entries[1].message["result"], json!({}));
+    }
+
+    #[test]
+    fn rotates_and_keeps_bounded_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ra.jsonl");
+        let mut writer = TraceWriter::with_limit(&path, 1).unwrap();
+        for id in 0..6 {
+            writer
+                .record(TraceDirection::Send, &json!({ "id": id }))
+                .unwrap();
+        }
+
+        let current = read_trace(&path).unwrap();
+        assert_eq!(current.len(), 1);
+        assert_eq!(current[0].message["id"], 5);
+        let oldest = read_trace(&writer.rotated_path(KEPT_ROTATIONS)).unwrap();
+        assert_eq!(oldest[0].message["id"], 2);
+        assert!(!writer.rotated_path(KEPT_ROTATIONS + 1).exists());
+    }
+
+    #[test]
+    fn sanitizes_server_ids_in_file_names() {
+        assert_eq!(trace_file_name("rust/analyzer:1"), "rust_analyzer_1.jsonl");
+    }
+}
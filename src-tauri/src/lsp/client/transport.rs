@@ -0,0 +1,257 @@
+//! Server transports
+//!
+//! A language server is reached through three byte streams. Local servers are
+//! child processes; other transports (such as an SSH exec channel) supply their
+//! own streams. When the server sees a different file system than the editor,
+//! a `UriMap` rewrites `file://` URIs in every message in both directions.
+
+use std::io::{Read, Write};
+use std::process::{Child, Stdio};
+
+use anyhow::{Context, Result, anyhow};
+use serde_json::Value;
+use tracing::info;
+
+use crate::lsp::types::LanguageServerConfig;
+
+/// A running server that can be stopped
+pub trait ServerProcess: Send {
+    fn kill(&mut self);
+}
+
+impl ServerProcess for Child {
+    fn kill(&mut self) {
+        let _ = Child::kill(self);
+    }
+}
+
+/// Streams connecting the client to a language server
+pub struct ServerTransport {
+    pub(super) stdin: Box<dyn Write + Send>,
+    pub(super) stdout: Box<dyn Read + Send>,
+    pub(super) stderr: Box<dyn Read + Send>,
+    pub(super) process: Box<dyn ServerProcess>,
+    pub(super) uri_map: Option<UriMap>,
+}
+
+impl ServerTransport {
+    pub fn new(
+        stdin: Box<dyn Write + Send>,
+        stdout: Box<dyn Read + Send>,
+        stderr: Box<dyn Read + Send>,
+        process: Box<dyn ServerProcess>,
+    ) -> Self {
+        Self {
+            stdin,
+            stdout,
+            stderr,
+            process,
+            uri_map: None,
+        }
+    }
+
+    /// Rewrite URIs between the editor's paths and the server's paths
+    pub fn with_uri_map(mut self, uri_map: UriMap) -> Self {
+        self.uri_map = Some(uri_map);
+        self
+    }
+
+    /// Spawn the server as a local child process
+    pub(super) fn spawn_local(config: &LanguageServerConfig) -> Result<Self> {
+        info!(
+            "Starting language server: {} ({})",
+            config.name, config.command
+        );
+
+        let mut process = crate::process_utils::command(&config.command)
+            .args(&config.args)
+            .current_dir(&config.root_path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("Failed to spawn language server: {}", config.command))?;
+
+        let stdin = process
+            .stdin
+            .take()
+            .ok_or_else(|| anyhow!("Failed to open stdin"))?;
+        let stdout = process
+            .stdout
+            .take()
+            .ok_or_else(|| anyhow!("Failed to open stdout"))?;
+        let stderr = process
+            .stderr
+            .take()
+            .ok_or_else(|| anyhow!("Failed to open stderr"))?;
+
+        Ok(Self::new(
+            Box::new(stdin),
+            Box::new(stdout),
+            Box::new(stderr),
+            Box::new(process),
+        ))
+    }
+}
+
+/// `file://` URI prefix for a root path, in the form used for documents
+fn root_uri(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    format!("file://{}", path.trim_end_matches('/'))
+}
+
+/// Maps a local workspace root onto the root the server sees
+#[derive(Debug, Clone)]
+pub struct UriMap {
+    local: String,
+    remote: String,
+    /// Percent-encoded form of `remote`, as servers may send it
+    remote_encoded: Option<String>,
+}
+
+impl UriMap {
+    pub fn new(local_root: &str, remote_root: &str) -> Self {
+        let remote = root_uri(remote_root);
+        let remote_encoded = url::Url::from_file_path(remote_root.trim_end_matches('/'))
+            .ok()
+            .map(|u| u.to_string())
+            .filter(|encoded| *encoded != remote);
+        Self {
+            local: root_uri(local_root),
+            remote,
+            remote_encoded,
+        }
+    }
+
+    /// Rewrite an outgoing message for the server
+    pub(super) fn to_remote(&self, value: &mut Value) {
+        rewrite(value, &|s| replace_root(s, &self.local, &self.remote));
+    }
+
+    /// Rewrite an incoming message for the editor
+    pub(super) fn to_local(&self, value: &mut Value) {
+        rewrite(value, &|s| {
+            let s = replace_root(s, &self.remote, &self.local);
+            match &self.remote_encoded {
+                Some(encoded) => replace_root(&s, encoded, &self.local),
+                None => s,
+            }
+        });
+    }
+}
+
+/// Apply `f` to every string (and object key) in a JSON value
+fn rewrite(value: &mut Value, f: &dyn Fn(&str) -> String) {
+    match value {
+        Value::String(s) => {
+            if s.contains("file://") {
+                *s = f(s);
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(|v| rewrite(v, f)),
+        Value::Object(map) => {
+            // Keys are URIs in WorkspaceEdit.changes and relatedDocuments
+            if map.keys().any(|k| k.contains("file://")) {
+                let entries = std::mem::take(map);
+                for (key, mut v) in entries {
+                    rewrite(&mut v, f);
+                    map.insert(f(&key), v);
+                }
+            } else {
+                map.values_mut().for_each(|v| rewrite(v, f));
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Replace occurrences of the root `from` with `to`
+///
+/// An occurrence only counts when it is followed by a path separator or ends
+/// the path, so `/work/app` does not match inside `/work/app2`.
+fn replace_root(s: &str, from: &str, to: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(pos) = rest.find(from) {
+        let after = &rest[pos + from.len()..];
+        let at_boundary = after
+            .chars()
+            .next()
+            .is_none_or(|c| !(c.is_alphanumeric() || matches!(c, '-' | '_' | '.' | '%')));
+        out.push_str(&rest[..pos]);
+        out.push_str(if at_boundary { to } else { from });
+        rest = after;
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn rewrites_roots_at_path_boundaries() {
+        assert_eq!(
+            replace_root(
+                "file:///local/ws/src/a.rs",
+                "file:///local/ws",
+                "file:///srv/ws"
+            ),
+            "file:///srv/ws/src/a.rs"
+        );
+        assert_eq!(
+            replace_root(
+                "file:///local/ws2/a.rs",
+                "file:///local/ws",
+                "file:///srv/ws"
+            ),
+            "file:///local/ws2/a.rs"
+        );
+        assert_eq!(
+            replace_root(
+                "see [a](file:///local/ws/a.rs#L3)",
+                "file:///local/ws",
+                "file:///srv/ws"
+            ),
+            "see [a](file:///srv/ws/a.rs#L3)"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn maps_messages_in_both_directions() {
+        let map = UriMap::new("/home/me/remote/box/proj/", "/srv/my proj");
+
+        let mut outgoing = json!({
+            "textDocument": { "uri": "file:///home/me/remote/box/proj/src/main.rs" },
+            "rootUri": "file:///home/me/remote/box/proj",
+        });
+        map.to_remote(&mut outgoing);
+        assert_eq!(
+            outgoing["textDocument"]["uri"],
+            "file:///srv/my proj/src/main.rs"
+        );
+        assert_eq!(outgoing["rootUri"], "file:///srv/my proj");
+
+        let mut incoming = json!({
+            "changes": {
+                "file:///srv/my%20proj/src/lib.rs": [{ "newText": "x" }]
+            },
+            "uri": "file:///srv/my proj/src/main.rs",
+            "other": "file:///etc/hosts",
+        });
+        map.to_local(&mut incoming);
+        assert_eq!(
+            incoming["uri"],
+            "file:///home/me/remote/box/proj/src/main.rs"
+        );
+        assert!(
+            incoming["changes"]
+                .get("file:///home/me/remote/box/proj/src/lib.rs")
+                .is_some()
+        );
+        assert_eq!(incoming["other"], "file:///etc/hosts");
+    }
+}
@@ -1,11 +1,14 @@
 //! LSP server auto-detection
 //!
 //! Detects installed language servers for common languages and provides
-//! configuration for starting them.
+//! configuration for starting them. Servers not on PATH are also looked up
+//! among the managed installs in the Cortex data directory.
 
 use serde::{Deserialize, Serialize};
 use tracing::info;
 
+use crate::lsp::installer;
+
 /// Result of auto-detecting an LSP server
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LspServerAutodetectResult {
@@ -117,6 +120,13 @@ fn detect_servers() -> Vec<LspServerAutodetectResult> {
         });
     }
 
+    for result in results.iter_mut().filter(|r| !r.installed) {
+        if let Some(path) = installer::managed_command_for(&result.command) {
+            result.command = path.to_string_lossy().to_string();
+            result.installed = true;
+        }
+    }
+
     results
 }
 
@@ -2,27 +2,42 @@
 //!
 //! Commands for document lifecycle notifications (open, change, save, close).
 
-use tauri::State;
+use std::sync::Arc;
 
+use tauri::{AppHandle, State};
+
+use crate::lsp::client::LspClient;
 use crate::lsp::types::{DidChangeParams, DidCloseParams, DidOpenParams, DidSaveParams};
 
+use super::pull_diagnostics::schedule_document_pull;
 use super::state::LspState;
 
+fn get_client(state: &LspState, server_id: &str) -> Result<Arc<LspClient>, String> {
+    state
+        .clients
+        .lock()
+        .get(server_id)
+        .cloned()
+        .ok_or_else(|| format!("Server not found: {}", server_id))
+}
+
 /// Notify that a document was opened
 #[tauri::command]
 pub fn lsp_did_open(
     server_id: String,
     params: DidOpenParams,
     state: State<'_, LspState>,
+    app: AppHandle,
 ) -> Result<(), String> {
-    let clients = state.clients.lock();
-    let client = clients
-        .get(&server_id)
-        .ok_or_else(|| format!("Server not found: {}", server_id))?;
+    let client = get_client(&state, &server_id)?;
+    let uri = params.uri.clone();
 
     client
         .did_open(params)
-        .map_err(|e| format!("Failed to send didOpen: {}", e))
+        .map_err(|e| format!("Failed to send didOpen: {}", e))?;
+
+    schedule_document_pull(&app, client, uri);
+    Ok(())
 }
 
 /// Notify that a document was changed
@@ -31,15 +46,17 @@ pub fn lsp_did_change(
     server_id: String,
     params: DidChangeParams,
     state: State<'_, LspState>,
+    app: AppHandle,
 ) -> Result<(), String> {
-    let clients = state.clients.lock();
-    let client = clients
-        .get(&server_id)
-        .ok_or_else(|| format!("Server not found: {}", server_id))?;
+    let client = get_client(&state, &server_id)?;
+    let uri = params.uri.clone();
 
     client
         .did_change(params)
-        .map_err(|e| format!("Failed to send didChange: {}", e))
+        .map_err(|e| format!("Failed to send didChange: {}", e))?;
+
+    schedule_document_pull(&app, client, uri);
+    Ok(())
 }
 
 /// Notify that a document was saved
@@ -48,15 +65,17 @@ pub fn lsp_did_save(
     server_id: String,
     params: DidSaveParams,
     state: State<'_, LspState>,
+    app: AppHandle,
 ) -> Result<(), String> {
-    let clients = state.clients.lock();
-    let client = clients
-        .get(&server_id)
-        .ok_or_else(|| format!("Server not found: {}", server_id))?;
+    let client = get_client(&state, &server_id)?;
+    let uri = params.uri.clone();
 
     client
         .did_save(params)
-        .map_err(|e| format!("Failed to send didSave: {}", e))
+        .map_err(|e| format!("Failed to send didSave: {}", e))?;
+
+    schedule_document_pull(&app, client, uri);
+    Ok(())
 }
 
 /// Notify that a document was closed
@@ -66,12 +85,14 @@ pub fn lsp_did_close(
     params: DidCloseParams,
     state: State<'_, LspState>,
 ) -> Result<(), String> {
-    let clients = state.clients.lock();
-    let client = clients
-        .get(&server_id)
-        .ok_or_else(|| format!("Server not found: {}", server_id))?;
+    let client = get_client(&state, &server_id)?;
+    let uri = params.uri.clone();
 
     client
         .did_close(params)
-        .map_err(|e| format!("Failed to send didClose: {}", e))
+        .map_err(|e| format!("Failed to send didClose: {}", e))?;
+
+    client.forget_pulled_document(&uri);
+    state.pull_generations.lock().remove(&(server_id, uri));
+    Ok(())
 }
@@ -5,6 +5,7 @@
 use tauri::{AppHandle, Emitter, Manager};
 use tracing::error;
 
+use super::file_watchers;
 use super::state::LspState;
 
 /// Setup LSP event listeners
@@ -22,4 +23,6 @@ pub fn setup_lsp_events(app: &AppHandle) {
             }
         });
     }
+
+    file_watchers::start_forwarding(app);
 }
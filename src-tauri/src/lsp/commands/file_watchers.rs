@@ -0,0 +1,311 @@
+//! Watched file forwarding
+//!
+//! Each running local server gets its own watcher on its root, independent of
+//! the watches the frontend holds through `fs::watcher`. Change events are
+//! collected per server until the file system has been quiet for a short
+//! while, then offered to that server only; the client filters the batch
+//! against its own registered globs.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use notify::{Config, RecommendedWatcher, RecursiveMode, Watcher};
+use tauri::{AppHandle, Manager};
+use tokio::time::Instant;
+use tracing::{debug, warn};
+
+use crate::fs::types::FileChangeEvent;
+use crate::fs::utils::matches_exclude_pattern;
+use crate::lsp::client::{FileChangeBatch, FileChangeType, LspClient};
+use crate::settings::types::FilesSettings;
+
+use super::state::LspState;
+
+/// Quiet period after the last change before a batch is sent
+const BATCH_DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Longest a batch is held back while changes keep arriving
+const MAX_BATCH_DELAY: Duration = Duration::from_secs(1);
+
+/// Map a watcher event to LSP change types
+///
+/// Renames arrive as modifications carrying both paths, and "any"/"other"
+/// events say nothing about the kind, so the file system is checked instead.
+fn changes_from_event(event: &FileChangeEvent) -> Vec<(PathBuf, FileChangeType)> {
+    let is_rename = event.event_type == "modify" && event.paths.len() > 1;
+
+    event
+        .paths
+        .iter()
+        .map(PathBuf::from)
+        .map(|path| {
+            let change = match event.event_type.as_str() {
+                "create" => FileChangeType::Created,
+                "remove" => FileChangeType::Deleted,
+                _ if !path.exists() => FileChangeType::Deleted,
+                _ if is_rename => FileChangeType::Created,
+                _ => FileChangeType::Changed,
+            };
+            (path, change)
+        })
+        .collect()
+}
+
+/// Add an event to the batch of the server whose root it came from
+fn collect(batches: &mut HashMap<String, FileChangeBatch>, event: &FileChangeEvent) {
+    let batch = batches.entry(event.watch_id.clone()).or_default();
+    for (path, change) in changes_from_event(event) {
+        batch.push(path, change);
+    }
+}
+
+fn dispatch(app: &AppHandle, batches: HashMap<String, FileChangeBatch>) {
+    let Some(state) = app.try_state::<LspState>() else {
+        return;
+    };
+
+    for (server_id, batch) in batches {
+        if batch.is_empty() {
+            continue;
+        }
+        let Some(client) = state.clients.lock().get(&server_id).cloned() else {
+            continue;
+        };
+        if !client.watches_files() {
+            continue;
+        }
+        if let Err(e) = client.did_change_watched_files(&batch) {
+            warn!(
+                "[{}] Failed to send watched file changes: {}",
+                client.name(),
+                e
+            );
+        }
+    }
+}
+
+/// Forward file system changes to language servers for the app's lifetime
+pub(crate) fn start_forwarding(app: &AppHandle) {
+    let Some(mut rx) = app
+        .try_state::<LspState>()
+        .and_then(|state| state.take_root_changes_receiver())
+    else {
+        return;
+    };
+    let app = app.clone();
+
+    tauri::async_runtime::spawn(async move {
+        while let Some(first) = rx.recv().await {
+            let mut batches = HashMap::new();
+            collect(&mut batches, &first);
+
+            let deadline = Instant::now() + MAX_BATCH_DELAY;
+            loop {
+                let quiet = (Instant::now() + BATCH_DEBOUNCE).min(deadline);
+                match tokio::time::timeout_at(quiet, rx.recv()).await {
+                    Ok(Some(event)) => collect(&mut batches, &event),
+                    Ok(None) | Err(_) => break,
+                }
+            }
+
+            dispatch(&app, batches);
+        }
+    });
+}
+
+/// Exclude patterns for server root watchers, the same defaults the editor uses
+fn default_excludes() -> Vec<String> {
+    FilesSettings::default()
+        .watch_exclude
+        .into_iter()
+        .filter(|(_, enabled)| *enabled)
+        .map(|(pattern, _)| pattern)
+        .collect()
+}
+
+/// Turn a notify event under `watched` into a change event for `server_id`
+///
+/// `watched` is the canonical form of the server's `root`, which is what
+/// notify reports paths under. They are moved back onto `root`, the path the
+/// server and its relative glob patterns know.
+fn to_change_event(
+    server_id: &str,
+    watched: &Path,
+    root: &Path,
+    exclude: &[String],
+    event: notify::Event,
+) -> Option<FileChangeEvent> {
+    let event_type = match event.kind {
+        notify::EventKind::Create(_) => "create",
+        notify::EventKind::Modify(_) => "modify",
+        notify::EventKind::Remove(_) => "remove",
+        notify::EventKind::Any => "any",
+        notify::EventKind::Other => "other",
+        _ => return None,
+    };
+
+    let paths: Vec<String> = event
+        .paths
+        .iter()
+        .filter(|p| !matches_exclude_pattern(p, exclude))
+        .filter_map(|p| p.strip_prefix(watched).ok())
+        .map(|relative| root.join(relative).to_string_lossy().to_string())
+        .collect();
+    if paths.is_empty() {
+        return None;
+    }
+
+    Some(FileChangeEvent {
+        event_type: event_type.to_string(),
+        paths,
+        watch_id: server_id.to_string(),
+    })
+}
+
+fn watch_root(state: &LspState, server_id: &str, root: &str) -> Result<RecommendedWatcher, String> {
+    let root = PathBuf::from(root);
+    let watched = root.canonicalize().unwrap_or_else(|_| root.clone());
+    let exclude = default_excludes();
+    let tx = state.root_changes_tx.clone();
+    let id = server_id.to_string();
+    let event_watched = watched.clone();
+
+    let mut watcher = RecommendedWatcher::new(
+        move |result: Result<notify::Event, notify::Error>| match result {
+            Ok(event) => {
+                if let Some(change) = to_change_event(&id, &event_watched, &root, &exclude, event) {
+                    // The forwarder only goes away with the app
+                    let _ = tx.send(change);
+                }
+            }
+            Err(e) => debug!("Watch error for language server {}: {}", id, e),
+        },
+        Config::default()
+            .with_poll_interval(Duration::from_secs(2))
+            .with_compare_contents(false),
+    )
+    .map_err(|e| format!("Failed to create watcher: {}", e))?;
+
+    watcher
+        .watch(&watched, RecursiveMode::Recursive)
+        .map_err(|e| format!("Failed to start watching: {}", e))?;
+    Ok(watcher)
+}
+
+/// Watch the server's root so its registered watchers see changes
+///
+/// Remote servers watch their own file system, so nothing is watched locally.
+pub(crate) fn on_server_started(app: &AppHandle, client: &LspClient) {
+    if client.config.remote.is_some() {
+        return;
+    }
+    let Some(state) = app.try_state::<LspState>() else {
+        return;
+    };
+
+    let root = &client.config.root_path;
+    match watch_root(&state, client.id(), root) {
+        Ok(watcher) => {
+            state
+                .root_watchers
+                .lock()
+                .insert(client.id().to_string(), watcher);
+        }
+        Err(e) => debug!("Not watching {} for language server: {}", root, e),
+    }
+}
+
+/// Drop the watcher created in `on_server_started`
+pub(crate) fn on_server_stopped(app: &AppHandle, server_id: &str) {
+    if let Some(state) = app.try_state::<LspState>() {
+        state.root_watchers.lock().remove(server_id);
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_watcher_events() {
+        let dir = tempfile::tempdir().unwrap();
+        let existing = dir.path().join("exists.txt");
+        std::fs::write(&existing, "").unwrap();
+        let missing = dir.path().join("missing.txt");
+
+        let event = |event_type: &str, paths: &[&PathBuf]| FileChangeEvent {
+            event_type: event_type.to_string(),
+            paths: paths
+                .iter()
+                .map(|p| p.to_string_lossy().to_string())
+                .collect(),
+            watch_id: "w".to_string(),
+        };
+
+        let changes = changes_from_event(&event("create", &[&existing]));
+        assert_eq!(changes[0].1, FileChangeType::Created);
+
+        let changes = changes_from_event(&event("modify", &[&existing]));
+        assert_eq!(changes[0].1, FileChangeType::Changed);
+
+        let changes = changes_from_event(&event("modify", &[&missing, &existing]));
+        assert_eq!(changes[0].1, FileChangeType::Deleted);
+        assert_eq!(changes[1].1, FileChangeType::Created);
+
+        let changes = changes_from_event(&event("other", &[&missing]));
+        assert_eq!(changes[0].1, FileChangeType::Deleted);
+    }
+
+    #[test]
+    fn root_events_are_filtered_and_keyed_by_server() {
+        let root = Path::new("/work/app");
+        let exclude = default_excludes();
+        let notify_event = |paths: &[&str]| {
+            let mut event =
+                notify::Event::new(notify::EventKind::Create(notify::event::CreateKind::File));
+            event.paths = paths.iter().map(PathBuf::from).collect();
+            event
+        };
+
+        let change = to_change_event(
+            "rust",
+            root,
+            root,
+            &exclude,
+            notify_event(&[
+                "/work/app/src/lib.rs",
+                "/work/app/node_modules/x/index.js",
+                "/work/other/main.rs",
+            ]),
+        )
+        .unwrap();
+        assert_eq!(change.watch_id, "rust");
+        assert_eq!(change.paths, vec!["/work/app/src/lib.rs".to_string()]);
+
+        let excluded = notify_event(&["/work/app/target/debug/build.rs"]);
+        assert!(to_change_event("rust", root, root, &exclude, excluded).is_none());
+
+        let mut access = notify_event(&["/work/app/src/lib.rs"]);
+        access.kind = notify::EventKind::Access(notify::event::AccessKind::Any);
+        assert!(to_change_event("rust", root, root, &exclude, access).is_none());
+
+        // Events under a symlinked root are reported on the configured path
+        let linked = notify_event(&["/private/var/app/src/lib.rs"]);
+        let change = to_change_event(
+            "rust",
+            Path::new("/private/var/app"),
+            Path::new("/var/app"),
+            &exclude,
+            linked,
+        )
+        .unwrap();
+        assert_eq!(change.paths, vec!["/var/app/src/lib.rs".to_string()]);
+
+        let mut batches = HashMap::new();
+        collect(&mut batches, &change);
+        assert_eq!(batches.len(), 1);
+        assert!(!batches["rust"].is_empty());
+    }
+}
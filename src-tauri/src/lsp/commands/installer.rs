@@ -0,0 +1,82 @@
+//! Managed language server commands
+//!
+//! Commands for listing, installing, updating and removing the language
+//! servers from the built-in catalog.
+
+use std::path::PathBuf;
+
+use tauri::{AppHandle, Emitter};
+use tracing::error;
+
+use crate::lsp::installer::{
+    self, InstallProgressEvent, LANGUAGE_SERVERS, ManagedServerInfo, ServerUpdateInfo, catalog,
+};
+
+fn entry(server_id: &str) -> Result<&'static catalog::CatalogEntry, String> {
+    catalog::find(server_id).ok_or_else(|| format!("Unknown language server: {}", server_id))
+}
+
+fn progress_emitter(app: AppHandle) -> impl Fn(InstallProgressEvent) + Send + Sync {
+    move |event| {
+        if let Err(e) = app.emit("lsp:install-progress", &event) {
+            error!("Failed to emit install progress: {}", e);
+        }
+    }
+}
+
+/// List catalog servers with their install state
+#[tauri::command]
+pub async fn lsp_installer_list() -> Result<Vec<ManagedServerInfo>, String> {
+    tokio::task::spawn_blocking(|| {
+        catalog::CATALOG
+            .iter()
+            .map(|entry| installer::status(LANGUAGE_SERVERS, entry))
+            .collect()
+    })
+    .await
+    .map_err(|e| format!("Listing task failed: {}", e))
+}
+
+/// Install (or update to) the pinned version of a server
+#[tauri::command]
+pub async fn lsp_installer_install(
+    app: AppHandle,
+    server_id: String,
+) -> Result<ManagedServerInfo, String> {
+    let entry = entry(&server_id)?;
+    let progress = progress_emitter(app);
+    installer::install(LANGUAGE_SERVERS, entry, &progress).await
+}
+
+/// Install a server from a local archive, for machines without network access
+#[tauri::command]
+pub async fn lsp_installer_install_from_archive(
+    app: AppHandle,
+    server_id: String,
+    archive_path: String,
+) -> Result<ManagedServerInfo, String> {
+    let entry = entry(&server_id)?;
+    let progress = progress_emitter(app);
+    installer::install_from_archive(
+        LANGUAGE_SERVERS,
+        entry,
+        &PathBuf::from(archive_path),
+        &progress,
+    )
+    .await
+}
+
+/// Remove an installed server
+#[tauri::command]
+pub async fn lsp_installer_uninstall(server_id: String) -> Result<(), String> {
+    let entry = entry(&server_id)?;
+    tokio::task::spawn_blocking(move || installer::uninstall(LANGUAGE_SERVERS, entry))
+        .await
+        .map_err(|e| format!("Uninstall task failed: {}", e))?
+}
+
+/// Check installed servers against the catalog and upstream releases
+#[tauri::command]
+pub async fn lsp_installer_check_updates() -> Result<Vec<ServerUpdateInfo>, String> {
+    Ok(installer::check_updates(LANGUAGE_SERVERS, catalog::CATALOG).await)
+}
@@ -13,14 +13,21 @@ pub mod completion;
 pub mod document;
 pub mod events;
 pub mod features;
+pub mod file_watchers;
 pub mod formatting;
 pub mod hierarchy;
+pub mod installer;
 pub mod multi_provider;
 pub mod navigation;
+pub mod pull_diagnostics;
+pub mod remote;
 pub mod semantic_tokens;
 pub mod server;
+pub mod server_requests;
 pub mod state;
+pub mod supervisor;
 pub mod symbols;
+pub mod trace;
 
 // Re-export the LspState for external use
 pub use state::LspState;
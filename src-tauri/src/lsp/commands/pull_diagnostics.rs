@@ -0,0 +1,138 @@
+//! Pull diagnostics scheduling
+//!
+//! Decides when to pull diagnostics from servers that support the pull model
+//! and publishes the results the same way pushed diagnostics are: as
+//! `lsp:diagnostics` events for the editor and into `DiagnosticsState` for the
+//! Problems view.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tauri::{AppHandle, Manager};
+use tracing::{debug, warn};
+
+use crate::diagnostics::{DiagnosticsState, UnifiedDiagnostic};
+use crate::lsp::client::{LspClient, PulledDiagnostics};
+use crate::lsp::types::DiagnosticsEvent;
+
+use super::state::LspState;
+
+/// Delay after the last edit before a document is pulled
+const PULL_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Publish pulled reports to the editor and the Problems view
+async fn publish(app: &AppHandle, client: &LspClient, reports: Vec<PulledDiagnostics>) {
+    let Some(lsp_state) = app.try_state::<LspState>() else {
+        return;
+    };
+    let diag_state = app.state::<DiagnosticsState>();
+
+    for report in reports {
+        let unified = report
+            .diagnostics
+            .iter()
+            .map(|d| UnifiedDiagnostic::from_lsp(&report.uri, client.name(), d))
+            .collect();
+
+        let _ = lsp_state.diagnostics_tx.send(DiagnosticsEvent {
+            server_id: client.id().to_string(),
+            uri: report.uri.clone(),
+            diagnostics: report.diagnostics,
+        });
+
+        if let Err(e) = crate::diagnostics::diagnostics_push_lsp(
+            app.clone(),
+            diag_state.clone(),
+            report.uri,
+            client.name().to_string(),
+            unified,
+        )
+        .await
+        {
+            warn!("Failed to store pulled diagnostics: {}", e);
+        }
+    }
+}
+
+/// Pull and publish diagnostics for one document
+pub(crate) async fn pull_document(app: &AppHandle, client: &LspClient, uri: &str) {
+    match client.pull_document_diagnostics(uri).await {
+        Ok(reports) => publish(app, client, reports).await,
+        Err(e) => debug!("[{}] Document diagnostic pull failed: {}", client.name(), e),
+    }
+}
+
+/// Pull and publish diagnostics for the whole workspace
+pub(crate) async fn pull_workspace(app: &AppHandle, client: &LspClient) {
+    if !client.supports_workspace_diagnostics() {
+        return;
+    }
+    match client.pull_workspace_diagnostics().await {
+        Ok(reports) => publish(app, client, reports).await,
+        Err(e) => debug!(
+            "[{}] Workspace diagnostic pull failed: {}",
+            client.name(),
+            e
+        ),
+    }
+}
+
+/// Re-pull everything, e.g. after `workspace/diagnostic/refresh`
+pub(crate) async fn refresh_all(app: &AppHandle, client: &LspClient) {
+    for uri in client.pulled_documents() {
+        pull_document(app, client, &uri).await;
+    }
+    pull_workspace(app, client).await;
+}
+
+/// Pull a document after edits settle
+///
+/// Each call supersedes earlier pending pulls for the same server and document.
+/// When the server reports inter-file dependencies, the workspace is pulled as
+/// well so dependent files are updated.
+pub(crate) fn schedule_document_pull(app: &AppHandle, client: Arc<LspClient>, uri: String) {
+    if !client.supports_pull_diagnostics() {
+        return;
+    }
+    let Some(state) = app.try_state::<LspState>() else {
+        return;
+    };
+
+    let key = (client.id().to_string(), uri.clone());
+    let generation = {
+        let mut generations = state.pull_generations.lock();
+        let generation = generations.entry(key.clone()).or_insert(0);
+        *generation += 1;
+        *generation
+    };
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(PULL_DEBOUNCE).await;
+        let current = app
+            .state::<LspState>()
+            .pull_generations
+            .lock()
+            .get(&key)
+            .copied();
+        if current != Some(generation) {
+            return;
+        }
+
+        pull_document(&app, &client, &uri).await;
+        if client.has_inter_file_dependencies() {
+            pull_workspace(&app, &client).await;
+        }
+    });
+}
+
+/// Initial workspace pull once a server is running
+pub(crate) fn on_server_started(app: &AppHandle, client: Arc<LspClient>) {
+    if !client.supports_workspace_diagnostics() {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        pull_workspace(&app, &client).await;
+    });
+}
@@ -0,0 +1,141 @@
+//! Language servers on remote hosts
+//!
+//! A server whose config names a remote target is started over an SSH exec
+//! channel on a dedicated session of an existing remote connection. Its
+//! messages pass through a `UriMap` so the frontend keeps using local virtual
+//! paths while the server sees paths on the remote host.
+
+use tauri::AppHandle;
+
+use crate::lsp::client::ServerTransport;
+use crate::lsp::types::{LanguageServerConfig, RemoteServerTarget};
+
+/// Quote a word for a POSIX shell
+pub(crate) fn shell_quote(word: &str) -> String {
+    if !word.is_empty()
+        && word.chars().all(|c| {
+            c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '/' | '=' | ':' | ',')
+        })
+    {
+        return word.to_string();
+    }
+    format!("'{}'", word.replace('\'', r"'\''"))
+}
+
+/// Command line that starts the server from the remote root
+///
+/// A login shell is used so the server binary is found on the user's `PATH`.
+fn remote_command_line(config: &LanguageServerConfig, target: &RemoteServerTarget) -> String {
+    let mut script = format!(
+        "cd {} && exec {}",
+        shell_quote(&target.remote_root),
+        shell_quote(&config.command)
+    );
+    for arg in &config.args {
+        script.push(' ');
+        script.push_str(&shell_quote(arg));
+    }
+    format!("sh -lc {}", shell_quote(&script))
+}
+
+#[cfg(feature = "remote-ssh")]
+mod ssh {
+    use crate::lsp::client::ServerProcess;
+    use crate::remote::exec_channel::ExecHandle;
+
+    impl ServerProcess for ExecHandle {
+        fn kill(&mut self) {
+            self.close();
+        }
+    }
+}
+
+/// Open a transport to the server on its remote host
+#[cfg(feature = "remote-ssh")]
+pub(crate) async fn spawn_remote(
+    app: &AppHandle,
+    config: &LanguageServerConfig,
+    target: &RemoteServerTarget,
+) -> Result<ServerTransport, String> {
+    use std::sync::Arc;
+
+    use tauri::Manager;
+    use tracing::info;
+
+    use crate::lsp::client::UriMap;
+    use crate::remote::{RemoteManager, exec_channel};
+
+    let manager = app.state::<Arc<RemoteManager>>().inner().clone();
+    let session = manager
+        .open_session(&target.connection_id)
+        .await
+        .map_err(|e| format!("Failed to open remote session: {}", e))?;
+
+    let command = remote_command_line(config, target);
+    info!(
+        "Starting language server {} on {}: {}",
+        config.name, target.connection_id, command
+    );
+    let process = tokio::task::spawn_blocking(move || exec_channel::spawn(session, &command))
+        .await
+        .map_err(|e| format!("Remote spawn task failed: {}", e))?
+        .map_err(|e| format!("Failed to start remote language server: {}", e))?;
+
+    Ok(ServerTransport::new(
+        Box::new(process.stdin),
+        Box::new(process.stdout),
+        Box::new(process.stderr),
+        Box::new(process.handle),
+    )
+    .with_uri_map(UriMap::new(&config.root_path, &target.remote_root)))
+}
+
+#[cfg(not(feature = "remote-ssh"))]
+pub(crate) async fn spawn_remote(
+    _app: &AppHandle,
+    config: &LanguageServerConfig,
+    _target: &RemoteServerTarget,
+) -> Result<ServerTransport, String> {
+    let _ = remote_command_line;
+    Err(format!(
+        "Cannot start {} remotely: SSH support is not enabled in this build",
+        config.name
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_shell_words() {
+        assert_eq!(shell_quote("rust-analyzer"), "rust-analyzer");
+        assert_eq!(shell_quote("--stdio"), "--stdio");
+        assert_eq!(shell_quote("my proj"), "'my proj'");
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+        assert_eq!(shell_quote(""), "''");
+    }
+
+    #[test]
+    fn builds_login_shell_command() {
+        let config = LanguageServerConfig {
+            id: "ra".to_string(),
+            name: "rust-analyzer".to_string(),
+            command: "rust-analyzer".to_string(),
+            args: vec!["--log-file".to_string(), "/tmp/ra log".to_string()],
+            root_path: "/local/proj".to_string(),
+            file_extensions: vec!["rs".to_string()],
+            language_id: "rust".to_string(),
+            remote: None,
+            trace: false,
+        };
+        let target = RemoteServerTarget {
+            connection_id: "box".to_string(),
+            remote_root: "/srv/proj".to_string(),
+        };
+        assert_eq!(
+            remote_command_line(&config, &target),
+            r"sh -lc 'cd /srv/proj && exec rust-analyzer --log-file '\''/tmp/ra log'\'''"
+        );
+    }
+}
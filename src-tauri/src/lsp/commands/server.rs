@@ -4,19 +4,70 @@
 
 use std::sync::Arc;
 
-use tauri::State;
+use tauri::{AppHandle, State};
 use tracing::{error, info};
 
 use crate::lsp::client::LspClient;
 use crate::lsp::types::{LanguageServerConfig, ServerInfo};
 
+use super::file_watchers;
+use super::pull_diagnostics;
+use super::remote;
+use super::server_requests::install_server_request_handlers;
 use super::state::LspState;
+use super::supervisor;
+
+/// Start the server process, locally or on its remote host
+async fn create_client(
+    app: &AppHandle,
+    state: &LspState,
+    config: LanguageServerConfig,
+) -> Result<LspClient, String> {
+    let diagnostics_tx = Some(state.diagnostics_tx.clone());
+    let client = match config.remote.clone() {
+        Some(target) => {
+            let transport = remote::spawn_remote(app, &config, &target).await?;
+            LspClient::with_transport(config, transport, diagnostics_tx)
+        }
+        None => LspClient::new(config, diagnostics_tx),
+    };
+    client.map_err(|e| format!("Failed to start language server: {}", e))
+}
+
+/// Start and initialize a server for `config`
+pub(super) async fn launch(
+    app: &AppHandle,
+    state: &LspState,
+    config: LanguageServerConfig,
+) -> Result<LspClient, String> {
+    let client = create_client(app, state, config).await?;
+    install_server_request_handlers(&client, app);
+
+    client
+        .initialize()
+        .await
+        .map_err(|e| format!("Failed to initialize language server: {}", e))?;
+    Ok(client)
+}
+
+/// Store an initialized client and start its watchers
+pub(super) fn activate(app: &AppHandle, state: &LspState, client: LspClient) -> Arc<LspClient> {
+    let client = Arc::new(client);
+    state
+        .clients
+        .lock()
+        .insert(client.id().to_string(), client.clone());
+    file_watchers::on_server_started(app, &client);
+    pull_diagnostics::on_server_started(app, client.clone());
+    client
+}
 
 /// Start a language server
 #[tauri::command]
 pub async fn lsp_start_server(
     config: LanguageServerConfig,
     state: State<'_, LspState>,
+    app: AppHandle,
 ) -> Result<ServerInfo, String> {
     let server_id = config.id.clone();
     let language_id = config.language_id.clone();
@@ -29,21 +80,10 @@ pub async fn lsp_start_server(
         }
     }
 
-    // Create and start the client
-    let client = LspClient::new(config, Some(state.diagnostics_tx.clone()))
-        .map_err(|e| format!("Failed to start language server: {}", e))?;
-
-    // Initialize the server
-    client
-        .initialize()
-        .await
-        .map_err(|e| format!("Failed to initialize language server: {}", e))?;
-
+    // Create, initialize and store the client
+    let client = activate(&app, &state, launch(&app, &state, config).await?);
+    supervisor::supervise(app.clone(), client.clone());
     let info = client.info();
-    let client = Arc::new(client);
-
-    // Store the client
-    state.clients.lock().insert(server_id.clone(), client);
 
     // Register client for language (multi-provider support)
     state.register_client_for_language(&language_id, &server_id);
@@ -57,13 +97,18 @@ pub async fn lsp_start_server(
 
 /// Stop a language server
 #[tauri::command]
-pub async fn lsp_stop_server(server_id: String, state: State<'_, LspState>) -> Result<(), String> {
+pub async fn lsp_stop_server(
+    server_id: String,
+    state: State<'_, LspState>,
+    app: AppHandle,
+) -> Result<(), String> {
     // Unregister from language mapping first
     state.unregister_client(&server_id);
 
     let client = state.clients.lock().remove(&server_id);
 
     if let Some(client) = client {
+        file_watchers::on_server_stopped(&app, &server_id);
         client
             .shutdown()
             .await
@@ -75,13 +120,17 @@ pub async fn lsp_stop_server(server_id: String, state: State<'_, LspState>) -> R
 
 /// Stop all language servers
 #[tauri::command]
-pub async fn lsp_stop_all_servers(state: State<'_, LspState>) -> Result<(), String> {
+pub async fn lsp_stop_all_servers(
+    state: State<'_, LspState>,
+    app: AppHandle,
+) -> Result<(), String> {
     // Clear language mapping
     state.language_clients.lock().clear();
 
     let clients: Vec<_> = state.clients.lock().drain().collect();
 
-    for (_, client) in clients {
+    for (server_id, client) in clients {
+        file_watchers::on_server_stopped(&app, &server_id);
         if let Err(e) = client.shutdown().await {
             error!("Failed to shutdown server: {}", e);
         }
@@ -107,47 +156,32 @@ pub fn lsp_get_server_info(server_id: String, state: State<'_, LspState>) -> Opt
 pub async fn lsp_restart(
     server_id: String,
     state: State<'_, LspState>,
+    app: AppHandle,
 ) -> Result<ServerInfo, String> {
-    // Get the current client's config
-    let config = {
-        let clients = state.clients.lock();
-        let client = clients
-            .get(&server_id)
-            .ok_or_else(|| format!("Server not found: {}", server_id))?;
-        client.config.clone()
-    };
-
     // Stop the server
-    {
-        let client = state.clients.lock().remove(&server_id);
-        if let Some(client) = client {
-            client
-                .shutdown()
-                .await
-                .map_err(|e| format!("Failed to shutdown server: {}", e))?;
-        }
-    }
-
-    // Start a new server with the same config
-    let client = LspClient::new(config.clone(), Some(state.diagnostics_tx.clone()))
-        .map_err(|e| format!("Failed to start language server: {}", e))?;
-
-    // Initialize the server
-    client
-        .initialize()
+    let previous = state
+        .clients
+        .lock()
+        .remove(&server_id)
+        .ok_or_else(|| format!("Server not found: {}", server_id))?;
+    file_watchers::on_server_stopped(&app, &server_id);
+    previous
+        .shutdown()
         .await
-        .map_err(|e| format!("Failed to initialize language server: {}", e))?;
+        .map_err(|e| format!("Failed to shutdown server: {}", e))?;
 
-    let info = client.info();
-    let client = Arc::new(client);
-
-    // Store the client
-    state.clients.lock().insert(server_id, client);
+    // Start a new server with the same config and open documents
+    let client = launch(&app, &state, previous.config.clone()).await?;
+    if let Err(e) = client.restore_from(&previous) {
+        error!("Failed to restore documents for {}: {}", server_id, e);
+    }
 
-    Ok(info)
+    let client = activate(&app, &state, client);
+    supervisor::supervise(app.clone(), client.clone());
+    Ok(client.info())
 }
 
-/// Get server logs
+/// Get server logs, including crash reports with the server's last stderr output
 #[tauri::command]
 pub fn lsp_get_logs(server_id: String, state: State<'_, LspState>) -> Result<Vec<String>, String> {
     // Check if server exists
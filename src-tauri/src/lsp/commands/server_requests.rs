@@ -0,0 +1,615 @@
+//! Server-to-client request handlers
+//!
+//! Handlers for requests that need application state: configuration is
+//! answered from workspace settings, edits are written through
+//! `fs::workspace_edit` (or the connection's SFTP for remote servers), message
+//! requests are forwarded to the frontend, and
+//! diagnostic refresh requests trigger a re-pull.
+//! `$/progress` notifications are re-emitted as `lsp:progress` events.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde_json::{Value, json};
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::sync::oneshot;
+use tracing::{debug, warn};
+
+use crate::fs::types::{TextEdit, TextEditPosition, TextEditRange};
+use crate::lsp::client::{INVALID_PARAMS, LspClient, LspResponseError, REQUEST_FAILED};
+use crate::lsp::types::{ProgressEvent, ShowMessageRequestEvent, WorkspaceEditAppliedEvent};
+use crate::remote::RemoteManager;
+
+use super::pull_diagnostics;
+use super::state::LspState;
+
+/// Install the application-level server request handlers on a new client
+///
+/// Must run before `initialize`, since servers start asking for configuration
+/// and progress tokens as soon as they are initialized.
+pub(crate) fn install_server_request_handlers(client: &LspClient, app: &AppHandle) {
+    let server_id = client.id().to_string();
+    let root_path = client.config.root_path.clone();
+
+    let config_app = app.clone();
+    client.on_request("workspace/configuration", move |params| {
+        let app = config_app.clone();
+        let root_path = root_path.clone();
+        async move { configuration(params, &root_path, app).await }
+    });
+
+    let edit_app = app.clone();
+    let edit_server = server_id.clone();
+    let edit_remote = RemoteFiles::for_client(client, app).map(Arc::new);
+    client.on_request("workspace/applyEdit", move |params| {
+        let app = edit_app.clone();
+        let server_id = edit_server.clone();
+        let remote = edit_remote.clone();
+        async move { apply_edit(params, &server_id, remote.as_deref(), &app).await }
+    });
+
+    let message_app = app.clone();
+    let message_server = server_id.clone();
+    client.on_request("window/showMessageRequest", move |params| {
+        let app = message_app.clone();
+        let server_id = message_server.clone();
+        async move { show_message_request(params, server_id, &app).await }
+    });
+
+    let refresh_app = app.clone();
+    let refresh_server = server_id.clone();
+    client.on_request("workspace/diagnostic/refresh", move |_| {
+        let app = refresh_app.clone();
+        let server_id = refresh_server.clone();
+        async move {
+            // Answer right away; the server must not wait for the re-pull.
+            let client = app
+                .state::<LspState>()
+                .clients
+                .lock()
+                .get(&server_id)
+                .cloned();
+            if let Some(client) = client {
+                tauri::async_runtime::spawn(async move {
+                    pull_diagnostics::refresh_all(&app, &client).await;
+                });
+            }
+            Ok(Value::Null)
+        }
+    });
+
+    let progress_app = app.clone();
+    client.on_notification("$/progress", move |_, params| {
+        let event = ProgressEvent {
+            server_id: server_id.clone(),
+            token: params.get("token").cloned().unwrap_or(Value::Null),
+            value: params.get("value").cloned().unwrap_or(Value::Null),
+        };
+        if let Err(e) = progress_app.emit("lsp:progress", &event) {
+            warn!("Failed to emit progress event: {}", e);
+        }
+    });
+}
+
+/// Convert a `file://` URI to a filesystem path
+fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    url::Url::parse(uri).ok()?.to_file_path().ok()
+}
+
+fn request_failed(message: impl Into<String>) -> LspResponseError {
+    LspResponseError::new(REQUEST_FAILED, message)
+}
+
+// ============================================================================
+// workspace/configuration
+// ============================================================================
+
+async fn configuration(
+    params: Value,
+    root_path: &str,
+    app: AppHandle,
+) -> Result<Value, LspResponseError> {
+    let items = params
+        .get("items")
+        .and_then(|i| i.as_array())
+        .ok_or_else(|| LspResponseError::new(INVALID_PARAMS, "Missing configuration items"))?;
+
+    let mut results = Vec::with_capacity(items.len());
+    for item in items {
+        let file_path = item
+            .get("scopeUri")
+            .and_then(|u| u.as_str())
+            .and_then(uri_to_path)
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_else(|| root_path.to_string());
+
+        let settings = crate::workspace_settings::settings_resolve_for_file(
+            file_path,
+            vec![root_path.to_string()],
+            Some(root_path.to_string()),
+            None,
+            app.clone(),
+        )
+        .await
+        .map_err(request_failed)?;
+
+        let section = item.get("section").and_then(|s| s.as_str());
+        results.push(crate::workspace_settings::configuration_section(
+            &settings, section,
+        ));
+    }
+
+    Ok(Value::Array(results))
+}
+
+// ============================================================================
+// workspace/applyEdit
+// ============================================================================
+
+fn parse_position(value: &Value) -> Option<TextEditPosition> {
+    Some(TextEditPosition {
+        line: value.get("line")?.as_u64()? as u32,
+        character: value.get("character")?.as_u64()? as u32,
+    })
+}
+
+/// Convert LSP `TextEdit`s (or `AnnotatedTextEdit`s) to `fs::types::TextEdit`
+fn parse_text_edits(edits: &Value) -> Result<Vec<TextEdit>, String> {
+    edits
+        .as_array()
+        .ok_or("Expected an array of text edits")?
+        .iter()
+        .map(|edit| {
+            let range = edit.get("range").ok_or("Text edit without range")?;
+            Ok(TextEdit {
+                range: TextEditRange {
+                    start: range
+                        .get("start")
+                        .and_then(parse_position)
+                        .ok_or("Invalid range start")?,
+                    end: range
+                        .get("end")
+                        .and_then(parse_position)
+                        .ok_or("Invalid range end")?,
+                },
+                new_text: edit
+                    .get("newText")
+                    .and_then(|t| t.as_str())
+                    .unwrap_or_default()
+                    .to_string(),
+            })
+        })
+        .collect()
+}
+
+fn option_flag(change: &Value, name: &str) -> bool {
+    change
+        .get("options")
+        .and_then(|o| o.get(name))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+}
+
+fn change_path(change: &Value, field: &str) -> Result<PathBuf, String> {
+    change
+        .get(field)
+        .and_then(|u| u.as_str())
+        .and_then(uri_to_path)
+        .ok_or_else(|| format!("Invalid or missing '{}'", field))
+}
+
+async fn apply_text_edits(uri: &str, edits: &Value) -> Result<(), String> {
+    let path = uri_to_path(uri).ok_or_else(|| format!("Unsupported URI: {}", uri))?;
+    let edits = parse_text_edits(edits)?;
+    let path = path.to_string_lossy().to_string();
+    crate::fs::workspace_edit::apply_workspace_edit(path, edits).await
+}
+
+async fn create_file(change: &Value) -> Result<(), String> {
+    let path = change_path(change, "uri")?;
+    if path.exists() && !option_flag(change, "overwrite") {
+        if option_flag(change, "ignoreIfExists") {
+            return Ok(());
+        }
+        return Err(format!("File already exists: {}", path.display()));
+    }
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| format!("Failed to create directory: {}", e))?;
+    }
+    tokio::fs::write(&path, "")
+        .await
+        .map_err(|e| format!("Failed to create '{}': {}", path.display(), e))
+}
+
+async fn rename_file(change: &Value) -> Result<(), String> {
+    let old_path = change_path(change, "oldUri")?;
+    let new_path = change_path(change, "newUri")?;
+    if new_path.exists() && !option_flag(change, "overwrite") {
+        if option_flag(change, "ignoreIfExists") {
+            return Ok(());
+        }
+        return Err(format!("File already exists: {}", new_path.display()));
+    }
+    if let Some(parent) = new_path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| format!("Failed to create directory: {}", e))?;
+    }
+    tokio::fs::rename(&old_path, &new_path)
+        .await
+        .map_err(|e| format!("Failed to rename '{}': {}", old_path.display(), e))
+}
+
+async fn delete_file(change: &Value) -> Result<(), String> {
+    let path = change_path(change, "uri")?;
+    if !path.exists() {
+        if option_flag(change, "ignoreIfNotExists") {
+            return Ok(());
+        }
+        return Err(format!("File not found: {}", path.display()));
+    }
+    let result = if Path::new(&path).is_dir() {
+        if option_flag(change, "recursive") {
+            tokio::fs::remove_dir_all(&path).await
+        } else {
+            tokio::fs::remove_dir(&path).await
+        }
+    } else {
+        tokio::fs::remove_file(&path).await
+    };
+    result.map_err(|e| format!("Failed to delete '{}': {}", path.display(), e))
+}
+
+/// Workspace of a remote server, written through its connection's SFTP
+///
+/// Edits arrive with local virtual paths under `local_root`; they are mapped
+/// onto `remote_root` on the host.
+struct RemoteFiles {
+    manager: Arc<RemoteManager>,
+    connection_id: String,
+    local_root: PathBuf,
+    remote_root: String,
+}
+
+impl RemoteFiles {
+    fn for_client(client: &LspClient, app: &AppHandle) -> Option<Self> {
+        let target = client.config.remote.as_ref()?;
+        let manager = app.try_state::<Arc<RemoteManager>>()?.inner().clone();
+        Some(Self {
+            manager,
+            connection_id: target.connection_id.clone(),
+            local_root: PathBuf::from(&client.config.root_path),
+            remote_root: target.remote_root.clone(),
+        })
+    }
+
+    /// Path on the remote host for a local virtual path
+    fn remote_path(&self, path: &Path) -> Result<String, String> {
+        let relative = path
+            .strip_prefix(&self.local_root)
+            .map_err(|_| format!("'{}' is outside the remote workspace", path.display()))?;
+        let mut remote = self.remote_root.trim_end_matches('/').to_string();
+        for component in relative.components() {
+            remote.push('/');
+            remote.push_str(&component.as_os_str().to_string_lossy());
+        }
+        Ok(remote)
+    }
+
+    async fn exists(&self, path: &str) -> bool {
+        self.manager.stat(&self.connection_id, path).await.is_ok()
+    }
+
+    async fn create_parent(&self, path: &str) -> Result<(), String> {
+        match path.rsplit_once('/') {
+            Some((parent, _)) if !parent.is_empty() => Ok(self
+                .manager
+                .create_directory(&self.connection_id, parent, true)
+                .await?),
+            _ => Ok(()),
+        }
+    }
+
+    async fn apply_text_edits(&self, uri: &str, edits: &Value) -> Result<(), String> {
+        let path = uri_to_path(uri).ok_or_else(|| format!("Unsupported URI: {}", uri))?;
+        let path = self.remote_path(&path)?;
+        let edits = parse_text_edits(edits)?;
+        let content = self.manager.read_file(&self.connection_id, &path).await?;
+        let content = crate::fs::workspace_edit::apply_text_edits(&content, edits)?;
+        Ok(self
+            .manager
+            .write_file(&self.connection_id, &path, &content)
+            .await?)
+    }
+
+    async fn create_file(&self, change: &Value) -> Result<(), String> {
+        let path = self.remote_path(&change_path(change, "uri")?)?;
+        if self.exists(&path).await && !option_flag(change, "overwrite") {
+            if option_flag(change, "ignoreIfExists") {
+                return Ok(());
+            }
+            return Err(format!("File already exists: {}", path));
+        }
+        self.create_parent(&path).await?;
+        Ok(self
+            .manager
+            .write_file(&self.connection_id, &path, "")
+            .await?)
+    }
+
+    async fn rename_file(&self, change: &Value) -> Result<(), String> {
+        let old_path = self.remote_path(&change_path(change, "oldUri")?)?;
+        let new_path = self.remote_path(&change_path(change, "newUri")?)?;
+        if self.exists(&new_path).await && !option_flag(change, "overwrite") {
+            if option_flag(change, "ignoreIfExists") {
+                return Ok(());
+            }
+            return Err(format!("File already exists: {}", new_path));
+        }
+        self.create_parent(&new_path).await?;
+        Ok(self
+            .manager
+            .rename(&self.connection_id, &old_path, &new_path)
+            .await?)
+    }
+
+    async fn delete_file(&self, change: &Value) -> Result<(), String> {
+        let path = self.remote_path(&change_path(change, "uri")?)?;
+        if !self.exists(&path).await {
+            if option_flag(change, "ignoreIfNotExists") {
+                return Ok(());
+            }
+            return Err(format!("File not found: {}", path));
+        }
+        Ok(self
+            .manager
+            .delete(&self.connection_id, &path, option_flag(change, "recursive"))
+            .await?)
+    }
+}
+
+/// Apply a `WorkspaceEdit`, recording the URIs touched so far
+///
+/// Changes are applied in order and stop at the first failure, as LSP
+/// `failureHandling: abort` describes. Remote servers' edits go to `remote`.
+async fn apply_workspace_edit(
+    edit: &Value,
+    remote: Option<&RemoteFiles>,
+    touched: &mut Vec<String>,
+) -> Result<(), String> {
+    if let Some(changes) = edit.get("documentChanges").and_then(|c| c.as_array()) {
+        for change in changes {
+            match (change.get("kind").and_then(|k| k.as_str()), remote) {
+                (Some("create"), Some(remote)) => remote.create_file(change).await?,
+                (Some("create"), None) => create_file(change).await?,
+                (Some("rename"), Some(remote)) => remote.rename_file(change).await?,
+                (Some("rename"), None) => rename_file(change).await?,
+                (Some("delete"), Some(remote)) => remote.delete_file(change).await?,
+                (Some("delete"), None) => delete_file(change).await?,
+                (Some(kind), _) => {
+                    return Err(format!("Unsupported resource operation: {}", kind));
+                }
+                (None, _) => {
+                    let uri = change
+                        .get("textDocument")
+                        .and_then(|d| d.get("uri"))
+                        .and_then(|u| u.as_str())
+                        .ok_or("Text document edit without URI")?;
+                    let edits = change.get("edits").cloned().unwrap_or(json!([]));
+                    match remote {
+                        Some(remote) => remote.apply_text_edits(uri, &edits).await?,
+                        None => apply_text_edits(uri, &edits).await?,
+                    }
+                }
+            }
+
+            for field in ["uri", "newUri"] {
+                if let Some(uri) = change.get(field).and_then(|u| u.as_str()) {
+                    touched.push(uri.to_string());
+                }
+            }
+            if let Some(uri) = change
+                .get("textDocument")
+                .and_then(|d| d.get("uri"))
+                .and_then(|u| u.as_str())
+            {
+                touched.push(uri.to_string());
+            }
+        }
+    } else if let Some(changes) = edit.get("changes").and_then(|c| c.as_object()) {
+        for (uri, edits) in changes {
+            match remote {
+                Some(remote) => remote.apply_text_edits(uri, edits).await?,
+                None => apply_text_edits(uri, edits).await?,
+            }
+            touched.push(uri.clone());
+        }
+    }
+    Ok(())
+}
+
+async fn apply_edit(
+    params: Value,
+    server_id: &str,
+    remote: Option<&RemoteFiles>,
+    app: &AppHandle,
+) -> Result<Value, LspResponseError> {
+    let edit = params
+        .get("edit")
+        .ok_or_else(|| LspResponseError::new(INVALID_PARAMS, "Missing edit"))?;
+    let label = params
+        .get("label")
+        .and_then(|l| l.as_str())
+        .map(str::to_string);
+
+    let mut uris = Vec::new();
+    let outcome = apply_workspace_edit(edit, remote, &mut uris).await;
+    uris.dedup();
+
+    let event = WorkspaceEditAppliedEvent {
+        server_id: server_id.to_string(),
+        label,
+        uris,
+        applied: outcome.is_ok(),
+        failure_reason: outcome.as_ref().err().cloned(),
+    };
+    if let Err(e) = app.emit("lsp:workspace-edit-applied", &event) {
+        warn!("Failed to emit workspace edit event: {}", e);
+    }
+
+    Ok(match outcome {
+        Ok(()) => json!({ "applied": true }),
+        Err(reason) => {
+            warn!("[{}] workspace/applyEdit failed: {}", server_id, reason);
+            json!({ "applied": false, "failureReason": reason })
+        }
+    })
+}
+
+// ============================================================================
+// window/showMessageRequest
+// ============================================================================
+
+async fn show_message_request(
+    params: Value,
+    server_id: String,
+    app: &AppHandle,
+) -> Result<Value, LspResponseError> {
+    let request_id = uuid::Uuid::new_v4().to_string();
+    let actions: Vec<String> = params
+        .get("actions")
+        .and_then(|a| a.as_array())
+        .map(|actions| {
+            actions
+                .iter()
+                .filter_map(|a| a.get("title").and_then(|t| t.as_str()))
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let (tx, rx) = oneshot::channel();
+    app.state::<LspState>()
+        .pending_message_requests
+        .lock()
+        .insert(request_id.clone(), tx);
+
+    let event = ShowMessageRequestEvent {
+        server_id,
+        request_id: request_id.clone(),
+        message_type: params.get("type").and_then(|t| t.as_u64()).unwrap_or(3) as u8,
+        message: params
+            .get("message")
+            .and_then(|m| m.as_str())
+            .unwrap_or_default()
+            .to_string(),
+        actions,
+    };
+    if let Err(e) = app.emit("lsp:show-message-request", &event) {
+        app.state::<LspState>()
+            .pending_message_requests
+            .lock()
+            .remove(&request_id);
+        return Err(request_failed(format!("Failed to show message: {}", e)));
+    }
+
+    // A dropped sender (e.g. server stopped) counts as a dismissal.
+    let choice = rx.await.unwrap_or(None);
+    debug!("Message request {} answered with {:?}", request_id, choice);
+    Ok(match choice {
+        Some(title) => json!({ "title": title }),
+        None => Value::Null,
+    })
+}
+
+/// Answer a `window/showMessageRequest`; `None` means the user dismissed it
+#[tauri::command]
+pub fn lsp_respond_show_message(
+    request_id: String,
+    action: Option<String>,
+    state: State<'_, LspState>,
+) -> Result<(), String> {
+    let tx = state
+        .pending_message_requests
+        .lock()
+        .remove(&request_id)
+        .ok_or_else(|| format!("Unknown message request: {}", request_id))?;
+    let _ = tx.send(action);
+    Ok(())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_lsp_text_edits() {
+        let edits = json!([{
+            "range": {"start": {"line": 1, "character": 2}, "end": {"line": 1, "character": 5}},
+            "newText": "abc"
+        }]);
+        let parsed = parse_text_edits(&edits).unwrap();
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].range.start.line, 1);
+        assert_eq!(parsed[0].range.end.character, 5);
+        assert_eq!(parsed[0].new_text, "abc");
+    }
+
+    #[test]
+    fn rejects_edit_without_range() {
+        assert!(parse_text_edits(&json!([{ "newText": "x" }])).is_err());
+    }
+
+    #[tokio::test]
+    async fn applies_changes_and_resource_operations() {
+        let dir = tempfile::tempdir().unwrap();
+        let existing = dir.path().join("a.txt");
+        std::fs::write(&existing, "hello world\n").unwrap();
+        let existing_uri = url::Url::from_file_path(&existing).unwrap().to_string();
+        let created_uri = url::Url::from_file_path(dir.path().join("new/b.txt"))
+            .unwrap()
+            .to_string();
+
+        let edit = json!({
+            "documentChanges": [
+                {"textDocument": {"uri": existing_uri, "version": 1}, "edits": [{
+                    "range": {"start": {"line": 0, "character": 0}, "end": {"line": 0, "character": 5}},
+                    "newText": "goodbye"
+                }]},
+                {"kind": "create", "uri": created_uri}
+            ]
+        });
+        let mut touched = Vec::new();
+        apply_workspace_edit(&edit, None, &mut touched)
+            .await
+            .unwrap();
+
+        assert!(
+            std::fs::read_to_string(&existing)
+                .unwrap()
+                .starts_with("goodbye world")
+        );
+        assert!(dir.path().join("new/b.txt").exists());
+        assert_eq!(touched, vec![existing_uri, created_uri]);
+    }
+
+    #[test]
+    fn maps_local_paths_onto_the_remote_root() {
+        let remote = RemoteFiles {
+            manager: Arc::new(RemoteManager::new()),
+            connection_id: "c".to_string(),
+            local_root: PathBuf::from("/local/ws"),
+            remote_root: "/srv/ws/".to_string(),
+        };
+        assert_eq!(
+            remote
+                .remote_path(Path::new("/local/ws/src/main.rs"))
+                .unwrap(),
+            "/srv/ws/src/main.rs"
+        );
+        assert!(remote.remote_path(Path::new("/local/other.rs")).is_err());
+    }
+}
@@ -7,9 +7,11 @@ use std::collections::HashMap;
 use std::sync::Arc;
 
 use chrono::Local;
+use notify::RecommendedWatcher;
 use parking_lot::Mutex;
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, oneshot};
 
+use crate::fs::types::FileChangeEvent;
 use crate::lsp::client::LspClient;
 use crate::lsp::types::DiagnosticsEvent;
 
@@ -26,17 +28,32 @@ pub struct LspState {
     server_logs: Mutex<HashMap<String, Vec<String>>>,
     pub(crate) diagnostics_tx: mpsc::UnboundedSender<DiagnosticsEvent>,
     diagnostics_rx: Mutex<Option<mpsc::UnboundedReceiver<DiagnosticsEvent>>>,
+    /// Map: request_id -> reply channel for pending `window/showMessageRequest`s
+    pub(crate) pending_message_requests: Mutex<HashMap<String, oneshot::Sender<Option<String>>>>,
+    /// Map: (server_id, uri) -> generation of the latest scheduled diagnostic pull
+    pub(crate) pull_generations: Mutex<HashMap<(String, String), u64>>,
+    /// Map: server_id -> watcher on the server's root (owned by the LSP, not the frontend)
+    pub(crate) root_watchers: Mutex<HashMap<String, RecommendedWatcher>>,
+    /// Change events from `root_watchers`, with `watch_id` set to the server id
+    pub(crate) root_changes_tx: mpsc::UnboundedSender<FileChangeEvent>,
+    root_changes_rx: Mutex<Option<mpsc::UnboundedReceiver<FileChangeEvent>>>,
 }
 
 impl LspState {
     pub fn new() -> Self {
         let (tx, rx) = mpsc::unbounded_channel();
+        let (root_changes_tx, root_changes_rx) = mpsc::unbounded_channel();
         Self {
             clients: Mutex::new(HashMap::new()),
             language_clients: Mutex::new(HashMap::new()),
             server_logs: Mutex::new(HashMap::new()),
             diagnostics_tx: tx,
             diagnostics_rx: Mutex::new(Some(rx)),
+            pending_message_requests: Mutex::new(HashMap::new()),
+            pull_generations: Mutex::new(HashMap::new()),
+            root_watchers: Mutex::new(HashMap::new()),
+            root_changes_tx,
+            root_changes_rx: Mutex::new(Some(root_changes_rx)),
         }
     }
 
@@ -70,6 +87,13 @@ impl LspState {
         self.diagnostics_rx.lock().take()
     }
 
+    /// Take the root watcher change receiver (can only be called once)
+    pub(crate) fn take_root_changes_receiver(
+        &self,
+    ) -> Option<mpsc::UnboundedReceiver<FileChangeEvent>> {
+        self.root_changes_rx.lock().take()
+    }
+
     /// Stop all language servers synchronously (for cleanup on exit)
     pub fn stop_all_servers(&self) -> Result<(), String> {
         // Clear language mapping
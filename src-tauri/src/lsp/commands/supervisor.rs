@@ -0,0 +1,250 @@
+//! Language server supervision
+//!
+//! Every started server gets a supervisor task. When the server exits or stops
+//! answering requests, the crash is written to the server log together with
+//! the tail of its stderr, and a replacement is started with exponential
+//! backoff. The replacement re-opens the documents that were open and inherits
+//! the dynamic registrations. A server that keeps crashing is left stopped once
+//! it has used up its restarts for the window.
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{error, info, warn};
+
+use crate::lsp::client::{LspClient, ServerFault};
+use crate::lsp::types::{ServerStatus, ServerStatusEvent};
+
+use super::server;
+use super::state::LspState;
+
+/// Restarts allowed per server within `RESTART_WINDOW`
+const MAX_RESTARTS: usize = 5;
+/// Window over which restarts are counted
+const RESTART_WINDOW: Duration = Duration::from_secs(3 * 60);
+/// Delay before the first restart; doubled for each further one
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+/// Upper bound on the restart delay
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Restarts of one server within the restart window
+#[derive(Default)]
+struct RestartHistory {
+    restarts: VecDeque<Instant>,
+}
+
+impl RestartHistory {
+    /// Record a restart at `now` and return how long to wait before it
+    ///
+    /// Returns `None` when the server has used up its restarts.
+    fn next_delay(&mut self, now: Instant) -> Option<Duration> {
+        while self
+            .restarts
+            .front()
+            .is_some_and(|t| now.duration_since(*t) > RESTART_WINDOW)
+        {
+            self.restarts.pop_front();
+        }
+        if self.restarts.len() >= MAX_RESTARTS {
+            return None;
+        }
+
+        let delay = INITIAL_BACKOFF
+            .saturating_mul(1 << self.restarts.len())
+            .min(MAX_BACKOFF);
+        self.restarts.push_back(now);
+        Some(delay)
+    }
+
+    fn count(&self) -> usize {
+        self.restarts.len()
+    }
+}
+
+fn emit_status(app: &AppHandle, event: ServerStatusEvent) {
+    if let Err(e) = app.emit("lsp:server-status", &event) {
+        error!("Failed to emit server status: {}", e);
+    }
+}
+
+/// Whether `client` is still the one registered under its id
+fn is_current(state: &LspState, client: &Arc<LspClient>) -> bool {
+    state
+        .clients
+        .lock()
+        .get(client.id())
+        .is_some_and(|current| Arc::ptr_eq(current, client))
+}
+
+/// Start a replacement for a crashed server
+async fn replace(
+    app: &AppHandle,
+    state: &LspState,
+    crashed: &Arc<LspClient>,
+) -> Result<Arc<LspClient>, String> {
+    let client = server::launch(app, state, crashed.config.clone()).await?;
+    if !is_current(state, crashed) {
+        // Stopped or restarted by the user while this one was starting
+        let _ = client.shutdown().await;
+        return Err("Server was replaced during restart".to_string());
+    }
+
+    let reopened = client
+        .restore_from(crashed)
+        .map_err(|e| format!("Failed to re-open documents: {}", e))?;
+    info!(
+        "Restarted language server {} ({} documents re-opened)",
+        crashed.id(),
+        reopened
+    );
+    Ok(server::activate(app, state, client))
+}
+
+/// Log a crash with the server's last stderr output
+fn record_crash(
+    app: &AppHandle,
+    state: &LspState,
+    client: &LspClient,
+    restart_count: usize,
+    fault: ServerFault,
+) {
+    let message = format!("{} {}", client.name(), fault);
+    warn!("Language server {}", message);
+    state.add_log(client.id(), format!("Crash: {}", message));
+    for line in client.stderr_tail() {
+        state.add_log(client.id(), format!("stderr: {}", line));
+    }
+    emit_status(
+        app,
+        ServerStatusEvent {
+            server_id: client.id().to_string(),
+            status: ServerStatus::Error,
+            restart_count,
+            message: Some(message),
+        },
+    );
+}
+
+/// Restart a crashed server until a replacement comes up
+///
+/// Returns `None` when the restarts are used up or the server was stopped or
+/// restarted by the user in the meantime.
+async fn restart(
+    app: &AppHandle,
+    state: &LspState,
+    crashed: &Arc<LspClient>,
+    history: &mut RestartHistory,
+) -> Option<Arc<LspClient>> {
+    let server_id = crashed.id().to_string();
+    loop {
+        let Some(delay) = history.next_delay(Instant::now()) else {
+            let message = format!(
+                "Not restarting {}: crashed {} times within {} minutes",
+                crashed.name(),
+                MAX_RESTARTS,
+                RESTART_WINDOW.as_secs() / 60
+            );
+            error!("{}", message);
+            state.add_log(&server_id, message.clone());
+            emit_status(
+                app,
+                ServerStatusEvent {
+                    server_id,
+                    status: ServerStatus::Error,
+                    restart_count: history.count(),
+                    message: Some(message),
+                },
+            );
+            return None;
+        };
+
+        emit_status(
+            app,
+            ServerStatusEvent {
+                server_id: server_id.clone(),
+                status: ServerStatus::Starting,
+                restart_count: history.count(),
+                message: None,
+            },
+        );
+        tokio::time::sleep(delay).await;
+        if !is_current(state, crashed) {
+            return None;
+        }
+
+        match replace(app, state, crashed).await {
+            Ok(replacement) => {
+                state.add_log(&server_id, "Restarted after crash".to_string());
+                emit_status(
+                    app,
+                    ServerStatusEvent {
+                        server_id,
+                        status: ServerStatus::Running,
+                        restart_count: history.count(),
+                        message: None,
+                    },
+                );
+                return Some(replacement);
+            }
+            Err(e) => {
+                warn!("Failed to restart {}: {}", server_id, e);
+                state.add_log(&server_id, format!("Restart failed: {}", e));
+            }
+        }
+    }
+}
+
+/// Watch `client` and restart it whenever it crashes
+pub(crate) fn supervise(app: AppHandle, client: Arc<LspClient>) {
+    tauri::async_runtime::spawn(async move {
+        let mut history = RestartHistory::default();
+        let mut client = client;
+
+        loop {
+            let fault = client.wait_for_fault().await;
+            let state = app.state::<LspState>();
+            // Stopped on purpose, or replaced by `lsp_restart`
+            if client.status() == ServerStatus::Stopped || !is_current(&state, &client) {
+                return;
+            }
+
+            record_crash(&app, &state, &client, history.count(), fault);
+            client.kill();
+
+            match restart(&app, &state, &client, &mut history).await {
+                Some(replacement) => client = replacement,
+                None => return,
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backs_off_exponentially() {
+        let mut history = RestartHistory::default();
+        let now = Instant::now();
+        assert_eq!(history.next_delay(now), Some(INITIAL_BACKOFF));
+        assert_eq!(history.next_delay(now), Some(INITIAL_BACKOFF * 2));
+        assert_eq!(history.next_delay(now), Some(INITIAL_BACKOFF * 4));
+    }
+
+    #[test]
+    fn gives_up_after_max_restarts_in_window() {
+        let mut history = RestartHistory::default();
+        let start = Instant::now();
+        for _ in 0..MAX_RESTARTS {
+            assert!(history.next_delay(start).is_some());
+        }
+        assert_eq!(history.next_delay(start), None);
+
+        // Restarts older than the window no longer count
+        let later = start + RESTART_WINDOW + Duration::from_secs(1);
+        assert_eq!(history.next_delay(later), Some(INITIAL_BACKOFF));
+    }
+}
@@ -0,0 +1,76 @@
+//! Trace and replay commands
+//!
+//! Commands for turning JSON-RPC tracing on and off for a running server and
+//! for replaying a recorded trace against a server binary.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use tauri::State;
+
+use crate::lsp::client::{self, ReplayReport};
+
+use super::state::LspState;
+
+/// Time each replayed request waits for its response unless overridden
+const DEFAULT_REPLAY_TIMEOUT_SECS: u64 = 30;
+
+/// Start tracing a server's traffic, returning the trace file path
+#[tauri::command]
+pub fn lsp_trace_start(
+    server_id: String,
+    path: Option<String>,
+    state: State<'_, LspState>,
+) -> Result<String, String> {
+    let client = state
+        .clients
+        .lock()
+        .get(&server_id)
+        .cloned()
+        .ok_or_else(|| format!("Server not found: {}", server_id))?;
+
+    let path = match path {
+        Some(path) => PathBuf::from(path),
+        None => client::default_trace_path(&server_id).map_err(|e| e.to_string())?,
+    };
+    client
+        .start_trace(&path)
+        .map_err(|e| format!("Failed to start trace: {}", e))?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// Stop tracing a server
+#[tauri::command]
+pub fn lsp_trace_stop(server_id: String, state: State<'_, LspState>) -> Result<(), String> {
+    let clients = state.clients.lock();
+    let client = clients
+        .get(&server_id)
+        .ok_or_else(|| format!("Server not found: {}", server_id))?;
+    client.stop_trace();
+    Ok(())
+}
+
+/// Replay a recorded trace against a server binary and diff its responses
+#[tauri::command]
+pub async fn lsp_replay_trace(
+    trace_path: String,
+    command: String,
+    args: Vec<String>,
+    root_path: String,
+    timeout_secs: Option<u64>,
+) -> Result<ReplayReport, String> {
+    let timeout = Duration::from_secs(timeout_secs.unwrap_or(DEFAULT_REPLAY_TIMEOUT_SECS));
+    tokio::task::spawn_blocking(move || {
+        let entries = client::read_trace(&PathBuf::from(&trace_path)).map_err(|e| e.to_string())?;
+        client::replay_command(
+            &command,
+            &args,
+            &PathBuf::from(root_path),
+            &entries,
+            timeout,
+        )
+        .map_err(|e| format!("Replay failed: {}", e))
+    })
+    .await
+    .map_err(|e| format!("Replay task failed: {}", e))?
+}
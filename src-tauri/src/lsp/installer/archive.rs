@@ -0,0 +1,193 @@
+//! Downloads, checksums and archive extraction for managed installs
+
+use std::fs;
+use std::io::{Read, Write};
+use std::path::Path;
+
+use futures::StreamExt;
+use sha2::{Digest, Sha256};
+
+use super::catalog::ArchiveFormat;
+
+/// Hex encoded SHA-256 of a file
+pub(crate) fn sha256_file(path: &Path) -> Result<String, String> {
+    let mut file =
+        fs::File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let n = file
+            .read(&mut buf)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Compare a computed checksum with the expected one
+///
+/// `expected` may carry a `sha256:` prefix, as in GitHub asset digests.
+pub(crate) fn verify_checksum(actual: &str, expected: &str) -> Result<(), String> {
+    let expected = expected.trim().trim_start_matches("sha256:");
+    if actual.eq_ignore_ascii_case(expected) {
+        Ok(())
+    } else {
+        Err(format!(
+            "Checksum mismatch: expected {}, got {}",
+            expected, actual
+        ))
+    }
+}
+
+/// Stream `url` into `dest`, returning the SHA-256 of the downloaded bytes
+pub(crate) async fn download(
+    client: &reqwest::Client,
+    url: &str,
+    dest: &Path,
+    mut on_progress: impl FnMut(u64, Option<u64>),
+) -> Result<String, String> {
+    let response = client
+        .get(url)
+        .header(reqwest::header::USER_AGENT, "Cortex-Desktop")
+        .send()
+        .await
+        .map_err(|e| format!("Failed to download {}: {}", url, e))?;
+    if !response.status().is_success() {
+        return Err(format!(
+            "Failed to download {}: HTTP {}",
+            url,
+            response.status()
+        ));
+    }
+
+    let total = response.content_length();
+    let mut file = fs::File::create(dest)
+        .map_err(|e| format!("Failed to create {}: {}", dest.display(), e))?;
+    let mut hasher = Sha256::new();
+    let mut downloaded = 0u64;
+    let mut stream = response.bytes_stream();
+
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| format!("Download interrupted: {}", e))?;
+        hasher.update(&chunk);
+        file.write_all(&chunk)
+            .map_err(|e| format!("Failed to write {}: {}", dest.display(), e))?;
+        downloaded += chunk.len() as u64;
+        on_progress(downloaded, total);
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Unpack `archive` into `dest_dir`
+///
+/// Single-file formats (gzip, raw) are written to `dest_dir/binary`.
+pub(crate) fn extract(
+    archive: &Path,
+    format: ArchiveFormat,
+    dest_dir: &Path,
+    binary: &str,
+) -> Result<(), String> {
+    fs::create_dir_all(dest_dir)
+        .map_err(|e| format!("Failed to create {}: {}", dest_dir.display(), e))?;
+
+    match format {
+        ArchiveFormat::Gzip => {
+            let file =
+                fs::File::open(archive).map_err(|e| format!("Failed to open archive: {}", e))?;
+            let mut decoder = flate2::read::GzDecoder::new(file);
+            let target = dest_dir.join(binary);
+            let mut out = fs::File::create(&target)
+                .map_err(|e| format!("Failed to create {}: {}", target.display(), e))?;
+            std::io::copy(&mut decoder, &mut out)
+                .map_err(|e| format!("Failed to decompress archive: {}", e))?;
+        }
+        ArchiveFormat::TarGz => {
+            let file =
+                fs::File::open(archive).map_err(|e| format!("Failed to open archive: {}", e))?;
+            let mut tar = tar::Archive::new(flate2::read::GzDecoder::new(file));
+            tar.unpack(dest_dir)
+                .map_err(|e| format!("Failed to unpack archive: {}", e))?;
+        }
+        ArchiveFormat::Zip => {
+            crate::extensions::utils::extract_zip_package(archive, dest_dir)?;
+        }
+        ArchiveFormat::Raw => {
+            let target = dest_dir.join(binary);
+            fs::copy(archive, &target)
+                .map_err(|e| format!("Failed to copy {}: {}", archive.display(), e))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Mark a file as executable on Unix
+pub(crate) fn make_executable(path: &Path) -> Result<(), String> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mut perms = fs::metadata(path)
+            .map_err(|e| format!("Failed to stat {}: {}", path.display(), e))?
+            .permissions();
+        perms.set_mode(perms.mode() | 0o755);
+        fs::set_permissions(path, perms)
+            .map_err(|e| format!("Failed to make {} executable: {}", path.display(), e))?;
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+    Ok(())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+
+    #[test]
+    fn checksums_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data");
+        fs::write(&path, b"abc").unwrap();
+        let sum = sha256_file(&path).unwrap();
+        assert_eq!(
+            sum,
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert!(verify_checksum(&sum, &format!("sha256:{}", sum.to_uppercase())).is_ok());
+        assert!(verify_checksum(&sum, "00").is_err());
+    }
+
+    #[test]
+    fn extracts_gzip_and_tarball() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let gz = dir.path().join("server.gz");
+        let mut encoder = GzEncoder::new(fs::File::create(&gz).unwrap(), Compression::default());
+        encoder.write_all(b"#!/bin/sh\n").unwrap();
+        encoder.finish().unwrap();
+        let out = dir.path().join("gz-out");
+        extract(&gz, ArchiveFormat::Gzip, &out, "server").unwrap();
+        assert_eq!(fs::read(out.join("server")).unwrap(), b"#!/bin/sh\n");
+
+        let tgz = dir.path().join("server.tar.gz");
+        let encoder = GzEncoder::new(fs::File::create(&tgz).unwrap(), Compression::default());
+        let mut builder = tar::Builder::new(encoder);
+        let mut header = tar::Header::new_gnu();
+        header.set_size(2);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "pkg/bin/server", &b"ok"[..])
+            .unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+        let out = dir.path().join("tgz-out");
+        extract(&tgz, ArchiveFormat::TarGz, &out, "server").unwrap();
+        assert_eq!(fs::read(out.join("pkg/bin/server")).unwrap(), b"ok");
+    }
+}
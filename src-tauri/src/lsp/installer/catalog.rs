@@ -0,0 +1,268 @@
+//! Catalog of managed language servers
+//!
+//! Every entry pins the version Cortex installs. Release binaries list one asset
+//! per supported platform; checksums are taken from the entry when present and
+//! otherwise from the SHA-256 digest GitHub publishes for the asset. Package
+//! manager installs rely on the integrity checks of npm, pip and the Go module
+//! proxy.
+
+/// How a catalog entry is installed
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum InstallMethod {
+    /// Prebuilt binary attached to a GitHub release
+    GithubRelease {
+        repo: &'static str,
+        /// Release tag, `{version}` is substituted
+        tag: &'static str,
+        assets: &'static [ReleaseAsset],
+    },
+    /// npm packages installed into a private prefix
+    Npm {
+        package: &'static str,
+        /// Peer packages installed alongside, already pinned
+        extra_packages: &'static [&'static str],
+    },
+    /// pip package installed into a private virtual environment
+    Pip { package: &'static str },
+    /// `go install` into a private GOBIN
+    Go { module: &'static str },
+}
+
+impl InstallMethod {
+    pub(crate) fn kind(&self) -> &'static str {
+        match self {
+            Self::GithubRelease { .. } => "github",
+            Self::Npm { .. } => "npm",
+            Self::Pip { .. } => "pip",
+            Self::Go { .. } => "go",
+        }
+    }
+}
+
+/// Packaging of a release asset
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ArchiveFormat {
+    /// A single gzip-compressed executable
+    Gzip,
+    TarGz,
+    Zip,
+    /// The executable itself
+    Raw,
+}
+
+impl ArchiveFormat {
+    /// Guess the format of a local archive from its file name
+    pub(crate) fn from_file_name(name: &str) -> Self {
+        let name = name.to_ascii_lowercase();
+        if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Self::TarGz
+        } else if name.ends_with(".gz") {
+            Self::Gzip
+        } else if name.ends_with(".zip") || name.ends_with(".vsix") {
+            Self::Zip
+        } else {
+            Self::Raw
+        }
+    }
+}
+
+/// A release asset for one platform
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ReleaseAsset {
+    /// `<arch>-<os>` as reported by `std::env::consts`, or `*` for assets
+    /// that run everywhere
+    pub target: &'static str,
+    /// Asset file name, `{version}` is substituted
+    pub name: &'static str,
+    pub format: ArchiveFormat,
+    /// Path of the executable inside the archive, `{version}` is substituted
+    pub binary: &'static str,
+    /// Pinned SHA-256 of the asset, hex encoded
+    pub sha256: Option<&'static str>,
+}
+
+/// A language server Cortex can install
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct CatalogEntry {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub languages: &'static [&'static str],
+    pub version: &'static str,
+    /// Executable name, as found on PATH or in the install prefix
+    pub command: &'static str,
+    pub args: &'static [&'static str],
+    pub method: InstallMethod,
+}
+
+impl CatalogEntry {
+    /// Release asset for the running platform
+    pub(crate) fn asset_for_current_target(&self) -> Option<&'static ReleaseAsset> {
+        let InstallMethod::GithubRelease { assets, .. } = self.method else {
+            return None;
+        };
+        let target = current_target();
+        assets
+            .iter()
+            .find(|a| a.target == target)
+            .or_else(|| assets.iter().find(|a| a.target == "*"))
+    }
+
+    pub(crate) fn substitute(&self, template: &str) -> String {
+        template.replace("{version}", self.version)
+    }
+}
+
+/// `<arch>-<os>` of the running platform
+pub(crate) fn current_target() -> String {
+    format!("{}-{}", std::env::consts::ARCH, std::env::consts::OS)
+}
+
+pub(crate) const CATALOG: &[CatalogEntry] = &[
+    CatalogEntry {
+        id: "rust-analyzer",
+        name: "rust-analyzer",
+        languages: &["rust"],
+        version: "2025-01-20",
+        command: "rust-analyzer",
+        args: &[],
+        method: InstallMethod::GithubRelease {
+            repo: "rust-lang/rust-analyzer",
+            tag: "{version}",
+            assets: &[
+                ReleaseAsset {
+                    target: "x86_64-linux",
+                    name: "rust-analyzer-x86_64-unknown-linux-gnu.gz",
+                    format: ArchiveFormat::Gzip,
+                    binary: "rust-analyzer",
+                    sha256: None,
+                },
+                ReleaseAsset {
+                    target: "aarch64-linux",
+                    name: "rust-analyzer-aarch64-unknown-linux-gnu.gz",
+                    format: ArchiveFormat::Gzip,
+                    binary: "rust-analyzer",
+                    sha256: None,
+                },
+                ReleaseAsset {
+                    target: "x86_64-macos",
+                    name: "rust-analyzer-x86_64-apple-darwin.gz",
+                    format: ArchiveFormat::Gzip,
+                    binary: "rust-analyzer",
+                    sha256: None,
+                },
+                ReleaseAsset {
+                    target: "aarch64-macos",
+                    name: "rust-analyzer-aarch64-apple-darwin.gz",
+                    format: ArchiveFormat::Gzip,
+                    binary: "rust-analyzer",
+                    sha256: None,
+                },
+                ReleaseAsset {
+                    target: "x86_64-windows",
+                    name: "rust-analyzer-x86_64-pc-windows-msvc.zip",
+                    format: ArchiveFormat::Zip,
+                    binary: "rust-analyzer.exe",
+                    sha256: None,
+                },
+            ],
+        },
+    },
+    CatalogEntry {
+        id: "clangd",
+        name: "clangd",
+        languages: &["c", "cpp"],
+        version: "19.1.2",
+        command: "clangd",
+        args: &[],
+        method: InstallMethod::GithubRelease {
+            repo: "clangd/clangd",
+            tag: "{version}",
+            assets: &[
+                ReleaseAsset {
+                    target: "x86_64-linux",
+                    name: "clangd-linux-{version}.zip",
+                    format: ArchiveFormat::Zip,
+                    binary: "clangd_{version}/bin/clangd",
+                    sha256: None,
+                },
+                ReleaseAsset {
+                    target: "x86_64-macos",
+                    name: "clangd-mac-{version}.zip",
+                    format: ArchiveFormat::Zip,
+                    binary: "clangd_{version}/bin/clangd",
+                    sha256: None,
+                },
+                ReleaseAsset {
+                    target: "aarch64-macos",
+                    name: "clangd-mac-{version}.zip",
+                    format: ArchiveFormat::Zip,
+                    binary: "clangd_{version}/bin/clangd",
+                    sha256: None,
+                },
+                ReleaseAsset {
+                    target: "x86_64-windows",
+                    name: "clangd-windows-{version}.zip",
+                    format: ArchiveFormat::Zip,
+                    binary: "clangd_{version}/bin/clangd.exe",
+                    sha256: None,
+                },
+            ],
+        },
+    },
+    CatalogEntry {
+        id: "pyright",
+        name: "Pyright",
+        languages: &["python"],
+        version: "1.1.391",
+        command: "pyright-langserver",
+        args: &["--stdio"],
+        method: InstallMethod::Npm {
+            package: "pyright",
+            extra_packages: &[],
+        },
+    },
+    CatalogEntry {
+        id: "typescript-language-server",
+        name: "TypeScript Language Server",
+        languages: &["typescript", "javascript"],
+        version: "4.3.3",
+        command: "typescript-language-server",
+        args: &["--stdio"],
+        method: InstallMethod::Npm {
+            package: "typescript-language-server",
+            extra_packages: &["typescript@5.7.3"],
+        },
+    },
+    CatalogEntry {
+        id: "pylsp",
+        name: "Python LSP Server",
+        languages: &["python"],
+        version: "1.12.0",
+        command: "pylsp",
+        args: &[],
+        method: InstallMethod::Pip {
+            package: "python-lsp-server",
+        },
+    },
+    CatalogEntry {
+        id: "gopls",
+        name: "gopls",
+        languages: &["go"],
+        version: "v0.17.1",
+        command: "gopls",
+        args: &["serve"],
+        method: InstallMethod::Go {
+            module: "golang.org/x/tools/gopls",
+        },
+    },
+];
+
+/// Look up a catalog entry by id
+pub(crate) fn find(id: &str) -> Option<&'static CatalogEntry> {
+    CATALOG.iter().find(|e| e.id == id)
+}
+
+/// Catalog entry providing an executable name
+pub(crate) fn find_by_command(command: &str) -> Option<&'static CatalogEntry> {
+    CATALOG.iter().find(|e| e.command == command)
+}
@@ -0,0 +1,822 @@
+//! Managed language server installation
+//!
+//! Installs the servers listed in the catalog into the Cortex data directory,
+//! one directory per server:
+//!
+//! ```text
+//! <data>/Cortex/language-servers/<id>/manifest.json
+//! <data>/Cortex/language-servers/<id>/<version>/...
+//! ```
+//!
+//! Installs are staged next to the final directory and moved into place once
+//! complete, so a failed download never replaces a working server. Other
+//! catalogs (debug adapters) install the same way into their own root.
+
+pub(crate) mod archive;
+pub(crate) mod catalog;
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use catalog::{ArchiveFormat, CatalogEntry, InstallMethod};
+
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// Record of an installed server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstallManifest {
+    pub id: String,
+    pub version: String,
+    pub method: String,
+    /// Executable path relative to the version directory
+    pub command: String,
+    /// Whether the download was checked against a known checksum
+    pub verified: bool,
+    pub installed_at: u64,
+}
+
+/// Catalog entry together with its install state
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManagedServerInfo {
+    pub id: String,
+    pub name: String,
+    pub languages: Vec<String>,
+    pub method: String,
+    pub pinned_version: String,
+    pub installed_version: Option<String>,
+    /// Absolute path of the installed executable
+    pub command_path: Option<String>,
+    pub args: Vec<String>,
+    /// Whether the platform has a build of this server
+    pub available: bool,
+    /// Installed version differs from the pinned one
+    pub update_available: bool,
+}
+
+/// Result of an update check for one server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerUpdateInfo {
+    pub id: String,
+    pub installed_version: Option<String>,
+    pub pinned_version: String,
+    /// Newest upstream release, when it could be determined
+    pub latest_version: Option<String>,
+    /// Installing would change the installed version
+    pub update_available: bool,
+    /// Upstream has released past the pinned version
+    pub newer_upstream: bool,
+}
+
+/// Progress of an install, emitted as `lsp:install-progress`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstallProgressEvent {
+    pub server_id: String,
+    pub stage: String,
+    pub message: String,
+    pub downloaded: Option<u64>,
+    pub total: Option<u64>,
+}
+
+impl InstallProgressEvent {
+    fn stage(server_id: &str, stage: &str, message: impl Into<String>) -> Self {
+        Self {
+            server_id: server_id.to_string(),
+            stage: stage.to_string(),
+            message: message.into(),
+            downloaded: None,
+            total: None,
+        }
+    }
+}
+
+/// Directory below `<data>/Cortex` that a catalog installs into
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct InstallRoot(&'static str);
+
+/// Root of managed language servers
+pub(crate) const LANGUAGE_SERVERS: InstallRoot = InstallRoot::new("language-servers");
+
+impl InstallRoot {
+    pub(crate) const fn new(dir_name: &'static str) -> Self {
+        Self(dir_name)
+    }
+
+    pub(crate) fn path(&self) -> Result<PathBuf, String> {
+        let data_dir = dirs::data_dir().ok_or("Could not find app data directory")?;
+        Ok(data_dir.join("Cortex").join(self.0))
+    }
+
+    fn entry_dir(&self, id: &str) -> Result<PathBuf, String> {
+        Ok(self.path()?.join(id))
+    }
+}
+
+pub(crate) fn read_manifest(root: InstallRoot, id: &str) -> Option<InstallManifest> {
+    let path = root.entry_dir(id).ok()?.join(MANIFEST_FILE);
+    let content = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn write_manifest(root: InstallRoot, manifest: &InstallManifest) -> Result<(), String> {
+    let path = root.entry_dir(&manifest.id)?.join(MANIFEST_FILE);
+    let content = serde_json::to_string_pretty(manifest)
+        .map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+    std::fs::write(&path, content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Installed executable of a catalog entry, if present on disk
+pub(crate) fn managed_command(root: InstallRoot, id: &str) -> Option<PathBuf> {
+    let manifest = read_manifest(root, id)?;
+    let path = root
+        .entry_dir(id)
+        .ok()?
+        .join(&manifest.version)
+        .join(&manifest.command);
+    path.is_file().then_some(path)
+}
+
+/// Installed executable providing `command`, for servers not on PATH
+pub(crate) fn managed_command_for(command: &str) -> Option<PathBuf> {
+    catalog::find_by_command(command).and_then(|entry| managed_command(LANGUAGE_SERVERS, entry.id))
+}
+
+pub(crate) fn status(root: InstallRoot, entry: &CatalogEntry) -> ManagedServerInfo {
+    let manifest = read_manifest(root, entry.id);
+    let command_path = managed_command(root, entry.id);
+    let installed_version = command_path
+        .as_ref()
+        .and(manifest.as_ref())
+        .map(|m| m.version.clone());
+    let available = match entry.method {
+        InstallMethod::GithubRelease { .. } => entry.asset_for_current_target().is_some(),
+        _ => true,
+    };
+
+    ManagedServerInfo {
+        id: entry.id.to_string(),
+        name: entry.name.to_string(),
+        languages: entry.languages.iter().map(|l| l.to_string()).collect(),
+        method: entry.method.kind().to_string(),
+        pinned_version: entry.version.to_string(),
+        update_available: installed_version
+            .as_deref()
+            .is_some_and(|v| v != entry.version),
+        installed_version,
+        command_path: command_path.map(|p| p.to_string_lossy().to_string()),
+        args: entry.args.iter().map(|a| a.to_string()).collect(),
+        available,
+    }
+}
+
+/// Executable name inside a package manager prefix
+fn package_bin(command: &str) -> PathBuf {
+    if cfg!(windows) {
+        PathBuf::from(format!("{}.cmd", command))
+    } else {
+        PathBuf::from(command)
+    }
+}
+
+fn npm_program() -> &'static str {
+    if cfg!(windows) { "npm.cmd" } else { "npm" }
+}
+
+fn python_program() -> &'static str {
+    if cfg!(windows) { "python" } else { "python3" }
+}
+
+/// Executable inside a virtual environment
+fn venv_bin(venv: &Path, name: &str) -> PathBuf {
+    if cfg!(windows) {
+        venv.join("Scripts").join(format!("{}.exe", name))
+    } else {
+        venv.join("bin").join(name)
+    }
+}
+
+/// Run an installer process, failing with its stderr
+async fn run(program: &str, args: &[String], envs: &[(&str, &Path)]) -> Result<(), String> {
+    let mut cmd = crate::process_utils::async_command(program);
+    cmd.args(args);
+    for (key, value) in envs {
+        cmd.env(key, value);
+    }
+    let output = cmd
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+/// Checksum the asset is expected to have
+///
+/// The pinned value wins; otherwise the digest GitHub publishes for the asset
+/// is used.
+async fn expected_checksum(
+    client: &reqwest::Client,
+    repo: &str,
+    tag: &str,
+    asset_name: &str,
+    pinned: Option<&str>,
+) -> Result<String, String> {
+    if let Some(pinned) = pinned {
+        return Ok(pinned.to_string());
+    }
+
+    let url = format!(
+        "https://api.github.com/repos/{}/releases/tags/{}",
+        repo, tag
+    );
+    let release: serde_json::Value = client
+        .get(&url)
+        .header(reqwest::header::USER_AGENT, "Cortex-Desktop")
+        .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+        .send()
+        .await
+        .map_err(|e| format!("Failed to fetch release {}: {}", tag, e))?
+        .error_for_status()
+        .map_err(|e| format!("Failed to fetch release {}: {}", tag, e))?
+        .json()
+        .await
+        .map_err(|e| format!("Invalid release metadata: {}", e))?;
+
+    release["assets"]
+        .as_array()
+        .and_then(|assets| {
+            assets
+                .iter()
+                .find(|a| a["name"].as_str() == Some(asset_name))
+        })
+        .and_then(|a| a["digest"].as_str())
+        .map(String::from)
+        .ok_or_else(|| format!("No checksum published for {} {}", repo, asset_name))
+}
+
+/// Install into `staging`, returning the executable path relative to it and
+/// whether the download was verified
+async fn install_into(
+    entry: &CatalogEntry,
+    staging: &Path,
+    progress: &(dyn Fn(InstallProgressEvent) + Send + Sync),
+) -> Result<(String, bool), String> {
+    match entry.method {
+        InstallMethod::GithubRelease { repo, tag, .. } => {
+            let asset = entry.asset_for_current_target().ok_or_else(|| {
+                format!(
+                    "{} has no release for {}",
+                    entry.name,
+                    catalog::current_target()
+                )
+            })?;
+            let tag = entry.substitute(tag);
+            let asset_name = entry.substitute(asset.name);
+            let client = reqwest::Client::new();
+
+            progress(InstallProgressEvent::stage(
+                entry.id,
+                "resolve",
+                format!("Resolving {} {}", entry.name, tag),
+            ));
+            let expected =
+                expected_checksum(&client, repo, &tag, &asset_name, asset.sha256).await?;
+
+            let url = format!(
+                "https://github.com/{}/releases/download/{}/{}",
+                repo, tag, asset_name
+            );
+            let download_path = staging.join(&asset_name);
+            let id = entry.id.to_string();
+            let actual = archive::download(&client, &url, &download_path, |downloaded, total| {
+                progress(InstallProgressEvent {
+                    server_id: id.clone(),
+                    stage: "download".to_string(),
+                    message: format!("Downloading {}", asset_name),
+                    downloaded: Some(downloaded),
+                    total,
+                });
+            })
+            .await?;
+            archive::verify_checksum(&actual, &expected)?;
+
+            progress(InstallProgressEvent::stage(
+                entry.id,
+                "extract",
+                "Extracting",
+            ));
+            let binary = entry.substitute(asset.binary);
+            archive::extract(&download_path, asset.format, staging, &binary)?;
+            let _ = std::fs::remove_file(&download_path);
+            archive::make_executable(&staging.join(&binary))?;
+            Ok((binary, true))
+        }
+        InstallMethod::Npm {
+            package,
+            extra_packages,
+        } => {
+            progress(InstallProgressEvent::stage(
+                entry.id,
+                "install",
+                format!("npm install {}@{}", package, entry.version),
+            ));
+            let mut args = npm_install_args(staging);
+            args.push(format!("{}@{}", package, entry.version));
+            args.extend(extra_packages.iter().map(|p| p.to_string()));
+            run(npm_program(), &args, &[]).await?;
+            Ok((npm_command(entry), true))
+        }
+        InstallMethod::Pip { package } => {
+            progress(InstallProgressEvent::stage(
+                entry.id,
+                "install",
+                format!("pip install {}=={}", package, entry.version),
+            ));
+            let venv = create_venv(staging).await?;
+            let args = vec![
+                "-m".to_string(),
+                "pip".to_string(),
+                "install".to_string(),
+                "--disable-pip-version-check".to_string(),
+                format!("{}=={}", package, entry.version),
+            ];
+            run(&venv_bin(&venv, "python").to_string_lossy(), &args, &[]).await?;
+            Ok((pip_command(entry), true))
+        }
+        InstallMethod::Go { module } => {
+            progress(InstallProgressEvent::stage(
+                entry.id,
+                "install",
+                format!("go install {}@{}", module, entry.version),
+            ));
+            let bin = staging.join("bin");
+            let args = vec![
+                "install".to_string(),
+                format!("{}@{}", module, entry.version),
+            ];
+            run("go", &args, &[("GOBIN", &bin)]).await?;
+            Ok((go_command(entry), true))
+        }
+    }
+}
+
+fn npm_install_args(prefix: &Path) -> Vec<String> {
+    vec![
+        "install".to_string(),
+        "--prefix".to_string(),
+        prefix.to_string_lossy().to_string(),
+        "--no-save".to_string(),
+        "--no-audit".to_string(),
+        "--no-fund".to_string(),
+    ]
+}
+
+fn npm_command(entry: &CatalogEntry) -> String {
+    Path::new("node_modules")
+        .join(".bin")
+        .join(package_bin(entry.command))
+        .to_string_lossy()
+        .to_string()
+}
+
+fn pip_command(entry: &CatalogEntry) -> String {
+    venv_bin(Path::new("venv"), entry.command)
+        .to_string_lossy()
+        .to_string()
+}
+
+fn go_command(entry: &CatalogEntry) -> String {
+    let name = if cfg!(windows) {
+        format!("{}.exe", entry.command)
+    } else {
+        entry.command.to_string()
+    };
+    Path::new("bin").join(name).to_string_lossy().to_string()
+}
+
+async fn create_venv(staging: &Path) -> Result<PathBuf, String> {
+    let venv = staging.join("venv");
+    let args = vec![
+        "-m".to_string(),
+        "venv".to_string(),
+        venv.to_string_lossy().to_string(),
+    ];
+    run(python_program(), &args, &[]).await?;
+    Ok(venv)
+}
+
+/// Install from a local file or directory, without network access
+///
+/// - release binaries: the release asset as published
+/// - npm: a package tarball from `npm pack`, or a directory of tarballs that
+///   includes any peer packages
+/// - pip: a wheel, or a directory of wheels used as the only package index
+/// - go: a prebuilt executable
+async fn install_offline_into(
+    entry: &CatalogEntry,
+    source: &Path,
+    staging: &Path,
+    progress: &(dyn Fn(InstallProgressEvent) + Send + Sync),
+) -> Result<(String, bool), String> {
+    let file_name = source
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    match entry.method {
+        InstallMethod::GithubRelease { .. } => {
+            if source.is_dir() {
+                return Err("Expected a release archive, got a directory".to_string());
+            }
+            let asset = entry.asset_for_current_target();
+            let matches_asset = asset.is_some_and(|a| entry.substitute(a.name) == file_name);
+
+            let mut verified = false;
+            if let Some(pinned) = asset.filter(|_| matches_asset).and_then(|a| a.sha256) {
+                archive::verify_checksum(&archive::sha256_file(source)?, pinned)?;
+                verified = true;
+            }
+
+            progress(InstallProgressEvent::stage(
+                entry.id,
+                "extract",
+                "Extracting",
+            ));
+            let (format, binary) = match asset.filter(|_| matches_asset) {
+                Some(asset) => (asset.format, entry.substitute(asset.binary)),
+                None => (
+                    ArchiveFormat::from_file_name(&file_name),
+                    package_exe(entry.command),
+                ),
+            };
+            archive::extract(source, format, staging, &binary)?;
+
+            let binary = if staging.join(&binary).is_file() {
+                binary
+            } else {
+                find_file(staging, &package_exe(entry.command))
+                    .ok_or_else(|| format!("{} not found in archive", entry.command))?
+            };
+            archive::make_executable(&staging.join(&binary))?;
+            Ok((binary, verified))
+        }
+        InstallMethod::Npm { .. } => {
+            progress(InstallProgressEvent::stage(
+                entry.id,
+                "install",
+                "npm install (offline)",
+            ));
+            let mut args = npm_install_args(staging);
+            args.push("--offline".to_string());
+            args.extend(archive_files(source, &[".tgz", ".tar.gz"])?);
+            run(npm_program(), &args, &[]).await?;
+            Ok((npm_command(entry), false))
+        }
+        InstallMethod::Pip { package } => {
+            progress(InstallProgressEvent::stage(
+                entry.id,
+                "install",
+                "pip install (offline)",
+            ));
+            let venv = create_venv(staging).await?;
+            let mut args = vec![
+                "-m".to_string(),
+                "pip".to_string(),
+                "install".to_string(),
+                "--disable-pip-version-check".to_string(),
+                "--no-index".to_string(),
+            ];
+            if source.is_dir() {
+                args.push("--find-links".to_string());
+                args.push(source.to_string_lossy().to_string());
+                args.push(format!("{}=={}", package, entry.version));
+            } else {
+                args.push(source.to_string_lossy().to_string());
+            }
+            run(&venv_bin(&venv, "python").to_string_lossy(), &args, &[]).await?;
+            Ok((pip_command(entry), false))
+        }
+        InstallMethod::Go { .. } => {
+            if source.is_dir() {
+                return Err("Expected a prebuilt executable, got a directory".to_string());
+            }
+            let binary = go_command(entry);
+            let target = staging.join(&binary);
+            if let Some(parent) = target.parent() {
+                std::fs::create_dir_all(parent)
+                    .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+            }
+            std::fs::copy(source, &target)
+                .map_err(|e| format!("Failed to copy {}: {}", source.display(), e))?;
+            archive::make_executable(&target)?;
+            Ok((binary, false))
+        }
+    }
+}
+
+/// Executable file name on this platform
+fn package_exe(command: &str) -> String {
+    if cfg!(windows) {
+        format!("{}.exe", command)
+    } else {
+        command.to_string()
+    }
+}
+
+/// A single archive, or the matching archives in a directory
+fn archive_files(source: &Path, extensions: &[&str]) -> Result<Vec<String>, String> {
+    if !source.is_dir() {
+        return Ok(vec![source.to_string_lossy().to_string()]);
+    }
+    let mut files: Vec<String> = std::fs::read_dir(source)
+        .map_err(|e| format!("Failed to read {}: {}", source.display(), e))?
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| {
+            let name = p.to_string_lossy();
+            extensions.iter().any(|ext| name.ends_with(ext))
+        })
+        .map(|p| p.to_string_lossy().to_string())
+        .collect();
+    if files.is_empty() {
+        return Err(format!("No package archives in {}", source.display()));
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Relative path of the first file named `name` below `dir`
+fn find_file(dir: &Path, name: &str) -> Option<String> {
+    walkdir::WalkDir::new(dir)
+        .into_iter()
+        .flatten()
+        .find(|e| e.file_type().is_file() && e.file_name().to_string_lossy() == name)
+        .and_then(|e| {
+            e.path()
+                .strip_prefix(dir)
+                .ok()
+                .map(|p| p.to_string_lossy().to_string())
+        })
+}
+
+/// Stage an install and move it into place
+async fn install_with<'a, F, Fut>(
+    root: InstallRoot,
+    entry: &'a CatalogEntry,
+    progress: &'a (dyn Fn(InstallProgressEvent) + Send + Sync),
+    install: F,
+) -> Result<ManagedServerInfo, String>
+where
+    F: FnOnce(PathBuf) -> Fut,
+    Fut: std::future::Future<Output = Result<(String, bool), String>> + 'a,
+{
+    let dir = root.entry_dir(entry.id)?;
+    let staging = dir.join(format!(".staging-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&staging)
+        .map_err(|e| format!("Failed to create {}: {}", staging.display(), e))?;
+
+    let (command, verified) = match install(staging.clone()).await {
+        Ok(result) => result,
+        Err(e) => {
+            let _ = std::fs::remove_dir_all(&staging);
+            return Err(e);
+        }
+    };
+
+    let version_dir = dir.join(entry.version);
+    if version_dir.exists() {
+        std::fs::remove_dir_all(&version_dir)
+            .map_err(|e| format!("Failed to replace {}: {}", version_dir.display(), e))?;
+    }
+    std::fs::rename(&staging, &version_dir)
+        .map_err(|e| format!("Failed to move install into place: {}", e))?;
+
+    write_manifest(
+        root,
+        &InstallManifest {
+            id: entry.id.to_string(),
+            version: entry.version.to_string(),
+            method: entry.method.kind().to_string(),
+            command,
+            verified,
+            installed_at: chrono::Utc::now().timestamp() as u64,
+        },
+    )?;
+    remove_other_versions(&dir, entry.version);
+
+    progress(InstallProgressEvent::stage(
+        entry.id,
+        "done",
+        format!("Installed {} {}", entry.name, entry.version),
+    ));
+    info!("Installed {} {}", entry.id, entry.version);
+    Ok(status(root, entry))
+}
+
+fn remove_other_versions(dir: &Path, keep: &str) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() && entry.file_name() != keep {
+            if let Err(e) = std::fs::remove_dir_all(&path) {
+                warn!("Failed to remove {}: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+/// Download and install the pinned version of a server
+pub(crate) async fn install(
+    root: InstallRoot,
+    entry: &CatalogEntry,
+    progress: &(dyn Fn(InstallProgressEvent) + Send + Sync),
+) -> Result<ManagedServerInfo, String> {
+    install_with(root, entry, progress, |staging| async move {
+        install_into(entry, &staging, progress).await
+    })
+    .await
+}
+
+/// Install a server from a local archive
+pub(crate) async fn install_from_archive(
+    root: InstallRoot,
+    entry: &CatalogEntry,
+    source: &Path,
+    progress: &(dyn Fn(InstallProgressEvent) + Send + Sync),
+) -> Result<ManagedServerInfo, String> {
+    if !source.exists() {
+        return Err(format!("Archive not found: {}", source.display()));
+    }
+    install_with(root, entry, progress, |staging| async move {
+        install_offline_into(entry, source, &staging, progress).await
+    })
+    .await
+}
+
+/// Remove an installed server
+pub(crate) fn uninstall(root: InstallRoot, entry: &CatalogEntry) -> Result<(), String> {
+    let dir = root.entry_dir(entry.id)?;
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir)
+            .map_err(|e| format!("Failed to remove {}: {}", dir.display(), e))?;
+    }
+    Ok(())
+}
+
+/// Newest upstream version of a server
+async fn latest_version(client: &reqwest::Client, entry: &CatalogEntry) -> Result<String, String> {
+    let (url, pointer) = match entry.method {
+        InstallMethod::GithubRelease { repo, .. } => (
+            format!("https://api.github.com/repos/{}/releases/latest", repo),
+            "/tag_name",
+        ),
+        InstallMethod::Npm { package, .. } => (
+            format!("https://registry.npmjs.org/{}/latest", package),
+            "/version",
+        ),
+        InstallMethod::Pip { package } => (
+            format!("https://pypi.org/pypi/{}/json", package),
+            "/info/version",
+        ),
+        InstallMethod::Go { module } => (
+            format!("https://proxy.golang.org/{}/@latest", module),
+            "/Version",
+        ),
+    };
+
+    let body: serde_json::Value = client
+        .get(&url)
+        .header(reqwest::header::USER_AGENT, "Cortex-Desktop")
+        .send()
+        .await
+        .map_err(|e| e.to_string())?
+        .error_for_status()
+        .map_err(|e| e.to_string())?
+        .json()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    body.pointer(pointer)
+        .and_then(|v| v.as_str())
+        .map(String::from)
+        .ok_or_else(|| format!("No version in {}", url))
+}
+
+/// Versions match, ignoring a leading `v` on either side
+fn same_version(a: &str, b: &str) -> bool {
+    a.trim_start_matches('v') == b.trim_start_matches('v')
+}
+
+/// Compare installed versions of `entries` with the catalog and with upstream
+pub(crate) async fn check_updates(
+    root: InstallRoot,
+    entries: impl IntoIterator<Item = &CatalogEntry>,
+) -> Vec<ServerUpdateInfo> {
+    let client = reqwest::Client::new();
+    let checks = entries.into_iter().map(|entry| {
+        let client = &client;
+        async move {
+            let installed_version = status(root, entry).installed_version;
+            let latest_version = match latest_version(client, entry).await {
+                Ok(version) => Some(version),
+                Err(e) => {
+                    warn!("Update check for {} failed: {}", entry.id, e);
+                    None
+                }
+            };
+            ServerUpdateInfo {
+                id: entry.id.to_string(),
+                update_available: installed_version
+                    .as_deref()
+                    .is_some_and(|v| v != entry.version),
+                newer_upstream: latest_version
+                    .as_deref()
+                    .is_some_and(|v| !same_version(v, entry.version)),
+                installed_version,
+                pinned_version: entry.version.to_string(),
+                latest_version,
+            }
+        }
+    });
+    futures::future::join_all(checks).await
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn catalog_ids_are_unique() {
+        let mut ids: Vec<&str> = catalog::CATALOG.iter().map(|e| e.id).collect();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), catalog::CATALOG.len());
+    }
+
+    #[test]
+    fn release_assets_are_well_formed() {
+        for entry in catalog::CATALOG {
+            if let InstallMethod::GithubRelease { assets, .. } = entry.method {
+                assert!(!assets.is_empty(), "{} has no assets", entry.id);
+                for asset in assets {
+                    assert_eq!(
+                        ArchiveFormat::from_file_name(asset.name),
+                        asset.format,
+                        "{} asset {}",
+                        entry.id,
+                        asset.name
+                    );
+                    if let Some(sum) = asset.sha256 {
+                        assert_eq!(sum.len(), 64);
+                    }
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn installs_release_binary_from_local_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let entry = catalog::find("rust-analyzer").unwrap();
+        let source = dir.path().join("rust-analyzer-custom");
+        std::fs::write(&source, b"binary").unwrap();
+        let staging = dir.path().join("staging");
+        std::fs::create_dir_all(&staging).unwrap();
+
+        let (command, verified) = install_offline_into(entry, &source, &staging, &|_| {})
+            .await
+            .unwrap();
+        assert_eq!(command, package_exe("rust-analyzer"));
+        assert!(!verified);
+        assert_eq!(std::fs::read(staging.join(&command)).unwrap(), b"binary");
+    }
+
+    #[test]
+    fn versions_compare_without_v_prefix() {
+        assert!(same_version("v1.97.0", "1.97.0"));
+        assert!(!same_version("v1.98.0", "1.97.0"));
+    }
+
+    #[test]
+    fn finds_files_in_nested_archives() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("clangd_19/bin");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::write(nested.join("clangd"), b"").unwrap();
+        assert_eq!(
+            find_file(dir.path(), "clangd").map(PathBuf::from),
+            Some(PathBuf::from("clangd_19/bin/clangd"))
+        );
+    }
+}
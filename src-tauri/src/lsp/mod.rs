@@ -5,9 +5,11 @@
 //! - Handle document synchronization (didOpen, didChange, didSave)
 //! - Request completions, hover info, definitions, references
 //! - Receive and process diagnostics
+//! - Install managed language servers into the Cortex data directory
 
 pub mod client;
 pub mod commands;
+pub mod installer;
 pub mod types;
 
 // Re-export state and event setup
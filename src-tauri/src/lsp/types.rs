@@ -21,6 +21,21 @@ pub struct LanguageServerConfig {
     pub file_extensions: Vec<String>,
     /// Language ID for document identification
     pub language_id: String,
+    /// Run the server on a remote host instead of locally
+    #[serde(default)]
+    pub remote: Option<RemoteServerTarget>,
+    /// Record all JSON-RPC traffic to a trace file
+    #[serde(default)]
+    pub trace: bool,
+}
+
+/// Remote host a language server runs on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoteServerTarget {
+    /// Connection in the remote manager to open the server over
+    pub connection_id: String,
+    /// Path on the remote host that corresponds to `root_path`
+    pub remote_root: String,
 }
 
 /// Status of a language server
@@ -255,6 +270,16 @@ pub struct DiagnosticsEvent {
     pub diagnostics: Vec<Diagnostic>,
 }
 
+/// Event emitted when a supervised server crashes, restarts or gives up
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerStatusEvent {
+    pub server_id: String,
+    pub status: ServerStatus,
+    /// Restarts within the current restart window
+    pub restart_count: usize,
+    pub message: Option<String>,
+}
+
 /// Rename parameters
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RenameParams {
@@ -582,6 +607,51 @@ pub struct TypeHierarchySubtypesParams {
     pub item: TypeHierarchyItem,
 }
 
+// ============================================================================
+// Server-to-Client Types
+// ============================================================================
+
+/// A capability registered by the server through `client/registerCapability`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DynamicRegistration {
+    pub id: String,
+    pub method: String,
+    pub register_options: Option<serde_json::Value>,
+}
+
+/// Event emitted for `$/progress` notifications
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProgressEvent {
+    pub server_id: String,
+    /// Token from `window/workDoneProgress/create` (string or integer)
+    pub token: serde_json::Value,
+    /// `WorkDoneProgressBegin`, `WorkDoneProgressReport` or `WorkDoneProgressEnd`
+    pub value: serde_json::Value,
+}
+
+/// Event emitted when a server asks the user to pick an action
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShowMessageRequestEvent {
+    pub server_id: String,
+    /// Key to pass back to `lsp_respond_show_message`
+    pub request_id: String,
+    /// LSP `MessageType`: 1 error, 2 warning, 3 info, 4 log
+    pub message_type: u8,
+    pub message: String,
+    pub actions: Vec<String>,
+}
+
+/// Event emitted after a server-initiated `workspace/applyEdit`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceEditAppliedEvent {
+    pub server_id: String,
+    pub label: Option<String>,
+    /// Files whose content changed on disk
+    pub uris: Vec<String>,
+    pub applied: bool,
+    pub failure_reason: Option<String>,
+}
+
 // ============================================================================
 // LSP Server Autodetect Types
 // ============================================================================
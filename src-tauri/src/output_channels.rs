@@ -120,6 +120,85 @@ impl OutputChannelState {
             .unwrap_or_default()
             .as_millis() as u64
     }
+
+    /// Return the id of the channel named `name`, creating it if needed.
+    ///
+    /// Backend subsystems use this to write into a stable, well-known channel
+    /// without tracking its generated id.
+    pub fn ensure_channel(
+        &self,
+        app: &AppHandle,
+        name: &str,
+        language_id: Option<&str>,
+    ) -> Result<String, String> {
+        let id = {
+            let mut channels = self
+                .channels
+                .lock()
+                .map_err(|_| "Failed to acquire lock".to_string())?;
+            if let Some(existing) = channels.values().find(|c| c.name == name) {
+                return Ok(existing.id.clone());
+            }
+            let id = self.generate_id()?;
+            channels.insert(
+                id.clone(),
+                OutputChannel {
+                    id: id.clone(),
+                    name: name.to_string(),
+                    language_id: language_id.map(str::to_string),
+                    lines: Vec::new(),
+                    visible: false,
+                    created_at: Self::now_ms(),
+                    max_lines: DEFAULT_MAX_LINES,
+                },
+            );
+            id
+        };
+
+        let event = OutputChannelEvent {
+            channel_id: id.clone(),
+            channel_name: name.to_string(),
+        };
+        let _ = app.emit("output:created", &event);
+
+        Ok(id)
+    }
+
+    /// Append a line (with trailing newline) to a channel and emit `output:append`.
+    pub fn append_line(
+        &self,
+        app: &AppHandle,
+        channel_id: &str,
+        text: &str,
+        level: OutputLevel,
+    ) -> Result<(), String> {
+        let line_text = format!("{}\n", text);
+
+        {
+            let mut channels = self
+                .channels
+                .lock()
+                .map_err(|_| "Failed to acquire lock".to_string())?;
+            let channel = channels
+                .get_mut(channel_id)
+                .ok_or_else(|| format!("Channel not found: {}", channel_id))?;
+
+            channel.lines.push(OutputLine {
+                timestamp: Self::now_ms(),
+                text: line_text.clone(),
+                level,
+            });
+            enforce_max_lines(channel);
+        }
+
+        let event = OutputContentEvent {
+            channel_id: channel_id.to_string(),
+            text: line_text,
+        };
+        let _ = app.emit("output:append", &event);
+
+        Ok(())
+    }
 }
 
 impl Default for OutputChannelState {
@@ -255,35 +334,7 @@ pub async fn output_channel_append_line(
     text: String,
     level: Option<OutputLevel>,
 ) -> Result<(), String> {
-    let now = OutputChannelState::now_ms();
-    let level = level.unwrap_or_default();
-    let line_text = format!("{}\n", text);
-
-    {
-        let mut channels = state
-            .channels
-            .lock()
-            .map_err(|_| "Failed to acquire lock".to_string())?;
-        let channel = channels
-            .get_mut(&channel_id)
-            .ok_or_else(|| format!("Channel not found: {}", channel_id))?;
-
-        let line = OutputLine {
-            timestamp: now,
-            text: line_text.clone(),
-            level,
-        };
-        channel.lines.push(line);
-        enforce_max_lines(channel);
-    }
-
-    let event = OutputContentEvent {
-        channel_id,
-        text: line_text,
-    };
-    let _ = app.emit("output:append", &event);
-
-    Ok(())
+    state.append_line(&app, &channel_id, &text, level.unwrap_or_default())
 }
 
 /// Clear a channel's content
@@ -0,0 +1,232 @@
+//! Connection from the app to a remote agent
+//!
+//! The client only needs the agent's stdio, so it is independent of SSH. A
+//! reader thread hands every frame to the `on_message` callback and then
+//! completes the request waiting for it. Terminals created through the
+//! client are remembered so their input can be routed back to it.
+
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use tokio::sync::oneshot;
+use tracing::{info, warn};
+
+use super::protocol::{
+    AgentMessage, AgentReply, AgentRequest, PROTOCOL_VERSION, RequestFrame, SearchContentParams,
+    write_frame,
+};
+use crate::fs::types::ContentSearchResponse;
+use crate::git::types::StatusResponse;
+use crate::tasks::TaskResult;
+use crate::terminal::{CreateTerminalOptions, TerminalInfo};
+
+/// How long a freshly started agent has to answer
+const HELLO_TIMEOUT: Duration = Duration::from_secs(30);
+
+type PendingReplies = Arc<Mutex<HashMap<u64, oneshot::Sender<Result<AgentReply, String>>>>>;
+
+pub struct AgentClient {
+    writer: Mutex<Option<Box<dyn Write + Send>>>,
+    pending: PendingReplies,
+    next_id: AtomicU64,
+    connected: Arc<AtomicBool>,
+    terminals: Mutex<HashSet<String>>,
+    platform: String,
+}
+
+fn unexpected(reply: AgentReply) -> String {
+    format!("Unexpected reply from remote agent: {:?}", reply)
+}
+
+impl AgentClient {
+    /// Talk to an agent through its stdio and check that it speaks our protocol
+    #[cfg_attr(not(feature = "remote-ssh"), allow(dead_code))]
+    pub(super) async fn connect(
+        reader: impl Read + Send + 'static,
+        writer: impl Write + Send + 'static,
+        on_message: impl Fn(&AgentMessage) + Send + 'static,
+    ) -> Result<Self, String> {
+        let pending: PendingReplies = Arc::new(Mutex::new(HashMap::new()));
+        let connected = Arc::new(AtomicBool::new(true));
+
+        let pending_clone = pending.clone();
+        let connected_clone = connected.clone();
+        thread::spawn(move || {
+            for line in BufReader::new(reader).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                let message: AgentMessage = match serde_json::from_str(&line) {
+                    Ok(message) => message,
+                    Err(e) => {
+                        warn!("Invalid remote agent message: {}", e);
+                        continue;
+                    }
+                };
+                on_message(&message);
+                if let AgentMessage::Reply { id, result } = message {
+                    if let Some(sender) = pending_clone.lock().remove(&id) {
+                        let _ = sender.send(result);
+                    }
+                }
+            }
+            connected_clone.store(false, Ordering::Relaxed);
+            // Fail requests still waiting for a reply
+            pending_clone.lock().clear();
+            info!("Disconnected from remote agent");
+        });
+
+        let mut client = Self {
+            writer: Mutex::new(Some(Box::new(writer))),
+            pending,
+            next_id: AtomicU64::new(1),
+            connected,
+            terminals: Mutex::new(HashSet::new()),
+            platform: String::new(),
+        };
+        let hello = client.request(AgentRequest::Hello {
+            version: PROTOCOL_VERSION,
+        });
+        let platform = match tokio::time::timeout(HELLO_TIMEOUT, hello).await {
+            Ok(Ok(AgentReply::Hello { platform, .. })) => Ok(platform),
+            Ok(Ok(reply)) => Err(unexpected(reply)),
+            Ok(Err(e)) => Err(e),
+            Err(_) => Err("Remote agent did not answer".to_string()),
+        };
+        match platform {
+            Ok(platform) => {
+                client.platform = platform;
+                Ok(client)
+            }
+            Err(e) => {
+                client.disconnect();
+                Err(e)
+            }
+        }
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::Relaxed)
+    }
+
+    /// `<os>-<arch>` of the remote host
+    pub fn platform(&self) -> &str {
+        &self.platform
+    }
+
+    /// Send a request and wait for its reply
+    async fn request(&self, request: AgentRequest) -> Result<AgentReply, String> {
+        if !self.is_connected() {
+            return Err("Remote agent is not running".to_string());
+        }
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (sender, receiver) = oneshot::channel();
+        self.pending.lock().insert(id, sender);
+
+        let written = match self.writer.lock().as_mut() {
+            Some(writer) => write_frame(writer, &RequestFrame { id, request })
+                .map_err(|e| format!("Failed to reach remote agent: {}", e)),
+            None => Err("Remote agent was stopped".to_string()),
+        };
+        if let Err(e) = written {
+            self.pending.lock().remove(&id);
+            return Err(e);
+        }
+
+        receiver
+            .await
+            .map_err(|_| "Lost connection to remote agent".to_string())?
+    }
+
+    /// Close the agent's stdin, which makes it close its terminals and exit
+    pub(super) fn disconnect(&self) {
+        self.writer.lock().take();
+        self.terminals.lock().clear();
+    }
+
+    pub(super) fn owns_terminal(&self, terminal_id: &str) -> bool {
+        self.terminals.lock().contains(terminal_id)
+    }
+
+    pub(super) async fn search_content(
+        &self,
+        params: SearchContentParams,
+    ) -> Result<ContentSearchResponse, String> {
+        match self.request(AgentRequest::SearchContent { params }).await? {
+            AgentReply::Search { response } => Ok(response),
+            reply => Err(unexpected(reply)),
+        }
+    }
+
+    pub(super) async fn git_status(&self, path: String) -> Result<StatusResponse, String> {
+        match self.request(AgentRequest::GitStatus { path }).await? {
+            AgentReply::GitStatus { status } => Ok(status),
+            reply => Err(unexpected(reply)),
+        }
+    }
+
+    pub(super) async fn run_task(
+        &self,
+        task_name: String,
+        workspace_path: String,
+    ) -> Result<TaskResult, String> {
+        let request = AgentRequest::RunTask {
+            task_name,
+            workspace_path,
+        };
+        match self.request(request).await? {
+            AgentReply::Task { result } => Ok(result),
+            reply => Err(unexpected(reply)),
+        }
+    }
+
+    pub(super) async fn terminal_create(
+        &self,
+        options: CreateTerminalOptions,
+    ) -> Result<TerminalInfo, String> {
+        let terminal_id = uuid::Uuid::new_v4().to_string();
+        let request = AgentRequest::TerminalCreate {
+            terminal_id: terminal_id.clone(),
+            options,
+        };
+        match self.request(request).await? {
+            AgentReply::TerminalCreated { info } => {
+                self.terminals.lock().insert(terminal_id);
+                Ok(info)
+            }
+            reply => Err(unexpected(reply)),
+        }
+    }
+
+    pub async fn terminal_write(&self, terminal_id: String, data: String) -> Result<(), String> {
+        self.request(AgentRequest::TerminalWrite { terminal_id, data })
+            .await
+            .map(|_| ())
+    }
+
+    pub async fn terminal_resize(
+        &self,
+        terminal_id: String,
+        cols: u16,
+        rows: u16,
+    ) -> Result<(), String> {
+        let request = AgentRequest::TerminalResize {
+            terminal_id,
+            cols,
+            rows,
+        };
+        self.request(request).await.map(|_| ())
+    }
+
+    pub async fn terminal_close(&self, terminal_id: String) -> Result<(), String> {
+        self.terminals.lock().remove(&terminal_id);
+        self.request(AgentRequest::TerminalClose { terminal_id })
+            .await
+            .map(|_| ())
+    }
+}
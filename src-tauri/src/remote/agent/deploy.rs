@@ -0,0 +1,181 @@
+//! Uploading and starting the agent over SSH
+//!
+//! The agent is installed once per app version under
+//! `~/.cortex-server/<version>/` on the remote host and started on a
+//! dedicated session of the connection. Its stderr is forwarded to the log.
+
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
+
+use super::AGENT_ARG;
+use super::client::AgentClient;
+use super::protocol::AgentMessage;
+use crate::lsp::commands::remote::shell_quote;
+use crate::remote::{RemoteManager, exec_channel};
+
+/// `<os>-<arch>` in Rust's naming from the output of `uname -sm`
+fn remote_platform(uname: &str) -> Result<String, String> {
+    let mut words = uname.split_whitespace();
+    let (Some(os), Some(arch)) = (words.next(), words.next()) else {
+        return Err(format!("Unrecognized remote platform: {}", uname.trim()));
+    };
+    let os = match os {
+        "Linux" => "linux",
+        "Darwin" => "macos",
+        "FreeBSD" => "freebsd",
+        other => return Err(format!("Remote agent does not support {}", other)),
+    };
+    let arch = match arch {
+        "x86_64" | "amd64" => "x86_64",
+        "aarch64" | "arm64" => "aarch64",
+        other => return Err(format!("Remote agent does not support {}", other)),
+    };
+    Ok(format!("{}-{}", os, arch))
+}
+
+/// Headless `cortex-server-<platform>` build next to the app
+///
+/// They are bundled as sidecars by `npm run tauri:build:agents`. The app
+/// itself is never uploaded: it links GUI libraries servers rarely have.
+fn agent_binary(platform: &str) -> Result<PathBuf, String> {
+    let exe = std::env::current_exe().map_err(|e| format!("Failed to locate Cortex: {}", e))?;
+    let headless = exe
+        .parent()
+        .map(|dir| {
+            dir.join(format!(
+                "cortex-server-{}{}",
+                platform,
+                std::env::consts::EXE_SUFFIX
+            ))
+        })
+        .filter(|path| path.is_file());
+    headless.ok_or_else(|| format!("No Cortex server build for {} is installed", platform))
+}
+
+async fn run(
+    manager: &RemoteManager,
+    connection_id: &str,
+    command: &str,
+) -> Result<String, String> {
+    let result = manager
+        .execute_command(connection_id, command, None)
+        .await
+        .map_err(|e| e.to_string())?;
+    if result.exit_code != 0 {
+        return Err(format!("`{}` failed: {}", command, result.stderr.trim()));
+    }
+    Ok(result.stdout)
+}
+
+/// Upload the agent unless this version is already installed
+///
+/// Returns the agent's path on the remote host.
+async fn install(manager: &RemoteManager, connection_id: &str) -> Result<String, String> {
+    let platform = remote_platform(&run(manager, connection_id, "uname -sm").await?)?;
+    let home = manager
+        .get_connection_status(connection_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .home_directory
+        .ok_or("Remote home directory is unknown")?;
+    let dir = format!("{}/.cortex-server/{}", home, env!("CARGO_PKG_VERSION"));
+    let path = format!("{}/cortex-server-{}", dir, platform);
+
+    let binary = agent_binary(&platform)?;
+    let bytes = tokio::fs::read(&binary)
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", binary.display(), e))?;
+
+    // An agent left over from a different build of the same version is
+    // replaced, as is one that was corrupted
+    let digest = format!("{:x}", Sha256::digest(&bytes));
+    let quoted = shell_quote(&path);
+    let installed = run(
+        manager,
+        connection_id,
+        &format!(
+            "sha256sum {} 2>/dev/null || shasum -a 256 {} 2>/dev/null || true",
+            quoted, quoted
+        ),
+    )
+    .await?;
+    if installed.split_whitespace().next() == Some(digest.as_str()) {
+        return Ok(path);
+    }
+
+    info!(
+        "Uploading remote agent ({} bytes) to {}:{}",
+        bytes.len(),
+        connection_id,
+        path
+    );
+    manager
+        .create_directory(connection_id, &dir, true)
+        .await
+        .map_err(|e| e.to_string())?;
+    // Upload beside the final path so a half-written agent is never started
+    let partial = format!("{}.partial", path);
+    manager
+        .write_file_bytes(connection_id, &partial, &bytes)
+        .await
+        .map_err(|e| e.to_string())?;
+    run(
+        manager,
+        connection_id,
+        &format!(
+            "chmod 755 {} && mv -f {} {}",
+            shell_quote(&partial),
+            shell_quote(&partial),
+            shell_quote(&path)
+        ),
+    )
+    .await?;
+    Ok(path)
+}
+
+/// Install the agent if needed, start it and complete the handshake
+pub(super) async fn start(
+    manager: &RemoteManager,
+    connection_id: &str,
+    on_message: impl Fn(&AgentMessage) + Send + 'static,
+) -> Result<AgentClient, String> {
+    let path = install(manager, connection_id).await?;
+    let session = manager
+        .open_session(connection_id)
+        .await
+        .map_err(|e| format!("Failed to open remote session: {}", e))?;
+
+    let command = format!("{} {}", shell_quote(&path), AGENT_ARG);
+    info!("Starting remote agent on {}: {}", connection_id, command);
+    let process = tokio::task::spawn_blocking(move || exec_channel::spawn(session, &command))
+        .await
+        .map_err(|e| format!("Remote spawn task failed: {}", e))?
+        .map_err(|e| format!("Failed to start remote agent: {}", e))?;
+
+    let label = connection_id.to_string();
+    let stderr = process.stderr;
+    std::thread::spawn(move || {
+        for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+            warn!("Remote agent on {}: {}", label, line);
+        }
+    });
+
+    AgentClient::connect(process.stdout, process.stdin, on_message).await
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_uname_output() {
+        assert_eq!(remote_platform("Linux x86_64\n").unwrap(), "linux-x86_64");
+        assert_eq!(remote_platform("Darwin arm64").unwrap(), "macos-aarch64");
+        assert!(remote_platform("Linux").is_err());
+        assert!(remote_platform("SunOS sparc64").is_err());
+    }
+}
@@ -0,0 +1,38 @@
+//! Remote agent
+//!
+//! Plain SFTP only gets remote workspaces file-by-file access. For search,
+//! git, tasks and terminals the app uploads the headless `cortex-server`
+//! build for the host's platform, shipped next to it, and starts it there as
+//! `--remote-agent`. The agent speaks a multiplexed RPC protocol over the
+//! exec channel's stdio and runs the same code the app runs locally.
+//!
+//! A remote workspace is mounted at a local virtual root, like remote
+//! language servers. Workspace-scoped commands check
+//! `RemoteAgentsState::mount_for` first and are forwarded to the agent, with
+//! paths translated between the local root and the remote root in both
+//! directions.
+//!
+//! # Module Structure
+//!
+//! - `protocol`: Frames exchanged with the agent
+//! - `server`: The agent process
+//! - `client`: Connection from the app to an agent
+//! - `deploy`: Uploading and starting the agent over SSH
+//! - `mounts`: Running agents, the workspaces they serve and their commands
+
+mod client;
+#[cfg(all(feature = "desktop", feature = "remote-ssh"))]
+mod deploy;
+#[cfg(feature = "desktop")]
+mod mounts;
+mod protocol;
+mod server;
+
+pub use client::AgentClient;
+#[cfg(feature = "desktop")]
+pub use mounts::*;
+pub use protocol::SearchContentParams;
+pub use server::run_from_args;
+
+/// Argument that starts the executable as the remote agent
+const AGENT_ARG: &str = "--remote-agent";
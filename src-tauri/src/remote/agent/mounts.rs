@@ -0,0 +1,326 @@
+//! Running agents and the remote workspaces they serve
+//!
+//! The app keeps one agent per SSH connection in `RemoteAgentsState`. Each
+//! mounts any number of remote directories at local virtual roots.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, State};
+use tracing::info;
+
+use super::client::AgentClient;
+use super::protocol::{AgentMessage, SearchContentParams};
+use crate::fs::types::ContentSearchResponse;
+use crate::git::types::StatusResponse;
+use crate::remote::RemoteManager;
+use crate::tasks::TaskResult;
+use crate::terminal::{CreateTerminalOptions, TerminalInfo, TerminalOutput, TerminalStatus};
+
+/// A remote directory mounted at a local virtual path
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteWorkspace {
+    pub local_root: String,
+    pub remote_root: String,
+}
+
+/// A running agent as reported to the frontend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteAgentInfo {
+    pub connection_id: String,
+    pub platform: String,
+    pub connected: bool,
+    pub workspaces: Vec<RemoteWorkspace>,
+}
+
+struct RunningAgent {
+    client: Arc<AgentClient>,
+    workspaces: Vec<RemoteWorkspace>,
+}
+
+/// Agents by connection ID
+#[derive(Clone, Default)]
+pub struct RemoteAgentsState {
+    agents: Arc<RwLock<HashMap<String, RunningAgent>>>,
+}
+
+/// The part of `path` below `root`, starting with `/`, or empty for the root
+fn relative_to(path: &str, root: &str) -> Option<String> {
+    let path = path.replace('\\', "/");
+    let root = root.replace('\\', "/");
+    let root = root.trim_end_matches('/');
+    let rest = path.strip_prefix(root)?;
+    if rest.is_empty() || rest.starts_with('/') {
+        Some(rest.trim_end_matches('/').to_string())
+    } else {
+        None
+    }
+}
+
+fn join_root(root: &str, rest: &str) -> String {
+    let root = root.trim_end_matches(['/', '\\']);
+    if root.is_empty() && rest.is_empty() {
+        "/".to_string()
+    } else {
+        format!("{}{}", root, rest)
+    }
+}
+
+/// A workspace together with the agent serving it
+#[derive(Clone)]
+pub struct Mount {
+    client: Arc<AgentClient>,
+    workspace: RemoteWorkspace,
+}
+
+impl Mount {
+    fn to_remote(&self, local: &str) -> String {
+        match relative_to(local, &self.workspace.local_root) {
+            Some(rest) => join_root(&self.workspace.remote_root, &rest),
+            None => local.to_string(),
+        }
+    }
+
+    fn to_local(&self, remote: &str) -> String {
+        match relative_to(remote, &self.workspace.remote_root) {
+            Some(rest) => join_root(&self.workspace.local_root, &rest),
+            None => remote.to_string(),
+        }
+    }
+
+    pub async fn search_content(
+        &self,
+        mut params: SearchContentParams,
+    ) -> Result<ContentSearchResponse, String> {
+        params.path = self.to_remote(&params.path);
+        let mut response = self.client.search_content(params).await?;
+        for result in &mut response.results {
+            result.file = self.to_local(&result.file);
+        }
+        Ok(response)
+    }
+
+    pub async fn git_status(&self, path: &str) -> Result<StatusResponse, String> {
+        self.client.git_status(self.to_remote(path)).await
+    }
+
+    pub async fn run_task(
+        &self,
+        task_name: String,
+        workspace_path: &str,
+    ) -> Result<TaskResult, String> {
+        self.client
+            .run_task(task_name, self.to_remote(workspace_path))
+            .await
+    }
+
+    pub async fn terminal_create(
+        &self,
+        mut options: CreateTerminalOptions,
+    ) -> Result<TerminalInfo, String> {
+        options.cwd = options.cwd.map(|cwd| self.to_remote(&cwd));
+        let mut info = self.client.terminal_create(options).await?;
+        info.cwd = self.to_local(&info.cwd);
+        Ok(info)
+    }
+}
+
+impl RemoteAgentsState {
+    /// The mounted remote workspace containing `path`, if any
+    pub fn mount_for(&self, path: &str) -> Option<Mount> {
+        let agents = self.agents.read();
+        agents
+            .values()
+            .flat_map(|agent| {
+                agent.workspaces.iter().filter_map(|workspace| {
+                    relative_to(path, &workspace.local_root)?;
+                    Some(Mount {
+                        client: agent.client.clone(),
+                        workspace: workspace.clone(),
+                    })
+                })
+            })
+            .max_by_key(|mount| mount.workspace.local_root.len())
+    }
+
+    /// The agent running terminal `terminal_id`, if it is a remote terminal
+    pub fn terminal_agent(&self, terminal_id: &str) -> Option<Arc<AgentClient>> {
+        self.agents
+            .read()
+            .values()
+            .find(|agent| agent.client.owns_terminal(terminal_id))
+            .map(|agent| agent.client.clone())
+    }
+}
+
+/// Re-emit terminal frames as the events local terminals produce
+fn forward_events(app: AppHandle) -> impl Fn(&AgentMessage) + Send + 'static {
+    move |message| match message {
+        AgentMessage::TerminalOutput { terminal_id, data } => {
+            let output = TerminalOutput {
+                terminal_id: terminal_id.clone(),
+                data: data.clone(),
+            };
+            let _ = app.emit("terminal:output", &output);
+        }
+        AgentMessage::TerminalExited {
+            terminal_id,
+            exit_code,
+        } => {
+            let status = TerminalStatus {
+                terminal_id: terminal_id.clone(),
+                status: "exited".to_string(),
+                exit_code: *exit_code,
+            };
+            let _ = app.emit("terminal:status", &status);
+        }
+        AgentMessage::Reply { .. } => {}
+    }
+}
+
+#[cfg(feature = "remote-ssh")]
+async fn start_agent(
+    app: AppHandle,
+    manager: &RemoteManager,
+    connection_id: &str,
+) -> Result<AgentClient, String> {
+    super::deploy::start(manager, connection_id, forward_events(app)).await
+}
+
+#[cfg(not(feature = "remote-ssh"))]
+async fn start_agent(
+    app: AppHandle,
+    _manager: &RemoteManager,
+    connection_id: &str,
+) -> Result<AgentClient, String> {
+    let _ = forward_events(app);
+    Err(format!(
+        "Cannot start a remote agent on {}: SSH support is not enabled in this build",
+        connection_id
+    ))
+}
+
+fn agent_info(connection_id: &str, agent: &RunningAgent) -> RemoteAgentInfo {
+    RemoteAgentInfo {
+        connection_id: connection_id.to_string(),
+        platform: agent.client.platform().to_string(),
+        connected: agent.client.is_connected(),
+        workspaces: agent.workspaces.clone(),
+    }
+}
+
+/// Mount `remote_root` at `local_root`, starting the connection's agent if
+/// it is not running yet
+#[tauri::command]
+pub async fn remote_agent_start(
+    app: AppHandle,
+    state: State<'_, Arc<RemoteManager>>,
+    agents: State<'_, RemoteAgentsState>,
+    connection_id: String,
+    local_root: String,
+    remote_root: String,
+) -> Result<RemoteAgentInfo, String> {
+    let running = agents
+        .agents
+        .read()
+        .get(&connection_id)
+        .is_some_and(|agent| agent.client.is_connected());
+    if !running {
+        let client = start_agent(app, &state, &connection_id).await?;
+        info!(
+            "Remote agent running on {} ({})",
+            connection_id,
+            client.platform()
+        );
+        let agent = RunningAgent {
+            client: Arc::new(client),
+            workspaces: Vec::new(),
+        };
+        let stale = agents.agents.write().insert(connection_id.clone(), agent);
+        if let Some(stale) = stale {
+            stale.client.disconnect();
+        }
+    }
+
+    let mut agents = agents.agents.write();
+    let agent = agents
+        .get_mut(&connection_id)
+        .ok_or_else(|| format!("Remote agent for {} was stopped", connection_id))?;
+    let workspace = RemoteWorkspace {
+        local_root,
+        remote_root,
+    };
+    if !agent.workspaces.contains(&workspace) {
+        info!(
+            "Mounted {}:{} at {}",
+            connection_id, workspace.remote_root, workspace.local_root
+        );
+        agent.workspaces.push(workspace);
+    }
+    Ok(agent_info(&connection_id, agent))
+}
+
+/// Stop a connection's agent, closing its terminals and unmounting its
+/// workspaces
+#[tauri::command]
+pub async fn remote_agent_stop(
+    agents: State<'_, RemoteAgentsState>,
+    connection_id: String,
+) -> Result<(), String> {
+    let agent = agents
+        .agents
+        .write()
+        .remove(&connection_id)
+        .ok_or_else(|| format!("No remote agent running for {}", connection_id))?;
+    agent.client.disconnect();
+    info!("Stopped remote agent on {}", connection_id);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn remote_agent_list(
+    agents: State<'_, RemoteAgentsState>,
+) -> Result<Vec<RemoteAgentInfo>, String> {
+    Ok(agents
+        .agents
+        .read()
+        .iter()
+        .map(|(connection_id, agent)| agent_info(connection_id, agent))
+        .collect())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths_below_root_are_relative() {
+        assert_eq!(relative_to("/srv/app", "/srv/app/"), Some(String::new()));
+        assert_eq!(
+            relative_to("/srv/app/src/main.rs", "/srv/app"),
+            Some("/src/main.rs".to_string())
+        );
+        assert_eq!(relative_to("/srv/application", "/srv/app"), None);
+        assert_eq!(
+            relative_to(r"C:\remote\box\src", r"C:\remote\box"),
+            Some("/src".to_string())
+        );
+        assert_eq!(
+            relative_to("/etc/hosts", "/"),
+            Some("/etc/hosts".to_string())
+        );
+    }
+
+    #[test]
+    fn roots_join_with_relative_paths() {
+        assert_eq!(join_root("/home/dev/app/", "/src"), "/home/dev/app/src");
+        assert_eq!(join_root("/home/dev/app", ""), "/home/dev/app");
+        assert_eq!(join_root("/", ""), "/");
+        assert_eq!(join_root("/", "/etc"), "/etc");
+    }
+}
@@ -0,0 +1,189 @@
+//! Remote agent wire protocol
+//!
+//! Newline-delimited JSON frames over the agent's stdin and stdout. The app
+//! sends numbered requests; the agent answers each with a reply carrying the
+//! same number, in whatever order they finish, and pushes terminal output
+//! and exits as they happen. Paths in frames are paths on the remote host.
+
+use std::io::{self, Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::fs::types::ContentSearchResponse;
+use crate::git::types::StatusResponse;
+use crate::tasks::TaskResult;
+use crate::terminal::{CreateTerminalOptions, TerminalInfo};
+
+/// Bumped whenever the frames change incompatibly
+pub(super) const PROTOCOL_VERSION: u32 = 1;
+
+/// Arguments of `fs_search_content`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SearchContentParams {
+    pub path: String,
+    pub query: String,
+    pub case_sensitive: Option<bool>,
+    pub regex: Option<bool>,
+    pub whole_word: Option<bool>,
+    pub multiline: Option<bool>,
+    pub include: Option<String>,
+    pub exclude: Option<String>,
+    pub max_results: Option<u32>,
+}
+
+/// Request from the app to the agent
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub(super) enum AgentRequest {
+    Hello {
+        version: u32,
+    },
+    SearchContent {
+        params: SearchContentParams,
+    },
+    GitStatus {
+        path: String,
+    },
+    RunTask {
+        task_name: String,
+        workspace_path: String,
+    },
+    TerminalCreate {
+        terminal_id: String,
+        options: CreateTerminalOptions,
+    },
+    TerminalWrite {
+        terminal_id: String,
+        data: String,
+    },
+    TerminalResize {
+        terminal_id: String,
+        cols: u16,
+        rows: u16,
+    },
+    TerminalClose {
+        terminal_id: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(super) struct RequestFrame {
+    pub id: u64,
+    pub request: AgentRequest,
+}
+
+/// Successful answer to a request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub(super) enum AgentReply {
+    Done,
+    Hello {
+        version: u32,
+        /// `<os>-<arch>` of the remote host
+        platform: String,
+    },
+    Search {
+        response: ContentSearchResponse,
+    },
+    GitStatus {
+        status: StatusResponse,
+    },
+    Task {
+        result: TaskResult,
+    },
+    TerminalCreated {
+        info: TerminalInfo,
+    },
+}
+
+/// Frame from the agent to the app
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub(super) enum AgentMessage {
+    Reply {
+        id: u64,
+        result: Result<AgentReply, String>,
+    },
+    TerminalOutput {
+        terminal_id: String,
+        data: String,
+    },
+    TerminalExited {
+        terminal_id: String,
+        exit_code: Option<i32>,
+    },
+}
+
+/// Write one frame
+pub(super) fn write_frame<T: Serialize>(writer: &mut impl Write, frame: &T) -> io::Result<()> {
+    let mut line = serde_json::to_vec(frame)?;
+    line.push(b'\n');
+    writer.write_all(&line)?;
+    writer.flush()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_round_trip_as_single_lines() {
+        let mut out = Vec::new();
+        write_frame(
+            &mut out,
+            &RequestFrame {
+                id: 3,
+                request: AgentRequest::SearchContent {
+                    params: SearchContentParams {
+                        path: "/srv/app".to_string(),
+                        query: "fn main\n".to_string(),
+                        regex: Some(true),
+                        ..Default::default()
+                    },
+                },
+            },
+        )
+        .unwrap();
+        assert_eq!(out.iter().filter(|&&b| b == b'\n').count(), 1);
+
+        let frame: RequestFrame = serde_json::from_slice(&out).unwrap();
+        assert_eq!(frame.id, 3);
+        match frame.request {
+            AgentRequest::SearchContent { params } => {
+                assert_eq!(params.path, "/srv/app");
+                assert_eq!(params.query, "fn main\n");
+                assert_eq!(params.regex, Some(true));
+                assert_eq!(params.max_results, None);
+            }
+            other => panic!("unexpected request {:?}", other),
+        }
+    }
+
+    #[test]
+    fn replies_carry_errors_and_results() {
+        let json = serde_json::to_string(&AgentMessage::Reply {
+            id: 1,
+            result: Err("not a git repository".to_string()),
+        })
+        .unwrap();
+        match serde_json::from_str(&json).unwrap() {
+            AgentMessage::Reply { id: 1, result } => {
+                assert_eq!(result.unwrap_err(), "not a git repository");
+            }
+            other => panic!("unexpected message {:?}", other),
+        }
+
+        let json = r#"{"type":"reply","id":2,"result":{"Ok":{"kind":"hello","version":1,"platform":"linux-x86_64"}}}"#;
+        match serde_json::from_str(json).unwrap() {
+            AgentMessage::Reply {
+                result: Ok(AgentReply::Hello { version, platform }),
+                ..
+            } => {
+                assert_eq!(version, 1);
+                assert_eq!(platform, "linux-x86_64");
+            }
+            other => panic!("unexpected message {:?}", other),
+        }
+    }
+}
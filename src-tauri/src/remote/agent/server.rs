@@ -0,0 +1,380 @@
+//! Headless agent process
+//!
+//! Runs on the remote host as `<cortex> --remote-agent`, with stdin and
+//! stdout bridged to an SSH exec channel. Searches, git and tasks are served
+//! concurrently by the same code the app runs locally; terminal input is
+//! applied in the order it arrives. The agent closes its terminals and exits
+//! when stdin reaches end of file. Nothing else may write to stdout.
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use portable_pty::{MasterPty, PtySize};
+use tracing::{info, warn};
+
+use super::AGENT_ARG;
+use super::protocol::{
+    AgentMessage, AgentReply, AgentRequest, PROTOCOL_VERSION, RequestFrame, write_frame,
+};
+use crate::terminal::{
+    CreateTerminalOptions, default_shell, inject_shell_integration, kill_process_tree, spawn_shell,
+    take_utf8,
+};
+
+/// Larger than a local terminal's reads, as each one becomes a frame
+const READ_BUFFER_SIZE: usize = 16 * 1024;
+
+/// Run as the remote agent if the process was started with `--remote-agent`
+///
+/// Returns `false` for a normal start.
+pub fn run_from_args() -> bool {
+    if std::env::args().nth(1).as_deref() != Some(AGENT_ARG) {
+        return false;
+    }
+    match tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+    {
+        Ok(runtime) => runtime.block_on(serve(std::io::stdin(), std::io::stdout())),
+        Err(e) => eprintln!("Remote agent failed to start: {}", e),
+    }
+    true
+}
+
+struct AgentTerminal {
+    master: Box<dyn MasterPty + Send>,
+    writer: Arc<Mutex<BufWriter<Box<dyn Write + Send>>>>,
+    child_pid: Option<u32>,
+}
+
+struct Agent {
+    writer: Mutex<Box<dyn Write + Send>>,
+    terminals: Mutex<HashMap<String, AgentTerminal>>,
+}
+
+impl Agent {
+    fn send(&self, message: &AgentMessage) {
+        if let Err(e) = write_frame(&mut *self.writer.lock(), message) {
+            warn!("Failed to write agent frame: {}", e);
+        }
+    }
+
+    fn pump_output(self: &Arc<Self>, terminal_id: String, mut reader: Box<dyn Read + Send>) {
+        let agent = self.clone();
+        thread::spawn(move || {
+            let mut buf = [0u8; READ_BUFFER_SIZE];
+            let mut pending = Vec::new();
+            loop {
+                match reader.read(&mut buf) {
+                    Ok(0) => break,
+                    Ok(n) => {
+                        pending.extend_from_slice(&buf[..n]);
+                        let data = take_utf8(&mut pending);
+                        if data.is_empty() {
+                            continue;
+                        }
+                        if !agent.terminals.lock().contains_key(&terminal_id) {
+                            break;
+                        }
+                        agent.send(&AgentMessage::TerminalOutput {
+                            terminal_id: terminal_id.clone(),
+                            data,
+                        });
+                    }
+                    Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                    Err(_) => break,
+                }
+            }
+        });
+    }
+
+    /// Spawn a terminal and reply before its first output
+    fn create_terminal(
+        self: &Arc<Self>,
+        id: u64,
+        terminal_id: String,
+        options: CreateTerminalOptions,
+    ) {
+        if self.terminals.lock().contains_key(&terminal_id) {
+            let result = Err(format!("Terminal {} already exists", terminal_id));
+            self.send(&AgentMessage::Reply { id, result });
+            return;
+        }
+        let shell = match spawn_shell(terminal_id.clone(), options, default_shell) {
+            Ok(shell) => shell,
+            Err(e) => {
+                self.send(&AgentMessage::Reply { id, result: Err(e) });
+                return;
+            }
+        };
+
+        let mut child = shell.child;
+        let terminal_id_clone = terminal_id.clone();
+        let agent = self.clone();
+        thread::spawn(move || {
+            let exit_code = child.wait().map(|s| s.exit_code() as i32).ok();
+            if agent.terminals.lock().contains_key(&terminal_id_clone) {
+                agent.send(&AgentMessage::TerminalExited {
+                    terminal_id: terminal_id_clone,
+                    exit_code,
+                });
+            }
+        });
+
+        let info = shell.info.clone();
+        self.terminals.lock().insert(
+            terminal_id.clone(),
+            AgentTerminal {
+                master: shell.master,
+                writer: shell.writer.clone(),
+                child_pid: shell.child_pid,
+            },
+        );
+        let result = Ok(AgentReply::TerminalCreated { info: info.clone() });
+        self.send(&AgentMessage::Reply { id, result });
+        self.pump_output(terminal_id, shell.reader);
+
+        if shell.inject_integration {
+            // Small delay to allow the shell to initialize
+            thread::sleep(Duration::from_millis(100));
+            if let Err(e) = inject_shell_integration(&info.shell, &shell.writer) {
+                warn!("Failed to inject shell integration: {}", e);
+            }
+        }
+    }
+
+    fn close_terminal(&self, terminal_id: &str) -> Result<(), String> {
+        let terminal = self
+            .terminals
+            .lock()
+            .remove(terminal_id)
+            .ok_or_else(|| format!("Terminal {} not found", terminal_id))?;
+        if let Some(pid) = terminal.child_pid {
+            if let Err(e) = kill_process_tree(pid) {
+                warn!("Failed to kill terminal process {}: {}", pid, e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Requests answered in arrival order
+    fn handle_terminal(&self, request: AgentRequest) -> Result<AgentReply, String> {
+        match request {
+            AgentRequest::TerminalWrite { terminal_id, data } => {
+                let writer = self
+                    .terminals
+                    .lock()
+                    .get(&terminal_id)
+                    .map(|t| t.writer.clone())
+                    .ok_or_else(|| format!("Terminal {} not found", terminal_id))?;
+                let mut writer = writer.lock();
+                writer
+                    .write_all(data.as_bytes())
+                    .and_then(|_| writer.flush())
+                    .map_err(|e| format!("Failed to write to terminal: {}", e))?;
+            }
+            AgentRequest::TerminalResize {
+                terminal_id,
+                cols,
+                rows,
+            } => {
+                let terminals = self.terminals.lock();
+                let terminal = terminals
+                    .get(&terminal_id)
+                    .ok_or_else(|| format!("Terminal {} not found", terminal_id))?;
+                terminal
+                    .master
+                    .resize(PtySize {
+                        rows,
+                        cols,
+                        pixel_width: 0,
+                        pixel_height: 0,
+                    })
+                    .map_err(|e| format!("Failed to resize PTY: {}", e))?;
+            }
+            AgentRequest::TerminalClose { terminal_id } => self.close_terminal(&terminal_id)?,
+            _ => return Err("Unexpected request".to_string()),
+        }
+        Ok(AgentReply::Done)
+    }
+
+    /// Requests that may take a while and are answered as they finish
+    async fn handle(&self, request: AgentRequest) -> Result<AgentReply, String> {
+        match request {
+            AgentRequest::Hello { version } => {
+                if version != PROTOCOL_VERSION {
+                    return Err(format!(
+                        "Remote agent speaks protocol {}, app speaks {}",
+                        PROTOCOL_VERSION, version
+                    ));
+                }
+                Ok(AgentReply::Hello {
+                    version: PROTOCOL_VERSION,
+                    platform: format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH),
+                })
+            }
+            AgentRequest::SearchContent { params } => crate::fs::search::fs_search_content_local(
+                params.path,
+                params.query,
+                params.case_sensitive,
+                params.regex,
+                params.whole_word,
+                params.multiline,
+                params.include,
+                params.exclude,
+                params.max_results,
+            )
+            .await
+            .map(|response| AgentReply::Search { response }),
+            AgentRequest::GitStatus { path } => crate::git::status::git_status_local(path)
+                .await
+                .map(|status| AgentReply::GitStatus { status }),
+            AgentRequest::RunTask {
+                task_name,
+                workspace_path,
+            } => crate::tasks::tasks_run_task_local(task_name, Some(workspace_path))
+                .await
+                .map(|result| AgentReply::Task { result }),
+            _ => Err("Unexpected request".to_string()),
+        }
+    }
+
+    fn close_all(&self) {
+        let ids: Vec<String> = self.terminals.lock().keys().cloned().collect();
+        for id in ids {
+            let _ = self.close_terminal(&id);
+        }
+    }
+}
+
+/// Serve requests read from `reader` until it closes
+pub(super) async fn serve(reader: impl Read + Send + 'static, writer: impl Write + Send + 'static) {
+    let agent = Arc::new(Agent {
+        writer: Mutex::new(Box::new(writer)),
+        terminals: Mutex::new(HashMap::new()),
+    });
+
+    let (line_tx, mut lines) = tokio::sync::mpsc::unbounded_channel();
+    thread::spawn(move || {
+        for line in BufReader::new(reader).lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if line_tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    while let Some(line) = lines.recv().await {
+        let frame: RequestFrame = match serde_json::from_str(&line) {
+            Ok(frame) => frame,
+            Err(e) => {
+                warn!("Invalid remote agent request: {}", e);
+                continue;
+            }
+        };
+        let id = frame.id;
+        let agent = agent.clone();
+        match frame.request {
+            AgentRequest::TerminalCreate {
+                terminal_id,
+                options,
+            } => {
+                tokio::task::spawn_blocking(move || {
+                    agent.create_terminal(id, terminal_id, options)
+                });
+            }
+            request @ (AgentRequest::TerminalWrite { .. }
+            | AgentRequest::TerminalResize { .. }
+            | AgentRequest::TerminalClose { .. }) => {
+                let result = agent.handle_terminal(request);
+                agent.send(&AgentMessage::Reply { id, result });
+            }
+            request => {
+                tokio::spawn(async move {
+                    let result = agent.handle(request).await;
+                    agent.send(&AgentMessage::Reply { id, result });
+                });
+            }
+        }
+    }
+
+    agent.close_all();
+    info!("Remote agent input closed");
+}
+
+#[cfg(all(test, unix))]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use std::os::unix::net::UnixStream;
+    use std::sync::mpsc;
+    use std::time::Instant;
+
+    use super::super::client::AgentClient;
+    use super::*;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn serves_an_in_process_client() {
+        let (app_end, agent_end) = UnixStream::pair().unwrap();
+        let agent_writer = agent_end.try_clone().unwrap();
+        tokio::spawn(serve(agent_end, agent_writer));
+
+        let (output_tx, output_rx) = mpsc::channel();
+        let client = AgentClient::connect(app_end.try_clone().unwrap(), app_end, move |message| {
+            if let AgentMessage::TerminalOutput { data, .. } = message {
+                let _ = output_tx.send(data.clone());
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(
+            client.platform(),
+            format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH)
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().to_string_lossy().to_string();
+        std::fs::write(dir.path().join("hello.txt"), "a needle here\n").unwrap();
+        let params = super::super::SearchContentParams {
+            path: root.clone(),
+            query: "needle".to_string(),
+            ..Default::default()
+        };
+        let response = client.search_content(params).await.unwrap();
+        assert_eq!(response.results.len(), 1);
+        assert!(response.results[0].file.ends_with("hello.txt"));
+
+        // Failures come back as error replies, not as a broken connection
+        assert!(client.git_status(root.clone()).await.is_err());
+        assert!(client.is_connected());
+
+        let options = CreateTerminalOptions {
+            cwd: Some(root),
+            shell: Some("/bin/sh".to_string()),
+            shell_integration: Some(false),
+            ..Default::default()
+        };
+        let info = client.terminal_create(options).await.unwrap();
+        assert!(client.owns_terminal(&info.id));
+        client
+            .terminal_write(info.id.clone(), "echo agent-$((40 + 2))\n".to_string())
+            .await
+            .unwrap();
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let mut output = String::new();
+        while !output.contains("agent-42") {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            output.push_str(&output_rx.recv_timeout(remaining).expect("terminal output"));
+        }
+
+        client.terminal_close(info.id.clone()).await.unwrap();
+        assert!(!client.owns_terminal(&info.id));
+        assert!(client.terminal_close(info.id).await.is_err());
+        client.disconnect();
+    }
+}
@@ -1,25 +1,51 @@
 //! Tauri command handlers for remote development operations.
 
+use std::path::Path;
 use std::sync::Arc;
-use tauri::State;
+use tauri::{AppHandle, Manager, State};
 use uuid::Uuid;
 
 use super::credentials::SecureSshCredentials;
+use super::devcontainer::config::{DevContainerConfig, load_config, save_config};
+use super::devcontainer::registry::{
+    DevContainerFeature, DevContainerTemplate, list_features, list_templates,
+};
+use super::devcontainer::{DevContainer, DevContainerState};
+use super::error::RemoteError;
+#[cfg(feature = "remote-ssh")]
+use super::host_keys::prompt_if_unknown;
 use super::manager::RemoteManager;
+use super::ssh_config::SshConfig;
 use super::types::{
     CommandResult, ConnectionInfo, ConnectionProfile, RemoteFileEntry, RemoteFileNode,
 };
+use crate::tasks::TaskResult;
+use crate::terminal::{TerminalInfo, TerminalState};
+
+/// Surface a connection error, asking the UI about unknown host keys
+fn connect_error(app: &AppHandle, error: RemoteError) -> String {
+    #[cfg(feature = "remote-ssh")]
+    prompt_if_unknown(app, &error);
+    #[cfg(not(feature = "remote-ssh"))]
+    let _ = app;
+    error.to_string()
+}
 
 #[tauri::command]
 pub async fn remote_connect(
+    app: AppHandle,
     profile: ConnectionProfile,
     state: State<'_, Arc<RemoteManager>>,
 ) -> Result<ConnectionInfo, String> {
-    state.connect(profile).await.map_err(|e| e.to_string())
+    state
+        .connect(profile)
+        .await
+        .map_err(|e| connect_error(&app, e))
 }
 
 #[tauri::command]
 pub async fn remote_connect_with_password(
+    app: AppHandle,
     profile: ConnectionProfile,
     password: String,
     state: State<'_, Arc<RemoteManager>>,
@@ -27,11 +53,12 @@ pub async fn remote_connect_with_password(
     state
         .connect_with_credentials(profile, Some(&password), None)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| connect_error(&app, e))
 }
 
 #[tauri::command]
 pub async fn remote_connect_with_passphrase(
+    app: AppHandle,
     profile: ConnectionProfile,
     passphrase: String,
     state: State<'_, Arc<RemoteManager>>,
@@ -39,7 +66,7 @@ pub async fn remote_connect_with_passphrase(
     state
         .connect_with_credentials(profile, None, Some(&passphrase))
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| connect_error(&app, e))
 }
 
 #[tauri::command]
@@ -247,6 +274,23 @@ pub fn remote_get_default_key_paths() -> Vec<String> {
     paths
 }
 
+/// Import every concrete `Host` of `~/.ssh/config` as a profile
+///
+/// Re-importing updates the profiles created by an earlier import.
+#[tauri::command]
+pub async fn remote_import_ssh_config(
+    state: State<'_, Arc<RemoteManager>>,
+) -> Result<Vec<ConnectionProfile>, String> {
+    let profiles = SshConfig::load().profiles();
+    for profile in &profiles {
+        state
+            .save_profile(profile.clone())
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(profiles)
+}
+
 #[tauri::command]
 pub fn remote_has_stored_password(profile_id: String) -> bool {
     SecureSshCredentials::has_password(&profile_id)
@@ -317,103 +361,195 @@ pub async fn tunnel_close(tunnel_id: String, local_port: u16) -> Result<(), Stri
 }
 
 // ============================================================================
-// DevContainer Commands (Stub Implementations)
+// DevContainer Commands
 // ============================================================================
 //
-// These commands are called by the frontend but DevContainer support is not
-// yet fully implemented. They return informative errors to prevent silent
-// failures and inform users about the feature status.
+// Workspace operations take an optional container id and default to the
+// container the workspace is connected to.
 
-/// Connect to a running dev container
+/// List dev containers, running or not
 #[tauri::command]
-pub async fn devcontainer_connect(container_id: String) -> Result<(), String> {
-    tracing::warn!(
-        "DevContainer connect called for container '{}' but feature is not implemented",
-        container_id
-    );
-    Err("DevContainer support is not yet implemented. This feature is planned for a future release.".to_string())
+pub async fn devcontainer_list(
+    state: State<'_, DevContainerState>,
+) -> Result<Vec<DevContainer>, String> {
+    state.list().await
+}
+
+/// Connect the workspace to a running dev container
+#[tauri::command]
+pub async fn devcontainer_connect(
+    container_id: String,
+    state: State<'_, DevContainerState>,
+) -> Result<(), String> {
+    state.connect(&container_id).await
 }
 
 /// Start a dev container
 #[tauri::command]
-pub async fn devcontainer_start(container_id: String) -> Result<(), String> {
-    tracing::warn!(
-        "DevContainer start called for container '{}' but feature is not implemented",
-        container_id
-    );
-    Err("DevContainer support is not yet implemented. This feature is planned for a future release.".to_string())
+pub async fn devcontainer_start(
+    container_id: String,
+    state: State<'_, DevContainerState>,
+) -> Result<(), String> {
+    state.start(&container_id).await
 }
 
 /// Stop a running dev container
 #[tauri::command]
-pub async fn devcontainer_stop(container_id: String) -> Result<(), String> {
-    tracing::warn!(
-        "DevContainer stop called for container '{}' but feature is not implemented",
-        container_id
-    );
-    Err("DevContainer support is not yet implemented. This feature is planned for a future release.".to_string())
+pub async fn devcontainer_stop(
+    container_id: String,
+    state: State<'_, DevContainerState>,
+) -> Result<(), String> {
+    state.stop(&container_id).await
 }
 
 /// Remove a dev container
 #[tauri::command]
-pub async fn devcontainer_remove(container_id: String) -> Result<(), String> {
-    tracing::warn!(
-        "DevContainer remove called for container '{}' but feature is not implemented",
-        container_id
-    );
-    Err("DevContainer support is not yet implemented. This feature is planned for a future release.".to_string())
+pub async fn devcontainer_remove(
+    container_id: String,
+    state: State<'_, DevContainerState>,
+) -> Result<(), String> {
+    state.remove(&container_id).await
 }
 
 /// Build a dev container from configuration
 #[tauri::command]
 pub async fn devcontainer_build(
+    app: AppHandle,
     workspace_path: String,
     config_path: Option<String>,
     build_id: String,
-) -> Result<serde_json::Value, String> {
-    tracing::warn!(
-        "DevContainer build called for workspace '{}' (build_id: {}) but feature is not implemented",
-        workspace_path,
-        build_id
-    );
-    let _ = config_path; // Suppress unused warning
-    Err("DevContainer support is not yet implemented. This feature is planned for a future release.".to_string())
+    state: State<'_, DevContainerState>,
+) -> Result<DevContainer, String> {
+    state
+        .build(&app, &workspace_path, config_path.as_deref(), &build_id)
+        .await
 }
 
 /// Load a devcontainer.json configuration file
 #[tauri::command]
-pub async fn devcontainer_load_config(config_path: String) -> Result<serde_json::Value, String> {
-    tracing::warn!(
-        "DevContainer load_config called for '{}' but feature is not implemented",
-        config_path
-    );
-    Err("DevContainer support is not yet implemented. This feature is planned for a future release.".to_string())
+pub async fn devcontainer_load_config(config_path: String) -> Result<DevContainerConfig, String> {
+    load_config(Path::new(&config_path))
 }
 
 /// Save a devcontainer.json configuration file
 #[tauri::command]
 pub async fn devcontainer_save_config(
-    config: serde_json::Value,
+    config: DevContainerConfig,
     workspace_path: String,
 ) -> Result<(), String> {
-    tracing::warn!(
-        "DevContainer save_config called for workspace '{}' but feature is not implemented",
-        workspace_path
-    );
-    let _ = config; // Suppress unused warning
-    Err("DevContainer support is not yet implemented. This feature is planned for a future release.".to_string())
+    save_config(config, Path::new(&workspace_path)).map(drop)
 }
 
 /// List available dev container features
 #[tauri::command]
-pub async fn devcontainer_list_features() -> Result<Vec<serde_json::Value>, String> {
-    tracing::warn!("DevContainer list_features called but feature is not implemented");
-    Err("DevContainer support is not yet implemented. This feature is planned for a future release.".to_string())
+pub async fn devcontainer_list_features() -> Result<Vec<DevContainerFeature>, String> {
+    list_features().await
 }
 
 /// List available dev container templates
 #[tauri::command]
-pub async fn devcontainer_list_templates() -> Result<Vec<serde_json::Value>, String> {
-    tracing::warn!("DevContainer list_templates called but feature is not implemented");
-    Err("DevContainer support is not yet implemented. This feature is planned for a future release.".to_string())
+pub async fn devcontainer_list_templates() -> Result<Vec<DevContainerTemplate>, String> {
+    list_templates().await
+}
+
+#[tauri::command]
+pub async fn devcontainer_read_file(
+    container_id: Option<String>,
+    path: String,
+    state: State<'_, DevContainerState>,
+) -> Result<String, String> {
+    let container = state.workspace(container_id.as_deref()).await?;
+    container.read_file(&path).await
+}
+
+#[tauri::command]
+pub async fn devcontainer_write_file(
+    container_id: Option<String>,
+    path: String,
+    content: String,
+    state: State<'_, DevContainerState>,
+) -> Result<(), String> {
+    let container = state.workspace(container_id.as_deref()).await?;
+    container.write_file(&path, &content).await
+}
+
+#[tauri::command]
+pub async fn devcontainer_list_directory(
+    container_id: Option<String>,
+    path: String,
+    state: State<'_, DevContainerState>,
+) -> Result<Vec<RemoteFileEntry>, String> {
+    let container = state.workspace(container_id.as_deref()).await?;
+    container.list_directory(&path).await
+}
+
+#[tauri::command]
+pub async fn devcontainer_delete(
+    container_id: Option<String>,
+    path: String,
+    recursive: bool,
+    state: State<'_, DevContainerState>,
+) -> Result<(), String> {
+    let container = state.workspace(container_id.as_deref()).await?;
+    container.delete(&path, recursive).await
+}
+
+#[tauri::command]
+pub async fn devcontainer_create_directory(
+    container_id: Option<String>,
+    path: String,
+    state: State<'_, DevContainerState>,
+) -> Result<(), String> {
+    let container = state.workspace(container_id.as_deref()).await?;
+    container.create_directory(&path).await
+}
+
+#[tauri::command]
+pub async fn devcontainer_rename(
+    container_id: Option<String>,
+    old_path: String,
+    new_path: String,
+    state: State<'_, DevContainerState>,
+) -> Result<(), String> {
+    let container = state.workspace(container_id.as_deref()).await?;
+    container.rename(&old_path, &new_path).await
+}
+
+/// Run a shell command line in a dev container
+#[tauri::command]
+pub async fn devcontainer_execute_command(
+    container_id: Option<String>,
+    command: String,
+    working_dir: Option<String>,
+    state: State<'_, DevContainerState>,
+) -> Result<CommandResult, String> {
+    let container = state.workspace(container_id.as_deref()).await?;
+    container
+        .execute_command(&command, working_dir.as_deref())
+        .await
+}
+
+/// Open a terminal running a shell in a dev container
+#[tauri::command]
+pub async fn devcontainer_open_terminal(
+    app: AppHandle,
+    container_id: Option<String>,
+    name: Option<String>,
+    state: State<'_, DevContainerState>,
+) -> Result<TerminalInfo, String> {
+    let container = state.workspace(container_id.as_deref()).await?;
+    let terminals = app.state::<TerminalState>();
+    terminals.create_terminal(&app, container.terminal_options(name))
+}
+
+/// Run a task of the workspace's tasks.json in a dev container
+#[tauri::command]
+pub async fn devcontainer_run_task(
+    app: AppHandle,
+    container_id: Option<String>,
+    task_name: String,
+    state: State<'_, DevContainerState>,
+) -> Result<TaskResult, String> {
+    let container = state.workspace(container_id.as_deref()).await?;
+    container.run_task(&app, &task_name).await
 }
@@ -0,0 +1,570 @@
+//! A dev container as a workspace backend
+//!
+//! Commands run through the exec API as the config's remote user, in the
+//! container's workspace folder and with its `remoteEnv`. Files move through
+//! the archive API so binary content and large files need no shell quoting.
+//! Terminals are local PTYs running the engine CLI's `exec -it`, so they get
+//! the regular terminal features.
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use super::ContainerMetadata;
+use super::config::LifecycleCommand;
+use super::engine::{Engine, StdStream};
+use crate::lsp::commands::remote::shell_quote;
+use crate::remote::types::{CommandResult, RemoteFileEntry};
+use crate::tasks::{TaskDefinition, TaskResult, substitute_variables};
+use crate::terminal::CreateTerminalOptions;
+
+/// Lists a directory as `<hex mode> <size> <mtime> <name>` lines, following
+/// symlinks; works with GNU and BusyBox `stat`
+const LIST_SCRIPT: &str = r#"cd "$1" || exit 1
+for f in * .[!.]* ..?*; do
+  [ -e "$f" ] || [ -L "$f" ] || continue
+  stat -L -c '%f %s %Y %n' -- "$f" 2>/dev/null || stat -c '%f %s %Y %n' -- "$f"
+done"#;
+
+/// Starts the user's preferred shell as a login shell
+const SHELL_SCRIPT: &str =
+    "if command -v bash >/dev/null 2>&1; then exec bash -l; else exec sh -l; fi";
+
+/// Symlinks followed when reading a file
+const MAX_LINK_DEPTH: usize = 8;
+
+/// Output of a task running in a container
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TaskOutputEvent<'a> {
+    container_id: &'a str,
+    task_name: &'a str,
+    data: String,
+}
+
+/// Splits streamed output into lines
+#[derive(Default)]
+struct LineBuffer {
+    pending: Vec<u8>,
+}
+
+impl LineBuffer {
+    fn push(&mut self, data: &[u8], mut on_line: impl FnMut(String)) {
+        self.pending.extend_from_slice(data);
+        while let Some(pos) = self.pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=pos).collect();
+            on_line(String::from_utf8_lossy(&line).trim_end().to_string());
+        }
+    }
+
+    fn finish(self, mut on_line: impl FnMut(String)) {
+        if !self.pending.is_empty() {
+            on_line(
+                String::from_utf8_lossy(&self.pending)
+                    .trim_end()
+                    .to_string(),
+            );
+        }
+    }
+}
+
+/// A running dev container with the settings commands run under
+pub struct ContainerWorkspace {
+    engine: Engine,
+    id: String,
+    user: Option<String>,
+    folder: String,
+    env: Vec<String>,
+    local_folder: Option<String>,
+}
+
+impl ContainerWorkspace {
+    /// Open a running container
+    ///
+    /// Containers created elsewhere have no Cortex metadata; they are used as
+    /// their configured user, in the folder the local workspace is mounted at.
+    pub(super) async fn open(engine: Engine, id: &str) -> Result<Self, String> {
+        let container = engine.inspect_container(id).await?;
+        if container["State"]["Running"].as_bool() != Some(true) {
+            return Err(format!("Container {} is not running", id));
+        }
+        let labels = &container["Config"]["Labels"];
+        let local_folder = labels[super::LOCAL_FOLDER_LABEL]
+            .as_str()
+            .map(str::to_string);
+        let metadata = labels[super::METADATA_LABEL]
+            .as_str()
+            .and_then(|m| serde_json::from_str::<ContainerMetadata>(m).ok());
+
+        let (user, folder, env) = match metadata {
+            Some(metadata) => (
+                metadata.remote_user,
+                metadata.workspace_folder,
+                metadata.remote_env,
+            ),
+            None => {
+                let folder = container["Mounts"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .find(|m| m["Source"].as_str() == local_folder.as_deref())
+                    .and_then(|m| m["Destination"].as_str())
+                    .unwrap_or("/")
+                    .to_string();
+                let user = container["Config"]["User"]
+                    .as_str()
+                    .filter(|u| !u.is_empty())
+                    .map(str::to_string);
+                (user, folder, Default::default())
+            }
+        };
+        Ok(Self {
+            engine,
+            id: container["Id"].as_str().unwrap_or(id).to_string(),
+            user,
+            folder,
+            env: env.iter().map(|(k, v)| format!("{}={}", k, v)).collect(),
+            local_folder,
+        })
+    }
+
+    /// A container path, relative paths being taken from the workspace folder
+    fn resolve(&self, path: &str) -> String {
+        if path.starts_with('/') {
+            path.to_string()
+        } else {
+            format!("{}/{}", self.folder.trim_end_matches('/'), path)
+        }
+    }
+
+    /// Run an argv, streaming its output, and return its exit code
+    pub async fn run(
+        &self,
+        argv: &[String],
+        cwd: Option<&str>,
+        on_output: impl FnMut(StdStream, &[u8]),
+    ) -> Result<i64, String> {
+        self.run_with_env(argv, cwd, &[], on_output).await
+    }
+
+    async fn run_with_env(
+        &self,
+        argv: &[String],
+        cwd: Option<&str>,
+        extra_env: &[String],
+        on_output: impl FnMut(StdStream, &[u8]),
+    ) -> Result<i64, String> {
+        let cwd = cwd.map(|c| self.resolve(c));
+        let mut env = self.env.clone();
+        env.extend_from_slice(extra_env);
+        self.engine
+            .exec(
+                &self.id,
+                argv,
+                self.user.as_deref(),
+                Some(cwd.as_deref().unwrap_or(&self.folder)),
+                &env,
+                on_output,
+            )
+            .await
+    }
+
+    /// Run an argv to completion, collecting its output
+    async fn capture(&self, argv: &[&str], cwd: Option<&str>) -> Result<CommandResult, String> {
+        let argv: Vec<String> = argv.iter().map(|a| a.to_string()).collect();
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        let exit_code = self
+            .run(&argv, cwd, |stream, data| match stream {
+                StdStream::Stdout => stdout.extend_from_slice(data),
+                StdStream::Stderr => stderr.extend_from_slice(data),
+            })
+            .await?;
+        Ok(CommandResult {
+            stdout: String::from_utf8_lossy(&stdout).to_string(),
+            stderr: String::from_utf8_lossy(&stderr).to_string(),
+            exit_code: exit_code as i32,
+        })
+    }
+
+    /// Run an argv that must succeed, returning its stdout
+    async fn check(&self, argv: &[&str]) -> Result<String, String> {
+        let result = self.capture(argv, None).await?;
+        if result.exit_code != 0 {
+            return Err(result.stderr.trim().to_string());
+        }
+        Ok(result.stdout)
+    }
+
+    /// Run a shell command line
+    pub async fn execute_command(
+        &self,
+        command: &str,
+        cwd: Option<&str>,
+    ) -> Result<CommandResult, String> {
+        self.capture(&["/bin/sh", "-c", command], cwd).await
+    }
+
+    pub async fn read_file(&self, path: &str) -> Result<String, String> {
+        let mut path = self.resolve(path);
+        for _ in 0..MAX_LINK_DEPTH {
+            let archive = self.engine.get_archive(&self.id, &path).await?;
+            let mut archive = tar::Archive::new(&archive[..]);
+            let mut entries = archive
+                .entries()
+                .map_err(|e| format!("Invalid archive from engine: {}", e))?;
+            let mut entry = entries
+                .next()
+                .ok_or_else(|| format!("File not found: {}", path))?
+                .map_err(|e| format!("Invalid archive from engine: {}", e))?;
+            let entry_type = entry.header().entry_type();
+            if entry_type.is_symlink() {
+                let target = entry
+                    .link_name()
+                    .ok()
+                    .flatten()
+                    .ok_or_else(|| format!("Broken symlink: {}", path))?
+                    .to_string_lossy()
+                    .to_string();
+                path = if target.starts_with('/') {
+                    target
+                } else {
+                    let parent = Path::new(&path).parent().unwrap_or(Path::new("/"));
+                    parent.join(target).to_string_lossy().to_string()
+                };
+                continue;
+            }
+            if !entry_type.is_file() {
+                return Err(format!("Not a file: {}", path));
+            }
+            let mut content = Vec::new();
+            std::io::Read::read_to_end(&mut entry, &mut content)
+                .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+            return String::from_utf8(content).map_err(|_| format!("Not a text file: {}", path));
+        }
+        Err(format!("Too many levels of symlinks: {}", path))
+    }
+
+    /// Write a file, keeping the mode of an existing one and giving a new one
+    /// to the remote user
+    pub async fn write_file(&self, path: &str, content: &str) -> Result<(), String> {
+        let path = self.resolve(path);
+        let (dir, name) = path
+            .rsplit_once('/')
+            .filter(|(_, name)| !name.is_empty())
+            .ok_or_else(|| format!("Invalid path: {}", path))?;
+        let dir = if dir.is_empty() { "/" } else { dir };
+
+        let owner = self.check(&["/bin/sh", "-c", "id -u; id -g"]).await?;
+        let mut ids = owner.lines().filter_map(|l| l.trim().parse::<u64>().ok());
+        let (uid, gid) = (ids.next().unwrap_or(0), ids.next().unwrap_or(0));
+        let mode = self
+            .check(&["stat", "-c", "%a", "--", &path])
+            .await
+            .ok()
+            .and_then(|m| u32::from_str_radix(m.trim(), 8).ok())
+            .unwrap_or(0o644);
+
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(mode);
+        header.set_uid(uid);
+        header.set_gid(gid);
+        header.set_mtime(chrono::Utc::now().timestamp().max(0) as u64);
+        header.set_cksum();
+        let mut builder = tar::Builder::new(Vec::new());
+        builder
+            .append_data(&mut header, name, content.as_bytes())
+            .map_err(|e| format!("Failed to write {}: {}", path, e))?;
+        let archive = builder
+            .into_inner()
+            .map_err(|e| format!("Failed to write {}: {}", path, e))?;
+        self.engine.put_archive(&self.id, dir, archive).await
+    }
+
+    /// Directory entries, directories first
+    pub async fn list_directory(&self, path: &str) -> Result<Vec<RemoteFileEntry>, String> {
+        let path = self.resolve(path);
+        let listing = self
+            .check(&["/bin/sh", "-c", LIST_SCRIPT, "sh", &path])
+            .await?;
+        let mut entries: Vec<RemoteFileEntry> = listing
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.splitn(4, ' ');
+                let mode = u32::from_str_radix(fields.next()?, 16).ok()?;
+                let size = fields.next()?.parse().ok()?;
+                let modified = fields.next()?.parse().ok();
+                let name = fields.next()?.to_string();
+                Some(RemoteFileEntry {
+                    path: format!("{}/{}", path.trim_end_matches('/'), name),
+                    name,
+                    is_dir: mode & 0o170000 == 0o040000,
+                    size,
+                    modified,
+                    permissions: Some(mode & 0o7777),
+                })
+            })
+            .collect();
+        entries.sort_by(|a, b| match (a.is_dir, b.is_dir) {
+            (true, false) => std::cmp::Ordering::Less,
+            (false, true) => std::cmp::Ordering::Greater,
+            _ => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+        });
+        Ok(entries)
+    }
+
+    pub async fn delete(&self, path: &str, recursive: bool) -> Result<(), String> {
+        let path = self.resolve(path);
+        let flag = if recursive { "-rf" } else { "-f" };
+        self.check(&["rm", flag, "--", &path]).await.map(drop)
+    }
+
+    pub async fn create_directory(&self, path: &str) -> Result<(), String> {
+        let path = self.resolve(path);
+        self.check(&["mkdir", "-p", "--", &path]).await.map(drop)
+    }
+
+    pub async fn rename(&self, old_path: &str, new_path: &str) -> Result<(), String> {
+        let (old_path, new_path) = (self.resolve(old_path), self.resolve(new_path));
+        self.check(&["mv", "--", &old_path, &new_path])
+            .await
+            .map(drop)
+    }
+
+    /// Options for a local terminal attached to the container
+    pub fn terminal_options(&self, name: Option<String>) -> CreateTerminalOptions {
+        // The engine CLI runs directly, so its arguments never pass through
+        // a host shell
+        let mut args = vec![
+            "exec".to_string(),
+            "-it".to_string(),
+            "-w".to_string(),
+            self.folder.clone(),
+        ];
+        if let Some(user) = &self.user {
+            args.push("-u".to_string());
+            args.push(user.clone());
+        }
+        for var in &self.env {
+            args.push("-e".to_string());
+            args.push(var.clone());
+        }
+        args.extend([
+            self.id.clone(),
+            "/bin/sh".to_string(),
+            "-c".to_string(),
+            SHELL_SCRIPT.to_string(),
+        ]);
+
+        // Podman's CLI talks to its local storage directly; Docker's needs
+        // to be pointed at the engine in use
+        let mut env = HashMap::new();
+        if self.engine.cli() == "docker" {
+            env.insert("DOCKER_HOST".to_string(), self.engine.endpoint().to_host());
+        }
+        CreateTerminalOptions {
+            name: name.or_else(|| Some(format!("Container {}", &self.id[..12.min(self.id.len())]))),
+            shell: Some(self.engine.cli().to_string()),
+            env: Some(env),
+            workspace: self.local_folder.clone(),
+            args: Some(args),
+            shell_integration: Some(false),
+            ..Default::default()
+        }
+    }
+
+    /// Run a task of the workspace's tasks.json in the container, after the
+    /// tasks it depends on, streaming their output as
+    /// `devcontainer:task-output`
+    ///
+    /// Variables such as `${workspaceFolder}` resolve to the container's
+    /// workspace folder, which task working directories are also taken
+    /// relative to.
+    pub async fn run_task(&self, app: &AppHandle, task_name: &str) -> Result<TaskResult, String> {
+        let local_folder = self
+            .local_folder
+            .as_deref()
+            .ok_or_else(|| "Container has no local workspace to read tasks from".to_string())?;
+        let config = crate::tasks::load_tasks_config(local_folder)?;
+        let mut order = Vec::new();
+        crate::tasks::resolve_dependency_order(
+            &config,
+            task_name,
+            &mut HashSet::new(),
+            &mut order,
+        )?;
+        let Some((task, dependencies)) = order.split_last() else {
+            return Err(format!("Task '{}' not found in tasks.json", task_name));
+        };
+        for dependency in dependencies {
+            let result = self.run_single_task(app, task_name, dependency).await?;
+            if !result.success {
+                return Err(format!("Dependency task '{}' failed", dependency.label));
+            }
+        }
+        if task.command.is_none() && !dependencies.is_empty() {
+            // Compound task, done once its dependencies are
+            return Ok(TaskResult {
+                task_name: task.label.clone(),
+                success: true,
+                exit_code: None,
+                output: String::new(),
+                error: None,
+            });
+        }
+        self.run_single_task(app, task_name, task).await
+    }
+
+    /// Run one task, its output streamed under the requested task's name
+    async fn run_single_task(
+        &self,
+        app: &AppHandle,
+        task_name: &str,
+        task: &TaskDefinition,
+    ) -> Result<TaskResult, String> {
+        let substitute = |s: &str| substitute_variables(s, &self.folder, None, None, None);
+        let command = substitute(task.command.as_deref().ok_or("Task has no command")?);
+        let args: Vec<String> = task.args.iter().map(|a| substitute(a)).collect();
+        let argv: Vec<String> = if task.task_type == "process" {
+            std::iter::once(command).chain(args).collect()
+        } else {
+            let mut line = command;
+            for arg in &args {
+                line.push(' ');
+                line.push_str(&shell_quote(arg));
+            }
+            let shell = task.options.shell.as_ref();
+            let mut argv = vec![
+                shell
+                    .and_then(|s| s.executable.clone())
+                    .unwrap_or_else(|| "/bin/sh".to_string()),
+            ];
+            match shell.filter(|s| !s.args.is_empty()) {
+                Some(shell) => argv.extend(shell.args.iter().cloned()),
+                None => argv.push("-c".to_string()),
+            }
+            argv.push(line);
+            argv
+        };
+        let cwd = task.options.cwd.as_deref().map(substitute);
+        let env: Vec<String> = task
+            .options
+            .env
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, substitute(v)))
+            .collect();
+
+        let mut output = Vec::new();
+        let mut stderr = Vec::new();
+        let exit_code = self
+            .run_with_env(&argv, cwd.as_deref(), &env, |stream, data| {
+                output.extend_from_slice(data);
+                if stream == StdStream::Stderr {
+                    stderr.extend_from_slice(data);
+                }
+                let _ = app.emit(
+                    "devcontainer:task-output",
+                    TaskOutputEvent {
+                        container_id: &self.id,
+                        task_name,
+                        data: String::from_utf8_lossy(data).to_string(),
+                    },
+                );
+            })
+            .await?;
+        Ok(TaskResult {
+            task_name: task.label.clone(),
+            success: exit_code == 0,
+            exit_code: Some(exit_code as i32),
+            output: String::from_utf8_lossy(&output).to_string(),
+            error: (exit_code != 0).then(|| String::from_utf8_lossy(&stderr).to_string()),
+        })
+    }
+
+    /// Run a lifecycle command, logging its output
+    ///
+    /// Named commands of a parallel set run concurrently and log with their
+    /// name as prefix; any failure fails the whole step.
+    pub(super) async fn run_lifecycle(
+        &self,
+        hook: &str,
+        command: &LifecycleCommand,
+        log: &(dyn Fn(String) + Sync),
+    ) -> Result<(), String> {
+        let runs = command.argvs().into_iter().map(|(name, argv)| async move {
+            let prefix = name
+                .as_ref()
+                .map(|n| format!("[{}] ", n))
+                .unwrap_or_default();
+            let mut lines = LineBuffer::default();
+            let exit_code = self
+                .run(&argv, None, |_, data| {
+                    lines.push(data, |line| log(format!("{}{}", prefix, line)))
+                })
+                .await?;
+            lines.finish(|line| log(format!("{}{}", prefix, line)));
+            if exit_code != 0 {
+                return Err(format!(
+                    "{} {}failed with exit code {}",
+                    hook, prefix, exit_code
+                ));
+            }
+            Ok(())
+        });
+        futures::future::try_join_all(runs).await.map(drop)
+    }
+}
+
+/// Run `initializeCommand` on the host, in the local workspace
+pub(super) async fn run_on_host(
+    command: &LifecycleCommand,
+    workspace: &Path,
+    log: &(dyn Fn(String) + Sync),
+) -> Result<(), String> {
+    for (_, argv) in command.argvs() {
+        let mut cmd = match command {
+            LifecycleCommand::Shell(line) if cfg!(windows) => {
+                let mut c = crate::process_utils::async_command("cmd");
+                c.arg("/C").arg(line);
+                c
+            }
+            _ => {
+                let mut c = crate::process_utils::async_command(&argv[0]);
+                c.args(&argv[1..]);
+                c
+            }
+        };
+        let output = cmd
+            .current_dir(workspace)
+            .output()
+            .await
+            .map_err(|e| format!("Failed to run initializeCommand: {}", e))?;
+        let mut lines = LineBuffer::default();
+        lines.push(&output.stdout, log);
+        lines.push(&output.stderr, log);
+        lines.finish(log);
+        if !output.status.success() {
+            return Err(format!("initializeCommand failed with {}", output.status));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_streamed_output_into_lines() {
+        let mut buffer = LineBuffer::default();
+        let mut lines = Vec::new();
+        buffer.push(b"npm ci\r\nadded 1", |l| lines.push(l));
+        buffer.push(b"2 packages\npartial", |l| lines.push(l));
+        buffer.finish(|l| lines.push(l));
+        assert_eq!(lines, vec!["npm ci", "added 12 packages", "partial"]);
+    }
+}
@@ -0,0 +1,471 @@
+//! devcontainer.json parsing
+//!
+//! The file is JSON with comments and trailing commas. For building,
+//! variables such as `${localWorkspaceFolder}` are substituted in the raw
+//! JSON before it is typed, so every string field sees them; the editor gets
+//! the file as written.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+/// Config locations tried, in order, when none is given
+const CONFIG_CANDIDATES: &[&str] = &[".devcontainer/devcontainer.json", ".devcontainer.json"];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dockerfile: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub args: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// A forwarded port, or a `service:port` of a Compose service
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum PortSpec {
+    Port(u16),
+    Named(String),
+}
+
+/// A mount, in `--mount` syntax or as an object
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum MountSpec {
+    Spec(String),
+    Mount {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        source: Option<String>,
+        target: String,
+        #[serde(rename = "type", default = "default_mount_type")]
+        kind: String,
+    },
+}
+
+fn default_mount_type() -> String {
+    "bind".to_string()
+}
+
+impl MountSpec {
+    /// The mount as an Engine API `Mount` object
+    pub fn to_engine_mount(&self) -> Result<Value, String> {
+        let (kind, source, target, read_only) = match self {
+            Self::Mount {
+                source,
+                target,
+                kind,
+            } => (kind.clone(), source.clone(), target.clone(), false),
+            Self::Spec(spec) => {
+                let mut kind = default_mount_type();
+                let (mut source, mut target, mut read_only) = (None, None, false);
+                for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+                    let (key, value) = part.split_once('=').unwrap_or((part, ""));
+                    match key {
+                        "type" => kind = value.to_string(),
+                        "source" | "src" => source = Some(value.to_string()),
+                        "target" | "destination" | "dst" => target = Some(value.to_string()),
+                        "readonly" | "ro" => read_only = value.is_empty() || value == "true",
+                        _ => {}
+                    }
+                }
+                let target = target.ok_or_else(|| format!("Mount has no target: {}", spec))?;
+                (kind, source, target, read_only)
+            }
+        };
+        let mut mount = json!({ "Type": kind, "Target": target, "ReadOnly": read_only });
+        if let Some(source) = source {
+            mount["Source"] = json!(source);
+        }
+        Ok(mount)
+    }
+}
+
+/// A lifecycle command: a shell line, an argv, or named commands run in
+/// parallel
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum LifecycleCommand {
+    Shell(String),
+    Exec(Vec<String>),
+    Parallel(BTreeMap<String, LifecycleCommand>),
+}
+
+impl LifecycleCommand {
+    /// The argvs to run, each with its name when part of a parallel set
+    pub fn argvs(&self) -> Vec<(Option<String>, Vec<String>)> {
+        match self {
+            Self::Shell(line) if line.trim().is_empty() => Vec::new(),
+            Self::Shell(line) => vec![(
+                None,
+                vec!["/bin/sh".to_string(), "-c".to_string(), line.clone()],
+            )],
+            Self::Exec(argv) if argv.is_empty() => Vec::new(),
+            Self::Exec(argv) => vec![(None, argv.clone())],
+            Self::Parallel(commands) => commands
+                .iter()
+                .flat_map(|(name, command)| {
+                    command
+                        .argvs()
+                        .into_iter()
+                        .map(move |(_, argv)| (Some(name.clone()), argv))
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DevContainerConfig {
+    /// Where the config was loaded from; not written to the file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub build: Option<BuildConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub docker_compose_file: Option<Value>,
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub features: Map<String, Value>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub forward_ports: Vec<PortSpec>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mounts: Vec<MountSpec>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub initialize_command: Option<LifecycleCommand>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_create_command: Option<LifecycleCommand>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub update_content_command: Option<LifecycleCommand>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_create_command: Option<LifecycleCommand>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_start_command: Option<LifecycleCommand>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_attach_command: Option<LifecycleCommand>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote_user: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub container_user: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace_folder: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace_mount: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub container_env: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub remote_env: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub run_args: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub override_command: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shutdown_action: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub customizations: Option<Value>,
+    /// Properties this editor does not know, kept so saving round-trips them
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+impl DevContainerConfig {
+    /// Folder the workspace is mounted at inside the container
+    pub fn container_workspace_folder(&self, workspace: &Path) -> String {
+        self.workspace_folder
+            .clone()
+            .unwrap_or_else(|| format!("/workspaces/{}", basename(workspace)))
+    }
+}
+
+fn basename(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "workspace".to_string())
+}
+
+/// Strip comments and trailing commas so the text parses as JSON
+///
+/// Comments become whitespace, keeping line numbers in parse errors right.
+pub fn strip_jsonc(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+    let mut in_string = false;
+    while i < chars.len() {
+        let c = chars[i];
+        if in_string {
+            out.push(c);
+            if c == '\\' && i + 1 < chars.len() {
+                out.push(chars[i + 1]);
+                i += 1;
+            } else if c == '"' {
+                in_string = false;
+            }
+        } else if c == '"' {
+            in_string = true;
+            out.push(c);
+        } else if c == '/' && chars.get(i + 1) == Some(&'/') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            continue;
+        } else if c == '/' && chars.get(i + 1) == Some(&'*') {
+            i += 2;
+            while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                out.push(if chars[i] == '\n' { '\n' } else { ' ' });
+                i += 1;
+            }
+            i += 2;
+            continue;
+        } else if c == ',' {
+            // Drop the comma when only whitespace and comments separate it
+            // from a closing bracket
+            if !matches!(next_significant(&chars, i + 1), Some('}' | ']')) {
+                out.push(c);
+            }
+        } else {
+            out.push(c);
+        }
+        i += 1;
+    }
+    out
+}
+
+/// First character from `i` on that is not whitespace or in a comment
+fn next_significant(chars: &[char], mut i: usize) -> Option<char> {
+    while i < chars.len() {
+        match (chars[i], chars.get(i + 1)) {
+            (c, _) if c.is_whitespace() => i += 1,
+            ('/', Some('/')) => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            }
+            ('/', Some('*')) => {
+                i += 2;
+                while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                    i += 1;
+                }
+                i += 2;
+            }
+            (c, _) => return Some(c),
+        }
+    }
+    None
+}
+
+/// Locate the config of a workspace: the given path (relative to the
+/// workspace) or the first conventional location that exists
+pub fn find_config(workspace: &Path, config_path: Option<&str>) -> Result<PathBuf, String> {
+    if let Some(config_path) = config_path.filter(|p| !p.trim().is_empty()) {
+        let path = workspace.join(config_path);
+        return if path.is_file() {
+            Ok(path)
+        } else {
+            Err(format!(
+                "Dev container config not found: {}",
+                path.display()
+            ))
+        };
+    }
+    CONFIG_CANDIDATES
+        .iter()
+        .map(|candidate| workspace.join(candidate))
+        .find(|path| path.is_file())
+        .ok_or_else(|| format!("No devcontainer.json found in {}", workspace.display()))
+}
+
+fn read_jsonc(path: &Path) -> Result<Value, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&strip_jsonc(&text))
+        .map_err(|e| format!("Invalid devcontainer.json {}: {}", path.display(), e))
+}
+
+fn typed(value: Value, path: &Path) -> Result<DevContainerConfig, String> {
+    let mut config: DevContainerConfig = serde_json::from_value(value)
+        .map_err(|e| format!("Invalid devcontainer.json {}: {}", path.display(), e))?;
+    config.path = Some(path.to_string_lossy().to_string());
+    Ok(config)
+}
+
+/// The config as written, for editing
+pub fn load_config(path: &Path) -> Result<DevContainerConfig, String> {
+    typed(read_jsonc(path)?, path)
+}
+
+/// The config with variables substituted, for building `workspace`
+pub fn resolve_config(path: &Path, workspace: &Path) -> Result<DevContainerConfig, String> {
+    let mut value = read_jsonc(path)?;
+    let local_folder = workspace.to_string_lossy().to_string();
+    let local_basename = basename(workspace);
+    substitute(&mut value, &|name| match name {
+        "localWorkspaceFolder" => Some(local_folder.clone()),
+        "localWorkspaceFolderBasename" => Some(local_basename.clone()),
+        _ => name.strip_prefix("localEnv:").map(|var| {
+            let (var, default) = var.split_once(':').unwrap_or((var, ""));
+            std::env::var(var).unwrap_or_else(|_| default.to_string())
+        }),
+    });
+
+    // The container folder may itself use the local variables above
+    let container_folder = value["workspaceFolder"]
+        .as_str()
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("/workspaces/{}", local_basename));
+    let container_basename = basename(Path::new(&container_folder));
+    substitute(&mut value, &|name| match name {
+        "containerWorkspaceFolder" => Some(container_folder.clone()),
+        "containerWorkspaceFolderBasename" => Some(container_basename.clone()),
+        _ => None,
+    });
+    typed(value, path)
+}
+
+/// Replace `${name}` in every string of a JSON value; unknown names are left
+/// as they are
+pub fn substitute(value: &mut Value, lookup: &dyn Fn(&str) -> Option<String>) {
+    match value {
+        Value::String(s) => *s = substitute_str(s, lookup),
+        Value::Array(items) => items.iter_mut().for_each(|v| substitute(v, lookup)),
+        Value::Object(map) => map.values_mut().for_each(|v| substitute(v, lookup)),
+        _ => {}
+    }
+}
+
+fn substitute_str(s: &str, lookup: &dyn Fn(&str) -> Option<String>) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find("${") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find('}') else {
+            rest = &rest[start..];
+            break;
+        };
+        match lookup(&after[..end]) {
+            Some(replacement) => out.push_str(&replacement),
+            None => out.push_str(&rest[start..start + 3 + end]),
+        }
+        rest = &after[end + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Write a config into a workspace, at its `path` or the default location
+///
+/// Comments in an existing file are not preserved.
+pub fn save_config(mut config: DevContainerConfig, workspace: &Path) -> Result<PathBuf, String> {
+    let path = match config.path.take() {
+        Some(path) if Path::new(&path).is_absolute() => PathBuf::from(path),
+        Some(path) => workspace.join(path),
+        None => workspace.join(CONFIG_CANDIDATES[0]),
+    };
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let mut text = serde_json::to_string_pretty(&config)
+        .map_err(|e| format!("Failed to serialize config: {}", e))?;
+    text.push('\n');
+    std::fs::write(&path, text)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_comments_and_trailing_commas_outside_strings() {
+        let text = r#"{
+            // line comment
+            "image": "node:20", /* block
+            comment */
+            "url": "http://x//y, ]",
+            "forwardPorts": [3000, 8080,],
+        }"#;
+        let value: Value = serde_json::from_str(&strip_jsonc(text)).unwrap();
+        assert_eq!(value["image"], "node:20");
+        assert_eq!(value["url"], "http://x//y, ]");
+        assert_eq!(value["forwardPorts"], json!([3000, 8080]));
+    }
+
+    #[test]
+    fn resolves_variables_and_typed_fields() {
+        let dir = tempfile::tempdir().unwrap();
+        let workspace = dir.path().join("demo");
+        std::fs::create_dir_all(workspace.join(".devcontainer")).unwrap();
+        std::fs::write(
+            workspace.join(".devcontainer/devcontainer.json"),
+            r#"{
+                "name": "${localWorkspaceFolderBasename} dev",
+                "build": { "dockerfile": "Dockerfile", "args": { "V": "1" } },
+                "features": { "ghcr.io/devcontainers/features/node:1": {} },
+                "forwardPorts": [3000, "db:5432"],
+                "mounts": ["source=cache,target=/cache,type=volume"],
+                "postCreateCommand": { "deps": "npm ci", "lint": ["npm", "run", "lint"] },
+                "remoteEnv": { "ROOT": "${containerWorkspaceFolder}", "U": "${unknown}" },
+                "hostRequirements": { "cpus": 2 }
+            }"#,
+        )
+        .unwrap();
+
+        let path = find_config(&workspace, None).unwrap();
+        let config = resolve_config(&path, &workspace).unwrap();
+        assert_eq!(config.name.as_deref(), Some("demo dev"));
+        assert_eq!(config.build.as_ref().unwrap().args["V"], "1");
+        assert_eq!(config.features.len(), 1);
+        assert!(matches!(config.forward_ports[1], PortSpec::Named(_)));
+        assert_eq!(config.remote_env["ROOT"], "/workspaces/demo");
+        assert_eq!(config.remote_env["U"], "${unknown}");
+        assert_eq!(config.other["hostRequirements"]["cpus"], 2);
+
+        let argvs = config.post_create_command.as_ref().unwrap().argvs();
+        assert_eq!(argvs[0].0.as_deref(), Some("deps"));
+        assert_eq!(argvs[0].1, vec!["/bin/sh", "-c", "npm ci"]);
+        assert_eq!(argvs[1].1, vec!["npm", "run", "lint"]);
+
+        let mount = config.mounts[0].to_engine_mount().unwrap();
+        assert_eq!(
+            mount,
+            json!({ "Type": "volume", "Source": "cache", "Target": "/cache", "ReadOnly": false })
+        );
+    }
+
+    #[test]
+    fn saving_round_trips_unknown_properties_without_the_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".devcontainer.json");
+        std::fs::write(
+            &path,
+            r#"{ "image": "alpine", "runArgs": ["--init"], "x-custom": true }"#,
+        )
+        .unwrap();
+        let config = load_config(&path).unwrap();
+        assert_eq!(config.path.as_deref(), Some(path.to_str().unwrap()));
+
+        let saved = save_config(config, dir.path()).unwrap();
+        assert_eq!(saved, path);
+        let value: Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(
+            value,
+            json!({ "image": "alpine", "runArgs": ["--init"], "x-custom": true })
+        );
+    }
+}
@@ -0,0 +1,794 @@
+//! Docker Engine API client
+//!
+//! Speaks HTTP/1.1 to the engine's local socket, one connection per request,
+//! which keeps the streaming endpoints (pull, build, exec) simple. Podman's
+//! Docker-compatible service answers the same API, so whichever engine is
+//! found first is used.
+
+use std::path::PathBuf;
+
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+
+/// API version requested; the oldest one Podman's compat service speaks
+const API_VERSION: &str = "v1.41";
+
+/// Size of a multiplexed stream frame header
+const FRAME_HEADER_LEN: usize = 8;
+
+trait Io: AsyncRead + AsyncWrite + Unpin + Send {}
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Io for T {}
+
+/// Address of an engine, as written in `DOCKER_HOST`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Endpoint {
+    Unix(PathBuf),
+    NamedPipe(String),
+    Tcp(String),
+}
+
+impl Endpoint {
+    pub fn parse(host: &str) -> Result<Self, String> {
+        if let Some(path) = host.strip_prefix("unix://") {
+            Ok(Self::Unix(PathBuf::from(path)))
+        } else if let Some(pipe) = host.strip_prefix("npipe://") {
+            Ok(Self::NamedPipe(pipe.replace('/', "\\")))
+        } else if let Some(addr) = host.strip_prefix("tcp://") {
+            Ok(Self::Tcp(addr.trim_end_matches('/').to_string()))
+        } else {
+            Err(format!("Unsupported engine address: {}", host))
+        }
+    }
+
+    pub fn to_host(&self) -> String {
+        match self {
+            Self::Unix(path) => format!("unix://{}", path.display()),
+            Self::NamedPipe(pipe) => format!("npipe://{}", pipe.replace('\\', "/")),
+            Self::Tcp(addr) => format!("tcp://{}", addr),
+        }
+    }
+
+    /// Sockets tried when neither `DOCKER_HOST` nor `CONTAINER_HOST` is set
+    fn defaults() -> Vec<Self> {
+        #[cfg(windows)]
+        {
+            vec![
+                Self::NamedPipe(r"\\.\pipe\docker_engine".to_string()),
+                Self::NamedPipe(r"\\.\pipe\podman-machine-default".to_string()),
+            ]
+        }
+        #[cfg(not(windows))]
+        {
+            let mut endpoints = vec![Self::Unix(PathBuf::from("/var/run/docker.sock"))];
+            if let Some(home) = dirs::home_dir() {
+                endpoints.push(Self::Unix(home.join(".docker/run/docker.sock")));
+            }
+            if let Some(runtime_dir) = std::env::var_os("XDG_RUNTIME_DIR") {
+                endpoints.push(Self::Unix(
+                    PathBuf::from(runtime_dir).join("podman/podman.sock"),
+                ));
+            }
+            endpoints.push(Self::Unix(PathBuf::from("/run/podman/podman.sock")));
+            endpoints
+        }
+    }
+
+    async fn open(&self) -> Result<Box<dyn Io>, String> {
+        match self {
+            #[cfg(unix)]
+            Self::Unix(path) => tokio::net::UnixStream::connect(path)
+                .await
+                .map(|s| Box::new(s) as Box<dyn Io>)
+                .map_err(|e| format!("Failed to connect to {}: {}", path.display(), e)),
+            #[cfg(not(unix))]
+            Self::Unix(path) => Err(format!(
+                "Unix sockets are not supported here: {}",
+                path.display()
+            )),
+            #[cfg(windows)]
+            Self::NamedPipe(name) => {
+                use tokio::net::windows::named_pipe::ClientOptions;
+                // A busy pipe frees up as soon as the engine accepts the
+                // previous client
+                for _ in 0..20 {
+                    match ClientOptions::new().open(name) {
+                        Ok(pipe) => return Ok(Box::new(pipe)),
+                        Err(e) if e.raw_os_error() == Some(231) => {
+                            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                        }
+                        Err(e) => return Err(format!("Failed to connect to {}: {}", name, e)),
+                    }
+                }
+                Err(format!("Engine pipe {} stayed busy", name))
+            }
+            #[cfg(not(windows))]
+            Self::NamedPipe(name) => Err(format!("Named pipes are not supported here: {}", name)),
+            Self::Tcp(addr) => tokio::net::TcpStream::connect(addr)
+                .await
+                .map(|s| Box::new(s) as Box<dyn Io>)
+                .map_err(|e| format!("Failed to connect to {}: {}", addr, e)),
+        }
+    }
+}
+
+/// Request body
+pub enum Body {
+    Empty,
+    Json(Value),
+    Tar(Vec<u8>),
+}
+
+/// Output stream of an exec
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StdStream {
+    Stdout,
+    Stderr,
+}
+
+/// Splits the multiplexed stdout/stderr stream of an exec without a TTY
+///
+/// Every frame starts with an 8-byte header: the stream type, three padding
+/// bytes and the big-endian payload length. Frames can span reads.
+#[derive(Default)]
+pub struct StreamDemuxer {
+    buffer: Vec<u8>,
+}
+
+impl StreamDemuxer {
+    pub fn push(&mut self, data: &[u8]) -> Vec<(StdStream, Vec<u8>)> {
+        self.buffer.extend_from_slice(data);
+        let mut frames = Vec::new();
+        loop {
+            if self.buffer.len() < FRAME_HEADER_LEN {
+                break;
+            }
+            let len = u32::from_be_bytes([
+                self.buffer[4],
+                self.buffer[5],
+                self.buffer[6],
+                self.buffer[7],
+            ]) as usize;
+            if self.buffer.len() < FRAME_HEADER_LEN + len {
+                break;
+            }
+            let stream = if self.buffer[0] == 2 {
+                StdStream::Stderr
+            } else {
+                StdStream::Stdout
+            };
+            let payload = self.buffer[FRAME_HEADER_LEN..FRAME_HEADER_LEN + len].to_vec();
+            self.buffer.drain(..FRAME_HEADER_LEN + len);
+            frames.push((stream, payload));
+        }
+        frames
+    }
+}
+
+enum Framing {
+    Length(usize),
+    Chunked(usize),
+    Eof,
+    Done,
+}
+
+/// Response whose body is read incrementally
+pub struct Response {
+    pub status: u16,
+    reader: BufReader<Box<dyn Io>>,
+    framing: Framing,
+}
+
+impl Response {
+    async fn read(stream: Box<dyn Io>) -> Result<Self, String> {
+        let mut reader = BufReader::new(stream);
+        let status_line = read_line(&mut reader).await?;
+        let status = status_line
+            .split_whitespace()
+            .nth(1)
+            .and_then(|code| code.parse::<u16>().ok())
+            .ok_or_else(|| format!("Malformed engine response: {}", status_line))?;
+
+        let mut length = None;
+        let mut chunked = false;
+        loop {
+            let line = read_line(&mut reader).await?;
+            if line.is_empty() {
+                break;
+            }
+            let Some((name, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.parse::<usize>().ok();
+            } else if name.eq_ignore_ascii_case("transfer-encoding") {
+                chunked = value.eq_ignore_ascii_case("chunked");
+            }
+        }
+
+        let framing = if status == 204 || status == 304 {
+            Framing::Done
+        } else if chunked {
+            Framing::Chunked(0)
+        } else if let Some(length) = length {
+            Framing::Length(length)
+        } else {
+            Framing::Eof
+        };
+        Ok(Self {
+            status,
+            reader,
+            framing,
+        })
+    }
+
+    /// Next piece of the body, `None` once it is complete
+    pub async fn chunk(&mut self) -> Result<Option<Vec<u8>>, String> {
+        loop {
+            match self.framing {
+                Framing::Done | Framing::Length(0) => {
+                    self.framing = Framing::Done;
+                    return Ok(None);
+                }
+                Framing::Chunked(0) => {
+                    let line = read_line(&mut self.reader).await?;
+                    let size = line.split(';').next().unwrap_or_default().trim();
+                    let size = usize::from_str_radix(size, 16)
+                        .map_err(|_| format!("Malformed chunk size: {}", line))?;
+                    if size == 0 {
+                        while !read_line(&mut self.reader).await?.is_empty() {}
+                        self.framing = Framing::Done;
+                        return Ok(None);
+                    }
+                    self.framing = Framing::Chunked(size);
+                }
+                Framing::Length(remaining) | Framing::Chunked(remaining) => {
+                    let mut buf = vec![0; remaining.min(16 * 1024)];
+                    let read = self
+                        .reader
+                        .read(&mut buf)
+                        .await
+                        .map_err(|e| format!("Failed to read engine response: {}", e))?;
+                    if read == 0 {
+                        return Err("Engine closed the connection mid-response".to_string());
+                    }
+                    buf.truncate(read);
+                    self.framing = match self.framing {
+                        Framing::Length(_) => Framing::Length(remaining - read),
+                        _ if remaining == read => {
+                            // Chunk data is followed by a CRLF
+                            read_line(&mut self.reader).await?;
+                            Framing::Chunked(0)
+                        }
+                        _ => Framing::Chunked(remaining - read),
+                    };
+                    return Ok(Some(buf));
+                }
+                Framing::Eof => {
+                    let mut buf = vec![0; 16 * 1024];
+                    let read = self
+                        .reader
+                        .read(&mut buf)
+                        .await
+                        .map_err(|e| format!("Failed to read engine response: {}", e))?;
+                    if read == 0 {
+                        self.framing = Framing::Done;
+                        return Ok(None);
+                    }
+                    buf.truncate(read);
+                    return Ok(Some(buf));
+                }
+            }
+        }
+    }
+
+    pub async fn bytes(mut self) -> Result<Vec<u8>, String> {
+        let mut body = Vec::new();
+        while let Some(chunk) = self.chunk().await? {
+            body.extend_from_slice(&chunk);
+        }
+        Ok(body)
+    }
+
+    pub async fn json<T: DeserializeOwned>(self) -> Result<T, String> {
+        let body = self.bytes().await?;
+        serde_json::from_slice(&body).map_err(|e| format!("Invalid engine response: {}", e))
+    }
+
+    /// Feed each message of a JSON-lines progress stream to `on_message`
+    ///
+    /// A message carrying an `error` ends the stream with that error.
+    pub async fn json_lines(mut self, mut on_message: impl FnMut(&Value)) -> Result<(), String> {
+        let mut pending = Vec::new();
+        loop {
+            let chunk = self.chunk().await?;
+            let done = chunk.is_none();
+            pending.extend(chunk.unwrap_or_default());
+            let mut lines: Vec<Vec<u8>> = Vec::new();
+            while let Some(pos) = pending.iter().position(|&b| b == b'\n') {
+                lines.push(pending.drain(..=pos).collect());
+            }
+            if done {
+                lines.push(std::mem::take(&mut pending));
+            }
+            for line in lines {
+                let line = String::from_utf8_lossy(&line);
+                let line = line.trim();
+                if line.is_empty() {
+                    continue;
+                }
+                let Ok(message) = serde_json::from_str::<Value>(line) else {
+                    continue;
+                };
+                if let Some(error) = message.get("error").and_then(Value::as_str) {
+                    return Err(error.trim().to_string());
+                }
+                on_message(&message);
+            }
+            if done {
+                return Ok(());
+            }
+        }
+    }
+}
+
+async fn read_line(reader: &mut BufReader<Box<dyn Io>>) -> Result<String, String> {
+    let mut line = String::new();
+    reader
+        .read_line(&mut line)
+        .await
+        .map_err(|e| format!("Failed to read engine response: {}", e))?;
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// Image reference with its tag split off, defaulting to `latest`
+///
+/// Pulling a bare name would fetch every tag of the repository.
+pub fn split_image_tag(image: &str) -> (&str, &str) {
+    if image.contains('@') {
+        return (image, "");
+    }
+    match image.rsplit_once(':') {
+        Some((name, tag)) if !tag.contains('/') => (name, tag),
+        _ => (image, "latest"),
+    }
+}
+
+/// Connection to a Docker or Podman engine
+#[derive(Debug, Clone)]
+pub struct Engine {
+    endpoint: Endpoint,
+    podman: bool,
+}
+
+impl Engine {
+    /// Connect to the engine named by `DOCKER_HOST`/`CONTAINER_HOST`, or the
+    /// first default socket that answers
+    pub async fn connect() -> Result<Self, String> {
+        let mut candidates = Vec::new();
+        for var in ["DOCKER_HOST", "CONTAINER_HOST"] {
+            match std::env::var(var) {
+                Ok(host) if !host.is_empty() => candidates.push(Endpoint::parse(&host)?),
+                _ => {}
+            }
+        }
+        if candidates.is_empty() {
+            candidates = Endpoint::defaults();
+        }
+
+        let mut last_error = String::new();
+        for endpoint in candidates {
+            let mut engine = Self {
+                endpoint,
+                podman: false,
+            };
+            match engine.get_json::<Value>("/version").await {
+                Ok(version) => {
+                    engine.podman =
+                        version["Components"]
+                            .as_array()
+                            .into_iter()
+                            .flatten()
+                            .any(|c| {
+                                c["Name"]
+                                    .as_str()
+                                    .is_some_and(|name| name.contains("Podman"))
+                            });
+                    return Ok(engine);
+                }
+                Err(e) => last_error = e,
+            }
+        }
+        Err(format!(
+            "No Docker or Podman engine is reachable ({}). Start one or set DOCKER_HOST.",
+            last_error
+        ))
+    }
+
+    pub fn endpoint(&self) -> &Endpoint {
+        &self.endpoint
+    }
+
+    /// CLI matching the engine, used for interactive terminals
+    pub fn cli(&self) -> &'static str {
+        if self.podman { "podman" } else { "docker" }
+    }
+
+    /// Send a request, returning the response whatever its status
+    pub async fn send(&self, method: &str, path: &str, body: Body) -> Result<Response, String> {
+        let mut stream = self.endpoint.open().await?;
+        let (content_type, payload) = match body {
+            Body::Empty => (None, Vec::new()),
+            Body::Json(value) => (Some("application/json"), value.to_string().into_bytes()),
+            Body::Tar(bytes) => (Some("application/x-tar"), bytes),
+        };
+        let mut head = format!(
+            "{} /{}{} HTTP/1.1\r\nHost: docker\r\nUser-Agent: Cortex\r\nConnection: close\r\n",
+            method, API_VERSION, path
+        );
+        if let Some(content_type) = content_type {
+            head.push_str(&format!("Content-Type: {}\r\n", content_type));
+        }
+        head.push_str(&format!("Content-Length: {}\r\n\r\n", payload.len()));
+
+        let write = async {
+            stream.write_all(head.as_bytes()).await?;
+            stream.write_all(&payload).await?;
+            stream.flush().await
+        };
+        write
+            .await
+            .map_err(|e| format!("Failed to send engine request: {}", e))?;
+        Response::read(stream).await
+    }
+
+    /// Send a request, turning an error status into the engine's message
+    pub async fn request(&self, method: &str, path: &str, body: Body) -> Result<Response, String> {
+        let response = self.send(method, path, body).await?;
+        if response.status < 400 {
+            return Ok(response);
+        }
+        let status = response.status;
+        let body = response.bytes().await.unwrap_or_default();
+        let message = serde_json::from_slice::<Value>(&body)
+            .ok()
+            .and_then(|v| v["message"].as_str().map(str::to_string))
+            .unwrap_or_else(|| String::from_utf8_lossy(&body).trim().to_string());
+        Err(format!("Engine error ({}): {}", status, message))
+    }
+
+    pub async fn get_json<T: DeserializeOwned>(&self, path: &str) -> Result<T, String> {
+        self.request("GET", path, Body::Empty).await?.json().await
+    }
+
+    /// Containers carrying a label, running or not
+    pub async fn list_containers(&self, label: &str) -> Result<Vec<Value>, String> {
+        let filters = json!({ "label": [label] }).to_string();
+        self.get_json(&format!(
+            "/containers/json?all=true&filters={}",
+            urlencoding::encode(&filters)
+        ))
+        .await
+    }
+
+    pub async fn inspect_container(&self, id: &str) -> Result<Value, String> {
+        self.get_json(&format!("/containers/{}/json", urlencoding::encode(id)))
+            .await
+    }
+
+    /// Image details, `None` when the image is not present locally
+    pub async fn inspect_image(&self, image: &str) -> Result<Option<Value>, String> {
+        let response = self
+            .send("GET", &format!("/images/{}/json", image), Body::Empty)
+            .await?;
+        if response.status == 404 {
+            return Ok(None);
+        }
+        if response.status >= 400 {
+            return Err(format!(
+                "Engine error ({}) inspecting {}",
+                response.status, image
+            ));
+        }
+        response.json().await.map(Some)
+    }
+
+    /// Pull an image, reporting each progress line
+    pub async fn pull_image(
+        &self,
+        image: &str,
+        mut on_log: impl FnMut(String),
+    ) -> Result<(), String> {
+        let (name, tag) = split_image_tag(image);
+        let mut path = format!("/images/create?fromImage={}", urlencoding::encode(name));
+        if !tag.is_empty() {
+            path.push_str(&format!("&tag={}", urlencoding::encode(tag)));
+        }
+        let response = self.request("POST", &path, Body::Empty).await?;
+        response
+            .json_lines(|message| {
+                let status = message["status"].as_str().unwrap_or_default();
+                // Per-layer byte counters would flood the log
+                if message
+                    .get("progressDetail")
+                    .is_some_and(|d| d["current"].is_number())
+                {
+                    return;
+                }
+                match message["id"].as_str() {
+                    Some(id) => on_log(format!("{}: {}", id, status)),
+                    None => on_log(status.to_string()),
+                }
+            })
+            .await
+    }
+
+    /// Build an image from a tar'd context, reporting each log line
+    pub async fn build_image(
+        &self,
+        context: Vec<u8>,
+        dockerfile: &str,
+        tag: &str,
+        build_args: &std::collections::BTreeMap<String, String>,
+        target: Option<&str>,
+        mut on_log: impl FnMut(String),
+    ) -> Result<(), String> {
+        let mut path = format!(
+            "/build?t={}&dockerfile={}&rm=true&forcerm=true",
+            urlencoding::encode(tag),
+            urlencoding::encode(dockerfile)
+        );
+        if !build_args.is_empty() {
+            let args = serde_json::to_string(build_args).map_err(|e| e.to_string())?;
+            path.push_str(&format!("&buildargs={}", urlencoding::encode(&args)));
+        }
+        if let Some(target) = target {
+            path.push_str(&format!("&target={}", urlencoding::encode(target)));
+        }
+        let response = self.request("POST", &path, Body::Tar(context)).await?;
+        response
+            .json_lines(|message| {
+                if let Some(stream) = message["stream"].as_str() {
+                    for line in stream.lines().filter(|l| !l.trim().is_empty()) {
+                        on_log(line.to_string());
+                    }
+                } else if let Some(status) = message["status"].as_str() {
+                    on_log(status.to_string());
+                }
+            })
+            .await
+    }
+
+    /// Create a container, returning its id
+    pub async fn create_container(&self, name: &str, spec: Value) -> Result<String, String> {
+        let created: Value = self
+            .request(
+                "POST",
+                &format!("/containers/create?name={}", urlencoding::encode(name)),
+                Body::Json(spec),
+            )
+            .await?
+            .json()
+            .await?;
+        created["Id"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| "Engine did not return a container id".to_string())
+    }
+
+    pub async fn start_container(&self, id: &str) -> Result<(), String> {
+        self.request(
+            "POST",
+            &format!("/containers/{}/start", urlencoding::encode(id)),
+            Body::Empty,
+        )
+        .await?
+        .bytes()
+        .await
+        .map(drop)
+    }
+
+    pub async fn stop_container(&self, id: &str) -> Result<(), String> {
+        self.request(
+            "POST",
+            &format!("/containers/{}/stop?t=10", urlencoding::encode(id)),
+            Body::Empty,
+        )
+        .await?
+        .bytes()
+        .await
+        .map(drop)
+    }
+
+    pub async fn remove_container(&self, id: &str) -> Result<(), String> {
+        self.request(
+            "DELETE",
+            &format!("/containers/{}?force=true", urlencoding::encode(id)),
+            Body::Empty,
+        )
+        .await?
+        .bytes()
+        .await
+        .map(drop)
+    }
+
+    /// Run a command in a container, streaming its output, and return its
+    /// exit code
+    pub async fn exec(
+        &self,
+        id: &str,
+        cmd: &[String],
+        user: Option<&str>,
+        working_dir: Option<&str>,
+        env: &[String],
+        mut on_output: impl FnMut(StdStream, &[u8]),
+    ) -> Result<i64, String> {
+        let mut spec = json!({
+            "AttachStdout": true,
+            "AttachStderr": true,
+            "Cmd": cmd,
+            "Env": env,
+        });
+        if let Some(user) = user {
+            spec["User"] = json!(user);
+        }
+        if let Some(working_dir) = working_dir {
+            spec["WorkingDir"] = json!(working_dir);
+        }
+        let created: Value = self
+            .request(
+                "POST",
+                &format!("/containers/{}/exec", urlencoding::encode(id)),
+                Body::Json(spec),
+            )
+            .await?
+            .json()
+            .await?;
+        let exec_id = created["Id"]
+            .as_str()
+            .ok_or_else(|| "Engine did not return an exec id".to_string())?
+            .to_string();
+
+        let mut response = self
+            .request(
+                "POST",
+                &format!("/exec/{}/start", exec_id),
+                Body::Json(json!({ "Detach": false, "Tty": false })),
+            )
+            .await?;
+        let mut demuxer = StreamDemuxer::default();
+        while let Some(chunk) = response.chunk().await? {
+            for (stream, payload) in demuxer.push(&chunk) {
+                on_output(stream, &payload);
+            }
+        }
+
+        let inspected: Value = self.get_json(&format!("/exec/{}/json", exec_id)).await?;
+        inspected["ExitCode"]
+            .as_i64()
+            .ok_or_else(|| "Engine did not report an exit code".to_string())
+    }
+
+    /// Tar archive of a path in a container
+    pub async fn get_archive(&self, id: &str, path: &str) -> Result<Vec<u8>, String> {
+        self.request(
+            "GET",
+            &format!(
+                "/containers/{}/archive?path={}",
+                urlencoding::encode(id),
+                urlencoding::encode(path)
+            ),
+            Body::Empty,
+        )
+        .await?
+        .bytes()
+        .await
+    }
+
+    /// Extract a tar archive into a directory of a container
+    pub async fn put_archive(&self, id: &str, dir: &str, archive: Vec<u8>) -> Result<(), String> {
+        self.request(
+            "PUT",
+            &format!(
+                "/containers/{}/archive?path={}",
+                urlencoding::encode(id),
+                urlencoding::encode(dir)
+            ),
+            Body::Tar(archive),
+        )
+        .await?
+        .bytes()
+        .await
+        .map(drop)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    async fn response_from(raw: &'static [u8]) -> Response {
+        let (client, mut server) = tokio::io::duplex(64);
+        tokio::spawn(async move {
+            // Dribble the response so frames and chunks span reads
+            for piece in raw.chunks(5) {
+                server.write_all(piece).await.unwrap();
+            }
+        });
+        Response::read(Box::new(client)).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn decodes_chunked_json_lines() {
+        let response = response_from(
+            b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n\
+              15\r\n{\"stream\":\"Step 1\\n\"}\r\n\
+              1c\r\n\n{\"status\":\"done\"}\n{\"error\":\r\n\
+              a\r\n\"boom\"}\n  \r\n\
+              0\r\n\r\n",
+        )
+        .await;
+        assert_eq!(response.status, 200);
+        let mut messages = Vec::new();
+        let result = response.json_lines(|m| messages.push(m.clone())).await;
+        assert_eq!(result, Err("boom".to_string()));
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[1]["status"], "done");
+    }
+
+    #[tokio::test]
+    async fn reads_length_and_eof_framed_bodies() {
+        let response =
+            response_from(b"HTTP/1.1 404 Not Found\r\nContent-Length: 5\r\n\r\nnope!extra").await;
+        assert_eq!(response.status, 404);
+        assert_eq!(response.bytes().await.unwrap(), b"nope!");
+
+        let response = response_from(b"HTTP/1.1 200 OK\r\n\r\nraw stream").await;
+        assert_eq!(response.bytes().await.unwrap(), b"raw stream");
+    }
+
+    #[test]
+    fn demuxes_frames_split_across_reads() {
+        let mut demuxer = StreamDemuxer::default();
+        let mut data = vec![1, 0, 0, 0, 0, 0, 0, 3];
+        data.extend_from_slice(b"out");
+        data.extend_from_slice(&[2, 0, 0, 0, 0, 0, 0, 4]);
+        data.extend_from_slice(b"err!");
+        assert!(demuxer.push(&data[..6]).is_empty());
+        let frames = demuxer.push(&data[6..13]);
+        assert_eq!(frames, vec![(StdStream::Stdout, b"out".to_vec())]);
+        let frames = demuxer.push(&data[13..]);
+        assert_eq!(frames, vec![(StdStream::Stderr, b"err!".to_vec())]);
+    }
+
+    #[test]
+    fn parses_engine_addresses_and_image_tags() {
+        assert_eq!(
+            Endpoint::parse("unix:///var/run/docker.sock").unwrap(),
+            Endpoint::Unix(PathBuf::from("/var/run/docker.sock"))
+        );
+        let pipe = Endpoint::parse("npipe:////./pipe/docker_engine").unwrap();
+        assert_eq!(
+            pipe,
+            Endpoint::NamedPipe(r"\\.\pipe\docker_engine".to_string())
+        );
+        assert_eq!(pipe.to_host(), "npipe:////./pipe/docker_engine");
+        assert!(Endpoint::parse("ssh://host").is_err());
+
+        assert_eq!(split_image_tag("ubuntu"), ("ubuntu", "latest"));
+        assert_eq!(
+            split_image_tag("localhost:5000/app"),
+            ("localhost:5000/app", "latest")
+        );
+        assert_eq!(
+            split_image_tag("mcr.microsoft.com/devcontainers/rust:1"),
+            ("mcr.microsoft.com/devcontainers/rust", "1")
+        );
+        assert_eq!(
+            split_image_tag("alpine@sha256:abc"),
+            ("alpine@sha256:abc", "")
+        );
+    }
+}
@@ -0,0 +1,292 @@
+//! Installing dev container Features
+//!
+//! Features come from OCI artifacts (`ghcr.io/devcontainers/features/node:1`),
+//! `.tgz` URLs, or folders next to devcontainer.json. They are installed, in
+//! the order the config lists them, by a Dockerfile layered on the base
+//! image: each feature's `install.sh` runs as root with its options exported
+//! as upper-cased environment variables.
+
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use serde_json::{Map, Value};
+
+use super::registry::{OciRef, fetch_artifact, read_tar_file};
+
+/// Where features are copied to inside the image while installing
+const FEATURES_DIR: &str = "/tmp/devcontainer-features";
+
+enum FeatureFiles {
+    /// An uncompressed tar of the feature folder
+    Archive(Vec<u8>),
+    Folder(PathBuf),
+}
+
+/// A feature ready to be installed
+pub struct ResolvedFeature {
+    pub id: String,
+    files: FeatureFiles,
+    /// Contents of `devcontainer-feature.json`
+    metadata: Value,
+    /// Options as given in devcontainer.json
+    options: Value,
+}
+
+/// Fetch every feature of a config
+///
+/// Local features are resolved against the folder holding devcontainer.json.
+pub async fn resolve_features(
+    features: &Map<String, Value>,
+    config_dir: &Path,
+) -> Result<Vec<ResolvedFeature>, String> {
+    let mut resolved = Vec::new();
+    for (id, options) in features {
+        let files = if id.starts_with("./") || id.starts_with("../") {
+            FeatureFiles::Folder(config_dir.join(id))
+        } else if id.starts_with("https://") {
+            let bytes = reqwest::get(id.as_str())
+                .await
+                .and_then(|r| r.error_for_status())
+                .map_err(|e| format!("Failed to download feature {}: {}", id, e))?
+                .bytes()
+                .await
+                .map_err(|e| format!("Failed to download feature {}: {}", id, e))?;
+            let mut tar = Vec::new();
+            flate2::read::GzDecoder::new(&bytes[..])
+                .read_to_end(&mut tar)
+                .map_err(|e| format!("Feature {} is not a .tgz: {}", id, e))?;
+            FeatureFiles::Archive(tar)
+        } else {
+            FeatureFiles::Archive(fetch_artifact(&OciRef::parse(id)?).await?)
+        };
+
+        let metadata = match &files {
+            FeatureFiles::Archive(archive) => read_tar_file(archive, "devcontainer-feature.json")?,
+            FeatureFiles::Folder(dir) => std::fs::read(dir.join("devcontainer-feature.json")).ok(),
+        }
+        .ok_or_else(|| format!("Feature {} has no devcontainer-feature.json", id))?;
+        let metadata = serde_json::from_slice(&metadata)
+            .map_err(|e| format!("Invalid devcontainer-feature.json in {}: {}", id, e))?;
+
+        resolved.push(ResolvedFeature {
+            id: id.clone(),
+            files,
+            metadata,
+            options: options.clone(),
+        });
+    }
+    Ok(resolved)
+}
+
+/// Name of the environment variable an option is exported as
+fn option_env_name(option: &str) -> String {
+    let name: String = option
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{}", name)
+    } else {
+        name
+    }
+}
+
+fn env_value(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+impl ResolvedFeature {
+    /// Option values with defaults filled in
+    ///
+    /// A bare string in devcontainer.json is shorthand for `version`.
+    fn option_env(&self) -> BTreeMap<String, String> {
+        let mut env = BTreeMap::new();
+        if let Some(declared) = self.metadata["options"].as_object() {
+            for (name, option) in declared {
+                if let Some(default) = option.get("default") {
+                    env.insert(option_env_name(name), env_value(default));
+                }
+            }
+        }
+        match &self.options {
+            Value::Object(given) => {
+                for (name, value) in given {
+                    env.insert(option_env_name(name), env_value(value));
+                }
+            }
+            Value::String(version) => {
+                env.insert("VERSION".to_string(), version.clone());
+            }
+            _ => {}
+        }
+        env
+    }
+}
+
+/// Quote a value in double quotes, escaping `specials`
+fn double_quote(value: &str, specials: &[char]) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        if c == '"' || c == '\\' || specials.contains(&c) {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn append_file(builder: &mut tar::Builder<Vec<u8>>, path: &str, data: &[u8]) -> Result<(), String> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    builder
+        .append_data(&mut header, path, data)
+        .map_err(|e| format!("Failed to assemble feature build: {}", e))
+}
+
+/// Build context and Dockerfile installing `features` on top of `base_image`
+///
+/// `image_user` is the base image's default user, restored after the
+/// installs, which run as root.
+pub fn feature_build_context(
+    features: &[ResolvedFeature],
+    base_image: &str,
+    image_user: Option<&str>,
+    remote_user: &str,
+    container_user: &str,
+) -> Result<Vec<u8>, String> {
+    let mut builder = tar::Builder::new(Vec::new());
+    let mut dockerfile = format!("FROM {}\nUSER root\n", base_image);
+
+    for (index, feature) in features.iter().enumerate() {
+        let dir = format!("features/{}", index);
+        match &feature.files {
+            FeatureFiles::Folder(folder) => builder
+                .append_dir_all(&dir, folder)
+                .map_err(|e| format!("Failed to read feature {}: {}", feature.id, e))?,
+            FeatureFiles::Archive(archive) => {
+                let mut archive = tar::Archive::new(&archive[..]);
+                let entries = archive
+                    .entries()
+                    .map_err(|e| format!("Invalid feature {}: {}", feature.id, e))?;
+                for entry in entries {
+                    let entry =
+                        entry.map_err(|e| format!("Invalid feature {}: {}", feature.id, e))?;
+                    let path = entry
+                        .path()
+                        .map_err(|e| format!("Invalid feature {}: {}", feature.id, e))?
+                        .to_string_lossy()
+                        .trim_start_matches("./")
+                        .to_string();
+                    if path.is_empty() {
+                        continue;
+                    }
+                    let mut header = entry.header().clone();
+                    builder
+                        .append_data(&mut header, format!("{}/{}", dir, path), entry)
+                        .map_err(|e| format!("Failed to assemble feature build: {}", e))?;
+                }
+            }
+        }
+
+        let mut env_file = String::new();
+        let mut env = feature.option_env();
+        env.insert("_REMOTE_USER".to_string(), remote_user.to_string());
+        env.insert("_CONTAINER_USER".to_string(), container_user.to_string());
+        for (name, value) in env {
+            env_file.push_str(&format!(
+                "export {}={}\n",
+                name,
+                double_quote(&value, &['$', '`'])
+            ));
+        }
+        append_file(
+            &mut builder,
+            &format!("{}/devcontainer-features.env", dir),
+            env_file.as_bytes(),
+        )?;
+
+        let target = format!("{}/{}", FEATURES_DIR, index);
+        dockerfile.push_str(&format!(
+            "COPY {dir} {target}\n\
+             RUN cd {target} && chmod +x install.sh && . ./devcontainer-features.env && ./install.sh\n",
+        ));
+        if let Some(container_env) = feature.metadata["containerEnv"].as_object() {
+            for (name, value) in container_env {
+                // Left unescaped so values can extend variables like `${PATH}`
+                dockerfile.push_str(&format!(
+                    "ENV {}={}\n",
+                    name,
+                    double_quote(&env_value(value), &[])
+                ));
+            }
+        }
+    }
+
+    dockerfile.push_str(&format!("RUN rm -rf {}\n", FEATURES_DIR));
+    if let Some(user) = image_user.filter(|u| !u.is_empty()) {
+        dockerfile.push_str(&format!("USER {}\n", user));
+    }
+    append_file(&mut builder, "Dockerfile", dockerfile.as_bytes())?;
+    builder
+        .into_inner()
+        .map_err(|e| format!("Failed to assemble feature build: {}", e))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn generates_an_install_layer_per_feature() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("install.sh"), "echo hi").unwrap();
+        let features = vec![ResolvedFeature {
+            id: "./local".to_string(),
+            files: FeatureFiles::Folder(dir.path().to_path_buf()),
+            metadata: json!({
+                "id": "local",
+                "options": { "version": { "default": "lts" }, "install-tools": { "default": true } },
+                "containerEnv": { "PATH": "/opt/x/bin:${PATH}" }
+            }),
+            options: json!({ "version": "20" }),
+        }];
+
+        let context =
+            feature_build_context(&features, "node:20", Some("node"), "node", "root").unwrap();
+        let dockerfile =
+            String::from_utf8(read_tar_file(&context, "Dockerfile").unwrap().unwrap()).unwrap();
+        assert!(dockerfile.starts_with("FROM node:20\nUSER root\nCOPY features/0 "));
+        assert!(dockerfile.contains("ENV PATH=\"/opt/x/bin:${PATH}\"\n"));
+        assert!(dockerfile.ends_with("USER node\n"));
+
+        let env = read_tar_file(&context, "features/0/devcontainer-features.env")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(env).unwrap(),
+            "export INSTALL_TOOLS=\"true\"\nexport VERSION=\"20\"\n\
+             export _CONTAINER_USER=\"root\"\nexport _REMOTE_USER=\"node\"\n"
+        );
+        assert!(
+            read_tar_file(&context, "features/0/install.sh")
+                .unwrap()
+                .is_some()
+        );
+    }
+}
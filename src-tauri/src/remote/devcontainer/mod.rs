@@ -0,0 +1,666 @@
+//! Dev Containers
+//!
+//! Builds and runs the containers described by devcontainer.json through the
+//! Docker or Podman Engine API, then serves them as workspace backends: file
+//! operations, terminals and tasks run inside the container.
+//!
+//! Containers are found again through their labels, so they outlive the app.
+//! The settings later commands run under, and the lifecycle commands still
+//! due on later starts and attaches, are kept in a label at creation.
+
+mod backend;
+pub mod config;
+mod engine;
+mod features;
+pub mod registry;
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Emitter};
+
+use backend::ContainerWorkspace;
+use config::{DevContainerConfig, LifecycleCommand, MountSpec, PortSpec};
+use engine::Engine;
+
+/// Labels shared with other devcontainer.json tools
+const LOCAL_FOLDER_LABEL: &str = "devcontainer.local_folder";
+const CONFIG_FILE_LABEL: &str = "devcontainer.config_file";
+/// Label holding the `ContainerMetadata` of containers built here
+const METADATA_LABEL: &str = "cortex.devcontainer.metadata";
+
+/// Keeps a container with an overridden command alive and lets it stop
+/// promptly
+const KEEP_ALIVE_SCRIPT: &str =
+    "echo Container started\ntrap \"exit 0\" 15\nwhile sleep 1 & wait $!; do :; done";
+
+/// `runArgs` flags that take a value
+const RUN_ARGS_WITH_VALUE: &[&str] = &[
+    "--cap-add",
+    "--security-opt",
+    "--network",
+    "--net",
+    "--add-host",
+];
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ContainerStatus {
+    Running,
+    Stopped,
+    Starting,
+    Error,
+}
+
+/// A dev container as listed in the UI
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DevContainer {
+    pub id: String,
+    pub name: String,
+    pub image: Option<String>,
+    pub status: ContainerStatus,
+    pub config_path: Option<String>,
+    pub workspace_path: Option<String>,
+    /// Epoch milliseconds, for running containers
+    pub started_at: Option<i64>,
+    pub ports: Vec<u16>,
+}
+
+/// Settings stored on a container at creation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ContainerMetadata {
+    name: Option<String>,
+    remote_user: Option<String>,
+    workspace_folder: String,
+    #[serde(default)]
+    remote_env: BTreeMap<String, String>,
+    post_start_command: Option<LifecycleCommand>,
+    post_attach_command: Option<LifecycleCommand>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BuildProgress<'a> {
+    build_id: &'a str,
+    message: String,
+}
+
+impl DevContainer {
+    fn from_summary(summary: &Value) -> Self {
+        let labels = &summary["Labels"];
+        let metadata = labels[METADATA_LABEL]
+            .as_str()
+            .and_then(|m| serde_json::from_str::<ContainerMetadata>(m).ok());
+        let container_name = summary["Names"][0]
+            .as_str()
+            .unwrap_or_default()
+            .trim_start_matches('/')
+            .to_string();
+        let status = match summary["State"].as_str().unwrap_or_default() {
+            "running" => ContainerStatus::Running,
+            "restarting" => ContainerStatus::Starting,
+            "dead" => ContainerStatus::Error,
+            _ => ContainerStatus::Stopped,
+        };
+        let mut ports: Vec<u16> = summary["Ports"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|p| p["PublicPort"].as_u64())
+            .filter_map(|p| u16::try_from(p).ok())
+            .collect();
+        ports.sort_unstable();
+        ports.dedup();
+
+        Self {
+            id: summary["Id"].as_str().unwrap_or_default().to_string(),
+            name: metadata.and_then(|m| m.name).unwrap_or(container_name),
+            image: summary["Image"].as_str().map(str::to_string),
+            status,
+            config_path: labels[CONFIG_FILE_LABEL].as_str().map(str::to_string),
+            workspace_path: labels[LOCAL_FOLDER_LABEL].as_str().map(str::to_string),
+            started_at: None,
+            ports,
+        }
+    }
+}
+
+/// Image tag for a workspace's config, stable across rebuilds
+fn image_tag(workspace: &Path, config_file: &Path) -> String {
+    let name: String = workspace
+        .file_name()
+        .map(|n| n.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    let hash = Sha256::digest(format!(
+        "{}\n{}",
+        workspace.display(),
+        config_file.display()
+    ));
+    let hash: String = hash[..6].iter().map(|b| format!("{:02x}", b)).collect();
+    format!("cortex-devcontainer-{}-{}", name.trim_matches('-'), hash)
+}
+
+/// Apply the `runArgs` that map onto the create request; others are skipped
+fn apply_run_args(args: &[String], host_config: &mut Value) {
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) => (flag, Some(value.to_string())),
+            None => (arg.as_str(), None),
+        };
+        let value = if RUN_ARGS_WITH_VALUE.contains(&flag) {
+            match inline.or_else(|| args.next().cloned()) {
+                Some(value) => value,
+                None => continue,
+            }
+        } else {
+            inline.unwrap_or_else(|| "true".to_string())
+        };
+        let list = match flag {
+            "--privileged" => {
+                host_config["Privileged"] = json!(value == "true");
+                continue;
+            }
+            "--init" => {
+                host_config["Init"] = json!(value == "true");
+                continue;
+            }
+            "--network" | "--net" => {
+                host_config["NetworkMode"] = json!(value);
+                continue;
+            }
+            "--cap-add" => "CapAdd",
+            "--security-opt" => "SecurityOpt",
+            "--add-host" => "ExtraHosts",
+            _ => {
+                tracing::warn!("Ignoring unsupported runArgs entry: {}", arg);
+                continue;
+            }
+        };
+        match host_config[list].as_array_mut() {
+            Some(values) => values.push(json!(value)),
+            None => host_config[list] = json!([value]),
+        }
+    }
+}
+
+/// Engine API create request for a dev container
+fn container_spec(
+    config: &DevContainerConfig,
+    workspace: &Path,
+    config_file: &Path,
+    image: &str,
+    metadata: &ContainerMetadata,
+) -> Result<Value, String> {
+    let workspace_mount = match &config.workspace_mount {
+        Some(spec) => MountSpec::Spec(spec.clone()).to_engine_mount()?,
+        None => json!({
+            "Type": "bind",
+            "Source": workspace.to_string_lossy(),
+            "Target": metadata.workspace_folder,
+        }),
+    };
+    let mut mounts = vec![workspace_mount];
+    for mount in &config.mounts {
+        mounts.push(mount.to_engine_mount()?);
+    }
+
+    // Forwarded container ports are published on the same local port
+    let mut exposed = Map::new();
+    let mut bindings = Map::new();
+    for port in &config.forward_ports {
+        match port {
+            PortSpec::Port(port) => {
+                let key = format!("{}/tcp", port);
+                exposed.insert(key.clone(), json!({}));
+                bindings.insert(
+                    key,
+                    json!([{ "HostIp": "127.0.0.1", "HostPort": port.to_string() }]),
+                );
+            }
+            PortSpec::Named(port) => {
+                tracing::warn!(
+                    "Not forwarding {}: only container ports are supported",
+                    port
+                )
+            }
+        }
+    }
+
+    let metadata = serde_json::to_string(metadata).map_err(|e| e.to_string())?;
+    let mut host_config = json!({ "Mounts": mounts, "PortBindings": bindings });
+    apply_run_args(&config.run_args, &mut host_config);
+    let mut spec = json!({
+        "Image": image,
+        "Labels": {
+            LOCAL_FOLDER_LABEL: workspace.to_string_lossy(),
+            CONFIG_FILE_LABEL: config_file.to_string_lossy(),
+            METADATA_LABEL: metadata,
+        },
+        "Env": config
+            .container_env
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect::<Vec<_>>(),
+        "ExposedPorts": exposed,
+        "HostConfig": host_config,
+    });
+    if let Some(user) = &config.container_user {
+        spec["User"] = json!(user);
+    }
+    if config.override_command.unwrap_or(true) {
+        spec["Entrypoint"] = json!(["/bin/sh"]);
+        spec["Cmd"] = json!(["-c", KEEP_ALIVE_SCRIPT]);
+    }
+    Ok(spec)
+}
+
+/// Tar a build context directory, adding the Dockerfile when it lives outside
+/// it; returns the archive and the Dockerfile's path inside it
+fn build_context(context_dir: &Path, dockerfile: &Path) -> Result<(Vec<u8>, String), String> {
+    let mut builder = tar::Builder::new(Vec::new());
+    builder.follow_symlinks(false);
+    builder.append_dir_all(".", context_dir).map_err(|e| {
+        format!(
+            "Failed to read build context {}: {}",
+            context_dir.display(),
+            e
+        )
+    })?;
+
+    let context_dir = context_dir
+        .canonicalize()
+        .unwrap_or(context_dir.to_path_buf());
+    let dockerfile = dockerfile
+        .canonicalize()
+        .unwrap_or(dockerfile.to_path_buf());
+    let inside = match dockerfile.strip_prefix(&context_dir) {
+        Ok(relative) => relative.to_string_lossy().replace('\\', "/"),
+        Err(_) => {
+            let name = ".cortex-devcontainer.Dockerfile";
+            builder
+                .append_path_with_name(&dockerfile, name)
+                .map_err(|e| format!("Failed to read {}: {}", dockerfile.display(), e))?;
+            name.to_string()
+        }
+    };
+    let archive = builder
+        .into_inner()
+        .map_err(|e| format!("Failed to assemble build context: {}", e))?;
+    Ok((archive, inside))
+}
+
+/// Dev containers known to the engine, and the one the workspace is attached
+/// to
+pub struct DevContainerState {
+    engine: tokio::sync::Mutex<Option<Engine>>,
+    active: parking_lot::Mutex<Option<String>>,
+}
+
+impl DevContainerState {
+    pub fn new() -> Self {
+        Self {
+            engine: tokio::sync::Mutex::new(None),
+            active: parking_lot::Mutex::new(None),
+        }
+    }
+
+    async fn engine(&self) -> Result<Engine, String> {
+        let mut engine = self.engine.lock().await;
+        if let Some(engine) = engine.as_ref() {
+            return Ok(engine.clone());
+        }
+        let connected = Engine::connect().await?;
+        *engine = Some(connected.clone());
+        Ok(connected)
+    }
+
+    /// Container the workspace is attached to
+    fn active(&self) -> Option<String> {
+        self.active.lock().clone()
+    }
+
+    pub async fn list(&self) -> Result<Vec<DevContainer>, String> {
+        let engine = self.engine().await?;
+        let mut containers = Vec::new();
+        for summary in engine.list_containers(LOCAL_FOLDER_LABEL).await? {
+            let mut container = DevContainer::from_summary(&summary);
+            if container.status == ContainerStatus::Running {
+                let inspected = engine.inspect_container(&container.id).await?;
+                container.started_at = inspected["State"]["StartedAt"]
+                    .as_str()
+                    .and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok())
+                    .map(|t| t.timestamp_millis());
+            }
+            containers.push(container);
+        }
+        Ok(containers)
+    }
+
+    async fn get(&self, id: &str) -> Result<DevContainer, String> {
+        self.list()
+            .await?
+            .into_iter()
+            .find(|c| c.id == id || c.id.starts_with(id))
+            .ok_or_else(|| format!("Dev container not found: {}", id))
+    }
+
+    /// Build the image of a workspace's config and (re)create its container,
+    /// running the lifecycle commands up to `postStartCommand`
+    ///
+    /// Logs stream as `devcontainer:build-progress` events.
+    pub async fn build(
+        &self,
+        app: &AppHandle,
+        workspace_path: &str,
+        config_path: Option<&str>,
+        build_id: &str,
+    ) -> Result<DevContainer, String> {
+        let log = |message: String| {
+            let _ = app.emit(
+                "devcontainer:build-progress",
+                BuildProgress { build_id, message },
+            );
+        };
+        let workspace = PathBuf::from(workspace_path);
+        let config_file = config::find_config(&workspace, config_path)?;
+        let config = config::resolve_config(&config_file, &workspace)?;
+        if config.docker_compose_file.is_some() {
+            return Err("Docker Compose based dev containers are not supported".to_string());
+        }
+        let config_dir = config_file.parent().unwrap_or(&workspace).to_path_buf();
+        log(format!("Using {}", config_file.display()));
+
+        if let Some(command) = &config.initialize_command {
+            log("Running initializeCommand".to_string());
+            backend::run_on_host(command, &workspace, &log).await?;
+        }
+
+        let engine = self.engine().await?;
+        let tag = image_tag(&workspace, &config_file);
+        let mut image = match (&config.build, &config.image) {
+            (Some(build), _) if build.dockerfile.is_some() => {
+                let dockerfile = config_dir.join(build.dockerfile.as_deref().unwrap_or_default());
+                let context_dir = config_dir.join(build.context.as_deref().unwrap_or("."));
+                log(format!("Building {}", dockerfile.display()));
+                let (context, dockerfile) =
+                    tokio::task::spawn_blocking(move || build_context(&context_dir, &dockerfile))
+                        .await
+                        .map_err(|e| format!("Build context task failed: {}", e))??;
+                engine
+                    .build_image(
+                        context,
+                        &dockerfile,
+                        &tag,
+                        &build.args,
+                        build.target.as_deref(),
+                        &log,
+                    )
+                    .await?;
+                tag.clone()
+            }
+            (_, Some(image)) => {
+                if engine.inspect_image(image).await?.is_none() {
+                    log(format!("Pulling {}", image));
+                    engine.pull_image(image, &log).await?;
+                }
+                image.clone()
+            }
+            _ => return Err("devcontainer.json needs an image or a build.dockerfile".to_string()),
+        };
+
+        let image_user = engine
+            .inspect_image(&image)
+            .await?
+            .and_then(|i| i["Config"]["User"].as_str().map(str::to_string))
+            .filter(|u| !u.is_empty());
+        if !config.features.is_empty() {
+            log(format!("Installing {} feature(s)", config.features.len()));
+            let resolved = features::resolve_features(&config.features, &config_dir).await?;
+            let container_user = config
+                .container_user
+                .clone()
+                .or(image_user.clone())
+                .unwrap_or_else(|| "root".to_string());
+            let remote_user = config
+                .remote_user
+                .clone()
+                .unwrap_or_else(|| container_user.clone());
+            let base = image.clone();
+            let base_user = image_user.clone();
+            let context = tokio::task::spawn_blocking(move || {
+                features::feature_build_context(
+                    &resolved,
+                    &base,
+                    base_user.as_deref(),
+                    &remote_user,
+                    &container_user,
+                )
+            })
+            .await
+            .map_err(|e| format!("Feature build task failed: {}", e))??;
+            let features_tag = format!("{}-features", tag);
+            engine
+                .build_image(
+                    context,
+                    "Dockerfile",
+                    &features_tag,
+                    &BTreeMap::new(),
+                    None,
+                    &log,
+                )
+                .await?;
+            image = features_tag;
+        }
+
+        // A rebuild replaces the previous container of the same config
+        let config_label = config_file.to_string_lossy().to_string();
+        let folder_filter = format!("{}={}", LOCAL_FOLDER_LABEL, workspace.to_string_lossy());
+        for existing in engine.list_containers(&folder_filter).await? {
+            if existing["Labels"][CONFIG_FILE_LABEL].as_str() == Some(config_label.as_str()) {
+                let id = existing["Id"].as_str().unwrap_or_default();
+                log(format!(
+                    "Removing previous container {}",
+                    &id[..12.min(id.len())]
+                ));
+                engine.remove_container(id).await?;
+            }
+        }
+
+        let metadata = ContainerMetadata {
+            name: config.name.clone(),
+            remote_user: config
+                .remote_user
+                .clone()
+                .or(config.container_user.clone())
+                .or(image_user),
+            workspace_folder: config.container_workspace_folder(&workspace),
+            remote_env: config.remote_env.clone(),
+            post_start_command: config.post_start_command.clone(),
+            post_attach_command: config.post_attach_command.clone(),
+        };
+        let spec = container_spec(&config, &workspace, &config_file, &image, &metadata)?;
+        log("Creating container".to_string());
+        let id = engine.create_container(&tag, spec).await?;
+        engine.start_container(&id).await?;
+
+        let container = ContainerWorkspace::open(engine, &id).await?;
+        for (hook, command) in [
+            ("onCreateCommand", &config.on_create_command),
+            ("updateContentCommand", &config.update_content_command),
+            ("postCreateCommand", &config.post_create_command),
+            ("postStartCommand", &config.post_start_command),
+        ] {
+            if let Some(command) = command {
+                log(format!("Running {}", hook));
+                container.run_lifecycle(hook, command, &log).await?;
+            }
+        }
+        log("Dev container ready".to_string());
+        self.get(&id).await
+    }
+
+    /// Start a stopped container and run its `postStartCommand`
+    pub async fn start(&self, id: &str) -> Result<(), String> {
+        let engine = self.engine().await?;
+        engine.start_container(id).await?;
+        self.run_stored_hook(engine, id, "postStartCommand", |m| m.post_start_command)
+            .await
+    }
+
+    pub async fn stop(&self, id: &str) -> Result<(), String> {
+        self.engine().await?.stop_container(id).await?;
+        self.detach(id);
+        Ok(())
+    }
+
+    pub async fn remove(&self, id: &str) -> Result<(), String> {
+        self.engine().await?.remove_container(id).await?;
+        self.detach(id);
+        Ok(())
+    }
+
+    /// Attach the workspace to a running container and run its
+    /// `postAttachCommand`
+    pub async fn connect(&self, id: &str) -> Result<(), String> {
+        let engine = self.engine().await?;
+        self.run_stored_hook(engine, id, "postAttachCommand", |m| m.post_attach_command)
+            .await?;
+        *self.active.lock() = Some(id.to_string());
+        Ok(())
+    }
+
+    fn detach(&self, id: &str) {
+        let mut active = self.active.lock();
+        if active.as_deref() == Some(id) {
+            *active = None;
+        }
+    }
+
+    async fn run_stored_hook(
+        &self,
+        engine: Engine,
+        id: &str,
+        hook: &str,
+        command: impl FnOnce(ContainerMetadata) -> Option<LifecycleCommand>,
+    ) -> Result<(), String> {
+        let container = engine.inspect_container(id).await?;
+        let command = container["Config"]["Labels"][METADATA_LABEL]
+            .as_str()
+            .and_then(|m| serde_json::from_str::<ContainerMetadata>(m).ok())
+            .and_then(command);
+        let workspace = ContainerWorkspace::open(engine, id).await?;
+        if let Some(command) = command {
+            let log = |line: String| tracing::info!("[{}] {}", hook, line);
+            workspace.run_lifecycle(hook, &command, &log).await?;
+        }
+        Ok(())
+    }
+
+    /// Workspace backend of a running container, by default the attached one
+    pub async fn workspace(&self, id: Option<&str>) -> Result<ContainerWorkspace, String> {
+        let id = match id {
+            Some(id) => id.to_string(),
+            None => self
+                .active()
+                .ok_or_else(|| "No dev container is connected".to_string())?,
+        };
+        ContainerWorkspace::open(self.engine().await?, &id).await
+    }
+}
+
+impl Default for DevContainerState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_the_create_request_from_the_config() {
+        let config: DevContainerConfig = serde_json::from_value(json!({
+            "image": "node:20",
+            "forwardPorts": [3000, "db:5432"],
+            "mounts": ["source=cache,target=/cache,type=volume"],
+            "containerEnv": { "CI": "1" },
+            "runArgs": ["--cap-add=SYS_PTRACE", "--security-opt", "seccomp=unconfined", "--init", "--gpus=all"]
+        }))
+        .unwrap();
+        let metadata = ContainerMetadata {
+            name: None,
+            remote_user: Some("node".to_string()),
+            workspace_folder: "/workspaces/demo".to_string(),
+            remote_env: BTreeMap::new(),
+            post_start_command: None,
+            post_attach_command: Some(LifecycleCommand::Shell("echo hi".to_string())),
+        };
+        let spec = container_spec(
+            &config,
+            Path::new("/home/me/demo"),
+            Path::new("/home/me/demo/.devcontainer/devcontainer.json"),
+            "node:20",
+            &metadata,
+        )
+        .unwrap();
+
+        assert_eq!(spec["Env"], json!(["CI=1"]));
+        assert_eq!(spec["Entrypoint"], json!(["/bin/sh"]));
+        let host = &spec["HostConfig"];
+        assert_eq!(host["Mounts"][0]["Source"], "/home/me/demo");
+        assert_eq!(host["Mounts"][0]["Target"], "/workspaces/demo");
+        assert_eq!(host["Mounts"][1]["Type"], "volume");
+        assert_eq!(host["PortBindings"]["3000/tcp"][0]["HostPort"], "3000");
+        assert_eq!(host["PortBindings"].as_object().unwrap().len(), 1);
+        assert_eq!(host["CapAdd"], json!(["SYS_PTRACE"]));
+        assert_eq!(host["SecurityOpt"], json!(["seccomp=unconfined"]));
+        assert_eq!(host["Init"], true);
+
+        let stored: ContainerMetadata =
+            serde_json::from_str(spec["Labels"][METADATA_LABEL].as_str().unwrap()).unwrap();
+        assert_eq!(stored.workspace_folder, "/workspaces/demo");
+        assert!(stored.post_attach_command.is_some());
+    }
+
+    #[test]
+    fn lists_containers_from_engine_summaries() {
+        let summary = json!({
+            "Id": "abc123",
+            "Names": ["/cortex-devcontainer-demo-0a1b2c"],
+            "Image": "node:20",
+            "State": "running",
+            "Ports": [{ "PrivatePort": 3000, "PublicPort": 3000 }, { "PrivatePort": 3000, "PublicPort": 3000 }],
+            "Labels": {
+                LOCAL_FOLDER_LABEL: "/home/me/demo",
+                METADATA_LABEL: r#"{"name":"Demo","workspaceFolder":"/workspaces/demo"}"#
+            }
+        });
+        let container = DevContainer::from_summary(&summary);
+        assert_eq!(container.name, "Demo");
+        assert_eq!(container.status, ContainerStatus::Running);
+        assert_eq!(container.ports, vec![3000]);
+        assert_eq!(container.workspace_path.as_deref(), Some("/home/me/demo"));
+        assert_eq!(
+            serde_json::to_value(&container).unwrap()["status"],
+            "running"
+        );
+
+        let tag = image_tag(
+            Path::new("/home/me/My App"),
+            Path::new("/home/me/My App/.devcontainer.json"),
+        );
+        assert!(tag.starts_with("cortex-devcontainer-my-app-"));
+        assert_eq!(tag.len(), "cortex-devcontainer-my-app-".len() + 12);
+    }
+}
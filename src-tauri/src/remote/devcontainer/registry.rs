@@ -0,0 +1,402 @@
+//! OCI registry access for Features and Templates
+//!
+//! Features and Templates are published as single-layer OCI artifacts, and
+//! each collection (`ghcr.io/devcontainers/features`) has a `latest`
+//! artifact listing its members. Public registries hand out anonymous pull
+//! tokens through the standard `WWW-Authenticate: Bearer` challenge.
+
+use std::io::Read;
+
+use reqwest::StatusCode;
+use reqwest::header::{ACCEPT, AUTHORIZATION, WWW_AUTHENTICATE};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use super::config::{DevContainerConfig, strip_jsonc};
+
+const MANIFEST_MEDIA_TYPE: &str = "application/vnd.oci.image.manifest.v1+json";
+const LAYER_MEDIA_TYPE: &str = "application/vnd.devcontainers.layer.v1+tar";
+
+/// Collections listed in the Features and Templates pickers
+const FEATURES_COLLECTION: &str = "ghcr.io/devcontainers/features";
+const TEMPLATES_COLLECTION: &str = "ghcr.io/devcontainers/templates";
+
+/// Reference to an artifact, e.g. `ghcr.io/devcontainers/features/node:1`
+#[derive(Debug, Clone, PartialEq)]
+pub struct OciRef {
+    pub registry: String,
+    pub repository: String,
+    /// Tag or digest
+    pub reference: String,
+}
+
+impl OciRef {
+    pub fn parse(id: &str) -> Result<Self, String> {
+        let (registry, rest) = id
+            .split_once('/')
+            .filter(|(registry, _)| {
+                !registry.starts_with('.') && (registry.contains('.') || registry.contains(':'))
+            })
+            .ok_or_else(|| format!("Not a registry reference: {}", id))?;
+        let (repository, reference) = match rest.split_once('@') {
+            Some((repository, digest)) => (repository, digest),
+            None => match rest.rsplit_once(':') {
+                Some((repository, tag)) if !tag.contains('/') => (repository, tag),
+                _ => (rest, "latest"),
+            },
+        };
+        Ok(Self {
+            registry: registry.to_string(),
+            repository: repository.to_lowercase(),
+            reference: reference.to_string(),
+        })
+    }
+}
+
+/// Bearer challenge parameters: realm, service and scope
+fn parse_bearer_challenge(header: &str) -> Option<(String, Option<String>, Option<String>)> {
+    let params = header.trim().strip_prefix("Bearer ")?;
+    let mut realm = None;
+    let mut service = None;
+    let mut scope = None;
+    let mut rest = params;
+    while let Some((key, after)) = rest.split_once("=\"") {
+        let (value, remainder) = after.split_once('"')?;
+        match key.trim().trim_start_matches(',').trim() {
+            "realm" => realm = Some(value.to_string()),
+            "service" => service = Some(value.to_string()),
+            "scope" => scope = Some(value.to_string()),
+            _ => {}
+        }
+        rest = remainder;
+    }
+    Some((realm?, service, scope))
+}
+
+struct RegistryClient {
+    http: reqwest::Client,
+    token: Option<String>,
+}
+
+impl RegistryClient {
+    fn new() -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            token: None,
+        }
+    }
+
+    async fn get(&mut self, url: &str, accept: &str) -> Result<Vec<u8>, String> {
+        for attempt in 0..2 {
+            let mut request = self.http.get(url).header(ACCEPT, accept);
+            if let Some(token) = &self.token {
+                request = request.header(AUTHORIZATION, format!("Bearer {}", token));
+            }
+            let response = request
+                .send()
+                .await
+                .map_err(|e| format!("Failed to fetch {}: {}", url, e))?;
+            if response.status() == StatusCode::UNAUTHORIZED && attempt == 0 {
+                let challenge = response
+                    .headers()
+                    .get(WWW_AUTHENTICATE)
+                    .and_then(|h| h.to_str().ok())
+                    .and_then(parse_bearer_challenge)
+                    .ok_or_else(|| format!("Registry requires credentials for {}", url))?;
+                self.token = Some(self.fetch_token(challenge).await?);
+                continue;
+            }
+            if !response.status().is_success() {
+                return Err(format!("Failed to fetch {}: {}", url, response.status()));
+            }
+            return response
+                .bytes()
+                .await
+                .map(|b| b.to_vec())
+                .map_err(|e| format!("Failed to fetch {}: {}", url, e));
+        }
+        Err(format!("Registry refused access to {}", url))
+    }
+
+    async fn fetch_token(
+        &self,
+        (realm, service, scope): (String, Option<String>, Option<String>),
+    ) -> Result<String, String> {
+        let mut query = Vec::new();
+        if let Some(service) = service {
+            query.push(("service", service));
+        }
+        if let Some(scope) = scope {
+            query.push(("scope", scope));
+        }
+        let body: Value = self
+            .http
+            .get(&realm)
+            .query(&query)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| format!("Failed to get registry token: {}", e))?
+            .json()
+            .await
+            .map_err(|e| format!("Invalid registry token response: {}", e))?;
+        body["token"]
+            .as_str()
+            .or_else(|| body["access_token"].as_str())
+            .map(str::to_string)
+            .ok_or_else(|| "Registry token response has no token".to_string())
+    }
+}
+
+/// Download an artifact's layer, an uncompressed tar
+pub async fn fetch_artifact(oci_ref: &OciRef) -> Result<Vec<u8>, String> {
+    let mut client = RegistryClient::new();
+    let base = format!("https://{}/v2/{}", oci_ref.registry, oci_ref.repository);
+    let manifest = client
+        .get(
+            &format!("{}/manifests/{}", base, oci_ref.reference),
+            MANIFEST_MEDIA_TYPE,
+        )
+        .await?;
+    let manifest: Value = serde_json::from_slice(&manifest)
+        .map_err(|e| format!("Invalid manifest for {}: {}", oci_ref.repository, e))?;
+    let layers = manifest["layers"].as_array().cloned().unwrap_or_default();
+    let layer = layers
+        .iter()
+        .find(|l| l["mediaType"] == LAYER_MEDIA_TYPE)
+        .or_else(|| layers.first())
+        .ok_or_else(|| format!("Artifact {} has no layers", oci_ref.repository))?;
+    let digest = layer["digest"]
+        .as_str()
+        .ok_or_else(|| format!("Artifact {} layer has no digest", oci_ref.repository))?;
+
+    let blob = client
+        .get(&format!("{}/blobs/{}", base, digest), "*/*")
+        .await?;
+    if let Some(expected) = digest.strip_prefix("sha256:") {
+        let actual: String = Sha256::digest(&blob)
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        if actual != expected {
+            return Err(format!("Digest mismatch for {}", oci_ref.repository));
+        }
+    }
+    Ok(blob)
+}
+
+/// Contents of one file in a tar archive
+pub fn read_tar_file(archive: &[u8], name: &str) -> Result<Option<Vec<u8>>, String> {
+    let mut archive = tar::Archive::new(archive);
+    let entries = archive
+        .entries()
+        .map_err(|e| format!("Invalid archive: {}", e))?;
+    for entry in entries {
+        let mut entry = entry.map_err(|e| format!("Invalid archive: {}", e))?;
+        let path = entry
+            .path()
+            .map_err(|e| format!("Invalid archive: {}", e))?
+            .to_string_lossy()
+            .trim_start_matches("./")
+            .to_string();
+        if path == name {
+            let mut data = Vec::new();
+            entry
+                .read_to_end(&mut data)
+                .map_err(|e| format!("Invalid archive: {}", e))?;
+            return Ok(Some(data));
+        }
+    }
+    Ok(None)
+}
+
+async fn fetch_collection(collection: &str) -> Result<Value, String> {
+    let archive = fetch_artifact(&OciRef::parse(&format!("{}:latest", collection))?).await?;
+    let data = read_tar_file(&archive, "devcontainer-collection.json")?
+        .ok_or_else(|| format!("{} has no collection metadata", collection))?;
+    serde_json::from_slice(&data).map_err(|e| format!("Invalid collection {}: {}", collection, e))
+}
+
+/// Major version tag of a published member, e.g. `1` for `1.4.2`
+fn major_tag(member: &Value) -> String {
+    member["version"]
+        .as_str()
+        .and_then(|v| v.split('.').next())
+        .unwrap_or("latest")
+        .to_string()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DevContainerFeature {
+    pub id: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub options: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DevContainerTemplate {
+    pub id: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config: Option<DevContainerConfig>,
+}
+
+/// Features of the public collection, referenced by major version
+pub async fn list_features() -> Result<Vec<DevContainerFeature>, String> {
+    let collection = fetch_collection(FEATURES_COLLECTION).await?;
+    Ok(collection["features"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|feature| {
+            let id = feature["id"].as_str()?;
+            Some(DevContainerFeature {
+                id: format!("{}/{}:{}", FEATURES_COLLECTION, id, major_tag(feature)),
+                name: feature["name"].as_str().unwrap_or(id).to_string(),
+                description: feature["description"].as_str().map(str::to_string),
+                version: feature["version"].as_str().map(str::to_string),
+                options: feature.get("options").cloned(),
+            })
+        })
+        .collect())
+}
+
+/// A template's devcontainer.json with its options at their defaults
+fn template_config(archive: &[u8], options: &Value) -> Result<Option<DevContainerConfig>, String> {
+    let data = match read_tar_file(archive, ".devcontainer/devcontainer.json")? {
+        Some(data) => data,
+        None => match read_tar_file(archive, ".devcontainer.json")? {
+            Some(data) => data,
+            None => return Ok(None),
+        },
+    };
+    let mut text = strip_jsonc(&String::from_utf8_lossy(&data));
+    if let Some(options) = options.as_object() {
+        for (name, option) in options {
+            let default = match &option["default"] {
+                Value::String(s) => s.clone(),
+                Value::Null => continue,
+                other => other.to_string(),
+            };
+            text = text.replace(&format!("${{templateOption:{}}}", name), &default);
+        }
+    }
+    let mut config: DevContainerConfig =
+        serde_json::from_str(&text).map_err(|e| format!("Invalid template config: {}", e))?;
+    config.path = Some(".devcontainer/devcontainer.json".to_string());
+    Ok(Some(config))
+}
+
+/// Templates of the public collection with their default configs
+///
+/// Templates that fail to download are listed without a config.
+pub async fn list_templates() -> Result<Vec<DevContainerTemplate>, String> {
+    let collection = fetch_collection(TEMPLATES_COLLECTION).await?;
+    let members: Vec<Value> = collection["templates"]
+        .as_array()
+        .cloned()
+        .unwrap_or_default();
+    let templates = members.into_iter().filter_map(|template| {
+        let id = template["id"].as_str()?.to_string();
+        Some(async move {
+            let reference = format!("{}/{}:{}", TEMPLATES_COLLECTION, id, major_tag(&template));
+            let config = match OciRef::parse(&reference) {
+                Ok(oci_ref) => match fetch_artifact(&oci_ref).await {
+                    Ok(archive) => {
+                        template_config(&archive, &template["options"]).unwrap_or_else(|e| {
+                            tracing::warn!("Skipping config of template {}: {}", id, e);
+                            None
+                        })
+                    }
+                    Err(e) => {
+                        tracing::warn!("Failed to fetch template {}: {}", id, e);
+                        None
+                    }
+                },
+                Err(_) => None,
+            };
+            DevContainerTemplate {
+                name: template["name"].as_str().unwrap_or(&id).to_string(),
+                description: template["description"].as_str().map(str::to_string),
+                category: template["keywords"][0].as_str().map(str::to_string),
+                config,
+                id: reference,
+            }
+        })
+    });
+    Ok(futures::future::join_all(templates).await)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_artifact_references() {
+        assert_eq!(
+            OciRef::parse("ghcr.io/devcontainers/features/node:1").unwrap(),
+            OciRef {
+                registry: "ghcr.io".to_string(),
+                repository: "devcontainers/features/node".to_string(),
+                reference: "1".to_string(),
+            }
+        );
+        let pinned = OciRef::parse("localhost:5000/f/go@sha256:abc").unwrap();
+        assert_eq!(pinned.registry, "localhost:5000");
+        assert_eq!(pinned.reference, "sha256:abc");
+        assert_eq!(OciRef::parse("ghcr.io/x/y").unwrap().reference, "latest");
+        assert!(OciRef::parse("./local-feature").is_err());
+    }
+
+    #[test]
+    fn parses_bearer_challenges() {
+        let (realm, service, scope) = parse_bearer_challenge(
+            r#"Bearer realm="https://ghcr.io/token",service="ghcr.io",scope="repository:a/b:pull""#,
+        )
+        .unwrap();
+        assert_eq!(realm, "https://ghcr.io/token");
+        assert_eq!(service.as_deref(), Some("ghcr.io"));
+        assert_eq!(scope.as_deref(), Some("repository:a/b:pull"));
+        assert!(parse_bearer_challenge("Basic realm=\"x\"").is_none());
+    }
+
+    #[test]
+    fn fills_template_options_with_defaults() {
+        let mut builder = tar::Builder::new(Vec::new());
+        let config = br#"{ "image": "mcr.microsoft.com/devcontainers/rust:${templateOption:imageVariant}", // pick
+            "features": {}, }"#;
+        let mut header = tar::Header::new_gnu();
+        header.set_size(config.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(
+                &mut header,
+                "./.devcontainer/devcontainer.json",
+                &config[..],
+            )
+            .unwrap();
+        let archive = builder.into_inner().unwrap();
+
+        let options =
+            serde_json::json!({ "imageVariant": { "type": "string", "default": "bookworm" } });
+        let config = template_config(&archive, &options).unwrap().unwrap();
+        assert_eq!(
+            config.image.as_deref(),
+            Some("mcr.microsoft.com/devcontainers/rust:bookworm")
+        );
+    }
+}
@@ -24,6 +24,34 @@ pub enum RemoteError {
     InvalidPath(String),
     #[error("Keyring error: {0}")]
     KeyringError(String),
+    #[cfg(feature = "remote-ssh")]
+    #[error("Host key for {host}:{port} is not trusted yet ({key_type} {fingerprint})")]
+    HostKeyUnknown {
+        host: String,
+        port: u16,
+        key_type: String,
+        fingerprint: String,
+    },
+    #[cfg(feature = "remote-ssh")]
+    #[error(
+        "Host key mismatch for {host}:{port}: the server presented {key_type} {fingerprint}, \
+         which differs from the key recorded in {recorded_in}. Someone may be intercepting \
+         the connection; remove the old key only if the server's key was changed on purpose"
+    )]
+    HostKeyMismatch {
+        host: String,
+        port: u16,
+        key_type: String,
+        fingerprint: String,
+        recorded_in: String,
+    },
+    #[cfg(feature = "remote-ssh")]
+    #[error("Host key {fingerprint} of {host}:{port} is marked @revoked in known_hosts")]
+    HostKeyRevoked {
+        host: String,
+        port: u16,
+        fingerprint: String,
+    },
 }
 
 impl From<RemoteError> for String {
@@ -0,0 +1,283 @@
+//! Long-running remote processes over an SSH exec channel.
+//!
+//! `ssh2` channels cannot be read and written from different threads without
+//! blocking each other on the session lock, so a single pump thread owns the
+//! channel in non-blocking mode and moves bytes between it and in-process
+//! pipes. Callers get plain `Read`/`Write` handles for stdin, stdout and stderr.
+
+use std::io::{self, ErrorKind, Read, Write};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::thread;
+use std::time::Duration;
+
+use ssh2::{Channel, Session};
+use tracing::{debug, warn};
+
+use super::error::RemoteError;
+
+/// Longest the pump sleeps when the channel is idle
+const MAX_IDLE_SLEEP: Duration = Duration::from_millis(20);
+
+/// Reading end of an in-process pipe
+pub struct PipeReader {
+    rx: Receiver<Vec<u8>>,
+    buf: Vec<u8>,
+    pos: usize,
+}
+
+impl Read for PipeReader {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        while self.pos >= self.buf.len() {
+            match self.rx.recv() {
+                Ok(chunk) => {
+                    self.buf = chunk;
+                    self.pos = 0;
+                }
+                // Sender gone: end of stream
+                Err(_) => return Ok(0),
+            }
+        }
+        let n = out.len().min(self.buf.len() - self.pos);
+        out[..n].copy_from_slice(&self.buf[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// Writing end of an in-process pipe
+pub struct PipeWriter {
+    tx: Sender<Vec<u8>>,
+}
+
+impl Write for PipeWriter {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        if data.is_empty() {
+            return Ok(0);
+        }
+        self.tx
+            .send(data.to_vec())
+            .map_err(|_| io::Error::new(ErrorKind::BrokenPipe, "remote process exited"))?;
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn pipe() -> (PipeWriter, PipeReader) {
+    let (tx, rx) = mpsc::channel();
+    (
+        PipeWriter { tx },
+        PipeReader {
+            rx,
+            buf: Vec::new(),
+            pos: 0,
+        },
+    )
+}
+
+/// Handle used to stop a remote process
+#[derive(Clone)]
+pub struct ExecHandle {
+    closed: Arc<AtomicBool>,
+}
+
+impl ExecHandle {
+    /// Close the channel; the remote process sees EOF on stdin and a hangup
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::SeqCst)
+    }
+}
+
+/// Pipes connected to a process running on the remote host
+pub struct RemoteProcess {
+    pub stdin: PipeWriter,
+    pub stdout: PipeReader,
+    pub stderr: PipeReader,
+    pub handle: ExecHandle,
+}
+
+/// Start `command` on the remote host
+///
+/// The session should be dedicated to this process: it is switched to
+/// non-blocking mode and driven by the pump thread until the process exits or
+/// the handle is closed.
+pub fn spawn(session: Session, command: &str) -> Result<RemoteProcess, RemoteError> {
+    let mut channel = session.channel_session()?;
+    channel.exec(command)?;
+    session.set_blocking(false);
+
+    let (stdin, stdin_rx) = {
+        let (tx, rx) = mpsc::channel();
+        (PipeWriter { tx }, rx)
+    };
+    let (stdout_tx, stdout) = pipe();
+    let (stderr_tx, stderr) = pipe();
+    let handle = ExecHandle {
+        closed: Arc::new(AtomicBool::new(false)),
+    };
+
+    let pump_handle = handle.clone();
+    let command = command.to_string();
+    thread::spawn(move || {
+        pump(
+            session,
+            channel,
+            stdin_rx,
+            stdout_tx,
+            stderr_tx,
+            pump_handle,
+        );
+        debug!("Remote process exited: {}", command);
+    });
+
+    Ok(RemoteProcess {
+        stdin,
+        stdout,
+        stderr,
+        handle,
+    })
+}
+
+/// Read whatever is available from a non-blocking stream
+///
+/// Returns `None` at end of stream.
+fn read_available(stream: &mut impl Read, out: &PipeWriter, buf: &mut [u8]) -> Option<bool> {
+    match stream.read(buf) {
+        Ok(0) => None,
+        Ok(n) => {
+            // The reader may have gone away; keep draining so the remote side
+            // never stalls on a full window.
+            let _ = out.tx.send(buf[..n].to_vec());
+            Some(true)
+        }
+        Err(e) if e.kind() == ErrorKind::WouldBlock => Some(false),
+        Err(e) => {
+            warn!("Remote process stream error: {}", e);
+            None
+        }
+    }
+}
+
+fn pump(
+    session: Session,
+    mut channel: Channel,
+    stdin_rx: Receiver<Vec<u8>>,
+    stdout_tx: PipeWriter,
+    stderr_tx: PipeWriter,
+    handle: ExecHandle,
+) {
+    let mut pending: Vec<u8> = Vec::new();
+    let mut stdin_open = true;
+    let mut stdout_open = true;
+    let mut stderr_open = true;
+    let mut buf = vec![0u8; 32 * 1024];
+    let mut idle_sleep = Duration::from_millis(1);
+
+    while !handle.is_closed() && (stdout_open || stderr_open) {
+        let mut progressed = false;
+
+        // stdin
+        if stdin_open && pending.is_empty() {
+            match stdin_rx.try_recv() {
+                Ok(chunk) => pending = chunk,
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Disconnected) => {
+                    stdin_open = false;
+                    while channel.send_eof().is_err_and(|e| is_would_block(&e)) {
+                        thread::sleep(idle_sleep);
+                    }
+                }
+            }
+        }
+        if !pending.is_empty() {
+            match channel.write(&pending) {
+                Ok(n) => {
+                    pending.drain(..n);
+                    progressed = true;
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+                Err(e) => {
+                    warn!("Failed to write to remote process: {}", e);
+                    break;
+                }
+            }
+        }
+
+        // stdout
+        if stdout_open {
+            match read_available(&mut channel, &stdout_tx, &mut buf) {
+                Some(read) => progressed |= read,
+                None => stdout_open = false,
+            }
+        }
+
+        // stderr
+        if stderr_open {
+            match read_available(&mut channel.stderr(), &stderr_tx, &mut buf) {
+                Some(read) => progressed |= read,
+                None => stderr_open = false,
+            }
+        }
+
+        if progressed {
+            idle_sleep = Duration::from_millis(1);
+        } else {
+            thread::sleep(idle_sleep);
+            idle_sleep = (idle_sleep * 2).min(MAX_IDLE_SLEEP);
+        }
+    }
+
+    session.set_blocking(true);
+    let _ = channel.close();
+    let _ = channel.wait_close();
+    handle.close();
+}
+
+fn is_would_block(e: &ssh2::Error) -> bool {
+    e.code() == ssh2::ErrorCode::Session(-37)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pipe_preserves_bytes_and_signals_eof() {
+        let (mut writer, mut reader) = pipe();
+        writer.write_all(b"Content-Length: 2\r\n\r\n").unwrap();
+        writer.write_all(b"{}").unwrap();
+        drop(writer);
+
+        let mut received = Vec::new();
+        reader.read_to_end(&mut received).unwrap();
+        assert_eq!(received, b"Content-Length: 2\r\n\r\n{}");
+    }
+
+    #[test]
+    fn small_reads_drain_large_chunks() {
+        let (mut writer, mut reader) = pipe();
+        writer.write_all(b"abcdef").unwrap();
+        let mut out = [0u8; 4];
+        assert_eq!(reader.read(&mut out).unwrap(), 4);
+        assert_eq!(&out, b"abcd");
+        assert_eq!(reader.read(&mut out).unwrap(), 2);
+        assert_eq!(&out[..2], b"ef");
+    }
+
+    #[test]
+    fn writing_after_reader_dropped_is_broken_pipe() {
+        let (mut writer, reader) = pipe();
+        drop(reader);
+        let err = writer.write(b"x").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::BrokenPipe);
+    }
+}
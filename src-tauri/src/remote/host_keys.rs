@@ -0,0 +1,414 @@
+//! SSH host key verification.
+//!
+//! After the handshake the server's host key is checked against OpenSSH's
+//! `known_hosts` files (plain and hashed entries) and Cortex's own
+//! trusted-hosts store. A key recorded for the host that does not match is a
+//! hard failure; a host nobody has recorded yet is reported to the UI with
+//! its fingerprint so the user can trust it on first use.
+
+use std::path::PathBuf;
+
+#[cfg(feature = "remote-ssh")]
+use base64::Engine as _;
+#[cfg(feature = "remote-ssh")]
+use base64::engine::general_purpose::{STANDARD, STANDARD_NO_PAD};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "remote-ssh")]
+use sha2::{Digest, Sha256};
+#[cfg(feature = "remote-ssh")]
+use ssh2::{CheckResult, KnownHostFileKind, Session};
+#[cfg(feature = "remote-ssh")]
+use tauri::{AppHandle, Emitter};
+use tracing::info;
+#[cfg(feature = "remote-ssh")]
+use tracing::warn;
+
+use super::connection::set_file_permissions;
+use super::error::RemoteError;
+
+/// Name under which Cortex's own trusted keys are reported in mismatch errors
+#[cfg(feature = "remote-ssh")]
+const TRUSTED_HOSTS_SOURCE: &str = "Cortex trusted hosts";
+
+/// A host key the user accepted from the UI
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TrustedHost {
+    pub host: String,
+    pub port: u16,
+    pub key_type: String,
+    /// OpenSSH style `SHA256:...` fingerprint
+    pub fingerprint: String,
+    pub added_at: i64,
+}
+
+/// Payload of `remote:host-key-unknown`
+#[cfg(feature = "remote-ssh")]
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct HostKeyPrompt {
+    host: String,
+    port: u16,
+    key_type: String,
+    fingerprint: String,
+}
+
+/// OpenSSH style SHA256 fingerprint of a raw host key blob
+#[cfg(feature = "remote-ssh")]
+fn fingerprint(key: &[u8]) -> String {
+    format!("SHA256:{}", STANDARD_NO_PAD.encode(Sha256::digest(key)))
+}
+
+/// Algorithm name a host key blob starts with (`ssh-ed25519`, ...)
+#[cfg(feature = "remote-ssh")]
+fn key_type_name(key: &[u8]) -> String {
+    key.get(..4)
+        .map(|len| u32::from_be_bytes([len[0], len[1], len[2], len[3]]) as usize)
+        .and_then(|len| key.get(4..4 + len))
+        .and_then(|name| std::str::from_utf8(name).ok())
+        .unwrap_or("unknown")
+        .to_string()
+}
+
+/// Whether a known_hosts file marks `key` as `@revoked`
+#[cfg(feature = "remote-ssh")]
+fn is_revoked(known_hosts: &str, key: &[u8]) -> bool {
+    known_hosts.lines().any(|line| {
+        let mut fields = line.split_whitespace();
+        fields.next() == Some("@revoked")
+            && fields
+                .nth(2)
+                .and_then(|encoded| STANDARD.decode(encoded).ok())
+                .is_some_and(|revoked| revoked == key)
+    })
+}
+
+/// known_hosts files consulted, most specific first
+#[cfg(feature = "remote-ssh")]
+fn known_hosts_files() -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    if let Some(home) = dirs::home_dir() {
+        files.push(home.join(".ssh").join("known_hosts"));
+        files.push(home.join(".ssh").join("known_hosts2"));
+    }
+    #[cfg(unix)]
+    files.push(PathBuf::from("/etc/ssh/ssh_known_hosts"));
+    files
+}
+
+/// Look a host up in the contents of a known_hosts file
+///
+/// Entries are fed to libssh2 one line at a time, which matches hashed
+/// (`|1|salt|hash`) and `[host]:port` entries, so a line libssh2 cannot
+/// parse (certificate authorities, unsupported key types) is skipped
+/// instead of hiding the rest of the file. Only entries of the presented
+/// key's type are loaded: a host recorded with an RSA key that negotiates
+/// Ed25519 is unknown, not mismatched.
+#[cfg(feature = "remote-ssh")]
+fn check_known_hosts(
+    session: &Session,
+    known_hosts: &str,
+    host: &str,
+    port: u16,
+    key: &[u8],
+) -> Result<CheckResult, RemoteError> {
+    let key_type = key_type_name(key);
+    let mut entries = session.known_hosts()?;
+    for line in known_hosts.lines() {
+        let line = line.trim();
+        if line.starts_with('#') || line.starts_with('@') {
+            continue;
+        }
+        if line.split_whitespace().nth(1) == Some(key_type.as_str()) {
+            let _ = entries.read_str(line, KnownHostFileKind::OpenSSH);
+        }
+    }
+    Ok(entries.check_port(host, port, key))
+}
+
+/// Check the host key of a freshly handshaken session
+///
+/// Fails with `HostKeyMismatch` or `HostKeyRevoked` when a recorded key
+/// disagrees with the server, and with `HostKeyUnknown` when the host has
+/// not been recorded anywhere.
+#[cfg(feature = "remote-ssh")]
+pub fn verify_host_key(session: &Session, host: &str, port: u16) -> Result<(), RemoteError> {
+    let (key, _) = session.host_key().ok_or_else(|| {
+        RemoteError::ConnectionFailed("Server did not present a host key".to_string())
+    })?;
+    let key_type = key_type_name(key);
+    let fingerprint = fingerprint(key);
+    let mismatch = |recorded_in: String| RemoteError::HostKeyMismatch {
+        host: host.to_string(),
+        port,
+        key_type: key_type.clone(),
+        fingerprint: fingerprint.clone(),
+        recorded_in,
+    };
+
+    let mut known = false;
+    for path in known_hosts_files() {
+        let Ok(contents) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        if is_revoked(&contents, key) {
+            return Err(RemoteError::HostKeyRevoked {
+                host: host.to_string(),
+                port,
+                fingerprint,
+            });
+        }
+        match check_known_hosts(session, &contents, host, port, key)? {
+            CheckResult::Match => known = true,
+            CheckResult::Mismatch => return Err(mismatch(path.display().to_string())),
+            CheckResult::NotFound | CheckResult::Failure => {}
+        }
+    }
+
+    match find_trusted(&load_trusted_hosts()?, host, port, &key_type) {
+        Some(trusted) if trusted.fingerprint == fingerprint => known = true,
+        Some(_) => return Err(mismatch(TRUSTED_HOSTS_SOURCE.to_string())),
+        None => {}
+    }
+
+    if known {
+        Ok(())
+    } else {
+        Err(RemoteError::HostKeyUnknown {
+            host: host.to_string(),
+            port,
+            key_type,
+            fingerprint,
+        })
+    }
+}
+
+/// Ask the UI to confirm an unknown host key
+///
+/// Emits `remote:host-key-unknown` for `HostKeyUnknown` errors; the UI
+/// answers by calling `remote_trust_host` and connecting again.
+#[cfg(feature = "remote-ssh")]
+pub fn prompt_if_unknown(app: &AppHandle, error: &RemoteError) {
+    if let RemoteError::HostKeyUnknown {
+        host,
+        port,
+        key_type,
+        fingerprint,
+    } = error
+    {
+        let prompt = HostKeyPrompt {
+            host: host.clone(),
+            port: *port,
+            key_type: key_type.clone(),
+            fingerprint: fingerprint.clone(),
+        };
+        if let Err(e) = app.emit("remote:host-key-unknown", prompt) {
+            warn!("Failed to emit host key prompt: {}", e);
+        }
+    }
+}
+
+// ============================================================================
+// Trusted hosts store
+// ============================================================================
+
+fn trusted_hosts_path() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("Cortex-desktop")
+        .join("trusted_hosts.json")
+}
+
+fn load_trusted_hosts() -> Result<Vec<TrustedHost>, RemoteError> {
+    let path = trusted_hosts_path();
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = std::fs::read_to_string(&path)?;
+    serde_json::from_str(&content)
+        .map_err(|e| RemoteError::IoError(std::io::Error::new(std::io::ErrorKind::InvalidData, e)))
+}
+
+fn save_trusted_hosts(hosts: &[TrustedHost]) -> Result<(), RemoteError> {
+    let path = trusted_hosts_path();
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let content = serde_json::to_string_pretty(hosts).map_err(|e| {
+        RemoteError::IoError(std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    })?;
+    std::fs::write(&path, content)?;
+    set_file_permissions(&path)
+}
+
+#[cfg(feature = "remote-ssh")]
+fn find_trusted<'a>(
+    hosts: &'a [TrustedHost],
+    host: &str,
+    port: u16,
+    key_type: &str,
+) -> Option<&'a TrustedHost> {
+    hosts
+        .iter()
+        .find(|h| h.host.eq_ignore_ascii_case(host) && h.port == port && h.key_type == key_type)
+}
+
+/// Record `entry`, replacing any key of the same type for that host
+fn insert_trusted(hosts: &mut Vec<TrustedHost>, entry: TrustedHost) {
+    hosts.retain(|h| {
+        !(h.host.eq_ignore_ascii_case(&entry.host)
+            && h.port == entry.port
+            && h.key_type == entry.key_type)
+    });
+    hosts.push(entry);
+}
+
+/// Forget keys of a host; every key type unless `key_type` is given
+fn remove_trusted(hosts: &mut Vec<TrustedHost>, host: &str, port: u16, key_type: Option<&str>) {
+    hosts.retain(|h| {
+        !(h.host.eq_ignore_ascii_case(host)
+            && h.port == port
+            && key_type.is_none_or(|t| h.key_type == t))
+    });
+}
+
+// ============================================================================
+// Tauri Commands
+// ============================================================================
+
+#[tauri::command]
+pub async fn remote_trusted_hosts_list() -> Result<Vec<TrustedHost>, String> {
+    Ok(load_trusted_hosts()?)
+}
+
+#[tauri::command]
+pub async fn remote_trust_host(
+    host: String,
+    port: u16,
+    key_type: String,
+    fingerprint: String,
+) -> Result<TrustedHost, String> {
+    if !fingerprint.starts_with("SHA256:") {
+        return Err(format!("Unsupported fingerprint: {}", fingerprint));
+    }
+    let entry = TrustedHost {
+        host,
+        port,
+        key_type,
+        fingerprint,
+        added_at: chrono::Utc::now().timestamp_millis(),
+    };
+    let mut hosts = load_trusted_hosts()?;
+    insert_trusted(&mut hosts, entry.clone());
+    save_trusted_hosts(&hosts)?;
+    info!(
+        "Trusted {} key {} for {}:{}",
+        entry.key_type, entry.fingerprint, entry.host, entry.port
+    );
+    Ok(entry)
+}
+
+#[tauri::command]
+pub async fn remote_untrust_host(
+    host: String,
+    port: u16,
+    key_type: Option<String>,
+) -> Result<(), String> {
+    let mut hosts = load_trusted_hosts()?;
+    remove_trusted(&mut hosts, &host, port, key_type.as_deref());
+    Ok(save_trusted_hosts(&hosts)?)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    /// `ssh-ed25519` blob with the key bytes 0..32
+    #[cfg(feature = "remote-ssh")]
+    const KEY: &str = "AAAAC3NzaC1lZDI1NTE5AAAAIAABAgMEBQYHCAkKCwwNDg8QERITFBUWFxgZGhscHR4f";
+
+    fn trusted(host: &str, key_type: &str, fingerprint: &str) -> TrustedHost {
+        TrustedHost {
+            host: host.to_string(),
+            port: 22,
+            key_type: key_type.to_string(),
+            fingerprint: fingerprint.to_string(),
+            added_at: 0,
+        }
+    }
+
+    #[cfg(feature = "remote-ssh")]
+    #[test]
+    fn fingerprints_like_openssh() {
+        let key = STANDARD.decode(KEY).unwrap();
+        assert_eq!(key_type_name(&key), "ssh-ed25519");
+        assert_eq!(
+            fingerprint(&key),
+            "SHA256:ZkAslGjFiUHdGf/WUL8rQvkib4PTvQatUV0OUQSncCA"
+        );
+        assert_eq!(key_type_name(&[0, 0, 0, 9, b'x']), "unknown");
+    }
+
+    #[cfg(feature = "remote-ssh")]
+    #[test]
+    fn detects_revoked_keys() {
+        let key = STANDARD.decode(KEY).unwrap();
+        let known_hosts = format!("# comment\n@revoked * ssh-ed25519 {}\n", KEY);
+        assert!(is_revoked(&known_hosts, &key));
+        assert!(!is_revoked(&format!("host ssh-ed25519 {}\n", KEY), &key));
+    }
+
+    #[test]
+    fn trusting_replaces_keys_of_the_same_type() {
+        let mut hosts = Vec::new();
+        insert_trusted(&mut hosts, trusted("build", "ssh-ed25519", "SHA256:old"));
+        insert_trusted(&mut hosts, trusted("build", "ssh-rsa", "SHA256:rsa"));
+        insert_trusted(&mut hosts, trusted("BUILD", "ssh-ed25519", "SHA256:new"));
+        assert_eq!(hosts.len(), 2);
+        assert_eq!(hosts[1].fingerprint, "SHA256:new");
+        assert_eq!(hosts[1].host, "BUILD");
+
+        remove_trusted(&mut hosts, "build", 22, Some("ssh-rsa"));
+        assert_eq!(hosts.len(), 1);
+        remove_trusted(&mut hosts, "build", 22, None);
+        assert!(hosts.is_empty());
+    }
+
+    #[cfg(feature = "remote-ssh")]
+    #[test]
+    fn matches_hashed_known_hosts_entries() {
+        let session = Session::new().unwrap();
+        let key = STANDARD.decode(KEY).unwrap();
+        let mut other = key.clone();
+        other[20] ^= 0xff;
+        let known_hosts = format!(
+            "@cert-authority *.example.com ssh-ed25519 {key}\n\
+             |1|ZGVmZ2hpamtsbW5vcHFyc3R1dnc=|YkF9uaH5tkTLfgrBJ3TlWKm0abk= ssh-ed25519 {key}\n\
+             |1|ZGVmZ2hpamtsbW5vcHFyc3R1dnc=|Jmwh/RXWS8Bqh/llxUQnpLZQAMw= ssh-ed25519 {other}\n\
+             other.example.com ssh-rsa AAAAB3NzaC1yc2EAAAADAQABAAAAAQC7\n",
+            key = KEY,
+            other = STANDARD.encode(&other)
+        );
+
+        let check = |host: &str, port: u16, key: &[u8]| {
+            check_known_hosts(&session, &known_hosts, host, port, key).unwrap()
+        };
+        assert!(matches!(
+            check("build.example.com", 22, &key),
+            CheckResult::Match
+        ));
+        assert!(matches!(
+            check("build.example.com", 22, &other),
+            CheckResult::Mismatch
+        ));
+        assert!(matches!(
+            check("build.example.com", 2222, &other),
+            CheckResult::Match
+        ));
+        assert!(matches!(
+            check("other.example.com", 22, &key),
+            CheckResult::NotFound
+        ));
+    }
+}
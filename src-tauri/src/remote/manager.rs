@@ -1,5 +1,7 @@
 //! Remote connection manager - thread-safe connection pool.
 
+#[cfg(feature = "remote-ssh")]
+use base64::Engine as _;
 #[cfg(feature = "remote-ssh")]
 use ssh2::{Session, Sftp};
 use std::collections::HashMap;
@@ -21,11 +23,238 @@ use super::credentials::SecureAuthCredentials;
 use super::credentials::SecureSshCredentials;
 use super::error::RemoteError;
 #[cfg(feature = "remote-ssh")]
+use super::host_keys::verify_host_key;
+#[cfg(feature = "remote-ssh")]
+use super::proxy_jump;
+#[cfg(feature = "remote-ssh")]
+use super::ssh_config::{DEFAULT_SSH_PORT, HostConfig, SshConfig, local_user};
+#[cfg(feature = "remote-ssh")]
 use super::types::ConnectionStatus;
 use super::types::{
     AuthMethod, CommandResult, ConnectionInfo, ConnectionProfile, RemoteFileEntry, RemoteFileNode,
 };
 
+/// One SSH server on the way to a profile's host
+#[cfg(feature = "remote-ssh")]
+struct Hop {
+    host: String,
+    port: u16,
+    username: String,
+    auth_method: AuthMethod,
+    config: HostConfig,
+}
+
+/// TCP connect (or take a jump tunnel), handshake and check the host key
+#[cfg(feature = "remote-ssh")]
+fn handshake(stream: Option<TcpStream>, hop: &Hop) -> Result<Session, RemoteError> {
+    let tcp = match stream {
+        Some(stream) => stream,
+        None => TcpStream::connect((hop.host.as_str(), hop.port)).map_err(|e| {
+            RemoteError::ConnectionFailed(format!(
+                "TCP connection to {}:{} failed: {}",
+                hop.host, hop.port, e
+            ))
+        })?,
+    };
+
+    tcp.set_read_timeout(Some(std::time::Duration::from_secs(30)))
+        .map_err(RemoteError::IoError)?;
+    tcp.set_write_timeout(Some(std::time::Duration::from_secs(30)))
+        .map_err(RemoteError::IoError)?;
+
+    // Create SSH session
+    let mut session = Session::new().map_err(|e| {
+        RemoteError::ConnectionFailed(format!("Failed to create SSH session: {}", e))
+    })?;
+
+    session.set_tcp_stream(tcp);
+    session
+        .handshake()
+        .map_err(|e| RemoteError::ConnectionFailed(format!("SSH handshake failed: {}", e)))?;
+    verify_host_key(&session, &hop.host, hop.port)?;
+    Ok(session)
+}
+
+/// Public key blob of an identity file, read from its `.pub` companion
+#[cfg(feature = "remote-ssh")]
+fn public_key_blob(identity_file: &std::path::Path) -> Option<Vec<u8>> {
+    let mut path = identity_file.as_os_str().to_owned();
+    path.push(".pub");
+    let public_key = std::fs::read_to_string(path).ok()?;
+    let encoded = public_key.split_whitespace().nth(1)?;
+    base64::engine::general_purpose::STANDARD
+        .decode(encoded)
+        .ok()
+}
+
+/// Try the agent's identities; with `IdentitiesOnly` only those whose
+/// public key matches a configured `IdentityFile`
+#[cfg(feature = "remote-ssh")]
+fn authenticate_with_agent(session: &Session, hop: &Hop) -> Result<bool, RemoteError> {
+    let mut agent = session.agent().map_err(|e| {
+        RemoteError::AuthenticationFailed(format!("Agent connection failed: {}", e))
+    })?;
+    agent
+        .connect()
+        .map_err(|e| RemoteError::AuthenticationFailed(format!("Agent connect failed: {}", e)))?;
+    agent.list_identities().map_err(|e| {
+        RemoteError::AuthenticationFailed(format!("Agent list identities failed: {}", e))
+    })?;
+
+    let identities = agent.identities().map_err(|e| {
+        RemoteError::AuthenticationFailed(format!("Failed to get identities: {}", e))
+    })?;
+
+    let allowed: Option<Vec<Vec<u8>>> = hop.config.identities_only.then(|| {
+        hop.config
+            .identity_files
+            .iter()
+            .filter_map(|file| public_key_blob(file))
+            .collect()
+    });
+    Ok(identities
+        .iter()
+        .filter(|identity| {
+            allowed
+                .as_ref()
+                .is_none_or(|allowed| allowed.iter().any(|blob| blob == identity.blob()))
+        })
+        .any(|identity| agent.userauth(&hop.username, identity).is_ok()))
+}
+
+/// Authenticate a hop using secure credentials
+#[cfg(feature = "remote-ssh")]
+fn authenticate(
+    session: &Session,
+    hop: &Hop,
+    password: Option<&str>,
+    passphrase: Option<&str>,
+) -> Result<(), RemoteError> {
+    match &hop.auth_method {
+        AuthMethod::Password { .. } => {
+            let pwd = password.ok_or_else(|| {
+                RemoteError::AuthenticationFailed("Password not found in keyring".to_string())
+            })?;
+            session.userauth_password(&hop.username, pwd).map_err(|e| {
+                RemoteError::AuthenticationFailed(format!("Password auth failed: {}", e))
+            })?;
+        }
+        AuthMethod::Key {
+            private_key_path, ..
+        } => {
+            let key_path = PathBuf::from(private_key_path);
+            if !key_path.exists() {
+                return Err(RemoteError::AuthenticationFailed(format!(
+                    "Private key not found: {}",
+                    private_key_path
+                )));
+            }
+            session
+                .userauth_pubkey_file(&hop.username, None, &key_path, passphrase)
+                .map_err(|e| {
+                    RemoteError::AuthenticationFailed(format!("Key auth failed: {}", e))
+                })?;
+        }
+        AuthMethod::Agent => {
+            // Fall back to the config's identity files, as ssh does
+            let agent_result = authenticate_with_agent(session, hop);
+            let authenticated = matches!(agent_result, Ok(true))
+                || hop
+                    .config
+                    .identity_files
+                    .iter()
+                    .filter(|file| file.exists())
+                    .any(|file| {
+                        session
+                            .userauth_pubkey_file(&hop.username, None, file, passphrase)
+                            .is_ok()
+                    });
+
+            if !authenticated {
+                return Err(match agent_result {
+                    Err(e) if hop.config.identity_files.is_empty() => e,
+                    _ => RemoteError::AuthenticationFailed(
+                        "No valid SSH key found in agent or IdentityFile".to_string(),
+                    ),
+                });
+            }
+        }
+    }
+
+    if !session.authenticated() {
+        return Err(RemoteError::AuthenticationFailed(
+            "Authentication failed".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Open and authenticate an SSH session for a profile (blocking)
+///
+/// The profile's host is looked up in `~/.ssh/config`, so it may be an
+/// alias: `HostName`, `IdentityFile`, `IdentitiesOnly` and `ProxyJump`
+/// apply, while `Port` and `User` only replace the default port and an
+/// empty username. Jump hosts authenticate with the agent and their
+/// identity files; the profile's credentials are only used for its host.
+#[cfg(feature = "remote-ssh")]
+pub(crate) fn establish_session(
+    profile: &ConnectionProfile,
+    password: Option<&str>,
+    passphrase: Option<&str>,
+) -> Result<Session, RemoteError> {
+    let config = SshConfig::load();
+    let host = config.resolve(&profile.host);
+    let jumps: Vec<Hop> = host
+        .proxy_jump
+        .iter()
+        .map(|jump| {
+            let hop = config.resolve(&jump.host);
+            Hop {
+                host: hop.host_name.clone(),
+                port: jump.port.or(hop.port).unwrap_or(DEFAULT_SSH_PORT),
+                username: jump
+                    .user
+                    .clone()
+                    .or_else(|| hop.user.clone())
+                    .unwrap_or_else(local_user),
+                auth_method: AuthMethod::Agent,
+                config: hop,
+            }
+        })
+        .collect();
+    let target = Hop {
+        host: host.host_name.clone(),
+        port: match host.port {
+            Some(port) if profile.port == DEFAULT_SSH_PORT => port,
+            _ => profile.port,
+        },
+        username: if profile.username.is_empty() {
+            host.user.clone().unwrap_or_else(local_user)
+        } else {
+            profile.username.clone()
+        },
+        auth_method: profile.auth_method.clone(),
+        config: host,
+    };
+
+    // Each jump host tunnels the connection to the next hop
+    let mut stream = None;
+    for (index, hop) in jumps.iter().enumerate() {
+        let session = handshake(stream.take(), hop)?;
+        authenticate(&session, hop, None, None)?;
+        let next = jumps.get(index + 1).unwrap_or(&target);
+        info!(
+            "Jumping through {}@{}:{} to {}:{}",
+            hop.username, hop.host, hop.port, next.host, next.port
+        );
+        stream = Some(proxy_jump::tunnel(session, &next.host, next.port)?);
+    }
+
+    let session = handshake(stream, &target)?;
+    authenticate(&session, &target, password, passphrase)?;
+    Ok(session)
+}
+
 /// Remote connection manager - thread-safe connection pool
 ///
 /// Note: SshConnection uses std::sync::Mutex (not tokio::sync::RwLock) because
@@ -173,107 +402,8 @@ impl RemoteManager {
 
         // Perform all blocking SSH operations in spawn_blocking
         let (session, home_directory, platform) = tokio::task::spawn_blocking(move || {
-            // Create TCP connection (blocking)
-            let addr = format!("{}:{}", profile_clone.host, profile_clone.port);
-            let tcp = TcpStream::connect(&addr).map_err(|e| {
-                RemoteError::ConnectionFailed(format!("TCP connection failed: {}", e))
-            })?;
-
-            tcp.set_read_timeout(Some(std::time::Duration::from_secs(30)))
-                .map_err(RemoteError::IoError)?;
-            tcp.set_write_timeout(Some(std::time::Duration::from_secs(30)))
-                .map_err(RemoteError::IoError)?;
-
-            // Create SSH session
-            let mut session = Session::new().map_err(|e| {
-                RemoteError::ConnectionFailed(format!("Failed to create SSH session: {}", e))
-            })?;
-
-            session.set_tcp_stream(tcp);
-            session.handshake().map_err(|e| {
-                RemoteError::ConnectionFailed(format!("SSH handshake failed: {}", e))
-            })?;
-
-            // Authenticate using secure credentials
-            match &profile_clone.auth_method {
-                AuthMethod::Password { .. } => {
-                    let pwd = password.as_ref().ok_or_else(|| {
-                        RemoteError::AuthenticationFailed(
-                            "Password not found in keyring".to_string(),
-                        )
-                    })?;
-                    session
-                        .userauth_password(&profile_clone.username, pwd)
-                        .map_err(|e| {
-                            RemoteError::AuthenticationFailed(format!(
-                                "Password auth failed: {}",
-                                e
-                            ))
-                        })?;
-                }
-                AuthMethod::Key {
-                    private_key_path, ..
-                } => {
-                    let key_path = PathBuf::from(private_key_path);
-                    if !key_path.exists() {
-                        return Err(RemoteError::AuthenticationFailed(format!(
-                            "Private key not found: {}",
-                            private_key_path
-                        )));
-                    }
-                    session
-                        .userauth_pubkey_file(
-                            &profile_clone.username,
-                            None,
-                            &key_path,
-                            passphrase.as_deref(),
-                        )
-                        .map_err(|e| {
-                            RemoteError::AuthenticationFailed(format!("Key auth failed: {}", e))
-                        })?;
-                }
-                AuthMethod::Agent => {
-                    let mut agent = session.agent().map_err(|e| {
-                        RemoteError::AuthenticationFailed(format!("Agent connection failed: {}", e))
-                    })?;
-                    agent.connect().map_err(|e| {
-                        RemoteError::AuthenticationFailed(format!("Agent connect failed: {}", e))
-                    })?;
-                    agent.list_identities().map_err(|e| {
-                        RemoteError::AuthenticationFailed(format!(
-                            "Agent list identities failed: {}",
-                            e
-                        ))
-                    })?;
-
-                    let identities = agent.identities().map_err(|e| {
-                        RemoteError::AuthenticationFailed(format!(
-                            "Failed to get identities: {}",
-                            e
-                        ))
-                    })?;
-
-                    let mut authenticated = false;
-                    for identity in identities.iter() {
-                        if agent.userauth(&profile_clone.username, identity).is_ok() {
-                            authenticated = true;
-                            break;
-                        }
-                    }
-
-                    if !authenticated {
-                        return Err(RemoteError::AuthenticationFailed(
-                            "No valid SSH key found in agent".to_string(),
-                        ));
-                    }
-                }
-            }
-
-            if !session.authenticated() {
-                return Err(RemoteError::AuthenticationFailed(
-                    "Authentication failed".to_string(),
-                ));
-            }
+            let session =
+                establish_session(&profile_clone, password.as_deref(), passphrase.as_deref())?;
 
             // Get home directory and platform info
             let temp_conn = SshConnection::new(
@@ -363,6 +493,36 @@ impl RemoteManager {
         self.connect(profile).await
     }
 
+    /// Open an additional authenticated session for a connected profile
+    ///
+    /// Long-running remote processes (language servers, ...) get their own
+    /// session so their streaming I/O never contends with SFTP calls on the
+    /// shared connection.
+    #[cfg(feature = "remote-ssh")]
+    pub async fn open_session(&self, connection_id: &str) -> Result<Session, RemoteError> {
+        let profile = {
+            let connections = self.connections.read().await;
+            let conn = connections
+                .get(connection_id)
+                .ok_or_else(|| RemoteError::ConnectionNotFound(connection_id.to_string()))?;
+            let conn = conn
+                .lock()
+                .map_err(|e| RemoteError::ConnectionFailed(format!("Lock poisoned: {}", e)))?;
+            conn.profile.clone()
+        };
+
+        let credentials =
+            SecureAuthCredentials::load_from_keyring(&profile.id, &profile.auth_method)?;
+        let password = credentials.password().map(|s| s.to_string());
+        let passphrase = credentials.passphrase().map(|s| s.to_string());
+
+        tokio::task::spawn_blocking(move || {
+            establish_session(&profile, password.as_deref(), passphrase.as_deref())
+        })
+        .await
+        .map_err(|e| RemoteError::ConnectionFailed(format!("Task join error: {}", e)))?
+    }
+
     #[cfg(feature = "remote-ssh")]
     pub async fn disconnect(&self, connection_id: &str) -> Result<(), RemoteError> {
         let mut connections = self.connections.write().await;
@@ -2,9 +2,14 @@
 //!
 //! This module provides SSH-based remote development capabilities including:
 //! - SSH connection management with key and password authentication
+//! - Host key verification against known_hosts and trusted hosts
+//! - `~/.ssh/config` hosts, identities and ProxyJump chains
 //! - Remote file system operations (list, read, write, delete)
 //! - Remote command execution
+//! - Long-running remote processes with streamed stdio
+//! - A headless agent serving search, git, tasks and terminals on the host
 //! - Port forwarding
+//! - Dev Containers through the Docker/Podman Engine API
 //! - Connection profile persistence
 //!
 //! Security features:
@@ -13,18 +18,38 @@
 //! - Automatic memory zeroization
 //! - File permissions enforcement (0600)
 
+pub mod agent;
+#[cfg(feature = "desktop")]
 pub mod commands;
+#[cfg(feature = "desktop")]
 pub mod connection;
+#[cfg(feature = "desktop")]
 pub mod credentials;
+#[cfg(feature = "desktop")]
+pub mod devcontainer;
+#[cfg(feature = "desktop")]
 pub mod error;
+#[cfg(all(feature = "remote-ssh", feature = "desktop"))]
+pub mod exec_channel;
+#[cfg(feature = "desktop")]
+pub mod host_keys;
+#[cfg(feature = "desktop")]
 pub mod manager;
+#[cfg(feature = "desktop")]
 pub mod port_forwarding;
+#[cfg(all(feature = "remote-ssh", feature = "desktop"))]
+pub mod proxy_jump;
+#[cfg(feature = "desktop")]
+pub mod ssh_config;
+#[cfg(feature = "desktop")]
 pub mod tunnel;
+#[cfg(feature = "desktop")]
 pub mod types;
 
 // Re-export main types for backwards compatibility
-#[cfg(feature = "remote-ssh")]
+#[cfg(all(feature = "remote-ssh", feature = "desktop"))]
 pub use credentials::SecureAuthCredentials;
+#[cfg(feature = "desktop")]
 pub use manager::RemoteManager;
-#[cfg(feature = "remote-ssh")]
+#[cfg(all(feature = "remote-ssh", feature = "desktop"))]
 pub use types::AuthMethod;
@@ -0,0 +1,133 @@
+//! `ProxyJump` tunnels.
+//!
+//! libssh2 can only run a session over a socket, so the next hop's session
+//! is given one end of a loopback socket pair while a pump thread copies
+//! bytes between the other end and a `direct-tcpip` channel opened on the
+//! jump host's session. The thread owns the jump session and exits when
+//! either side closes.
+
+use std::io::{ErrorKind, Read, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::thread;
+use std::time::Duration;
+
+use ssh2::{Channel, Session};
+use tracing::{debug, warn};
+
+use super::error::RemoteError;
+
+/// Longest the pump sleeps when neither side has data
+const MAX_IDLE_SLEEP: Duration = Duration::from_millis(20);
+
+/// Connected loopback sockets; only the peer we connected is accepted
+fn socket_pair() -> Result<(TcpStream, TcpStream), RemoteError> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+    let client = TcpStream::connect(listener.local_addr()?)?;
+    loop {
+        let (server, peer) = listener.accept()?;
+        if peer == client.local_addr()? {
+            return Ok((client, server));
+        }
+        warn!(
+            "Rejected unexpected connection to jump socket from {}",
+            peer
+        );
+    }
+}
+
+/// Open a stream to `host:port` through an authenticated jump session
+///
+/// The returned socket is meant for `Session::set_tcp_stream` of the next
+/// hop.
+pub fn tunnel(jump: Session, host: &str, port: u16) -> Result<TcpStream, RemoteError> {
+    let channel = jump.channel_direct_tcpip(host, port, None).map_err(|e| {
+        RemoteError::ConnectionFailed(format!("Jump to {}:{} failed: {}", host, port, e))
+    })?;
+    let (stream, local) = socket_pair()?;
+    local.set_nonblocking(true)?;
+    jump.set_blocking(false);
+
+    let target = format!("{}:{}", host, port);
+    thread::spawn(move || {
+        pump(&jump, channel, local);
+        debug!("Jump tunnel to {} closed", target);
+    });
+    Ok(stream)
+}
+
+/// Write as much of `pending` as `out` accepts without blocking
+///
+/// Returns `None` once `out` is closed.
+fn flush_pending(out: &mut impl Write, pending: &mut Vec<u8>) -> Option<bool> {
+    if pending.is_empty() {
+        return Some(false);
+    }
+    match out.write(pending) {
+        Ok(0) => None,
+        Ok(n) => {
+            pending.drain(..n);
+            Some(true)
+        }
+        Err(e) if e.kind() == ErrorKind::WouldBlock => Some(false),
+        Err(e) => {
+            warn!("Jump tunnel write failed: {}", e);
+            None
+        }
+    }
+}
+
+/// Read into `pending` if it is empty
+///
+/// Returns `None` at end of stream.
+fn fill_pending(input: &mut impl Read, pending: &mut Vec<u8>, buf: &mut [u8]) -> Option<bool> {
+    if !pending.is_empty() {
+        return Some(false);
+    }
+    match input.read(buf) {
+        Ok(0) => None,
+        Ok(n) => {
+            pending.extend_from_slice(&buf[..n]);
+            Some(true)
+        }
+        Err(e) if e.kind() == ErrorKind::WouldBlock => Some(false),
+        Err(e) => {
+            warn!("Jump tunnel read failed: {}", e);
+            None
+        }
+    }
+}
+
+fn pump(session: &Session, mut channel: Channel, mut local: TcpStream) {
+    let mut to_remote: Vec<u8> = Vec::new();
+    let mut to_local: Vec<u8> = Vec::new();
+    let mut buf = vec![0u8; 32 * 1024];
+    let mut idle_sleep = Duration::from_millis(1);
+
+    loop {
+        let mut progressed = false;
+        let steps = [
+            fill_pending(&mut local, &mut to_remote, &mut buf),
+            flush_pending(&mut channel, &mut to_remote),
+            fill_pending(&mut channel, &mut to_local, &mut buf),
+            flush_pending(&mut local, &mut to_local),
+        ];
+        for step in steps {
+            match step {
+                Some(moved) => progressed |= moved,
+                None => {
+                    session.set_blocking(true);
+                    let _ = channel.close();
+                    let _ = local.shutdown(std::net::Shutdown::Both);
+                    return;
+                }
+            }
+        }
+
+        if progressed {
+            idle_sleep = Duration::from_millis(1);
+        } else {
+            thread::sleep(idle_sleep);
+            idle_sleep = (idle_sleep * 2).min(MAX_IDLE_SLEEP);
+        }
+    }
+}
@@ -0,0 +1,596 @@
+//! OpenSSH client configuration (`~/.ssh/config`).
+//!
+//! Supports `Host` and `Match` blocks (`all`, `host`, `originalhost`, `user`,
+//! `localuser`, `final`), `Include`, and the options Cortex connects with:
+//! `HostName`, `Port`, `User`, `IdentityFile`, `IdentitiesOnly` and
+//! `ProxyJump`. As in OpenSSH the first value obtained for an option wins,
+//! except `IdentityFile`, which accumulates.
+
+use std::path::{Path, PathBuf};
+
+use tracing::warn;
+
+use super::types::{AuthMethod, ConnectionProfile};
+
+/// Port used when neither the profile nor the config names one
+pub const DEFAULT_SSH_PORT: u16 = 22;
+
+/// Nesting limit for `Include`, as in OpenSSH
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// One criterion of a `Match` line
+#[derive(Debug, Clone, PartialEq)]
+enum Criterion {
+    All,
+    Final,
+    Host(String),
+    OriginalHost(String),
+    User(String),
+    LocalUser(String),
+    /// `exec`, `canonical` and other criteria Cortex cannot evaluate
+    Unsupported,
+}
+
+/// When a block's options apply
+#[derive(Debug, Clone, PartialEq)]
+enum Condition {
+    /// Options before the first `Host` or `Match`
+    Always,
+    Host(Vec<String>),
+    Match(Vec<(bool, Criterion)>),
+}
+
+#[derive(Debug, Clone)]
+struct Block {
+    condition: Condition,
+    /// Lower-cased keyword and unquoted value
+    options: Vec<(String, String)>,
+}
+
+/// A `ProxyJump` hop as written: `[user@]host[:port]`
+#[derive(Debug, Clone, PartialEq)]
+pub struct JumpHost {
+    pub user: Option<String>,
+    pub host: String,
+    pub port: Option<u16>,
+}
+
+/// Options resolved for one destination
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HostConfig {
+    /// Address to connect to; the alias itself without `HostName`
+    pub host_name: String,
+    pub port: Option<u16>,
+    pub user: Option<String>,
+    pub identity_files: Vec<PathBuf>,
+    pub identities_only: bool,
+    pub proxy_jump: Vec<JumpHost>,
+}
+
+/// Parsed `~/.ssh/config`
+#[derive(Debug, Clone, Default)]
+pub struct SshConfig {
+    blocks: Vec<Block>,
+}
+
+/// Name of the local user, used for `%u` and as the default remote user
+pub fn local_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_default()
+}
+
+/// Match `text` against an OpenSSH pattern with `*` and `?` wildcards
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            t = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Match against a pattern list: any negated match rejects, otherwise any
+/// positive match accepts
+fn pattern_list_match<'a>(patterns: impl IntoIterator<Item = &'a str>, text: &str) -> bool {
+    let mut matched = false;
+    for pattern in patterns {
+        match pattern.strip_prefix('!') {
+            Some(negated) if wildcard_match(negated, text) => return false,
+            Some(_) => {}
+            None => matched |= wildcard_match(pattern, text),
+        }
+    }
+    matched
+}
+
+/// Split a line into words, honouring double quotes
+fn split_words(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut quoted = false;
+    let mut in_word = false;
+    for c in line.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                in_word = true;
+            }
+            c if c.is_whitespace() && !quoted => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            c => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    words
+}
+
+/// Split `Keyword value` or `Keyword=value`
+fn split_option(line: &str) -> Option<(String, String)> {
+    let line = line.trim();
+    let end = line.find(|c: char| c.is_whitespace() || c == '=')?;
+    let keyword = line[..end].to_lowercase();
+    let rest = line[end..].trim_start();
+    let rest = rest.strip_prefix('=').unwrap_or(rest).trim();
+    Some((keyword, rest.to_string()))
+}
+
+fn parse_match(value: &str) -> Vec<(bool, Criterion)> {
+    let words = split_words(value);
+    let mut criteria = Vec::new();
+    let mut words = words.into_iter();
+    while let Some(word) = words.next() {
+        let (negated, name) = match word.strip_prefix('!') {
+            Some(name) => (true, name.to_lowercase()),
+            None => (false, word.to_lowercase()),
+        };
+        let criterion = match name.as_str() {
+            "all" => Criterion::All,
+            "final" => Criterion::Final,
+            "canonical" => Criterion::Unsupported,
+            "host" | "originalhost" | "user" | "localuser" => {
+                let list = words.next().unwrap_or_default();
+                match name.as_str() {
+                    "host" => Criterion::Host(list),
+                    "originalhost" => Criterion::OriginalHost(list),
+                    "user" => Criterion::User(list),
+                    _ => Criterion::LocalUser(list),
+                }
+            }
+            _ => {
+                // Remaining criteria take an argument
+                words.next();
+                Criterion::Unsupported
+            }
+        };
+        criteria.push((negated, criterion));
+    }
+    criteria
+}
+
+/// Parse a `ProxyJump` value; `none` disables jumping
+fn parse_proxy_jump(value: &str) -> Vec<JumpHost> {
+    if value.eq_ignore_ascii_case("none") {
+        return Vec::new();
+    }
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|hop| !hop.is_empty())
+        .map(|hop| {
+            let hop = hop.strip_prefix("ssh://").unwrap_or(hop);
+            let (user, address) = match hop.rsplit_once('@') {
+                Some((user, address)) => (Some(user.to_string()), address),
+                None => (None, hop),
+            };
+            let (host, port) = if let Some(bracketed) = address.strip_prefix('[') {
+                // [v6::addr]:port
+                let (host, rest) = bracketed.split_once(']').unwrap_or((bracketed, ""));
+                (host, rest.strip_prefix(':').and_then(|p| p.parse().ok()))
+            } else {
+                match address.rsplit_once(':') {
+                    Some((host, port)) if !host.contains(':') => (host, port.parse().ok()),
+                    _ => (address, None),
+                }
+            };
+            JumpHost {
+                user,
+                host: host.to_string(),
+                port,
+            }
+        })
+        .collect()
+}
+
+fn home_dir() -> PathBuf {
+    dirs::home_dir().unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// Expand `~` and the `%` tokens OpenSSH allows in `IdentityFile`
+fn expand_path(value: &str, host: &str, user: &str, port: u16) -> PathBuf {
+    let mut expanded = String::new();
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            expanded.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('%') => expanded.push('%'),
+            Some('d') => expanded.push_str(&home_dir().to_string_lossy()),
+            Some('h') => expanded.push_str(host),
+            Some('r') => expanded.push_str(user),
+            Some('u') => expanded.push_str(&local_user()),
+            Some('p') => expanded.push_str(&port.to_string()),
+            Some(other) => {
+                expanded.push('%');
+                expanded.push(other);
+            }
+            None => expanded.push('%'),
+        }
+    }
+    match expanded.strip_prefix("~/") {
+        Some(rest) => home_dir().join(rest),
+        None if expanded == "~" => home_dir(),
+        None => PathBuf::from(expanded),
+    }
+}
+
+impl SshConfig {
+    /// Read `~/.ssh/config`; a missing or unreadable file yields no options
+    pub fn load() -> Self {
+        let ssh_dir = home_dir().join(".ssh");
+        let path = ssh_dir.join("config");
+        match std::fs::read_to_string(&path) {
+            Ok(text) => Self::parse(&text, &ssh_dir),
+            Err(e) => {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    warn!("Failed to read {}: {}", path.display(), e);
+                }
+                Self::default()
+            }
+        }
+    }
+
+    /// Parse config text; relative `Include` paths are resolved against
+    /// `include_dir`
+    pub fn parse(text: &str, include_dir: &Path) -> Self {
+        let mut blocks = vec![Block {
+            condition: Condition::Always,
+            options: Vec::new(),
+        }];
+        Self::parse_into(text, include_dir, &mut blocks, 0);
+        Self { blocks }
+    }
+
+    fn parse_into(text: &str, include_dir: &Path, blocks: &mut Vec<Block>, depth: usize) {
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((keyword, value)) = split_option(line) else {
+                continue;
+            };
+            match keyword.as_str() {
+                "host" => blocks.push(Block {
+                    condition: Condition::Host(split_words(&value)),
+                    options: Vec::new(),
+                }),
+                "match" => blocks.push(Block {
+                    condition: Condition::Match(parse_match(&value)),
+                    options: Vec::new(),
+                }),
+                "include" => {
+                    if depth >= MAX_INCLUDE_DEPTH {
+                        warn!("Include nested too deeply in SSH config: {}", value);
+                        continue;
+                    }
+                    for pattern in split_words(&value) {
+                        for path in Self::include_paths(&pattern, include_dir) {
+                            match std::fs::read_to_string(&path) {
+                                Ok(text) => Self::parse_into(&text, include_dir, blocks, depth + 1),
+                                Err(e) => warn!("Failed to read {}: {}", path.display(), e),
+                            }
+                        }
+                    }
+                }
+                _ => {
+                    if let Some(block) = blocks.last_mut() {
+                        block.options.push((keyword, split_words(&value).join(" ")));
+                    }
+                }
+            }
+        }
+    }
+
+    fn include_paths(pattern: &str, include_dir: &Path) -> Vec<PathBuf> {
+        let pattern = expand_path(pattern, "", "", DEFAULT_SSH_PORT);
+        let pattern = if pattern.is_absolute() {
+            pattern
+        } else {
+            include_dir.join(pattern)
+        };
+        match glob::glob(&pattern.to_string_lossy()) {
+            Ok(paths) => {
+                let mut paths: Vec<PathBuf> = paths.filter_map(Result::ok).collect();
+                paths.sort();
+                paths
+            }
+            Err(e) => {
+                warn!("Invalid Include pattern {}: {}", pattern.display(), e);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Aliases named by `Host` lines, without wildcards or negations
+    pub fn host_aliases(&self) -> Vec<String> {
+        let mut aliases: Vec<String> = Vec::new();
+        for block in &self.blocks {
+            if let Condition::Host(patterns) = &block.condition {
+                for pattern in patterns {
+                    let concrete = !pattern.contains(['*', '?', '!']);
+                    if concrete && !aliases.contains(pattern) {
+                        aliases.push(pattern.clone());
+                    }
+                }
+            }
+        }
+        aliases
+    }
+
+    /// Options for connecting to `alias`
+    pub fn resolve(&self, alias: &str) -> HostConfig {
+        let mut host_name: Option<String> = None;
+        let mut port: Option<u16> = None;
+        let mut user: Option<String> = None;
+        let mut identity_files: Vec<String> = Vec::new();
+        let mut identities_only: Option<bool> = None;
+        let mut proxy_jump: Option<Vec<JumpHost>> = None;
+        let local = local_user();
+
+        for block in &self.blocks {
+            let applies = match &block.condition {
+                Condition::Always => true,
+                Condition::Host(patterns) => {
+                    pattern_list_match(patterns.iter().map(String::as_str), alias)
+                }
+                Condition::Match(criteria) => criteria.iter().all(|(negated, criterion)| {
+                    let matched = match criterion {
+                        Criterion::All | Criterion::Final => true,
+                        Criterion::Host(list) => {
+                            let host = host_name.as_deref().unwrap_or(alias);
+                            pattern_list_match(list.split(','), host)
+                        }
+                        Criterion::OriginalHost(list) => pattern_list_match(list.split(','), alias),
+                        Criterion::User(list) => {
+                            pattern_list_match(list.split(','), user.as_deref().unwrap_or(&local))
+                        }
+                        Criterion::LocalUser(list) => pattern_list_match(list.split(','), &local),
+                        Criterion::Unsupported => false,
+                    };
+                    matched != *negated
+                }),
+            };
+            if !applies {
+                continue;
+            }
+
+            for (keyword, value) in &block.options {
+                match keyword.as_str() {
+                    "hostname" if host_name.is_none() => {
+                        host_name = Some(value.replace("%h", alias).replace("%%", "%"));
+                    }
+                    "port" if port.is_none() => port = value.parse().ok(),
+                    "user" if user.is_none() => user = Some(value.clone()),
+                    "identityfile" => identity_files.push(value.clone()),
+                    "identitiesonly" if identities_only.is_none() => {
+                        identities_only = Some(value.eq_ignore_ascii_case("yes"));
+                    }
+                    "proxyjump" if proxy_jump.is_none() => {
+                        proxy_jump = Some(parse_proxy_jump(value));
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let host_name = host_name.unwrap_or_else(|| alias.to_string());
+        let token_user = user.clone().unwrap_or_else(|| local.clone());
+        let token_port = port.unwrap_or(DEFAULT_SSH_PORT);
+        HostConfig {
+            identity_files: identity_files
+                .iter()
+                .map(|file| expand_path(file, &host_name, &token_user, token_port))
+                .collect(),
+            host_name,
+            port,
+            user,
+            identities_only: identities_only.unwrap_or(false),
+            proxy_jump: proxy_jump.unwrap_or_default(),
+        }
+    }
+
+    /// Connection profiles for every concrete `Host` alias
+    ///
+    /// Profiles keep the alias as their host so `HostName` and `ProxyJump`
+    /// are re-resolved on every connect; ids are derived from the alias so a
+    /// second import updates the first.
+    pub fn profiles(&self) -> Vec<ConnectionProfile> {
+        self.host_aliases()
+            .into_iter()
+            .map(|alias| {
+                let host = self.resolve(&alias);
+                let auth_method = match host.identity_files.first() {
+                    Some(key) => AuthMethod::Key {
+                        private_key_path: key.to_string_lossy().into_owned(),
+                        has_passphrase: false,
+                    },
+                    None => AuthMethod::Agent,
+                };
+                ConnectionProfile {
+                    id: format!("ssh-config-{}", alias),
+                    name: alias.clone(),
+                    host: alias,
+                    port: host.port.unwrap_or(DEFAULT_SSH_PORT),
+                    username: host.user.unwrap_or_else(local_user),
+                    auth_method,
+                    default_directory: None,
+                    port_forwards: Vec::new(),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+# Company servers
+Host bastion
+    HostName bastion.example.com
+    User jump
+    IdentityFile ~/.ssh/bastion_ed25519
+
+Host *.internal !db.internal
+    ProxyJump bastion
+    User deploy
+
+Host build build2
+    HostName %h.internal
+    Port=2222
+    IdentityFile "~/.ssh/id %r"
+    IdentitiesOnly yes
+
+Match host *.internal user deploy
+    IdentityFile ~/.ssh/deploy_key
+
+Match originalhost db.internal
+    ProxyJump admin@bastion:2200,[fd00::1]:22
+
+Host *
+    User fallback
+    IdentityFile ~/.ssh/id_ed25519
+"#;
+
+    fn config() -> SshConfig {
+        SshConfig::parse(CONFIG, Path::new("/nonexistent"))
+    }
+
+    #[test]
+    fn matches_openssh_patterns() {
+        assert!(wildcard_match("*.internal", "db.INTERNAL"));
+        assert!(wildcard_match("web-??", "web-01"));
+        assert!(!wildcard_match("web-??", "web-1"));
+        assert!(wildcard_match("a*b*c", "axxbyyc"));
+        assert!(!pattern_list_match(
+            ["*.internal", "!db.internal"],
+            "db.internal"
+        ));
+        assert!(pattern_list_match(
+            "x,*.internal".split(','),
+            "web.internal"
+        ));
+    }
+
+    #[test]
+    fn resolves_first_value_and_accumulates_identities() {
+        let home = home_dir();
+        let build = config().resolve("build");
+        assert_eq!(build.host_name, "build.internal");
+        assert_eq!(build.port, Some(2222));
+        assert_eq!(build.user.as_deref(), Some("fallback"));
+        assert!(build.identities_only);
+        assert_eq!(
+            build.identity_files,
+            vec![home.join(".ssh/id fallback"), home.join(".ssh/id_ed25519")]
+        );
+        assert!(build.proxy_jump.is_empty());
+
+        let web = config().resolve("web.internal");
+        assert_eq!(web.user.as_deref(), Some("deploy"));
+        assert_eq!(web.identity_files[0], home.join(".ssh/deploy_key"));
+        assert_eq!(
+            web.proxy_jump,
+            vec![JumpHost {
+                user: None,
+                host: "bastion".to_string(),
+                port: None
+            }]
+        );
+    }
+
+    #[test]
+    fn parses_jump_chains() {
+        let db = config().resolve("db.internal");
+        assert_eq!(db.user.as_deref(), Some("fallback"));
+        assert_eq!(
+            db.proxy_jump,
+            vec![
+                JumpHost {
+                    user: Some("admin".to_string()),
+                    host: "bastion".to_string(),
+                    port: Some(2200)
+                },
+                JumpHost {
+                    user: None,
+                    host: "fd00::1".to_string(),
+                    port: Some(22)
+                }
+            ]
+        );
+        assert!(parse_proxy_jump("none").is_empty());
+    }
+
+    #[test]
+    fn follows_includes_and_imports_profiles() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("config.d")).unwrap();
+        std::fs::write(
+            dir.path().join("config.d/work"),
+            "Host work\n  HostName 10.0.0.5\n  Port 2022\n",
+        )
+        .unwrap();
+        let config = SshConfig::parse(
+            "Include config.d/*\nHost personal\n  User me\nHost *\n  User other\n",
+            dir.path(),
+        );
+
+        assert_eq!(config.host_aliases(), vec!["work", "personal"]);
+        assert_eq!(config.resolve("work").host_name, "10.0.0.5");
+        let profiles = config.profiles();
+        assert_eq!(profiles[0].id, "ssh-config-work");
+        assert_eq!(profiles[0].host, "work");
+        assert_eq!(profiles[0].port, 2022);
+        assert_eq!(profiles[0].username, "other");
+        assert!(matches!(profiles[0].auth_method, AuthMethod::Agent));
+        assert_eq!(profiles[1].username, "me");
+    }
+}
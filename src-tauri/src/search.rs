@@ -491,6 +491,7 @@ pub async fn search_workspace(
 
     for root in &roots {
         let response = crate::fs::search::fs_search_content(
+            app.clone(),
             root.clone(),
             query.clone(),
             case_sensitive,
@@ -20,11 +20,13 @@ use std::time::Duration;
 use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
 use ssh2::{Channel, Session};
-use std::net::TcpStream;
 use tauri::{AppHandle, Emitter, Manager};
 use tracing::{error, info, warn};
 use uuid::Uuid;
 
+use crate::remote::host_keys::prompt_if_unknown;
+use crate::remote::manager::establish_session;
+use crate::remote::types::ConnectionProfile;
 use crate::remote::{AuthMethod, SecureAuthCredentials};
 
 /// Output batching interval in milliseconds
@@ -39,9 +41,6 @@ const FLOW_CONTROL_MAX_PENDING: usize = 100_000;
 /// Reconnection attempt interval
 const RECONNECT_INTERVAL_MS: u64 = 5000;
 
-/// Connection timeout in seconds
-const CONNECTION_TIMEOUT_SECS: u64 = 30;
-
 /// SSH connection configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SSHConfig {
@@ -169,86 +168,26 @@ impl SSHTerminalState {
             config.username, config.host, config.port
         );
 
-        // Create TCP connection
-        let addr = format!("{}:{}", config.host, config.port);
-        let tcp = TcpStream::connect(&addr).map_err(|e| format!("TCP connection failed: {}", e))?;
-
-        tcp.set_read_timeout(Some(Duration::from_secs(CONNECTION_TIMEOUT_SECS)))
-            .map_err(|e| format!("Failed to set read timeout: {}", e))?;
-        tcp.set_write_timeout(Some(Duration::from_secs(CONNECTION_TIMEOUT_SECS)))
-            .map_err(|e| format!("Failed to set write timeout: {}", e))?;
-
-        // Create SSH session
-        let mut session =
-            Session::new().map_err(|e| format!("Failed to create SSH session: {}", e))?;
-
-        session.set_tcp_stream(tcp);
-        session
-            .handshake()
-            .map_err(|e| format!("SSH handshake failed: {}", e))?;
-
-        // Authenticate
+        // Connect through ~/.ssh/config (HostName, ProxyJump, ...) and
+        // verify the host key like remote connections do
         let profile_id = config.profile_id.as_deref().unwrap_or(&session_id);
         let credentials = SecureAuthCredentials::load_from_keyring(profile_id, &config.auth_method)
             .map_err(|e| format!("Failed to load credentials: {}", e))?;
-
-        match &config.auth_method {
-            AuthMethod::Password { .. } => {
-                let password = credentials
-                    .password()
-                    .ok_or_else(|| "Password not found".to_string())?;
-                session
-                    .userauth_password(&config.username, password)
-                    .map_err(|e| format!("Password auth failed: {}", e))?;
-            }
-            AuthMethod::Key {
-                private_key_path, ..
-            } => {
-                let key_path = std::path::PathBuf::from(private_key_path);
-                if !key_path.exists() {
-                    return Err(format!("Private key not found: {}", private_key_path));
-                }
-                session
-                    .userauth_pubkey_file(
-                        &config.username,
-                        None,
-                        &key_path,
-                        credentials.passphrase(),
-                    )
-                    .map_err(|e| format!("Key auth failed: {}", e))?;
-            }
-            AuthMethod::Agent => {
-                let mut agent = session
-                    .agent()
-                    .map_err(|e| format!("Agent connection failed: {}", e))?;
-                agent
-                    .connect()
-                    .map_err(|e| format!("Agent connect failed: {}", e))?;
-                agent
-                    .list_identities()
-                    .map_err(|e| format!("Agent list identities failed: {}", e))?;
-
-                let identities = agent
-                    .identities()
-                    .map_err(|e| format!("Failed to get identities: {}", e))?;
-
-                let mut authenticated = false;
-                for identity in identities.iter() {
-                    if agent.userauth(&config.username, identity).is_ok() {
-                        authenticated = true;
-                        break;
-                    }
-                }
-
-                if !authenticated {
-                    return Err("No valid SSH key found in agent".to_string());
-                }
-            }
-        }
-
-        if !session.authenticated() {
-            return Err("Authentication failed".to_string());
-        }
+        let profile = ConnectionProfile {
+            id: profile_id.to_string(),
+            name: format!("{}@{}", config.username, config.host),
+            host: config.host.clone(),
+            port: config.port,
+            username: config.username.clone(),
+            auth_method: config.auth_method.clone(),
+            default_directory: config.initial_cwd.clone(),
+            port_forwards: Vec::new(),
+        };
+        let session = establish_session(&profile, credentials.password(), credentials.passphrase())
+            .map_err(|e| {
+                prompt_if_unknown(app_handle, &e);
+                e.to_string()
+            })?;
 
         info!("SSH authenticated to {}@{}", config.username, config.host);
 
@@ -11,8 +11,13 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::process::Stdio;
-use tauri::{AppHandle, Emitter};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+#[cfg(feature = "desktop")]
+use tauri::{AppHandle, Emitter, Manager};
 use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::sync::broadcast;
 use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
@@ -25,6 +30,13 @@ static TASK_PROBLEMS: Lazy<Mutex<Vec<TaskDiagnosticEvent>>> = Lazy::new(|| Mutex
 
 static PROBLEMS_MAP: Lazy<DashMap<String, Vec<TaskDiagnosticEvent>>> = Lazy::new(DashMap::new);
 
+/// Task status changes for in-process subscribers (see `run_task_until_ready`)
+static TASK_STATUS: Lazy<broadcast::Sender<TaskStatusEvent>> =
+    Lazy::new(|| broadcast::channel(256).0);
+
+/// Longest `run_task_until_ready` waits for a task to finish or become ready
+const TASK_READY_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
 pub fn store_problem(diagnostic: &TaskDiagnosticEvent) {
     TASK_PROBLEMS.lock().push(diagnostic.clone());
     PROBLEMS_MAP
@@ -54,6 +66,16 @@ struct TaskStatusEvent {
     status: String,
 }
 
+#[cfg(feature = "desktop")]
+fn emit_task_status(app: &AppHandle, task_id: &str, status: &str) {
+    let event = TaskStatusEvent {
+        task_id: task_id.to_string(),
+        status: status.to_string(),
+    };
+    let _ = app.emit("task:status", &event);
+    let _ = TASK_STATUS.send(event);
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TaskDiagnosticEvent {
@@ -192,7 +214,8 @@ pub struct TaskResult {
 
 // ============== Problem Matcher ==============
 
-struct ProblemMatcher {
+#[derive(Clone)]
+pub(crate) struct ProblemMatcher {
     name: String,
     pattern: Regex,
     file_group: usize,
@@ -203,7 +226,7 @@ struct ProblemMatcher {
     code_group: Option<usize>,
 }
 
-fn get_builtin_problem_matcher(name: &str) -> Option<ProblemMatcher> {
+pub(crate) fn get_builtin_problem_matcher(name: &str) -> Option<ProblemMatcher> {
     match name {
         "$tsc" => {
             // TypeScript: src/file.ts(10,5): error TS2304: Cannot find name 'x'.
@@ -335,13 +358,37 @@ fn get_problem_matchers(problem_matcher: &Option<serde_json::Value>) -> Vec<Prob
     matchers
 }
 
-fn apply_problem_matchers(
+/// Pattern that marks a background task as ready
+///
+/// Taken from `background.endsPattern` of the first problem matcher that has
+/// one; the pattern may be a string or an object with a `regexp`.
+fn background_ready_pattern(problem_matcher: &Option<serde_json::Value>) -> Option<Regex> {
+    fn ends_pattern(matcher: &serde_json::Value) -> Option<&str> {
+        let pattern = matcher.get("background")?.get("endsPattern")?;
+        pattern
+            .as_str()
+            .or_else(|| pattern.get("regexp").and_then(|r| r.as_str()))
+    }
+
+    let pattern = match problem_matcher.as_ref()? {
+        serde_json::Value::Array(matchers) => matchers.iter().find_map(ends_pattern),
+        matcher => ends_pattern(matcher),
+    }?;
+    Regex::new(pattern)
+        .map_err(|e| warn!("Invalid background endsPattern '{}': {}", pattern, e))
+        .ok()
+}
+
+/// Match a single output line against a set of problem matchers.
+///
+/// `last_rustc_message` carries the `(severity, message)` of the most recent
+/// rustc header line, since rustc reports the location on a separate line.
+pub(crate) fn match_problem_line(
     line: &str,
     matchers: &[ProblemMatcher],
     task_id: &str,
-    app_handle: &AppHandle,
     last_rustc_message: &Option<(String, String)>,
-) {
+) -> Option<TaskDiagnosticEvent> {
     for matcher in matchers {
         if let Some(caps) = matcher.pattern.captures(line) {
             let file = if matcher.file_group > 0 {
@@ -391,7 +438,7 @@ fn apply_problem_matchers(
                 .and_then(|g| caps.get(g))
                 .map(|m| m.as_str().to_string());
 
-            let event = TaskDiagnosticEvent {
+            return Some(TaskDiagnosticEvent {
                 task_id: task_id.to_string(),
                 file,
                 line: line_num,
@@ -400,22 +447,32 @@ fn apply_problem_matchers(
                 message,
                 code,
                 source: matcher.name.clone(),
-            };
-
-            store_problem(&event);
+            });
+        }
+    }
+    None
+}
 
-            if let Err(e) = app_handle.emit("task:diagnostic", &event) {
-                warn!("Failed to emit task:diagnostic event: {}", e);
-            }
+#[cfg(feature = "desktop")]
+fn apply_problem_matchers(
+    line: &str,
+    matchers: &[ProblemMatcher],
+    task_id: &str,
+    app_handle: &AppHandle,
+    last_rustc_message: &Option<(String, String)>,
+) {
+    if let Some(event) = match_problem_line(line, matchers, task_id, last_rustc_message) {
+        store_problem(&event);
 
-            break;
+        if let Err(e) = app_handle.emit("task:diagnostic", &event) {
+            warn!("Failed to emit task:diagnostic event: {}", e);
         }
     }
 }
 
 // ============== Variable Substitution ==============
 
-fn substitute_variables(
+pub(crate) fn substitute_variables(
     input: &str,
     workspace_path: &str,
     file_path: Option<&str>,
@@ -631,7 +688,7 @@ static RUSTC_HEADER_RE: Lazy<Regex> = Lazy::new(|| {
     Regex::new(r"^(error|warning)(?:\[([A-Z]\d+)\])?:\s+(.+)$").expect("Invalid rustc header regex")
 });
 
-fn parse_rustc_header(line: &str) -> Option<(String, String)> {
+pub(crate) fn parse_rustc_header(line: &str) -> Option<(String, String)> {
     RUSTC_HEADER_RE.captures(line).map(|caps| {
         let severity = caps[1].to_string();
         let message = caps[3].to_string();
@@ -642,7 +699,7 @@ fn parse_rustc_header(line: &str) -> Option<(String, String)> {
 // ============== Core Execution ==============
 
 /// Load tasks.json from the workspace
-fn load_tasks_config(workspace_path: &str) -> Result<TasksConfig, String> {
+pub(crate) fn load_tasks_config(workspace_path: &str) -> Result<TasksConfig, String> {
     let vscode_path = PathBuf::from(workspace_path)
         .join(".vscode")
         .join("tasks.json");
@@ -661,7 +718,7 @@ fn load_tasks_config(workspace_path: &str) -> Result<TasksConfig, String> {
 }
 
 /// Remove single-line and multi-line comments from JSON content
-fn remove_json_comments(content: &str) -> String {
+pub(crate) fn remove_json_comments(content: &str) -> String {
     let mut result = String::with_capacity(content.len());
     let mut chars = content.chars().peekable();
     let mut in_string = false;
@@ -719,7 +776,10 @@ fn remove_json_comments(content: &str) -> String {
 }
 
 /// Find a task by name
-fn find_task<'a>(config: &'a TasksConfig, task_name: &str) -> Option<&'a TaskDefinition> {
+pub(crate) fn find_task<'a>(
+    config: &'a TasksConfig,
+    task_name: &str,
+) -> Option<&'a TaskDefinition> {
     config.tasks.iter().find(|t| t.label == task_name)
 }
 
@@ -783,6 +843,7 @@ async fn run_single_task(
 }
 
 /// Execute a task with streaming output, problem matchers, and background support
+#[cfg(feature = "desktop")]
 async fn execute_task_streaming(
     task: TaskDefinition,
     workspace_path: String,
@@ -796,13 +857,7 @@ async fn execute_task_streaming(
 
     info!("Executing task '{}' (id: {})", task.label, task_id);
 
-    let _ = app_handle.emit(
-        "task:status",
-        &TaskStatusEvent {
-            task_id: task_id.clone(),
-            status: "started".to_string(),
-        },
-    );
+    emit_task_status(&app_handle, &task_id, "started");
 
     let mut child = build_command(&task, &workspace_path)
         .spawn()
@@ -810,25 +865,21 @@ async fn execute_task_streaming(
 
     let matchers = get_problem_matchers(&task.problem_matcher);
     let has_rustc_matcher = matchers.iter().any(|m| m.name == "rustc");
+    let ready_pattern = if task.is_background {
+        background_ready_pattern(&task.problem_matcher)
+    } else {
+        None
+    };
+    let ready_sent = Arc::new(AtomicBool::new(false));
 
     let stdout_handle = if let Some(stdout) = child.stdout.take() {
         let reader = BufReader::new(stdout);
         let app = app_handle.clone();
         let tid = task_id.clone();
-        let matchers_for_stdout: Vec<_> = matchers
-            .iter()
-            .map(|m| ProblemMatcher {
-                name: m.name.clone(),
-                pattern: m.pattern.clone(),
-                file_group: m.file_group,
-                line_group: m.line_group,
-                column_group: m.column_group,
-                severity_group: m.severity_group,
-                message_group: m.message_group,
-                code_group: m.code_group,
-            })
-            .collect();
+        let matchers_for_stdout = matchers.clone();
         let track_rustc = has_rustc_matcher;
+        let ready_pattern = ready_pattern.clone();
+        let ready_sent = ready_sent.clone();
 
         Some(tokio::spawn(async move {
             let mut lines = reader.lines();
@@ -851,6 +902,12 @@ async fn execute_task_streaming(
                 }
 
                 apply_problem_matchers(&line, &matchers_for_stdout, &tid, &app, &last_rustc_ctx);
+
+                if ready_pattern.as_ref().is_some_and(|re| re.is_match(&line))
+                    && !ready_sent.swap(true, Ordering::SeqCst)
+                {
+                    emit_task_status(&app, &tid, "ready");
+                }
             }
         }))
     } else {
@@ -861,20 +918,10 @@ async fn execute_task_streaming(
         let reader = BufReader::new(stderr);
         let app = app_handle.clone();
         let tid = task_id.clone();
-        let matchers_for_stderr: Vec<_> = matchers
-            .iter()
-            .map(|m| ProblemMatcher {
-                name: m.name.clone(),
-                pattern: m.pattern.clone(),
-                file_group: m.file_group,
-                line_group: m.line_group,
-                column_group: m.column_group,
-                severity_group: m.severity_group,
-                message_group: m.message_group,
-                code_group: m.code_group,
-            })
-            .collect();
+        let matchers_for_stderr = matchers.clone();
         let track_rustc = has_rustc_matcher;
+        let ready_pattern = ready_pattern.clone();
+        let ready_sent = ready_sent.clone();
 
         Some(tokio::spawn(async move {
             let mut lines = reader.lines();
@@ -897,6 +944,12 @@ async fn execute_task_streaming(
                 }
 
                 apply_problem_matchers(&line, &matchers_for_stderr, &tid, &app, &last_rustc_ctx);
+
+                if ready_pattern.as_ref().is_some_and(|re| re.is_match(&line))
+                    && !ready_sent.swap(true, Ordering::SeqCst)
+                {
+                    emit_task_status(&app, &tid, "ready");
+                }
             }
         }))
     } else {
@@ -904,13 +957,7 @@ async fn execute_task_streaming(
     };
 
     if task.is_background {
-        let _ = app_handle.emit(
-            "task:status",
-            &TaskStatusEvent {
-                task_id: task_id.clone(),
-                status: "running".to_string(),
-            },
-        );
+        emit_task_status(&app_handle, &task_id, "running");
 
         info!(
             "Background task '{}' (id: {}) is running",
@@ -940,13 +987,7 @@ async fn execute_task_streaming(
                 "failed"
             };
 
-            let _ = app_for_bg.emit(
-                "task:status",
-                &TaskStatusEvent {
-                    task_id: tid_for_bg.clone(),
-                    status: final_status.to_string(),
-                },
-            );
+            emit_task_status(&app_for_bg, &tid_for_bg, final_status);
 
             info!(
                 "Background task '{}' (id: {}) finished with status: {}",
@@ -979,13 +1020,7 @@ async fn execute_task_streaming(
 
     let final_status = if success { "completed" } else { "failed" };
 
-    let _ = app_handle.emit(
-        "task:status",
-        &TaskStatusEvent {
-            task_id: task_id.clone(),
-            status: final_status.to_string(),
-        },
-    );
+    emit_task_status(&app_handle, &task_id, final_status);
 
     info!(
         "Task '{}' (id: {}) finished with status: {}",
@@ -1006,6 +1041,7 @@ async fn execute_task_streaming(
 }
 
 /// Run dependency tasks before the main task
+#[cfg(feature = "desktop")]
 async fn run_dependencies(
     depends_on: &[String],
     depends_order: Option<&str>,
@@ -1083,8 +1119,26 @@ async fn run_dependencies(
 // ============== Tauri Commands ==============
 
 /// Run a task by name
+#[cfg(feature = "desktop")]
 #[tauri::command]
 pub async fn tasks_run_task(
+    app: AppHandle,
+    task_name: String,
+    workspace_path: Option<String>,
+) -> Result<TaskResult, String> {
+    if let Some(workspace) = &workspace_path {
+        let mount = app
+            .state::<crate::remote::agent::RemoteAgentsState>()
+            .mount_for(workspace);
+        if let Some(mount) = mount {
+            return mount.run_task(task_name, workspace).await;
+        }
+    }
+    tasks_run_task_local(task_name, workspace_path).await
+}
+
+/// Run a task on this machine, as served by the remote agent
+pub async fn tasks_run_task_local(
     task_name: String,
     workspace_path: Option<String>,
 ) -> Result<TaskResult, String> {
@@ -1117,6 +1171,7 @@ pub async fn tasks_run_task(
 }
 
 /// List all available tasks
+#[cfg(feature = "desktop")]
 #[tauri::command]
 pub async fn tasks_list(workspace_path: Option<String>) -> Result<Vec<String>, String> {
     let workspace = workspace_path.unwrap_or_else(|| {
@@ -1130,6 +1185,7 @@ pub async fn tasks_list(workspace_path: Option<String>) -> Result<Vec<String>, S
 }
 
 /// Get task configuration
+#[cfg(feature = "desktop")]
 #[tauri::command]
 pub async fn tasks_get_config(workspace_path: Option<String>) -> Result<TasksConfig, String> {
     let workspace = workspace_path.unwrap_or_else(|| {
@@ -1142,6 +1198,7 @@ pub async fn tasks_get_config(workspace_path: Option<String>) -> Result<TasksCon
 }
 
 /// Execute a task with streaming output, problem matchers, and dependency support
+#[cfg(feature = "desktop")]
 #[tauri::command]
 pub async fn tasks_execute_task(
     task: TaskDefinition,
@@ -1184,13 +1241,7 @@ pub async fn tasks_execute_task(
             }
             Err(e) => {
                 error!("Task {} failed: {}", task_id_clone, e);
-                let _ = app_clone.emit(
-                    "task:status",
-                    &TaskStatusEvent {
-                        task_id: task_id_clone.clone(),
-                        status: "failed".to_string(),
-                    },
-                );
+                emit_task_status(&app_clone, &task_id_clone, "failed");
                 RUNNING_TASKS.lock().remove(&task_id_clone);
             }
         }
@@ -1207,14 +1258,16 @@ pub async fn tasks_execute_task(
 }
 
 /// Cancel a running task
+#[cfg(feature = "desktop")]
 #[tauri::command]
-pub async fn tasks_cancel_task(task_id: String) -> Result<(), String> {
+pub async fn tasks_cancel_task(task_id: String, app_handle: AppHandle) -> Result<(), String> {
     let running_task = RUNNING_TASKS.lock().remove(&task_id);
 
     match running_task {
         Some(task) => {
             info!("Cancelling task {}", task_id);
             task.abort_handle.abort();
+            emit_task_status(&app_handle, &task_id, "cancelled");
             Ok(())
         }
         None => Err(format!("Task '{}' not found or already completed", task_id)),
@@ -1222,12 +1275,14 @@ pub async fn tasks_cancel_task(task_id: String) -> Result<(), String> {
 }
 
 /// Get all accumulated task problems/diagnostics
+#[cfg(feature = "desktop")]
 #[tauri::command]
 pub async fn tasks_get_problems() -> Result<Vec<TaskDiagnosticEvent>, String> {
     Ok(TASK_PROBLEMS.lock().clone())
 }
 
 /// Clear accumulated task problems, optionally filtered by task_id
+#[cfg(feature = "desktop")]
 #[tauri::command]
 pub async fn tasks_clear_problems(task_id: Option<String>) -> Result<(), String> {
     let mut problems = TASK_PROBLEMS.lock();
@@ -1240,6 +1295,7 @@ pub async fn tasks_clear_problems(task_id: Option<String>) -> Result<(), String>
 }
 
 /// Filter problems by severity and/or file path
+#[cfg(feature = "desktop")]
 #[tauri::command]
 pub async fn tasks_filter_problems(
     severity: Option<String>,
@@ -1266,6 +1322,7 @@ pub async fn tasks_filter_problems(
 }
 
 /// Run a task with full dependency graph resolution
+#[cfg(feature = "desktop")]
 #[tauri::command]
 pub async fn tasks_run_with_dependencies(
     task_name: String,
@@ -1324,13 +1381,7 @@ pub async fn tasks_run_with_dependencies(
             }
             Err(e) => {
                 error!("Task {} failed: {}", task_id_clone, e);
-                let _ = app_clone.emit(
-                    "task:status",
-                    &TaskStatusEvent {
-                        task_id: task_id_clone.clone(),
-                        status: "failed".to_string(),
-                    },
-                );
+                emit_task_status(&app_clone, &task_id_clone, "failed");
                 RUNNING_TASKS.lock().remove(&task_id_clone);
             }
         }
@@ -1346,8 +1397,69 @@ pub async fn tasks_run_with_dependencies(
     Ok(task_id)
 }
 
+/// Run a task with its dependencies and wait until it is done
+///
+/// Background tasks never finish on their own, so they count as done once a
+/// line of output matches their problem matcher's `background.endsPattern`,
+/// or as soon as they are running when they have no such pattern. Gives up
+/// after `TASK_READY_TIMEOUT`, leaving the task running.
+#[cfg(feature = "desktop")]
+pub(crate) async fn run_task_until_ready(
+    task_name: &str,
+    workspace_path: &str,
+    app_handle: &AppHandle,
+) -> Result<(), String> {
+    let waits_for_pattern = load_tasks_config(workspace_path)?
+        .tasks
+        .iter()
+        .find(|t| t.label == task_name)
+        .is_some_and(|t| background_ready_pattern(&t.problem_matcher).is_some());
+
+    // Subscribe first so no status change of the new task is missed
+    let mut statuses = TASK_STATUS.subscribe();
+    let task_id = tasks_run_with_dependencies(
+        task_name.to_string(),
+        Some(workspace_path.to_string()),
+        app_handle.clone(),
+    )
+    .await?;
+
+    let deadline = tokio::time::Instant::now() + TASK_READY_TIMEOUT;
+    loop {
+        let received = tokio::time::timeout_at(deadline, statuses.recv())
+            .await
+            .map_err(|_| {
+                format!(
+                    "Task '{}' did not finish or become ready within {} minutes",
+                    task_name,
+                    TASK_READY_TIMEOUT.as_secs() / 60
+                )
+            })?;
+        let event = match received {
+            Ok(event) => event,
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                warn!("Missed {} task status updates", skipped);
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => {
+                return Err("Task status channel closed".to_string());
+            }
+        };
+        if event.task_id != task_id {
+            continue;
+        }
+        match event.status.as_str() {
+            "completed" | "ready" => return Ok(()),
+            "running" if !waits_for_pattern => return Ok(()),
+            "failed" => return Err(format!("Task '{}' failed", task_name)),
+            "cancelled" => return Err(format!("Task '{}' was cancelled", task_name)),
+            _ => {}
+        }
+    }
+}
+
 /// Resolve dependency order with cycle detection
-fn resolve_dependency_order(
+pub(crate) fn resolve_dependency_order(
     config: &TasksConfig,
     task_name: &str,
     visited: &mut std::collections::HashSet<String>,
@@ -1376,6 +1488,7 @@ fn resolve_dependency_order(
 }
 
 /// Resolve a task input variable (for prompts and pick strings)
+#[cfg(feature = "desktop")]
 #[tauri::command]
 pub async fn tasks_resolve_input(
     input_id: String,
@@ -1656,6 +1769,35 @@ mod tests {
         assert!(matchers.is_empty());
     }
 
+    // ---- background_ready_pattern ----
+
+    #[test]
+    fn ready_pattern_from_string_ends_pattern() {
+        let val = Some(serde_json::json!({
+            "background": {"beginsPattern": "Compiling", "endsPattern": "Watching for changes"}
+        }));
+        let re = background_ready_pattern(&val).unwrap();
+        assert!(re.is_match("[12:00] Watching for changes..."));
+    }
+
+    #[test]
+    fn ready_pattern_from_object_in_array() {
+        let val = Some(serde_json::json!([
+            "$tsc",
+            {"background": {"endsPattern": {"regexp": "^ready in \\d+ms"}}}
+        ]));
+        let re = background_ready_pattern(&val).unwrap();
+        assert!(re.is_match("ready in 312ms"));
+    }
+
+    #[test]
+    fn ready_pattern_missing_or_invalid() {
+        assert!(background_ready_pattern(&None).is_none());
+        assert!(background_ready_pattern(&Some(serde_json::json!("$tsc"))).is_none());
+        let invalid = Some(serde_json::json!({"background": {"endsPattern": "("}}));
+        assert!(background_ready_pattern(&invalid).is_none());
+    }
+
     // ---- parse_rustc_header ----
 
     #[test]
@@ -0,0 +1,627 @@
+//! Command blocks from OSC 633 shell integration
+//!
+//! Every command run in a terminal with shell integration becomes a block:
+//! its command line, working directory, start and end time, exit code and
+//! the byte range of its output in the terminal's output stream. Finished
+//! blocks keep their output as plain text (rendered through the emulator, so
+//! progress bars and colors collapse to what was on screen) and are
+//! persisted per workspace as JSON lines in the app data directory.
+
+use std::collections::{HashMap, VecDeque};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Emitter};
+use tracing::warn;
+use uuid::Uuid;
+
+use super::constants::{
+    COMMAND_BLOCK_CAPTURE_BYTES, COMMAND_BLOCK_OUTPUT_MAX_BYTES, COMMAND_HISTORY_MAX_BLOCKS,
+};
+use super::emulator::{ExportFormat, TerminalEmulator};
+use super::problems::{self, BlockProblemMatcher, ProblemScan};
+use super::protocol::{ShellIntegrationEvent, parse_osc_633_spans};
+use crate::diagnostics::UnifiedDiagnostic;
+
+/// Longest partial OSC 633 sequence held back between output chunks
+const MAX_CARRY_BYTES: usize = 4096;
+
+/// Scrollback used when rendering a block's output to text
+const RENDER_SCROLLBACK_LINES: usize = 20_000;
+
+/// One command run in a terminal
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandBlock {
+    pub id: String,
+    pub terminal_id: String,
+    pub workspace: Option<String>,
+    /// Empty when the shell did not report the command line
+    pub command_line: String,
+    pub cwd: Option<String>,
+    /// Milliseconds since the Unix epoch
+    pub started_at: i64,
+    pub ended_at: Option<i64>,
+    pub exit_code: Option<i32>,
+    /// Offset of the first output byte in the terminal's output stream
+    pub output_start: u64,
+    /// Offset just past the last output byte
+    pub output_end: Option<u64>,
+    /// Output as plain text
+    pub output: String,
+    /// Whether the start of the output was cut off
+    #[serde(default)]
+    pub output_truncated: bool,
+}
+
+impl CommandBlock {
+    pub fn failed(&self) -> bool {
+        self.exit_code.is_some_and(|code| code != 0)
+    }
+
+    /// The block formatted as context for an AI chat message
+    pub fn ai_context(&self) -> String {
+        let mut context = format!("Command: `{}`\n", self.command_line);
+        if let Some(cwd) = &self.cwd {
+            context.push_str(&format!("Working directory: {}\n", cwd));
+        }
+        match self.exit_code {
+            Some(code) => context.push_str(&format!("Exit code: {}\n", code)),
+            None => context.push_str("Exit code: unknown\n"),
+        }
+        if self.output_truncated {
+            context.push_str("Output (beginning cut off):\n");
+        } else {
+            context.push_str("Output:\n");
+        }
+        context.push_str(&format!("```text\n{}\n```", self.output));
+        context
+    }
+}
+
+/// Command line as reported by `633;E`, with its escapes undone
+fn unescape_command_line(raw: &str) -> String {
+    let mut out = String::with_capacity(raw.len());
+    let mut rest = raw;
+    while let Some(index) = rest.find('\\') {
+        out.push_str(&rest[..index]);
+        rest = &rest[index..];
+        if let Some(after) = rest.strip_prefix("\\\\") {
+            out.push('\\');
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("\\x3b") {
+            out.push(';');
+            rest = after;
+        } else {
+            out.push('\\');
+            rest = &rest[1..];
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Render raw terminal output to plain text, keeping the end if too long
+fn render_output(raw: &[u8], cols: usize, rows: usize) -> (String, bool) {
+    let mut emulator = TerminalEmulator::new(cols, rows, RENDER_SCROLLBACK_LINES);
+    emulator.process(raw);
+    let text = emulator.export(ExportFormat::Text);
+    let text = text.trim_end();
+    if text.len() <= COMMAND_BLOCK_OUTPUT_MAX_BYTES {
+        return (text.to_string(), false);
+    }
+    let mut start = text.len() - COMMAND_BLOCK_OUTPUT_MAX_BYTES;
+    while !text.is_char_boundary(start) {
+        start += 1;
+    }
+    (text[start..].to_string(), true)
+}
+
+/// Start of a trailing OSC 633 sequence whose end has not arrived yet,
+/// including a chunk that ends partway through the introducer
+fn incomplete_sequence_start(text: &str) -> Option<usize> {
+    const INTRODUCER: &str = "\x1b]633;";
+    if let Some(start) = text.rfind(INTRODUCER) {
+        let tail = &text[start..];
+        if !tail.contains('\x07') && !tail.contains("\x1b\\") {
+            return (tail.len() <= MAX_CARRY_BYTES).then_some(start);
+        }
+    }
+    let start = text.rfind('\x1b')?;
+    INTRODUCER.starts_with(&text[start..]).then_some(start)
+}
+
+fn now_millis() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
+
+struct RunningBlock {
+    block: CommandBlock,
+    output: VecDeque<u8>,
+    truncated: bool,
+    problems: ProblemScan,
+}
+
+/// Builds command blocks from one terminal's output stream
+pub(super) struct CommandTracker {
+    terminal_id: String,
+    workspace: Option<String>,
+    cols: usize,
+    rows: usize,
+    /// Bytes of output processed so far
+    offset: u64,
+    /// Start of an OSC 633 sequence split across chunks
+    carry: String,
+    cwd: Option<String>,
+    /// Command line reported before the command started
+    command_line: Option<String>,
+    running: Option<RunningBlock>,
+    /// Problem matching on running blocks, if enabled
+    problems: Option<BlockProblemMatcher>,
+    /// Problems of finished blocks not reported yet, by source name
+    problem_reports: Vec<(String, Vec<UnifiedDiagnostic>)>,
+}
+
+impl CommandTracker {
+    pub(super) fn new(
+        terminal_id: String,
+        workspace: Option<String>,
+        cols: usize,
+        rows: usize,
+    ) -> Self {
+        Self {
+            terminal_id,
+            workspace,
+            cols,
+            rows,
+            offset: 0,
+            carry: String::new(),
+            cwd: None,
+            command_line: None,
+            running: None,
+            problems: None,
+            problem_reports: Vec::new(),
+        }
+    }
+
+    /// Enable problem matching with the named builtin matchers, or disable
+    /// it with none
+    pub(super) fn set_problem_matchers(&mut self, names: &[String]) -> Result<(), String> {
+        self.problems = if names.is_empty() {
+            None
+        } else {
+            Some(BlockProblemMatcher::new(names)?)
+        };
+        Ok(())
+    }
+
+    pub(super) fn problem_matchers(&self) -> Vec<String> {
+        self.problems
+            .as_ref()
+            .map(|problems| problems.names().to_vec())
+            .unwrap_or_default()
+    }
+
+    pub(super) fn resize(&mut self, cols: usize, rows: usize) {
+        self.cols = cols;
+        self.rows = rows;
+    }
+
+    /// Feed a chunk of output, returning the blocks it finished
+    pub(super) fn push(&mut self, data: &str) -> Vec<CommandBlock> {
+        let mut text = std::mem::take(&mut self.carry);
+        text.push_str(data);
+
+        if let Some(start) = incomplete_sequence_start(&text) {
+            self.carry = text.split_off(start);
+        }
+
+        let base = self.offset;
+        let mut finished = Vec::new();
+        let mut cursor = 0;
+        for (span, event) in parse_osc_633_spans(&text) {
+            self.capture(&text[cursor..span.start]);
+            cursor = span.end;
+            self.apply(
+                event,
+                base + span.start as u64,
+                base + span.end as u64,
+                &mut finished,
+            );
+        }
+        self.capture(&text[cursor..]);
+        self.offset = base + text.len() as u64;
+        finished
+    }
+
+    fn capture(&mut self, output: &str) {
+        let Some(running) = self.running.as_mut() else {
+            return;
+        };
+        if let Some(matcher) = &self.problems {
+            matcher.scan(&mut running.problems, &running.block.id, output);
+        }
+        running.output.extend(output.as_bytes());
+        let excess = running
+            .output
+            .len()
+            .saturating_sub(COMMAND_BLOCK_CAPTURE_BYTES);
+        if excess > 0 {
+            running.output.drain(..excess);
+            running.truncated = true;
+        }
+    }
+
+    fn apply(
+        &mut self,
+        event: ShellIntegrationEvent,
+        start: u64,
+        end: u64,
+        finished: &mut Vec<CommandBlock>,
+    ) {
+        match event {
+            ShellIntegrationEvent::CommandExecuted {
+                command_line: Some(command_line),
+            } => {
+                let command_line = unescape_command_line(&command_line);
+                match self.running.as_mut() {
+                    // Reported after the command started; the first report wins
+                    Some(running) if running.block.command_line.is_empty() => {
+                        running.block.command_line = command_line;
+                    }
+                    Some(_) => {}
+                    None => self.command_line = Some(command_line),
+                }
+            }
+            ShellIntegrationEvent::CommandExecuted { command_line: None } => {
+                finished.extend(self.finish(None, start));
+                self.running = Some(RunningBlock {
+                    block: CommandBlock {
+                        id: Uuid::new_v4().to_string(),
+                        terminal_id: self.terminal_id.clone(),
+                        workspace: self.workspace.clone(),
+                        command_line: self.command_line.take().unwrap_or_default(),
+                        cwd: self.cwd.clone(),
+                        started_at: now_millis(),
+                        ended_at: None,
+                        exit_code: None,
+                        output_start: end,
+                        output_end: None,
+                        output: String::new(),
+                        output_truncated: false,
+                    },
+                    output: VecDeque::new(),
+                    truncated: false,
+                    problems: ProblemScan::default(),
+                });
+            }
+            ShellIntegrationEvent::CommandFinished { exit_code } => {
+                finished.extend(self.finish(exit_code, start));
+            }
+            // A new prompt without a finish mark ends the command with an unknown status
+            ShellIntegrationEvent::PromptStart => finished.extend(self.finish(None, start)),
+            ShellIntegrationEvent::SetCwd { cwd } => self.cwd = Some(cwd),
+            ShellIntegrationEvent::CommandStart | ShellIntegrationEvent::SetMark => {}
+        }
+    }
+
+    fn finish(&mut self, exit_code: Option<i32>, output_end: u64) -> Option<CommandBlock> {
+        let mut running = self.running.take()?;
+        let (output, truncated) =
+            render_output(running.output.make_contiguous(), self.cols, self.rows);
+        let block = &mut running.block;
+        block.ended_at = Some(now_millis());
+        block.exit_code = exit_code;
+        block.output_end = Some(output_end);
+        block.output = output;
+        block.output_truncated = truncated || running.truncated;
+        if let Some(matcher) = &self.problems {
+            self.problem_reports
+                .extend(matcher.diagnostics(&running.block, running.problems));
+        }
+        Some(running.block)
+    }
+}
+
+/// History file of a workspace
+fn history_file(dir: &Path, workspace: &str) -> PathBuf {
+    let digest = Sha256::digest(workspace.as_bytes());
+    let name: String = digest[..8].iter().map(|b| format!("{:02x}", b)).collect();
+    dir.join(format!("{}.jsonl", name))
+}
+
+/// Read a history file, compacting it once it holds far more blocks than are kept
+fn load_history(path: &Path) -> VecDeque<CommandBlock> {
+    let Ok(content) = fs::read_to_string(path) else {
+        return VecDeque::new();
+    };
+    let mut blocks: VecDeque<CommandBlock> = content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+    if blocks.len() > COMMAND_HISTORY_MAX_BLOCKS {
+        blocks.drain(..blocks.len() - COMMAND_HISTORY_MAX_BLOCKS);
+        let compacted: String = blocks
+            .iter()
+            .filter_map(|block| serde_json::to_string(block).ok())
+            .map(|line| line + "\n")
+            .collect();
+        if let Err(e) = fs::write(path, compacted) {
+            warn!(
+                "Failed to compact command history {}: {}",
+                path.display(),
+                e
+            );
+        }
+    }
+    blocks
+}
+
+fn append_history(path: &Path, block: &CommandBlock) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create command history directory: {}", e))?;
+    }
+    let line = serde_json::to_string(block)
+        .map_err(|e| format!("Failed to serialize command block: {}", e))?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("Failed to open command history: {}", e))?;
+    writeln!(file, "{}", line).map_err(|e| format!("Failed to write command history: {}", e))
+}
+
+/// Command blocks of all terminals, grouped by workspace
+///
+/// Blocks of terminals without a workspace are kept in memory only.
+#[derive(Clone)]
+pub struct CommandHistory {
+    dir: Option<PathBuf>,
+    workspaces: Arc<Mutex<HashMap<String, VecDeque<CommandBlock>>>>,
+}
+
+impl Default for CommandHistory {
+    fn default() -> Self {
+        Self::with_dir(dirs::data_dir().map(|dir| dir.join("Cortex").join("terminal-history")))
+    }
+}
+
+impl CommandHistory {
+    fn with_dir(dir: Option<PathBuf>) -> Self {
+        Self {
+            dir,
+            workspaces: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Run `f` on a workspace's blocks, loading them from disk on first use
+    fn with_blocks<R>(
+        &self,
+        workspace: Option<&str>,
+        f: impl FnOnce(&mut VecDeque<CommandBlock>) -> R,
+    ) -> R {
+        let mut workspaces = self.workspaces.lock();
+        let key = workspace.unwrap_or_default().to_string();
+        let blocks = workspaces
+            .entry(key)
+            .or_insert_with(|| match (workspace, &self.dir) {
+                (Some(workspace), Some(dir)) => load_history(&history_file(dir, workspace)),
+                _ => VecDeque::new(),
+            });
+        f(blocks)
+    }
+
+    /// Add a finished block and persist it
+    fn insert(&self, block: CommandBlock) {
+        // Append only once the workspace is loaded, or the first block of a
+        // session is read back from disk and then added a second time
+        let workspace = block.workspace.clone();
+        self.with_blocks(workspace.as_deref(), |blocks| {
+            if let (Some(workspace), Some(dir)) = (&workspace, &self.dir) {
+                if let Err(e) = append_history(&history_file(dir, workspace), &block) {
+                    warn!("{}", e);
+                }
+            }
+            blocks.push_back(block);
+            if blocks.len() > COMMAND_HISTORY_MAX_BLOCKS {
+                blocks.pop_front();
+            }
+        });
+    }
+
+    /// Feed a terminal's output to its tracker and record the blocks it
+    /// finished, reporting their problems if matching is enabled
+    pub(super) fn track(&self, app: &AppHandle, tracker: &mut CommandTracker, data: &str) {
+        let finished = tracker.push(data);
+        for (source_name, diagnostics) in std::mem::take(&mut tracker.problem_reports) {
+            problems::report(app, &source_name, diagnostics);
+        }
+        for block in finished {
+            let _ = app.emit("terminal:command-block", &block);
+            self.insert(block);
+        }
+    }
+
+    /// Blocks of a workspace, newest first
+    pub fn list(
+        &self,
+        workspace: Option<&str>,
+        terminal_id: Option<&str>,
+        limit: Option<usize>,
+    ) -> Vec<CommandBlock> {
+        self.with_blocks(workspace, |blocks| {
+            blocks
+                .iter()
+                .rev()
+                .filter(|block| terminal_id.is_none_or(|id| block.terminal_id == id))
+                .take(limit.unwrap_or(usize::MAX))
+                .cloned()
+                .collect()
+        })
+    }
+
+    /// Blocks whose command line or output contains `query`, newest first
+    pub fn search(
+        &self,
+        workspace: Option<&str>,
+        query: &str,
+        failed_only: bool,
+        limit: Option<usize>,
+    ) -> Vec<CommandBlock> {
+        let query = query.to_lowercase();
+        self.with_blocks(workspace, |blocks| {
+            blocks
+                .iter()
+                .rev()
+                .filter(|block| !failed_only || block.failed())
+                .filter(|block| {
+                    block.command_line.to_lowercase().contains(&query)
+                        || block.output.to_lowercase().contains(&query)
+                })
+                .take(limit.unwrap_or(usize::MAX))
+                .cloned()
+                .collect()
+        })
+    }
+
+    /// Find a block among the workspaces loaded so far
+    pub fn get(&self, block_id: &str) -> Option<CommandBlock> {
+        self.workspaces
+            .lock()
+            .values()
+            .flat_map(|blocks| blocks.iter())
+            .find(|block| block.id == block_id)
+            .cloned()
+    }
+
+    /// Forget a workspace's blocks, on disk too
+    pub fn clear(&self, workspace: Option<&str>) -> Result<(), String> {
+        self.workspaces
+            .lock()
+            .insert(workspace.unwrap_or_default().to_string(), VecDeque::new());
+        if let (Some(workspace), Some(dir)) = (workspace, &self.dir) {
+            let path = history_file(dir, workspace);
+            if path.exists() {
+                fs::remove_file(&path)
+                    .map_err(|e| format!("Failed to remove command history: {}", e))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    fn tracker() -> CommandTracker {
+        CommandTracker::new("t1".to_string(), Some("/ws".to_string()), 80, 24)
+    }
+
+    const SESSION: &str = "\x1b]633;P;Cwd=/ws\x07$ \x1b]633;C\x07\x1b]633;E;cargo build\x07\
+        \x1b[31merror\x1b[0m: failed\r\n\x1b]633;D;101\x07\x1b]633;A\x07$ ";
+
+    #[test]
+    fn builds_blocks_from_shell_marks() {
+        let blocks = tracker().push(SESSION);
+        assert_eq!(blocks.len(), 1);
+        let block = &blocks[0];
+        assert_eq!(block.command_line, "cargo build");
+        assert_eq!(block.cwd.as_deref(), Some("/ws"));
+        assert_eq!(block.exit_code, Some(101));
+        assert!(block.failed());
+        assert_eq!(block.output, "error: failed");
+
+        let start = block.output_start as usize;
+        let end = block.output_end.unwrap() as usize;
+        assert!(SESSION[start..end].starts_with("\x1b]633;E;cargo build"));
+        assert!(SESSION[start..end].ends_with("failed\r\n"));
+    }
+
+    #[test]
+    fn handles_sequences_split_across_chunks() {
+        let whole = tracker().push(SESSION).remove(0);
+        for size in [1, 3, 5, 7] {
+            let mut tracker = tracker();
+            let mut blocks = Vec::new();
+            for chunk in SESSION.as_bytes().chunks(size) {
+                blocks.extend(tracker.push(std::str::from_utf8(chunk).unwrap()));
+            }
+            assert_eq!(blocks.len(), 1);
+            assert_eq!(blocks[0].command_line, whole.command_line);
+            assert_eq!(blocks[0].output, whole.output);
+            assert_eq!(blocks[0].output_start, whole.output_start);
+            assert_eq!(blocks[0].output_end, whole.output_end);
+        }
+    }
+
+    #[test]
+    fn problems_before_the_kept_output_are_reported() {
+        let mut tracker = tracker();
+        tracker.set_problem_matchers(&["$gcc".to_string()]).unwrap();
+        let filler = "x".repeat(79) + "\r\n";
+        let mut blocks = tracker.push("\x1b]633;E;make\x07\x1b]633;C\x07main.c:1:1: error: x\r\n");
+        for _ in 0..COMMAND_BLOCK_OUTPUT_MAX_BYTES / 64 {
+            blocks.extend(tracker.push(&filler));
+        }
+        blocks.extend(tracker.push("\x1b]633;D;2\x07"));
+
+        assert!(blocks[0].output_truncated);
+        assert!(!blocks[0].output.contains("error"));
+        let (source_name, diagnostics) = tracker.problem_reports.remove(0);
+        assert_eq!(source_name, "make (/ws)");
+        assert_eq!(diagnostics.len(), 1);
+    }
+
+    #[test]
+    fn command_line_reported_before_start_is_used() {
+        let blocks =
+            tracker().push("\x1b]633;E;echo a\\x3bb \\\\n\x07\x1b]633;C\x07a\r\n\x1b]633;D;0\x07");
+        assert_eq!(blocks[0].command_line, "echo a;b \\n");
+        assert!(!blocks[0].failed());
+    }
+
+    #[test]
+    fn prompt_without_finish_mark_ends_the_block() {
+        let blocks = tracker().push("\x1b]633;C\x07out\r\n\x1b]633;A\x07");
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].exit_code, None);
+        assert_eq!(blocks[0].output, "out");
+    }
+
+    #[test]
+    fn history_persists_per_workspace() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = CommandHistory::with_dir(Some(dir.path().to_path_buf()));
+        let mut block = tracker().push(SESSION).remove(0);
+        history.insert(block.clone());
+        assert_eq!(history.list(Some("/ws"), None, None).len(), 1);
+        block.id = "other".to_string();
+        block.workspace = Some("/elsewhere".to_string());
+        history.insert(block);
+
+        let reloaded = CommandHistory::with_dir(Some(dir.path().to_path_buf()));
+        let blocks = reloaded.list(Some("/ws"), None, None);
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].command_line, "cargo build");
+        assert_eq!(reloaded.search(Some("/ws"), "ERROR", true, None).len(), 1);
+        assert!(
+            reloaded
+                .search(Some("/ws"), "warning", false, None)
+                .is_empty()
+        );
+        assert!(reloaded.get(&blocks[0].id).is_some());
+
+        reloaded.clear(Some("/ws")).unwrap();
+        let reloaded = CommandHistory::with_dir(Some(dir.path().to_path_buf()));
+        assert!(reloaded.list(Some("/ws"), None, None).is_empty());
+        assert_eq!(reloaded.list(Some("/elsewhere"), None, None).len(), 1);
+    }
+}
@@ -2,12 +2,19 @@
 //!
 //! Exposes terminal functionality to the frontend via Tauri's IPC system.
 
-use tauri::{AppHandle, Manager};
+use std::path::{Path, PathBuf};
+
+use tauri::{AppHandle, Emitter, Manager, State};
 use tracing::info;
 
+use super::blocks::CommandBlock;
 use super::process::{get_process_on_port_impl, kill_process_by_pid, list_listening_ports_impl};
+use super::recording::{RecordingInfo, ReplayInfo};
 use super::state::TerminalState;
-use super::types::{CreateTerminalOptions, PortProcess, TerminalInfo, UpdateTerminalOptions};
+use super::types::{
+    CreateTerminalOptions, PortProcess, RestoredTerminal, TerminalInfo, UpdateTerminalOptions,
+};
+use crate::remote::agent::RemoteAgentsState;
 
 // ===== Terminal Commands =====
 
@@ -16,8 +23,16 @@ pub async fn terminal_create(
     app: AppHandle,
     options: Option<CreateTerminalOptions>,
 ) -> Result<TerminalInfo, String> {
+    let options = options.unwrap_or_default();
+    // Terminals in a remote workspace run on its host
+    let agents = app.state::<RemoteAgentsState>();
+    if let Some(mount) = options.cwd.as_deref().and_then(|cwd| agents.mount_for(cwd)) {
+        let info = mount.terminal_create(options).await?;
+        let _ = app.emit("terminal:created", &info);
+        return Ok(info);
+    }
     let state = app.state::<TerminalState>();
-    state.create_terminal(&app, options.unwrap_or_default())
+    state.create_terminal(&app, options)
 }
 
 #[tauri::command]
@@ -26,6 +41,12 @@ pub async fn terminal_write(
     terminal_id: String,
     data: String,
 ) -> Result<(), String> {
+    if let Some(agent) = app
+        .state::<RemoteAgentsState>()
+        .terminal_agent(&terminal_id)
+    {
+        return agent.terminal_write(terminal_id, data).await;
+    }
     let state = app.state::<TerminalState>();
     state.write_terminal(&terminal_id, &data)
 }
@@ -47,12 +68,24 @@ pub async fn terminal_resize(
     cols: u16,
     rows: u16,
 ) -> Result<(), String> {
+    if let Some(agent) = app
+        .state::<RemoteAgentsState>()
+        .terminal_agent(&terminal_id)
+    {
+        return agent.terminal_resize(terminal_id, cols, rows).await;
+    }
     let state = app.state::<TerminalState>();
     state.resize_terminal(&terminal_id, cols, rows)
 }
 
 #[tauri::command]
 pub async fn terminal_close(app: AppHandle, terminal_id: String) -> Result<(), String> {
+    if let Some(agent) = app
+        .state::<RemoteAgentsState>()
+        .terminal_agent(&terminal_id)
+    {
+        return agent.terminal_close(terminal_id).await;
+    }
     let state = app.state::<TerminalState>();
     state.close_terminal(&app, &terminal_id)
 }
@@ -74,18 +107,38 @@ pub async fn terminal_get(
 
 #[tauri::command]
 pub async fn terminal_send_interrupt(app: AppHandle, terminal_id: String) -> Result<(), String> {
+    if let Some(agent) = app
+        .state::<RemoteAgentsState>()
+        .terminal_agent(&terminal_id)
+    {
+        return agent.terminal_write(terminal_id, "\x03".to_string()).await;
+    }
     let state = app.state::<TerminalState>();
     state.send_interrupt(&terminal_id)
 }
 
 #[tauri::command]
 pub async fn terminal_send_eof(app: AppHandle, terminal_id: String) -> Result<(), String> {
+    if let Some(agent) = app
+        .state::<RemoteAgentsState>()
+        .terminal_agent(&terminal_id)
+    {
+        return agent.terminal_write(terminal_id, "\x04".to_string()).await;
+    }
     let state = app.state::<TerminalState>();
     state.send_eof(&terminal_id)
 }
 
 #[tauri::command]
 pub async fn terminal_ack(app: AppHandle, terminal_id: String, bytes: usize) -> Result<(), String> {
+    // Remote output is not flow controlled
+    if app
+        .state::<RemoteAgentsState>()
+        .terminal_agent(&terminal_id)
+        .is_some()
+    {
+        return Ok(());
+    }
     let state = app.state::<TerminalState>();
     state.acknowledge_output(&terminal_id, bytes)
 }
@@ -96,6 +149,13 @@ pub async fn terminal_close_all(app: AppHandle) -> Result<(), String> {
     state.close_all(&app)
 }
 
+/// Reattach to persistent terminals that survived the last app session
+#[tauri::command]
+pub async fn terminal_restore(app: AppHandle) -> Result<Vec<RestoredTerminal>, String> {
+    let state = app.state::<TerminalState>();
+    state.restore_terminals(&app)
+}
+
 #[tauri::command]
 pub async fn terminal_get_default_shell() -> String {
     TerminalState::get_default_shell()
@@ -282,6 +342,266 @@ pub async fn terminal_search(
     ))
 }
 
+/// Search a terminal's full scrollback on the backend
+///
+/// Unlike `terminal_search`, lines soft-wrapped by the terminal width are
+/// matched as the single line the program printed.
+#[tauri::command]
+pub async fn terminal_search_scrollback(
+    app: AppHandle,
+    terminal_id: String,
+    query: String,
+    case_sensitive: Option<bool>,
+    max_results: Option<usize>,
+) -> Result<super::emulator::GridSearchResult, String> {
+    let state = app.state::<TerminalState>();
+    state.search_scrollback(
+        &terminal_id,
+        &query,
+        case_sensitive.unwrap_or(false),
+        max_results,
+    )
+}
+
+// ===== Scrollback Commands =====
+
+/// Export a terminal's scrollback as plain text or HTML with colors
+#[tauri::command]
+pub async fn terminal_export_scrollback(
+    app: AppHandle,
+    terminal_id: String,
+    format: super::emulator::ExportFormat,
+) -> Result<String, String> {
+    let state = app.state::<TerminalState>();
+    state.export_scrollback(&terminal_id, format)
+}
+
+/// Get escape sequences that redraw a terminal's screen and scrollback,
+/// for attaching a new view without replaying its raw output
+#[tauri::command]
+pub async fn terminal_snapshot(app: AppHandle, terminal_id: String) -> Result<String, String> {
+    let state = app.state::<TerminalState>();
+    state.snapshot_terminal(&terminal_id)
+}
+
+// ===== Command Block Commands =====
+
+/// List recorded command blocks of a workspace, newest first
+#[tauri::command]
+pub async fn terminal_command_blocks_list(
+    app: AppHandle,
+    workspace: Option<String>,
+    terminal_id: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<CommandBlock>, String> {
+    let state = app.state::<TerminalState>();
+    Ok(state
+        .command_history()
+        .list(workspace.as_deref(), terminal_id.as_deref(), limit))
+}
+
+/// Search recorded command blocks by command line and output
+#[tauri::command]
+pub async fn terminal_command_blocks_search(
+    app: AppHandle,
+    workspace: Option<String>,
+    query: String,
+    failed_only: Option<bool>,
+    limit: Option<usize>,
+) -> Result<Vec<CommandBlock>, String> {
+    let state = app.state::<TerminalState>();
+    Ok(state.command_history().search(
+        workspace.as_deref(),
+        &query,
+        failed_only.unwrap_or(false),
+        limit,
+    ))
+}
+
+fn command_block(app: &AppHandle, block_id: &str) -> Result<CommandBlock, String> {
+    app.state::<TerminalState>()
+        .command_history()
+        .get(block_id)
+        .ok_or_else(|| format!("Command block not found: {}", block_id))
+}
+
+/// Run a block's command again, in the given terminal or the one it ran in
+#[tauri::command]
+pub async fn terminal_command_block_rerun(
+    app: AppHandle,
+    block_id: String,
+    terminal_id: Option<String>,
+) -> Result<(), String> {
+    let block = command_block(&app, &block_id)?;
+    if block.command_line.is_empty() {
+        return Err("The command line of this block is unknown".to_string());
+    }
+    let terminal_id = terminal_id.unwrap_or(block.terminal_id);
+    let state = app.state::<TerminalState>();
+    state.write_terminal(&terminal_id, &format!("{}\r", block.command_line))
+}
+
+/// Copy a block's output to the clipboard
+#[tauri::command]
+pub async fn terminal_command_block_copy_output(
+    app: AppHandle,
+    block_id: String,
+) -> Result<(), String> {
+    use tauri_plugin_clipboard_manager::ClipboardExt;
+
+    let block = command_block(&app, &block_id)?;
+    app.clipboard()
+        .write_text(block.output)
+        .map_err(|e| format!("Failed to copy to clipboard: {}", e))
+}
+
+/// Send a block's command, exit code and output to an AI session
+#[tauri::command]
+pub async fn terminal_command_block_send_to_ai(
+    app: AppHandle,
+    ai_state: State<'_, crate::ai::AIState>,
+    block_id: String,
+    session_id: String,
+    prompt: Option<String>,
+) -> Result<(), String> {
+    let block = command_block(&app, &block_id)?;
+    let prompt = prompt.unwrap_or_else(|| {
+        if block.failed() {
+            "This command failed. Explain why and how to fix it.".to_string()
+        } else {
+            "Explain the output of this command.".to_string()
+        }
+    });
+    let content = format!("{}\n\n{}", prompt, block.ai_context());
+    ai_state
+        .session_manager
+        .send_message(&session_id, content)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Enable problem matching on a terminal's command blocks
+///
+/// `matchers` names builtin task problem matchers such as `$rustc` or
+/// `$tsc`; an empty list turns matching off.
+#[tauri::command]
+pub async fn terminal_set_problem_matchers(
+    app: AppHandle,
+    terminal_id: String,
+    matchers: Vec<String>,
+) -> Result<(), String> {
+    let state = app.state::<TerminalState>();
+    state.set_problem_matchers(&terminal_id, &matchers)
+}
+
+/// Get the problem matchers enabled on a terminal
+#[tauri::command]
+pub async fn terminal_get_problem_matchers(
+    app: AppHandle,
+    terminal_id: String,
+) -> Result<Vec<String>, String> {
+    let state = app.state::<TerminalState>();
+    state.problem_matchers(&terminal_id)
+}
+
+/// Forget the command blocks of a workspace
+#[tauri::command]
+pub async fn terminal_command_blocks_clear(
+    app: AppHandle,
+    workspace: Option<String>,
+) -> Result<(), String> {
+    let state = app.state::<TerminalState>();
+    state.command_history().clear(workspace.as_deref())
+}
+
+// ===== Recording Commands =====
+
+/// Start recording a terminal in asciicast v2 format
+///
+/// Without a `path` the recording goes to the app data directory. With
+/// `redact`, API keys and stored secrets are replaced in the output.
+#[tauri::command]
+pub async fn terminal_recording_start(
+    app: AppHandle,
+    terminal_id: String,
+    path: Option<String>,
+    redact: Option<bool>,
+) -> Result<RecordingInfo, String> {
+    let state = app.state::<TerminalState>();
+    state.start_recording(
+        &terminal_id,
+        path.map(PathBuf::from),
+        redact.unwrap_or(false),
+    )
+}
+
+/// Stop recording a terminal
+#[tauri::command]
+pub async fn terminal_recording_stop(
+    app: AppHandle,
+    terminal_id: String,
+) -> Result<RecordingInfo, String> {
+    let state = app.state::<TerminalState>();
+    state.stop_recording(&terminal_id)
+}
+
+/// Get the active recording of a terminal, if any
+#[tauri::command]
+pub async fn terminal_recording_status(
+    app: AppHandle,
+    terminal_id: String,
+) -> Result<Option<RecordingInfo>, String> {
+    let state = app.state::<TerminalState>();
+    state.recording(&terminal_id)
+}
+
+/// Replay an asciicast recording into a read-only terminal
+///
+/// Output is sent as `terminal:replay-output` events under the returned
+/// replay ID. `speed` multiplies the playback rate and `idle_limit` caps
+/// pauses, in seconds.
+#[tauri::command]
+pub async fn terminal_replay_start(
+    app: AppHandle,
+    path: String,
+    speed: Option<f64>,
+    idle_limit: Option<f64>,
+) -> Result<ReplayInfo, String> {
+    let state = app.state::<TerminalState>();
+    state
+        .replays()
+        .start(&app, Path::new(&path), speed.unwrap_or(1.0), idle_limit)
+}
+
+/// Change the speed of a running replay
+#[tauri::command]
+pub async fn terminal_replay_set_speed(
+    app: AppHandle,
+    replay_id: String,
+    speed: f64,
+) -> Result<(), String> {
+    let state = app.state::<TerminalState>();
+    state.replays().set_speed(&replay_id, speed)
+}
+
+/// Pause or resume a replay
+#[tauri::command]
+pub async fn terminal_replay_set_paused(
+    app: AppHandle,
+    replay_id: String,
+    paused: bool,
+) -> Result<(), String> {
+    let state = app.state::<TerminalState>();
+    state.replays().set_paused(&replay_id, paused)
+}
+
+/// Stop a replay
+#[tauri::command]
+pub async fn terminal_replay_stop(app: AppHandle, replay_id: String) -> Result<(), String> {
+    let state = app.state::<TerminalState>();
+    state.replays().stop(&replay_id)
+}
+
 // ===== Shell Integration Protocol Commands =====
 
 /// Parse OSC 633 shell integration sequences from terminal output
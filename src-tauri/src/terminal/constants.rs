@@ -36,3 +36,18 @@ pub const DANGEROUS_ENV_VARS: &[&str] = &[
     "ENV",
     "ZDOTDIR",
 ];
+
+/// Scrollback kept per persistent terminal for replay on reattach (1MB)
+pub const PTY_HOST_SCROLLBACK_BYTES: usize = 1024 * 1024;
+
+/// Scrollback lines kept by the server-side emulator of each terminal
+pub const EMULATOR_SCROLLBACK_LINES: usize = 5_000;
+
+/// Raw output captured per running command, keeping the most recent bytes (1MB)
+pub const COMMAND_BLOCK_CAPTURE_BYTES: usize = 1024 * 1024;
+
+/// Plain-text output kept per command block, keeping the end (64KB)
+pub const COMMAND_BLOCK_OUTPUT_MAX_BYTES: usize = 64 * 1024;
+
+/// Command blocks kept per workspace
+pub const COMMAND_HISTORY_MAX_BLOCKS: usize = 500;
@@ -0,0 +1,150 @@
+//! Grid cells, colors and rows
+
+/// Cell color as set by SGR sequences
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Color {
+    #[default]
+    Default,
+    /// Index into the 256-color palette
+    Indexed(u8),
+    Rgb(u8, u8, u8),
+}
+
+pub mod flags {
+    pub const BOLD: u8 = 1;
+    pub const DIM: u8 = 1 << 1;
+    pub const ITALIC: u8 = 1 << 2;
+    pub const UNDERLINE: u8 = 1 << 3;
+    pub const INVERSE: u8 = 1 << 4;
+    pub const STRIKETHROUGH: u8 = 1 << 5;
+    /// Right half of a double-width character; carries no text
+    pub const WIDE_SPACER: u8 = 1 << 6;
+}
+
+/// Graphic rendition of a cell
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Style {
+    pub fg: Color,
+    pub bg: Color,
+    pub flags: u8,
+}
+
+impl Style {
+    pub fn has(&self, flag: u8) -> bool {
+        self.flags & flag != 0
+    }
+
+    /// Style of cells cleared by erase operations, which keep only the background
+    pub(super) fn erased(&self) -> Self {
+        Self {
+            bg: self.bg,
+            ..Self::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cell {
+    pub c: char,
+    pub style: Style,
+}
+
+impl Cell {
+    pub(super) fn blank(style: Style) -> Self {
+        Self { c: ' ', style }
+    }
+
+    pub fn is_spacer(&self) -> bool {
+        self.style.has(flags::WIDE_SPACER)
+    }
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Self::blank(Style::default())
+    }
+}
+
+/// One row of the grid
+///
+/// Rows only hold cells up to the last one written, so blank scrollback is
+/// cheap. `wrapped` marks a row whose text continues on the next row because
+/// it ran past the right margin, as opposed to ending with a line feed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Row {
+    pub cells: Vec<Cell>,
+    pub wrapped: bool,
+}
+
+impl Row {
+    /// Cell at `col`, padding the row with blanks as needed
+    pub(super) fn cell_mut(&mut self, col: usize) -> &mut Cell {
+        if self.cells.len() <= col {
+            self.cells.resize(col + 1, Cell::default());
+        }
+        &mut self.cells[col]
+    }
+
+    /// Blank the cells in `start..end`
+    ///
+    /// Cells past the end of the row are only materialized when the erase
+    /// style has a background color.
+    pub(super) fn erase(&mut self, start: usize, end: usize, style: Style) {
+        let blank = Cell::blank(style);
+        if end >= self.cells.len() && style == Style::default() {
+            self.cells.truncate(start);
+            return;
+        }
+        if self.cells.len() < end {
+            self.cells.resize(end, Cell::default());
+        }
+        for cell in &mut self.cells[start..end] {
+            *cell = blank;
+        }
+    }
+
+    /// Text of the row without trailing blanks
+    pub fn text(&self) -> String {
+        let text: String = self
+            .cells
+            .iter()
+            .filter(|cell| !cell.is_spacer())
+            .map(|cell| cell.c)
+            .collect();
+        text.trim_end_matches(' ').to_string()
+    }
+}
+
+/// RGB value of a palette color, using the xterm defaults
+pub fn palette_rgb(index: u8) -> (u8, u8, u8) {
+    const BASE: [(u8, u8, u8); 16] = [
+        (0x00, 0x00, 0x00),
+        (0xcd, 0x31, 0x31),
+        (0x0d, 0xbc, 0x79),
+        (0xe5, 0xe5, 0x10),
+        (0x24, 0x72, 0xc8),
+        (0xbc, 0x3f, 0xbc),
+        (0x11, 0xa8, 0xcd),
+        (0xe5, 0xe5, 0xe5),
+        (0x66, 0x66, 0x66),
+        (0xf1, 0x4c, 0x4c),
+        (0x23, 0xd1, 0x8b),
+        (0xf5, 0xf5, 0x43),
+        (0x3b, 0x8e, 0xea),
+        (0xd6, 0x70, 0xd6),
+        (0x29, 0xb8, 0xdb),
+        (0xe5, 0xe5, 0xe5),
+    ];
+    match index {
+        0..=15 => BASE[index as usize],
+        16..=231 => {
+            let i = index - 16;
+            let level = |v: u8| if v == 0 { 0 } else { 55 + v * 40 };
+            (level(i / 36), level((i / 6) % 6), level(i % 6))
+        }
+        _ => {
+            let gray = 8 + (index - 232) * 10;
+            (gray, gray, gray)
+        }
+    }
+}
@@ -0,0 +1,278 @@
+//! Scrollback export
+//!
+//! Plain text and HTML exports join soft-wrapped rows back into the lines
+//! the program printed. The ANSI snapshot instead reproduces the grid row by
+//! row so a freshly opened view can be brought up to date in one write.
+
+use serde::{Deserialize, Serialize};
+
+use super::cell::{Cell, Color, Row, Style, flags, palette_rgb};
+use super::screen::Screen;
+
+/// Colors used in HTML exports for cells without an explicit color
+const DEFAULT_FG: (u8, u8, u8) = (0xcc, 0xcc, 0xcc);
+const DEFAULT_BG: (u8, u8, u8) = (0x1e, 0x1e, 0x1e);
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Text,
+    Html,
+}
+
+/// Cells of a row that carry content, dropping trailing blanks unless the
+/// row wrapped (its trailing spaces are part of the line)
+fn content_cells(row: &Row) -> &[Cell] {
+    if row.wrapped {
+        return &row.cells;
+    }
+    let end = row
+        .cells
+        .iter()
+        .rposition(|cell| cell.c != ' ' || cell.style.bg != Color::Default)
+        .map_or(0, |index| index + 1);
+    &row.cells[..end]
+}
+
+/// Drop trailing empty rows
+fn trimmed_rows<'a>(rows: impl Iterator<Item = &'a Row>) -> Vec<&'a Row> {
+    let mut rows: Vec<&Row> = rows.collect();
+    while rows.last().is_some_and(|row| content_cells(row).is_empty()) {
+        rows.pop();
+    }
+    rows
+}
+
+pub fn to_text<'a>(rows: impl Iterator<Item = &'a Row>) -> String {
+    let mut out = String::new();
+    for row in trimmed_rows(rows) {
+        out.extend(
+            content_cells(row)
+                .iter()
+                .filter(|cell| !cell.is_spacer())
+                .map(|cell| cell.c),
+        );
+        if !row.wrapped {
+            let trimmed = out.trim_end_matches(' ').len();
+            out.truncate(trimmed);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+fn hex((r, g, b): (u8, u8, u8)) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+fn rgb(color: Color, default: (u8, u8, u8)) -> (u8, u8, u8) {
+    match color {
+        Color::Default => default,
+        Color::Indexed(index) => palette_rgb(index),
+        Color::Rgb(r, g, b) => (r, g, b),
+    }
+}
+
+fn css(style: &Style) -> String {
+    let inverse = style.has(flags::INVERSE);
+    let (mut fg, mut bg) = (style.fg, style.bg);
+    let (mut fg_default, mut bg_default) = (DEFAULT_FG, DEFAULT_BG);
+    if inverse {
+        std::mem::swap(&mut fg, &mut bg);
+        std::mem::swap(&mut fg_default, &mut bg_default);
+    }
+    let mut rules = Vec::new();
+    if fg != Color::Default || inverse {
+        rules.push(format!("color:{}", hex(rgb(fg, fg_default))));
+    }
+    if bg != Color::Default || inverse {
+        rules.push(format!("background-color:{}", hex(rgb(bg, bg_default))));
+    }
+    if style.has(flags::BOLD) {
+        rules.push("font-weight:bold".to_string());
+    }
+    if style.has(flags::DIM) {
+        rules.push("opacity:0.6".to_string());
+    }
+    if style.has(flags::ITALIC) {
+        rules.push("font-style:italic".to_string());
+    }
+    let decorations: Vec<&str> = [
+        (flags::UNDERLINE, "underline"),
+        (flags::STRIKETHROUGH, "line-through"),
+    ]
+    .into_iter()
+    .filter(|(flag, _)| style.has(*flag))
+    .map(|(_, decoration)| decoration)
+    .collect();
+    if !decorations.is_empty() {
+        rules.push(format!("text-decoration:{}", decorations.join(" ")));
+    }
+    rules.join(";")
+}
+
+fn escape_html(c: char, out: &mut String) {
+    match c {
+        '&' => out.push_str("&amp;"),
+        '<' => out.push_str("&lt;"),
+        '>' => out.push_str("&gt;"),
+        '"' => out.push_str("&quot;"),
+        c => out.push(c),
+    }
+}
+
+pub fn to_html<'a>(rows: impl Iterator<Item = &'a Row>) -> String {
+    let mut out = format!(
+        "<pre class=\"terminal\" style=\"color:{};background-color:{}\">",
+        hex(DEFAULT_FG),
+        hex(DEFAULT_BG)
+    );
+    for row in trimmed_rows(rows) {
+        let cells: Vec<&Cell> = content_cells(row)
+            .iter()
+            .filter(|cell| !cell.is_spacer())
+            .collect();
+        for run in cells.chunk_by(|a, b| a.style == b.style) {
+            let rules = css(&run[0].style);
+            if !rules.is_empty() {
+                out.push_str(&format!("<span style=\"{}\">", rules));
+            }
+            for cell in run {
+                escape_html(cell.c, &mut out);
+            }
+            if !rules.is_empty() {
+                out.push_str("</span>");
+            }
+        }
+        if !row.wrapped {
+            out.push('\n');
+        }
+    }
+    out.push_str("</pre>");
+    out
+}
+
+fn sgr(style: &Style) -> String {
+    let mut params = vec!["0".to_string()];
+    for (flag, code) in [
+        (flags::BOLD, "1"),
+        (flags::DIM, "2"),
+        (flags::ITALIC, "3"),
+        (flags::UNDERLINE, "4"),
+        (flags::INVERSE, "7"),
+        (flags::STRIKETHROUGH, "9"),
+    ] {
+        if style.has(flag) {
+            params.push(code.to_string());
+        }
+    }
+    for (color, base) in [(style.fg, 38), (style.bg, 48)] {
+        match color {
+            Color::Default => {}
+            Color::Indexed(index) => params.push(format!("{};5;{}", base, index)),
+            Color::Rgb(r, g, b) => params.push(format!("{};2;{};{};{}", base, r, g, b)),
+        }
+    }
+    format!("\x1b[{}m", params.join(";"))
+}
+
+fn write_rows<'a>(rows: impl Iterator<Item = &'a Row>, cols: usize, out: &mut String) {
+    let mut style = Style::default();
+    let mut continued = true;
+    for row in rows {
+        if !continued {
+            // Reset first so scrolling does not paint new rows
+            if style != Style::default() {
+                style = Style::default();
+                out.push_str("\x1b[0m");
+            }
+            out.push_str("\r\n");
+        }
+        for cell in content_cells(row).iter().filter(|cell| !cell.is_spacer()) {
+            if cell.style != style {
+                style = cell.style;
+                out.push_str(&sgr(&style));
+            }
+            out.push(cell.c);
+        }
+        // A full wrapped row continues on the next one through autowrap
+        continued = row.wrapped && row.cells.len() >= cols;
+    }
+    out.push_str("\x1b[0m");
+}
+
+/// Escape sequences that redraw the screen and its scrollback
+///
+/// Meant to be written to a terminal of the same size that was just reset.
+pub fn to_ansi(screen: &Screen) -> String {
+    let mut out = String::from("\x1b[0m");
+    write_rows(screen.history(), screen.cols(), &mut out);
+    if screen.is_alternate_screen() {
+        out.push_str("\x1b[?1049h\x1b[H");
+        write_rows(screen.visible(), screen.cols(), &mut out);
+    }
+    let cursor = screen.cursor();
+    out.push_str(&format!("\x1b[{};{}H", cursor.row + 1, cursor.col + 1));
+    if screen.style() != Style::default() {
+        out.push_str(&sgr(&screen.style()));
+    }
+    out
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::super::TerminalEmulator;
+    use super::*;
+
+    fn emulator(cols: usize, rows: usize, input: &str) -> TerminalEmulator {
+        let mut emulator = TerminalEmulator::new(cols, rows, 100);
+        emulator.process(input.as_bytes());
+        emulator
+    }
+
+    #[test]
+    fn text_export_joins_wrapped_rows() {
+        let emulator = emulator(5, 4, "hello world\r\n\x1b[31mred\x1b[0m\r\n");
+        assert_eq!(emulator.export(ExportFormat::Text), "hello world\nred\n");
+    }
+
+    #[test]
+    fn html_export_escapes_and_colors_runs() {
+        let emulator = emulator(20, 2, "<a> \x1b[1;32mok\x1b[0m & done");
+        let html = emulator.export(ExportFormat::Html);
+        assert!(html.starts_with("<pre class=\"terminal\""));
+        assert!(html.contains("&lt;a&gt; "));
+        assert!(html.contains("<span style=\"color:#0dbc79;font-weight:bold\">ok</span>"));
+        assert!(html.contains(" &amp; done\n</pre>"));
+    }
+
+    #[test]
+    fn snapshot_reproduces_the_grid() {
+        let source = emulator(
+            6,
+            3,
+            "one\r\n\x1b[44mtwo\x1b[0m\r\nwrapped line\r\n\x1b[1mlast",
+        );
+        let mut replica = TerminalEmulator::new(6, 3, 100);
+        replica.process(source.snapshot().as_bytes());
+
+        let rows = |e: &TerminalEmulator| e.screen().history().cloned().collect::<Vec<_>>();
+        assert_eq!(rows(&replica), rows(&source));
+        assert_eq!(replica.screen().cursor(), source.screen().cursor());
+        assert_eq!(replica.screen().style(), source.screen().style());
+    }
+
+    #[test]
+    fn snapshot_restores_the_alternate_screen() {
+        let source = emulator(10, 2, "prompt\r\n\x1b[?1049h\x1b[Hmenu");
+        let mut replica = TerminalEmulator::new(10, 2, 100);
+        replica.process(source.snapshot().as_bytes());
+
+        assert!(replica.screen().is_alternate_screen());
+        let text = |e: &TerminalEmulator| e.screen().visible().map(Row::text).collect::<Vec<_>>();
+        assert_eq!(text(&replica), ["menu", ""]);
+        replica.process(b"\x1b[?1049l");
+        assert_eq!(replica.export(ExportFormat::Text), "prompt\n");
+    }
+}
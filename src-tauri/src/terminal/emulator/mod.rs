@@ -0,0 +1,81 @@
+//! Server-side terminal emulation
+//!
+//! Every terminal's output is also fed through a VT parser into a grid with
+//! bounded scrollback, so the backend knows what is on screen without asking
+//! the frontend. This backs searching the full scrollback (including lines
+//! soft-wrapped by the terminal width), exporting it as text or HTML, and
+//! snapshots that bring a newly opened view up to date.
+//!
+//! Resizing does not reflow existing rows; narrower terminals truncate them.
+//!
+//! # Module Structure
+//!
+//! - `cell`: Cells, colors and rows
+//! - `screen`: Screen state driven by the parser
+//! - `search`: Search across logical lines
+//! - `export`: Text, HTML and ANSI exports
+
+mod cell;
+mod export;
+mod screen;
+mod search;
+
+pub use cell::{Cell, Color, Row, Style, flags};
+pub use export::ExportFormat;
+pub use screen::{Cursor, Screen};
+pub use search::{GridSearchMatch, GridSearchResult};
+
+/// VT parser and the screen it drives
+pub struct TerminalEmulator {
+    parser: vte::Parser,
+    screen: Screen,
+}
+
+impl TerminalEmulator {
+    pub fn new(cols: usize, rows: usize, scrollback_lines: usize) -> Self {
+        Self {
+            parser: vte::Parser::new(),
+            screen: Screen::new(cols, rows, scrollback_lines),
+        }
+    }
+
+    /// Feed raw PTY output
+    ///
+    /// Sequences and UTF-8 characters split across calls are reassembled.
+    pub fn process(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.parser.advance(&mut self.screen, *byte);
+        }
+    }
+
+    pub fn resize(&mut self, cols: usize, rows: usize) {
+        self.screen.resize(cols, rows);
+    }
+
+    pub fn screen(&self) -> &Screen {
+        &self.screen
+    }
+
+    /// Search the scrollback and screen of the normal buffer
+    pub fn search(
+        &self,
+        query: &str,
+        case_sensitive: bool,
+        max_results: Option<usize>,
+    ) -> GridSearchResult {
+        search::search_rows(self.screen.history(), query, case_sensitive, max_results)
+    }
+
+    /// Export the scrollback and screen of the normal buffer
+    pub fn export(&self, format: ExportFormat) -> String {
+        match format {
+            ExportFormat::Text => export::to_text(self.screen.history()),
+            ExportFormat::Html => export::to_html(self.screen.history()),
+        }
+    }
+
+    /// Escape sequences that reproduce the current grid in a new view
+    pub fn snapshot(&self) -> String {
+        export::to_ansi(&self.screen)
+    }
+}
@@ -0,0 +1,824 @@
+//! Screen state driven by the VT parser
+//!
+//! Implements the subset of xterm behavior that affects what ends up on
+//! screen and in scrollback: printing with autowrap, cursor movement,
+//! erasing, scroll regions, insert/delete, SGR attributes and the alternate
+//! screen. Anything else (titles, mouse modes, OSC 633 marks) is ignored
+//! here; the frontend and `protocol` module handle it.
+
+use std::collections::VecDeque;
+
+use unicode_width::UnicodeWidthChar;
+use vte::{Params, Perform};
+
+use super::cell::{Cell, Color, Row, Style, flags};
+
+const TAB_WIDTH: usize = 8;
+
+/// Lines of one screen buffer
+///
+/// The last `rows` lines are the visible screen and the lines before them
+/// are scrollback, so scrolling a full-screen region is just appending a row.
+#[derive(Debug, Clone)]
+struct Buffer {
+    lines: VecDeque<Row>,
+    scrollback_limit: usize,
+    /// Lines dropped from the front of the scrollback so far
+    dropped: u64,
+}
+
+impl Buffer {
+    fn new(rows: usize, scrollback_limit: usize) -> Self {
+        Self {
+            lines: (0..rows).map(|_| Row::default()).collect(),
+            scrollback_limit,
+            dropped: 0,
+        }
+    }
+
+    fn scrollback_len(&self, rows: usize) -> usize {
+        self.lines.len() - rows
+    }
+
+    fn row_mut(&mut self, rows: usize, row: usize) -> &mut Row {
+        let start = self.scrollback_len(rows);
+        &mut self.lines[start + row]
+    }
+
+    fn trim(&mut self, rows: usize) {
+        while self.scrollback_len(rows) > self.scrollback_limit {
+            self.lines.pop_front();
+            self.dropped += 1;
+        }
+    }
+
+    /// Scroll `top..=bottom` up by `count` rows
+    ///
+    /// With `into_scrollback` the top row is kept as scrollback instead of
+    /// being discarded; rows below the region stay in place either way.
+    fn scroll_up(
+        &mut self,
+        rows: usize,
+        top: usize,
+        bottom: usize,
+        count: usize,
+        into_scrollback: bool,
+    ) {
+        for _ in 0..count.min(bottom + 1 - top) {
+            let start = self.scrollback_len(rows);
+            if into_scrollback {
+                self.lines.insert(start + bottom + 1, Row::default());
+            } else {
+                self.lines.remove(start + top);
+                self.lines.insert(start + bottom, Row::default());
+            }
+        }
+        self.trim(rows);
+    }
+
+    /// Scroll `top..=bottom` down by `count` rows, discarding the bottom rows
+    fn scroll_down(&mut self, rows: usize, top: usize, bottom: usize, count: usize) {
+        for _ in 0..count.min(bottom + 1 - top) {
+            let start = self.scrollback_len(rows);
+            self.lines.remove(start + bottom);
+            self.lines.insert(start + top, Row::default());
+        }
+    }
+
+    /// Change the number of visible rows, returning the new cursor row
+    ///
+    /// Shrinking drops blank rows below the cursor first and pushes the rest
+    /// into scrollback; growing pulls rows back out of scrollback.
+    fn resize_rows(&mut self, old: usize, new: usize, cursor_row: usize) -> usize {
+        let cursor_row = cursor_row.min(old - 1);
+        if new < old {
+            let excess = old - new;
+            let below = excess.min(old - 1 - cursor_row);
+            for _ in 0..below {
+                self.lines.pop_back();
+            }
+            self.trim(new);
+            cursor_row - (excess - below)
+        } else {
+            let grow = new - old;
+            let pulled = grow.min(self.scrollback_len(old));
+            for _ in 0..grow - pulled {
+                self.lines.push_back(Row::default());
+            }
+            cursor_row + pulled
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Cursor {
+    pub row: usize,
+    pub col: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct SavedCursor {
+    cursor: Cursor,
+    style: Style,
+}
+
+/// Terminal screen with bounded scrollback
+#[derive(Debug, Clone)]
+pub struct Screen {
+    cols: usize,
+    rows: usize,
+    primary: Buffer,
+    /// Alternate screen used by full-screen programs; never has scrollback
+    alternate: Option<Buffer>,
+    cursor: Cursor,
+    saved: Option<SavedCursor>,
+    style: Style,
+    /// Set after printing in the last column; the next character wraps
+    pending_wrap: bool,
+    autowrap: bool,
+    scroll_top: usize,
+    scroll_bottom: usize,
+}
+
+impl Screen {
+    pub fn new(cols: usize, rows: usize, scrollback_limit: usize) -> Self {
+        let cols = cols.max(1);
+        let rows = rows.max(1);
+        Self {
+            cols,
+            rows,
+            primary: Buffer::new(rows, scrollback_limit),
+            alternate: None,
+            cursor: Cursor::default(),
+            saved: None,
+            style: Style::default(),
+            pending_wrap: false,
+            autowrap: true,
+            scroll_top: 0,
+            scroll_bottom: rows - 1,
+        }
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cursor(&self) -> Cursor {
+        self.cursor
+    }
+
+    /// Current graphic rendition for newly printed text
+    pub fn style(&self) -> Style {
+        self.style
+    }
+
+    pub fn is_alternate_screen(&self) -> bool {
+        self.alternate.is_some()
+    }
+
+    /// Scrollback and screen of the normal buffer, oldest line first
+    ///
+    /// Output of full-screen programs on the alternate screen is not part
+    /// of the history.
+    pub fn history(&self) -> impl Iterator<Item = &Row> {
+        self.primary.lines.iter()
+    }
+
+    /// Number of lines in `history`
+    pub fn history_len(&self) -> usize {
+        self.primary.lines.len()
+    }
+
+    /// Lines dropped from the front of the scrollback since the screen was created
+    ///
+    /// Adding this to a `history` index gives a line number that stays
+    /// stable as old scrollback is discarded.
+    pub fn dropped_lines(&self) -> u64 {
+        self.primary.dropped
+    }
+
+    /// Rows currently on screen, top first
+    pub fn visible(&self) -> impl Iterator<Item = &Row> {
+        let buffer = self.buffer();
+        buffer.lines.iter().skip(buffer.scrollback_len(self.rows))
+    }
+
+    pub fn resize(&mut self, cols: usize, rows: usize) {
+        let cols = cols.max(1);
+        let rows = rows.max(1);
+
+        if rows != self.rows {
+            let primary_cursor = match (&self.alternate, &self.saved) {
+                (None, _) => self.cursor.row,
+                (Some(_), Some(saved)) => saved.cursor.row,
+                (Some(_), None) => self.rows - 1,
+            };
+            let primary_row = self.primary.resize_rows(self.rows, rows, primary_cursor);
+            match self.alternate.as_mut() {
+                Some(alternate) => {
+                    self.cursor.row = alternate.resize_rows(self.rows, rows, self.cursor.row);
+                    if let Some(saved) = self.saved.as_mut() {
+                        saved.cursor.row = primary_row;
+                    }
+                }
+                None => self.cursor.row = primary_row,
+            }
+            self.rows = rows;
+        }
+
+        if cols < self.cols {
+            let rows = self.rows;
+            for buffer in std::iter::once(&mut self.primary).chain(self.alternate.as_mut()) {
+                let start = buffer.scrollback_len(rows);
+                for row in buffer.lines.iter_mut().skip(start) {
+                    row.cells.truncate(cols);
+                }
+            }
+        }
+        self.cols = cols;
+
+        self.cursor.row = self.cursor.row.min(self.rows - 1);
+        self.cursor.col = self.cursor.col.min(self.cols - 1);
+        self.scroll_top = 0;
+        self.scroll_bottom = self.rows - 1;
+        self.pending_wrap = false;
+    }
+
+    fn buffer(&self) -> &Buffer {
+        self.alternate.as_ref().unwrap_or(&self.primary)
+    }
+
+    fn buffer_mut(&mut self) -> &mut Buffer {
+        self.alternate.as_mut().unwrap_or(&mut self.primary)
+    }
+
+    fn current_row(&mut self) -> &mut Row {
+        let (rows, row) = (self.rows, self.cursor.row);
+        self.buffer_mut().row_mut(rows, row)
+    }
+
+    fn scroll_up(&mut self, count: usize) {
+        let (rows, top, bottom) = (self.rows, self.scroll_top, self.scroll_bottom);
+        let into_scrollback = top == 0 && self.alternate.is_none();
+        self.buffer_mut()
+            .scroll_up(rows, top, bottom, count, into_scrollback);
+    }
+
+    fn scroll_down(&mut self, count: usize) {
+        let (rows, top, bottom) = (self.rows, self.scroll_top, self.scroll_bottom);
+        self.buffer_mut().scroll_down(rows, top, bottom, count);
+    }
+
+    fn linefeed(&mut self) {
+        self.pending_wrap = false;
+        if self.cursor.row == self.scroll_bottom {
+            self.scroll_up(1);
+        } else if self.cursor.row + 1 < self.rows {
+            self.cursor.row += 1;
+        }
+    }
+
+    fn reverse_index(&mut self) {
+        self.pending_wrap = false;
+        if self.cursor.row == self.scroll_top {
+            self.scroll_down(1);
+        } else if self.cursor.row > 0 {
+            self.cursor.row -= 1;
+        }
+    }
+
+    fn carriage_return(&mut self) {
+        self.pending_wrap = false;
+        self.cursor.col = 0;
+    }
+
+    /// Continue on the next row after running past the right margin
+    fn wrap(&mut self) {
+        self.current_row().wrapped = true;
+        self.carriage_return();
+        self.linefeed();
+    }
+
+    fn goto(&mut self, row: usize, col: usize) {
+        self.pending_wrap = false;
+        self.cursor.row = row.min(self.rows - 1);
+        self.cursor.col = col.min(self.cols - 1);
+    }
+
+    fn save_cursor(&mut self) {
+        self.saved = Some(SavedCursor {
+            cursor: self.cursor,
+            style: self.style,
+        });
+    }
+
+    fn restore_cursor(&mut self) {
+        let saved = self.saved.unwrap_or(SavedCursor {
+            cursor: Cursor::default(),
+            style: Style::default(),
+        });
+        self.style = saved.style;
+        self.goto(saved.cursor.row, saved.cursor.col);
+    }
+
+    fn clear_row(&mut self, row: usize) {
+        let (rows, cols, style) = (self.rows, self.cols, self.style.erased());
+        let row = self.buffer_mut().row_mut(rows, row);
+        row.erase(0, cols, style);
+        row.wrapped = false;
+    }
+
+    fn erase_display(&mut self, mode: u16) {
+        let (cols, style) = (self.cols, self.style.erased());
+        let col = self.cursor.col;
+        match mode {
+            0 => {
+                let row = self.current_row();
+                row.erase(col, cols, style);
+                row.wrapped = false;
+                for row in self.cursor.row + 1..self.rows {
+                    self.clear_row(row);
+                }
+            }
+            1 => {
+                self.current_row().erase(0, col + 1, style);
+                for row in 0..self.cursor.row {
+                    self.clear_row(row);
+                }
+            }
+            2 => {
+                for row in 0..self.rows {
+                    self.clear_row(row);
+                }
+            }
+            3 => {
+                let rows = self.rows;
+                let buffer = self.buffer_mut();
+                let scrollback = buffer.scrollback_len(rows);
+                buffer.lines.drain(..scrollback);
+                buffer.dropped += scrollback as u64;
+            }
+            _ => {}
+        }
+    }
+
+    fn erase_line(&mut self, mode: u16) {
+        let (cols, style) = (self.cols, self.style.erased());
+        let col = self.cursor.col;
+        let row = self.current_row();
+        match mode {
+            0 => {
+                row.erase(col, cols, style);
+                row.wrapped = false;
+            }
+            1 => row.erase(0, col + 1, style),
+            2 => {
+                row.erase(0, cols, style);
+                row.wrapped = false;
+            }
+            _ => {}
+        }
+    }
+
+    fn insert_lines(&mut self, count: usize) {
+        if (self.scroll_top..=self.scroll_bottom).contains(&self.cursor.row) {
+            let (rows, top, bottom) = (self.rows, self.cursor.row, self.scroll_bottom);
+            self.buffer_mut().scroll_down(rows, top, bottom, count);
+            self.carriage_return();
+        }
+    }
+
+    fn delete_lines(&mut self, count: usize) {
+        if (self.scroll_top..=self.scroll_bottom).contains(&self.cursor.row) {
+            let (rows, top, bottom) = (self.rows, self.cursor.row, self.scroll_bottom);
+            self.buffer_mut().scroll_up(rows, top, bottom, count, false);
+            self.carriage_return();
+        }
+    }
+
+    fn insert_chars(&mut self, count: usize) {
+        let (cols, col, style) = (self.cols, self.cursor.col, self.style.erased());
+        let row = self.current_row();
+        if col < row.cells.len() {
+            let count = count.min(cols - col);
+            row.cells
+                .splice(col..col, std::iter::repeat_n(Cell::blank(style), count));
+            row.cells.truncate(cols);
+        }
+    }
+
+    fn delete_chars(&mut self, count: usize) {
+        let col = self.cursor.col;
+        let row = self.current_row();
+        if col < row.cells.len() {
+            let end = (col + count).min(row.cells.len());
+            row.cells.drain(col..end);
+        }
+    }
+
+    fn erase_chars(&mut self, count: usize) {
+        let (cols, col, style) = (self.cols, self.cursor.col, self.style.erased());
+        let end = (col + count).min(cols);
+        self.current_row().erase(col, end, style);
+    }
+
+    fn set_scroll_region(&mut self, top: usize, bottom: usize) {
+        let bottom = bottom.min(self.rows);
+        if top < bottom {
+            self.scroll_top = top - 1;
+            self.scroll_bottom = bottom - 1;
+            self.goto(0, 0);
+        }
+    }
+
+    fn set_alternate_screen(&mut self, enabled: bool, save_cursor: bool) {
+        if enabled && self.alternate.is_none() {
+            if save_cursor {
+                self.save_cursor();
+            }
+            self.alternate = Some(Buffer::new(self.rows, 0));
+        } else if !enabled && self.alternate.is_some() {
+            self.alternate = None;
+            if save_cursor {
+                self.restore_cursor();
+            }
+        }
+    }
+
+    fn set_private_mode(&mut self, mode: u16, enabled: bool) {
+        match mode {
+            7 => self.autowrap = enabled,
+            47 | 1047 => self.set_alternate_screen(enabled, false),
+            1049 => self.set_alternate_screen(enabled, true),
+            _ => {}
+        }
+    }
+
+    fn reset(&mut self) {
+        self.alternate = None;
+        self.saved = None;
+        self.style = Style::default();
+        self.autowrap = true;
+        self.scroll_top = 0;
+        self.scroll_bottom = self.rows - 1;
+        self.erase_display(2);
+        self.goto(0, 0);
+    }
+
+    fn set_graphic_rendition(&mut self, params: &Params) {
+        let mut iter = params.iter();
+        if params.is_empty() {
+            self.style = Style::default();
+            return;
+        }
+        while let Some(param) = iter.next() {
+            let style = &mut self.style;
+            match param[0] {
+                0 => *style = Style::default(),
+                1 => style.flags |= flags::BOLD,
+                2 => style.flags |= flags::DIM,
+                3 => style.flags |= flags::ITALIC,
+                4 => style.flags |= flags::UNDERLINE,
+                7 => style.flags |= flags::INVERSE,
+                9 => style.flags |= flags::STRIKETHROUGH,
+                22 => style.flags &= !(flags::BOLD | flags::DIM),
+                23 => style.flags &= !flags::ITALIC,
+                24 => style.flags &= !flags::UNDERLINE,
+                27 => style.flags &= !flags::INVERSE,
+                29 => style.flags &= !flags::STRIKETHROUGH,
+                n @ 30..=37 => style.fg = Color::Indexed((n - 30) as u8),
+                38 => {
+                    if let Some(color) = extended_color(param, &mut iter) {
+                        style.fg = color;
+                    }
+                }
+                39 => style.fg = Color::Default,
+                n @ 40..=47 => style.bg = Color::Indexed((n - 40) as u8),
+                48 => {
+                    if let Some(color) = extended_color(param, &mut iter) {
+                        style.bg = color;
+                    }
+                }
+                49 => style.bg = Color::Default,
+                n @ 90..=97 => style.fg = Color::Indexed((n - 90 + 8) as u8),
+                n @ 100..=107 => style.bg = Color::Indexed((n - 100 + 8) as u8),
+                _ => {}
+            }
+        }
+    }
+}
+
+fn color_component(value: u16) -> u8 {
+    u8::try_from(value).unwrap_or(u8::MAX)
+}
+
+/// Parse a 256-color or truecolor SGR argument
+///
+/// Accepts both the colon form (`38:2::r:g:b`), where the arguments are
+/// subparameters of `param`, and the semicolon form (`38;2;r;g;b`), where
+/// they are the following parameters.
+fn extended_color<'a>(param: &[u16], rest: &mut impl Iterator<Item = &'a [u16]>) -> Option<Color> {
+    if param.len() > 1 {
+        return match param[1] {
+            5 => param.get(2).map(|n| Color::Indexed(color_component(*n))),
+            2 => {
+                // An optional color space ID precedes the components
+                let rgb = &param[2..];
+                let rgb = if rgb.len() >= 4 { &rgb[1..] } else { rgb };
+                (rgb.len() >= 3).then(|| {
+                    Color::Rgb(
+                        color_component(rgb[0]),
+                        color_component(rgb[1]),
+                        color_component(rgb[2]),
+                    )
+                })
+            }
+            _ => None,
+        };
+    }
+    match rest.next()?[0] {
+        5 => Some(Color::Indexed(color_component(rest.next()?[0]))),
+        2 => {
+            let r = color_component(rest.next()?[0]);
+            let g = color_component(rest.next()?[0]);
+            let b = color_component(rest.next()?[0]);
+            Some(Color::Rgb(r, g, b))
+        }
+        _ => None,
+    }
+}
+
+impl Perform for Screen {
+    fn print(&mut self, c: char) {
+        // Combining and other zero-width characters are dropped
+        let width = c.width().unwrap_or(0);
+        if width == 0 {
+            return;
+        }
+        if self.pending_wrap {
+            self.wrap();
+        }
+        if width == 2 && self.cursor.col + 1 >= self.cols {
+            if !self.autowrap || self.cols < 2 {
+                return;
+            }
+            self.wrap();
+        }
+
+        let (col, style) = (self.cursor.col, self.style);
+        let row = self.current_row();
+        *row.cell_mut(col) = Cell { c, style };
+        if width == 2 {
+            *row.cell_mut(col + 1) = Cell {
+                c: ' ',
+                style: Style {
+                    flags: style.flags | flags::WIDE_SPACER,
+                    ..style
+                },
+            };
+        }
+
+        let next = col + width;
+        if next >= self.cols {
+            self.cursor.col = self.cols - 1;
+            self.pending_wrap = self.autowrap;
+        } else {
+            self.cursor.col = next;
+        }
+    }
+
+    fn execute(&mut self, byte: u8) {
+        match byte {
+            0x08 => {
+                self.pending_wrap = false;
+                self.cursor.col = self.cursor.col.saturating_sub(1);
+            }
+            b'\t' => {
+                let next = (self.cursor.col / TAB_WIDTH + 1) * TAB_WIDTH;
+                self.cursor.col = next.min(self.cols - 1);
+            }
+            b'\n' | 0x0b | 0x0c => self.linefeed(),
+            b'\r' => self.carriage_return(),
+            _ => {}
+        }
+    }
+
+    fn csi_dispatch(&mut self, params: &Params, intermediates: &[u8], ignore: bool, action: char) {
+        if ignore {
+            return;
+        }
+        let args: Vec<u16> = params.iter().map(|param| param[0]).collect();
+        let arg = |index: usize| {
+            args.get(index)
+                .copied()
+                .filter(|value| *value != 0)
+                .map_or(1, usize::from)
+        };
+        let mode = args.first().copied().unwrap_or(0);
+
+        match intermediates {
+            [b'?'] => match action {
+                'h' => args
+                    .iter()
+                    .for_each(|mode| self.set_private_mode(*mode, true)),
+                'l' => args
+                    .iter()
+                    .for_each(|mode| self.set_private_mode(*mode, false)),
+                _ => {}
+            },
+            [] => match action {
+                'A' => self.goto(self.cursor.row.saturating_sub(arg(0)), self.cursor.col),
+                'B' | 'e' => self.goto(self.cursor.row + arg(0), self.cursor.col),
+                'C' | 'a' => self.goto(self.cursor.row, self.cursor.col + arg(0)),
+                'D' => self.goto(self.cursor.row, self.cursor.col.saturating_sub(arg(0))),
+                'E' => self.goto(self.cursor.row + arg(0), 0),
+                'F' => self.goto(self.cursor.row.saturating_sub(arg(0)), 0),
+                'G' | '`' => self.goto(self.cursor.row, arg(0) - 1),
+                'H' | 'f' => self.goto(arg(0) - 1, arg(1) - 1),
+                'd' => self.goto(arg(0) - 1, self.cursor.col),
+                'J' => self.erase_display(mode),
+                'K' => self.erase_line(mode),
+                'L' => self.insert_lines(arg(0)),
+                'M' => self.delete_lines(arg(0)),
+                '@' => self.insert_chars(arg(0)),
+                'P' => self.delete_chars(arg(0)),
+                'X' => self.erase_chars(arg(0)),
+                'S' => self.scroll_up(arg(0)),
+                'T' => self.scroll_down(arg(0)),
+                'm' => self.set_graphic_rendition(params),
+                'r' => {
+                    let bottom = args.get(1).copied().filter(|v| *v != 0);
+                    self.set_scroll_region(arg(0), bottom.map_or(self.rows, usize::from));
+                }
+                's' => self.save_cursor(),
+                'u' => self.restore_cursor(),
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
+    fn esc_dispatch(&mut self, intermediates: &[u8], ignore: bool, byte: u8) {
+        // Charset designations and other sequences with intermediates do
+        // not change the grid
+        if ignore || !intermediates.is_empty() {
+            return;
+        }
+        match byte {
+            b'7' => self.save_cursor(),
+            b'8' => self.restore_cursor(),
+            b'D' => self.linefeed(),
+            b'E' => {
+                self.carriage_return();
+                self.linefeed();
+            }
+            b'M' => self.reverse_index(),
+            b'c' => self.reset(),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::super::TerminalEmulator;
+    use super::*;
+
+    fn feed(cols: usize, rows: usize, scrollback: usize, input: &str) -> TerminalEmulator {
+        let mut emulator = TerminalEmulator::new(cols, rows, scrollback);
+        emulator.process(input.as_bytes());
+        emulator
+    }
+
+    fn visible_text(emulator: &TerminalEmulator) -> Vec<String> {
+        emulator.screen().visible().map(Row::text).collect()
+    }
+
+    fn history_text(emulator: &TerminalEmulator) -> Vec<String> {
+        emulator.screen().history().map(Row::text).collect()
+    }
+
+    #[test]
+    fn prints_and_moves_to_new_lines() {
+        let emulator = feed(10, 3, 10, "hello\r\nworld");
+        assert_eq!(visible_text(&emulator), ["hello", "world", ""]);
+        assert_eq!(emulator.screen().cursor(), Cursor { row: 1, col: 5 });
+    }
+
+    #[test]
+    fn wraps_long_lines_and_marks_them() {
+        let emulator = feed(4, 3, 10, "abcdefghij");
+        assert_eq!(visible_text(&emulator), ["abcd", "efgh", "ij"]);
+        let wrapped: Vec<bool> = emulator.screen().visible().map(|row| row.wrapped).collect();
+        assert_eq!(wrapped, [true, true, false]);
+    }
+
+    #[test]
+    fn exactly_filling_a_row_does_not_wrap() {
+        let emulator = feed(4, 3, 10, "abcd\r\nef");
+        assert_eq!(visible_text(&emulator), ["abcd", "ef", ""]);
+        assert!(!emulator.screen().visible().next().unwrap().wrapped);
+    }
+
+    #[test]
+    fn scrolled_lines_move_into_bounded_scrollback() {
+        let input: String = (0..10).map(|i| format!("line {}\r\n", i)).collect();
+        let emulator = feed(10, 3, 4, &input);
+        assert_eq!(
+            history_text(&emulator),
+            [
+                "line 4", "line 5", "line 6", "line 7", "line 8", "line 9", ""
+            ]
+        );
+        assert_eq!(emulator.screen().dropped_lines(), 4);
+    }
+
+    #[test]
+    fn erases_lines_and_display() {
+        let emulator = feed(10, 3, 10, "aaaa\r\nbbbb\r\ncccc\x1b[2;3H\x1b[K");
+        assert_eq!(visible_text(&emulator), ["aaaa", "bb", "cccc"]);
+
+        let emulator = feed(10, 3, 10, "aaaa\r\nbbbb\r\ncccc\x1b[2;3H\x1b[J");
+        assert_eq!(visible_text(&emulator), ["aaaa", "bb", ""]);
+
+        let emulator = feed(10, 2, 10, "aaaa\r\nbbbb\r\ncccc\x1b[2J\x1b[3J");
+        assert_eq!(history_text(&emulator), ["", ""]);
+    }
+
+    #[test]
+    fn carriage_return_overwrites_progress_output() {
+        let emulator = feed(20, 2, 10, "progress 10%\rprogress 100%\r\n");
+        assert_eq!(history_text(&emulator)[0], "progress 100%");
+    }
+
+    #[test]
+    fn applies_sgr_colors_and_attributes() {
+        let emulator = feed(
+            20,
+            2,
+            10,
+            "\x1b[1;31mA\x1b[38;5;200mB\x1b[38:2::1:2:3mC\x1b[48;2;4;5;6mD\x1b[0mE",
+        );
+        let row = emulator.screen().visible().next().unwrap();
+        assert_eq!(row.cells[0].style.fg, Color::Indexed(1));
+        assert!(row.cells[0].style.has(flags::BOLD));
+        assert_eq!(row.cells[1].style.fg, Color::Indexed(200));
+        assert_eq!(row.cells[2].style.fg, Color::Rgb(1, 2, 3));
+        assert_eq!(row.cells[3].style.bg, Color::Rgb(4, 5, 6));
+        assert_eq!(row.cells[4].style, Style::default());
+    }
+
+    #[test]
+    fn alternate_screen_keeps_history_intact() {
+        let emulator = feed(
+            10,
+            2,
+            10,
+            "shell\r\n\x1b[?1049h\x1b[Hvim\r\n~\r\n~\r\n~\x1b[?1049l",
+        );
+        assert!(!emulator.screen().is_alternate_screen());
+        assert_eq!(history_text(&emulator), ["shell", ""]);
+        assert_eq!(emulator.screen().cursor(), Cursor { row: 1, col: 0 });
+    }
+
+    #[test]
+    fn scroll_region_scrolls_only_its_rows() {
+        let emulator = feed(10, 4, 10, "top\r\na\r\nb\r\nbottom\x1b[2;3r\x1b[3;1H\nc");
+        assert_eq!(visible_text(&emulator), ["top", "b", "c", "bottom"]);
+        assert_eq!(emulator.screen().history_len(), 4);
+    }
+
+    #[test]
+    fn wide_characters_take_two_cells() {
+        let emulator = feed(5, 2, 10, "日本語");
+        assert_eq!(visible_text(&emulator), ["日本", "語"]);
+        assert!(emulator.screen().visible().next().unwrap().wrapped);
+        assert_eq!(emulator.screen().cursor(), Cursor { row: 1, col: 2 });
+    }
+
+    #[test]
+    fn utf8_split_across_chunks_is_reassembled() {
+        let mut emulator = TerminalEmulator::new(10, 2, 10);
+        let bytes = "é".as_bytes();
+        emulator.process(&bytes[..1]);
+        emulator.process(&bytes[1..]);
+        assert_eq!(visible_text(&emulator)[0], "é");
+    }
+
+    #[test]
+    fn shrinking_pushes_rows_above_the_cursor_into_scrollback() {
+        let mut emulator = feed(10, 4, 10, "a\r\nb\r\nc");
+        emulator.resize(10, 2);
+        assert_eq!(visible_text(&emulator), ["b", "c"]);
+        assert_eq!(emulator.screen().cursor().row, 1);
+
+        emulator.resize(10, 4);
+        assert_eq!(visible_text(&emulator), ["a", "b", "c", ""]);
+        assert_eq!(emulator.screen().cursor().row, 2);
+    }
+}
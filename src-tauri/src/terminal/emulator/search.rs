@@ -0,0 +1,207 @@
+//! Scrollback search
+//!
+//! Rows that soft-wrapped are joined into one logical line before matching,
+//! so a URL or path broken across rows by the terminal width is still found.
+//! Matches report cell positions, which differ from character offsets once
+//! double-width characters are involved.
+
+use serde::{Deserialize, Serialize};
+
+use super::cell::Row;
+
+/// A search match spanning one or more rows
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct GridSearchMatch {
+    /// Index into the screen history of the row where the match starts
+    pub start_row: usize,
+    pub start_column: usize,
+    pub end_row: usize,
+    /// Exclusive end column on `end_row`
+    pub end_column: usize,
+    /// The full logical line containing the match
+    pub line_text: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GridSearchResult {
+    pub matches: Vec<GridSearchMatch>,
+    pub total_count: usize,
+    pub query: String,
+}
+
+/// Position of one character of a logical line in the grid
+#[derive(Clone, Copy)]
+struct CharPosition {
+    row: usize,
+    col: usize,
+    width: usize,
+}
+
+/// Soft-wrapped rows joined back together
+struct LogicalLine {
+    chars: Vec<char>,
+    positions: Vec<CharPosition>,
+}
+
+impl LogicalLine {
+    fn push_row(&mut self, index: usize, row: &Row) {
+        for (col, cell) in row.cells.iter().enumerate() {
+            if cell.is_spacer() {
+                if let Some(last) = self.positions.last_mut() {
+                    last.width = 2;
+                }
+                continue;
+            }
+            self.chars.push(cell.c);
+            self.positions.push(CharPosition {
+                row: index,
+                col,
+                width: 1,
+            });
+        }
+    }
+
+    fn trim_end(&mut self) {
+        while self.chars.last() == Some(&' ') {
+            self.chars.pop();
+            self.positions.pop();
+        }
+    }
+}
+
+/// Split rows into logical lines, keeping the history index of each row
+fn logical_lines<'a>(rows: impl Iterator<Item = &'a Row>) -> impl Iterator<Item = LogicalLine> {
+    let mut rows = rows.enumerate();
+    std::iter::from_fn(move || {
+        let mut line = LogicalLine {
+            chars: Vec::new(),
+            positions: Vec::new(),
+        };
+        loop {
+            let (index, row) = rows.next()?;
+            line.push_row(index, row);
+            if !row.wrapped {
+                break;
+            }
+        }
+        line.trim_end();
+        Some(line)
+    })
+}
+
+fn fold(c: char, case_sensitive: bool) -> char {
+    if case_sensitive {
+        c
+    } else {
+        c.to_lowercase().next().unwrap_or(c)
+    }
+}
+
+/// Search the given rows, oldest first
+pub fn search_rows<'a>(
+    rows: impl Iterator<Item = &'a Row>,
+    query: &str,
+    case_sensitive: bool,
+    max_results: Option<usize>,
+) -> GridSearchResult {
+    let max = max_results.unwrap_or(1000);
+    let needle: Vec<char> = query.chars().map(|c| fold(c, case_sensitive)).collect();
+    let mut matches = Vec::new();
+
+    if !needle.is_empty() {
+        'lines: for line in logical_lines(rows) {
+            if line.chars.len() < needle.len() {
+                continue;
+            }
+            let haystack: Vec<char> = line
+                .chars
+                .iter()
+                .map(|c| fold(*c, case_sensitive))
+                .collect();
+            let mut line_text = None;
+            for start in 0..=haystack.len() - needle.len() {
+                if haystack[start..start + needle.len()] != needle[..] {
+                    continue;
+                }
+                if matches.len() >= max {
+                    break 'lines;
+                }
+                let first = line.positions[start];
+                let last = line.positions[start + needle.len() - 1];
+                matches.push(GridSearchMatch {
+                    start_row: first.row,
+                    start_column: first.col,
+                    end_row: last.row,
+                    end_column: last.col + last.width,
+                    line_text: line_text
+                        .get_or_insert_with(|| line.chars.iter().collect::<String>())
+                        .clone(),
+                });
+            }
+        }
+    }
+
+    let total_count = matches.len();
+    GridSearchResult {
+        matches,
+        total_count,
+        query: query.to_string(),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::super::TerminalEmulator;
+
+    fn emulator(cols: usize, input: &str) -> TerminalEmulator {
+        let mut emulator = TerminalEmulator::new(cols, 3, 100);
+        emulator.process(input.as_bytes());
+        emulator
+    }
+
+    #[test]
+    fn finds_matches_across_wrapped_rows() {
+        let emulator = emulator(8, "see https://example.com/x\r\n");
+        let result = emulator.search("example.com", false, None);
+        assert_eq!(result.total_count, 1);
+        let m = &result.matches[0];
+        assert_eq!((m.start_row, m.start_column), (1, 4));
+        assert_eq!((m.end_row, m.end_column), (2, 7));
+        assert_eq!(m.line_text, "see https://example.com/x");
+
+        let result = emulator.search("https://example", false, None);
+        let m = &result.matches[0];
+        assert_eq!(
+            (m.start_row, m.start_column, m.end_row, m.end_column),
+            (0, 4, 2, 3)
+        );
+    }
+
+    #[test]
+    fn does_not_join_rows_ended_by_line_feeds() {
+        let emulator = emulator(20, "foo\r\nbar\r\n");
+        assert_eq!(emulator.search("foobar", false, None).total_count, 0);
+        assert_eq!(emulator.search("bar", false, None).matches[0].start_row, 1);
+    }
+
+    #[test]
+    fn respects_case_and_limits() {
+        let emulator = emulator(20, "Error error ERROR\r\n");
+        assert_eq!(emulator.search("error", false, None).total_count, 3);
+        assert_eq!(emulator.search("error", true, None).total_count, 1);
+        assert_eq!(emulator.search("error", false, Some(2)).total_count, 2);
+        assert_eq!(emulator.search("", false, None).total_count, 0);
+    }
+
+    #[test]
+    fn reports_cell_columns_after_wide_characters() {
+        let emulator = emulator(20, "日本 ok\r\n");
+        let m = &emulator.search("ok", false, None).matches[0];
+        assert_eq!((m.start_column, m.end_column), (5, 7));
+        let m = &emulator.search("本", false, None).matches[0];
+        assert_eq!((m.start_column, m.end_column), (2, 4));
+    }
+}
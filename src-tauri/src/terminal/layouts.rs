@@ -0,0 +1,670 @@
+//! Terminal layouts declared per project
+//!
+//! `.cortex/terminals.json` names the sets of terminals a project needs,
+//! each terminal with its command, working directory, environment and
+//! restart policy, plus how they are split into panes:
+//!
+//! ```json
+//! {
+//!   "layouts": {
+//!     "dev": {
+//!       "terminals": [
+//!         { "name": "check", "command": "cargo watch -x check", "restart": "always" },
+//!         { "name": "web", "command": "npm run dev", "cwd": "web", "restart": "onFailure" },
+//!         { "name": "db", "command": "psql", "env": { "PGDATABASE": "app" } }
+//!       ],
+//!       "panes": {
+//!         "split": "horizontal",
+//!         "panes": [
+//!           { "terminal": "check" },
+//!           { "split": "vertical", "panes": [{ "terminal": "web" }, { "terminal": "db" }] }
+//!         ]
+//!       }
+//!     }
+//!   }
+//! }
+//! ```
+//!
+//! Spawning a layout creates its terminals and tracks them as a group, so
+//! they can be restarted or stopped together. Members are restarted when
+//! they exit if their policy asks for it; closing a member removes it from
+//! its layout.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Listener, Manager};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use super::state::TerminalState;
+use super::types::{CreateTerminalOptions, TerminalInfo, TerminalStatus};
+
+/// First delay before restarting a member that exited
+const RESTART_DELAY: Duration = Duration::from_secs(1);
+
+/// Longest delay between restarts of a member that keeps exiting
+const MAX_RESTART_DELAY: Duration = Duration::from_secs(30);
+
+/// Members that ran this long before exiting are restarted without backoff
+const STABLE_RUN: Duration = Duration::from_secs(10);
+
+/// When a layout terminal is started again after it exits
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum RestartPolicy {
+    #[default]
+    Never,
+    /// Restart when it exits with a non-zero or unknown code
+    OnFailure,
+    Always,
+}
+
+impl RestartPolicy {
+    fn applies(self, exit_code: Option<i32>) -> bool {
+        match self {
+            Self::Never => false,
+            Self::OnFailure => exit_code != Some(0),
+            Self::Always => true,
+        }
+    }
+}
+
+/// A terminal declared in a layout
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LayoutTerminal {
+    pub name: String,
+    /// Command to run; an interactive shell when omitted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    /// Working directory, relative to the workspace
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shell: Option<String>,
+    #[serde(default)]
+    pub restart: RestartPolicy,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SplitDirection {
+    /// Panes side by side
+    Horizontal,
+    /// Panes stacked
+    Vertical,
+}
+
+/// Pane arrangement of a layout
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum LayoutPane {
+    Terminal {
+        terminal: String,
+        /// Share of the parent split
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        size: Option<f32>,
+    },
+    Split {
+        split: SplitDirection,
+        panes: Vec<LayoutPane>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        size: Option<f32>,
+    },
+}
+
+impl LayoutPane {
+    fn terminal_names<'a>(&'a self, names: &mut Vec<&'a str>) {
+        match self {
+            Self::Terminal { terminal, .. } => names.push(terminal),
+            Self::Split { panes, .. } => {
+                for pane in panes {
+                    pane.terminal_names(names);
+                }
+            }
+        }
+    }
+}
+
+/// A named set of terminals
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TerminalLayout {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub terminals: Vec<LayoutTerminal>,
+    /// Pane arrangement; all terminals side by side when omitted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub panes: Option<LayoutPane>,
+}
+
+impl TerminalLayout {
+    fn validate(&self) -> Result<(), String> {
+        if self.terminals.is_empty() {
+            return Err("Layout has no terminals".to_string());
+        }
+        let mut declared = HashSet::new();
+        for terminal in &self.terminals {
+            if !declared.insert(terminal.name.as_str()) {
+                return Err(format!("Terminal '{}' is declared twice", terminal.name));
+            }
+        }
+        let mut placed = Vec::new();
+        if let Some(panes) = &self.panes {
+            panes.terminal_names(&mut placed);
+        }
+        let mut seen = HashSet::new();
+        for name in placed {
+            if !declared.contains(name) {
+                return Err(format!("Pane refers to unknown terminal '{}'", name));
+            }
+            if !seen.insert(name) {
+                return Err(format!("Terminal '{}' is placed in two panes", name));
+            }
+        }
+        Ok(())
+    }
+
+    /// Pane arrangement, with terminals not placed in one put side by side
+    fn resolved_panes(&self) -> LayoutPane {
+        let default_pane = |terminal: &LayoutTerminal| LayoutPane::Terminal {
+            terminal: terminal.name.clone(),
+            size: None,
+        };
+        match &self.panes {
+            Some(panes) => {
+                let mut placed = Vec::new();
+                panes.terminal_names(&mut placed);
+                let unplaced: Vec<LayoutPane> = self
+                    .terminals
+                    .iter()
+                    .filter(|t| !placed.contains(&t.name.as_str()))
+                    .map(default_pane)
+                    .collect();
+                if unplaced.is_empty() {
+                    panes.clone()
+                } else {
+                    let mut all = vec![panes.clone()];
+                    all.extend(unplaced);
+                    LayoutPane::Split {
+                        split: SplitDirection::Horizontal,
+                        panes: all,
+                        size: None,
+                    }
+                }
+            }
+            None => LayoutPane::Split {
+                split: SplitDirection::Horizontal,
+                panes: self.terminals.iter().map(default_pane).collect(),
+                size: None,
+            },
+        }
+    }
+}
+
+/// Contents of `.cortex/terminals.json`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TerminalLayoutsFile {
+    #[serde(default)]
+    pub layouts: BTreeMap<String, TerminalLayout>,
+}
+
+fn layouts_path(workspace: &Path) -> PathBuf {
+    workspace.join(".cortex").join("terminals.json")
+}
+
+/// Read a workspace's layouts; a missing file has none
+pub fn load_layouts(workspace: &Path) -> Result<TerminalLayoutsFile, String> {
+    let path = layouts_path(workspace);
+    if !path.exists() {
+        return Ok(TerminalLayoutsFile::default());
+    }
+    let content = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Invalid {}: {}", path.display(), e))
+}
+
+/// A terminal of a running layout
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LayoutMember {
+    pub name: String,
+    pub terminal_id: String,
+    pub restarts: u32,
+}
+
+/// A spawned layout
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LayoutInstance {
+    pub id: String,
+    pub layout: String,
+    pub workspace: String,
+    pub members: Vec<LayoutMember>,
+    pub panes: LayoutPane,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MemberRestarted {
+    layout_id: String,
+    name: String,
+    previous_terminal_id: String,
+    terminal_id: String,
+}
+
+struct Member {
+    spec: LayoutTerminal,
+    terminal_id: String,
+    restarts: u32,
+    started: Instant,
+    delay: Duration,
+}
+
+struct RunningLayout {
+    id: String,
+    layout: String,
+    workspace: PathBuf,
+    panes: LayoutPane,
+    members: Vec<Member>,
+}
+
+impl RunningLayout {
+    fn instance(&self) -> LayoutInstance {
+        LayoutInstance {
+            id: self.id.clone(),
+            layout: self.layout.clone(),
+            workspace: self.workspace.to_string_lossy().to_string(),
+            members: self
+                .members
+                .iter()
+                .map(|m| LayoutMember {
+                    name: m.spec.name.clone(),
+                    terminal_id: m.terminal_id.clone(),
+                    restarts: m.restarts,
+                })
+                .collect(),
+            panes: self.panes.clone(),
+        }
+    }
+}
+
+fn create_member(
+    app: &AppHandle,
+    workspace: &Path,
+    spec: &LayoutTerminal,
+) -> Result<TerminalInfo, String> {
+    let cwd = match &spec.cwd {
+        Some(cwd) => workspace.join(cwd),
+        None => workspace.to_path_buf(),
+    };
+    let options = CreateTerminalOptions {
+        name: Some(spec.name.clone()),
+        cwd: Some(cwd.to_string_lossy().to_string()),
+        shell: spec.shell.clone(),
+        env: (!spec.env.is_empty()).then(|| spec.env.clone()),
+        workspace: Some(workspace.to_string_lossy().to_string()),
+        command: spec.command.clone(),
+        // Layouts are spawned again from their file, so members do not
+        // outlive the app
+        persistent: Some(false),
+        ..Default::default()
+    };
+    app.state::<TerminalState>().create_terminal(app, options)
+}
+
+fn close_member(app: &AppHandle, terminal_id: &str) {
+    let state = app.state::<TerminalState>();
+    if let Err(e) = state.close_terminal(app, terminal_id) {
+        warn!("Failed to close layout terminal {}: {}", terminal_id, e);
+    }
+}
+
+/// Layouts spawned in this session
+#[derive(Clone, Default)]
+pub struct TerminalLayoutsState {
+    layouts: Arc<Mutex<HashMap<String, RunningLayout>>>,
+    listening: Arc<AtomicBool>,
+}
+
+impl TerminalLayoutsState {
+    /// Follow terminal exits to apply restart policies
+    fn listen(&self, app: &AppHandle) {
+        if self.listening.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let state = self.clone();
+        let app_clone = app.clone();
+        app.listen("terminal:status", move |event| {
+            if let Ok(status) = serde_json::from_str::<TerminalStatus>(event.payload()) {
+                state.on_status(&app_clone, status);
+            }
+        });
+    }
+
+    fn on_status(&self, app: &AppHandle, status: TerminalStatus) {
+        let mut layouts = self.layouts.lock();
+        let Some((layout, index)) = layouts.values_mut().find_map(|layout| {
+            let index = layout
+                .members
+                .iter()
+                .position(|m| m.terminal_id == status.terminal_id)?;
+            Some((layout, index))
+        }) else {
+            return;
+        };
+
+        // A member that was closed rather than exited leaves its layout. This
+        // runs inside emit, so trust the event instead of asking TerminalState
+        if status.status == "closed" {
+            layout.members.remove(index);
+            return;
+        }
+        if status.status != "exited" {
+            return;
+        }
+
+        let member = &mut layout.members[index];
+        if !member.spec.restart.applies(status.exit_code) {
+            return;
+        }
+        member.delay = if member.started.elapsed() >= STABLE_RUN {
+            RESTART_DELAY
+        } else {
+            (member.delay * 2).clamp(RESTART_DELAY, MAX_RESTART_DELAY)
+        };
+        let delay = member.delay;
+        let layout_id = layout.id.clone();
+        let previous = status.terminal_id;
+        info!(
+            "Restarting layout terminal {} in {:?} (exit code {:?})",
+            member.spec.name, delay, status.exit_code
+        );
+
+        let state = self.clone();
+        let app = app.clone();
+        thread::spawn(move || {
+            thread::sleep(delay);
+            state.restart_member(&app, &layout_id, &previous);
+        });
+    }
+
+    fn restart_member(&self, app: &AppHandle, layout_id: &str, previous: &str) {
+        let mut layouts = self.layouts.lock();
+        // The layout may have been stopped or the member closed meanwhile
+        let Some(layout) = layouts.get_mut(layout_id) else {
+            return;
+        };
+        let workspace = layout.workspace.clone();
+        let Some(member) = layout
+            .members
+            .iter_mut()
+            .find(|m| m.terminal_id == previous)
+        else {
+            return;
+        };
+        match create_member(app, &workspace, &member.spec) {
+            Ok(info) => {
+                member.terminal_id = info.id.clone();
+                member.restarts += 1;
+                member.started = Instant::now();
+                let event = MemberRestarted {
+                    layout_id: layout_id.to_string(),
+                    name: member.spec.name.clone(),
+                    previous_terminal_id: previous.to_string(),
+                    terminal_id: info.id,
+                };
+                drop(layouts);
+                close_member(app, previous);
+                let _ = app.emit("terminal:layout-member-restarted", &event);
+            }
+            Err(e) => warn!(
+                "Failed to restart layout terminal {}: {}",
+                member.spec.name, e
+            ),
+        }
+    }
+
+    fn start(
+        &self,
+        app: &AppHandle,
+        id: String,
+        workspace: &Path,
+        name: &str,
+    ) -> Result<LayoutInstance, String> {
+        let file = load_layouts(workspace)?;
+        let layout = file
+            .layouts
+            .get(name)
+            .ok_or_else(|| format!("Layout '{}' not found", name))?;
+        layout
+            .validate()
+            .map_err(|e| format!("Layout '{}': {}", name, e))?;
+
+        self.listen(app);
+        let mut members: Vec<Member> = Vec::new();
+        for spec in &layout.terminals {
+            match create_member(app, workspace, spec) {
+                Ok(info) => members.push(Member {
+                    spec: spec.clone(),
+                    terminal_id: info.id,
+                    restarts: 0,
+                    started: Instant::now(),
+                    delay: RESTART_DELAY,
+                }),
+                Err(e) => {
+                    for member in &members {
+                        close_member(app, &member.terminal_id);
+                    }
+                    return Err(format!("Failed to start terminal '{}': {}", spec.name, e));
+                }
+            }
+        }
+
+        let running = RunningLayout {
+            id: id.clone(),
+            layout: name.to_string(),
+            workspace: workspace.to_path_buf(),
+            panes: layout.resolved_panes(),
+            members,
+        };
+        let instance = running.instance();
+        self.layouts.lock().insert(id, running);
+        info!(
+            "Spawned terminal layout {} in {}",
+            name,
+            workspace.display()
+        );
+        Ok(instance)
+    }
+
+    /// Spawn a layout from the workspace's `.cortex/terminals.json`
+    pub fn spawn(
+        &self,
+        app: &AppHandle,
+        workspace: &Path,
+        name: &str,
+    ) -> Result<LayoutInstance, String> {
+        self.start(app, Uuid::new_v4().to_string(), workspace, name)
+    }
+
+    /// Close all terminals of a layout
+    pub fn stop(&self, app: &AppHandle, layout_id: &str) -> Result<(), String> {
+        let layout = self
+            .layouts
+            .lock()
+            .remove(layout_id)
+            .ok_or_else(|| format!("Layout {} is not running", layout_id))?;
+        for member in &layout.members {
+            close_member(app, &member.terminal_id);
+        }
+        info!("Stopped terminal layout {}", layout.layout);
+        Ok(())
+    }
+
+    /// Close all terminals of a layout and spawn it again, re-reading the
+    /// layout file
+    pub fn restart(&self, app: &AppHandle, layout_id: &str) -> Result<LayoutInstance, String> {
+        let (name, workspace) = {
+            let layouts = self.layouts.lock();
+            let layout = layouts
+                .get(layout_id)
+                .ok_or_else(|| format!("Layout {} is not running", layout_id))?;
+            (layout.layout.clone(), layout.workspace.clone())
+        };
+        self.stop(app, layout_id)?;
+        self.start(app, layout_id.to_string(), &workspace, &name)
+    }
+
+    /// Running layouts, optionally of one workspace
+    pub fn list(&self, workspace: Option<&Path>) -> Vec<LayoutInstance> {
+        self.layouts
+            .lock()
+            .values()
+            .filter(|layout| workspace.is_none_or(|w| layout.workspace == w))
+            .map(RunningLayout::instance)
+            .collect()
+    }
+}
+
+// ===== Tauri Commands =====
+
+/// Get the layouts declared in a workspace's `.cortex/terminals.json`
+#[tauri::command]
+pub async fn terminal_layouts_load(workspace_path: String) -> Result<TerminalLayoutsFile, String> {
+    load_layouts(Path::new(&workspace_path))
+}
+
+/// Spawn all terminals of a layout
+#[tauri::command]
+pub async fn terminal_layout_spawn(
+    app: AppHandle,
+    state: tauri::State<'_, TerminalLayoutsState>,
+    workspace_path: String,
+    layout: String,
+) -> Result<LayoutInstance, String> {
+    state.spawn(&app, Path::new(&workspace_path), &layout)
+}
+
+/// List running layouts
+#[tauri::command]
+pub async fn terminal_layouts_running(
+    state: tauri::State<'_, TerminalLayoutsState>,
+    workspace_path: Option<String>,
+) -> Result<Vec<LayoutInstance>, String> {
+    Ok(state.list(workspace_path.as_deref().map(Path::new)))
+}
+
+/// Restart all terminals of a running layout
+#[tauri::command]
+pub async fn terminal_layout_restart(
+    app: AppHandle,
+    state: tauri::State<'_, TerminalLayoutsState>,
+    layout_id: String,
+) -> Result<LayoutInstance, String> {
+    state.restart(&app, &layout_id)
+}
+
+/// Stop all terminals of a running layout
+#[tauri::command]
+pub async fn terminal_layout_stop(
+    app: AppHandle,
+    state: tauri::State<'_, TerminalLayoutsState>,
+    layout_id: String,
+) -> Result<(), String> {
+    state.stop(&app, &layout_id)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    fn parse(json: &str) -> TerminalLayoutsFile {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn parses_layout_file() {
+        let file = parse(
+            r#"{
+                "layouts": {
+                    "dev": {
+                        "terminals": [
+                            { "name": "check", "command": "cargo watch", "restart": "always" },
+                            { "name": "web", "cwd": "web", "env": { "PORT": "3000" }, "restart": "onFailure" },
+                            { "name": "db" }
+                        ],
+                        "panes": {
+                            "split": "horizontal",
+                            "panes": [
+                                { "terminal": "check", "size": 0.5 },
+                                { "split": "vertical", "panes": [{ "terminal": "web" }, { "terminal": "db" }] }
+                            ]
+                        }
+                    }
+                }
+            }"#,
+        );
+        let layout = &file.layouts["dev"];
+        assert!(layout.validate().is_ok());
+        assert_eq!(layout.terminals[0].restart, RestartPolicy::Always);
+        assert_eq!(layout.terminals[1].env["PORT"], "3000");
+        assert_eq!(layout.terminals[2].restart, RestartPolicy::Never);
+        assert_eq!(layout.resolved_panes(), layout.panes.clone().unwrap());
+    }
+
+    #[test]
+    fn rejects_inconsistent_layouts() {
+        let file = parse(
+            r#"{ "layouts": {
+                "dup": { "terminals": [{ "name": "a" }, { "name": "a" }] },
+                "unknown": { "terminals": [{ "name": "a" }], "panes": { "terminal": "b" } },
+                "twice": { "terminals": [{ "name": "a" }], "panes": { "split": "vertical", "panes": [{ "terminal": "a" }, { "terminal": "a" }] } },
+                "empty": { "terminals": [] }
+            } }"#,
+        );
+        for layout in file.layouts.values() {
+            assert!(layout.validate().is_err());
+        }
+    }
+
+    #[test]
+    fn unplaced_terminals_are_added_side_by_side() {
+        let file = parse(
+            r#"{ "layouts": { "dev": {
+                "terminals": [{ "name": "a" }, { "name": "b" }],
+                "panes": { "terminal": "a" }
+            } } }"#,
+        );
+        let mut names = Vec::new();
+        let panes = file.layouts["dev"].resolved_panes();
+        panes.terminal_names(&mut names);
+        assert_eq!(names, ["a", "b"]);
+    }
+
+    #[test]
+    fn restart_policies() {
+        assert!(!RestartPolicy::Never.applies(Some(1)));
+        assert!(RestartPolicy::OnFailure.applies(Some(1)));
+        assert!(RestartPolicy::OnFailure.applies(None));
+        assert!(!RestartPolicy::OnFailure.applies(Some(0)));
+        assert!(RestartPolicy::Always.applies(Some(0)));
+    }
+
+    #[test]
+    fn missing_layout_file_has_no_layouts() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(load_layouts(dir.path()).unwrap().layouts.is_empty());
+    }
+}
@@ -12,29 +12,65 @@
 //! # Module Structure
 //!
 //! - `types`: Data structures for terminal info, options, and events
+//! - `blocks`: Command blocks and per-workspace command history
+//! - `problems`: Problem matching on command block output
+//! - `recording`: asciicast recording and replay of terminal sessions
+//! - `layouts`: Project terminal layouts from `.cortex/terminals.json`
 //! - `constants`: Performance tuning constants
 //! - `flow_control`: Backpressure management for terminal output
+//! - `emulator`: Server-side VT emulation with scrollback, search and export
 //! - `shell_integration`: Shell integration script injection
 //! - `process`: Process management utilities
+//! - `pty`: Shell PTY spawning shared by the app and the PTY host
+//! - `pty_host`: Daemon keeping persistent terminals alive across restarts
 //! - `state`: Core terminal state and PTY management
 //! - `commands`: Tauri IPC commands
 
+#[cfg(feature = "desktop")]
+pub mod blocks;
+#[cfg(feature = "desktop")]
 pub mod commands;
 mod constants;
+#[cfg(feature = "desktop")]
 mod flow_control;
+#[cfg(feature = "desktop")]
+mod problems;
 mod process;
+mod pty;
+#[cfg(all(unix, feature = "desktop"))]
+pub mod pty_host;
+#[cfg(feature = "desktop")]
+pub mod recording;
 mod shell_integration;
+#[cfg(feature = "desktop")]
 mod state;
 mod types;
 
+#[cfg(feature = "desktop")]
+pub mod emulator;
+#[cfg(feature = "desktop")]
+pub mod layouts;
+#[cfg(feature = "desktop")]
 pub mod links;
+#[cfg(feature = "desktop")]
 pub mod profiles;
+#[cfg(feature = "desktop")]
 pub mod protocol;
+#[cfg(feature = "desktop")]
 pub mod search;
 
 // Re-export public types for external use
-pub use types::CreateTerminalOptions;
+pub use types::{CreateTerminalOptions, TerminalInfo, TerminalOutput, TerminalStatus};
+
+// Used by the remote agent to serve terminals on an SSH host
+pub(crate) use process::kill_process_tree;
+pub(crate) use pty::{default_shell, spawn_shell, take_utf8};
+pub(crate) use shell_integration::inject_shell_integration;
 
 // Re-export state
+#[cfg(feature = "desktop")]
+pub use layouts::TerminalLayoutsState;
+#[cfg(feature = "desktop")]
 pub use profiles::TerminalProfilesState;
+#[cfg(feature = "desktop")]
 pub use state::TerminalState;
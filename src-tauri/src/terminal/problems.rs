@@ -0,0 +1,241 @@
+//! Problem matching on command blocks
+//!
+//! Opt-in per terminal. A running command's output is matched line by line
+//! against the builtin problem matchers of `tasks` (`$rustc`, `$tsc`,
+//! `$gcc`, ...) as it streams in, so problems early in a long output are
+//! found even though its block keeps only the end. When the block finishes,
+//! the hits become build diagnostics. Each command line and working
+//! directory reports under its own source name, so running the same command
+//! again replaces its previous problems, and a clean run clears them.
+
+use std::path::Path;
+
+use tauri::{AppHandle, Manager};
+
+use super::blocks::CommandBlock;
+use crate::diagnostics::{DiagnosticsState, UnifiedDiagnostic};
+use crate::tasks::{
+    ProblemMatcher, TaskDiagnosticEvent, get_builtin_problem_matcher, match_problem_line,
+    parse_rustc_header,
+};
+
+/// Longest partial line held back between output chunks
+const MAX_PARTIAL_LINE_BYTES: usize = 16 * 1024;
+
+/// Text of a raw output line as it was shown: escape sequences dropped and
+/// carriage-return overwrites resolved
+fn plain_text(raw: &str) -> String {
+    let shown = raw.rsplit('\r').find(|s| !s.is_empty()).unwrap_or("");
+    let mut out = String::with_capacity(shown.len());
+    let mut chars = shown.chars();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            // CSI: parameters up to the final byte
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if ('\x40'..='\x7e').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            // OSC: up to BEL or ST
+            Some(']') => loop {
+                match chars.next() {
+                    None | Some('\x07') => break,
+                    Some('\x1b') => {
+                        chars.next();
+                        break;
+                    }
+                    Some(_) => {}
+                }
+            },
+            _ => {}
+        }
+    }
+    out
+}
+
+/// Problems found so far in a running command's output
+#[derive(Default)]
+pub(super) struct ProblemScan {
+    /// Raw text of the line still being written
+    partial: String,
+    last_rustc: Option<(String, String)>,
+    problems: Vec<TaskDiagnosticEvent>,
+}
+
+/// Problem matchers enabled on a terminal
+pub(super) struct BlockProblemMatcher {
+    names: Vec<String>,
+    matchers: Vec<ProblemMatcher>,
+}
+
+impl BlockProblemMatcher {
+    /// Builtin matchers by name, e.g. `$rustc`
+    pub(super) fn new(names: &[String]) -> Result<Self, String> {
+        let matchers = names
+            .iter()
+            .map(|name| {
+                get_builtin_problem_matcher(name)
+                    .ok_or_else(|| format!("Unknown problem matcher: {}", name))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            names: names.to_vec(),
+            matchers,
+        })
+    }
+
+    pub(super) fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Match the lines a chunk of a block's raw output completes
+    pub(super) fn scan(&self, scan: &mut ProblemScan, block_id: &str, output: &str) {
+        scan.partial.push_str(output);
+        if let Some(end) = scan.partial.rfind('\n') {
+            let rest = scan.partial.split_off(end + 1);
+            let complete = std::mem::replace(&mut scan.partial, rest);
+            for line in complete.lines() {
+                self.match_line(scan, block_id, line);
+            }
+        }
+        // A line this long is no problem report
+        if scan.partial.len() > MAX_PARTIAL_LINE_BYTES {
+            scan.partial.clear();
+        }
+    }
+
+    fn match_line(&self, scan: &mut ProblemScan, block_id: &str, raw: &str) {
+        let line = plain_text(raw);
+        if let Some(ctx) = parse_rustc_header(&line) {
+            scan.last_rustc = Some(ctx);
+        }
+        if let Some(problem) = match_problem_line(&line, &self.matchers, block_id, &scan.last_rustc)
+        {
+            scan.problems.push(problem);
+        }
+    }
+
+    /// Diagnostics scanned from a finished block's output, with the source
+    /// name they are reported under
+    ///
+    /// Blocks without a command line or directory to resolve paths against
+    /// are skipped.
+    pub(super) fn diagnostics(
+        &self,
+        block: &CommandBlock,
+        mut scan: ProblemScan,
+    ) -> Option<(String, Vec<UnifiedDiagnostic>)> {
+        let partial = std::mem::take(&mut scan.partial);
+        self.match_line(&mut scan, &block.id, &partial);
+
+        if block.command_line.is_empty() {
+            return None;
+        }
+        let base_dir = block.cwd.as_deref().or(block.workspace.as_deref())?;
+        let source_name = format!("{} ({})", block.command_line, base_dir);
+        let diagnostics = scan
+            .problems
+            .iter()
+            .filter_map(|problem| {
+                UnifiedDiagnostic::from_problem(problem, Path::new(base_dir), &source_name)
+            })
+            .collect();
+        Some((source_name, diagnostics))
+    }
+}
+
+/// Replace the build diagnostics reported under `source_name`
+pub(super) fn report(app: &AppHandle, source_name: &str, diagnostics: Vec<UnifiedDiagnostic>) {
+    if let Some(state) = app.try_state::<DiagnosticsState>() {
+        state.replace_build_diagnostics(app, source_name, diagnostics);
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    fn block(command_line: &str, cwd: Option<&str>) -> CommandBlock {
+        CommandBlock {
+            id: "block".to_string(),
+            terminal_id: "term".to_string(),
+            workspace: Some("/work".to_string()),
+            command_line: command_line.to_string(),
+            cwd: cwd.map(str::to_string),
+            started_at: 0,
+            ended_at: Some(1),
+            exit_code: Some(1),
+            output_start: 0,
+            output_end: Some(0),
+            output: String::new(),
+            output_truncated: false,
+        }
+    }
+
+    fn scan_output(
+        matcher: &BlockProblemMatcher,
+        block: &CommandBlock,
+        chunks: &[&str],
+    ) -> Option<(String, Vec<UnifiedDiagnostic>)> {
+        let mut scan = ProblemScan::default();
+        for chunk in chunks {
+            matcher.scan(&mut scan, &block.id, chunk);
+        }
+        matcher.diagnostics(block, scan)
+    }
+
+    #[test]
+    fn matches_rustc_errors_relative_to_the_block_cwd() {
+        let matcher = BlockProblemMatcher::new(&["$rustc".to_string()]).unwrap();
+        let output = "   Compiling demo v0.1.0\n\
+                      error[E0308]: mismatched types\n \
+                      --> src/main.rs:2:5\n";
+        let (source_name, diagnostics) = scan_output(
+            &matcher,
+            &block("cargo build", Some("/work/demo")),
+            &[output],
+        )
+        .unwrap();
+        assert_eq!(source_name, "cargo build (/work/demo)");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].uri, "file:///work/demo/src/main.rs");
+        assert_eq!(diagnostics[0].message, "mismatched types");
+        assert_eq!(diagnostics[0].range.start.line, 1);
+    }
+
+    #[test]
+    fn clean_runs_report_no_problems_and_unknown_commands_are_skipped() {
+        let matcher = BlockProblemMatcher::new(&["$gcc".to_string()]).unwrap();
+        let (_, diagnostics) =
+            scan_output(&matcher, &block("make", None), &["Nothing to be done"]).unwrap();
+        assert!(diagnostics.is_empty());
+        assert!(scan_output(&matcher, &block("", None), &["main.c:1:1: error: x"]).is_none());
+    }
+
+    #[test]
+    fn matches_lines_split_across_chunks_and_styled() {
+        let matcher = BlockProblemMatcher::new(&["$gcc".to_string()]).unwrap();
+        let chunks = [
+            "  0%\r\x1b[1mmain.c:3:7: \x1b[31mer",
+            "ror\x1b[0m: expected ';'\r\n",
+            "util.c:9:1: warning: unused",
+        ];
+        let (_, diagnostics) = scan_output(&matcher, &block("make", None), &chunks).unwrap();
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].uri, "file:///work/main.c");
+        assert_eq!(diagnostics[0].message, "expected ';'");
+        assert_eq!(diagnostics[1].uri, "file:///work/util.c");
+    }
+
+    #[test]
+    fn rejects_unknown_matchers() {
+        assert!(BlockProblemMatcher::new(&["$nope".to_string()]).is_err());
+    }
+}
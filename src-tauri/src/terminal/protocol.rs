@@ -3,6 +3,8 @@
 //! Parses VS Code-style shell integration sequences (OSC 633) to track
 //! command boundaries, working directory, and other shell state.
 
+use std::ops::Range;
+
 use serde::{Deserialize, Serialize};
 
 /// Shell integration events derived from OSC 633 sequences
@@ -39,6 +41,15 @@ pub struct ShellIntegrationState {
 ///   P;Cwd=path - Set working directory
 ///   SetMark - Set a mark
 pub fn parse_osc_633(text: &str) -> Vec<ShellIntegrationEvent> {
+    parse_osc_633_spans(text)
+        .into_iter()
+        .map(|(_, event)| event)
+        .collect()
+}
+
+/// Parse OSC 633 sequences along with the byte range each one occupies in
+/// `text`, terminator included.
+pub fn parse_osc_633_spans(text: &str) -> Vec<(Range<usize>, ShellIntegrationEvent)> {
     let mut events = Vec::new();
     let mut pos = 0;
     let bytes = text.as_bytes();
@@ -50,19 +61,20 @@ pub fn parse_osc_633(text: &str) -> Vec<ShellIntegrationEvent> {
                     let content_start = pos + 6;
                     let end_st = text[content_start..].find('\x07');
                     let end_esc = text[content_start..].find("\x1b\\");
-                    let end_pos = match (end_st, end_esc) {
-                        (Some(a), Some(b)) => Some(a.min(b)),
-                        (Some(a), None) => Some(a),
-                        (None, Some(b)) => Some(b),
+                    let end = match (end_st, end_esc) {
+                        (Some(a), Some(b)) if b < a => Some((b, 2)),
+                        (Some(a), _) => Some((a, 1)),
+                        (None, Some(b)) => Some((b, 2)),
                         (None, None) => None,
                     };
 
-                    if let Some(end) = end_pos {
+                    if let Some((end, terminator_len)) = end {
                         let content = &text[content_start..content_start + end];
+                        let sequence_end = content_start + end + terminator_len;
                         if let Some(event) = parse_osc_633_content(content) {
-                            events.push(event);
+                            events.push((pos..sequence_end, event));
                         }
-                        pos = content_start + end + 1;
+                        pos = sequence_end;
                         continue;
                     }
                 }
@@ -258,6 +270,16 @@ mod tests {
         assert!(!state.in_command);
     }
 
+    #[test]
+    fn test_spans_cover_whole_sequences() {
+        let text = "a\x1b]633;C\x07out\x1b]633;D;1\x1b\\b";
+        let spans = parse_osc_633_spans(text);
+        assert_eq!(spans.len(), 2);
+        assert_eq!(&text[spans[0].0.clone()], "\x1b]633;C\x07");
+        assert_eq!(&text[spans[1].0.clone()], "\x1b]633;D;1\x1b\\");
+        assert_eq!(&text[spans[0].0.end..spans[1].0.start], "out");
+    }
+
     #[test]
     fn test_no_osc_sequences() {
         let events = parse_osc_633("just regular text");
@@ -0,0 +1,318 @@
+//! Shell PTY spawning
+//!
+//! Opens a PTY and starts a shell in it. Shared by terminals owned by the app,
+//! the PTY host daemon and the remote agent.
+
+use std::io::{BufWriter, Read, Write};
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use portable_pty::{Child, CommandBuilder, MasterPty, PtyPair, PtySize, native_pty_system};
+use tracing::info;
+
+use super::constants::{DANGEROUS_ENV_VARS, OUTPUT_BUFFER_MAX_SIZE};
+use super::shell_integration::SHELL_INTEGRATION_PWSH;
+use super::types::{CreateTerminalOptions, TerminalInfo};
+
+/// A shell running in a freshly opened PTY
+pub(crate) struct ShellPty {
+    pub info: TerminalInfo,
+    pub master: Box<dyn MasterPty + Send>,
+    pub writer: Arc<Mutex<BufWriter<Box<dyn Write + Send>>>>,
+    pub reader: Box<dyn Read + Send>,
+    pub child: Box<dyn Child + Send + Sync>,
+    /// Process ID for cleanup on close
+    pub child_pid: Option<u32>,
+    /// Whether shell integration should be injected once the shell is up
+    pub inject_integration: bool,
+}
+
+/// Get the default working directory
+fn get_default_cwd() -> String {
+    std::env::current_dir()
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_else(|_| {
+            dirs::home_dir()
+                .map(|p| p.to_string_lossy().to_string())
+                .unwrap_or_else(|| ".".to_string())
+        })
+}
+
+/// Get the default shell for the current platform
+pub(crate) fn default_shell() -> String {
+    #[cfg(target_os = "windows")]
+    {
+        // Try PowerShell first, fall back to cmd.exe
+        if std::env::var("COMSPEC").is_ok() {
+            // Check if PowerShell exists
+            let pwsh_paths = [
+                "C:\\Program Files\\PowerShell\\7\\pwsh.exe",
+                "C:\\Windows\\System32\\WindowsPowerShell\\v1.0\\powershell.exe",
+            ];
+            for path in pwsh_paths {
+                if std::path::Path::new(path).exists() {
+                    return path.to_string();
+                }
+            }
+        }
+        std::env::var("COMSPEC").unwrap_or_else(|_| "cmd.exe".to_string())
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        std::env::var("SHELL").unwrap_or_else(|_| "/bin/zsh".to_string())
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        std::env::var("SHELL").unwrap_or_else(|_| "/bin/bash".to_string())
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+    {
+        std::env::var("SHELL").unwrap_or_else(|_| "/bin/sh".to_string())
+    }
+}
+
+/// Open a PTY and spawn the shell described by `options` in it
+pub(crate) fn spawn_shell(
+    terminal_id: String,
+    options: CreateTerminalOptions,
+    default_shell: impl FnOnce() -> String,
+) -> Result<ShellPty, String> {
+    let shell = options.shell.unwrap_or_else(default_shell);
+    let cwd = options.cwd.unwrap_or_else(get_default_cwd);
+    let name = options
+        .name
+        .unwrap_or_else(|| format!("Terminal {}", terminal_id.split('-').next().unwrap_or("1")));
+    let cols = options.cols.unwrap_or(120);
+    let rows = options.rows.unwrap_or(30);
+
+    // Create PTY
+    let pty_system = native_pty_system();
+    let pair: PtyPair = pty_system
+        .openpty(PtySize {
+            rows,
+            cols,
+            pixel_width: 0,
+            pixel_height: 0,
+        })
+        .map_err(|e| format!("Failed to open PTY: {}", e))?;
+
+    // Build command - for PowerShell, inject shell integration via command line args (like VS Code)
+    let shell_lower = shell.to_lowercase();
+    // A command runs without a prompt, so there is nothing to integrate with
+    let should_inject = options.shell_integration.unwrap_or(true)
+        && options.command.is_none()
+        && options.args.is_none();
+    let is_powershell = shell_lower.contains("pwsh") || shell_lower.contains("powershell");
+    let is_cmd = shell_lower.ends_with("cmd.exe") || shell_lower == "cmd";
+
+    let mut cmd = if let Some(args) = &options.args {
+        let mut c = CommandBuilder::new(&shell);
+        c.args(args);
+        c
+    } else if let Some(command) = &options.command {
+        let mut c = CommandBuilder::new(&shell);
+        if is_powershell {
+            c.arg("-NoLogo");
+            c.arg("-Command");
+        } else if is_cmd {
+            c.arg("/C");
+        } else {
+            c.arg("-c");
+        }
+        c.arg(command);
+        c
+    } else if is_powershell && should_inject {
+        // For PowerShell: use -NoExit -Command to source the integration script
+        // This avoids the script being echoed to the terminal (like VS Code does)
+        let mut c = CommandBuilder::new(&shell);
+        c.arg("-NoLogo");
+        c.arg("-NoExit");
+        c.arg("-Command");
+        // Inline the shell integration script as a command
+        // Using try/catch to suppress errors silently
+        let script = SHELL_INTEGRATION_PWSH
+            .lines()
+            .filter(|l| !l.trim().starts_with('#') && !l.trim().is_empty())
+            .collect::<Vec<_>>()
+            .join("; ");
+        c.arg(format!("try {{ {} }} catch {{}}", script));
+        c
+    } else {
+        CommandBuilder::new(&shell)
+    };
+
+    // Set working directory
+    cmd.cwd(&cwd);
+
+    // Add environment variables (with security filtering)
+    if let Some(env) = &options.env {
+        for (key, value) in env {
+            let key_upper = key.to_uppercase();
+            if DANGEROUS_ENV_VARS
+                .iter()
+                .any(|&blocked| key_upper == blocked)
+            {
+                tracing::warn!("Blocked potentially dangerous env var: {}", key);
+                continue;
+            }
+            cmd.env(key, value);
+        }
+    }
+
+    // Set common terminal environment variables
+    cmd.env("TERM", "xterm-256color");
+    cmd.env("COLORTERM", "truecolor");
+    cmd.env("TERM_PROGRAM", "CortexDesktop");
+    cmd.env("TERM_PROGRAM_VERSION", env!("CARGO_PKG_VERSION"));
+
+    #[cfg(target_os = "windows")]
+    {
+        // Force UTF-8 on Windows
+        cmd.env("PYTHONIOENCODING", "utf-8");
+    }
+
+    // Spawn the child process
+    let child = pair
+        .slave
+        .spawn_command(cmd)
+        .map_err(|e| format!("Failed to spawn shell process: {}", e))?;
+
+    // Store PID for cleanup on close
+    let child_pid = child.process_id();
+
+    info!(
+        "Terminal {} created with shell: {}, cwd: {}, pid: {:?}",
+        terminal_id, shell, cwd, child_pid
+    );
+
+    let master = pair.master;
+
+    // Get writer from master with buffering
+    let raw_writer = master
+        .take_writer()
+        .map_err(|e| format!("Failed to get PTY writer: {}", e))?;
+    let writer = Arc::new(Mutex::new(BufWriter::with_capacity(
+        OUTPUT_BUFFER_MAX_SIZE,
+        raw_writer,
+    )));
+
+    let reader = master
+        .try_clone_reader()
+        .map_err(|e| format!("Failed to get PTY reader: {}", e))?;
+
+    let info = TerminalInfo {
+        id: terminal_id,
+        name,
+        cwd,
+        shell,
+        cols,
+        rows,
+        status: "running".to_string(),
+        created_at: chrono::Utc::now().timestamp_millis(),
+        last_command: None,
+        last_exit_code: None,
+        command_running: false,
+        workspace: options.workspace,
+    };
+
+    Ok(ShellPty {
+        info,
+        master,
+        writer,
+        reader,
+        child,
+        child_pid,
+        inject_integration: should_inject,
+    })
+}
+
+/// Take the valid UTF-8 text at the front of `pending`
+///
+/// Truly invalid sequences are replaced; an incomplete sequence at the end is
+/// kept for the next read.
+pub(crate) fn take_utf8(pending: &mut Vec<u8>) -> String {
+    let mut text = String::new();
+    loop {
+        match std::str::from_utf8(pending) {
+            Ok(s) => {
+                text.push_str(s);
+                pending.clear();
+                return text;
+            }
+            Err(e) => {
+                let valid_up_to = e.valid_up_to();
+                text.push_str(&String::from_utf8_lossy(&pending[..valid_up_to]));
+                match e.error_len() {
+                    Some(error_len) => {
+                        // Skip truly invalid sequence
+                        text.push_str(&String::from_utf8_lossy(
+                            &pending[valid_up_to..valid_up_to + error_len],
+                        ));
+                        pending.drain(..valid_up_to + error_len);
+                    }
+                    None => {
+                        // Keep incomplete sequence for next read
+                        pending.drain(..valid_up_to);
+                        return text;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_incomplete_sequences_for_the_next_read() {
+        let euro = "€".as_bytes();
+        let mut pending = b"ab".to_vec();
+        pending.extend_from_slice(&euro[..2]);
+        assert_eq!(take_utf8(&mut pending), "ab");
+        assert_eq!(pending, &euro[..2]);
+
+        pending.extend_from_slice(&euro[2..]);
+        assert_eq!(take_utf8(&mut pending), "€");
+        assert!(pending.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn args_reach_the_program_without_a_shell() {
+        let options = CreateTerminalOptions {
+            shell: Some("printf".to_string()),
+            args: Some(
+                ["[%s]", "a b", "$HOME", "%PATH%", "&|<>^\"'"]
+                    .map(str::to_string)
+                    .to_vec(),
+            ),
+            persistent: Some(false),
+            ..Default::default()
+        };
+        let mut shell = spawn_shell("t".to_string(), options, String::new).unwrap();
+        shell.child.wait().unwrap();
+        let mut output = Vec::new();
+        let mut buf = [0u8; 1024];
+        // The PTY reports EIO rather than EOF once the program has exited
+        while let Ok(n @ 1..) = shell.reader.read(&mut buf) {
+            output.extend_from_slice(&buf[..n]);
+        }
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "[a b][$HOME][%PATH%][&|<>^\"']"
+        );
+    }
+
+    #[test]
+    fn replaces_invalid_bytes() {
+        let mut pending = vec![b'a', 0xff, b'b'];
+        assert_eq!(take_utf8(&mut pending), "a\u{fffd}b");
+        assert!(pending.is_empty());
+    }
+}
@@ -0,0 +1,253 @@
+//! Connection from the app to the PTY host
+//!
+//! Requests block the calling thread until their reply arrives. A reader
+//! thread hands every frame to the `on_message` callback first, so terminals
+//! can be registered from their `Created`/`Attached` reply before any of
+//! their output is processed. The callback can only send notifications,
+//! since replies are read by the thread it runs on.
+//!
+//! A host speaking an older protocol is asked to shut down and replaced.
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader};
+use std::os::unix::net::UnixStream;
+use std::os::unix::process::CommandExt;
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use tracing::{info, warn};
+
+use super::protocol::{
+    HostMessage, HostReply, HostRequest, MIN_PROTOCOL_VERSION, NOTIFICATION_ID, PROTOCOL_VERSION,
+    RequestFrame, write_frame,
+};
+use super::{DAEMON_ARG, socket_path};
+
+/// How long to wait for a reply from the host
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long a replaced host gets to exit
+const UPGRADE_TIMEOUT: Duration = Duration::from_secs(5);
+
+type PendingReplies = Arc<Mutex<HashMap<u64, mpsc::Sender<Result<HostReply, String>>>>>;
+
+pub(super) type MessageHandler = Arc<dyn Fn(&HostMessage, &Notifier) + Send + Sync>;
+
+/// Sends requests without waiting for their replies
+#[derive(Clone)]
+pub(super) struct Notifier {
+    writer: Arc<Mutex<UnixStream>>,
+}
+
+impl Notifier {
+    pub(super) fn notify(&self, request: HostRequest) -> Result<(), String> {
+        let frame = RequestFrame {
+            id: NOTIFICATION_ID,
+            request,
+        };
+        write_frame(&mut *self.writer.lock(), &frame)
+            .map_err(|e| format!("Failed to reach PTY host: {}", e))
+    }
+}
+
+pub(super) struct PtyHostClient {
+    notifier: Notifier,
+    pending: PendingReplies,
+    next_id: AtomicU64,
+    connected: Arc<AtomicBool>,
+}
+
+/// Start the host detached from the app, so it outlives it
+fn spawn_host(socket: &Path) -> Result<(), String> {
+    let exe = std::env::current_exe().map_err(|e| format!("Failed to locate Cortex: {}", e))?;
+    let mut command = std::process::Command::new(exe);
+    command
+        .arg(DAEMON_ARG)
+        .arg(socket)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    // SAFETY: setsid is async-signal-safe and touches no memory; it moves the
+    // host into its own session so closing the app's terminal or process
+    // group does not take it down.
+    unsafe {
+        command.pre_exec(|| {
+            libc::setsid();
+            Ok(())
+        });
+    }
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("Failed to start PTY host: {}", e))?;
+    // Reap the host if it exits while the app is still running
+    thread::spawn(move || child.wait());
+    Ok(())
+}
+
+/// Wait for a host that is shutting down to stop accepting connections
+fn wait_for_exit(socket: &Path) {
+    let started = std::time::Instant::now();
+    while UnixStream::connect(socket).is_ok() && started.elapsed() < UPGRADE_TIMEOUT {
+        thread::sleep(Duration::from_millis(100));
+    }
+}
+
+impl PtyHostClient {
+    /// Connect to the PTY host, starting it first when `spawn` is set
+    ///
+    /// Returns `Ok(None)` when no host is running and `spawn` is not set. A
+    /// host too old for this version is replaced only once none of its
+    /// terminals is running; until then it keeps serving them to the app
+    /// that started it.
+    pub(super) fn connect(spawn: bool, on_message: MessageHandler) -> Result<Option<Self>, String> {
+        let socket = socket_path()?;
+        let Some((client, version)) = Self::connect_to(&socket, spawn, on_message.clone())? else {
+            return Ok(None);
+        };
+        if version >= MIN_PROTOCOL_VERSION {
+            return Ok(Some(client));
+        }
+
+        let shutdown = client.request(HostRequest::Shutdown);
+        client.disconnect();
+        shutdown.map_err(|e| format!("PTY host from an older version is busy: {}", e))?;
+        info!("Replacing PTY host that speaks protocol {}", version);
+        wait_for_exit(&socket);
+        match Self::connect_to(&socket, spawn, on_message)? {
+            Some((client, version)) if version >= MIN_PROTOCOL_VERSION => Ok(Some(client)),
+            Some(_) => Err("PTY host from an older version is still running".to_string()),
+            None => Ok(None),
+        }
+    }
+
+    fn connect_to(
+        socket: &Path,
+        spawn: bool,
+        on_message: MessageHandler,
+    ) -> Result<Option<(Self, u32)>, String> {
+        let stream = match UnixStream::connect(socket) {
+            Ok(stream) => stream,
+            Err(_) if !spawn => return Ok(None),
+            Err(_) => {
+                spawn_host(socket)?;
+                let mut attempts = 0;
+                loop {
+                    thread::sleep(Duration::from_millis(100));
+                    match UnixStream::connect(socket) {
+                        Ok(stream) => break stream,
+                        Err(e) if attempts >= 50 => {
+                            return Err(format!("PTY host did not start: {}", e));
+                        }
+                        Err(_) => attempts += 1,
+                    }
+                }
+            }
+        };
+
+        let reader = stream
+            .try_clone()
+            .map_err(|e| format!("Failed to clone PTY host stream: {}", e))?;
+        let pending: PendingReplies = Arc::new(Mutex::new(HashMap::new()));
+        let connected = Arc::new(AtomicBool::new(true));
+        let notifier = Notifier {
+            writer: Arc::new(Mutex::new(stream)),
+        };
+
+        let pending_clone = pending.clone();
+        let connected_clone = connected.clone();
+        let notifier_clone = notifier.clone();
+        thread::spawn(move || {
+            for line in BufReader::new(reader).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                let message: HostMessage = match serde_json::from_str(&line) {
+                    Ok(message) => message,
+                    Err(e) => {
+                        warn!("Invalid PTY host message: {}", e);
+                        continue;
+                    }
+                };
+                on_message(&message, &notifier_clone);
+                if let HostMessage::Reply { id, result } = message {
+                    if let Some(sender) = pending_clone.lock().remove(&id) {
+                        let _ = sender.send(result);
+                    }
+                }
+            }
+            connected_clone.store(false, Ordering::Relaxed);
+            // Fail requests still waiting for a reply
+            pending_clone.lock().clear();
+            info!("Disconnected from PTY host");
+        });
+
+        let client = Self {
+            notifier,
+            pending,
+            next_id: AtomicU64::new(1),
+            connected,
+        };
+        let version = match client.request(HostRequest::Hello {
+            version: PROTOCOL_VERSION,
+        }) {
+            Ok(HostReply::Hello { version }) => version,
+            Ok(_) => PROTOCOL_VERSION,
+            Err(e) => {
+                client.disconnect();
+                return Err(e);
+            }
+        };
+        Ok(Some((client, version)))
+    }
+
+    pub(super) fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::Relaxed)
+    }
+
+    /// Send a request and wait for its reply
+    pub(super) fn request(&self, request: HostRequest) -> Result<HostReply, String> {
+        // IDs start at 1, after `NOTIFICATION_ID`
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (sender, receiver) = mpsc::channel();
+        self.pending.lock().insert(id, sender);
+
+        if let Err(e) = write_frame(
+            &mut *self.notifier.writer.lock(),
+            &RequestFrame { id, request },
+        ) {
+            self.pending.lock().remove(&id);
+            return Err(format!("Failed to reach PTY host: {}", e));
+        }
+
+        match receiver.recv_timeout(REQUEST_TIMEOUT) {
+            Ok(result) => result,
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                self.pending.lock().remove(&id);
+                Err("PTY host did not answer".to_string())
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                Err("Lost connection to PTY host".to_string())
+            }
+        }
+    }
+
+    /// Send a request without waiting for its reply
+    pub(super) fn notify(&self, request: HostRequest) -> Result<(), String> {
+        self.notifier.notify(request)
+    }
+
+    /// Close the connection, leaving the host and its terminals running
+    pub(super) fn disconnect(&self) {
+        let _ = self
+            .notifier
+            .writer
+            .lock()
+            .shutdown(std::net::Shutdown::Both);
+    }
+}
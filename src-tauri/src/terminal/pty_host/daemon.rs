@@ -0,0 +1,617 @@
+//! PTY host daemon
+//!
+//! Owns persistent terminals. Each terminal keeps a bounded scrollback and
+//! its `TerminalInfo`, which follows the shell's OSC 633 reports so cwd and
+//! last command stay current while no app is attached. Exited terminals are
+//! dropped once an app has been told about the exit and none is connected,
+//! and the host exits once it has neither terminals nor clients for a while.
+//! It logs to `pty-host.log` next to its socket.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::net::Shutdown;
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, mpsc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+use portable_pty::{MasterPty, PtySize};
+use tracing::{error, info, warn};
+
+use super::super::constants::{PTY_HOST_SCROLLBACK_BYTES, PTY_READ_BUFFER_SIZE};
+use super::super::process::kill_process_tree;
+use super::super::protocol::{ShellIntegrationState, parse_osc_633, update_state};
+use super::super::pty::{spawn_shell, take_utf8};
+use super::super::shell_integration::inject_shell_integration;
+use super::super::state::TerminalState;
+use super::super::types::{CreateTerminalOptions, TerminalInfo};
+use super::DAEMON_ARG;
+use super::protocol::{HostMessage, HostReply, HostRequest, RequestFrame, negotiate, write_frame};
+
+/// How long the host stays up with nothing to do
+const IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Frames queued for a client before it counts as stuck and is dropped
+const CLIENT_QUEUE_FRAMES: usize = 4096;
+
+/// How often a paused terminal checks whether it may read again
+const PAUSE_POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// Run as the PTY host if the process was started with `--pty-host <socket>`
+///
+/// Returns `false` for a normal start.
+pub fn run_from_args() -> bool {
+    let mut args = std::env::args().skip(1);
+    if args.next().as_deref() != Some(DAEMON_ARG) {
+        return false;
+    }
+    let Some(socket) = args.next() else {
+        return false;
+    };
+    init_logging(Path::new(&socket));
+    if let Err(e) = serve(Path::new(&socket)) {
+        error!("PTY host failed: {}", e);
+    }
+    true
+}
+
+/// Log to a file next to the socket, as the host has no terminal
+fn init_logging(socket: &Path) {
+    if let Some(parent) = socket.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    let Ok(file) = std::fs::File::create(socket.with_extension("log")) else {
+        return;
+    };
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::from_default_env()
+                .add_directive(tracing::Level::INFO.into()),
+        )
+        .with_ansi(false)
+        .with_writer(std::sync::Mutex::new(file))
+        .init();
+}
+
+/// Output kept for replay when an app reattaches, bounded in bytes
+pub(super) struct Scrollback {
+    chunks: VecDeque<String>,
+    bytes: usize,
+    limit: usize,
+}
+
+impl Scrollback {
+    pub(super) fn new(limit: usize) -> Self {
+        Self {
+            chunks: VecDeque::new(),
+            bytes: 0,
+            limit,
+        }
+    }
+
+    pub(super) fn push(&mut self, text: &str) {
+        self.chunks.push_back(text.to_string());
+        self.bytes += text.len();
+        while self.bytes > self.limit {
+            let Some(front) = self.chunks.front_mut() else {
+                break;
+            };
+            let excess = self.bytes - self.limit;
+            if front.len() <= excess {
+                self.bytes -= front.len();
+                self.chunks.pop_front();
+            } else {
+                let mut cut = excess;
+                while !front.is_char_boundary(cut) {
+                    cut += 1;
+                }
+                front.drain(..cut);
+                self.bytes -= cut;
+            }
+        }
+    }
+
+    pub(super) fn contents(&self) -> String {
+        self.chunks.concat()
+    }
+}
+
+struct HostedPty {
+    info: TerminalInfo,
+    shell_state: ShellIntegrationState,
+    master: Box<dyn MasterPty + Send>,
+    writer: Arc<Mutex<BufWriter<Box<dyn Write + Send>>>>,
+    scrollback: Scrollback,
+    child_pid: Option<u32>,
+    /// Clients that asked to pause this terminal's output (flow control)
+    paused_by: HashSet<u64>,
+    /// Set while `paused_by` is not empty; read by the output pump
+    paused: Arc<AtomicBool>,
+    /// Whether a client has been told that the terminal exited
+    exit_delivered: bool,
+}
+
+impl HostedPty {
+    fn set_paused(&mut self, client_id: u64, paused: bool) {
+        if paused {
+            self.paused_by.insert(client_id);
+        } else {
+            self.paused_by.remove(&client_id);
+        }
+        self.paused
+            .store(!self.paused_by.is_empty(), Ordering::Relaxed);
+    }
+}
+
+impl Drop for HostedPty {
+    fn drop(&mut self) {
+        // Let the pump run into EOF instead of waiting forever
+        self.paused.store(false, Ordering::Relaxed);
+    }
+}
+
+/// Queue of frames written to a client by its writer thread
+#[derive(Clone)]
+struct ClientWriter {
+    queue: mpsc::SyncSender<HostMessage>,
+    stream: Arc<UnixStream>,
+}
+
+struct Host {
+    terminals: Mutex<HashMap<String, HostedPty>>,
+    clients: Mutex<HashMap<u64, ClientWriter>>,
+    next_client: AtomicU64,
+    socket: PathBuf,
+}
+
+/// Queue a message for a client; fails when it is gone or not keeping up
+///
+/// A client that fails is disconnected rather than left with gaps in its
+/// output.
+fn send(writer: &ClientWriter, message: HostMessage) -> bool {
+    if writer.queue.try_send(message).is_ok() {
+        return true;
+    }
+    let _ = writer.stream.shutdown(Shutdown::Both);
+    false
+}
+
+impl Host {
+    /// Send a message to every client, dropping the ones that went away
+    ///
+    /// Returns whether any client got it. Callers hold the terminals lock so
+    /// output is ordered with replies that snapshot terminal state; sending
+    /// only queues, so a slow client cannot hold the lock up.
+    fn broadcast(&self, message: &HostMessage) -> bool {
+        let mut clients = self.clients.lock();
+        clients.retain(|_, writer| send(writer, message.clone()));
+        !clients.is_empty()
+    }
+
+    /// Drop exited terminals whose exit reached an app, once none is connected
+    fn reap_exited(&self) {
+        if !self.clients.lock().is_empty() {
+            return;
+        }
+        self.terminals
+            .lock()
+            .retain(|_, t| !(t.info.status == "exited" && t.exit_delivered));
+    }
+
+    fn is_idle(&self) -> bool {
+        self.terminals.lock().is_empty() && self.clients.lock().is_empty()
+    }
+
+    fn pump_output(
+        self: &Arc<Self>,
+        terminal_id: String,
+        mut reader: Box<dyn Read + Send>,
+        paused: Arc<AtomicBool>,
+    ) {
+        let host = self.clone();
+        thread::spawn(move || {
+            let mut buf = [0u8; PTY_READ_BUFFER_SIZE];
+            let mut pending = Vec::new();
+            loop {
+                // Leave output in the PTY while an app is catching up, so the
+                // program blocks instead of flooding it
+                if paused.load(Ordering::Relaxed) {
+                    thread::sleep(PAUSE_POLL_INTERVAL);
+                    continue;
+                }
+                match reader.read(&mut buf) {
+                    Ok(0) => break,
+                    Ok(n) => {
+                        pending.extend_from_slice(&buf[..n]);
+                        let text = take_utf8(&mut pending);
+                        if text.is_empty() {
+                            continue;
+                        }
+                        let mut terminals = host.terminals.lock();
+                        let Some(terminal) = terminals.get_mut(&terminal_id) else {
+                            break;
+                        };
+                        terminal.scrollback.push(&text);
+                        let events = parse_osc_633(&text);
+                        if !events.is_empty() {
+                            update_state(&mut terminal.shell_state, &events);
+                            let state = &terminal.shell_state;
+                            if let Some(cwd) = &state.cwd {
+                                terminal.info.cwd = cwd.clone();
+                            }
+                            terminal.info.last_command = state.last_command.clone();
+                            terminal.info.last_exit_code = state.last_exit_code;
+                            terminal.info.command_running = state.in_command;
+                        }
+                        host.broadcast(&HostMessage::Output {
+                            terminal_id: terminal_id.clone(),
+                            data: text,
+                        });
+                    }
+                    Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                    Err(_) => break,
+                }
+            }
+        });
+    }
+
+    fn create(
+        self: &Arc<Self>,
+        writer: &ClientWriter,
+        id: u64,
+        terminal_id: String,
+        options: CreateTerminalOptions,
+    ) {
+        if self.terminals.lock().contains_key(&terminal_id) {
+            let result = Err(format!("Terminal {} already exists", terminal_id));
+            send(writer, HostMessage::Reply { id, result });
+            return;
+        }
+        let shell = match spawn_shell(
+            terminal_id.clone(),
+            options,
+            TerminalState::get_default_shell,
+        ) {
+            Ok(shell) => shell,
+            Err(e) => {
+                send(writer, HostMessage::Reply { id, result: Err(e) });
+                return;
+            }
+        };
+
+        let mut child = shell.child;
+        let terminal_id_clone = terminal_id.clone();
+        let host = self.clone();
+        thread::spawn(move || {
+            let exit_code = child.wait().map(|s| s.exit_code() as i32).ok();
+            info!(
+                "Terminal {} exited with code: {:?}",
+                terminal_id_clone, exit_code
+            );
+            let mut terminals = host.terminals.lock();
+            if let Some(terminal) = terminals.get_mut(&terminal_id_clone) {
+                terminal.info.status = "exited".to_string();
+                terminal.exit_delivered = host.broadcast(&HostMessage::Exited {
+                    terminal_id: terminal_id_clone,
+                    exit_code,
+                });
+            }
+        });
+
+        let info = shell.info.clone();
+        let paused = Arc::new(AtomicBool::new(false));
+        {
+            // Reply before the terminal's first output reaches the client
+            let mut terminals = self.terminals.lock();
+            terminals.insert(
+                terminal_id.clone(),
+                HostedPty {
+                    info: shell.info,
+                    shell_state: ShellIntegrationState::default(),
+                    master: shell.master,
+                    writer: shell.writer.clone(),
+                    scrollback: Scrollback::new(PTY_HOST_SCROLLBACK_BYTES),
+                    child_pid: shell.child_pid,
+                    paused_by: HashSet::new(),
+                    paused: paused.clone(),
+                    exit_delivered: false,
+                },
+            );
+            let result = Ok(HostReply::Created { info: info.clone() });
+            send(writer, HostMessage::Reply { id, result });
+            self.pump_output(terminal_id, shell.reader, paused);
+        }
+
+        if shell.inject_integration {
+            // Small delay to allow the shell to initialize
+            thread::sleep(Duration::from_millis(100));
+            if let Err(e) = inject_shell_integration(&info.shell, &shell.writer) {
+                warn!("Failed to inject shell integration: {}", e);
+            }
+        }
+    }
+
+    fn attach(&self, writer: &ClientWriter, id: u64, terminal_id: &str) {
+        // The snapshot and the reply are taken under one lock, so the client
+        // sees every byte exactly once: in the scrollback or as live output
+        let mut terminals = self.terminals.lock();
+        let result = terminals
+            .get_mut(terminal_id)
+            .map(|t| {
+                // The reply carries the exited status
+                if t.info.status == "exited" {
+                    t.exit_delivered = true;
+                }
+                HostReply::Attached {
+                    info: t.info.clone(),
+                    scrollback: t.scrollback.contents(),
+                }
+            })
+            .ok_or_else(|| format!("Terminal {} not found", terminal_id));
+        send(writer, HostMessage::Reply { id, result });
+    }
+
+    /// Number of terminals whose shell is still running
+    fn live_terminals(terminals: &HashMap<String, HostedPty>) -> usize {
+        terminals
+            .values()
+            .filter(|t| t.info.status != "exited")
+            .count()
+    }
+
+    fn pause(&self, client_id: u64, terminal_id: &str, paused: bool) -> Result<HostReply, String> {
+        let mut terminals = self.terminals.lock();
+        let terminal = terminals
+            .get_mut(terminal_id)
+            .ok_or_else(|| format!("Terminal {} not found", terminal_id))?;
+        terminal.set_paused(client_id, paused);
+        Ok(HostReply::Done)
+    }
+
+    fn handle(&self, client_id: u64, request: HostRequest) -> Result<HostReply, String> {
+        match request {
+            HostRequest::Hello { version } => Ok(HostReply::Hello {
+                version: negotiate(version)?,
+            }),
+            HostRequest::Write { terminal_id, data } => {
+                let writer = self
+                    .terminals
+                    .lock()
+                    .get(&terminal_id)
+                    .map(|t| t.writer.clone())
+                    .ok_or_else(|| format!("Terminal {} not found", terminal_id))?;
+                let mut writer = writer.lock();
+                writer
+                    .write_all(data.as_bytes())
+                    .and_then(|_| writer.flush())
+                    .map_err(|e| format!("Failed to write to terminal: {}", e))?;
+                Ok(HostReply::Done)
+            }
+            HostRequest::Resize {
+                terminal_id,
+                cols,
+                rows,
+            } => {
+                let mut terminals = self.terminals.lock();
+                let terminal = terminals
+                    .get_mut(&terminal_id)
+                    .ok_or_else(|| format!("Terminal {} not found", terminal_id))?;
+                terminal.info.cols = cols;
+                terminal.info.rows = rows;
+                terminal
+                    .master
+                    .resize(PtySize {
+                        rows,
+                        cols,
+                        pixel_width: 0,
+                        pixel_height: 0,
+                    })
+                    .map_err(|e| format!("Failed to resize PTY: {}", e))?;
+                Ok(HostReply::Done)
+            }
+            HostRequest::Close { terminal_id } => {
+                let terminal = self
+                    .terminals
+                    .lock()
+                    .remove(&terminal_id)
+                    .ok_or_else(|| format!("Terminal {} not found", terminal_id))?;
+                if let Some(pid) = terminal.child_pid {
+                    if let Err(e) = kill_process_tree(pid) {
+                        warn!("Failed to kill terminal process {}: {}", pid, e);
+                    }
+                }
+                info!("Terminal {} closed", terminal_id);
+                Ok(HostReply::Done)
+            }
+            HostRequest::Update { info } => {
+                let mut terminals = self.terminals.lock();
+                let terminal = terminals
+                    .get_mut(&info.id)
+                    .ok_or_else(|| format!("Terminal {} not found", info.id))?;
+                let status = std::mem::take(&mut terminal.info.status);
+                terminal.info = TerminalInfo { status, ..info };
+                Ok(HostReply::Done)
+            }
+            HostRequest::List => Ok(HostReply::Terminals {
+                terminals: self
+                    .terminals
+                    .lock()
+                    .values()
+                    .map(|t| t.info.clone())
+                    .collect(),
+            }),
+            HostRequest::Pause { terminal_id } => self.pause(client_id, &terminal_id, true),
+            HostRequest::Resume { terminal_id } => self.pause(client_id, &terminal_id, false),
+            // Answered in `serve_client`, which needs the client
+            HostRequest::Create { .. } | HostRequest::Attach { .. } | HostRequest::Shutdown => {
+                Err("Unexpected request".to_string())
+            }
+        }
+    }
+
+    fn serve_client(self: &Arc<Self>, stream: UnixStream) {
+        let (mut write_stream, shutdown_stream) = match stream
+            .try_clone()
+            .and_then(|s| Ok((s, stream.try_clone()?)))
+        {
+            Ok(streams) => streams,
+            Err(e) => {
+                warn!("Failed to clone client stream: {}", e);
+                return;
+            }
+        };
+        // Frames are written on their own thread, so a client that stops
+        // reading never blocks the terminals or the other clients
+        let (queue_tx, queue) = mpsc::sync_channel::<HostMessage>(CLIENT_QUEUE_FRAMES);
+        let writer = ClientWriter {
+            queue: queue_tx,
+            stream: Arc::new(shutdown_stream),
+        };
+        let write_thread = thread::spawn(move || {
+            for message in queue {
+                if write_frame(&mut write_stream, &message).is_err() {
+                    break;
+                }
+            }
+            let _ = write_stream.shutdown(Shutdown::Both);
+        });
+        let client_id = self.next_client.fetch_add(1, Ordering::Relaxed);
+        self.clients.lock().insert(client_id, writer.clone());
+
+        for line in BufReader::new(stream).lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            let frame: RequestFrame = match serde_json::from_str(&line) {
+                Ok(frame) => frame,
+                Err(e) => {
+                    warn!("Invalid PTY host request: {}", e);
+                    continue;
+                }
+            };
+            let id = frame.id;
+            match frame.request {
+                HostRequest::Shutdown => {
+                    // Held until exit, so no terminal is created meanwhile
+                    let terminals = self.terminals.lock();
+                    let live = Self::live_terminals(&terminals);
+                    if live > 0 {
+                        let result = Err(format!("PTY host still runs {} terminals", live));
+                        send(&writer, HostMessage::Reply { id, result });
+                        continue;
+                    }
+                    info!("Shutting down for a newer PTY host");
+                    send(
+                        &writer,
+                        HostMessage::Reply {
+                            id,
+                            result: Ok(HostReply::Done),
+                        },
+                    );
+                    // Flush the reply before exiting
+                    self.clients.lock().remove(&client_id);
+                    drop(writer);
+                    let _ = write_thread.join();
+                    let _ = std::fs::remove_file(&self.socket);
+                    std::process::exit(0);
+                }
+                HostRequest::Create {
+                    terminal_id,
+                    options,
+                } => self.create(&writer, id, terminal_id, options),
+                HostRequest::Attach { terminal_id } => self.attach(&writer, id, &terminal_id),
+                request => {
+                    let result = self.handle(client_id, request);
+                    send(&writer, HostMessage::Reply { id, result });
+                }
+            }
+        }
+
+        self.clients.lock().remove(&client_id);
+        for terminal in self.terminals.lock().values_mut() {
+            terminal.set_paused(client_id, false);
+        }
+        info!("PTY host client {} disconnected", client_id);
+    }
+}
+
+fn serve(socket: &Path) -> Result<(), String> {
+    if UnixStream::connect(socket).is_ok() {
+        return Err("Another PTY host is already running".to_string());
+    }
+    if let Some(parent) = socket.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create socket directory: {}", e))?;
+    }
+    // A socket left behind by a host that crashed
+    let _ = std::fs::remove_file(socket);
+    let listener =
+        UnixListener::bind(socket).map_err(|e| format!("Failed to bind PTY host socket: {}", e))?;
+    std::fs::set_permissions(socket, std::fs::Permissions::from_mode(0o600))
+        .map_err(|e| format!("Failed to restrict PTY host socket: {}", e))?;
+
+    let host = Arc::new(Host {
+        terminals: Mutex::new(HashMap::new()),
+        clients: Mutex::new(HashMap::new()),
+        next_client: AtomicU64::new(0),
+        socket: socket.to_path_buf(),
+    });
+
+    let watchdog = host.clone();
+    let socket_path = socket.to_path_buf();
+    thread::spawn(move || {
+        let mut idle_since = Instant::now();
+        loop {
+            thread::sleep(Duration::from_secs(1));
+            watchdog.reap_exited();
+            if !watchdog.is_idle() {
+                idle_since = Instant::now();
+            } else if idle_since.elapsed() >= IDLE_TIMEOUT {
+                let _ = std::fs::remove_file(&socket_path);
+                std::process::exit(0);
+            }
+        }
+    });
+
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                let host = host.clone();
+                thread::spawn(move || host.serve_client(stream));
+            }
+            Err(e) => warn!("Failed to accept PTY host client: {}", e),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scrollback_keeps_the_newest_bytes() {
+        let mut scrollback = Scrollback::new(8);
+        scrollback.push("abc");
+        scrollback.push("defg");
+        assert_eq!(scrollback.contents(), "abcdefg");
+
+        scrollback.push("hij");
+        assert_eq!(scrollback.contents(), "cdefghij");
+
+        scrollback.push("0123456789");
+        assert_eq!(scrollback.contents(), "23456789");
+    }
+
+    #[test]
+    fn scrollback_trims_on_char_boundaries() {
+        let mut scrollback = Scrollback::new(4);
+        scrollback.push("€€");
+        assert_eq!(scrollback.contents(), "€");
+    }
+}
@@ -0,0 +1,473 @@
+//! App-side registry of terminals owned by the PTY host
+//!
+//! Methods taking a terminal ID return `None` when the terminal is not
+//! hosted, so `TerminalState` can fall back to its own PTYs.
+//!
+//! Flow control spans the socket: when the frontend falls behind, the host is
+//! asked to pause reading the terminal's PTY until output is acknowledged.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use tauri::{AppHandle, Emitter};
+use tracing::{info, warn};
+
+use super::super::blocks::{CommandHistory, CommandTracker};
+use super::super::constants::EMULATOR_SCROLLBACK_LINES;
+use super::super::emulator::TerminalEmulator;
+use super::super::flow_control::{FlowController, OutputBatcher};
+use super::super::recording::{Recorder, RecordingInfo};
+use super::super::types::{
+    CreateTerminalOptions, RestoredTerminal, TerminalInfo, TerminalStatus, UpdateTerminalOptions,
+};
+use super::client::{Notifier, PtyHostClient};
+use super::protocol::{HostMessage, HostReply, HostRequest};
+
+struct HostedTerminal {
+    info: TerminalInfo,
+    flow_controller: Arc<FlowController>,
+    /// Whether the host was asked to pause this terminal's output
+    paused: bool,
+    batcher: OutputBatcher,
+    emulator: Arc<Mutex<TerminalEmulator>>,
+    commands: CommandTracker,
+    recording: Option<Recorder>,
+}
+
+type Registry = Arc<Mutex<HashMap<String, HostedTerminal>>>;
+
+#[derive(Clone)]
+pub(in crate::terminal) struct HostedTerminals {
+    client: Arc<Mutex<Option<Arc<PtyHostClient>>>>,
+    terminals: Registry,
+    history: CommandHistory,
+}
+
+fn register(app: &AppHandle, terminals: &Registry, info: &TerminalInfo, scrollback: &str) {
+    let flow_controller = Arc::new(FlowController::new());
+    let batcher = OutputBatcher::new(info.id.clone(), app.clone())
+        .with_flow_controller(flow_controller.clone());
+    let mut emulator = TerminalEmulator::new(
+        info.cols as usize,
+        info.rows as usize,
+        EMULATOR_SCROLLBACK_LINES,
+    );
+    emulator.process(scrollback.as_bytes());
+    let mut commands = CommandTracker::new(
+        info.id.clone(),
+        info.workspace.clone(),
+        info.cols as usize,
+        info.rows as usize,
+    );
+    // Blocks that finished before a restart were recorded at the time; this
+    // only picks up a command that is still running
+    let _ = commands.push(scrollback);
+    terminals
+        .lock()
+        .entry(info.id.clone())
+        .or_insert(HostedTerminal {
+            info: info.clone(),
+            flow_controller,
+            paused: false,
+            batcher,
+            emulator: Arc::new(Mutex::new(emulator)),
+            commands,
+            recording: None,
+        });
+}
+
+fn on_message(
+    app: &AppHandle,
+    terminals: &Registry,
+    history: &CommandHistory,
+    message: &HostMessage,
+    notifier: &Notifier,
+) {
+    match message {
+        HostMessage::Reply {
+            result: Ok(HostReply::Created { info }),
+            ..
+        } => register(app, terminals, info, ""),
+        HostMessage::Reply {
+            result: Ok(HostReply::Attached { info, scrollback }),
+            ..
+        } => register(app, terminals, info, scrollback),
+        HostMessage::Output { terminal_id, data } => {
+            // Output of terminals not attached yet is part of their scrollback
+            if let Some(terminal) = terminals.lock().get_mut(terminal_id) {
+                terminal.emulator.lock().process(data.as_bytes());
+                history.track(app, &mut terminal.commands, data);
+                if let Some(recorder) = terminal.recording.as_mut() {
+                    recorder.output(data);
+                }
+                terminal.batcher.push(data);
+                if !terminal.paused && terminal.flow_controller.should_pause() {
+                    let pause = HostRequest::Pause {
+                        terminal_id: terminal_id.clone(),
+                    };
+                    match notifier.notify(pause) {
+                        Ok(()) => terminal.paused = true,
+                        Err(e) => warn!("Failed to pause terminal {}: {}", terminal_id, e),
+                    }
+                }
+            }
+        }
+        HostMessage::Exited {
+            terminal_id,
+            exit_code,
+        } => {
+            let known = match terminals.lock().get_mut(terminal_id) {
+                Some(terminal) => {
+                    terminal.info.status = "exited".to_string();
+                    true
+                }
+                None => false,
+            };
+            if known {
+                let status_event = TerminalStatus {
+                    terminal_id: terminal_id.clone(),
+                    status: "exited".to_string(),
+                    exit_code: *exit_code,
+                };
+                let _ = app.emit("terminal:status", &status_event);
+            }
+        }
+        HostMessage::Reply { .. } => {}
+    }
+}
+
+impl HostedTerminals {
+    pub(in crate::terminal) fn new(history: CommandHistory) -> Self {
+        Self {
+            client: Arc::default(),
+            terminals: Registry::default(),
+            history,
+        }
+    }
+
+    /// Connection to the host, made (and the host started) on first use
+    ///
+    /// After a lost connection, the terminals known from it are reattached;
+    /// the ones the host no longer has are reported as exited.
+    fn client(&self, app: &AppHandle, spawn: bool) -> Result<Option<Arc<PtyHostClient>>, String> {
+        let mut client = self.client.lock();
+        if let Some(existing) = client.as_ref().filter(|c| c.is_connected()) {
+            return Ok(Some(existing.clone()));
+        }
+
+        let app_clone = app.clone();
+        let terminals = self.terminals.clone();
+        let history = self.history.clone();
+        let connected = PtyHostClient::connect(
+            spawn,
+            Arc::new(move |message: &HostMessage, notifier: &Notifier| {
+                on_message(&app_clone, &terminals, &history, message, notifier)
+            }),
+        )?
+        .map(Arc::new);
+        client.clone_from(&connected);
+
+        let known: Vec<String> = self.terminals.lock().keys().cloned().collect();
+        for terminal_id in known {
+            let reattached = match &connected {
+                Some(c) => c.request(HostRequest::Attach {
+                    terminal_id: terminal_id.clone(),
+                }),
+                None => Err("PTY host is not running".to_string()),
+            };
+            match reattached {
+                // Pauses ended with the old connection
+                Ok(_) => {
+                    if let Some(terminal) = self.terminals.lock().get_mut(&terminal_id) {
+                        terminal.paused = false;
+                    }
+                }
+                Err(e) => {
+                    warn!(
+                        "Terminal {} did not survive reconnecting: {}",
+                        terminal_id, e
+                    );
+                    self.terminals.lock().remove(&terminal_id);
+                    let status_event = TerminalStatus {
+                        terminal_id,
+                        status: "exited".to_string(),
+                        exit_code: None,
+                    };
+                    let _ = app.emit("terminal:status", &status_event);
+                }
+            }
+        }
+        Ok(connected)
+    }
+
+    fn hosted_client(&self, terminal_id: &str) -> Option<Result<Arc<PtyHostClient>, String>> {
+        if !self.terminals.lock().contains_key(terminal_id) {
+            return None;
+        }
+        Some(
+            self.client
+                .lock()
+                .clone()
+                .filter(|c| c.is_connected())
+                .ok_or_else(|| "Lost connection to PTY host".to_string()),
+        )
+    }
+
+    /// Create a terminal in the host, starting the host if needed
+    pub(in crate::terminal) fn create(
+        &self,
+        app: &AppHandle,
+        terminal_id: String,
+        options: CreateTerminalOptions,
+    ) -> Result<TerminalInfo, String> {
+        let client = self.client(app, true)?.ok_or("PTY host is not running")?;
+        match client.request(HostRequest::Create {
+            terminal_id,
+            options,
+        })? {
+            HostReply::Created { info } => Ok(info),
+            other => Err(format!("Unexpected PTY host reply: {:?}", other)),
+        }
+    }
+
+    pub(in crate::terminal) fn write(
+        &self,
+        terminal_id: &str,
+        data: &str,
+    ) -> Option<Result<(), String>> {
+        let client = self.hosted_client(terminal_id)?;
+        Some(client.and_then(|c| {
+            c.request(HostRequest::Write {
+                terminal_id: terminal_id.to_string(),
+                data: data.to_string(),
+            })
+            .map(|_| ())
+        }))
+    }
+
+    pub(in crate::terminal) fn resize(
+        &self,
+        terminal_id: &str,
+        cols: u16,
+        rows: u16,
+    ) -> Option<Result<(), String>> {
+        let client = self.hosted_client(terminal_id)?;
+        Some(client.and_then(|c| {
+            c.request(HostRequest::Resize {
+                terminal_id: terminal_id.to_string(),
+                cols,
+                rows,
+            })?;
+            if let Some(terminal) = self.terminals.lock().get_mut(terminal_id) {
+                terminal.info.cols = cols;
+                terminal.info.rows = rows;
+                terminal
+                    .emulator
+                    .lock()
+                    .resize(cols as usize, rows as usize);
+                terminal.commands.resize(cols as usize, rows as usize);
+                if let Some(recorder) = terminal.recording.as_mut() {
+                    recorder.resize(cols, rows);
+                }
+            }
+            info!("Terminal {} resized to {}x{}", terminal_id, cols, rows);
+            Ok(())
+        }))
+    }
+
+    pub(in crate::terminal) fn update(
+        &self,
+        terminal_id: &str,
+        options: UpdateTerminalOptions,
+    ) -> Option<Result<TerminalInfo, String>> {
+        let client = self.hosted_client(terminal_id)?;
+        Some(client.and_then(|c| {
+            let info = {
+                let mut terminals = self.terminals.lock();
+                let terminal = terminals
+                    .get_mut(terminal_id)
+                    .ok_or_else(|| format!("Terminal {} not found", terminal_id))?;
+                options.apply(&mut terminal.info);
+                terminal.info.clone()
+            };
+            c.request(HostRequest::Update { info: info.clone() })?;
+            Ok(info)
+        }))
+    }
+
+    pub(in crate::terminal) fn close(&self, terminal_id: &str) -> Option<Result<(), String>> {
+        let client = self.hosted_client(terminal_id)?;
+        self.terminals.lock().remove(terminal_id);
+        Some(client.and_then(|c| {
+            c.request(HostRequest::Close {
+                terminal_id: terminal_id.to_string(),
+            })
+            .map(|_| ())
+        }))
+    }
+
+    pub(in crate::terminal) fn acknowledge(
+        &self,
+        terminal_id: &str,
+        bytes: usize,
+    ) -> Option<Result<(), String>> {
+        let resume = {
+            let mut terminals = self.terminals.lock();
+            let terminal = terminals.get_mut(terminal_id)?;
+            terminal.flow_controller.acknowledge(bytes);
+            let resume = terminal.paused && !terminal.flow_controller.should_pause();
+            if resume {
+                terminal.paused = false;
+            }
+            resume
+        };
+        if !resume {
+            return Some(Ok(()));
+        }
+        let client = self.hosted_client(terminal_id)?;
+        Some(client.and_then(|c| {
+            c.notify(HostRequest::Resume {
+                terminal_id: terminal_id.to_string(),
+            })
+        }))
+    }
+
+    pub(in crate::terminal) fn get(&self, terminal_id: &str) -> Option<TerminalInfo> {
+        self.terminals
+            .lock()
+            .get(terminal_id)
+            .map(|t| t.info.clone())
+    }
+
+    pub(in crate::terminal) fn emulator(
+        &self,
+        terminal_id: &str,
+    ) -> Option<Arc<Mutex<TerminalEmulator>>> {
+        self.terminals
+            .lock()
+            .get(terminal_id)
+            .map(|t| t.emulator.clone())
+    }
+
+    pub(in crate::terminal) fn set_problem_matchers(
+        &self,
+        terminal_id: &str,
+        names: &[String],
+    ) -> Option<Result<(), String>> {
+        self.terminals
+            .lock()
+            .get_mut(terminal_id)
+            .map(|t| t.commands.set_problem_matchers(names))
+    }
+
+    pub(in crate::terminal) fn problem_matchers(&self, terminal_id: &str) -> Option<Vec<String>> {
+        self.terminals
+            .lock()
+            .get(terminal_id)
+            .map(|t| t.commands.problem_matchers())
+    }
+
+    pub(in crate::terminal) fn start_recording(
+        &self,
+        terminal_id: &str,
+        path: Option<PathBuf>,
+        redact: bool,
+    ) -> Option<Result<RecordingInfo, String>> {
+        let mut terminals = self.terminals.lock();
+        let terminal = terminals.get_mut(terminal_id)?;
+        if terminal.recording.is_some() {
+            return Some(Err(format!(
+                "Terminal {} is already being recorded",
+                terminal_id
+            )));
+        }
+        Some(
+            Recorder::for_terminal(&terminal.info, path, redact).map(|recorder| {
+                let info = recorder.info();
+                terminal.recording = Some(recorder);
+                info
+            }),
+        )
+    }
+
+    pub(in crate::terminal) fn stop_recording(
+        &self,
+        terminal_id: &str,
+    ) -> Option<Result<RecordingInfo, String>> {
+        let mut terminals = self.terminals.lock();
+        let terminal = terminals.get_mut(terminal_id)?;
+        Some(
+            terminal
+                .recording
+                .take()
+                .map(|mut recorder| recorder.finish())
+                .ok_or_else(|| format!("Terminal {} is not being recorded", terminal_id)),
+        )
+    }
+
+    pub(in crate::terminal) fn recording(
+        &self,
+        terminal_id: &str,
+    ) -> Option<Option<RecordingInfo>> {
+        self.terminals
+            .lock()
+            .get(terminal_id)
+            .map(|t| t.recording.as_ref().map(Recorder::info))
+    }
+
+    pub(in crate::terminal) fn list(&self) -> Vec<TerminalInfo> {
+        self.terminals
+            .lock()
+            .values()
+            .map(|t| t.info.clone())
+            .collect()
+    }
+
+    pub(in crate::terminal) fn ids(&self) -> Vec<String> {
+        self.terminals.lock().keys().cloned().collect()
+    }
+
+    /// Reattach to the terminals of a running host
+    ///
+    /// Terminals that are already attached are skipped. Does not start a host.
+    pub(in crate::terminal) fn restore(
+        &self,
+        app: &AppHandle,
+    ) -> Result<Vec<RestoredTerminal>, String> {
+        let Some(client) = self.client(app, false)? else {
+            return Ok(Vec::new());
+        };
+        let terminals = match client.request(HostRequest::List)? {
+            HostReply::Terminals { terminals } => terminals,
+            other => return Err(format!("Unexpected PTY host reply: {:?}", other)),
+        };
+
+        let mut restored = Vec::new();
+        for terminal in terminals {
+            if self.terminals.lock().contains_key(&terminal.id) {
+                continue;
+            }
+            match client.request(HostRequest::Attach {
+                terminal_id: terminal.id.clone(),
+            }) {
+                Ok(HostReply::Attached { info, scrollback }) => {
+                    restored.push(RestoredTerminal { info, scrollback })
+                }
+                Ok(other) => warn!("Unexpected PTY host reply: {:?}", other),
+                Err(e) => warn!("Failed to reattach terminal {}: {}", terminal.id, e),
+            }
+        }
+        info!("Reattached {} persistent terminals", restored.len());
+        Ok(restored)
+    }
+
+    /// Drop the connection to the host; its terminals keep running
+    pub(in crate::terminal) fn detach(&self) {
+        if let Some(client) = self.client.lock().take() {
+            client.disconnect();
+        }
+        self.terminals.lock().clear();
+    }
+}
@@ -0,0 +1,35 @@
+//! Persistent terminal host
+//!
+//! Persistent terminals are owned by a small daemon rather than by the app:
+//! the Cortex executable started with `--pty-host <socket>` keeps the PTYs
+//! and serves them over a Unix socket, so shells, dev servers and REPLs
+//! survive closing or reloading the app. On startup the app reattaches,
+//! replays each terminal's scrollback and restores its `TerminalInfo`.
+//!
+//! # Module Structure
+//!
+//! - `protocol`: Messages exchanged over the socket
+//! - `daemon`: The host process
+//! - `client`: Connection from the app to the host
+//! - `hosted`: App-side registry of hosted terminals
+
+mod client;
+mod daemon;
+mod hosted;
+mod protocol;
+
+use std::path::PathBuf;
+
+pub use daemon::run_from_args;
+pub(super) use hosted::HostedTerminals;
+
+/// Argument that starts the executable as the PTY host
+const DAEMON_ARG: &str = "--pty-host";
+
+/// Socket the host listens on
+fn socket_path() -> Result<PathBuf, String> {
+    let dir = dirs::runtime_dir()
+        .or_else(dirs::data_dir)
+        .ok_or("Could not find a directory for the terminal host socket")?;
+    Ok(dir.join("Cortex").join("pty-host.sock"))
+}
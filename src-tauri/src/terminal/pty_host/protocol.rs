@@ -0,0 +1,175 @@
+//! PTY host wire protocol
+//!
+//! Newline-delimited JSON frames. The app sends numbered requests; the host
+//! answers each with a reply carrying the same number, and pushes terminal
+//! output and exits to every connected client as they happen.
+
+use std::io::{self, Write};
+
+use serde::{Deserialize, Serialize};
+
+use super::super::types::{CreateTerminalOptions, TerminalInfo};
+
+/// Bumped whenever the frames change incompatibly
+pub(super) const PROTOCOL_VERSION: u32 = 3;
+
+/// Oldest protocol still spoken. A host and an app agree on the lower of
+/// their versions, so a newer app keeps using an older host's terminals.
+pub(super) const MIN_PROTOCOL_VERSION: u32 = 3;
+
+/// Request ID of notifications, whose replies nobody waits for
+pub(super) const NOTIFICATION_ID: u64 = 0;
+
+/// Request from the app to the host
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub(super) enum HostRequest {
+    Hello {
+        version: u32,
+    },
+    Create {
+        terminal_id: String,
+        options: CreateTerminalOptions,
+    },
+    Write {
+        terminal_id: String,
+        data: String,
+    },
+    Resize {
+        terminal_id: String,
+        cols: u16,
+        rows: u16,
+    },
+    Close {
+        terminal_id: String,
+    },
+    /// Replace the stored info (name, cwd, last command) of a terminal
+    Update {
+        info: TerminalInfo,
+    },
+    List,
+    /// Start receiving a terminal's output, after its scrollback
+    Attach {
+        terminal_id: String,
+    },
+    /// Stop reading a terminal's output until `Resume` (flow control)
+    Pause {
+        terminal_id: String,
+    },
+    Resume {
+        terminal_id: String,
+    },
+    /// Exit so a newer host can take the socket; refused while any
+    /// terminal is still running
+    Shutdown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(super) struct RequestFrame {
+    pub id: u64,
+    pub request: HostRequest,
+}
+
+/// Successful answer to a request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub(super) enum HostReply {
+    Done,
+    Hello {
+        version: u32,
+    },
+    Created {
+        info: TerminalInfo,
+    },
+    Terminals {
+        terminals: Vec<TerminalInfo>,
+    },
+    Attached {
+        info: TerminalInfo,
+        scrollback: String,
+    },
+}
+
+/// Frame from the host to the app
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub(super) enum HostMessage {
+    Reply {
+        id: u64,
+        result: Result<HostReply, String>,
+    },
+    Output {
+        terminal_id: String,
+        data: String,
+    },
+    Exited {
+        terminal_id: String,
+        exit_code: Option<i32>,
+    },
+}
+
+/// Protocol a host speaks with an app that says `Hello` with `version`
+pub(super) fn negotiate(version: u32) -> Result<u32, String> {
+    if version < MIN_PROTOCOL_VERSION {
+        return Err(format!(
+            "PTY host speaks protocol {} to {}, app speaks {}",
+            MIN_PROTOCOL_VERSION, PROTOCOL_VERSION, version
+        ));
+    }
+    Ok(version.min(PROTOCOL_VERSION))
+}
+
+/// Write one frame
+pub(super) fn write_frame<T: Serialize>(writer: &mut impl Write, frame: &T) -> io::Result<()> {
+    let mut line = serde_json::to_vec(frame)?;
+    line.push(b'\n');
+    writer.write_all(&line)?;
+    writer.flush()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_are_single_json_lines() {
+        let mut out = Vec::new();
+        write_frame(
+            &mut out,
+            &RequestFrame {
+                id: 7,
+                request: HostRequest::Write {
+                    terminal_id: "t".to_string(),
+                    data: "echo hi\n".to_string(),
+                },
+            },
+        )
+        .unwrap();
+        assert_eq!(out.iter().filter(|b| **b == b'\n').count(), 1);
+
+        let frame: RequestFrame = serde_json::from_slice(&out).unwrap();
+        assert_eq!(frame.id, 7);
+        assert!(matches!(frame.request, HostRequest::Write { data, .. } if data == "echo hi\n"));
+    }
+
+    #[test]
+    fn newer_apps_get_the_hosts_protocol() {
+        assert_eq!(negotiate(PROTOCOL_VERSION + 1).unwrap(), PROTOCOL_VERSION);
+        assert_eq!(negotiate(PROTOCOL_VERSION).unwrap(), PROTOCOL_VERSION);
+        assert!(negotiate(MIN_PROTOCOL_VERSION - 1).is_err());
+    }
+
+    #[test]
+    fn replies_round_trip_errors() {
+        let message = HostMessage::Reply {
+            id: 1,
+            result: Err("Terminal t not found".to_string()),
+        };
+        let json = serde_json::to_string(&message).unwrap();
+        let back: HostMessage = serde_json::from_str(&json).unwrap();
+        assert!(
+            matches!(back, HostMessage::Reply { result: Err(e), .. } if e.contains("not found"))
+        );
+    }
+}
@@ -0,0 +1,535 @@
+//! Terminal session recording and replay
+//!
+//! Recordings are asciicast v2 files: a JSON header line followed by one
+//! `[time, code, data]` line per output (`"o"`) or resize (`"r"`) event, so
+//! they play in asciinema as well. Secrets can be redacted from the output
+//! before it is written; the tail of each chunk that could be the start of a
+//! key, or an authorization scheme still waiting for its token, is held back
+//! until the next chunk so secrets split across reads are still caught.
+//!
+//! Replays stream a recording to the frontend under their own id, which
+//! renders them in a read-only terminal.
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LazyLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+use tracing::warn;
+use uuid::Uuid;
+
+use super::types::{TerminalInfo, TerminalOutput};
+use crate::settings::secure_store::SecretRedactor;
+
+/// Longest output tail held back while waiting for the rest of a secret
+const MAX_PENDING_BYTES: usize = 256;
+
+/// Authorization scheme at the end of a chunk, whose token may follow in the
+/// next one
+static TRAILING_SCHEME_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    // SAFETY: This regex pattern is a compile-time constant and is always valid.
+    #[allow(clippy::expect_used)]
+    Regex::new(r"(?i)\bbearer\s*$").expect("Trailing scheme regex is valid")
+});
+
+/// Longest sleep between checks of a replay's controls
+const REPLAY_TICK: Duration = Duration::from_millis(50);
+
+/// Summary of a recording
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingInfo {
+    pub terminal_id: String,
+    pub path: String,
+    /// Milliseconds since the Unix epoch
+    pub started_at: i64,
+    pub redacted: bool,
+    /// Seconds recorded so far
+    pub duration: f64,
+    pub event_count: u64,
+}
+
+/// asciicast v2 header
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CastHeader {
+    version: u8,
+    width: u16,
+    height: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    timestamp: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    env: HashMap<String, String>,
+}
+
+/// asciicast v2 event: seconds since the start, event code and data
+type CastEvent = (f64, String, String);
+
+/// Characters that can be part of an API key or token
+fn is_secret_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "-_./+=~".contains(c)
+}
+
+/// Default file for a new recording in the app data directory
+fn default_recording_path(terminal_name: &str) -> Result<PathBuf, String> {
+    let dir = dirs::data_dir()
+        .ok_or("Could not determine data directory")?
+        .join("Cortex")
+        .join("terminal-recordings");
+    let name: String = terminal_name
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '-' })
+        .collect();
+    Ok(dir.join(format!(
+        "{}-{}.cast",
+        name,
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    )))
+}
+
+/// Writes one terminal's output to an asciicast file
+pub(super) struct Recorder {
+    writer: BufWriter<File>,
+    info: RecordingInfo,
+    started: Instant,
+    redactor: Option<SecretRedactor>,
+    /// Output tail that could be the start of a secret
+    pending: String,
+}
+
+impl Recorder {
+    pub(super) fn start(
+        terminal_id: &str,
+        title: &str,
+        shell: &str,
+        cols: u16,
+        rows: u16,
+        path: &Path,
+        redact: bool,
+    ) -> Result<Self, String> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        let file = File::create(path)
+            .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+        let mut writer = BufWriter::new(file);
+
+        let started_at = chrono::Utc::now();
+        let header = CastHeader {
+            version: 2,
+            width: cols,
+            height: rows,
+            timestamp: Some(started_at.timestamp()),
+            title: Some(title.to_string()),
+            env: HashMap::from([
+                ("SHELL".to_string(), shell.to_string()),
+                ("TERM".to_string(), "xterm-256color".to_string()),
+            ]),
+        };
+        let line = serde_json::to_string(&header).map_err(|e| e.to_string())?;
+        writeln!(writer, "{}", line)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+        Ok(Self {
+            writer,
+            info: RecordingInfo {
+                terminal_id: terminal_id.to_string(),
+                path: path.to_string_lossy().to_string(),
+                started_at: started_at.timestamp_millis(),
+                redacted: redact,
+                duration: 0.0,
+                event_count: 0,
+            },
+            started: Instant::now(),
+            redactor: redact.then(SecretRedactor::load),
+            pending: String::new(),
+        })
+    }
+
+    /// Start recording a terminal, by default into the app data directory
+    pub(super) fn for_terminal(
+        info: &TerminalInfo,
+        path: Option<PathBuf>,
+        redact: bool,
+    ) -> Result<Self, String> {
+        let path = match path {
+            Some(path) => path,
+            None => default_recording_path(&info.name)?,
+        };
+        Self::start(
+            &info.id,
+            &info.name,
+            &info.shell,
+            info.cols,
+            info.rows,
+            &path,
+            redact,
+        )
+    }
+
+    pub(super) fn info(&self) -> RecordingInfo {
+        RecordingInfo {
+            duration: self.started.elapsed().as_secs_f64(),
+            ..self.info.clone()
+        }
+    }
+
+    fn write_event(&mut self, code: &str, data: &str) {
+        let time = self.started.elapsed().as_secs_f64();
+        let event = (time, code, data);
+        let written = serde_json::to_string(&event)
+            .map_err(|e| e.to_string())
+            .and_then(|line| writeln!(self.writer, "{}", line).map_err(|e| e.to_string()));
+        match written {
+            Ok(()) => self.info.event_count += 1,
+            Err(e) => warn!("Failed to write to recording {}: {}", self.info.path, e),
+        }
+    }
+
+    pub(super) fn output(&mut self, data: &str) {
+        let Some(redactor) = &self.redactor else {
+            self.write_event("o", data);
+            return;
+        };
+        let mut text = std::mem::take(&mut self.pending);
+        text.push_str(data);
+        let tail = text
+            .char_indices()
+            .rev()
+            .take_while(|(_, c)| is_secret_char(*c))
+            .last()
+            .map(|(index, _)| index)
+            .unwrap_or(text.len());
+        let start = TRAILING_SCHEME_REGEX
+            .find(&text[..tail])
+            .map_or(tail, |scheme| scheme.start());
+        if start < text.len() && text.len() - start <= MAX_PENDING_BYTES {
+            self.pending = text.split_off(start);
+        }
+        if !text.is_empty() {
+            let redacted = redactor.redact(&text).into_owned();
+            self.write_event("o", &redacted);
+        }
+    }
+
+    pub(super) fn resize(&mut self, cols: u16, rows: u16) {
+        self.write_event("r", &format!("{}x{}", cols, rows));
+    }
+
+    /// Write held back output and flush the file
+    pub(super) fn finish(&mut self) -> RecordingInfo {
+        let pending = std::mem::take(&mut self.pending);
+        if !pending.is_empty() {
+            let pending = match &self.redactor {
+                Some(redactor) => redactor.redact(&pending).into_owned(),
+                None => pending,
+            };
+            self.write_event("o", &pending);
+        }
+        if let Err(e) = self.writer.flush() {
+            warn!("Failed to flush recording {}: {}", self.info.path, e);
+        }
+        self.info()
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+/// Read an asciicast v2 file
+fn load_cast(path: &Path) -> Result<(CastHeader, Vec<CastEvent>), String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut lines = BufReader::new(file).lines();
+    let header_line = lines
+        .next()
+        .ok_or_else(|| format!("{} is empty", path.display()))?
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let header: CastHeader = serde_json::from_str(&header_line)
+        .map_err(|e| format!("Invalid asciicast header: {}", e))?;
+    if header.version != 2 {
+        return Err(format!(
+            "Unsupported asciicast version {}, expected 2",
+            header.version
+        ));
+    }
+
+    let mut events = Vec::new();
+    for (index, line) in lines.enumerate() {
+        let line = line.map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let event: CastEvent = serde_json::from_str(&line)
+            .map_err(|e| format!("Invalid asciicast event on line {}: {}", index + 2, e))?;
+        events.push(event);
+    }
+    Ok((header, events))
+}
+
+/// A replay started from a recording
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayInfo {
+    /// Id the replay's output events are sent under
+    pub replay_id: String,
+    pub cols: u16,
+    pub rows: u16,
+    pub title: Option<String>,
+    /// Seconds at normal speed, before idle time is capped
+    pub duration: f64,
+    pub event_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ReplayResize {
+    replay_id: String,
+    cols: u16,
+    rows: u16,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ReplayFinished {
+    replay_id: String,
+    /// False when the replay was stopped early
+    completed: bool,
+}
+
+struct ReplayControl {
+    speed: Mutex<f64>,
+    paused: AtomicBool,
+    stopped: AtomicBool,
+}
+
+impl ReplayControl {
+    /// Wait `delay` of recording time, following speed changes and pauses;
+    /// false if the replay was stopped
+    fn wait(&self, delay: f64) -> bool {
+        let mut remaining = delay;
+        loop {
+            if self.stopped.load(Ordering::Relaxed) {
+                return false;
+            }
+            if self.paused.load(Ordering::Relaxed) {
+                thread::sleep(REPLAY_TICK);
+                continue;
+            }
+            if remaining <= 0.0 {
+                return true;
+            }
+            let speed = *self.speed.lock();
+            let wall = Duration::from_secs_f64(remaining / speed).min(REPLAY_TICK);
+            thread::sleep(wall);
+            remaining -= wall.as_secs_f64() * speed;
+        }
+    }
+}
+
+/// Recordings being replayed
+#[derive(Clone, Default)]
+pub struct Replays {
+    replays: Arc<Mutex<HashMap<String, Arc<ReplayControl>>>>,
+}
+
+fn valid_speed(speed: f64) -> Result<f64, String> {
+    if speed.is_finite() && speed > 0.0 {
+        Ok(speed)
+    } else {
+        Err(format!("Invalid replay speed: {}", speed))
+    }
+}
+
+impl Replays {
+    /// Start streaming a recording
+    ///
+    /// Pauses longer than `idle_limit` seconds are shortened to it.
+    pub fn start(
+        &self,
+        app: &AppHandle,
+        path: &Path,
+        speed: f64,
+        idle_limit: Option<f64>,
+    ) -> Result<ReplayInfo, String> {
+        let speed = valid_speed(speed)?;
+        let (header, events) = load_cast(path)?;
+        let replay_id = format!("replay-{}", Uuid::new_v4());
+        let info = ReplayInfo {
+            replay_id: replay_id.clone(),
+            cols: header.width,
+            rows: header.height,
+            title: header.title,
+            duration: events.last().map_or(0.0, |event| event.0),
+            event_count: events.len(),
+        };
+
+        let control = Arc::new(ReplayControl {
+            speed: Mutex::new(speed),
+            paused: AtomicBool::new(false),
+            stopped: AtomicBool::new(false),
+        });
+        self.replays
+            .lock()
+            .insert(replay_id.clone(), control.clone());
+
+        let app = app.clone();
+        let replays = self.replays.clone();
+        thread::spawn(move || {
+            let mut last = 0.0;
+            let mut completed = true;
+            for (time, code, data) in events {
+                let mut delay = (time - last).max(0.0);
+                if let Some(limit) = idle_limit {
+                    delay = delay.min(limit);
+                }
+                last = time;
+                if !control.wait(delay) {
+                    completed = false;
+                    break;
+                }
+                match code.as_str() {
+                    "o" => {
+                        let output = TerminalOutput {
+                            terminal_id: replay_id.clone(),
+                            data,
+                        };
+                        let _ = app.emit("terminal:replay-output", &output);
+                    }
+                    "r" => {
+                        if let Some((cols, rows)) = data
+                            .split_once('x')
+                            .and_then(|(c, r)| Some((c.parse().ok()?, r.parse().ok()?)))
+                        {
+                            let resize = ReplayResize {
+                                replay_id: replay_id.clone(),
+                                cols,
+                                rows,
+                            };
+                            let _ = app.emit("terminal:replay-resize", &resize);
+                        }
+                    }
+                    // Input and marker events are not shown
+                    _ => {}
+                }
+            }
+            replays.lock().remove(&replay_id);
+            let finished = ReplayFinished {
+                replay_id,
+                completed,
+            };
+            let _ = app.emit("terminal:replay-finished", &finished);
+        });
+
+        Ok(info)
+    }
+
+    fn control(&self, replay_id: &str) -> Result<Arc<ReplayControl>, String> {
+        self.replays
+            .lock()
+            .get(replay_id)
+            .cloned()
+            .ok_or_else(|| format!("Replay {} not found", replay_id))
+    }
+
+    pub fn set_speed(&self, replay_id: &str, speed: f64) -> Result<(), String> {
+        *self.control(replay_id)?.speed.lock() = valid_speed(speed)?;
+        Ok(())
+    }
+
+    pub fn set_paused(&self, replay_id: &str, paused: bool) -> Result<(), String> {
+        self.control(replay_id)?
+            .paused
+            .store(paused, Ordering::Relaxed);
+        Ok(())
+    }
+
+    pub fn stop(&self, replay_id: &str) -> Result<(), String> {
+        self.control(replay_id)?
+            .stopped
+            .store(true, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    fn record(redact: bool, chunks: &[&str]) -> (CastHeader, Vec<CastEvent>) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.cast");
+        let mut recorder =
+            Recorder::start("t1", "bash", "/bin/bash", 80, 24, &path, redact).unwrap();
+        for chunk in chunks {
+            recorder.output(chunk);
+        }
+        recorder.resize(100, 30);
+        let info = recorder.finish();
+        drop(recorder);
+        let (header, events) = load_cast(&path).unwrap();
+        assert_eq!(info.event_count as usize, events.len());
+        (header, events)
+    }
+
+    #[test]
+    fn writes_asciicast_v2() {
+        let (header, events) = record(false, &["$ ls\r\n", "a  b\r\n"]);
+        assert_eq!((header.version, header.width, header.height), (2, 80, 24));
+        assert_eq!(header.title.as_deref(), Some("bash"));
+        let events: Vec<(&str, &str)> = events
+            .iter()
+            .map(|(_, code, data)| (code.as_str(), data.as_str()))
+            .collect();
+        assert_eq!(
+            events,
+            [("o", "$ ls\r\n"), ("o", "a  b\r\n"), ("r", "100x30")]
+        );
+    }
+
+    #[test]
+    fn redacts_secrets_split_across_chunks() {
+        let (_, events) = record(
+            true,
+            &["key: sk-abcdefghij", "klmnopqrstuvwxyz\r\n", "done"],
+        );
+        let output: String = events
+            .iter()
+            .filter(|(_, code, _)| code == "o")
+            .map(|(_, _, data)| data.as_str())
+            .collect();
+        assert_eq!(output, "key: [REDACTED]\r\ndone");
+
+        let (_, events) = record(
+            true,
+            &["Authorization: Bearer ", "abcdef0123456789abcdef\r\n"],
+        );
+        let output: String = events
+            .iter()
+            .filter(|(_, code, _)| code == "o")
+            .map(|(_, _, data)| data.as_str())
+            .collect();
+        assert_eq!(output, "Authorization: [REDACTED]\r\n");
+    }
+
+    #[test]
+    fn rejects_other_versions() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("v1.cast");
+        fs::write(&path, "{\"version\":1,\"width\":80,\"height\":24}\n").unwrap();
+        assert!(load_cast(&path).unwrap_err().contains("version 1"));
+    }
+}
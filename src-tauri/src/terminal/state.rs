@@ -5,33 +5,54 @@
 
 use std::collections::HashMap;
 use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::thread;
 use std::time::Duration;
 
 use parking_lot::Mutex;
-use portable_pty::{CommandBuilder, MasterPty, PtyPair, PtySize, native_pty_system};
+use portable_pty::{MasterPty, PtySize};
 use tauri::{AppHandle, Emitter};
 use tracing::{error, info, warn};
 use uuid::Uuid;
 
-use super::constants::{DANGEROUS_ENV_VARS, OUTPUT_BUFFER_MAX_SIZE, PTY_READ_BUFFER_SIZE};
+use super::blocks::{CommandHistory, CommandTracker};
+use super::constants::{EMULATOR_SCROLLBACK_LINES, PTY_READ_BUFFER_SIZE};
+use super::emulator::{ExportFormat, GridSearchResult, TerminalEmulator};
 use super::flow_control::{FlowController, OutputBatcher};
 use super::process::kill_process_tree;
-use super::shell_integration::{SHELL_INTEGRATION_PWSH, inject_shell_integration};
-use super::types::{CreateTerminalOptions, TerminalInfo, TerminalStatus, UpdateTerminalOptions};
+use super::pty::{default_shell, spawn_shell, take_utf8};
+#[cfg(unix)]
+use super::pty_host::HostedTerminals;
+use super::recording::{Recorder, RecordingInfo, Replays};
+use super::shell_integration::inject_shell_integration;
+use super::types::{
+    CreateTerminalOptions, RestoredTerminal, TerminalInfo, TerminalStatus, UpdateTerminalOptions,
+};
 
 /// Terminal state stored in Tauri app state
 #[derive(Clone)]
 pub struct TerminalState {
     terminals: Arc<Mutex<HashMap<String, TerminalInstance>>>,
+    /// Command blocks recorded from shell integration
+    history: CommandHistory,
+    /// Recordings being replayed
+    replays: Replays,
+    /// Persistent terminals owned by the PTY host
+    #[cfg(unix)]
+    hosted: HostedTerminals,
 }
 
 impl TerminalState {
     pub fn new() -> Self {
+        let history = CommandHistory::default();
         Self {
             terminals: Arc::new(Mutex::new(HashMap::new())),
+            #[cfg(unix)]
+            hosted: HostedTerminals::new(history.clone()),
+            history,
+            replays: Replays::default(),
         }
     }
 }
@@ -50,6 +71,12 @@ pub(super) struct TerminalInstance {
     pub _reader_handle: thread::JoinHandle<()>,
     pub running: Arc<AtomicBool>,
     pub flow_controller: Arc<FlowController>,
+    /// Server-side grid and scrollback fed from the PTY output
+    pub emulator: Arc<Mutex<TerminalEmulator>>,
+    /// Command blocks of the running shell
+    pub commands: Arc<Mutex<CommandTracker>>,
+    /// Active asciicast recording
+    pub recording: Arc<Mutex<Option<Recorder>>>,
     /// Process ID for cleanup on close
     pub child_pid: Option<u32>,
 }
@@ -57,181 +84,43 @@ pub(super) struct TerminalInstance {
 impl TerminalState {
     /// Get the default shell for the current platform
     pub fn get_default_shell() -> String {
-        #[cfg(target_os = "windows")]
-        {
-            // Try PowerShell first, fall back to cmd.exe
-            if std::env::var("COMSPEC").is_ok() {
-                // Check if PowerShell exists
-                let pwsh_paths = [
-                    "C:\\Program Files\\PowerShell\\7\\pwsh.exe",
-                    "C:\\Windows\\System32\\WindowsPowerShell\\v1.0\\powershell.exe",
-                ];
-                for path in pwsh_paths {
-                    if std::path::Path::new(path).exists() {
-                        return path.to_string();
-                    }
-                }
-            }
-            std::env::var("COMSPEC").unwrap_or_else(|_| "cmd.exe".to_string())
-        }
-
-        #[cfg(target_os = "macos")]
-        {
-            std::env::var("SHELL").unwrap_or_else(|_| "/bin/zsh".to_string())
-        }
-
-        #[cfg(target_os = "linux")]
-        {
-            std::env::var("SHELL").unwrap_or_else(|_| "/bin/bash".to_string())
-        }
-
-        #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
-        {
-            std::env::var("SHELL").unwrap_or_else(|_| "/bin/sh".to_string())
-        }
-    }
-
-    /// Get the default working directory
-    fn get_default_cwd() -> String {
-        std::env::current_dir()
-            .map(|p| p.to_string_lossy().to_string())
-            .unwrap_or_else(|_| {
-                dirs::home_dir()
-                    .map(|p| p.to_string_lossy().to_string())
-                    .unwrap_or_else(|| ".".to_string())
-            })
+        default_shell()
     }
 
     /// Create a new terminal
+    ///
+    /// Terminals are persistent by default where the PTY host is supported;
+    /// if the host cannot be reached the terminal is owned by the app.
     pub fn create_terminal(
         &self,
         app_handle: &AppHandle,
         options: CreateTerminalOptions,
     ) -> Result<TerminalInfo, String> {
         let terminal_id = Uuid::new_v4().to_string();
-        let shell = options.shell.unwrap_or_else(Self::get_default_shell);
-        let cwd = options.cwd.unwrap_or_else(Self::get_default_cwd);
-        let name = options.name.unwrap_or_else(|| {
-            format!("Terminal {}", terminal_id.split('-').next().unwrap_or("1"))
-        });
-        let cols = options.cols.unwrap_or(120);
-        let rows = options.rows.unwrap_or(30);
-
-        // Create PTY
-        let pty_system = native_pty_system();
-        let pair: PtyPair = pty_system
-            .openpty(PtySize {
-                rows,
-                cols,
-                pixel_width: 0,
-                pixel_height: 0,
-            })
-            .map_err(|e| format!("Failed to open PTY: {}", e))?;
-
-        // Build command - for PowerShell, inject shell integration via command line args (like VS Code)
-        let shell_lower = shell.to_lowercase();
-        let should_inject = options.shell_integration.unwrap_or(true);
-        let is_powershell = shell_lower.contains("pwsh") || shell_lower.contains("powershell");
-
-        let mut cmd = if is_powershell && should_inject {
-            // For PowerShell: use -NoExit -Command to source the integration script
-            // This avoids the script being echoed to the terminal (like VS Code does)
-            let mut c = CommandBuilder::new(&shell);
-            c.arg("-NoLogo");
-            c.arg("-NoExit");
-            c.arg("-Command");
-            // Inline the shell integration script as a command
-            // Using try/catch to suppress errors silently
-            let script = SHELL_INTEGRATION_PWSH
-                .lines()
-                .filter(|l| !l.trim().starts_with('#') && !l.trim().is_empty())
-                .collect::<Vec<_>>()
-                .join("; ");
-            c.arg(format!("try {{ {} }} catch {{}}", script));
-            c
-        } else {
-            CommandBuilder::new(&shell)
-        };
 
-        // Set working directory
-        cmd.cwd(&cwd);
-
-        // Add environment variables (with security filtering)
-        if let Some(env) = &options.env {
-            for (key, value) in env {
-                let key_upper = key.to_uppercase();
-                if DANGEROUS_ENV_VARS
-                    .iter()
-                    .any(|&blocked| key_upper == blocked)
-                {
-                    tracing::warn!("Blocked potentially dangerous env var: {}", key);
-                    continue;
+        #[cfg(unix)]
+        if options.persistent.unwrap_or(true) {
+            match self
+                .hosted
+                .create(app_handle, terminal_id.clone(), options.clone())
+            {
+                Ok(info) => {
+                    let _ = app_handle.emit("terminal:created", &info);
+                    return Ok(info);
                 }
-                cmd.env(key, value);
+                Err(e) => warn!("Persistent terminal unavailable, using a local one: {}", e),
             }
         }
 
-        // Set common terminal environment variables
-        cmd.env("TERM", "xterm-256color");
-        cmd.env("COLORTERM", "truecolor");
-        cmd.env("TERM_PROGRAM", "CortexDesktop");
-        cmd.env("TERM_PROGRAM_VERSION", env!("CARGO_PKG_VERSION"));
-
-        #[cfg(target_os = "windows")]
-        {
-            // Force UTF-8 on Windows
-            cmd.env("PYTHONIOENCODING", "utf-8");
-        }
-
-        // Spawn the child process
-        let child = pair
-            .slave
-            .spawn_command(cmd)
-            .map_err(|e| format!("Failed to spawn shell process: {}", e))?;
-
-        // Store PID for cleanup on close
-        let child_pid = child.process_id();
-
-        info!(
-            "Terminal {} created with shell: {}, cwd: {}, pid: {:?}",
-            terminal_id, shell, cwd, child_pid
-        );
-
-        // Store the master PTY for resize operations
-        let master_pty = pair.master;
-
-        // Get writer from master with buffering
-        let raw_writer = master_pty
-            .take_writer()
-            .map_err(|e| format!("Failed to get PTY writer: {}", e))?;
-        let writer = Arc::new(Mutex::new(BufWriter::with_capacity(
-            OUTPUT_BUFFER_MAX_SIZE,
-            raw_writer,
-        )));
-
-        // Get reader from master with buffering
-        let raw_reader = master_pty
-            .try_clone_reader()
-            .map_err(|e| format!("Failed to get PTY reader: {}", e))?;
-        let mut reader = BufReader::with_capacity(PTY_READ_BUFFER_SIZE, raw_reader);
+        let shell = spawn_shell(terminal_id.clone(), options, Self::get_default_shell)?;
+        let info = shell.info;
+        let writer = shell.writer;
+        let child = shell.child;
+        let child_pid = shell.child_pid;
+        let mut reader = BufReader::with_capacity(PTY_READ_BUFFER_SIZE, shell.reader);
 
         // Wrap master PTY in Arc<Mutex<>> for thread-safe resize access
-        let master = Arc::new(Mutex::new(master_pty));
-
-        // Create terminal info
-        let info = TerminalInfo {
-            id: terminal_id.clone(),
-            name,
-            cwd,
-            shell,
-            cols,
-            rows,
-            status: "running".to_string(),
-            created_at: chrono::Utc::now().timestamp_millis(),
-            last_command: None,
-            last_exit_code: None,
-            command_running: false,
-        };
+        let master = Arc::new(Mutex::new(shell.master));
 
         // Emit terminal created event
         let _ = app_handle.emit("terminal:created", &info);
@@ -244,6 +133,25 @@ impl TerminalState {
         let flow_controller = Arc::new(FlowController::new());
         let flow_controller_clone = flow_controller.clone();
 
+        let emulator = Arc::new(Mutex::new(TerminalEmulator::new(
+            info.cols as usize,
+            info.rows as usize,
+            EMULATOR_SCROLLBACK_LINES,
+        )));
+        let emulator_clone = emulator.clone();
+
+        let commands = Arc::new(Mutex::new(CommandTracker::new(
+            terminal_id.clone(),
+            info.workspace.clone(),
+            info.cols as usize,
+            info.rows as usize,
+        )));
+        let commands_clone = commands.clone();
+        let history = self.history.clone();
+
+        let recording: Arc<Mutex<Option<Recorder>>> = Arc::new(Mutex::new(None));
+        let recording_clone = recording.clone();
+
         // Spawn reader thread with batched output
         let app_handle_clone = app_handle.clone();
         let terminal_id_clone = terminal_id.clone();
@@ -285,34 +193,17 @@ impl TerminalState {
                         break;
                     }
                     Ok(n) => {
+                        emulator_clone.lock().process(&buf[..n]);
                         leftover.extend_from_slice(&buf[..n]);
 
                         // Process available valid UTF-8
-                        match std::str::from_utf8(&leftover) {
-                            Ok(s) => {
-                                batcher.push(s);
-                                leftover.clear();
-                            }
-                            Err(e) => {
-                                let valid_up_to = e.valid_up_to();
-                                if valid_up_to > 0 {
-                                    let s = std::str::from_utf8(&leftover[..valid_up_to])
-                                        .unwrap_or_default();
-                                    batcher.push(s);
-                                }
-
-                                if let Some(error_len) = e.error_len() {
-                                    // Skip truly invalid sequence
-                                    let s = String::from_utf8_lossy(
-                                        &leftover[valid_up_to..valid_up_to + error_len],
-                                    );
-                                    batcher.push(&s);
-                                    leftover = leftover[valid_up_to + error_len..].to_vec();
-                                } else {
-                                    // Keep incomplete sequence for next read
-                                    leftover = leftover[valid_up_to..].to_vec();
-                                }
+                        let data = take_utf8(&mut leftover);
+                        if !data.is_empty() {
+                            history.track(&app_handle_clone, &mut commands_clone.lock(), &data);
+                            if let Some(recorder) = recording_clone.lock().as_mut() {
+                                recorder.output(&data);
                             }
+                            batcher.push(&data);
                         }
                     }
                     Err(e) => {
@@ -352,7 +243,7 @@ impl TerminalState {
         // Store terminal instance
         let writer_clone = writer.clone();
         let shell_for_injection = info.shell.clone();
-        let should_inject = options.shell_integration.unwrap_or(true);
+        let should_inject = shell.inject_integration;
 
         let instance = TerminalInstance {
             info: info.clone(),
@@ -361,6 +252,9 @@ impl TerminalState {
             _reader_handle: reader_handle,
             running,
             flow_controller,
+            emulator,
+            commands,
+            recording,
             child_pid,
         };
 
@@ -387,6 +281,11 @@ impl TerminalState {
 
     /// Write data to a terminal
     pub fn write_terminal(&self, terminal_id: &str, data: &str) -> Result<(), String> {
+        #[cfg(unix)]
+        if let Some(result) = self.hosted.write(terminal_id, data) {
+            return result;
+        }
+
         let terminals = self.terminals.lock();
 
         let terminal = terminals
@@ -412,33 +311,30 @@ impl TerminalState {
         terminal_id: &str,
         options: UpdateTerminalOptions,
     ) -> Result<TerminalInfo, String> {
+        #[cfg(unix)]
+        let options = match self.hosted.update(terminal_id, options.clone()) {
+            Some(result) => return result,
+            None => options,
+        };
+
         let mut terminals = self.terminals.lock();
 
         let terminal = terminals
             .get_mut(terminal_id)
             .ok_or_else(|| format!("Terminal {} not found", terminal_id))?;
 
-        if let Some(cwd) = options.cwd {
-            terminal.info.cwd = cwd;
-        }
-
-        if let Some(last_command) = options.last_command {
-            terminal.info.last_command = Some(last_command);
-        }
-
-        if let Some(last_exit_code) = options.last_exit_code {
-            terminal.info.last_exit_code = Some(last_exit_code);
-        }
-
-        if let Some(command_running) = options.command_running {
-            terminal.info.command_running = command_running;
-        }
+        options.apply(&mut terminal.info);
 
         Ok(terminal.info.clone())
     }
 
     /// Resize a terminal
     pub fn resize_terminal(&self, terminal_id: &str, cols: u16, rows: u16) -> Result<(), String> {
+        #[cfg(unix)]
+        if let Some(result) = self.hosted.resize(terminal_id, cols, rows) {
+            return result;
+        }
+
         let mut terminals = self.terminals.lock();
 
         let terminal = terminals
@@ -448,6 +344,17 @@ impl TerminalState {
         // Update stored dimensions
         terminal.info.cols = cols;
         terminal.info.rows = rows;
+        terminal
+            .emulator
+            .lock()
+            .resize(cols as usize, rows as usize);
+        terminal
+            .commands
+            .lock()
+            .resize(cols as usize, rows as usize);
+        if let Some(recorder) = terminal.recording.lock().as_mut() {
+            recorder.resize(cols, rows);
+        }
 
         // Actually resize the PTY
         let master = terminal.master.lock();
@@ -467,9 +374,24 @@ impl TerminalState {
 
     /// Close a terminal
     pub fn close_terminal(&self, app_handle: &AppHandle, terminal_id: &str) -> Result<(), String> {
-        let mut terminals = self.terminals.lock();
+        #[cfg(unix)]
+        if let Some(result) = self.hosted.close(terminal_id) {
+            result?;
+            let status_event = TerminalStatus {
+                terminal_id: terminal_id.to_string(),
+                status: "closed".to_string(),
+                exit_code: None,
+            };
+            let _ = app_handle.emit("terminal:status", &status_event);
+            info!("Terminal {} closed", terminal_id);
+            return Ok(());
+        }
 
-        if let Some(terminal) = terminals.remove(terminal_id) {
+        // Release the lock before emitting: status listeners run inside emit
+        // and may look terminals up again
+        let removed = self.terminals.lock().remove(terminal_id);
+
+        if let Some(terminal) = removed {
             // Set running to false to stop the reader thread (lock-free write)
             terminal.running.store(false, Ordering::Relaxed);
 
@@ -499,11 +421,20 @@ impl TerminalState {
     /// List all terminals
     pub fn list_terminals(&self) -> Result<Vec<TerminalInfo>, String> {
         let terminals = self.terminals.lock();
-        Ok(terminals.values().map(|t| t.info.clone()).collect())
+        #[allow(unused_mut)]
+        let mut list: Vec<TerminalInfo> = terminals.values().map(|t| t.info.clone()).collect();
+        #[cfg(unix)]
+        list.extend(self.hosted.list());
+        Ok(list)
     }
 
     /// Get info for a specific terminal
     pub fn get_terminal(&self, terminal_id: &str) -> Result<Option<TerminalInfo>, String> {
+        #[cfg(unix)]
+        if let Some(info) = self.hosted.get(terminal_id) {
+            return Ok(Some(info));
+        }
+
         let terminals = self.terminals.lock();
         Ok(terminals.get(terminal_id).map(|t| t.info.clone()))
     }
@@ -525,6 +456,11 @@ impl TerminalState {
     /// The frontend should call this after processing terminal output
     /// to release backpressure and allow more output to flow.
     pub fn acknowledge_output(&self, terminal_id: &str, bytes: usize) -> Result<(), String> {
+        #[cfg(unix)]
+        if let Some(result) = self.hosted.acknowledge(terminal_id, bytes) {
+            return result;
+        }
+
         let terminals = self.terminals.lock();
 
         let terminal = terminals
@@ -536,12 +472,164 @@ impl TerminalState {
         Ok(())
     }
 
-    /// Close all terminals
+    fn emulator(&self, terminal_id: &str) -> Result<Arc<Mutex<TerminalEmulator>>, String> {
+        #[cfg(unix)]
+        if let Some(emulator) = self.hosted.emulator(terminal_id) {
+            return Ok(emulator);
+        }
+
+        let terminals = self.terminals.lock();
+        terminals
+            .get(terminal_id)
+            .map(|t| t.emulator.clone())
+            .ok_or_else(|| format!("Terminal {} not found", terminal_id))
+    }
+
+    /// Enable problem matching on a terminal's command blocks with the named
+    /// builtin matchers, or disable it with none
+    pub fn set_problem_matchers(&self, terminal_id: &str, names: &[String]) -> Result<(), String> {
+        #[cfg(unix)]
+        if let Some(result) = self.hosted.set_problem_matchers(terminal_id, names) {
+            return result;
+        }
+
+        let terminals = self.terminals.lock();
+        let terminal = terminals
+            .get(terminal_id)
+            .ok_or_else(|| format!("Terminal {} not found", terminal_id))?;
+        terminal.commands.lock().set_problem_matchers(names)
+    }
+
+    /// Problem matchers enabled on a terminal
+    pub fn problem_matchers(&self, terminal_id: &str) -> Result<Vec<String>, String> {
+        #[cfg(unix)]
+        if let Some(names) = self.hosted.problem_matchers(terminal_id) {
+            return Ok(names);
+        }
+
+        let terminals = self.terminals.lock();
+        terminals
+            .get(terminal_id)
+            .map(|t| t.commands.lock().problem_matchers())
+            .ok_or_else(|| format!("Terminal {} not found", terminal_id))
+    }
+
+    /// Start recording a terminal's output in asciicast format
+    pub fn start_recording(
+        &self,
+        terminal_id: &str,
+        path: Option<PathBuf>,
+        redact: bool,
+    ) -> Result<RecordingInfo, String> {
+        #[cfg(unix)]
+        if let Some(result) = self
+            .hosted
+            .start_recording(terminal_id, path.clone(), redact)
+        {
+            return result;
+        }
+
+        let terminals = self.terminals.lock();
+        let terminal = terminals
+            .get(terminal_id)
+            .ok_or_else(|| format!("Terminal {} not found", terminal_id))?;
+        let mut recording = terminal.recording.lock();
+        if recording.is_some() {
+            return Err(format!(
+                "Terminal {} is already being recorded",
+                terminal_id
+            ));
+        }
+        let recorder = Recorder::for_terminal(&terminal.info, path, redact)?;
+        let info = recorder.info();
+        *recording = Some(recorder);
+        info!("Recording terminal {} to {}", terminal_id, info.path);
+        Ok(info)
+    }
+
+    /// Stop recording a terminal
+    pub fn stop_recording(&self, terminal_id: &str) -> Result<RecordingInfo, String> {
+        #[cfg(unix)]
+        if let Some(result) = self.hosted.stop_recording(terminal_id) {
+            return result;
+        }
+
+        let terminals = self.terminals.lock();
+        let terminal = terminals
+            .get(terminal_id)
+            .ok_or_else(|| format!("Terminal {} not found", terminal_id))?;
+        let mut recorder = terminal
+            .recording
+            .lock()
+            .take()
+            .ok_or_else(|| format!("Terminal {} is not being recorded", terminal_id))?;
+        Ok(recorder.finish())
+    }
+
+    /// The active recording of a terminal
+    pub fn recording(&self, terminal_id: &str) -> Result<Option<RecordingInfo>, String> {
+        #[cfg(unix)]
+        if let Some(info) = self.hosted.recording(terminal_id) {
+            return Ok(info);
+        }
+
+        let terminals = self.terminals.lock();
+        terminals
+            .get(terminal_id)
+            .map(|t| t.recording.lock().as_ref().map(Recorder::info))
+            .ok_or_else(|| format!("Terminal {} not found", terminal_id))
+    }
+
+    /// Recordings being replayed
+    pub fn replays(&self) -> &Replays {
+        &self.replays
+    }
+
+    /// Search a terminal's scrollback, including soft-wrapped lines
+    pub fn search_scrollback(
+        &self,
+        terminal_id: &str,
+        query: &str,
+        case_sensitive: bool,
+        max_results: Option<usize>,
+    ) -> Result<GridSearchResult, String> {
+        let emulator = self.emulator(terminal_id)?;
+        let result = emulator.lock().search(query, case_sensitive, max_results);
+        Ok(result)
+    }
+
+    /// Export a terminal's scrollback
+    pub fn export_scrollback(
+        &self,
+        terminal_id: &str,
+        format: ExportFormat,
+    ) -> Result<String, String> {
+        let emulator = self.emulator(terminal_id)?;
+        let exported = emulator.lock().export(format);
+        Ok(exported)
+    }
+
+    /// Command blocks recorded from shell integration
+    pub fn command_history(&self) -> &CommandHistory {
+        &self.history
+    }
+
+    /// Escape sequences that reproduce a terminal's screen and scrollback in a new view
+    pub fn snapshot_terminal(&self, terminal_id: &str) -> Result<String, String> {
+        let emulator = self.emulator(terminal_id)?;
+        let snapshot = emulator.lock().snapshot();
+        Ok(snapshot)
+    }
+
+    /// Close all terminals, including persistent ones
     pub fn close_all(&self, app_handle: &AppHandle) -> Result<(), String> {
-        let terminal_ids: Vec<String> = {
+        #[allow(unused_mut)]
+        let mut terminal_ids: Vec<String> = {
             let terminals = self.terminals.lock();
             terminals.keys().cloned().collect()
         };
+        #[cfg(unix)]
+        terminal_ids.extend(self.hosted.ids());
 
         for id in terminal_ids {
             let _ = self.close_terminal(app_handle, &id);
@@ -549,4 +637,28 @@ impl TerminalState {
 
         Ok(())
     }
+
+    /// Close the terminals owned by the app and detach from persistent ones,
+    /// which keep running in the PTY host
+    pub fn detach_all(&self, app_handle: &AppHandle) {
+        #[cfg(unix)]
+        self.hosted.detach();
+        let _ = self.close_all(app_handle);
+    }
+
+    /// Reattach to persistent terminals left running by a previous session
+    pub fn restore_terminals(
+        &self,
+        app_handle: &AppHandle,
+    ) -> Result<Vec<RestoredTerminal>, String> {
+        #[cfg(unix)]
+        {
+            self.hosted.restore(app_handle)
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = app_handle;
+            Ok(Vec::new())
+        }
+    }
 }
@@ -19,6 +19,9 @@ pub struct TerminalInfo {
     pub last_command: Option<String>,
     pub last_exit_code: Option<i32>,
     pub command_running: bool,
+    /// Workspace the terminal belongs to
+    #[serde(default)]
+    pub workspace: Option<String>,
 }
 
 /// Terminal output event sent to frontend
@@ -37,7 +40,7 @@ pub struct TerminalStatus {
 }
 
 /// Options for creating a terminal
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateTerminalOptions {
     pub name: Option<String>,
     pub cwd: Option<String>,
@@ -48,6 +51,21 @@ pub struct CreateTerminalOptions {
     /// Whether to inject shell integration scripts (defaults to true)
     #[serde(default)]
     pub shell_integration: Option<bool>,
+    /// Whether the terminal outlives the app (defaults to true where supported)
+    #[serde(default)]
+    pub persistent: Option<bool>,
+    /// Workspace the terminal belongs to, used to file its command history
+    #[serde(default)]
+    pub workspace: Option<String>,
+    /// Command the shell runs instead of an interactive session; the
+    /// terminal exits when the command does
+    #[serde(default)]
+    pub command: Option<String>,
+    /// Arguments `shell` is started with when it is a program rather than a
+    /// shell; passed as they are, without a shell to quote them for, and
+    /// used instead of `command`
+    #[serde(default)]
+    pub args: Option<Vec<String>>,
 }
 
 impl Default for CreateTerminalOptions {
@@ -60,6 +78,10 @@ impl Default for CreateTerminalOptions {
             cols: None,
             rows: None,
             shell_integration: None,
+            persistent: None,
+            workspace: None,
+            command: None,
+            args: None,
         }
     }
 }
@@ -73,6 +95,35 @@ pub struct UpdateTerminalOptions {
     pub command_running: Option<bool>,
 }
 
+impl UpdateTerminalOptions {
+    /// Apply the provided fields to `info`
+    pub fn apply(self, info: &mut TerminalInfo) {
+        if let Some(cwd) = self.cwd {
+            info.cwd = cwd;
+        }
+
+        if let Some(last_command) = self.last_command {
+            info.last_command = Some(last_command);
+        }
+
+        if let Some(last_exit_code) = self.last_exit_code {
+            info.last_exit_code = Some(last_exit_code);
+        }
+
+        if let Some(command_running) = self.command_running {
+            info.command_running = command_running;
+        }
+    }
+}
+
+/// A persistent terminal reattached after a restart
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RestoredTerminal {
+    pub info: TerminalInfo,
+    /// Output to replay before live output continues
+    pub scrollback: String,
+}
+
 /// Information about a process using a network port
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PortProcess {
@@ -222,6 +222,33 @@ fn get_nested_value(obj: &Value, key: &str) -> Option<Value> {
     Some(current.clone())
 }
 
+/// Resolve a `workspace/configuration` section against merged settings.
+///
+/// Settings files usually store flat dotted keys (`"rust-analyzer.cargo.features"`)
+/// while language servers ask for a section and expect a nested object, so
+/// both forms are folded together before the lookup. `None` returns the whole
+/// settings object.
+pub(crate) fn configuration_section(settings: &Value, section: Option<&str>) -> Value {
+    let Some(section) = section.filter(|s| !s.is_empty()) else {
+        return settings.clone();
+    };
+
+    let mut expanded = Value::Object(Map::new());
+    if let Value::Object(map) = settings {
+        for (key, value) in map {
+            // Skip "[languageId]" override blocks
+            if key.starts_with('[') {
+                continue;
+            }
+            let mut nested = Value::Object(Map::new());
+            set_nested_value(&mut nested, key, value.clone());
+            merge_json(&mut expanded, &nested);
+        }
+    }
+
+    get_nested_value(&expanded, section).unwrap_or(Value::Null)
+}
+
 /// Set a nested value in a JSON object using dot notation
 fn set_nested_value(obj: &mut Value, key: &str, value: Value) {
     let parts: Vec<&str> = key.split('.').collect();
@@ -589,6 +589,10 @@ pub async fn wsl_open_terminal(
         cols: None,
         rows: None,
         shell_integration: Some(true),
+        persistent: None,
+        workspace: None,
+        command: None,
+        args: None,
     };
 
     terminal_state
@@ -49,6 +49,8 @@ interface FileEntry {
   modifiedAt?: number;
   extension?: string;
   children?: FileEntry[];
+  /** Tracked but outside the sparse-checkout set, so not on disk */
+  sparseExcluded?: boolean;
 }
 
 interface CompactedFileEntry extends FileEntry {
@@ -763,8 +765,9 @@ function VirtualItem(props: VirtualItemProps) {
       style={{ 
         "padding-left": `${props.item.depth * TREE_INDENT_SIZE + TREE_BASE_PADDING}px`,
         height: `${ITEM_HEIGHT}px`,
-        opacity: props.isCut ? 0.5 : 1,
+        opacity: props.isCut || props.item.entry.sparseExcluded ? 0.5 : 1,
       }}
+      title={props.item.entry.sparseExcluded ? "Outside the sparse-checkout set" : undefined}
       onClick={handleClick}
       onDblClick={handleDoubleClick}
       onKeyDown={handleKeyDown}